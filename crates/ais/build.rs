@@ -0,0 +1,21 @@
+// Generates the gRPC service glue for proto/ais.proto. The messages are
+// written out in src/grpc.rs, so building needs no protoc.
+fn main() {
+    let method = |name: &str, route: &str, input: &str, output: &str| {
+        tonic_build::manual::Method::builder()
+            .name(name)
+            .route_name(route)
+            .input_type(format!("crate::grpc::{}", input))
+            .output_type(format!("crate::grpc::{}", output))
+            .codec_path("tonic::codec::ProstCodec")
+    };
+    let service = tonic_build::manual::Service::builder()
+        .name("AisService")
+        .package("yachtpit.ais.v1")
+        .method(method("stream_vessels", "StreamVessels", "StreamVesselsRequest", "VesselReport").server_streaming().build())
+        .method(method("get_vessel", "GetVessel", "GetVesselRequest", "Vessel").build())
+        .method(method("query_history", "QueryHistory", "QueryHistoryRequest", "QueryHistoryResponse").build())
+        .build();
+    tonic_build::manual::Builder::new().build_client(false).compile(&[service]);
+    println!("cargo:rerun-if-changed=proto/ais.proto");
+}
@@ -0,0 +1,192 @@
+use axum::extract::{Query, Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::time::Instant;
+use crate::ais::AppState;
+
+/// Stream clients served at once unless configured otherwise
+pub const DEFAULT_MAX_CLIENTS: usize = 100;
+
+/// AIS messages per second sent to one client unless configured otherwise
+pub const DEFAULT_CLIENT_RATE: u32 = 50;
+
+// Who may use the streaming endpoints and how much of the feed they get.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessPolicy {
+    /// Accepted tokens; the streams are open when empty
+    pub tokens: HashSet<String>,
+    pub max_clients: usize,
+    /// Messages per second per client, with up to a second's worth in a burst
+    pub client_rate: u32,
+}
+
+impl Default for AccessPolicy {
+    fn default() -> Self {
+        Self {
+            tokens: HashSet::new(),
+            max_clients: DEFAULT_MAX_CLIENTS,
+            client_rate: DEFAULT_CLIENT_RATE,
+        }
+    }
+}
+
+impl AccessPolicy {
+    pub fn is_open(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    pub fn accepts(&self, token: Option<&str>) -> bool {
+        self.is_open()
+            || token.is_some_and(|token| self.tokens.iter().any(|accepted| constant_time_eq(accepted.as_bytes(), token.as_bytes())))
+    }
+}
+
+// Compare without stopping at the first differing byte, so response times
+// do not give away how much of a token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+// Connected stream clients, capped.
+#[derive(Debug, Default)]
+pub struct ClientSlots {
+    in_use: Arc<AtomicUsize>,
+}
+
+impl ClientSlots {
+    // A slot for one more client, or `None` when `max` are connected.
+    pub fn acquire(&self, max: usize) -> Option<ClientSlot> {
+        self.in_use
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |in_use| (in_use < max).then_some(in_use + 1))
+            .ok()?;
+        Some(ClientSlot { in_use: self.in_use.clone() })
+    }
+
+    pub fn in_use(&self) -> usize {
+        self.in_use.load(Ordering::Acquire)
+    }
+}
+
+// Held for the life of a client connection; frees the slot when dropped.
+#[derive(Debug)]
+pub struct ClientSlot {
+    in_use: Arc<AtomicUsize>,
+}
+
+impl Drop for ClientSlot {
+    fn drop(&mut self) {
+        self.in_use.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+// Token bucket limiting the messages sent to one client.
+#[derive(Debug)]
+pub struct RateLimiter {
+    rate: f64,
+    tokens: f64,
+    last: Instant,
+    dropped: u64,
+}
+
+impl RateLimiter {
+    pub fn new(per_second: u32) -> Self {
+        Self {
+            rate: per_second as f64,
+            tokens: per_second as f64,
+            last: Instant::now(),
+            dropped: 0,
+        }
+    }
+
+    // Messages refused so far.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    // Whether another message may be sent now.
+    pub fn allow(&mut self) -> bool {
+        self.allow_at(Instant::now())
+    }
+
+    fn allow_at(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.last = now;
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            self.dropped += 1;
+            false
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct TokenQuery {
+    token: Option<String>,
+}
+
+// Middleware turning away vessel data requests without an accepted token, given
+// as `?token=` (browsers cannot set headers on WebSockets) or as a bearer
+// `Authorization` header.
+pub(crate) async fn require_token(
+    State(state): State<AppState>,
+    Query(query): Query<TokenQuery>,
+    headers: HeaderMap,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let token = query.token.as_deref().or(bearer);
+    if state.ais_stream_manager.access().accepts(token) {
+        Ok(next.run(request).await)
+    } else {
+        Err(StatusCode::UNAUTHORIZED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_tokens_slots_and_rate() {
+        let open = AccessPolicy::default();
+        assert!(open.accepts(None));
+        let policy = AccessPolicy {
+            tokens: HashSet::from(["s3cret".to_string()]),
+            ..AccessPolicy::default()
+        };
+        assert!(policy.accepts(Some("s3cret")));
+        assert!(!policy.accepts(Some("s3cre")));
+        assert!(!policy.accepts(None));
+
+        let slots = ClientSlots::default();
+        let first = slots.acquire(2).unwrap();
+        let second = slots.acquire(2).unwrap();
+        assert!(slots.acquire(2).is_none());
+        drop(first);
+        assert_eq!(slots.in_use(), 1);
+        assert!(slots.acquire(2).is_some());
+        drop(second);
+
+        let mut limiter = RateLimiter::new(2);
+        let start = limiter.last;
+        assert!(limiter.allow_at(start));
+        assert!(limiter.allow_at(start));
+        assert!(!limiter.allow_at(start));
+        assert!(limiter.allow_at(start + Duration::from_millis(500)));
+        assert!(!limiter.allow_at(start + Duration::from_millis(600)));
+        assert_eq!(limiter.dropped(), 2);
+    }
+}
@@ -1,35 +1,62 @@
 use axum::{
-    extract::{ws::{Message as WsMessage, WebSocket}, Query, State, WebSocketUpgrade},
+    extract::{ws::{Message as WsMessage, WebSocket}, Path, Query, State, WebSocketUpgrade},
     http::StatusCode,
-    response::{Json, Response}
+    response::{sse::{Event, KeepAlive, Sse}, IntoResponse, Json, Response}
 
     ,
 };
-use base64::{engine::general_purpose::STANDARD, Engine as _};
-use futures_util::{SinkExt, StreamExt};
+use futures_util::StreamExt;
+use prost::Message as _;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::{
     sync::{broadcast, Mutex},
     task::JoinHandle,
 };
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
 use tokio_util::sync::CancellationToken;
-use url::Url;
-
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct SubscriptionMessage {
-    #[serde(rename = "Apikey")]
-    apikey: String,
-    #[serde(rename = "BoundingBoxes")]
-    bounding_boxes: Vec<Vec<[f64; 2]>>,
-    #[serde(rename = "FiltersShipMMSI")]
-    filters_ship_mmsi: Vec<String>,
-    // Uncomment and add if needed:
-    // #[serde(rename = "FilterMessageTypes")]
-    // filter_message_types: Vec<String>,
+use crate::store::{persist_stream, unix_millis, AisStore, PositionQuery, StoredAisMessage};
+use crate::track::{simplify, TrackPoint};
+use crate::access::{AccessPolicy, ClientSlot, ClientSlots, RateLimiter};
+use crate::grpc::vessel_report;
+use crate::collision::{CollisionAlert, CollisionWatch, OwnShip, OwnShipState, Traffic};
+use crate::metrics::{AisMetrics, StreamGauges, PROMETHEUS_CONTENT_TYPE};
+use crate::registry::{VesselDimensions, VesselRegistry};
+use crate::spatial::VesselIndex;
+use crate::subscription::{Subscription, WatchArea};
+use crate::upstream::{run_upstream, Upstream, UpstreamMerger};
+
+/// WebSocket subprotocol under which a client gets AIS reports as binary
+/// protobuf `VesselReport` frames (proto/ais.proto) instead of JSON text
+pub const PROTOBUF_SUBPROTOCOL: &str = "yachtpit.ais.v1.protobuf";
+
+/// How often the map feed sends each vessel's latest state
+pub const MAP_FEED_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Time span a history query covers when `from` is not given
+pub const HISTORY_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Position reports per history page, by default and at most
+pub const HISTORY_PAGE_SIZE: usize = 500;
+pub const MAX_HISTORY_PAGE_SIZE: usize = 5000;
+
+/// Default Douglas-Peucker tolerance of a vessel track, in metres
+pub const TRACK_TOLERANCE_M: f64 = 10.0;
+
+/// Fixes read for one track at most
+pub const MAX_TRACK_FIXES: usize = 100_000;
+
+/// Environment variable holding the own-ship MMSI
+pub const OWN_MMSI_ENV: &str = "YACHTPIT_OWN_MMSI";
+
+/// Own-ship MMSI from [`OWN_MMSI_ENV`], when set to a nine-digit number
+pub fn own_mmsi_from_env() -> Option<String> {
+    std::env::var(OWN_MMSI_ENV)
+        .ok()
+        .map(|mmsi| mmsi.trim().to_string())
+        .filter(|mmsi| mmsi.len() == 9 && mmsi.bytes().all(|byte| byte.is_ascii_digit()))
 }
 
 #[derive(Deserialize, Debug)]
@@ -40,12 +67,92 @@ pub struct BoundingBoxQuery {
     ne_lon: f64,  // Northeast longitude
 }
 
+// Optional bounding box of the SSE stream, named as for /ais.
+#[derive(Deserialize, Debug, Default)]
+pub struct StreamQuery {
+    sw_lat: Option<f64>,
+    sw_lon: Option<f64>,
+    ne_lat: Option<f64>,
+    ne_lon: Option<f64>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct WebSocketBoundingBox {
-    sw_lat: f64,  // Southwest latitude
-    sw_lon: f64,  // Southwest longitude
-    ne_lat: f64,  // Northeast latitude
-    ne_lon: f64,  // Northeast longitude
+    pub(crate) sw_lat: f64,  // Southwest latitude
+    pub(crate) sw_lon: f64,  // Southwest longitude
+    pub(crate) ne_lat: f64,  // Northeast latitude
+    pub(crate) ne_lon: f64,  // Northeast longitude
+}
+
+impl WebSocketBoundingBox {
+    // Parse `sw_lat,sw_lon,ne_lat,ne_lon`.
+    pub(crate) fn parse(text: &str) -> Option<Self> {
+        let corners: Vec<f64> = text.split(',').map(|value| value.trim().parse().ok()).collect::<Option<_>>()?;
+        match corners[..] {
+            [sw_lat, sw_lon, ne_lat, ne_lon] if sw_lat <= ne_lat && sw_lon <= ne_lon => {
+                Some(Self { sw_lat, sw_lon, ne_lat, ne_lon })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct HistoryParams {
+    mmsi: Option<String>,
+    from: Option<i64>,  // Unix milliseconds, default HISTORY_WINDOW before `to`
+    to: Option<i64>,    // Unix milliseconds, default now
+    bbox: Option<String>,  // sw_lat,sw_lon,ne_lat,ne_lon
+    limit: Option<usize>,
+    offset: Option<usize>,
+}
+
+// A page of stored position reports; `next_offset` is set while more follow.
+#[derive(Serialize, Debug)]
+pub struct HistoryPage {
+    positions: Vec<StoredAisMessage>,
+    next_offset: Option<usize>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TrackParams {
+    from: Option<i64>,  // Unix milliseconds, default HISTORY_WINDOW before `to`
+    to: Option<i64>,    // Unix milliseconds, default now
+    tolerance_m: Option<f64>,
+}
+
+// A vessel's simplified track, oldest point first.
+#[derive(Serialize, Debug)]
+pub struct VesselTrack {
+    mmsi: String,
+    from: i64,
+    to: i64,
+    /// Fixes on record before simplification
+    fix_count: usize,
+    points: Vec<TrackPoint>,
+}
+
+// Health of the relay, for clients showing whether live AIS is coming in.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RelayStatus {
+    uptime_s: u64,
+    /// Whether the upstreams are connected for clients; they idle without any
+    stream_running: bool,
+    clients: usize,
+    /// Vessels with cached static data
+    vessels: usize,
+    /// Unix milliseconds of the newest report from any upstream
+    last_message_at: Option<i64>,
+    upstreams: Vec<UpstreamStatus>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UpstreamStatus {
+    name: String,
+    connected: bool,
+    last_message_at: Option<i64>,
+    messages: u64,
+    reconnects: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -54,42 +161,190 @@ pub struct WebSocketMessage {
     message_type: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     bounding_box: Option<WebSocketBoundingBox>,
+    // Filters of a `set_filter` message; a report must match each one given
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    mmsis: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    ship_types: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    navigation_statuses: Vec<String>,
+    // Named areas replacing all others in a `set_bounding_box` message, the
+    // one of an `add_area` message, and the name of a `remove_area` message
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    areas: Vec<WatchArea>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    area: Option<WatchArea>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    // Own ship of a `set_own_ship` message; collision alerts stop without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    own_ship: Option<OwnShipState>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct AisResponse {
-    message_type: Option<String>,
-    mmsi: Option<String>,
-    ship_name: Option<String>,
-    latitude: Option<f64>,
-    longitude: Option<f64>,
-    timestamp: Option<String>,
-    speed_over_ground: Option<f64>,
-    course_over_ground: Option<f64>,
-    heading: Option<f64>,
-    navigation_status: Option<String>,
-    ship_type: Option<String>,
-    raw_message: Value,
-}
-
-// Manages the lifecycle of the upstream AIS stream.
+    pub(crate) message_type: Option<String>,
+    pub(crate) mmsi: Option<String>,
+    pub(crate) ship_name: Option<String>,
+    pub(crate) latitude: Option<f64>,
+    pub(crate) longitude: Option<f64>,
+    pub(crate) timestamp: Option<String>,
+    pub(crate) speed_over_ground: Option<f64>,
+    pub(crate) course_over_ground: Option<f64>,
+    pub(crate) heading: Option<f64>,
+    pub(crate) navigation_status: Option<String>,
+    pub(crate) ship_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) callsign: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) dimensions: Option<VesselDimensions>,
+    /// Set for reports from our own transponder
+    #[serde(default)]
+    pub(crate) own_ship: bool,
+    /// The upstream the report came from, e.g. `aisstream` or `tcp://host:port`
+    #[serde(default)]
+    pub(crate) source: Option<String>,
+    pub(crate) raw_message: Value,
+}
+
+// Manages the lifecycle of the upstream AIS streams.
 pub struct AisStreamManager {
     state: Mutex<ManagerState>,
+    upstreams: Vec<Upstream>,
+    own_mmsi: Option<String>,
+    /// Static data per vessel; outlives the stream so names survive restarts
+    vessels: Arc<VesselRegistry>,
+    /// Latest motion per vessel, for collision checks
+    traffic: Arc<Traffic>,
+    /// Latest position report per vessel, for bounding-box queries
+    positions: Arc<VesselIndex>,
+    metrics: Arc<AisMetrics>,
+    started: Instant,
+    /// Per-vessel position report interval; every report goes out when unset
+    downsample: Option<Duration>,
+    store: Option<AisStore>,
+    access: AccessPolicy,
+    clients: ClientSlots,
 }
 
 // The internal state of the manager, protected by a Mutex.
 #[derive(Default)]
 struct ManagerState {
     tx: Option<broadcast::Sender<AisResponse>>,
-    stream_task: Option<JoinHandle<()>>,
+    /// One per upstream
+    stream_tasks: Vec<JoinHandle<()>>,
+    persist_task: Option<JoinHandle<()>>,
     cancellation_token: Option<CancellationToken>,
     client_count: usize,
 }
 
 impl AisStreamManager {
-    pub(crate) fn new() -> Self {
+    // Merges the reports of every upstream into one stream.
+    pub(crate) fn new(upstreams: Vec<Upstream>) -> Self {
         Self {
             state: Mutex::new(ManagerState::default()),
+            upstreams,
+            own_mmsi: None,
+            vessels: Arc::default(),
+            traffic: Arc::default(),
+            positions: Arc::default(),
+            metrics: Arc::default(),
+            started: Instant::now(),
+            downsample: None,
+            store: None,
+            access: AccessPolicy::default(),
+            clients: ClientSlots::default(),
+        }
+    }
+
+    // Tokens and limits applied to stream clients.
+    pub(crate) fn with_access(mut self, access: AccessPolicy) -> Self {
+        self.access = access;
+        self
+    }
+
+    pub(crate) fn access(&self) -> &AccessPolicy {
+        &self.access
+    }
+
+    // A slot for a new stream client, unless the cap is reached.
+    pub(crate) fn admit_client(&self) -> Option<ClientSlot> {
+        self.clients.acquire(self.access.max_clients)
+    }
+
+    pub(crate) fn vessels(&self) -> &VesselRegistry {
+        &self.vessels
+    }
+
+    pub(crate) fn store(&self) -> Option<&AisStore> {
+        self.store.as_ref()
+    }
+
+    // Messages received while the stream runs are also written to `store`.
+    pub(crate) fn with_store(mut self, store: Option<AisStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    pub(crate) fn with_downsampling(mut self, interval: Option<Duration>) -> Self {
+        self.downsample = interval;
+        self
+    }
+
+    // Static data already known, e.g. loaded from the store.
+    pub(crate) fn with_vessels(mut self, vessels: VesselRegistry) -> Self {
+        self.vessels = Arc::new(vessels);
+        self
+    }
+
+    // Reports from this MMSI are flagged as own ship in the stream.
+    pub(crate) fn with_own_mmsi(mut self, own_mmsi: Option<String>) -> Self {
+        self.own_mmsi = own_mmsi;
+        self
+    }
+
+    // The relay's metrics in the Prometheus text format.
+    pub(crate) async fn render_metrics(&self) -> String {
+        let gauges = {
+            let state = self.state.lock().await;
+            StreamGauges {
+                clients: state.client_count,
+                max_clients: self.access.max_clients,
+                stream_running: state.tx.is_some(),
+                broadcast_queue: state.tx.as_ref().map_or(0, |tx| tx.len()),
+                vessels: self.vessels.len(),
+            }
+        };
+        self.metrics.render(gauges)
+    }
+
+    pub(crate) async fn status(&self) -> RelayStatus {
+        let (stream_running, clients) = {
+            let state = self.state.lock().await;
+            (state.tx.is_some(), state.client_count)
+        };
+        let upstreams: Vec<UpstreamStatus> = self
+            .upstreams
+            .iter()
+            .map(|upstream| {
+                let name = upstream.to_string();
+                let counters = self.metrics.upstream_counters(&name);
+                UpstreamStatus {
+                    name,
+                    connected: counters.connected,
+                    last_message_at: counters.last_message_at,
+                    messages: counters.received,
+                    reconnects: counters.reconnects,
+                }
+            })
+            .collect();
+        RelayStatus {
+            uptime_s: self.started.elapsed().as_secs(),
+            stream_running,
+            clients,
+            vessels: self.vessels.len(),
+            last_message_at: upstreams.iter().filter_map(|upstream| upstream.last_message_at).max(),
+            upstreams,
         }
     }
 
@@ -101,24 +356,34 @@ impl AisStreamManager {
         state.client_count += 1;
         println!("Client connected. Total clients: {}", state.client_count);
 
-        if state.stream_task.is_none() {
+        if let Some(tx) = &state.tx {
+            // Stream is already running, return the existing sender.
+            tx.clone()
+        } else {
             println!("Starting new AIS stream...");
             let (tx, _) = broadcast::channel(1000);
             let token = CancellationToken::new();
 
-            let stream_task = tokio::spawn(connect_to_ais_stream_with_broadcast(
-                tx.clone(),
-                token.clone(),
-            ));
-
+            let merger = Arc::new(
+                UpstreamMerger::new(tx.clone(), self.own_mmsi.clone(), self.vessels.clone(), self.metrics.clone())
+                    .with_downsampling(self.downsample)
+                    .with_traffic(self.traffic.clone())
+                    .with_index(self.positions.clone()),
+            );
+            state.stream_tasks = self
+                .upstreams
+                .iter()
+                .map(|upstream| tokio::spawn(run_upstream(upstream.clone(), merger.clone(), token.clone())))
+                .collect();
+
+            state.persist_task = self
+                .store
+                .clone()
+                .map(|store| tokio::spawn(persist_stream(store, tx.subscribe(), self.vessels.clone(), self.metrics.clone(), token.clone())));
             state.tx = Some(tx.clone());
-            state.stream_task = Some(stream_task);
             state.cancellation_token = Some(token);
             println!("AIS stream started.");
             tx
-        } else {
-            // Stream is already running, return the existing sender.
-            state.tx.as_ref().unwrap().clone()
         }
     }
 
@@ -134,8 +399,12 @@ impl AisStreamManager {
             if let Some(token) = state.cancellation_token.take() {
                 token.cancel();
             }
-            if let Some(task) = state.stream_task.take() {
-                // Wait for the task to finish to ensure clean shutdown.
+            for task in std::mem::take(&mut state.stream_tasks) {
+                // Wait for the tasks to finish to ensure clean shutdown.
+                let _ = task.await;
+            }
+            if let Some(task) = state.persist_task.take() {
+                // The last batch is written before the stream counts as stopped
                 let _ = task.await;
             }
             state.tx = None;
@@ -165,125 +434,147 @@ pub struct AppState {
     pub(crate) ais_stream_manager: Arc<AisStreamManager>,
 }
 
-// Convert raw AIS message to structured response
-fn parse_ais_message(ais_message: &Value) -> AisResponse {
-    let message_type = ais_message.get("MessageType")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-
-    let metadata = ais_message.get("MetaData");
-    let mmsi = metadata
-        .and_then(|m| m.get("MMSI"))
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-
-    let ship_name = metadata
-        .and_then(|m| m.get("ShipName"))
-        .and_then(|v| v.as_str())
-        .map(|s| s.trim().to_string());
-
-    let latitude = metadata
-        .and_then(|m| m.get("latitude"))
-        .and_then(|v| v.as_f64());
-
-    let longitude = metadata
-        .and_then(|m| m.get("longitude"))
-        .and_then(|v| v.as_f64());
-
-    let timestamp = metadata
-        .and_then(|m| m.get("time_utc"))
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-
-    // Extract position report data
-    let message = ais_message.get("Message");
-    let pos_report = message.and_then(|m| m.get("PositionReport"));
-
-    let speed_over_ground = pos_report
-        .and_then(|pr| pr.get("Sog"))
-        .and_then(|v| v.as_f64());
-
-    let course_over_ground = pos_report
-        .and_then(|pr| pr.get("Cog"))
-        .and_then(|v| v.as_f64());
-
-    let heading = pos_report
-        .and_then(|pr| pr.get("TrueHeading"))
-        .and_then(|v| v.as_f64());
-
-    let navigation_status = pos_report
-        .and_then(|pr| pr.get("NavigationalStatus"))
-        .and_then(|v| v.as_u64())
-        .map(|status| match status {
-            0 => "Under way using engine",
-            1 => "At anchor",
-            2 => "Not under command",
-            3 => "Restricted manoeuvrability",
-            4 => "Constrained by her draught",
-            5 => "Moored",
-            6 => "Aground",
-            7 => "Engaged in fishing",
-            8 => "Under way sailing",
-            _ => "Other"
-        }.to_string());
-
-    // Extract ship type from static data
-    let ship_type = message
-        .and_then(|m| m.get("StaticDataReport"))
-        .and_then(|sdr| sdr.get("ReportB"))
-        .and_then(|rb| rb.get("ShipType"))
-        .and_then(|v| v.as_u64())
-        .map(|st| get_ship_type_description(st).to_string());
-
-    AisResponse {
-        message_type,
+// HTTP endpoint paging through stored position reports, for drawing where
+// vessels have been
+pub(crate) async fn get_ais_history(
+    Query(params): Query<HistoryParams>,
+    State(state): State<AppState>,
+) -> Result<Json<HistoryPage>, (StatusCode, String)> {
+    let store = history_store(&state)?;
+    let (from, to) = history_range(params.from, params.to)?;
+    let bounding_box = match params.bbox.as_deref() {
+        Some(bbox) => Some(WebSocketBoundingBox::parse(bbox).ok_or((
+            StatusCode::BAD_REQUEST,
+            "`bbox` must be sw_lat,sw_lon,ne_lat,ne_lon".to_string(),
+        ))?),
+        None => None,
+    };
+    let limit = params.limit.unwrap_or(HISTORY_PAGE_SIZE).clamp(1, MAX_HISTORY_PAGE_SIZE);
+    let offset = params.offset.unwrap_or(0);
+
+    // One row beyond the page tells whether another follows
+    let query = PositionQuery {
+        mmsi: params.mmsi.filter(|mmsi| !mmsi.is_empty()),
+        from,
+        to,
+        bounding_box,
+        limit: limit + 1,
+        offset,
+    };
+    let mut positions = query_positions(store, &query).await?;
+    let next_offset = (positions.len() > limit).then_some(offset + limit);
+    positions.truncate(limit);
+    Ok(Json(HistoryPage { positions, next_offset }))
+}
+
+// HTTP endpoint checking a thin client's own ship against the vessels on
+// the stream, for clients that cannot work out CPA and TCPA themselves
+pub(crate) async fn post_collision_check(
+    State(state): State<AppState>,
+    Json(own_ship): Json<OwnShipState>,
+) -> Result<Json<Vec<CollisionAlert>>, (StatusCode, String)> {
+    let now = tokio::time::Instant::now();
+    let own = OwnShip::new(&own_ship, now).ok_or((
+        StatusCode::BAD_REQUEST,
+        "Own ship position, speed or thresholds out of range".to_string(),
+    ))?;
+    Ok(Json(state.ais_stream_manager.traffic.alerts(&own, now)))
+}
+
+// HTTP endpoint returning a vessel's track, simplified for drawing as is
+pub(crate) async fn get_vessel_track(
+    Path(mmsi): Path<String>,
+    Query(params): Query<TrackParams>,
+    State(state): State<AppState>,
+) -> Result<Json<VesselTrack>, (StatusCode, String)> {
+    let store = history_store(&state)?;
+    let (from, to) = history_range(params.from, params.to)?;
+    let tolerance_m = params.tolerance_m.unwrap_or(TRACK_TOLERANCE_M);
+    if !tolerance_m.is_finite() || tolerance_m < 0.0 {
+        return Err((StatusCode::BAD_REQUEST, "`tolerance_m` must be a distance in metres".to_string()));
+    }
+
+    let query = PositionQuery {
+        mmsi: Some(mmsi.clone()),
+        from,
+        to,
+        bounding_box: None,
+        limit: MAX_TRACK_FIXES,
+        offset: 0,
+    };
+    let fixes: Vec<TrackPoint> = query_positions(store, &query)
+        .await?
+        .into_iter()
+        .filter_map(|stored| {
+            Some(TrackPoint {
+                latitude: stored.message.latitude?,
+                longitude: stored.message.longitude?,
+                received_at: stored.received_at,
+            })
+        })
+        .collect();
+    Ok(Json(VesselTrack {
         mmsi,
-        ship_name,
-        latitude,
-        longitude,
-        timestamp,
-        speed_over_ground,
-        course_over_ground,
-        heading,
-        navigation_status,
-        ship_type,
-        raw_message: ais_message.clone(),
+        from,
+        to,
+        fix_count: fixes.len(),
+        points: simplify(&fixes, tolerance_m),
+    }))
+}
+
+fn history_store(state: &AppState) -> Result<&AisStore, (StatusCode, String)> {
+    state
+        .ais_stream_manager
+        .store()
+        .ok_or((StatusCode::SERVICE_UNAVAILABLE, "AIS history is not being recorded".to_string()))
+}
+
+// The `from..to` window of a history request, defaulting to the last HISTORY_WINDOW.
+pub(crate) fn history_range(from: Option<i64>, to: Option<i64>) -> Result<(i64, i64), (StatusCode, String)> {
+    let to = to.unwrap_or_else(|| unix_millis(std::time::SystemTime::now()));
+    let from = from.unwrap_or(to - HISTORY_WINDOW.as_millis() as i64);
+    if from >= to {
+        return Err((StatusCode::BAD_REQUEST, "`from` must be before `to`".to_string()));
     }
+    Ok((from, to))
+}
+
+pub(crate) async fn query_positions(store: &AisStore, query: &PositionQuery) -> Result<Vec<StoredAisMessage>, (StatusCode, String)> {
+    store.positions(query).await.map_err(|e| {
+        eprintln!("AIS history query failed: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, "AIS history query failed".to_string())
+    })
 }
 
-// HTTP endpoint to get AIS data for a bounding box
+// Upstream connection state, clients and vessels, as JSON
+pub(crate) async fn get_status(State(state): State<AppState>) -> Json<RelayStatus> {
+    Json(state.ais_stream_manager.status().await)
+}
+
+// Prometheus scrape endpoint for the relay's throughput, clients and queues
+pub(crate) async fn get_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(axum::http::header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)],
+        state.ais_stream_manager.render_metrics().await,
+    )
+}
+
+// HTTP endpoint returning the latest report of each vessel within a
+// bounding box, as heard on the stream
 pub(crate) async fn get_ais_data(
     Query(params): Query<BoundingBoxQuery>,
-    State(_state): State<AppState>,
+    State(state): State<AppState>,
 ) -> Result<Json<Vec<AisResponse>>, StatusCode> {
-    println!("Received bounding box request: {:?}", params);
-
-    // This remains a placeholder. A full implementation could query a database
-    // populated by the AIS stream.
-
-    let response = vec![AisResponse {
-        message_type: Some("Info".to_string()),
-        mmsi: None,
-        ship_name: Some("Bounding Box Query Received".to_string()),
-        latitude: Some((params.sw_lat + params.ne_lat) / 2.0),
-        longitude: Some((params.sw_lon + params.ne_lon) / 2.0),
-        timestamp: Some("Query processed".to_string()),
-        speed_over_ground: None,
-        course_over_ground: None,
-        heading: None,
-        navigation_status: Some("Query processed".to_string()),
-        ship_type: None,
-        raw_message: serde_json::json!({
-            "bounding_box": {
-                "sw_lat": params.sw_lat,
-                "sw_lon": params.sw_lon,
-                "ne_lat": params.ne_lat,
-                "ne_lon": params.ne_lon
-            }
-        }),
-    }];
-
-    Ok(Json(response))
+    if params.sw_lat > params.ne_lat || params.sw_lon > params.ne_lon {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let bbox = WebSocketBoundingBox {
+        sw_lat: params.sw_lat,
+        sw_lon: params.sw_lon,
+        ne_lat: params.ne_lat,
+        ne_lon: params.ne_lon,
+    };
+    Ok(Json(state.ais_stream_manager.positions.query(&bbox)))
 }
 
 
@@ -292,11 +583,88 @@ pub(crate) async fn websocket_handler(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
 ) -> Response {
-    ws.on_upgrade(|socket| handle_websocket(socket, state.ais_stream_manager))
+    upgrade_client(ws, state.ais_stream_manager, None)
+}
+
+// WebSocket handler for the map overlay: the latest state of each vessel,
+// sent at most once per MAP_FEED_INTERVAL
+pub(crate) async fn map_websocket_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+) -> Response {
+    upgrade_client(ws, state.ais_stream_manager, Some(MAP_FEED_INTERVAL))
+}
+
+// Accept a stream client if there is room for it
+fn upgrade_client(ws: WebSocketUpgrade, manager: Arc<AisStreamManager>, throttle: Option<Duration>) -> Response {
+    let Some(slot) = manager.admit_client() else {
+        println!("Refusing stream client: {} already connected", manager.clients.in_use());
+        return (StatusCode::SERVICE_UNAVAILABLE, "Too many AIS stream clients").into_response();
+    };
+    ws.protocols([PROTOBUF_SUBPROTOCOL]).on_upgrade(move |socket| async move {
+        handle_websocket(socket, manager, throttle).await;
+        drop(slot);
+    })
+}
+
+// Server-Sent Events stream of AIS reports, for displays that cannot speak
+// WebSocket. One-way, so the bounding box comes from the query string.
+pub(crate) async fn sse_handler(Query(params): Query<StreamQuery>, State(state): State<AppState>) -> Response {
+    let bounding_box = match (params.sw_lat, params.sw_lon, params.ne_lat, params.ne_lon) {
+        (None, None, None, None) => None,
+        (Some(sw_lat), Some(sw_lon), Some(ne_lat), Some(ne_lon)) => Some(WebSocketBoundingBox { sw_lat, sw_lon, ne_lat, ne_lon }),
+        _ => {
+            return (StatusCode::BAD_REQUEST, "A bounding box needs sw_lat, sw_lon, ne_lat and ne_lon").into_response();
+        }
+    };
+    let manager = state.ais_stream_manager;
+    let Some(slot) = manager.admit_client() else {
+        println!("Refusing stream client: {} already connected", manager.clients.in_use());
+        return (StatusCode::SERVICE_UNAVAILABLE, "Too many AIS stream clients").into_response();
+    };
+    let mut subscription = Subscription::default();
+    subscription.bounding_box = bounding_box;
+    let events = client_reports(manager, subscription, slot)
+        .await
+        .filter_map(|data| async move { Event::default().event("ais").json_data(&data).ok().map(Ok::<_, std::convert::Infallible>) });
+    Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+}
+
+// The reports a one-way stream client (SSE, gRPC) subscribed to, within its
+// rate limit. The client counts as connected until the stream is dropped.
+pub(crate) async fn client_reports(
+    manager: Arc<AisStreamManager>,
+    subscription: Subscription,
+    slot: ClientSlot,
+) -> impl futures_util::Stream<Item = AisResponse> {
+    let rx = manager.start_stream_if_needed().await.subscribe();
+    let guard = ConnectionGuard { manager: manager.clone() };
+    let rate_limit = RateLimiter::new(manager.access.client_rate);
+    let client = (rx, subscription, rate_limit, guard, slot);
+    futures_util::stream::unfold(client, |mut client| async move {
+        let (rx, subscription, rate_limit, guard, _) = &mut client;
+        loop {
+            match rx.recv().await {
+                Ok(data) => {
+                    subscription.observe(&data);
+                    if !subscription.matches(&data) {
+                        continue;
+                    }
+                    if !rate_limit.allow() {
+                        guard.manager.metrics.rate_limited();
+                        continue;
+                    }
+                    return Some((data, client));
+                }
+                Err(broadcast::error::RecvError::Lagged(n)) => guard.manager.metrics.lagged(n),
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
 }
 
 // Function to check if AIS data is within bounding box
-fn is_within_bounding_box(ais_data: &AisResponse, bbox: &WebSocketBoundingBox) -> bool {
+pub(crate) fn is_within_bounding_box(ais_data: &AisResponse, bbox: &WebSocketBoundingBox) -> bool {
     if let (Some(lat), Some(lon)) = (ais_data.latitude, ais_data.longitude) {
         lat >= bbox.sw_lat && lat <= bbox.ne_lat &&
             lon >= bbox.sw_lon && lon <= bbox.ne_lon
@@ -305,8 +673,159 @@ fn is_within_bounding_box(ais_data: &AisResponse, bbox: &WebSocketBoundingBox) -
     }
 }
 
-// Handle individual WebSocket connections
-async fn handle_websocket(mut socket: WebSocket, manager: Arc<AisStreamManager>) {
+// Latest state per MMSI, collected between map feed flushes
+#[derive(Default)]
+struct MapFeed {
+    pending: HashMap<String, AisResponse>,
+}
+
+impl MapFeed {
+    // Merge a report into the vessel's pending state. Position and static
+    // reports carry different fields, so a newer report only replaces the
+    // fields it has. Reports without an MMSI cannot be placed and are dropped.
+    fn push(&mut self, data: AisResponse) {
+        let Some(mmsi) = data.mmsi.clone() else {
+            return;
+        };
+        match self.pending.get_mut(&mmsi) {
+            Some(state) => {
+                fn take<T>(field: &mut Option<T>, newer: Option<T>) {
+                    if newer.is_some() {
+                        *field = newer;
+                    }
+                }
+                take(&mut state.message_type, data.message_type);
+                take(&mut state.ship_name, data.ship_name);
+                take(&mut state.latitude, data.latitude);
+                take(&mut state.longitude, data.longitude);
+                take(&mut state.timestamp, data.timestamp);
+                take(&mut state.speed_over_ground, data.speed_over_ground);
+                take(&mut state.course_over_ground, data.course_over_ground);
+                take(&mut state.heading, data.heading);
+                take(&mut state.navigation_status, data.navigation_status);
+                take(&mut state.ship_type, data.ship_type);
+                take(&mut state.callsign, data.callsign);
+                take(&mut state.dimensions, data.dimensions);
+                take(&mut state.source, data.source);
+                state.own_ship |= data.own_ship;
+                state.raw_message = data.raw_message;
+            }
+            None => {
+                self.pending.insert(mmsi, data);
+            }
+        }
+    }
+
+    // Vessels updated since the last flush
+    fn drain(&mut self) -> Vec<AisResponse> {
+        self.pending.drain().map(|(_, state)| state).collect()
+    }
+}
+
+// How a WebSocket client gets its AIS reports. Browsers get JSON unless
+// they ask for the protobuf subprotocol; status messages and collision
+// alerts are JSON text either way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReportFormat {
+    Json,
+    Protobuf,
+}
+
+impl ReportFormat {
+    fn negotiated(socket: &WebSocket) -> Self {
+        match socket.protocol().and_then(|protocol| protocol.to_str().ok()) {
+            Some(PROTOBUF_SUBPROTOCOL) => ReportFormat::Protobuf,
+            _ => ReportFormat::Json,
+        }
+    }
+
+    // The frame carrying `data`, tagged with the client's named `areas` it lies in
+    fn frame(self, data: &AisResponse, areas: &[String]) -> Option<WsMessage> {
+        match self {
+            ReportFormat::Json => serde_json::to_string(&TaggedReport { data, areas }).ok().map(WsMessage::Text),
+            ReportFormat::Protobuf => {
+                let mut report = vessel_report(data.clone(), 0);
+                report.areas = areas.to_vec();
+                Some(WsMessage::Binary(report.encode_to_vec()))
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TaggedReport<'a> {
+    #[serde(flatten)]
+    data: &'a AisResponse,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    areas: &'a [String],
+}
+
+// Send one AIS report if the client subscribed to it; false once the client is gone
+async fn send_ais_data(
+    socket: &mut WebSocket,
+    format: ReportFormat,
+    data: &AisResponse,
+    subscription: &Subscription,
+    rate_limit: &mut RateLimiter,
+    metrics: &AisMetrics,
+) -> bool {
+    let Some(areas) = subscription.matching_areas(data) else {
+        return true;
+    };
+    if !rate_limit.allow() {
+        metrics.rate_limited();
+        if rate_limit.dropped().is_power_of_two() {
+            println!("WebSocket client over its rate limit; {} messages dropped", rate_limit.dropped());
+        }
+        return true;
+    }
+    match format.frame(data, &areas) {
+        Some(frame) => socket.send(frame).await.is_ok(),
+        None => true,
+    }
+}
+
+// Send the last known report of each vessel within `boxes` that the client
+// subscribed to, so its map fills in before the next live reports; false
+// once the client is gone. The burst is not held to the client's rate limit.
+async fn send_backfill(
+    socket: &mut WebSocket,
+    format: ReportFormat,
+    positions: &VesselIndex,
+    boxes: &[WebSocketBoundingBox],
+    subscription: &mut Subscription,
+) -> bool {
+    let mut sent = std::collections::HashSet::new();
+    let known = boxes.iter().flat_map(|bbox| positions.query(bbox));
+    for data in known {
+        if !sent.insert(data.mmsi.clone()) {
+            continue;
+        }
+        subscription.observe(&data);
+        let Some(areas) = subscription.matching_areas(&data) else {
+            continue;
+        };
+        if let Some(frame) = format.frame(&data, &areas) {
+            if socket.send(frame).await.is_err() {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+// Send a `collision_alert` message; false once the client is gone
+async fn send_collision_alert(socket: &mut WebSocket, alert: &CollisionAlert) -> bool {
+    match serde_json::to_string(alert) {
+        Ok(json_data) => socket.send(WsMessage::Text(json_data)).await.is_ok(),
+        Err(_) => true,
+    }
+}
+
+// Handle individual WebSocket connections. With a `throttle` interval the
+// reports are coalesced per vessel and flushed on that interval instead of
+// being forwarded as they arrive.
+async fn handle_websocket(mut socket: WebSocket, manager: Arc<AisStreamManager>, throttle: Option<Duration>) {
     // This guard ensures that when the function returns (and the connection closes),
     // the client count is decremented.
     let _guard = ConnectionGuard { manager: manager.clone() };
@@ -315,8 +834,14 @@ async fn handle_websocket(mut socket: WebSocket, manager: Arc<AisStreamManager>)
     let ais_tx = manager.start_stream_if_needed().await;
     let mut ais_rx = ais_tx.subscribe();
 
-    // Store bounding box state for this connection
-    let mut bounding_box: Option<WebSocketBoundingBox> = None;
+    // Bounding box and filters this client asked for
+    let mut subscription = Subscription::default();
+    let mut collisions = CollisionWatch::default();
+    let format = ReportFormat::negotiated(&socket);
+
+    let mut map_feed = MapFeed::default();
+    let mut flush = tokio::time::interval(throttle.unwrap_or(MAP_FEED_INTERVAL));
+    let mut rate_limit = RateLimiter::new(manager.access.client_rate);
 
     // Send initial connection confirmation
     if socket.send(WsMessage::Text("Connected to AIS stream".to_string())).await.is_err() {
@@ -332,14 +857,64 @@ async fn handle_websocket(mut socket: WebSocket, manager: Arc<AisStreamManager>)
                     Some(Ok(WsMessage::Text(text))) => {
                         // Try to parse as a command message
                         if let Ok(ws_msg) = serde_json::from_str::<WebSocketMessage>(&text) {
-                            if ws_msg.message_type == "set_bounding_box" {
-                                if let Some(bbox) = ws_msg.bounding_box {
-                                    println!("Setting bounding box: {:?}", bbox);
-                                    bounding_box = Some(bbox);
-                                } else {
-                                    println!("Clearing bounding box");
-                                    bounding_box = None;
+                            match ws_msg.message_type.as_str() {
+                                "set_bounding_box" => {
+                                    // Either one unnamed box or a list of named areas
+                                    let mut boxes: Vec<WebSocketBoundingBox> =
+                                        ws_msg.areas.iter().map(|area| area.bounding_box.clone()).collect();
+                                    if let Some(bbox) = ws_msg.bounding_box {
+                                        println!("Setting bounding box: {:?}", bbox);
+                                        boxes.push(bbox.clone());
+                                        subscription.bounding_box = Some(bbox);
+                                    } else {
+                                        subscription.bounding_box = None;
+                                    }
+                                    if !ws_msg.areas.is_empty() {
+                                        println!("Watching {} named areas", ws_msg.areas.len());
+                                    } else if boxes.is_empty() {
+                                        println!("Clearing bounding box");
+                                    }
+                                    subscription.set_areas(ws_msg.areas);
+                                    if !send_backfill(&mut socket, format, &manager.positions, &boxes, &mut subscription).await {
+                                        return;
+                                    }
+                                }
+                                "add_area" => {
+                                    let Some(area) = ws_msg.area else {
+                                        continue;
+                                    };
+                                    let bbox = area.bounding_box.clone();
+                                    if !subscription.add_area(area) {
+                                        println!("Client already watches {} areas", crate::subscription::MAX_AREAS);
+                                        continue;
+                                    }
+                                    if !send_backfill(&mut socket, format, &manager.positions, &[bbox], &mut subscription).await {
+                                        return;
+                                    }
                                 }
+                                "remove_area" => {
+                                    if let Some(name) = &ws_msg.name {
+                                        subscription.remove_area(name);
+                                    }
+                                }
+                                "set_filter" => {
+                                    println!("Setting subscription filter: {}", text);
+                                    subscription.set_filters(ws_msg.mmsis, ws_msg.ship_types, ws_msg.navigation_statuses);
+                                }
+                                "clear_filter" => {
+                                    println!("Clearing subscription filter");
+                                    subscription.clear_filters();
+                                }
+                                "set_own_ship" => {
+                                    let now = tokio::time::Instant::now();
+                                    let own = ws_msg.own_ship.and_then(|state| OwnShip::new(&state, now));
+                                    for alert in collisions.set_own_ship(own, &manager.traffic, now) {
+                                        if !send_collision_alert(&mut socket, &alert).await {
+                                            return;
+                                        }
+                                    }
+                                }
+                                _ => {}
                             }
                         } else {
                             // Echo back unrecognized messages
@@ -361,21 +936,22 @@ async fn handle_websocket(mut socket: WebSocket, manager: Arc<AisStreamManager>)
             ais_data_result = ais_rx.recv() => {
                 match ais_data_result {
                     Ok(data) => {
-                        // Apply bounding box filter if it exists
-                        let should_send = bounding_box.as_ref()
-                            .map(|bbox| is_within_bounding_box(&data, bbox))
-                            .unwrap_or(true); // Send if no bbox is set
-
-                        if should_send {
-                            if let Ok(json_data) = serde_json::to_string(&data) {
-                                if socket.send(WsMessage::Text(json_data)).await.is_err() {
-                                    // Client is likely disconnected
-                                    break;
-                                }
+                        subscription.observe(&data);
+                        // Alerts go out whatever the client subscribed to
+                        if let Some(alert) = collisions.check(&data, tokio::time::Instant::now()) {
+                            if !send_collision_alert(&mut socket, &alert).await {
+                                break;
                             }
                         }
+                        if throttle.is_some() {
+                            map_feed.push(data);
+                        } else if !send_ais_data(&mut socket, format, &data, &subscription, &mut rate_limit, &manager.metrics).await {
+                            // Client is likely disconnected
+                            break;
+                        }
                     }
                     Err(broadcast::error::RecvError::Lagged(n)) => {
+                        manager.metrics.lagged(n);
                         println!("WebSocket client lagged behind by {} messages", n);
                     }
                     Err(broadcast::error::RecvError::Closed) => {
@@ -384,164 +960,20 @@ async fn handle_websocket(mut socket: WebSocket, manager: Arc<AisStreamManager>)
                     }
                 }
             }
-        }
-    }
-}
-
-
-
-fn print_detailed_ais_message(ais_message: &Value) {
-    println!("\n=== AIS MESSAGE DETAILS ===");
-
-    // Print message type
-    if let Some(msg_type) = ais_message.get("MessageType") {
-        println!("Message Type: {}", msg_type);
-    }
-
-    // Print metadata information
-    if let Some(metadata) = ais_message.get("MetaData") {
-        if let Some(timestamp) = metadata.get("time_utc") {
-            println!("Timestamp: {}", timestamp);
-        }
-
-        if let Some(mmsi) = metadata.get("MMSI") {
-            println!("MMSI: {}", mmsi);
-        }
-
-        if let Some(ship_name) = metadata.get("ShipName") {
-            println!("Ship Name: {}", ship_name.as_str().unwrap_or("N/A").trim());
-        }
-
-        if let Some(lat) = metadata.get("latitude") {
-            println!("Latitude: {}", lat);
-        }
-
-        if let Some(lon) = metadata.get("longitude") {
-            println!("Longitude: {}", lon);
-        }
-    }
-
-    // Parse message content based on type
-    if let Some(message) = ais_message.get("Message") {
-        // Handle Position Report messages
-        if let Some(pos_report) = message.get("PositionReport") {
-            println!("\n--- Position Report Details ---");
-
-            if let Some(sog) = pos_report.get("Sog") {
-                println!("Speed Over Ground: {} knots", sog);
-            }
-
-            if let Some(cog) = pos_report.get("Cog") {
-                println!("Course Over Ground: {}°", cog);
-            }
-
-            if let Some(heading) = pos_report.get("TrueHeading") {
-                println!("True Heading: {}°", heading);
-            }
-
-            if let Some(nav_status) = pos_report.get("NavigationalStatus") {
-                let status_text = match nav_status.as_u64().unwrap_or(15) {
-                    0 => "Under way using engine",
-                    1 => "At anchor",
-                    2 => "Not under command",
-                    3 => "Restricted manoeuvrability",
-                    4 => "Constrained by her draught",
-                    5 => "Moored",
-                    6 => "Aground",
-                    7 => "Engaged in fishing",
-                    8 => "Under way sailing",
-                    9 => "Reserved for HSC",
-                    10 => "Reserved for WIG",
-                    11 => "Power-driven vessel towing astern",
-                    12 => "Power-driven vessel pushing ahead",
-                    13 => "Reserved for future use",
-                    14 => "AIS-SART, MOB-AIS, EPIRB-AIS",
-                    _ => "Not defined (default)"
-                };
-                println!("Navigation Status: {} ({})", nav_status, status_text);
-            }
-
-            if let Some(rot) = pos_report.get("RateOfTurn") {
-                if rot.as_i64().unwrap_or(127) != 127 {
-                    println!("Rate of Turn: {}°/min", rot);
-                } else {
-                    println!("Rate of Turn: Not available");
-                }
-            }
-
-            if let Some(accuracy) = pos_report.get("PositionAccuracy") {
-                println!("Position Accuracy: {}", if accuracy.as_bool().unwrap_or(false) { "High (< 10m)" } else { "Low (> 10m)" });
-            }
-
-            if let Some(raim) = pos_report.get("Raim") {
-                println!("RAIM: {}", if raim.as_bool().unwrap_or(false) { "In use" } else { "Not in use" });
-            }
-        }
-
-        // Handle Static Data Report messages
-        if let Some(static_report) = message.get("StaticDataReport") {
-            println!("\n--- Static Data Report Details ---");
-
-            if let Some(report_a) = static_report.get("ReportA") {
-                if let Some(name) = report_a.get("Name") {
-                    println!("Vessel Name: {}", name.as_str().unwrap_or("N/A").trim());
-                }
-            }
-
-            if let Some(report_b) = static_report.get("ReportB") {
-                if let Some(call_sign) = report_b.get("CallSign") {
-                    let call_sign_str = call_sign.as_str().unwrap_or("").trim();
-                    if !call_sign_str.is_empty() {
-                        println!("Call Sign: {}", call_sign_str);
-                    }
-                }
-
-                if let Some(ship_type) = report_b.get("ShipType") {
-                    let ship_type_num = ship_type.as_u64().unwrap_or(0);
-                    if ship_type_num > 0 {
-                        println!("Ship Type: {} ({})", ship_type_num, get_ship_type_description(ship_type_num));
-                    }
-                }
-
-                if let Some(dimension) = report_b.get("Dimension") {
-                    let a = dimension.get("A").and_then(|v| v.as_u64()).unwrap_or(0);
-                    let b = dimension.get("B").and_then(|v| v.as_u64()).unwrap_or(0);
-                    let c = dimension.get("C").and_then(|v| v.as_u64()).unwrap_or(0);
-                    let d = dimension.get("D").and_then(|v| v.as_u64()).unwrap_or(0);
-
-                    if a > 0 || b > 0 || c > 0 || d > 0 {
-                        println!("Dimensions: Length {}m ({}m to bow, {}m to stern), Width {}m ({}m to port, {}m to starboard)",
-                                 a + b, a, b, c + d, c, d);
+            // Send the coalesced vessel states to a throttled client
+            _ = flush.tick(), if throttle.is_some() => {
+                for data in map_feed.drain() {
+                    if !send_ais_data(&mut socket, format, &data, &subscription, &mut rate_limit, &manager.metrics).await {
+                        return;
                     }
                 }
             }
         }
-
-        // Handle Voyage Data messages
-        if let Some(voyage_data) = message.get("VoyageData") {
-            println!("\n--- Voyage Data Details ---");
-
-            if let Some(destination) = voyage_data.get("Destination") {
-                println!("Destination: {}", destination.as_str().unwrap_or("N/A").trim());
-            }
-
-            if let Some(eta) = voyage_data.get("Eta") {
-                println!("ETA: {:?}", eta);
-            }
-
-            if let Some(draught) = voyage_data.get("MaximumStaticDraught") {
-                println!("Maximum Draught: {} meters", draught);
-            }
-        }
     }
-
-    // Print raw message for debugging
-    println!("\nRaw JSON: {}", ais_message);
-    println!("========================\n");
 }
 
 
-fn get_ship_type_description(ship_type: u64) -> &'static str {
+pub(crate) fn get_ship_type_description(ship_type: u64) -> &'static str {
     match ship_type {
         20..=29 => "Wing in ground (WIG)",
         30 => "Fishing",
@@ -570,117 +1002,6 @@ fn get_ship_type_description(ship_type: u64) -> &'static str {
 }
 
 
-// Connects to the AIS stream and broadcasts messages.
-// Shuts down when the cancellation_token is triggered.
-async fn connect_to_ais_stream_with_broadcast(
-    tx: broadcast::Sender<AisResponse>,
-    cancellation_token: CancellationToken,
-) {
-    loop {
-        tokio::select! {
-            // Check if the task has been cancelled.
-            _ = cancellation_token.cancelled() => {
-                println!("Cancellation signal received. Shutting down AIS stream connection.");
-                return;
-            }
-            // Try to connect and process messages.
-            result = connect_and_process_ais_stream(&tx, &cancellation_token) => {
-                if let Err(e) = result {
-                    eprintln!("AIS stream error: {}. Reconnecting in 5 seconds...", e);
-                }
-                 // If the connection drops, wait before retrying, but still listen for cancellation.
-                tokio::select! {
-                    _ = tokio::time::sleep(tokio::time::Duration::from_secs(5)) => {},
-                    _ = cancellation_token.cancelled() => {
-                         println!("Cancellation signal received during reconnect wait. Shutting down.");
-                        return;
-                    }
-                }
-            }
-        }
-    }
-}
-
-
-async fn connect_and_process_ais_stream(
-    tx: &broadcast::Sender<AisResponse>,
-    cancellation_token: &CancellationToken
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> { // <--- THE FIX IS HERE
-
-    let url = Url::parse("wss://stream.aisstream.io/v0/stream")?;
-    let (ws_stream, _) = connect_async(url).await.map_err(|e| format!("WebSocket connection failed: {}", e))?;
-    println!("Upstream WebSocket connection to aisstream.io opened.");
-
-    let (mut sender, mut receiver) = ws_stream.split();
-
-    let key = "MDc4YzY5NTdkMGUwM2UzMzQ1Zjc5NDFmOTA1ODg4ZTMyOGQ0MjM0MA==";
-    let subscription_message = SubscriptionMessage {
-        apikey: STANDARD.decode(key)
-            .ok()
-            .and_then(|bytes| String::from_utf8(bytes).ok())
-            .unwrap_or_default(),
-        bounding_boxes: vec![vec![[-90.0, -180.0], [90.0, 180.0]]], // Global coverage
-        filters_ship_mmsi: vec![],
-    };
-
-    let message_json = serde_json::to_string(&subscription_message)?;
-    sender.send(Message::Text(message_json)).await?;
-    println!("Upstream subscription message sent.");
-
-    loop {
-        tokio::select! {
-            // Forward messages from upstream
-            message = receiver.next() => {
-                match message {
-                    Some(Ok(msg)) => {
-                        if process_upstream_message(msg, tx).is_err() {
-                            // If there's a critical error processing, break to reconnect
-                            break;
-                        }
-                    },
-                    Some(Err(e)) => {
-                        eprintln!("Upstream WebSocket error: {}", e);
-                        return Err(e.into());
-                    },
-                    None => {
-                        println!("Upstream WebSocket connection closed.");
-                        return Ok(()); // Connection closed normally
-                    }
-                }
-            }
-            // Listen for the shutdown signal
-            _ = cancellation_token.cancelled() => {
-                println!("Closing upstream WebSocket connection due to cancellation.");
-                 let _ = sender.send(Message::Close(None)).await;
-                return Ok(());
-            }
-        }
-    }
-    Ok(())
-}
-
-fn process_upstream_message(
-    msg: Message,
-    tx: &broadcast::Sender<AisResponse>,
-) -> Result<(), ()> {
-    let text = match msg {
-        Message::Text(text) => text,
-        Message::Binary(data) => String::from_utf8_lossy(&data).to_string(),
-        Message::Ping(_) | Message::Pong(_) | Message::Close(_) => return Ok(()),
-        Message::Frame(_) => return Ok(()),
-    };
-
-    if let Ok(ais_message) = serde_json::from_str::<Value>(&text) {
-        let parsed_message = parse_ais_message(&ais_message);
-        // The broadcast send will fail if there are no receivers, which is fine.
-        let _ = tx.send(parsed_message);
-    } else {
-        eprintln!("Failed to parse JSON from upstream: {}", text);
-    }
-    Ok(())
-}
-
-
 // Graceful shutdown signal handler
 pub async fn shutdown_signal() {
     let ctrl_c = async {
@@ -716,8 +1037,26 @@ pub async fn shutdown_signal() {
 mod tests {
     use super::*;
     use crate::create_router;
+    use crate::upstream::aisstream_response;
     use axum_test::TestServer;
+    use futures_util::SinkExt;
     use serde_json::json;
+    use std::time::SystemTime;
+    use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+
+    fn test_manager() -> AisStreamManager {
+        AisStreamManager::new(vec![Upstream::AisStream { api_key: "test-key".to_string() }])
+    }
+
+    fn position_report(mmsi: &str, latitude: f64, longitude: f64) -> AisResponse {
+        let user_id: u32 = mmsi.parse().unwrap();
+        aisstream_response(&json!({
+            "MessageType": "PositionReport",
+            "MetaData": {"MMSI": user_id},
+            "Message": {"PositionReport": {"UserID": user_id, "Latitude": latitude, "Longitude": longitude}}
+        }), SystemTime::now())
+        .unwrap()
+    }
 
     #[test]
     fn test_get_ship_type_description() {
@@ -731,90 +1070,21 @@ mod tests {
         assert_eq!(get_ship_type_description(999), "Unknown");
     }
 
-    #[test]
-    fn test_parse_ais_message_position_report() {
-        let ais_message = json!({
-            "MessageType": "PositionReport",
-            "MetaData": {
-                "MMSI": "123456789",
-                "ShipName": "TEST VESSEL",
-                "latitude": 33.7,
-                "longitude": -118.3,
-                "time_utc": "2023-01-01T12:00:00Z"
-            },
-            "Message": {
-                "PositionReport": {
-                    "Sog": 12.5,
-                    "Cog": 180.0,
-                    "TrueHeading": 175.0,
-                    "NavigationalStatus": 0
-                }
-            }
-        });
-
-        let result = parse_ais_message(&ais_message);
-
-        assert_eq!(result.message_type, Some("PositionReport".to_string()));
-        assert_eq!(result.mmsi, Some("123456789".to_string()));
-        assert_eq!(result.ship_name, Some("TEST VESSEL".to_string()));
-        assert_eq!(result.latitude, Some(33.7));
-        assert_eq!(result.longitude, Some(-118.3));
-        assert_eq!(result.timestamp, Some("2023-01-01T12:00:00Z".to_string()));
-        assert_eq!(result.speed_over_ground, Some(12.5));
-        assert_eq!(result.course_over_ground, Some(180.0));
-        assert_eq!(result.heading, Some(175.0));
-        assert_eq!(result.navigation_status, Some("Under way using engine".to_string()));
-    }
-
-    #[test]
-    fn test_parse_ais_message_static_data() {
-        let ais_message = json!({
-            "MessageType": "StaticDataReport",
-            "MetaData": {
-                "MMSI": "987654321",
-                "ShipName": "CARGO SHIP",
-                "latitude": 34.0,
-                "longitude": -118.0,
-                "time_utc": "2023-01-01T13:00:00Z"
-            },
-            "Message": {
-                "StaticDataReport": {
-                    "ReportB": {
-                        "ShipType": 70
-                    }
-                }
-            }
-        });
-
-        let result = parse_ais_message(&ais_message);
-
-        assert_eq!(result.message_type, Some("StaticDataReport".to_string()));
-        assert_eq!(result.mmsi, Some("987654321".to_string()));
-        assert_eq!(result.ship_name, Some("CARGO SHIP".to_string()));
-        assert_eq!(result.ship_type, Some("Cargo".to_string()));
-    }
-
-    #[test]
-    fn test_parse_ais_message_empty() {
-        let ais_message = json!({});
-        let result = parse_ais_message(&ais_message);
-
-        assert_eq!(result.message_type, None);
-        assert_eq!(result.mmsi, None);
-        assert_eq!(result.ship_name, None);
-        assert_eq!(result.latitude, None);
-        assert_eq!(result.longitude, None);
-    }
-
     #[tokio::test]
     async fn test_get_ais_data_endpoint() {
-        // Create test state
-        let state = AppState {
-            ais_stream_manager: Arc::new(AisStreamManager::new()),
+        let manager = test_manager();
+        let (tx, _rx) = broadcast::channel(4);
+        let merger = UpstreamMerger::new(tx, None, Arc::default(), Arc::default()).with_index(manager.positions.clone());
+        let report = |mmsi: &str, latitude: f64| {
+            let mut report = position_report(mmsi, latitude, -118.2);
+            report.ship_name = Some("SEA BREEZE".to_string());
+            report
         };
+        merger.publish(report("111111111", 40.0), "aisstream");
+        merger.publish(report("222222222", 33.7), "aisstream");
 
         // Create test server
-        let app = create_router(state);
+        let app = create_router(AppState { ais_stream_manager: Arc::new(manager) });
         let server = TestServer::new(app).unwrap();
 
         // Test valid bounding box request
@@ -830,16 +1100,26 @@ mod tests {
 
         let json_response: Vec<AisResponse> = response.json();
         assert_eq!(json_response.len(), 1);
-        assert_eq!(json_response[0].ship_name, Some("Bounding Box Query Received".to_string()));
-        assert_eq!(json_response[0].latitude, Some(33.75)); // Average of sw_lat and ne_lat
-        assert_eq!(json_response[0].longitude, Some(-118.25)); // Average of sw_lon and ne_lon
+        assert_eq!(json_response[0].mmsi, Some("222222222".to_string()));
+        assert_eq!(json_response[0].ship_name, Some("SEA BREEZE".to_string()));
+        assert_eq!(json_response[0].latitude, Some(33.7));
+
+        // Corners the wrong way round
+        let response = server
+            .get("/ais")
+            .add_query_param("sw_lat", "33.9")
+            .add_query_param("sw_lon", "-118.5")
+            .add_query_param("ne_lat", "33.6")
+            .add_query_param("ne_lon", "-118.0")
+            .await;
+        response.assert_status_bad_request();
     }
 
     #[tokio::test]
     async fn test_get_ais_data_endpoint_missing_params() {
         // Create test state
         let state = AppState {
-            ais_stream_manager: Arc::new(AisStreamManager::new()),
+            ais_stream_manager: Arc::new(test_manager()),
         };
 
         // Create test server
@@ -861,7 +1141,7 @@ mod tests {
     async fn test_get_ais_data_endpoint_invalid_params() {
         // Create test state
         let state = AppState {
-            ais_stream_manager: Arc::new(AisStreamManager::new()),
+            ais_stream_manager: Arc::new(test_manager()),
         };
 
         // Create test server
@@ -909,6 +1189,10 @@ mod tests {
             heading: Some(85.0),
             navigation_status: Some("Under way using engine".to_string()),
             ship_type: Some("Cargo".to_string()),
+            callsign: None,
+            dimensions: None,
+            own_ship: false,
+            source: None,
             raw_message: json!({"test": "data"}),
         };
 
@@ -925,37 +1209,17 @@ mod tests {
     #[tokio::test]
     async fn test_app_state_creation() {
         let state = AppState {
-            ais_stream_manager: Arc::new(AisStreamManager::new()),
+            ais_stream_manager: Arc::new(test_manager()),
         };
         // Test that the manager is accessible.
         assert_eq!(state.ais_stream_manager.state.lock().await.client_count, 0);
     }
 
-    #[test]
-    fn test_subscription_message_serialization() {
-        let subscription = SubscriptionMessage {
-            apikey: "test_key".to_string(),
-            bounding_boxes: vec![vec![
-                [33.6, -118.5],
-                [33.9, -118.0]
-            ]],
-            filters_ship_mmsi: vec!["123456789".to_string()],
-        };
-
-        let json_result = serde_json::to_string(&subscription);
-        assert!(json_result.is_ok());
-
-        let json_string = json_result.unwrap();
-        assert!(json_string.contains("Apikey"));
-        assert!(json_string.contains("BoundingBoxes"));
-        assert!(json_string.contains("FiltersShipMMSI"));
-    }
-
     #[tokio::test]
     async fn test_websocket_endpoint_exists() {
         // Create test state
         let state = AppState {
-            ais_stream_manager: Arc::new(AisStreamManager::new()),
+            ais_stream_manager: Arc::new(test_manager()),
         };
 
         // Create test server
@@ -966,6 +1230,163 @@ mod tests {
         // when accessed via HTTP GET without proper websocket headers
         let response = server.get("/ws").await;
         response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        server.get("/ws/map").await.assert_status(axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_websocket_backfills_known_positions() {
+        let manager = Arc::new(AisStreamManager::new(Vec::new()));
+        for (mmsi, latitude) in [("111111111", 40.0), ("222222222", 33.7)] {
+            manager.positions.update(&position_report(mmsi, latitude, -118.2));
+        }
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let app = create_router(AppState { ais_stream_manager: manager });
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let (mut ws, _) = connect_async(format!("ws://{}/ws", address)).await.unwrap();
+        assert_eq!(ws.next().await.unwrap().unwrap(), Message::Text("Connected to AIS stream".to_string()));
+        let set_bbox = json!({
+            "type": "set_bounding_box",
+            "bounding_box": {"sw_lat": 33.6, "sw_lon": -118.5, "ne_lat": 33.9, "ne_lon": -118.0}
+        });
+        ws.send(Message::Text(set_bbox.to_string())).await.unwrap();
+        let Message::Text(text) = ws.next().await.unwrap().unwrap() else {
+            panic!("reports are JSON text by default");
+        };
+        let backfilled: AisResponse = serde_json::from_str(&text).unwrap();
+        assert_eq!(backfilled.mmsi.as_deref(), Some("222222222"));
+
+        // A named area added later backfills too, tagged with its name
+        let add_area = json!({
+            "type": "add_area",
+            "area": {"name": "offshore", "bounding_box": {"sw_lat": 39.5, "sw_lon": -118.5, "ne_lat": 40.5, "ne_lon": -118.0}}
+        });
+        ws.send(Message::Text(add_area.to_string())).await.unwrap();
+        let Message::Text(text) = ws.next().await.unwrap().unwrap() else {
+            panic!("reports are JSON text by default");
+        };
+        let tagged: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(tagged["mmsi"], "111111111");
+        assert_eq!(tagged["areas"], json!(["offshore"]));
+        ws.close(None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_websocket_requires_configured_token() {
+        use crate::access::AccessPolicy;
+
+        let access = AccessPolicy {
+            tokens: ["s3cret".to_string()].into(),
+            ..AccessPolicy::default()
+        };
+        let state = AppState {
+            ais_stream_manager: Arc::new(test_manager().with_access(access)),
+        };
+        let server = TestServer::new(create_router(state)).unwrap();
+
+        server.get("/ws").await.assert_status(StatusCode::UNAUTHORIZED);
+        server.get("/ws/map").add_query_param("token", "guess").await.assert_status(StatusCode::UNAUTHORIZED);
+        // Past the token check, a plain GET fails the WebSocket upgrade instead
+        server.get("/ws").add_query_param("token", "s3cret").await.assert_status_bad_request();
+        server
+            .get("/ws/map")
+            .add_header(axum::http::header::AUTHORIZATION, axum::http::HeaderValue::from_static("Bearer s3cret"))
+            .await
+            .assert_status_bad_request();
+    }
+
+    #[tokio::test]
+    async fn test_vessel_data_requires_configured_token() {
+        use crate::access::AccessPolicy;
+
+        let access = AccessPolicy {
+            tokens: ["s3cret".to_string()].into(),
+            ..AccessPolicy::default()
+        };
+        let state = AppState {
+            ais_stream_manager: Arc::new(test_manager().with_access(access)),
+        };
+        let server = TestServer::new(create_router(state)).unwrap();
+
+        let bbox = [("sw_lat", "33.6"), ("sw_lon", "-118.5"), ("ne_lat", "33.9"), ("ne_lon", "-118.0")];
+        server.get("/ais").add_query_params(bbox).await.assert_status(StatusCode::UNAUTHORIZED);
+        server.get("/ais/history").await.assert_status(StatusCode::UNAUTHORIZED);
+        server.get("/vessels/366123456/track").await.assert_status(StatusCode::UNAUTHORIZED);
+        // With the token the request gets through, to a server without history
+        server.get("/ais/history").add_query_param("token", "s3cret").await.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        server.get("/ais").add_query_params(bbox).add_query_param("token", "s3cret").await.assert_status_ok();
+        // Health and metrics stay open
+        server.get("/status").await.assert_status_ok();
+        server.get("/metrics").await.assert_status_ok();
+    }
+
+    #[test]
+    fn test_report_frames_in_negotiated_format() {
+        let data = aisstream_response(&json!({
+            "MessageType": "PositionReport",
+            "MetaData": {"MMSI": 366123456, "ShipName": "SEA BREEZE", "latitude": 37.8, "longitude": -122.4},
+            "Message": {"PositionReport": {"UserID": 366123456, "Latitude": 37.8, "Longitude": -122.4, "Sog": 6.5, "Cog": 270.0}}
+        }), SystemTime::now())
+        .unwrap();
+
+        let Some(WsMessage::Text(text)) = ReportFormat::Json.frame(&data, &[]) else {
+            panic!("JSON reports go out as text");
+        };
+        assert_eq!(serde_json::from_str::<Value>(&text).unwrap()["mmsi"], "366123456");
+        let Some(WsMessage::Binary(bytes)) = ReportFormat::Protobuf.frame(&data, &["harbor".to_string()]) else {
+            panic!("protobuf reports go out as binary");
+        };
+        assert!(bytes.len() < text.len() / 4, "{} bytes against {}", bytes.len(), text.len());
+        let report = crate::grpc::VesselReport::decode(bytes.as_slice()).unwrap();
+        assert_eq!(report.mmsi, "366123456");
+        assert_eq!(report.ship_name.as_deref(), Some("SEA BREEZE"));
+        assert_eq!(report.speed_over_ground, Some(6.5));
+        assert_eq!(report.areas, vec!["harbor"]);
+    }
+
+    #[test]
+    fn test_map_feed_keeps_latest_state_per_vessel() {
+        let position = |mmsi: &str, latitude: f64| AisResponse {
+            message_type: Some("PositionReport".to_string()),
+            mmsi: Some(mmsi.to_string()),
+            ship_name: None,
+            latitude: Some(latitude),
+            longitude: Some(-118.5),
+            timestamp: None,
+            speed_over_ground: Some(10.0),
+            course_over_ground: None,
+            heading: None,
+            navigation_status: None,
+            ship_type: None,
+            callsign: None,
+            dimensions: None,
+            own_ship: false,
+            source: None,
+            raw_message: serde_json::json!({}),
+        };
+
+        let mut feed = MapFeed::default();
+        feed.push(AisResponse {
+            message_type: Some("ShipStaticData".to_string()),
+            ship_name: Some("Test Ship".to_string()),
+            latitude: None,
+            speed_over_ground: None,
+            ..position("123456789", 0.0)
+        });
+        for latitude in [33.1, 33.2, 33.3] {
+            feed.push(position("123456789", latitude));
+        }
+        feed.push(position("987654321", 34.0));
+        feed.push(AisResponse { mmsi: None, ..position("", 35.0) });
+
+        let mut sent = feed.drain();
+        sent.sort_by(|a, b| a.mmsi.cmp(&b.mmsi));
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].latitude, Some(33.3));
+        assert_eq!(sent[0].ship_name.as_deref(), Some("Test Ship"));
+        assert_eq!(sent[0].message_type.as_deref(), Some("PositionReport"));
+        assert!(feed.drain().is_empty());
     }
 
     #[test]
@@ -990,6 +1411,10 @@ mod tests {
             heading: Some(85.0),
             navigation_status: Some("Under way using engine".to_string()),
             ship_type: Some("Cargo".to_string()),
+            callsign: None,
+            dimensions: None,
+            own_ship: false,
+            source: None,
             raw_message: serde_json::json!({"test": "data"}),
         };
 
@@ -1004,4 +1429,257 @@ mod tests {
 
         assert!(!is_within_bounding_box(&ais_outside_lat, &bbox));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_own_ship_reports_flagged_in_stream() {
+        let (tx, mut rx) = broadcast::channel(4);
+        let report = |mmsi: &str| position_report(mmsi, 37.8, -122.4);
+
+        let merger = UpstreamMerger::new(tx.clone(), Some("211234560".to_string()), Arc::default(), Arc::default());
+        merger.publish(report("211234560"), "aisstream");
+        merger.publish(report("987654321"), "aisstream");
+        UpstreamMerger::new(tx, None, Arc::default(), Arc::default()).publish(report("211234560"), "aisstream");
+
+        let own = rx.try_recv().unwrap();
+        assert!(own.own_ship);
+        assert_eq!(own.source.as_deref(), Some("aisstream"));
+        assert!(!rx.try_recv().unwrap().own_ship);
+        assert!(!rx.try_recv().unwrap().own_ship);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint() {
+        let manager = test_manager();
+        let (tx, _rx) = broadcast::channel(4);
+        let merger = UpstreamMerger::new(tx, None, Arc::default(), manager.metrics.clone());
+        let mut report = position_report("211234560", 37.8, -122.4);
+        report.timestamp = Some("2022-12-29 18:22:32.318353 +0000 UTC".to_string());
+        merger.publish(report.clone(), "aisstream");
+        merger.publish(report, "tcp://10.0.0.5:10110");
+        manager.metrics.reconnected("aisstream");
+
+        let server = TestServer::new(create_router(AppState { ais_stream_manager: Arc::new(manager) })).unwrap();
+        let response = server.get("/metrics").await;
+        response.assert_status_ok();
+        assert!(response.header("content-type").to_str().unwrap().starts_with("text/plain; version=0.0.4"));
+        let text = response.text();
+        for line in [
+            "ais_upstream_messages_total{upstream=\"aisstream\"} 1",
+            "ais_upstream_messages_total{upstream=\"tcp://10.0.0.5:10110\"} 1",
+            "ais_upstream_reconnects_total{upstream=\"aisstream\"} 1",
+            "ais_messages_broadcast_total 1",
+            "ais_messages_duplicate_total 1",
+            "ais_clients_connected 0",
+            "ais_stream_running 0",
+            "# TYPE ais_broadcast_queue_depth gauge",
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing {:?} in\n{}", line, text);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_status_endpoint() {
+        let manager = AisStreamManager::new(vec![
+            Upstream::AisStream { api_key: "test-key".to_string() },
+            Upstream::NmeaTcp { address: "10.0.0.5:10110".to_string() },
+        ]);
+        let (tx, _rx) = broadcast::channel(4);
+        let merger = UpstreamMerger::new(tx, None, Arc::default(), manager.metrics.clone());
+        merger.set_connected("tcp://10.0.0.5:10110", true);
+        merger.publish(position_report("211234560", 37.8, -122.4), "tcp://10.0.0.5:10110");
+
+        let server = TestServer::new(create_router(AppState { ais_stream_manager: Arc::new(manager) })).unwrap();
+        let status: Value = server.get("/status").await.json();
+        assert_eq!(status["stream_running"], false);
+        assert_eq!(status["clients"], 0);
+        assert!(status["last_message_at"].as_i64().unwrap() > 0);
+        let upstreams = status["upstreams"].as_array().unwrap();
+        assert_eq!(upstreams.len(), 2);
+        assert_eq!(upstreams[0]["name"], "aisstream");
+        assert_eq!(upstreams[0]["connected"], false);
+        assert_eq!(upstreams[0]["last_message_at"], Value::Null);
+        assert_eq!(upstreams[1]["connected"], true);
+        assert_eq!(upstreams[1]["messages"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_collision_check_endpoint() {
+        let manager = test_manager();
+        let (tx, _rx) = broadcast::channel(4);
+        let merger = UpstreamMerger::new(tx, None, Arc::default(), Arc::default()).with_traffic(manager.traffic.clone());
+        // Drifting a mile north of the own ship below
+        let report = json!({
+            "MessageType": "PositionReport",
+            "MetaData": {"MMSI": 211234560, "ShipName": "DRIFTER"},
+            "Message": {"PositionReport": {"UserID": 211234560, "Latitude": 37.016667, "Longitude": -122.0, "Sog": 0.0, "Cog": 0.0}}
+        });
+        merger.publish(aisstream_response(&report, SystemTime::now()).unwrap(), "aisstream");
+
+        let server = TestServer::new(create_router(AppState { ais_stream_manager: Arc::new(manager) })).unwrap();
+        let own_ship = json!({"latitude": 37.0, "longitude": -122.0, "speed_over_ground": 10.0, "course_over_ground": 0.0});
+        let alerts: Value = server.post("/ais/collisions").json(&own_ship).await.json();
+        assert_eq!(alerts[0]["type"], "collision_alert");
+        assert_eq!(alerts[0]["ship_name"], "DRIFTER");
+        assert!((alerts[0]["tcpa_min"].as_f64().unwrap() - 6.0).abs() < 0.1);
+
+        let response = server.post("/ais/collisions").json(&json!({"latitude": 95.0, "longitude": 0.0})).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_sse_stream_filtered_by_bounding_box() {
+        let server = TestServer::new(create_router(AppState { ais_stream_manager: Arc::new(test_manager()) })).unwrap();
+        let partial = server.get("/ais/stream").add_query_param("sw_lat", "33.6").await;
+        partial.assert_status(StatusCode::BAD_REQUEST);
+
+        // No upstreams, so the test feeds the broadcast itself
+        let manager = Arc::new(AisStreamManager::new(Vec::new()));
+        let slot = manager.admit_client().unwrap();
+        let mut subscription = Subscription::default();
+        subscription.bounding_box = WebSocketBoundingBox::parse("33.6,-118.5,33.9,-118.0");
+        let mut reports = Box::pin(client_reports(manager.clone(), subscription, slot).await);
+        let tx = manager.state.lock().await.tx.clone().unwrap();
+        tx.send(position_report("111111111", 40.0, -118.2)).unwrap();
+        tx.send(position_report("222222222", 33.7, -118.2)).unwrap();
+        assert_eq!(reports.next().await.unwrap().mmsi.as_deref(), Some("222222222"));
+        assert_eq!(manager.clients.in_use(), 1);
+        drop(reports);
+        assert_eq!(manager.clients.in_use(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_history_endpoint_pages_stored_positions() {
+        use crate::store::{AisStore, StoredAisMessage};
+
+        let no_store = TestServer::new(create_router(AppState {
+            ais_stream_manager: Arc::new(test_manager()),
+        }))
+        .unwrap();
+        no_store.get("/ais/history").await.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+
+        let path = std::env::temp_dir().join(format!("ais-history-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let store = AisStore::connect(&format!("sqlite://{}?mode=rwc", path.display())).await.unwrap();
+        let position = |received_at: i64, mmsi: &str, latitude: Option<f64>| StoredAisMessage {
+            received_at,
+            message: AisResponse {
+                message_type: Some("PositionReport".to_string()),
+                mmsi: Some(mmsi.to_string()),
+                ship_name: None,
+                latitude,
+                longitude: Some(-118.2),
+                timestamp: None,
+                speed_over_ground: None,
+                course_over_ground: None,
+                heading: None,
+                navigation_status: None,
+                ship_type: None,
+                callsign: None,
+                dimensions: None,
+                own_ship: false,
+                source: None,
+                raw_message: json!({}),
+            },
+        };
+        store
+            .insert(&[
+                position(1_000, "123456789", Some(33.70)),
+                position(2_000, "987654321", Some(33.75)),
+                position(3_000, "123456789", None),
+                position(4_000, "123456789", Some(33.80)),
+                position(5_000, "123456789", Some(35.00)),
+            ])
+            .await
+            .unwrap();
+
+        let server = TestServer::new(create_router(AppState {
+            ais_stream_manager: Arc::new(test_manager().with_store(Some(store))),
+        }))
+        .unwrap();
+        let page = |offset: &str| {
+            server
+                .get("/ais/history")
+                .add_query_param("mmsi", "123456789")
+                .add_query_param("from", "0")
+                .add_query_param("to", "10000")
+                .add_query_param("bbox", "33.0,-119.0,34.0,-118.0")
+                .add_query_param("limit", "1")
+                .add_query_param("offset", offset)
+        };
+
+        let first: Value = page("0").await.json();
+        assert_eq!(first["positions"][0]["received_at"], 1_000);
+        assert_eq!(first["positions"][0]["latitude"], 33.70);
+        assert_eq!(first["next_offset"], 1);
+        // The report without a position and the one outside the box are skipped
+        let last: Value = page("1").await.json();
+        assert_eq!(last["positions"][0]["received_at"], 4_000);
+        assert_eq!(last["next_offset"], Value::Null);
+
+        server.get("/ais/history").add_query_param("bbox", "north").await.assert_status_bad_request();
+        server
+            .get("/ais/history")
+            .add_query_param("from", "5000")
+            .add_query_param("to", "1000")
+            .await
+            .assert_status_bad_request();
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_vessel_track_is_simplified() {
+        use crate::store::{AisStore, StoredAisMessage};
+
+        let path = std::env::temp_dir().join(format!("ais-track-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let store = AisStore::connect(&format!("sqlite://{}?mode=rwc", path.display())).await.unwrap();
+        // Due north along a meridian, then a turn east
+        let fix = |received_at: i64, latitude: f64, longitude: f64| StoredAisMessage {
+            received_at,
+            message: AisResponse {
+                message_type: Some("PositionReport".to_string()),
+                mmsi: Some("123456789".to_string()),
+                ship_name: None,
+                latitude: Some(latitude),
+                longitude: Some(longitude),
+                timestamp: None,
+                speed_over_ground: None,
+                course_over_ground: None,
+                heading: None,
+                navigation_status: None,
+                ship_type: None,
+                callsign: None,
+                dimensions: None,
+                own_ship: false,
+                source: None,
+                raw_message: json!({}),
+            },
+        };
+        let fixes: Vec<_> = (0..5)
+            .map(|i| fix(i * 1_000, 33.70 + i as f64 * 0.01, -118.2))
+            .chain((1..4).map(|i| fix(4_000 + i * 1_000, 33.74, -118.2 + i as f64 * 0.01)))
+            .collect();
+        store.insert(&fixes).await.unwrap();
+
+        let server = TestServer::new(create_router(AppState {
+            ais_stream_manager: Arc::new(test_manager().with_store(Some(store))),
+        }))
+        .unwrap();
+        let track: Value = server
+            .get("/vessels/123456789/track")
+            .add_query_param("from", "0")
+            .add_query_param("to", "100000")
+            .await
+            .json();
+        assert_eq!(track["fix_count"], 8);
+        let corners: Vec<_> = track["points"].as_array().unwrap().iter().map(|point| point["received_at"].clone()).collect();
+        assert_eq!(corners, vec![json!(0), json!(4_000), json!(7_000)]);
+
+        server
+            .get("/vessels/123456789/track")
+            .add_query_param("tolerance_m", "-1")
+            .await
+            .assert_status_bad_request();
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,304 @@
+use crate::ais::AisResponse;
+use datalink::geo::{closest_approach, destination, METERS_PER_NM};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Closest approach that raises an alert unless the client sets its own, in nautical miles
+pub const DEFAULT_CPA_NM: f64 = 0.5;
+
+/// How far ahead an approach raises an alert unless the client sets its own, in minutes
+pub const DEFAULT_TCPA_MIN: f64 = 12.0;
+
+/// Vessels not heard from for this long are no longer checked
+pub const TARGET_TIMEOUT: Duration = Duration::from_secs(6 * 60);
+
+/// Least time between alerts about the same vessel while it stays a threat
+pub const ALERT_REPEAT: Duration = Duration::from_secs(60);
+
+/// Vessels tracked before stale entries are swept
+const SWEEP_THRESHOLD: usize = 20_000;
+
+// Where a client's own ship is and the approach it wants to hear about,
+// as sent to POST /ais/collisions or in a `set_own_ship` message.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct OwnShipState {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Knots; stationary when left out
+    #[serde(default)]
+    pub speed_over_ground: f64,
+    /// Degrees true
+    #[serde(default)]
+    pub course_over_ground: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpa_nm: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tcpa_min: Option<f64>,
+}
+
+// A vessel taken to hold its course and speed over ground from `at`.
+#[derive(Debug, Clone, Copy)]
+struct Motion {
+    position: (f64, f64),
+    speed_kts: f64,
+    course_deg: f64,
+    at: Instant,
+}
+
+impl Motion {
+    // The motion a position report gives. AIS sends 102.3 kn and 360° for
+    // "not available"; the vessel is then taken to be stopped.
+    fn from_report(report: &AisResponse, at: Instant) -> Option<Self> {
+        let speed_kts = report.speed_over_ground.filter(|speed| (0.0..102.3).contains(speed));
+        let course_deg = report.course_over_ground.filter(|course| (0.0..360.0).contains(course));
+        Some(Self {
+            position: (report.latitude?, report.longitude?),
+            speed_kts: course_deg.and(speed_kts).unwrap_or(0.0),
+            course_deg: course_deg.unwrap_or(0.0),
+            at,
+        })
+    }
+
+    fn position_at(&self, now: Instant) -> (f64, f64) {
+        let hours = now.saturating_duration_since(self.at).as_secs_f64() / 3600.0;
+        destination(self.position, self.course_deg, self.speed_kts * hours * METERS_PER_NM)
+    }
+}
+
+// A vessel coming within the client's thresholds.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CollisionAlert {
+    #[serde(rename = "type")]
+    pub message_type: String,
+    pub mmsi: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ship_name: Option<String>,
+    /// Where the vessel is now, dead reckoned from its last report
+    pub latitude: f64,
+    pub longitude: f64,
+    pub range_nm: f64,
+    pub cpa_nm: f64,
+    /// Minutes until the closest point of approach
+    pub tcpa_min: f64,
+}
+
+// A client's own ship, dead reckoned from where it last said it was.
+#[derive(Debug, Clone, Copy)]
+pub struct OwnShip {
+    motion: Motion,
+    cpa_nm: f64,
+    tcpa_min: f64,
+}
+
+impl OwnShip {
+    // None when the position or a threshold is out of range.
+    pub fn new(state: &OwnShipState, now: Instant) -> Option<Self> {
+        let cpa_nm = state.cpa_nm.unwrap_or(DEFAULT_CPA_NM);
+        let tcpa_min = state.tcpa_min.unwrap_or(DEFAULT_TCPA_MIN);
+        let valid = (-90.0..=90.0).contains(&state.latitude)
+            && (-180.0..=180.0).contains(&state.longitude)
+            && state.speed_over_ground.is_finite()
+            && state.speed_over_ground >= 0.0
+            && state.course_over_ground.is_finite()
+            && cpa_nm > 0.0
+            && tcpa_min > 0.0;
+        valid.then_some(Self {
+            motion: Motion {
+                position: (state.latitude, state.longitude),
+                speed_kts: state.speed_over_ground,
+                course_deg: state.course_over_ground.rem_euclid(360.0),
+                at: now,
+            },
+            cpa_nm,
+            tcpa_min,
+        })
+    }
+
+    // An alert if `target` will pass within the thresholds. Only closing
+    // vessels are a threat; one already past its CPA is not.
+    fn check(&self, mmsi: &str, ship_name: Option<&str>, target: &Motion, now: Instant) -> Option<CollisionAlert> {
+        let target_position = target.position_at(now);
+        let approach = closest_approach(
+            self.motion.position_at(now),
+            (self.motion.speed_kts, self.motion.course_deg),
+            target_position,
+            (target.speed_kts, target.course_deg),
+        );
+        if approach.tcpa_min <= 0.0 || approach.tcpa_min > self.tcpa_min || approach.cpa_nm > self.cpa_nm {
+            return None;
+        }
+        Some(CollisionAlert {
+            message_type: "collision_alert".to_string(),
+            mmsi: mmsi.to_string(),
+            ship_name: ship_name.map(str::to_string),
+            latitude: target_position.0,
+            longitude: target_position.1,
+            range_nm: approach.range_nm,
+            cpa_nm: approach.cpa_nm,
+            tcpa_min: approach.tcpa_min,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Target {
+    motion: Motion,
+    ship_name: Option<String>,
+}
+
+// The latest motion of every vessel heard on the stream, for checking a
+// newly registered own ship against all of them at once.
+#[derive(Debug, Default)]
+pub struct Traffic {
+    targets: Mutex<HashMap<String, Target>>,
+}
+
+impl Traffic {
+    pub fn observe(&self, report: &AisResponse) {
+        let now = Instant::now();
+        let (Some(mmsi), Some(motion)) = (&report.mmsi, Motion::from_report(report, now)) else {
+            return;
+        };
+        if report.own_ship {
+            return;
+        }
+        let mut targets = self.targets.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if targets.len() >= SWEEP_THRESHOLD && !targets.contains_key(mmsi) {
+            targets.retain(|_, target| now.duration_since(target.motion.at) < TARGET_TIMEOUT);
+        }
+        targets.insert(
+            mmsi.clone(),
+            Target {
+                motion,
+                ship_name: report.ship_name.clone(),
+            },
+        );
+    }
+
+    // Alerts for every recently heard vessel within `own`'s thresholds,
+    // soonest first.
+    pub fn alerts(&self, own: &OwnShip, now: Instant) -> Vec<CollisionAlert> {
+        let targets = self.targets.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut alerts: Vec<CollisionAlert> = targets
+            .iter()
+            .filter(|(_, target)| now.saturating_duration_since(target.motion.at) < TARGET_TIMEOUT)
+            .filter_map(|(mmsi, target)| own.check(mmsi, target.ship_name.as_deref(), &target.motion, now))
+            .collect();
+        alerts.sort_by(|a, b| a.tcpa_min.total_cmp(&b.tcpa_min));
+        alerts
+    }
+}
+
+// One WebSocket client's own ship and the vessels it was last warned
+// about, so a lasting threat is repeated every ALERT_REPEAT rather than
+// with every report.
+#[derive(Debug, Default)]
+pub struct CollisionWatch {
+    own: Option<OwnShip>,
+    alerted: HashMap<String, Instant>,
+}
+
+impl CollisionWatch {
+    // Register (or clear) the own ship; returns the alerts already due.
+    pub fn set_own_ship(&mut self, own: Option<OwnShip>, traffic: &Traffic, now: Instant) -> Vec<CollisionAlert> {
+        self.own = own;
+        self.alerted.clear();
+        let Some(own) = &self.own else {
+            return Vec::new();
+        };
+        let alerts = traffic.alerts(own, now);
+        for alert in &alerts {
+            self.alerted.insert(alert.mmsi.clone(), now);
+        }
+        alerts
+    }
+
+    // Check the vessel `report` is about against the own ship.
+    pub fn check(&mut self, report: &AisResponse, now: Instant) -> Option<CollisionAlert> {
+        let own = self.own.as_ref()?;
+        let mmsi = report.mmsi.as_deref()?;
+        if report.own_ship {
+            return None;
+        }
+        let motion = Motion::from_report(report, now)?;
+        let Some(alert) = own.check(mmsi, report.ship_name.as_deref(), &motion, now) else {
+            self.alerted.remove(mmsi);
+            return None;
+        };
+        if self.alerted.get(mmsi).is_some_and(|last| now.duration_since(*last) < ALERT_REPEAT) {
+            return None;
+        }
+        self.alerted.insert(mmsi.to_string(), now);
+        Some(alert)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(mmsi: &str, latitude: f64, longitude: f64, speed: f64, course: f64) -> AisResponse {
+        AisResponse {
+            message_type: Some("PositionReport".to_string()),
+            mmsi: Some(mmsi.to_string()),
+            ship_name: None,
+            latitude: Some(latitude),
+            longitude: Some(longitude),
+            timestamp: None,
+            speed_over_ground: Some(speed),
+            course_over_ground: Some(course),
+            heading: None,
+            navigation_status: None,
+            ship_type: None,
+            callsign: None,
+            dimensions: None,
+            own_ship: false,
+            source: None,
+            raw_message: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_crossing_vessel_raises_collision_alert() {
+        let now = Instant::now();
+        // Heading north at 10 kn; a vessel heading west at 10 kn from 2 nm
+        // east of the point 2 nm ahead meets it there in 12 minutes
+        let own_state = OwnShipState {
+            latitude: 37.0,
+            longitude: -122.0,
+            speed_over_ground: 10.0,
+            course_over_ground: 0.0,
+            cpa_nm: None,
+            tcpa_min: Some(15.0),
+        };
+        let own = OwnShip::new(&own_state, now).unwrap();
+        let ahead = destination((37.0, -122.0), 0.0, 2.0 * METERS_PER_NM);
+        let east = destination(ahead, 90.0, 2.0 * METERS_PER_NM);
+        let crossing = report("111111111", east.0, east.1, 10.0, 270.0);
+        let diverging = report("222222222", east.0, east.1, 10.0, 90.0);
+
+        let traffic = Traffic::default();
+        traffic.observe(&crossing);
+        traffic.observe(&diverging);
+        let mut watch = CollisionWatch::default();
+        let alerts = watch.set_own_ship(Some(own), &traffic, now);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].mmsi, "111111111");
+        assert!(alerts[0].cpa_nm < 0.05, "{:?}", alerts[0]);
+        assert!((alerts[0].tcpa_min - 12.0).abs() < 0.1, "{:?}", alerts[0]);
+
+        // Repeated only once ALERT_REPEAT has passed
+        assert_eq!(watch.check(&crossing, now), None);
+        let later = destination(east, 270.0, 10.0 * METERS_PER_NM / 60.0);
+        assert!(watch.check(&report("111111111", later.0, later.1, 10.0, 270.0), now + ALERT_REPEAT).is_some());
+        assert_eq!(watch.check(&diverging, now), None);
+
+        // Outside a tighter time threshold
+        let cautious = OwnShipState { tcpa_min: Some(5.0), ..own_state };
+        assert!(watch.set_own_ship(OwnShip::new(&cautious, now), &traffic, now).is_empty());
+        assert!(OwnShip::new(&OwnShipState { latitude: 91.0, ..own_state }, now).is_none());
+    }
+}
@@ -0,0 +1,428 @@
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+use crate::access::AccessPolicy;
+use crate::replay::DEFAULT_REPLAY_SPEED;
+use crate::upstream::Upstream;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Environment variable holding the aisstream.io API key
+pub const API_KEY_ENV: &str = "AISSTREAM_API_KEY";
+
+/// Environment variable holding the comma-separated upstreams to merge
+pub const UPSTREAMS_ENV: &str = "YACHTPIT_AIS_UPSTREAMS";
+
+/// Environment variables naming a recorded capture to serve instead of the
+/// upstreams, and how many times real time to play it at
+pub const REPLAY_ENV: &str = "YACHTPIT_AIS_REPLAY";
+pub const REPLAY_SPEED_ENV: &str = "YACHTPIT_AIS_REPLAY_SPEED";
+
+/// Environment variable holding the database URL AIS messages are written to
+pub const DATABASE_ENV: &str = "YACHTPIT_AIS_DATABASE";
+
+/// Environment variable holding the comma-separated tokens accepted on the streams
+pub const ACCESS_TOKENS_ENV: &str = "YACHTPIT_AIS_TOKENS";
+
+/// Environment variable capping the stream clients served at once
+pub const MAX_CLIENTS_ENV: &str = "YACHTPIT_AIS_MAX_CLIENTS";
+
+/// Environment variable limiting the messages per second sent to a client
+pub const CLIENT_RATE_ENV: &str = "YACHTPIT_AIS_CLIENT_RATE";
+
+/// Environment variable setting the seconds between one vessel's position reports
+pub const DOWNSAMPLE_ENV: &str = "YACHTPIT_AIS_DOWNSAMPLE_S";
+
+/// Environment variable holding the IP address the server listens on
+pub const BIND_ENV: &str = "YACHTPIT_AIS_BIND";
+
+/// Environment variable holding the port the server listens on
+pub const PORT_ENV: &str = "YACHTPIT_AIS_PORT";
+
+/// Environment variable holding the comma-separated origins browsers may call from
+pub const ALLOWED_ORIGINS_ENV: &str = "YACHTPIT_AIS_ALLOWED_ORIGINS";
+
+/// Environment variables holding the PEM certificate chain and private key to serve TLS with
+pub const TLS_CERT_ENV: &str = "YACHTPIT_AIS_TLS_CERT";
+pub const TLS_KEY_ENV: &str = "YACHTPIT_AIS_TLS_KEY";
+
+/// Environment variable holding the port of the gRPC API; off when unset
+pub const GRPC_PORT_ENV: &str = "YACHTPIT_AIS_GRPC_PORT";
+
+/// Listen address unless configured otherwise
+pub const DEFAULT_BIND: IpAddr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+pub const DEFAULT_PORT: u16 = 3000;
+
+/// Environment variable naming the server's config file
+pub const CONFIG_FILE_ENV: &str = "YACHTPIT_AIS_CONFIG";
+
+const USAGE: &str = "Usage: ais [--api-key <key>] [--upstreams <a,b>] [--database <url>] [--access-tokens <a,b>] \
+    [--max-clients <n>] [--client-rate <per second>] [--downsample <seconds>] [--bind <ip>] [--port <n>] \
+    [--allowed-origins <a,b>] [--tls-cert <pem> --tls-key <pem>] [--grpc-port <n>] [--replay <file> [--replay-speed <x>]] [--config <file>]";
+
+// Settings read from the JSON config file; every field is optional.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    api_key: Option<String>,
+    upstreams: Option<Vec<String>>,
+    database_url: Option<String>,
+    access_tokens: Option<Vec<String>>,
+    max_clients: Option<usize>,
+    client_rate: Option<u32>,
+    downsample_s: Option<u64>,
+    bind: Option<String>,
+    port: Option<u16>,
+    allowed_origins: Option<Vec<String>>,
+    tls_cert: Option<PathBuf>,
+    tls_key: Option<PathBuf>,
+    grpc_port: Option<u16>,
+    replay: Option<PathBuf>,
+    replay_speed: Option<f64>,
+}
+
+// Why the server could not be configured.
+#[derive(Debug)]
+pub enum ConfigError {
+    MissingApiKey,
+    MissingValue(String),
+    UnknownArgument(String),
+    InvalidValue(&'static str, String),
+    Unreadable(PathBuf, std::io::Error),
+    Invalid(PathBuf, serde_json::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::MissingApiKey => write!(
+                f,
+                "No aisstream.io API key configured. Set {}, pass --api-key <key>, \
+                 or add \"api_key\" to the config file given by --config or {}; \
+                 or leave aisstream out of the upstreams.",
+                API_KEY_ENV, CONFIG_FILE_ENV
+            ),
+            ConfigError::MissingValue(flag) => write!(f, "{} needs a value\n{}", flag, USAGE),
+            ConfigError::UnknownArgument(arg) => write!(f, "Unknown argument: {}\n{}", arg, USAGE),
+            ConfigError::InvalidValue(setting, value) => write!(f, "Invalid {}: {}", setting, value),
+            ConfigError::Unreadable(path, e) => write!(f, "Cannot read config file {}: {}", path.display(), e),
+            ConfigError::Invalid(path, e) => write!(f, "Invalid config file {}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+// Server settings, resolved from the command line, then the environment,
+// then the config file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    /// Sources whose reports are merged into the stream; just the capture when replaying
+    pub upstreams: Vec<Upstream>,
+    /// `sqlite://` or `postgres://` URL to persist messages to
+    pub database_url: Option<String>,
+    pub access: AccessPolicy,
+    /// Least time between a vessel's position reports; all go out when unset
+    pub downsample: Option<Duration>,
+    pub listen: SocketAddr,
+    /// Origins browsers may call the server from; any when empty
+    pub allowed_origins: Vec<String>,
+    /// Served over HTTPS when set
+    pub tls: Option<TlsFiles>,
+    /// Where the gRPC API listens, on the same address as HTTP; not served when unset
+    pub grpc_listen: Option<SocketAddr>,
+}
+
+// PEM files of the certificate chain and its private key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsFiles {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+impl ServerConfig {
+    // Configuration from the process arguments and environment.
+    pub fn load() -> Result<Self, ConfigError> {
+        Self::resolve(std::env::args().skip(1), |name| std::env::var(name).ok())
+    }
+
+    // Configuration from `args` (without the program name) and the variables
+    // `env` looks up.
+    pub fn resolve(
+        args: impl IntoIterator<Item = String>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let mut api_key = None;
+        let mut upstreams = None;
+        let mut database_url = None;
+        let mut access_tokens = None;
+        let mut max_clients = None;
+        let mut client_rate = None;
+        let mut downsample = None;
+        let mut bind = None;
+        let mut port = None;
+        let mut allowed_origins = None;
+        let mut tls_cert = None;
+        let mut tls_key = None;
+        let mut grpc_port = None;
+        let mut replay = None;
+        let mut replay_speed = None;
+        let mut config_path = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg.clone(), None),
+            };
+            let target = match flag.as_str() {
+                "--api-key" => &mut api_key,
+                "--upstreams" => &mut upstreams,
+                "--database" => &mut database_url,
+                "--access-tokens" => &mut access_tokens,
+                "--max-clients" => &mut max_clients,
+                "--client-rate" => &mut client_rate,
+                "--downsample" => &mut downsample,
+                "--bind" => &mut bind,
+                "--port" => &mut port,
+                "--allowed-origins" => &mut allowed_origins,
+                "--tls-cert" => &mut tls_cert,
+                "--tls-key" => &mut tls_key,
+                "--grpc-port" => &mut grpc_port,
+                "--replay" => &mut replay,
+                "--replay-speed" => &mut replay_speed,
+                "--config" => &mut config_path,
+                _ => return Err(ConfigError::UnknownArgument(arg)),
+            };
+            let value = inline.or_else(|| args.next()).ok_or(ConfigError::MissingValue(flag))?;
+            *target = Some(value);
+        }
+
+        let config_path = config_path.or_else(|| env(CONFIG_FILE_ENV)).map(PathBuf::from);
+        let file = match &config_path {
+            Some(path) => read_config_file(path)?,
+            None => ConfigFile::default(),
+        };
+
+        let api_key = first_set([api_key, env(API_KEY_ENV), file.api_key]);
+        let replay = first_set([replay, env(REPLAY_ENV), None]).map(PathBuf::from).or(file.replay);
+        // A replay stands in for every upstream
+        let specs = match first_set([upstreams, env(UPSTREAMS_ENV), None]) {
+            _ if replay.is_some() => Vec::new(),
+            Some(list) => list.split(',').map(str::to_string).collect(),
+            None => file.upstreams.unwrap_or_else(|| vec!["aisstream".to_string()]),
+        };
+        let mut upstreams = Vec::new();
+        if let Some(path) = replay {
+            let speed = parse_setting("replay speed", [replay_speed, env(REPLAY_SPEED_ENV), None])?
+                .or(file.replay_speed)
+                .unwrap_or(DEFAULT_REPLAY_SPEED);
+            if !(speed.is_finite() && speed > 0.0) {
+                return Err(ConfigError::InvalidValue("replay speed", speed.to_string()));
+            }
+            upstreams.push(Upstream::Replay { path, speed });
+        }
+        for spec in specs.iter().map(|spec| spec.trim()).filter(|spec| !spec.is_empty()) {
+            if spec == "aisstream" && api_key.is_none() {
+                return Err(ConfigError::MissingApiKey);
+            }
+            let upstream = Upstream::parse(spec, api_key.as_deref())
+                .map_err(|spec| ConfigError::InvalidValue("upstream", spec))?;
+            if !upstreams.contains(&upstream) {
+                upstreams.push(upstream);
+            }
+        }
+        if upstreams.is_empty() {
+            return Err(ConfigError::InvalidValue("upstreams", "none given".to_string()));
+        }
+        let database_url = first_set([database_url, env(DATABASE_ENV), file.database_url]);
+
+        let tokens = match first_set([access_tokens, env(ACCESS_TOKENS_ENV), None]) {
+            Some(list) => list.split(',').map(str::to_string).collect(),
+            None => file.access_tokens.unwrap_or_default(),
+        };
+        let defaults = AccessPolicy::default();
+        let access = AccessPolicy {
+            tokens: tokens.into_iter().map(|token| token.trim().to_string()).filter(|token| !token.is_empty()).collect(),
+            max_clients: parse_setting("max clients", [max_clients, env(MAX_CLIENTS_ENV), None])?
+                .or(file.max_clients)
+                .unwrap_or(defaults.max_clients),
+            client_rate: parse_setting("client rate", [client_rate, env(CLIENT_RATE_ENV), None])?
+                .or(file.client_rate)
+                .unwrap_or(defaults.client_rate),
+        };
+        if access.max_clients == 0 || access.client_rate == 0 {
+            return Err(ConfigError::InvalidValue("stream limits", "must be above zero".to_string()));
+        }
+        let downsample = parse_setting("downsample interval", [downsample, env(DOWNSAMPLE_ENV), None])?
+            .or(file.downsample_s)
+            .filter(|seconds| *seconds > 0)
+            .map(Duration::from_secs);
+
+        let bind = parse_setting("bind address", [bind, env(BIND_ENV), file.bind])?.unwrap_or(DEFAULT_BIND);
+        let port = parse_setting("port", [port, env(PORT_ENV), None])?.or(file.port).unwrap_or(DEFAULT_PORT);
+        let allowed_origins = match first_set([allowed_origins, env(ALLOWED_ORIGINS_ENV), None]) {
+            Some(list) => list.split(',').map(str::to_string).collect(),
+            None => file.allowed_origins.unwrap_or_default(),
+        };
+        let allowed_origins = allowed_origins
+            .into_iter()
+            .map(|origin| origin.trim().trim_end_matches('/').to_string())
+            .filter(|origin| !origin.is_empty())
+            .map(|origin| match url::Url::parse(&origin) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") && url.path() == "/" => Ok(origin),
+                _ => Err(ConfigError::InvalidValue("allowed origin", origin)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let tls_cert = first_set([tls_cert, env(TLS_CERT_ENV), None]).map(PathBuf::from).or(file.tls_cert);
+        let tls_key = first_set([tls_key, env(TLS_KEY_ENV), None]).map(PathBuf::from).or(file.tls_key);
+        let tls = match (tls_cert, tls_key) {
+            (Some(cert), Some(key)) => Some(TlsFiles { cert, key }),
+            (None, None) => None,
+            _ => return Err(ConfigError::InvalidValue("TLS files", "a certificate needs its key".to_string())),
+        };
+        let grpc_port: Option<u16> = parse_setting("gRPC port", [grpc_port, env(GRPC_PORT_ENV), None])?.or(file.grpc_port);
+        if grpc_port == Some(port) {
+            return Err(ConfigError::InvalidValue("gRPC port", format!("{} is taken by HTTP", port)));
+        }
+
+        Ok(Self {
+            upstreams,
+            database_url,
+            access,
+            downsample,
+            listen: SocketAddr::new(bind, port),
+            allowed_origins,
+            tls,
+            grpc_listen: grpc_port.map(|port| SocketAddr::new(bind, port)),
+        })
+    }
+}
+
+// The first setting given, parsed.
+fn parse_setting<T: FromStr>(setting: &'static str, values: [Option<String>; 3]) -> Result<Option<T>, ConfigError> {
+    first_set(values)
+        .map(|value| value.parse().map_err(|_| ConfigError::InvalidValue(setting, value)))
+        .transpose()
+}
+
+// The first non-blank setting, trimmed.
+fn first_set(values: [Option<String>; 3]) -> Option<String> {
+    values
+        .into_iter()
+        .flatten()
+        .map(|value| value.trim().to_string())
+        .find(|value| !value.is_empty())
+}
+
+fn read_config_file(path: &Path) -> Result<ConfigFile, ConfigError> {
+    let text = std::fs::read_to_string(path).map_err(|e| ConfigError::Unreadable(path.to_path_buf(), e))?;
+    serde_json::from_str(&text).map_err(|e| ConfigError::Invalid(path.to_path_buf(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn api_key(config: &ServerConfig) -> Option<&str> {
+        config.upstreams.iter().find_map(|upstream| match upstream {
+            Upstream::AisStream { api_key } => Some(api_key.as_str()),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_config_precedence() {
+        let path = std::env::temp_dir().join(format!("ais-config-{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"api_key": "from-file", "database_url": "sqlite://ais.db"}"#).unwrap();
+        let file = path.to_str().unwrap();
+        let env_key = |name: &str| (name == API_KEY_ENV).then(|| "from-env".to_string());
+
+        let config = ServerConfig::resolve(args(&["--config", file]), |_| None).unwrap();
+        assert_eq!(api_key(&config), Some("from-file"));
+        assert_eq!(config.database_url.as_deref(), Some("sqlite://ais.db"));
+        let config = ServerConfig::resolve(args(&["--config", file, "--database", "postgres://ais"]), |_| None).unwrap();
+        assert_eq!(config.database_url.as_deref(), Some("postgres://ais"));
+        let config = ServerConfig::resolve(args(&["--config", file]), env_key).unwrap();
+        assert_eq!(api_key(&config), Some("from-env"));
+        let config = ServerConfig::resolve(args(&["--api-key=from-flag", "--config", file]), env_key).unwrap();
+        assert_eq!(api_key(&config), Some("from-flag"));
+
+        // The config file can also come from the environment
+        let env_file = |name: &str| (name == CONFIG_FILE_ENV).then(|| file.to_string());
+        assert_eq!(api_key(&ServerConfig::resolve(Vec::new(), env_file).unwrap()), Some("from-file"));
+        std::fs::remove_file(&path).unwrap();
+
+        let config = ServerConfig::resolve(args(&["--api-key", "key"]), |_| None).unwrap();
+        assert_eq!(config.database_url, None);
+        assert!(config.access.is_open());
+        let env_tokens = |name: &str| (name == ACCESS_TOKENS_ENV).then(|| "one, two".to_string());
+        let config = ServerConfig::resolve(args(&["--api-key", "key", "--max-clients", "5"]), env_tokens).unwrap();
+        assert_eq!(config.access.tokens.len(), 2);
+        assert!(config.access.accepts(Some("two")));
+        assert_eq!(config.access.max_clients, 5);
+        assert_eq!(config.downsample, None);
+        assert_eq!(config.listen, SocketAddr::from(([0, 0, 0, 0], 3000)));
+        assert!(config.allowed_origins.is_empty() && config.tls.is_none());
+        assert_eq!(config.grpc_listen, None);
+        let config = ServerConfig::resolve(args(&["--api-key", "key", "--downsample", "30"]), |_| None).unwrap();
+        assert_eq!(config.downsample, Some(Duration::from_secs(30)));
+
+        let env_origins = |name: &str| (name == ALLOWED_ORIGINS_ENV).then(|| "https://chart.example/, http://localhost:5173".to_string());
+        let deployed = args(&["--api-key", "key", "--bind", "::1", "--port=8443", "--tls-cert", "cert.pem", "--tls-key", "key.pem"]);
+        let config = ServerConfig::resolve(deployed, env_origins).unwrap();
+        assert_eq!(config.listen, "[::1]:8443".parse().unwrap());
+        assert_eq!(config.allowed_origins, vec!["https://chart.example", "http://localhost:5173"]);
+        assert_eq!(config.tls.unwrap().key, PathBuf::from("key.pem"));
+        let config = ServerConfig::resolve(args(&["--api-key", "key", "--bind", "::1", "--grpc-port", "50051"]), |_| None).unwrap();
+        assert_eq!(config.grpc_listen, Some("[::1]:50051".parse().unwrap()));
+        for invalid in [
+            &["--api-key", "key", "--bind", "localhost"][..],
+            &["--api-key", "key", "--port", "70000"],
+            &["--api-key", "key", "--allowed-origins", "chart.example"],
+            &["--api-key", "key", "--tls-cert", "cert.pem"],
+            &["--api-key", "key", "--grpc-port", "3000"],
+        ] {
+            assert!(matches!(ServerConfig::resolve(args(invalid), |_| None), Err(ConfigError::InvalidValue(..))), "{:?}", invalid);
+        }
+        assert!(matches!(
+            ServerConfig::resolve(args(&["--api-key", "key", "--client-rate", "fast"]), |_| None),
+            Err(ConfigError::InvalidValue(..))
+        ));
+        let missing = ServerConfig::resolve(Vec::new(), |_| None).unwrap_err();
+        assert!(matches!(missing, ConfigError::MissingApiKey));
+        assert!(missing.to_string().contains(API_KEY_ENV));
+
+        // Without aisstream among the upstreams no key is needed
+        let env_upstreams = |name: &str| (name == UPSTREAMS_ENV).then(|| "tcp://10.0.0.5:10110, udp://0.0.0.0:10110".to_string());
+        let config = ServerConfig::resolve(Vec::new(), env_upstreams).unwrap();
+        assert_eq!(config.upstreams.len(), 2);
+        assert_eq!(api_key(&config), None);
+        let config = ServerConfig::resolve(args(&["--upstreams", "aisstream,ws://relay:3000/ws", "--api-key", "key"]), |_| None).unwrap();
+        assert_eq!(config.upstreams[1], Upstream::Relay { url: "ws://relay:3000/ws".to_string() });
+        // A replay needs neither upstreams nor a key
+        let config = ServerConfig::resolve(args(&["--upstreams", "aisstream", "--replay", "harbour.nmea", "--replay-speed=10"]), |_| None).unwrap();
+        assert_eq!(config.upstreams, vec![Upstream::Replay { path: PathBuf::from("harbour.nmea"), speed: 10.0 }]);
+        assert!(matches!(
+            ServerConfig::resolve(args(&["--replay", "harbour.nmea", "--replay-speed", "0"]), |_| None),
+            Err(ConfigError::InvalidValue("replay speed", _))
+        ));
+        assert!(matches!(
+            ServerConfig::resolve(args(&["--upstreams", "serial:///dev/ttyUSB0"]), |_| None),
+            Err(ConfigError::InvalidValue("upstream", _))
+        ));
+        assert!(matches!(
+            ServerConfig::resolve(args(&["--api-key"]), |_| None),
+            Err(ConfigError::MissingValue(_))
+        ));
+        assert!(matches!(
+            ServerConfig::resolve(args(&["--verbose"]), |_| None),
+            Err(ConfigError::UnknownArgument(_))
+        ));
+    }
+}
@@ -0,0 +1,130 @@
+use crate::ais::AisResponse;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Course change that lets a position through early, in degrees
+pub const COURSE_CHANGE_DEG: f64 = 10.0;
+
+/// Speed change that lets a position through early, in knots
+pub const SPEED_CHANGE_KTS: f64 = 1.0;
+
+/// Vessels remembered before stale entries are swept
+const SWEEP_THRESHOLD: usize = 20_000;
+
+#[derive(Debug, Clone, Copy)]
+struct SentPosition {
+    at: Instant,
+    speed: Option<f64>,
+    course: Option<f64>,
+}
+
+// Thins out position reports: at most one per vessel per interval, unless
+// the vessel turned or changed speed since the last one sent. Static
+// reports and our own ship are never held back.
+#[derive(Debug)]
+pub struct Downsampler {
+    interval: Duration,
+    sent: Mutex<HashMap<String, SentPosition>>,
+}
+
+impl Downsampler {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            sent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Whether `report` should go out now.
+    pub fn admit(&self, report: &AisResponse) -> bool {
+        self.admit_at(report, Instant::now())
+    }
+
+    fn admit_at(&self, report: &AisResponse, now: Instant) -> bool {
+        let Some(mmsi) = &report.mmsi else {
+            return true;
+        };
+        if report.own_ship || report.latitude.is_none() || report.longitude.is_none() {
+            return true;
+        }
+
+        let position = SentPosition {
+            at: now,
+            speed: report.speed_over_ground,
+            course: report.course_over_ground,
+        };
+        let mut sent = self.sent.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(last) = sent.get(mmsi) {
+            if now.duration_since(last.at) < self.interval && !changed(last, &position) {
+                return false;
+            }
+        }
+        if sent.len() >= SWEEP_THRESHOLD && !sent.contains_key(mmsi) {
+            let interval = self.interval;
+            sent.retain(|_, last| now.duration_since(last.at) < interval);
+        }
+        sent.insert(mmsi.clone(), position);
+        true
+    }
+}
+
+fn changed(last: &SentPosition, now: &SentPosition) -> bool {
+    let speed_changed = match (last.speed, now.speed) {
+        (Some(before), Some(after)) => (after - before).abs() >= SPEED_CHANGE_KTS,
+        _ => false,
+    };
+    let course_changed = match (last.course, now.course) {
+        (Some(before), Some(after)) => ((after - before + 540.0) % 360.0 - 180.0).abs() >= COURSE_CHANGE_DEG,
+        _ => false,
+    };
+    speed_changed || course_changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(speed: f64, course: f64) -> AisResponse {
+        AisResponse {
+            message_type: Some("PositionReport".to_string()),
+            mmsi: Some("366123456".to_string()),
+            ship_name: None,
+            latitude: Some(37.8),
+            longitude: Some(-122.4),
+            timestamp: None,
+            speed_over_ground: Some(speed),
+            course_over_ground: Some(course),
+            heading: None,
+            navigation_status: None,
+            ship_type: None,
+            callsign: None,
+            dimensions: None,
+            own_ship: false,
+            source: None,
+            raw_message: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_positions_thinned_unless_course_or_speed_change() {
+        let downsampler = Downsampler::new(Duration::from_secs(30));
+        let start = Instant::now();
+        let at = |seconds: u64| start + Duration::from_secs(seconds);
+
+        assert!(downsampler.admit_at(&position(8.0, 355.0), at(0)));
+        assert!(!downsampler.admit_at(&position(8.3, 2.0), at(5)), "a 7 degree turn across north");
+        assert!(downsampler.admit_at(&position(8.3, 8.0), at(10)), "13 degrees off the last sent");
+        assert!(!downsampler.admit_at(&position(8.8, 8.0), at(15)));
+        assert!(downsampler.admit_at(&position(9.3, 8.0), at(20)), "a knot faster than the last sent");
+        assert!(downsampler.admit_at(&position(9.3, 8.0), at(50)), "the interval has passed");
+
+        let mut static_report = position(9.3, 8.0);
+        static_report.latitude = None;
+        assert!(downsampler.admit_at(&static_report, at(51)));
+        let mut own = position(9.3, 8.0);
+        own.own_ship = true;
+        assert!(downsampler.admit_at(&own, at(52)));
+    }
+}
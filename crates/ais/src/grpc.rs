@@ -0,0 +1,330 @@
+use crate::ais::{
+    client_reports, history_range, query_positions, AisResponse, AisStreamManager, WebSocketBoundingBox,
+    HISTORY_PAGE_SIZE, MAX_HISTORY_PAGE_SIZE,
+};
+use crate::registry::VesselDimensions;
+use crate::store::PositionQuery;
+use crate::subscription::Subscription;
+use futures_util::{Stream, StreamExt};
+use std::pin::Pin;
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+include!(concat!(env!("OUT_DIR"), "/yachtpit.ais.v1.AisService.rs"));
+
+pub use ais_service_server::{AisService, AisServiceServer};
+
+// The messages of proto/ais.proto, kept in step with it by hand.
+
+#[derive(Clone, Copy, PartialEq, prost::Message)]
+pub struct BoundingBox {
+    #[prost(double, tag = "1")]
+    pub sw_lat: f64,
+    #[prost(double, tag = "2")]
+    pub sw_lon: f64,
+    #[prost(double, tag = "3")]
+    pub ne_lat: f64,
+    #[prost(double, tag = "4")]
+    pub ne_lon: f64,
+}
+
+#[derive(Clone, Copy, PartialEq, prost::Message)]
+pub struct Dimensions {
+    #[prost(uint32, tag = "1")]
+    pub to_bow: u32,
+    #[prost(uint32, tag = "2")]
+    pub to_stern: u32,
+    #[prost(uint32, tag = "3")]
+    pub to_port: u32,
+    #[prost(uint32, tag = "4")]
+    pub to_starboard: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct StreamVesselsRequest {
+    #[prost(message, optional, tag = "1")]
+    pub bounding_box: Option<BoundingBox>,
+    #[prost(string, repeated, tag = "2")]
+    pub mmsis: Vec<String>,
+    #[prost(string, repeated, tag = "3")]
+    pub ship_types: Vec<String>,
+    #[prost(string, repeated, tag = "4")]
+    pub navigation_statuses: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct VesselReport {
+    #[prost(string, tag = "1")]
+    pub mmsi: String,
+    #[prost(string, optional, tag = "2")]
+    pub message_type: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub ship_name: Option<String>,
+    #[prost(double, optional, tag = "4")]
+    pub latitude: Option<f64>,
+    #[prost(double, optional, tag = "5")]
+    pub longitude: Option<f64>,
+    #[prost(string, optional, tag = "6")]
+    pub timestamp: Option<String>,
+    #[prost(double, optional, tag = "7")]
+    pub speed_over_ground: Option<f64>,
+    #[prost(double, optional, tag = "8")]
+    pub course_over_ground: Option<f64>,
+    #[prost(double, optional, tag = "9")]
+    pub heading: Option<f64>,
+    #[prost(string, optional, tag = "10")]
+    pub navigation_status: Option<String>,
+    #[prost(string, optional, tag = "11")]
+    pub ship_type: Option<String>,
+    #[prost(string, optional, tag = "12")]
+    pub callsign: Option<String>,
+    #[prost(message, optional, tag = "13")]
+    pub dimensions: Option<Dimensions>,
+    #[prost(bool, tag = "14")]
+    pub own_ship: bool,
+    #[prost(string, optional, tag = "15")]
+    pub source: Option<String>,
+    #[prost(int64, tag = "16")]
+    pub received_at: i64,
+    #[prost(string, repeated, tag = "17")]
+    pub areas: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GetVesselRequest {
+    #[prost(string, tag = "1")]
+    pub mmsi: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Vessel {
+    #[prost(string, tag = "1")]
+    pub mmsi: String,
+    #[prost(string, optional, tag = "2")]
+    pub ship_name: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub callsign: Option<String>,
+    #[prost(string, optional, tag = "4")]
+    pub ship_type: Option<String>,
+    #[prost(message, optional, tag = "5")]
+    pub dimensions: Option<Dimensions>,
+    #[prost(int64, tag = "6")]
+    pub updated_at: i64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct QueryHistoryRequest {
+    #[prost(string, optional, tag = "1")]
+    pub mmsi: Option<String>,
+    #[prost(int64, optional, tag = "2")]
+    pub from: Option<i64>,
+    #[prost(int64, optional, tag = "3")]
+    pub to: Option<i64>,
+    #[prost(message, optional, tag = "4")]
+    pub bounding_box: Option<BoundingBox>,
+    #[prost(uint32, optional, tag = "5")]
+    pub limit: Option<u32>,
+    #[prost(uint64, tag = "6")]
+    pub offset: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct QueryHistoryResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub reports: Vec<VesselReport>,
+    #[prost(uint64, optional, tag = "2")]
+    pub next_offset: Option<u64>,
+}
+
+impl From<BoundingBox> for WebSocketBoundingBox {
+    fn from(bbox: BoundingBox) -> Self {
+        WebSocketBoundingBox {
+            sw_lat: bbox.sw_lat,
+            sw_lon: bbox.sw_lon,
+            ne_lat: bbox.ne_lat,
+            ne_lon: bbox.ne_lon,
+        }
+    }
+}
+
+impl From<VesselDimensions> for Dimensions {
+    fn from(dimensions: VesselDimensions) -> Self {
+        Dimensions {
+            to_bow: dimensions.to_bow.into(),
+            to_stern: dimensions.to_stern.into(),
+            to_port: dimensions.to_port.into(),
+            to_starboard: dimensions.to_starboard.into(),
+        }
+    }
+}
+
+pub(crate) fn vessel_report(data: AisResponse, received_at: i64) -> VesselReport {
+    VesselReport {
+        mmsi: data.mmsi.unwrap_or_default(),
+        message_type: data.message_type,
+        ship_name: data.ship_name,
+        latitude: data.latitude,
+        longitude: data.longitude,
+        timestamp: data.timestamp,
+        speed_over_ground: data.speed_over_ground,
+        course_over_ground: data.course_over_ground,
+        heading: data.heading,
+        navigation_status: data.navigation_status,
+        ship_type: data.ship_type,
+        callsign: data.callsign,
+        dimensions: data.dimensions.map(Dimensions::from),
+        own_ship: data.own_ship,
+        source: data.source,
+        received_at,
+        areas: Vec::new(),
+    }
+}
+
+// The AIS relay over gRPC, sharing the HTTP server's manager.
+pub struct AisGrpc {
+    manager: Arc<AisStreamManager>,
+}
+
+impl AisGrpc {
+    pub fn new(manager: Arc<AisStreamManager>) -> Self {
+        Self { manager }
+    }
+}
+
+// Status is large, but it is what tonic has services return
+#[allow(clippy::result_large_err)]
+#[tonic::async_trait]
+impl AisService for AisGrpc {
+    type StreamVesselsStream = Pin<Box<dyn Stream<Item = Result<VesselReport, Status>> + Send>>;
+
+    async fn stream_vessels(
+        &self,
+        request: Request<StreamVesselsRequest>,
+    ) -> Result<Response<Self::StreamVesselsStream>, Status> {
+        // The stream passes on the upstream feed, so it takes the same tokens as /ws
+        let token = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        if !self.manager.access().accepts(token) {
+            return Err(Status::unauthenticated("An AIS stream access token is required"));
+        }
+        let Some(slot) = self.manager.admit_client() else {
+            return Err(Status::resource_exhausted("Too many AIS stream clients"));
+        };
+
+        let request = request.into_inner();
+        let mut subscription = Subscription::default();
+        subscription.bounding_box = request.bounding_box.map(WebSocketBoundingBox::from);
+        subscription.set_filters(request.mmsis, request.ship_types, request.navigation_statuses);
+        let reports = client_reports(self.manager.clone(), subscription, slot)
+            .await
+            .map(|data| Ok(vessel_report(data, 0)));
+        Ok(Response::new(Box::pin(reports)))
+    }
+
+    async fn get_vessel(&self, request: Request<GetVesselRequest>) -> Result<Response<Vessel>, Status> {
+        let mmsi = request.into_inner().mmsi;
+        let record = self
+            .manager
+            .vessels()
+            .get(&mmsi)
+            .ok_or_else(|| Status::not_found(format!("No static data for {}", mmsi)))?;
+        Ok(Response::new(Vessel {
+            mmsi,
+            ship_name: record.ship_name,
+            callsign: record.callsign,
+            ship_type: record.ship_type,
+            dimensions: record.dimensions.map(Dimensions::from),
+            updated_at: record.updated_at,
+        }))
+    }
+
+    async fn query_history(
+        &self,
+        request: Request<QueryHistoryRequest>,
+    ) -> Result<Response<QueryHistoryResponse>, Status> {
+        let store = self
+            .manager
+            .store()
+            .ok_or_else(|| Status::unavailable("AIS history is not being recorded"))?;
+        let request = request.into_inner();
+        let (from, to) = history_range(request.from, request.to).map_err(|(_, message)| Status::invalid_argument(message))?;
+        let limit = request
+            .limit
+            .map_or(HISTORY_PAGE_SIZE, |limit| limit as usize)
+            .clamp(1, MAX_HISTORY_PAGE_SIZE);
+        let offset = request.offset as usize;
+
+        // One row beyond the page tells whether another follows
+        let query = PositionQuery {
+            mmsi: request.mmsi.filter(|mmsi| !mmsi.is_empty()),
+            from,
+            to,
+            bounding_box: request.bounding_box.map(WebSocketBoundingBox::from),
+            limit: limit + 1,
+            offset,
+        };
+        let mut positions = query_positions(store, &query).await.map_err(|(_, message)| Status::internal(message))?;
+        let next_offset = (positions.len() > limit).then_some((offset + limit) as u64);
+        positions.truncate(limit);
+        Ok(Response::new(QueryHistoryResponse {
+            reports: positions
+                .into_iter()
+                .map(|stored| vessel_report(stored.message, stored.received_at))
+                .collect(),
+            next_offset,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::AccessPolicy;
+    use crate::upstream::aisstream_response;
+    use serde_json::json;
+    use std::collections::HashSet;
+
+    #[tokio::test]
+    async fn test_grpc_service() {
+        let access = AccessPolicy {
+            tokens: HashSet::from(["s3cret".to_string()]),
+            max_clients: 1,
+            ..AccessPolicy::default()
+        };
+        let manager = Arc::new(AisStreamManager::new(Vec::new()).with_access(access));
+        let service = AisGrpc::new(manager.clone());
+        let get = |mmsi: &str| Request::new(GetVesselRequest { mmsi: mmsi.to_string() });
+
+        let status = service.get_vessel(get("366123456")).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+        let mut static_data = aisstream_response(&json!({
+            "MessageType": "ShipStaticData",
+            "MetaData": {"MMSI": 366123456, "ShipName": "SEA BREEZE"},
+            "Message": {"ShipStaticData": {"UserID": 366123456, "CallSign": "WDA1234", "Dimension": {"A": 8, "B": 4, "C": 2, "D": 2}}}
+        }), std::time::SystemTime::now())
+        .unwrap();
+        manager.vessels().enrich(&mut static_data);
+        let vessel = service.get_vessel(get("366123456")).await.unwrap().into_inner();
+        assert_eq!(vessel.ship_name.as_deref(), Some("SEA BREEZE"));
+        assert_eq!(vessel.dimensions.map(|d| d.to_bow + d.to_stern), Some(12));
+
+        let status = service.query_history(Request::new(QueryHistoryRequest::default())).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unavailable);
+
+        let status = service.stream_vessels(Request::new(StreamVesselsRequest::default())).await.err().unwrap();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        let authorized = || {
+            let mut request = Request::new(StreamVesselsRequest::default());
+            request.metadata_mut().insert("authorization", "Bearer s3cret".parse().unwrap());
+            request
+        };
+        let stream = service.stream_vessels(authorized()).await.unwrap();
+        let status = service.stream_vessels(authorized()).await.err().unwrap();
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+        drop(stream);
+        assert!(service.stream_vessels(authorized()).await.is_ok());
+    }
+}
@@ -1,36 +1,166 @@
 use std::sync::Arc;
+use std::time::Duration;
 use axum::Router;
-use axum::routing::get;
-use tower_http::cors::CorsLayer;
+use axum::middleware;
+use axum::routing::{get, post};
+use axum::http::{header, HeaderValue, Method};
+use axum_server::tls_rustls::RustlsConfig;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use crate::ais::{AisStreamManager, AppState};
+use crate::registry::VesselRegistry;
+use crate::config::ServerConfig;
+use crate::grpc::{AisGrpc, AisServiceServer};
+use crate::store::AisStore;
 
+mod access;
 mod ais;
+mod collision;
+mod config;
+mod downsample;
+mod grpc;
+mod metrics;
+mod registry;
+mod replay;
+mod spatial;
+mod store;
+mod subscription;
+mod track;
+mod upstream;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let config = match ServerConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+
+    let store = match &config.database_url {
+        Some(url) => {
+            let store = AisStore::connect(url)
+                .await
+                .map_err(|e| format!("Cannot open AIS database {}: {}", url, e))?;
+            println!("Persisting AIS messages to {}", url);
+            Some(store)
+        }
+        None => None,
+    };
+
+    // Names and dimensions heard in earlier runs
+    let vessels = match &store {
+        Some(store) => {
+            let records = store.vessels().await.map_err(|e| format!("Cannot load vessel records: {}", e))?;
+            println!("Loaded static data for {} vessels", records.len());
+            VesselRegistry::from_records(records)
+        }
+        None => VesselRegistry::default(),
+    };
+
+    let sources: Vec<String> = config.upstreams.iter().map(ToString::to_string).collect();
+    println!("AIS upstreams: {}", sources.join(", "));
+    if let Some(interval) = config.downsample {
+        println!("Position reports downsampled to one per vessel every {}s", interval.as_secs());
+    }
+
     // Create the shared state with the AIS stream manager
+    let manager = AisStreamManager::new(config.upstreams)
+        .with_own_mmsi(ais::own_mmsi_from_env())
+        .with_store(store)
+        .with_vessels(vessels)
+        .with_downsampling(config.downsample)
+        .with_access(config.access);
+    if manager.access().is_open() {
+        println!("No access tokens configured: the AIS streams are open to anyone");
+    }
+    let manager = Arc::new(manager);
+
+    // Typed access for other onboard services, next to the HTTP server
+    if let Some(grpc_listen) = config.grpc_listen {
+        let service = AisServiceServer::new(AisGrpc::new(manager.clone()));
+        println!("AIS gRPC API running on {}", grpc_listen);
+        tokio::spawn(async move {
+            let served = tonic::transport::Server::builder()
+                .add_service(service)
+                .serve_with_shutdown(grpc_listen, ais::shutdown_signal())
+                .await;
+            if let Err(e) = served {
+                eprintln!("AIS gRPC API stopped: {}", e);
+            }
+        });
+    }
+
     let state = AppState {
-        ais_stream_manager: Arc::new(AisStreamManager::new()),
+        ais_stream_manager: manager,
     };
 
     // Create and start the Axum HTTP server
-    let app = create_router(state);
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await?;
-
-    println!("AIS server running on http://0.0.0.0:3000");
-
-    axum::serve(listener, app)
-        .with_graceful_shutdown(ais::shutdown_signal())
-        .await?;
+    let app = create_router(state).layer(cors_layer(&config.allowed_origins));
+    match config.tls {
+        Some(tls) => {
+            // Only the ring provider is compiled in; install it for rustls
+            let _ = rustls::crypto::ring::default_provider().install_default();
+            let tls_config = RustlsConfig::from_pem_file(&tls.cert, &tls.key)
+                .await
+                .map_err(|e| format!("Cannot load TLS certificate {}: {}", tls.cert.display(), e))?;
+            let handle = axum_server::Handle::new();
+            let shutdown = handle.clone();
+            tokio::spawn(async move {
+                ais::shutdown_signal().await;
+                shutdown.graceful_shutdown(Some(Duration::from_secs(10)));
+            });
+            println!("AIS server running on https://{}", config.listen);
+            axum_server::bind_rustls(config.listen, tls_config)
+                .handle(handle)
+                .serve(app.into_make_service())
+                .await?;
+        }
+        None => {
+            let listener = tokio::net::TcpListener::bind(config.listen).await?;
+            println!("AIS server running on http://{}", config.listen);
+            axum::serve(listener, app)
+                .with_graceful_shutdown(ais::shutdown_signal())
+                .await?;
+        }
+    }
 
     Ok(())
 }
 
 // Create the Axum router
 fn create_router(state: AppState) -> Router {
-    Router::new()
+    // Vessel data passes on the paid upstream feed, so it takes a token;
+    // only health and metrics stay open
+    let vessel_data = Router::new()
         .route("/ais", get(crate::ais::get_ais_data))
+        .route("/ais/history", get(crate::ais::get_ais_history))
+        .route("/vessels/:mmsi/track", get(crate::ais::get_vessel_track))
         .route("/ws", get(crate::ais::websocket_handler))
-        .layer(CorsLayer::permissive())
+        .route("/ws/map", get(crate::ais::map_websocket_handler))
+        .route("/ais/stream", get(crate::ais::sse_handler))
+        .route("/ais/collisions", post(crate::ais::post_collision_check))
+        .route_layer(middleware::from_fn_with_state(state.clone(), crate::access::require_token));
+
+    Router::new()
+        .route("/status", get(crate::ais::get_status))
+        .route("/metrics", get(crate::ais::get_metrics))
+        .merge(vessel_data)
         .with_state(state)
+}
+
+// Cross-origin access for browsers: from `allowed_origins`, or any origin
+// when none are configured.
+fn cors_layer(allowed_origins: &[String]) -> CorsLayer {
+    if allowed_origins.is_empty() {
+        return CorsLayer::permissive();
+    }
+    let origins: Vec<HeaderValue> = allowed_origins
+        .iter()
+        .filter_map(|origin| HeaderValue::from_str(origin).ok())
+        .collect();
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods([Method::GET, Method::POST])
+        .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE])
 }
\ No newline at end of file
@@ -0,0 +1,155 @@
+use crate::store::unix_millis;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Content type of the Prometheus text exposition format
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UpstreamCounters {
+    pub received: u64,
+    pub reconnects: u64,
+    pub connected: bool,
+    /// Unix milliseconds of the newest report
+    pub last_message_at: Option<i64>,
+}
+
+// Counters kept while the relay runs. Gauges that can be read off the
+// manager's state (clients, queue lengths) are sampled when rendering.
+#[derive(Debug, Default)]
+pub struct AisMetrics {
+    upstreams: Mutex<BTreeMap<String, UpstreamCounters>>,
+    broadcast: AtomicU64,
+    duplicates: AtomicU64,
+    downsampled: AtomicU64,
+    /// Messages receivers missed by falling behind the broadcast
+    lagged: AtomicU64,
+    /// Messages held back from clients over their rate limit
+    rate_limited: AtomicU64,
+    /// Records received but not yet written by the store
+    store_pending: AtomicU64,
+}
+
+// Gauges sampled from the manager for one scrape.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StreamGauges {
+    pub clients: usize,
+    pub max_clients: usize,
+    pub stream_running: bool,
+    pub broadcast_queue: usize,
+    pub vessels: usize,
+}
+
+impl AisMetrics {
+    pub fn received(&self, upstream: &str) {
+        let now = unix_millis(SystemTime::now());
+        self.upstream(upstream, |counters| {
+            counters.received += 1;
+            counters.last_message_at = Some(now);
+        });
+    }
+
+    pub fn set_connected(&self, upstream: &str, connected: bool) {
+        self.upstream(upstream, |counters| counters.connected = connected);
+    }
+
+    pub fn reconnected(&self, upstream: &str) {
+        self.upstream(upstream, |counters| counters.reconnects += 1);
+    }
+
+    // What is known of `upstream`; all zero before it was first tried.
+    pub fn upstream_counters(&self, upstream: &str) -> UpstreamCounters {
+        let upstreams = self.upstreams.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        upstreams.get(upstream).copied().unwrap_or_default()
+    }
+
+    pub fn broadcast(&self) {
+        self.broadcast.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn duplicate(&self) {
+        self.duplicates.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn downsampled(&self) {
+        self.downsampled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn lagged(&self, skipped: u64) {
+        self.lagged.fetch_add(skipped, Ordering::Relaxed);
+    }
+
+    pub fn rate_limited(&self) {
+        self.rate_limited.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn set_store_pending(&self, pending: usize) {
+        self.store_pending.store(pending as u64, Ordering::Relaxed);
+    }
+
+    fn upstream(&self, upstream: &str, update: impl FnOnce(&mut UpstreamCounters)) {
+        let mut upstreams = self.upstreams.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match upstreams.get_mut(upstream) {
+            Some(counters) => update(counters),
+            None => update(upstreams.entry(upstream.to_string()).or_default()),
+        }
+    }
+
+    // Everything in the Prometheus text format.
+    pub fn render(&self, gauges: StreamGauges) -> String {
+        let upstreams = self.upstreams.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+        let mut out = String::new();
+
+        header(&mut out, "ais_upstream_messages_total", "counter", "AIS reports received per upstream, duplicates included");
+        for (upstream, counters) in &upstreams {
+            let _ = writeln!(out, "ais_upstream_messages_total{{upstream=\"{}\"}} {}", escape(upstream), counters.received);
+        }
+        header(&mut out, "ais_upstream_reconnects_total", "counter", "Times an upstream connection was lost or refused");
+        for (upstream, counters) in &upstreams {
+            let _ = writeln!(out, "ais_upstream_reconnects_total{{upstream=\"{}\"}} {}", escape(upstream), counters.reconnects);
+        }
+        header(&mut out, "ais_upstream_connected", "gauge", "Whether an upstream is connected");
+        for (upstream, counters) in &upstreams {
+            let _ = writeln!(out, "ais_upstream_connected{{upstream=\"{}\"}} {}", escape(upstream), u64::from(counters.connected));
+        }
+
+        let counters = [
+            ("ais_messages_broadcast_total", "AIS reports sent out on the stream", &self.broadcast),
+            ("ais_messages_duplicate_total", "AIS reports dropped as already heard from another upstream", &self.duplicates),
+            ("ais_messages_downsampled_total", "Position reports held back by per-vessel downsampling", &self.downsampled),
+            ("ais_broadcast_lagged_total", "AIS reports missed by receivers that fell behind", &self.lagged),
+            ("ais_client_rate_limited_total", "AIS reports held back from clients over their rate limit", &self.rate_limited),
+        ];
+        for (name, help, value) in counters {
+            header(&mut out, name, "counter", help);
+            let _ = writeln!(out, "{} {}", name, value.load(Ordering::Relaxed));
+        }
+
+        let gauges = [
+            ("ais_clients_connected", "Stream clients connected", gauges.clients as u64),
+            ("ais_clients_max", "Stream clients served at most", gauges.max_clients as u64),
+            ("ais_stream_running", "Whether the upstreams are connected for clients", u64::from(gauges.stream_running)),
+            ("ais_broadcast_queue_depth", "AIS reports queued for the slowest receiver", gauges.broadcast_queue as u64),
+            ("ais_store_queue_depth", "AIS reports waiting to be written to the database", self.store_pending.load(Ordering::Relaxed)),
+            ("ais_vessels_known", "Vessels with cached static data", gauges.vessels as u64),
+        ];
+        for (name, help, value) in gauges {
+            header(&mut out, name, "gauge", help);
+            let _ = writeln!(out, "{} {}", name, value);
+        }
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+// A label value with `\`, `"` and newlines escaped.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
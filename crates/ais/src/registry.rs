@@ -0,0 +1,178 @@
+use crate::ais::AisResponse;
+use crate::store::unix_millis;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+// Distances from the position reference point to the hull, in metres.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VesselDimensions {
+    pub to_bow: u16,
+    pub to_stern: u16,
+    pub to_port: u16,
+    pub to_starboard: u16,
+}
+
+// What static reports (types 5 and 24) said about a vessel.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct VesselRecord {
+    pub ship_name: Option<String>,
+    pub callsign: Option<String>,
+    pub ship_type: Option<String>,
+    pub dimensions: Option<VesselDimensions>,
+    /// Milliseconds since the Unix epoch
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Default)]
+struct RegistryState {
+    vessels: HashMap<String, VesselRecord>,
+    /// MMSIs whose record changed since the store last took them
+    dirty: HashSet<String>,
+}
+
+// Static data per MMSI, built from the stream. Position reports rarely carry
+// a name, so they are filled in from here before going out.
+#[derive(Debug, Default)]
+pub struct VesselRegistry {
+    state: Mutex<RegistryState>,
+}
+
+impl VesselRegistry {
+    // A registry starting from previously stored records.
+    pub fn from_records(records: impl IntoIterator<Item = (String, VesselRecord)>) -> Self {
+        Self {
+            state: Mutex::new(RegistryState {
+                vessels: records.into_iter().collect(),
+                dirty: HashSet::new(),
+            }),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().vessels.len()
+    }
+
+    pub fn get(&self, mmsi: &str) -> Option<VesselRecord> {
+        self.lock().vessels.get(mmsi).cloned()
+    }
+
+    // Record the static data `report` carries, then fill in what it lacks
+    // from what is known about the vessel.
+    pub fn enrich(&self, report: &mut AisResponse) {
+        let Some(mmsi) = report.mmsi.clone() else {
+            return;
+        };
+        let mut state = self.lock();
+        let record = state.vessels.entry(mmsi.clone()).or_default();
+
+        let mut changed = false;
+        fn learn<T: Clone + PartialEq>(known: &mut Option<T>, reported: &Option<T>, changed: &mut bool) {
+            if reported.is_some() && known != reported {
+                *known = reported.clone();
+                *changed = true;
+            }
+        }
+        let non_blank = |value: &Option<String>| value.clone().filter(|value| !value.is_empty());
+        learn(&mut record.ship_name, &non_blank(&report.ship_name), &mut changed);
+        learn(&mut record.callsign, &non_blank(&report.callsign), &mut changed);
+        learn(&mut record.ship_type, &report.ship_type, &mut changed);
+        learn(&mut record.dimensions, &report.dimensions, &mut changed);
+
+        fn fill<T: Clone>(reported: &mut Option<T>, known: &Option<T>) {
+            if reported.is_none() {
+                *reported = known.clone();
+            }
+        }
+        fill(&mut report.ship_name, &record.ship_name);
+        fill(&mut report.callsign, &record.callsign);
+        fill(&mut report.ship_type, &record.ship_type);
+        fill(&mut report.dimensions, &record.dimensions);
+
+        if changed {
+            record.updated_at = unix_millis(SystemTime::now());
+            state.dirty.insert(mmsi);
+        } else if *record == VesselRecord::default() {
+            // Nothing known yet; do not keep an empty record for every vessel heard
+            state.vessels.remove(&mmsi);
+        }
+    }
+
+    // Records changed since the last call, for the store to write.
+    pub fn take_dirty(&self) -> Vec<(String, VesselRecord)> {
+        let mut state = self.lock();
+        let dirty = std::mem::take(&mut state.dirty);
+        dirty
+            .into_iter()
+            .filter_map(|mmsi| state.vessels.get(&mmsi).cloned().map(|record| (mmsi, record)))
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, RegistryState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn report(mmsi: &str) -> AisResponse {
+        AisResponse {
+            message_type: Some("PositionReport".to_string()),
+            mmsi: Some(mmsi.to_string()),
+            ship_name: None,
+            latitude: Some(33.7),
+            longitude: Some(-118.2),
+            timestamp: None,
+            speed_over_ground: Some(12.0),
+            course_over_ground: None,
+            heading: None,
+            navigation_status: None,
+            ship_type: None,
+            callsign: None,
+            dimensions: None,
+            own_ship: false,
+            source: None,
+            raw_message: Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_static_reports_enrich_positions() {
+        let registry = VesselRegistry::default();
+        let mut unknown = report("111111111");
+        registry.enrich(&mut unknown);
+        assert_eq!(unknown.ship_name, None);
+        assert!(registry.lock().vessels.is_empty());
+
+        // Class B static data arrives in two parts
+        let mut part_a = report("222222222");
+        part_a.latitude = None;
+        part_a.ship_name = Some("SEA BREEZE".to_string());
+        let mut part_b = part_a.clone();
+        part_b.ship_name = None;
+        part_b.callsign = Some("WDA1234".to_string());
+        part_b.ship_type = Some("Sailing".to_string());
+        part_b.dimensions = Some(VesselDimensions { to_bow: 8, to_stern: 4, to_port: 2, to_starboard: 2 });
+        registry.enrich(&mut part_a);
+        registry.enrich(&mut part_b);
+        assert_eq!(part_b.ship_name.as_deref(), Some("SEA BREEZE"));
+
+        let mut position = report("222222222");
+        registry.enrich(&mut position);
+        assert_eq!(position.ship_name.as_deref(), Some("SEA BREEZE"));
+        assert_eq!(position.callsign.as_deref(), Some("WDA1234"));
+        assert_eq!(position.ship_type.as_deref(), Some("Sailing"));
+        assert_eq!(position.dimensions.map(|d| d.to_bow + d.to_stern), Some(12));
+
+        let dirty = registry.take_dirty();
+        assert_eq!(dirty.len(), 1);
+        assert!(dirty[0].1.updated_at > 0);
+        // Repeating what is already known changes nothing
+        registry.enrich(&mut position);
+        assert!(registry.take_dirty().is_empty());
+    }
+}
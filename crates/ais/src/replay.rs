@@ -0,0 +1,168 @@
+use crate::ais::AisResponse;
+use crate::upstream::{aisstream_response, process_nmea_sentence, utc_timestamp, UpstreamMerger};
+use datalink_provider::AisFragmentAssembler;
+use serde_json::Value;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/// Replay speed unless configured otherwise; 1 is real time
+pub const DEFAULT_REPLAY_SPEED: f64 = 1.0;
+
+/// Wait after a line the capture gives no time for, in capture seconds
+const UNTIMED_GAP: Duration = Duration::from_millis(100);
+
+/// Longest wait between lines, so quiet spells in a capture do not stall a demo
+const MAX_GAP: Duration = Duration::from_secs(10);
+
+// Feed the capture at `path` into `merger`, keeping its timing scaled by
+// `speed`. Lines are aisstream JSON messages, reports as recorded from /ws,
+// or NMEA sentences, optionally after a `\c:<unix time>*hh\` tag block.
+// Reports go out stamped with the time they are replayed, so the capture
+// looks live to clients, the store and the deduplication alike.
+pub(crate) async fn replay_capture(
+    path: &Path,
+    speed: f64,
+    merger: &UpstreamMerger,
+    source: &str,
+) -> std::io::Result<()> {
+    let file = tokio::fs::File::open(path).await?;
+    println!("Replaying AIS capture {} at {}x.", path.display(), speed);
+    merger.set_connected(source, true);
+    let mut lines = BufReader::new(file).lines();
+    let mut assembler = AisFragmentAssembler::new();
+    let mut previous = None;
+    while let Some(line) = lines.next_line().await? {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (captured_at, entry) = parse_line(line);
+        let gap = match (previous, captured_at) {
+            (Some(previous), Some(captured_at)) => Duration::try_from_secs_f64(captured_at - previous).unwrap_or_default(),
+            (_, None) => UNTIMED_GAP,
+            (None, Some(_)) => Duration::ZERO,
+        };
+        previous = captured_at.or(previous);
+        tokio::time::sleep(gap.div_f64(speed).min(MAX_GAP)).await;
+
+        match entry {
+            Entry::Report(mut report) => {
+                report.timestamp = Some(utc_timestamp(SystemTime::now()));
+                merger.publish(*report, source);
+            }
+            Entry::Sentence(sentence) => process_nmea_sentence(sentence, &mut assembler, merger, source),
+        }
+    }
+    println!("AIS capture {} finished.", path.display());
+    Ok(())
+}
+
+enum Entry<'a> {
+    Report(Box<AisResponse>),
+    Sentence(&'a str),
+}
+
+// A capture line and the Unix time it was captured at, if it says.
+fn parse_line(line: &str) -> (Option<f64>, Entry<'_>) {
+    if let Ok(message) = serde_json::from_str::<Value>(line) {
+        // Reports recorded from aisstream carry their time in the metadata
+        let (report, captured_at) = if message.get("MessageType").is_some() {
+            let captured_at = message.pointer("/MetaData/time_utc").and_then(Value::as_str).and_then(parse_utc_timestamp);
+            match aisstream_response(&message, SystemTime::now()) {
+                Some(report) => (report, captured_at),
+                None => return (None, Entry::Sentence(line)),
+            }
+        } else {
+            match serde_json::from_value::<AisResponse>(message) {
+                Ok(report) => {
+                    let captured_at = report.timestamp.as_deref().and_then(parse_utc_timestamp);
+                    (report, captured_at)
+                }
+                Err(_) => return (None, Entry::Sentence(line)),
+            }
+        };
+        return (captured_at, Entry::Report(Box::new(report)));
+    }
+    // A tag block: \c:1672338152,s:receiver*hh\!AIVDM,...
+    if let Some((tags, sentence)) = line.strip_prefix('\\').and_then(|rest| rest.split_once('\\')) {
+        let captured_at = tags
+            .split('*')
+            .next()
+            .unwrap_or_default()
+            .split(',')
+            .find_map(|tag| tag.strip_prefix("c:"))
+            .and_then(|time| time.parse::<f64>().ok())
+            // Some receivers stamp milliseconds
+            .map(|time| if time > 1e11 { time / 1000.0 } else { time });
+        return (captured_at, Entry::Sentence(sentence));
+    }
+    (None, Entry::Sentence(line))
+}
+
+// Unix seconds of an aisstream timestamp, "2022-12-29 18:22:32.318353 +0000 UTC".
+fn parse_utc_timestamp(timestamp: &str) -> Option<f64> {
+    let (date, time) = timestamp.split_once(' ')?;
+    let mut date = date.split('-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+    let time = time.split(' ').next()?;
+    let mut time = time.split(':');
+    let (hour, minute) = (time.next()?.parse::<i64>().ok()?, time.next()?.parse::<i64>().ok()?);
+    let second = time.next()?.parse::<f64>().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Days since the epoch from the civil date (Howard Hinnant's algorithm)
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+    Some((days * 86_400 + hour * 3_600 + minute * 60) as f64 + second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::UNIX_EPOCH;
+    use tokio::sync::broadcast;
+
+    #[tokio::test]
+    async fn test_capture_replayed_with_its_timing() {
+        let received = UNIX_EPOCH + Duration::from_micros(1_672_338_152_318_353);
+        let stamped = utc_timestamp(received);
+        assert_eq!(parse_utc_timestamp(&stamped), Some(1_672_338_152.318353));
+
+        let capture = [
+            "# harbour approach, recorded from aisstream".to_string(),
+            serde_json::json!({
+                "MessageType": "PositionReport",
+                "MetaData": {"MMSI": 366123456, "time_utc": stamped},
+                "Message": {"PositionReport": {"UserID": 366123456, "Latitude": 37.8, "Longitude": -122.4}}
+            })
+            .to_string(),
+            "\\c:1672338154*5A\\!AIVDM,1,1,,B,15M67FC000G?ufbE`FepT@3n00Sa,0*5C".to_string(),
+        ];
+        let path = std::env::temp_dir().join(format!("ais-replay-{}.txt", std::process::id()));
+        std::fs::write(&path, capture.join("\n")).unwrap();
+
+        let (tx, mut rx) = broadcast::channel(4);
+        let merger = UpstreamMerger::new(tx, None, Arc::default(), Arc::default());
+        let started = tokio::time::Instant::now();
+        // Two seconds of capture at 20x
+        replay_capture(&path, 20.0, &merger, "replay").await.unwrap();
+        let elapsed = started.elapsed();
+        std::fs::remove_file(&path).unwrap();
+        assert!(elapsed >= Duration::from_millis(80) && elapsed < Duration::from_secs(1), "{:?}", elapsed);
+
+        let first = rx.try_recv().unwrap();
+        assert_eq!(first.mmsi.as_deref(), Some("366123456"));
+        assert_ne!(first.timestamp.as_deref(), Some(stamped.as_str()), "restamped as replayed");
+        let second = rx.try_recv().unwrap();
+        assert_eq!(second.message_type.as_deref(), Some("PositionReport"));
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use crate::ais::{AisResponse, WebSocketBoundingBox};
+use rstar::primitives::GeomWithData;
+use rstar::{RTree, AABB};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Vessels not heard from for this long drop out of bounding-box queries
+pub const VESSEL_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Vessels indexed before stale entries are swept
+const SWEEP_THRESHOLD: usize = 50_000;
+
+// A vessel's position as (longitude, latitude), keyed by MMSI.
+type IndexedPoint = GeomWithData<[f64; 2], String>;
+
+#[derive(Debug)]
+struct LatestReport {
+    report: AisResponse,
+    point: IndexedPoint,
+    at: Instant,
+}
+
+#[derive(Debug, Default)]
+struct IndexState {
+    tree: RTree<IndexedPoint>,
+    latest: HashMap<String, LatestReport>,
+}
+
+// The latest position report of every vessel on the stream, in an R-tree
+// for answering bounding-box queries without scanning them all.
+#[derive(Debug, Default)]
+pub struct VesselIndex {
+    state: Mutex<IndexState>,
+}
+
+impl VesselIndex {
+    // Index `report` if it places a vessel; static reports are already
+    // folded into position reports by the registry.
+    pub fn update(&self, report: &AisResponse) {
+        let now = Instant::now();
+        let (Some(mmsi), Some(latitude), Some(longitude)) = (&report.mmsi, report.latitude, report.longitude) else {
+            return;
+        };
+        let point = IndexedPoint::new([longitude, latitude], mmsi.clone());
+        let mut state = self.lock();
+        if state.latest.len() >= SWEEP_THRESHOLD && !state.latest.contains_key(mmsi) {
+            state.sweep(now);
+        }
+        if let Some(previous) = state.latest.remove(mmsi) {
+            state.tree.remove(&previous.point);
+        }
+        state.tree.insert(point.clone());
+        state.latest.insert(
+            mmsi.clone(),
+            LatestReport {
+                report: report.clone(),
+                point,
+                at: now,
+            },
+        );
+    }
+
+    // Latest reports of the vessels within `bbox` heard from recently.
+    pub fn query(&self, bbox: &WebSocketBoundingBox) -> Vec<AisResponse> {
+        let now = Instant::now();
+        let envelope = AABB::from_corners([bbox.sw_lon, bbox.sw_lat], [bbox.ne_lon, bbox.ne_lat]);
+        let state = self.lock();
+        state
+            .tree
+            .locate_in_envelope(&envelope)
+            .filter_map(|point| state.latest.get(&point.data))
+            .filter(|latest| now.duration_since(latest.at) < VESSEL_TIMEOUT)
+            .map(|latest| latest.report.clone())
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, IndexState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl IndexState {
+    fn sweep(&mut self, now: Instant) {
+        let stale: Vec<String> = self
+            .latest
+            .iter()
+            .filter(|(_, latest)| now.duration_since(latest.at) >= VESSEL_TIMEOUT)
+            .map(|(mmsi, _)| mmsi.clone())
+            .collect();
+        for mmsi in stale {
+            if let Some(latest) = self.latest.remove(&mmsi) {
+                self.tree.remove(&latest.point);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(mmsi: &str, latitude: f64, longitude: f64) -> AisResponse {
+        AisResponse {
+            message_type: Some("PositionReport".to_string()),
+            mmsi: Some(mmsi.to_string()),
+            ship_name: None,
+            latitude: Some(latitude),
+            longitude: Some(longitude),
+            timestamp: None,
+            speed_over_ground: None,
+            course_over_ground: None,
+            heading: None,
+            navigation_status: None,
+            ship_type: None,
+            callsign: None,
+            dimensions: None,
+            own_ship: false,
+            source: None,
+            raw_message: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_index_answers_bounding_box_with_latest_positions() {
+        let index = VesselIndex::default();
+        index.update(&report("111111111", 33.7, -118.2));
+        index.update(&report("222222222", 40.0, -118.2));
+        // Moves out of the box; only its latest position counts
+        index.update(&report("333333333", 33.8, -118.3));
+        index.update(&report("333333333", 34.5, -118.3));
+        let mut static_report = report("444444444", 0.0, 0.0);
+        static_report.latitude = None;
+        index.update(&static_report);
+
+        let bbox = WebSocketBoundingBox::parse("33.6,-118.5,33.9,-118.0").unwrap();
+        let found = index.query(&bbox);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].mmsi.as_deref(), Some("111111111"));
+        assert_eq!(index.lock().latest.len(), 3);
+        assert_eq!(index.lock().tree.size(), 3);
+    }
+}
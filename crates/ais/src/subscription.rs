@@ -0,0 +1,220 @@
+use crate::ais::{is_within_bounding_box, AisResponse, WebSocketBoundingBox};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Vessels whose ship type and navigation status a client connection remembers
+const MAX_KNOWN_VESSELS: usize = 50_000;
+
+/// Named areas one client may watch at once
+pub const MAX_AREAS: usize = 16;
+
+// A bounding box a client watches under a name, e.g. "harbor entrance".
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WatchArea {
+    pub name: String,
+    pub bounding_box: WebSocketBoundingBox,
+}
+
+// What a stream client asked for. Empty sets do not filter; a report must
+// pass every filter that is set.
+#[derive(Debug, Default)]
+pub struct Subscription {
+    /// An unnamed box; with named areas too, a report must lie in any one
+    pub bounding_box: Option<WebSocketBoundingBox>,
+    areas: Vec<WatchArea>,
+    mmsis: HashSet<String>,
+    /// Ship type descriptions, lower case
+    ship_types: HashSet<String>,
+    /// Navigation statuses, lower case
+    navigation_statuses: HashSet<String>,
+    /// Latest ship type and navigation status per MMSI. Ship types come with
+    /// static reports and statuses with position reports, so each kind of
+    /// report is matched on what the other kind said.
+    known: HashMap<String, (Option<String>, Option<String>)>,
+}
+
+impl Subscription {
+    // Replace the MMSI, ship type and navigation status filters.
+    pub fn set_filters(&mut self, mmsis: Vec<String>, ship_types: Vec<String>, navigation_statuses: Vec<String>) {
+        let normalized = |values: Vec<String>| -> HashSet<String> {
+            values
+                .into_iter()
+                .map(|value| value.trim().to_lowercase())
+                .filter(|value| !value.is_empty())
+                .collect()
+        };
+        self.mmsis = normalized(mmsis);
+        self.ship_types = normalized(ship_types);
+        self.navigation_statuses = normalized(navigation_statuses);
+    }
+
+    pub fn clear_filters(&mut self) {
+        self.set_filters(Vec::new(), Vec::new(), Vec::new());
+    }
+
+    // Replace the named areas; past MAX_AREAS they are left out.
+    pub fn set_areas(&mut self, areas: Vec<WatchArea>) {
+        self.areas.clear();
+        for area in areas {
+            self.add_area(area);
+        }
+    }
+
+    // Watch `area` too, replacing one of the same name. False when the
+    // client already watches MAX_AREAS others.
+    pub fn add_area(&mut self, area: WatchArea) -> bool {
+        match self.areas.iter().position(|watched| watched.name == area.name) {
+            Some(index) => self.areas[index] = area,
+            None if self.areas.len() < MAX_AREAS => self.areas.push(area),
+            None => return false,
+        }
+        true
+    }
+
+    pub fn remove_area(&mut self, name: &str) {
+        self.areas.retain(|area| area.name != name);
+    }
+
+    // Names of the areas `data` lies in, or None if the client should not
+    // get it at all.
+    pub fn matching_areas(&self, data: &AisResponse) -> Option<Vec<String>> {
+        if !self.matches(data) {
+            return None;
+        }
+        let names = self
+            .areas
+            .iter()
+            .filter(|area| is_within_bounding_box(data, &area.bounding_box))
+            .map(|area| area.name.clone())
+            .collect();
+        Some(names)
+    }
+
+    pub fn has_filters(&self) -> bool {
+        !(self.mmsis.is_empty() && self.ship_types.is_empty() && self.navigation_statuses.is_empty())
+    }
+
+    // Note the ship type and status a report gives for its vessel. Every
+    // report on the stream should pass through here, sent or not.
+    pub fn observe(&mut self, data: &AisResponse) {
+        if data.ship_type.is_none() && data.navigation_status.is_none() {
+            return;
+        }
+        let Some(mmsi) = &data.mmsi else {
+            return;
+        };
+        if self.known.len() >= MAX_KNOWN_VESSELS && !self.known.contains_key(mmsi) {
+            self.known.clear();
+        }
+        let (ship_type, navigation_status) = self.known.entry(mmsi.clone()).or_default();
+        if let Some(value) = &data.ship_type {
+            *ship_type = Some(value.to_lowercase());
+        }
+        if let Some(value) = &data.navigation_status {
+            *navigation_status = Some(value.to_lowercase());
+        }
+    }
+
+    // Whether `data` should go to the client.
+    pub fn matches(&self, data: &AisResponse) -> bool {
+        if self.bounding_box.is_some() || !self.areas.is_empty() {
+            let in_box = self.bounding_box.as_ref().is_some_and(|bbox| is_within_bounding_box(data, bbox));
+            if !in_box && !self.areas.iter().any(|area| is_within_bounding_box(data, &area.bounding_box)) {
+                return false;
+            }
+        }
+        if !self.has_filters() {
+            return true;
+        }
+        let Some(mmsi) = &data.mmsi else {
+            return false;
+        };
+        if !self.mmsis.is_empty() && !self.mmsis.contains(mmsi) {
+            return false;
+        }
+        let (ship_type, navigation_status) = self.known.get(mmsi).cloned().unwrap_or_default();
+        let matches = |wanted: &HashSet<String>, own: &Option<String>, known: Option<String>| {
+            wanted.is_empty()
+                || own
+                    .as_deref()
+                    .map(str::to_lowercase)
+                    .or(known)
+                    .is_some_and(|value| wanted.contains(&value))
+        };
+        matches(&self.ship_types, &data.ship_type, ship_type)
+            && matches(&self.navigation_statuses, &data.navigation_status, navigation_status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(mmsi: &str, ship_type: Option<&str>, navigation_status: Option<&str>) -> AisResponse {
+        AisResponse {
+            message_type: None,
+            mmsi: Some(mmsi.to_string()),
+            ship_name: None,
+            latitude: Some(33.7),
+            longitude: Some(-118.2),
+            timestamp: None,
+            speed_over_ground: None,
+            course_over_ground: None,
+            heading: None,
+            navigation_status: navigation_status.map(str::to_string),
+            ship_type: ship_type.map(str::to_string),
+            callsign: None,
+            dimensions: None,
+            own_ship: false,
+            source: None,
+            raw_message: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_filters_combine_across_report_kinds() {
+        let mut subscription = Subscription::default();
+        let tanker_static = report("111111111", Some("Tanker"), None);
+        let tanker_moored = report("111111111", None, Some("Moored"));
+        let cargo_under_way = report("222222222", None, Some("Under way using engine"));
+        assert!(subscription.matches(&tanker_moored));
+
+        subscription.set_filters(Vec::new(), vec!["tanker".to_string()], Vec::new());
+        // Position reports carry no ship type until a static report named it
+        assert!(!subscription.matches(&tanker_moored));
+        for data in [&tanker_static, &tanker_moored, &cargo_under_way] {
+            subscription.observe(data);
+        }
+        assert!(subscription.matches(&tanker_static));
+        assert!(subscription.matches(&tanker_moored));
+        assert!(!subscription.matches(&cargo_under_way));
+
+        subscription.set_filters(Vec::new(), vec!["Tanker".to_string()], vec!["under way using engine".to_string()]);
+        assert!(!subscription.matches(&tanker_moored));
+        assert!(!subscription.matches(&tanker_static), "the tanker's last status is moored");
+
+        subscription.set_filters(vec!["222222222".to_string()], Vec::new(), Vec::new());
+        assert!(subscription.matches(&cargo_under_way));
+        assert!(!subscription.matches(&tanker_moored));
+
+        subscription.clear_filters();
+        subscription.bounding_box = WebSocketBoundingBox::parse("34.0,-119.0,35.0,-118.0");
+        assert!(!subscription.matches(&cargo_under_way));
+
+        // Named areas widen what passes and tag it
+        let area = |name: &str, bbox: &str| WatchArea {
+            name: name.to_string(),
+            bounding_box: WebSocketBoundingBox::parse(bbox).unwrap(),
+        };
+        subscription.set_areas(vec![area("harbor", "33.6,-118.5,33.9,-118.0"), area("offshore", "33.0,-119.0,34.0,-118.1")]);
+        assert_eq!(subscription.matching_areas(&cargo_under_way), Some(vec!["harbor".to_string(), "offshore".to_string()]));
+        subscription.remove_area("harbor");
+        assert_eq!(subscription.matching_areas(&cargo_under_way), Some(vec!["offshore".to_string()]));
+        subscription.remove_area("offshore");
+        assert_eq!(subscription.matching_areas(&cargo_under_way), None);
+        for i in 0..MAX_AREAS {
+            assert!(subscription.add_area(area(&format!("waypoint {}", i), "0,0,1,1")));
+        }
+        assert!(!subscription.add_area(area("one too many", "0,0,1,1")));
+    }
+}
@@ -0,0 +1,61 @@
+use serde::Serialize;
+
+/// Mean Earth radius used to project track points, in metres
+const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+// A fix on a vessel's track.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct TrackPoint {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Milliseconds since the Unix epoch
+    pub received_at: i64,
+}
+
+// Drop points that lie within `tolerance_m` of the line through their
+// neighbours (Douglas-Peucker), keeping the first and last point.
+pub fn simplify(points: &[TrackPoint], tolerance_m: f64) -> Vec<TrackPoint> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+
+    // Equirectangular projection about the track's mean latitude: accurate
+    // to well under a metre over the few tens of miles a track spans.
+    let mean_latitude = points.iter().map(|point| point.latitude).sum::<f64>() / points.len() as f64;
+    let x_scale = EARTH_RADIUS_M * mean_latitude.to_radians().cos();
+    let projected: Vec<(f64, f64)> = points
+        .iter()
+        .map(|point| (point.longitude.to_radians() * x_scale, point.latitude.to_radians() * EARTH_RADIUS_M))
+        .collect();
+
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+    // Iterative to stay clear of the stack on long tracks
+    let mut spans = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = spans.pop() {
+        let farthest = (first + 1..last)
+            .map(|index| (index, distance_to_segment(projected[index], projected[first], projected[last])))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((index, distance)) = farthest {
+            if distance > tolerance_m {
+                keep[index] = true;
+                spans.push((first, index));
+                spans.push((index, last));
+            }
+        }
+    }
+
+    points.iter().zip(keep).filter(|(_, keep)| *keep).map(|(point, _)| *point).collect()
+}
+
+fn distance_to_segment(point: (f64, f64), start: (f64, f64), end: (f64, f64)) -> f64 {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq == 0.0 {
+        0.0
+    } else {
+        (((point.0 - start.0) * dx + (point.1 - start.1) * dy) / length_sq).clamp(0.0, 1.0)
+    };
+    (point.0 - (start.0 + t * dx)).hypot(point.1 - (start.1 + t * dy))
+}
@@ -0,0 +1,509 @@
+use crate::ais::{get_ship_type_description, AisResponse};
+use crate::collision::Traffic;
+use crate::downsample::Downsampler;
+use crate::metrics::AisMetrics;
+use crate::registry::{VesselDimensions, VesselRegistry};
+use crate::replay::replay_capture;
+use crate::spatial::VesselIndex;
+use datalink::{DataLinkConfig, DataLinkReceiver, DataLinkStatus};
+use datalink_provider::{aisstream_report, AisDataLinkProvider, AisFragmentAssembler, AisReport, AisStreamIoProvider};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::broadcast;
+use tokio::time::Instant;
+use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+use tokio_util::sync::CancellationToken;
+
+/// How long a report is remembered to drop copies of it from other upstreams
+pub const DEDUPLICATION_WINDOW: Duration = Duration::from_secs(60);
+
+/// Wait before reconnecting to an upstream that failed or closed
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Largest UDP datagram read from a receiver
+const MAX_DATAGRAM: usize = 65_536;
+
+/// How often reports queued by the aisstream.io datalink are taken
+const AISSTREAM_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// Where AIS reports come from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Upstream {
+    /// aisstream.io, subscribed with the API key
+    AisStream { api_key: String },
+    /// A local receiver sending NMEA `!AIVDM` sentences over TCP, at `host:port`
+    NmeaTcp { address: String },
+    /// A local receiver broadcasting NMEA sentences over UDP, received on `address`
+    NmeaUdp { address: String },
+    /// Another server's JSON report stream, e.g. `ws://relay:3000/ws`
+    Relay { url: String },
+    /// A recorded capture played back at `speed` times real time, over and over
+    Replay { path: PathBuf, speed: f64 },
+}
+
+impl Upstream {
+    // Parse `aisstream`, `tcp://host:port`, `udp://bind:port` or a ws(s) URL.
+    // The API key is only needed for aisstream.
+    pub fn parse(spec: &str, api_key: Option<&str>) -> Result<Self, String> {
+        let spec = spec.trim();
+        if spec == "aisstream" {
+            return api_key
+                .map(|api_key| Upstream::AisStream { api_key: api_key.to_string() })
+                .ok_or_else(|| "aisstream needs an API key".to_string());
+        }
+        match spec.split_once("://") {
+            Some(("tcp", address)) if !address.is_empty() => Ok(Upstream::NmeaTcp { address: address.to_string() }),
+            Some(("udp", address)) if !address.is_empty() => Ok(Upstream::NmeaUdp { address: address.to_string() }),
+            Some(("ws" | "wss", _)) if url::Url::parse(spec).is_ok() => Ok(Upstream::Relay { url: spec.to_string() }),
+            _ => Err(spec.to_string()),
+        }
+    }
+}
+
+// The source attribution put on reports from the upstream. Keys and tokens
+// in relay URLs are left out.
+impl fmt::Display for Upstream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Upstream::AisStream { .. } => write!(f, "aisstream"),
+            Upstream::NmeaTcp { address } => write!(f, "tcp://{}", address),
+            Upstream::NmeaUdp { address } => write!(f, "udp://{}", address),
+            Upstream::Relay { url } => match url::Url::parse(url) {
+                Ok(mut url) => {
+                    url.set_query(None);
+                    let _ = url.set_password(None);
+                    let _ = url.set_username("");
+                    write!(f, "{}", url)
+                }
+                Err(_) => write!(f, "relay"),
+            },
+            Upstream::Replay { path, .. } => write!(f, "replay://{}", path.display()),
+        }
+    }
+}
+
+// Joins the reports of all upstreams into the broadcast stream. A vessel's
+// report heard by several upstreams goes out once, from whichever was first,
+// with the vessel's static data filled in.
+pub struct UpstreamMerger {
+    tx: broadcast::Sender<AisResponse>,
+    own_mmsi: Option<String>,
+    vessels: Arc<VesselRegistry>,
+    metrics: Arc<AisMetrics>,
+    downsampler: Option<Downsampler>,
+    traffic: Option<Arc<Traffic>>,
+    positions: Option<Arc<VesselIndex>>,
+    /// When each (MMSI, report kind, timestamp to the second) was first seen
+    seen: Mutex<HashMap<(String, bool, String), Instant>>,
+}
+
+impl UpstreamMerger {
+    pub fn new(
+        tx: broadcast::Sender<AisResponse>,
+        own_mmsi: Option<String>,
+        vessels: Arc<VesselRegistry>,
+        metrics: Arc<AisMetrics>,
+    ) -> Self {
+        Self {
+            tx,
+            own_mmsi,
+            vessels,
+            metrics,
+            downsampler: None,
+            traffic: None,
+            positions: None,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Hold back position reports so each vessel sends at most one per
+    // `interval`, unless it turns or changes speed.
+    pub fn with_downsampling(mut self, interval: Option<Duration>) -> Self {
+        self.downsampler = interval.map(Downsampler::new);
+        self
+    }
+
+    // Keep `traffic` up to date with every report that goes out.
+    pub fn with_traffic(mut self, traffic: Arc<Traffic>) -> Self {
+        self.traffic = Some(traffic);
+        self
+    }
+
+    // Keep `positions` up to date with every report that goes out.
+    pub fn with_index(mut self, positions: Arc<VesselIndex>) -> Self {
+        self.positions = Some(positions);
+        self
+    }
+
+    // Broadcast `report` from `source` unless another upstream already sent
+    // it or it is downsampled away. Returns whether it went out.
+    pub fn publish(&self, mut report: AisResponse, source: &str) -> bool {
+        self.metrics.received(source);
+        if self.is_duplicate(&report, Instant::now()) {
+            self.metrics.duplicate();
+            return false;
+        }
+        if self.own_mmsi.is_some() && report.mmsi.as_deref() == self.own_mmsi.as_deref() {
+            report.own_ship = true;
+        }
+        if self.downsampler.as_ref().is_some_and(|downsampler| !downsampler.admit(&report)) {
+            self.metrics.downsampled();
+            return false;
+        }
+        if report.own_ship {
+            println!(
+                "Own ship report ({}) from {}: {:?} at {:?}, {:?}",
+                report.mmsi.as_deref().unwrap_or_default(),
+                source,
+                report.message_type,
+                report.latitude,
+                report.longitude
+            );
+        }
+        report.source = Some(source.to_string());
+        self.vessels.enrich(&mut report);
+        if let Some(traffic) = &self.traffic {
+            traffic.observe(&report);
+        }
+        if let Some(positions) = &self.positions {
+            positions.update(&report);
+        }
+        self.metrics.broadcast();
+        // The broadcast send will fail if there are no receivers, which is fine.
+        let _ = self.tx.send(report);
+        true
+    }
+
+    // Note that `source` is connected (or no longer is).
+    pub fn set_connected(&self, source: &str, connected: bool) {
+        self.metrics.set_connected(source, connected);
+    }
+
+    // Reports without an MMSI or timestamp cannot be matched and always pass.
+    fn is_duplicate(&self, report: &AisResponse, now: Instant) -> bool {
+        let (Some(mmsi), Some(timestamp)) = (&report.mmsi, &report.timestamp) else {
+            return false;
+        };
+        // Sources agree on the second but not on the fraction
+        let second = timestamp.get(..19).unwrap_or(timestamp);
+        let key = (mmsi.clone(), report.latitude.is_some(), second.to_string());
+
+        let mut seen = self.seen.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        seen.retain(|_, first_seen| now.duration_since(*first_seen) < DEDUPLICATION_WINDOW);
+        if seen.contains_key(&key) {
+            return true;
+        }
+        seen.insert(key, now);
+        false
+    }
+}
+
+// Keep `upstream` connected and its reports flowing into `merger` until
+// cancelled.
+pub(crate) async fn run_upstream(upstream: Upstream, merger: Arc<UpstreamMerger>, cancellation_token: CancellationToken) {
+    let source = upstream.to_string();
+    loop {
+        let result = tokio::select! {
+            _ = cancellation_token.cancelled() => {
+                println!("Cancellation signal received. Disconnecting from {}.", source);
+                merger.set_connected(&source, false);
+                return;
+            }
+            result = connect_and_process(&upstream, &source, &merger, &cancellation_token) => result,
+        };
+        merger.set_connected(&source, false);
+        match result {
+            Err(e) => eprintln!("AIS upstream {} error: {}. Reconnecting in 5 seconds...", source, e),
+            Ok(()) if cancellation_token.is_cancelled() => return,
+            Ok(()) => println!("AIS upstream {} closed. Reconnecting in 5 seconds...", source),
+        }
+        merger.metrics.reconnected(&source);
+        // If the connection drops, wait before retrying, but still listen for cancellation.
+        tokio::select! {
+            _ = tokio::time::sleep(RECONNECT_DELAY) => {},
+            _ = cancellation_token.cancelled() => return,
+        }
+    }
+}
+
+async fn connect_and_process(
+    upstream: &Upstream,
+    source: &str,
+    merger: &UpstreamMerger,
+    cancellation_token: &CancellationToken,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    match upstream {
+        Upstream::AisStream { api_key } => {
+            // Reconnecting is left to `run_upstream`, like the other upstreams
+            let mut config = DataLinkConfig::new("aisstream".to_string()).with_parameter("api_key".to_string(), api_key.clone());
+            config.auto_reconnect = false;
+            let mut aisstream = AisStreamIoProvider::new();
+            DataLinkReceiver::connect(&mut aisstream, &config)?;
+            let mut poll = tokio::time::interval(AISSTREAM_POLL_INTERVAL);
+            loop {
+                poll.tick().await;
+                while let Some(message) = aisstream.receive_message()? {
+                    let report = serde_json::from_slice::<Value>(&message.payload)
+                        .ok()
+                        .and_then(|json| aisstream_response(&json, message.received_at));
+                    if let Some(report) = report {
+                        merger.publish(report, source);
+                    }
+                }
+                match DataLinkReceiver::status(&aisstream) {
+                    DataLinkStatus::Connected => merger.set_connected(source, true),
+                    DataLinkStatus::Error(e) => return Err(e.into()),
+                    DataLinkStatus::Connecting | DataLinkStatus::Disconnected => {}
+                }
+            }
+        }
+        Upstream::NmeaTcp { address } => {
+            let stream = TcpStream::connect(address).await?;
+            println!("Connected to AIS receiver at {}.", address);
+            merger.set_connected(source, true);
+            let mut lines = BufReader::new(stream).lines();
+            let mut assembler = AisFragmentAssembler::new();
+            while let Some(line) = lines.next_line().await? {
+                process_nmea_sentence(&line, &mut assembler, merger, source);
+            }
+            Ok(())
+        }
+        Upstream::NmeaUdp { address } => {
+            let socket = UdpSocket::bind(address).await?;
+            println!("Listening for AIS sentences on udp://{}.", address);
+            merger.set_connected(source, true);
+            let mut buffer = vec![0; MAX_DATAGRAM];
+            let mut assembler = AisFragmentAssembler::new();
+            loop {
+                let (length, _) = socket.recv_from(&mut buffer).await?;
+                for line in String::from_utf8_lossy(&buffer[..length]).lines() {
+                    process_nmea_sentence(line, &mut assembler, merger, source);
+                }
+            }
+        }
+        Upstream::Replay { path, speed } => Ok(replay_capture(path, *speed, merger, source).await?),
+        Upstream::Relay { url } => {
+            let (ws_stream, _) = connect_async(url.as_str()).await?;
+            println!("Connected to AIS relay {}.", source);
+            merger.set_connected(source, true);
+            let (mut sender, mut receiver) = ws_stream.split();
+            loop {
+                tokio::select! {
+                    message = receiver.next() => match message {
+                        Some(Ok(Message::Text(text))) => {
+                            // The relay also sends status lines, which are not reports
+                            if let Ok(report) = serde_json::from_str::<AisResponse>(&text) {
+                                merger.publish(report, source);
+                            }
+                        }
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => return Err(e.into()),
+                        None => return Ok(()),
+                    },
+                    _ = cancellation_token.cancelled() => {
+                        let _ = sender.send(Message::Close(None)).await;
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn process_nmea_sentence(line: &str, assembler: &mut AisFragmentAssembler, merger: &UpstreamMerger, source: &str) {
+    let Some(sentence) = AisDataLinkProvider::parse_ais_sentence(line.trim()) else {
+        return;
+    };
+    if let Some(report) = assembler.push(&sentence) {
+        let mut report = nmea_report(report, line.trim(), SystemTime::now());
+        report.own_ship = sentence.get_bool("own_ship").unwrap_or(false);
+        merger.publish(report, source);
+    }
+}
+
+// A report decoded from NMEA sentences, in the shape aisstream reports take.
+// Receivers do not time their sentences, so the timestamp is when it came in.
+pub(crate) fn nmea_report(report: AisReport, sentence: &str, received: SystemTime) -> AisResponse {
+    decoded_report(report, serde_json::json!({ "sentence": sentence }), received)
+}
+
+// An aisstream report, decoded by the aisstream.io datalink. `None` for the
+// report types it does not decode. The name and time come from the metadata
+// aisstream adds to every report.
+pub(crate) fn aisstream_response(json: &Value, received: SystemTime) -> Option<AisResponse> {
+    let report = aisstream_report(json)?;
+    let metadata = json.get("MetaData");
+    let mut response = decoded_report(report, json.clone(), received);
+    response.message_type = json.get("MessageType").and_then(Value::as_str).map(str::to_string);
+    if let Some(time) = metadata.and_then(|m| m.get("time_utc")).and_then(Value::as_str) {
+        response.timestamp = Some(time.to_string());
+    }
+    if response.ship_name.is_none() {
+        response.ship_name = metadata
+            .and_then(|m| m.get("ShipName"))
+            .and_then(Value::as_str)
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty());
+    }
+    Some(response)
+}
+
+fn decoded_report(report: AisReport, raw_message: Value, received: SystemTime) -> AisResponse {
+    let mut response = AisResponse {
+        message_type: None,
+        mmsi: Some(report.mmsi().to_string()),
+        ship_name: None,
+        latitude: None,
+        longitude: None,
+        timestamp: Some(utc_timestamp(received)),
+        speed_over_ground: None,
+        course_over_ground: None,
+        heading: None,
+        navigation_status: None,
+        ship_type: None,
+        callsign: None,
+        dimensions: None,
+        own_ship: false,
+        source: None,
+        raw_message,
+    };
+    match report {
+        AisReport::Position { latitude, longitude, speed_kts, course_deg, heading_deg, .. } => {
+            response.message_type = Some("PositionReport".to_string());
+            response.latitude = latitude;
+            response.longitude = longitude;
+            response.speed_over_ground = speed_kts.map(f64::from);
+            response.course_over_ground = course_deg.map(f64::from);
+            response.heading = heading_deg.map(f64::from);
+        }
+        AisReport::Static { name, callsign, ship_type, dimensions, .. } => {
+            response.message_type = Some("ShipStaticData".to_string());
+            response.ship_name = name.map(|name| name.trim().to_string());
+            response.callsign = callsign.map(|callsign| callsign.trim().to_string());
+            response.dimensions = dimensions.map(|dimensions| VesselDimensions {
+                to_bow: dimensions.to_bow,
+                to_stern: dimensions.to_stern,
+                to_port: dimensions.to_port.into(),
+                to_starboard: dimensions.to_starboard.into(),
+            });
+            response.ship_type = ship_type.map(|ship_type| get_ship_type_description(ship_type.into()).to_string());
+        }
+    }
+    response
+}
+
+// `time` as aisstream writes it: "2022-12-29 18:22:32.318353 +0000 UTC".
+pub(crate) fn utc_timestamp(time: SystemTime) -> String {
+    let elapsed = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = elapsed.as_secs();
+    let (days, second_of_day) = ((seconds / 86_400) as i64, seconds % 86_400);
+
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:06} +0000 UTC",
+        year,
+        month,
+        day,
+        second_of_day / 3_600,
+        second_of_day / 60 % 60,
+        second_of_day % 60,
+        elapsed.subsec_micros()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_merged_across_upstreams() {
+        assert_eq!(
+            Upstream::parse("tcp://192.168.1.20:10110", None),
+            Ok(Upstream::NmeaTcp { address: "192.168.1.20:10110".to_string() })
+        );
+        assert!(Upstream::parse("aisstream", None).is_err());
+        assert!(Upstream::parse("serial:///dev/ttyUSB0", None).is_err());
+        let relay = Upstream::parse("wss://relay.example/ws?token=s3cret", None).unwrap();
+        assert_eq!(relay.to_string(), "wss://relay.example/ws");
+
+        let received = UNIX_EPOCH + Duration::from_micros(1_672_338_152_318_353);
+        assert_eq!(utc_timestamp(received), "2022-12-29 18:22:32.318353 +0000 UTC");
+
+        let (tx, mut rx) = broadcast::channel(16);
+        let merger = UpstreamMerger::new(tx, Some("366123456".to_string()), Arc::default(), Arc::default());
+        let sentence = "!AIVDM,1,1,,B,15M67FC000G?ufbE`FepT@3n00Sa,0*5C";
+        let message = AisDataLinkProvider::parse_ais_sentence(sentence).unwrap();
+        let decoded = AisFragmentAssembler::new().push(&message).unwrap();
+        let local = nmea_report(decoded, sentence, received);
+        assert_eq!(local.message_type.as_deref(), Some("PositionReport"));
+        assert!(local.latitude.is_some());
+
+        // The relay heard the same transmission, stamped a little later
+        let mut relayed = local.clone();
+        relayed.timestamp = Some("2022-12-29 18:22:32.901 +0000 UTC".to_string());
+        let mut own = local.clone();
+        own.mmsi = Some("366123456".to_string());
+
+        assert!(merger.publish(local.clone(), "tcp://192.168.1.20:10110"));
+        assert!(!merger.publish(relayed, "wss://relay.example/ws"));
+        assert!(merger.publish(own, "aisstream"));
+
+        let first = rx.try_recv().unwrap();
+        assert_eq!(first.mmsi, local.mmsi);
+        assert_eq!(first.source.as_deref(), Some("tcp://192.168.1.20:10110"));
+        assert!(!first.own_ship);
+        let second = rx.try_recv().unwrap();
+        assert!(second.own_ship);
+        assert_eq!(second.source.as_deref(), Some("aisstream"));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_aisstream_reports_decoded_by_datalink() {
+        let received = UNIX_EPOCH + Duration::from_secs(1_672_574_400);
+        let position = aisstream_response(&serde_json::json!({
+            "MessageType": "PositionReport",
+            "MetaData": {"MMSI": 123456789, "ShipName": "TEST VESSEL  ", "time_utc": "2023-01-01 12:00:00.000000 +0000 UTC"},
+            "Message": {"PositionReport": {"UserID": 123456789, "Latitude": 33.7, "Longitude": -118.3, "Sog": 12.5, "Cog": 180.0, "TrueHeading": 175}}
+        }), received)
+        .unwrap();
+        assert_eq!(position.message_type.as_deref(), Some("PositionReport"));
+        assert_eq!(position.mmsi.as_deref(), Some("123456789"));
+        assert_eq!(position.ship_name.as_deref(), Some("TEST VESSEL"));
+        assert_eq!((position.latitude, position.longitude), (Some(33.7), Some(-118.3)));
+        assert_eq!(position.timestamp.as_deref(), Some("2023-01-01 12:00:00.000000 +0000 UTC"));
+        assert_eq!(position.speed_over_ground, Some(12.5));
+        assert_eq!(position.heading, Some(175.0));
+
+        let static_data = aisstream_response(&serde_json::json!({
+            "MessageType": "StaticDataReport",
+            "MetaData": {"MMSI": 987654321, "ShipName": "CARGO SHIP"},
+            "Message": {"StaticDataReport": {"UserID": 987654321, "ReportB": {"ShipType": 70, "CallSign": "WDA1234"}}}
+        }), received)
+        .unwrap();
+        assert_eq!(static_data.message_type.as_deref(), Some("StaticDataReport"));
+        assert_eq!(static_data.ship_name.as_deref(), Some("CARGO SHIP"));
+        assert_eq!(static_data.ship_type.as_deref(), Some("Cargo"));
+        assert_eq!(static_data.callsign.as_deref(), Some("WDA1234"));
+        assert_eq!(static_data.timestamp, Some(utc_timestamp(received)));
+
+        assert!(aisstream_response(&serde_json::json!({}), received).is_none());
+    }
+}
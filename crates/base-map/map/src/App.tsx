@@ -6,12 +6,14 @@ import MapNext from "@/MapNext.tsx";
 import {getNeumorphicColors, getNeumorphicStyle} from './theme/neumorphic-theme';
 import {layers, LayerSelector} from "@/LayerSelector.tsx";
 import {useAISProvider, type VesselData} from './ais-provider';
-import type {GpsPosition, VesselStatus} from './types';
+import {useAnnotations} from './annotations-provider';
+import type {Annotation, GpsPosition, VesselStatus} from './types';
 import {GpsFeed} from "@/components/map/GpsFeedInfo.tsx";
 import {AisFeed} from './components/map/AisFeedInfo';
 import {Search} from "@/components/map/Search.tsx";
 import {SearchResult} from "@/components/map/SearchResult.tsx";
 import {NativeGeolocation} from "@/CustomGeolocate.ts";
+import {usePositionChannel} from './position-provider';
 
 // public key
 const key =
@@ -180,6 +182,27 @@ function App() {
     // Vessel position state
     const [vesselPosition, setVesselPosition] = useState<VesselStatus | null>(null);
 
+    // Own-ship fixes pushed by the base-map server, which may also centre the map
+    const {position: pushedPosition, center: centerRequest} = usePositionChannel();
+    useEffect(() => {
+        if (!pushedPosition) return;
+        setVesselPosition(previous => ({
+            latitude: pushedPosition.lat,
+            longitude: pushedPosition.lon,
+            heading: pushedPosition.heading ?? previous?.heading ?? 0,
+            // m/s to knots
+            speed: pushedPosition.speed != null ? pushedPosition.speed * 1.943844 : previous?.speed ?? 0,
+        }));
+    }, [pushedPosition]);
+    useEffect(() => {
+        if (!centerRequest) return;
+        setMapView(view => ({
+            latitude: centerRequest.lat,
+            longitude: centerRequest.lon,
+            zoom: centerRequest.zoom ?? view.zoom,
+        }));
+    }, [centerRequest]);
+
     // AIS state management
     const [aisEnabled, setAisEnabled] = useState(false);
     const [boundingBox, _setBoundingBox] = useState<{
@@ -198,6 +221,23 @@ function App() {
         connectionStatus
     } = useAISProvider(aisEnabled ? boundingBox : undefined);
 
+    // Chart annotations, placed by clicking the map after choosing a kind
+    const {annotations, saveAnnotation, deleteAnnotation} = useAnnotations();
+    const [placing, setPlacing] = useState<Annotation['type'] | null>(null);
+
+    const handleMapClick = useCallback(async (latitude: number, longitude: number) => {
+        if (!placing) return;
+        const label = `${placing.toUpperCase()} ${annotations.length + 1}`;
+        const base = {id: 0, latitude, longitude, label};
+        const annotation: Annotation =
+            placing === 'mark' ? {...base, type: 'mark', symbol: 'flag'}
+                : placing === 'hazard' ? {...base, type: 'hazard', radius_m: 100}
+                    : placing === 'bridge' ? {...base, type: 'bridge', clearance_m: 10}
+                        : {...base, type: 'note', text: ''};
+        await saveAnnotation(annotation);
+        setPlacing(null);
+    }, [placing, annotations.length, saveAnnotation]);
+
 
     const selectSearchResult = useCallback(async (searchResult: { lat: string, lon: string }) => {
         // Navigate to the selected location with zoom
@@ -371,6 +411,18 @@ function App() {
                 >
                     <Text>AIS {aisEnabled ? 'ON' : 'OFF'}</Text>
                 </Button>
+                {(['mark', 'hazard', 'note', 'bridge'] as const).map(kind => (
+                    <Button
+                        key={kind}
+                        size="sm"
+                        variant="surface"
+                        onClick={() => setPlacing(placing === kind ? null : kind)}
+                        {...getNeumorphicStyle(colorMode as 'light' | 'dark')}
+                        bg={placing === kind ? 'orange.500' : undefined}
+                    >
+                        <Text>{kind.toUpperCase()}</Text>
+                    </Button>
+                ))}
                 <LayerSelector onClick={handleLayerChange}/>
             </HStack>
             <MapNext
@@ -383,6 +435,10 @@ function App() {
                 onVesselClick={setVesselPopup}
                 vesselPopup={vesselPopup}
                 onVesselPopupClose={() => setVesselPopup(null)}
+                annotations={annotations}
+                onAnnotationSave={saveAnnotation}
+                onAnnotationDelete={deleteAnnotation}
+                onMapClick={handleMapClick}
             />
         </Box>
     );
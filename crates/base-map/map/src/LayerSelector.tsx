@@ -4,9 +4,15 @@ import {useColorMode} from './components/ui/color-mode';
 import {useState} from "react";
 import {getNeumorphicColors, getNeumorphicStyle} from './theme/neumorphic-theme';
 
-export const layers = [
+export const layers: Layers = [
     { name: 'Standard', value: 'mapbox://styles/mapbox/dark-v11' },
     { name: 'Satellite', value: 'mapbox://styles/mapbox/satellite-v9' },
+    // Official raster charts quilted by the base-map server, over the standard style
+    { name: 'Charts', value: 'mapbox://styles/mapbox/dark-v11', overlay: 'http://localhost:8080/charts/tiles/{z}/{x}/{y}' },
+    // GRIB forecasts rendered by the base-map server for the current hour
+    { name: 'Wind', value: 'mapbox://styles/mapbox/dark-v11', overlay: 'http://localhost:8080/weather/tiles/wind/{z}/{x}/{y}' },
+    { name: 'Pressure', value: 'mapbox://styles/mapbox/dark-v11', overlay: 'http://localhost:8080/weather/tiles/pressure/{z}/{x}/{y}' },
+    { name: 'Waves', value: 'mapbox://styles/mapbox/dark-v11', overlay: 'http://localhost:8080/weather/tiles/waves/{z}/{x}/{y}' },
 ];
 
 
@@ -24,7 +30,7 @@ export const layers = [
 // };
 
 
-export type Layer = { name: string; value: string };
+export type Layer = { name: string; value: string; overlay?: string };
 export type Layers = Layer[];
 
 // interface MapViewParams {
@@ -64,9 +70,9 @@ export function LayerSelector(props: { onClick: (layer: Layer) => Promise<void>
                     >
                         {layers.map(layer => (
                             <Menu.Item
-                                key={layer.value}
+                                key={layer.name}
                                 id={layer.value}
-                                value={layer.value}
+                                value={layer.name}
                                 borderRadius={6}
                                 transition="all 0.2s ease-in-out"
                                 _hover={{
@@ -6,13 +6,17 @@ import Map, {
     FullscreenControl,
     ScaleControl,
     GeolocateControl,
+    Source,
+    Layer,
     type MapRef
 } from 'react-map-gl/mapbox';
 
 import ControlPanel from './control-panel.tsx';
 import Pin from './pin.tsx';
 import VesselMarker from './vessel-marker';
+import AnnotationLayer from './annotation-layer';
 import type { VesselData } from './ais-provider';
+import type { Annotation } from './types';
 
 import PORTS from './test_data/nautical-base-data.json';
 import {Box} from "@chakra-ui/react";
@@ -39,6 +43,10 @@ interface MapNextProps {
     onVesselClick?: (vessel: VesselData) => void;
     vesselPopup?: VesselData | null;
     onVesselPopupClose?: () => void;
+    annotations?: Annotation[];
+    onAnnotationSave?: (annotation: Annotation) => void;
+    onAnnotationDelete?: (id: number) => void;
+    onMapClick?: (latitude: number, longitude: number) => void;
 }
 
 export default function MapNext(props: MapNextProps) {
@@ -105,6 +113,18 @@ export default function MapNext(props: MapNextProps) {
         }
     };
 
+    // Six-minute heading vectors of the moving vessels
+    const vesselVectors = useMemo(() => ({
+        type: 'FeatureCollection' as const,
+        features: (props.aisVessels || [])
+            .filter(vessel => vessel.vector)
+            .map(vessel => ({
+                type: 'Feature' as const,
+                properties: {mmsi: vessel.mmsi},
+                geometry: {type: 'LineString' as const, coordinates: vessel.vector!},
+            })),
+    }), [props.aisVessels]);
+
     // Create vessel markers
     const vesselMarkers = useMemo(() => 
         (props.aisVessels || []).map((vessel) => (
@@ -145,6 +165,7 @@ export default function MapNext(props: MapNextProps) {
                 mapStyle={props.layer?.value || "mapbox://styles/mapbox/standard"}
                 mapboxAccessToken={props.mapboxPublicKey}
                 style={{position: "fixed", width: '100%', height: '100%', bottom: 0, top: 0, left: 0, right: 0}}
+                onClick={e => props.onMapClick && props.onMapClick(e.lngLat.lat, e.lngLat.lng)}
             >
                 <GeolocateControl 
                     showUserHeading={true} 
@@ -159,8 +180,23 @@ export default function MapNext(props: MapNextProps) {
                 <NavigationControl position="top-left" />
                 <ScaleControl />
 
+                {props.layer?.overlay && (
+                    <Source key={props.layer.overlay} id="layer-overlay" type="raster" tiles={[props.layer.overlay]} tileSize={256}>
+                        <Layer id="layer-overlay" type="raster" />
+                    </Source>
+                )}
+
+                <Source id="vessel-vectors" type="geojson" data={vesselVectors}>
+                    <Layer id="vessel-vectors" type="line" paint={{'line-color': '#ffffff', 'line-width': 1.5, 'line-dasharray': [2, 1]}} />
+                </Source>
+
                 {pins}
                 {vesselMarkers}
+                <AnnotationLayer
+                    annotations={props.annotations || []}
+                    onSave={annotation => props.onAnnotationSave && props.onAnnotationSave(annotation)}
+                    onDelete={id => props.onAnnotationDelete && props.onAnnotationDelete(id)}
+                />
 
                 {/* Vessel Popup */}
                 {props.vesselPopup && (
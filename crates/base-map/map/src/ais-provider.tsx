@@ -15,23 +15,26 @@ export interface VesselData {
     callSign: string;
     destination?: string;
     eta?: string;
+    /** Where the vessel will be in six minutes, as a [[lon, lat], [lon, lat]] line */
+    vector?: [number, number][];
     lastUpdate: Date;
 }
 
-// AIS service response structure (matching Rust AisResponse)
-interface AisResponse {
-    message_type?: string;
-    mmsi?: string;
-    ship_name?: string;
-    latitude?: number;
-    longitude?: number;
-    timestamp?: string;
-    speed_over_ground?: number;
-    course_over_ground?: number;
-    heading?: number;
-    navigation_status?: string;
-    ship_type?: string;
-    raw_message: any;
+// A vessel Feature from base-map's /overlays/ais feed
+interface AisTargetFeature {
+    type: 'Feature';
+    geometry: { type: 'Point'; coordinates: [number, number] };
+    properties: {
+        kind: 'vessel';
+        mmsi: string;
+        name?: string | null;
+        ship_type?: string | null;
+        /** Knots */
+        speed?: number | null;
+        course?: number | null;
+        heading?: number | null;
+        vector?: [number, number][] | null;
+    };
 }
 
 // Bounding box for AIS queries
@@ -42,39 +45,33 @@ interface BoundingBox {
     ne_lon: number;
 }
 
-// WebSocket message types for communication with the backend
-interface WebSocketMessage {
-    type: string;
-    bounding_box?: BoundingBox;
-}
+// The overlay feed takes its box as [west, south, east, north]
+const toBbox = (box: BoundingBox): [number, number, number, number] =>
+    [box.sw_lon, box.sw_lat, box.ne_lon, box.ne_lat];
 
-// Convert AIS service response to VesselData format
-const convertAisResponseToVesselData = (aisResponse: AisResponse): VesselData | null => {
-    if ((!aisResponse.raw_message?.MetaData?.MMSI) || !aisResponse.latitude || !aisResponse.longitude) {
-        console.log('Skipping vessel with missing data:', {
-            mmsi: aisResponse.mmsi,
-            metadataMSSI: aisResponse.raw_message?.MetaData?.MSSI,
-            latitude: aisResponse.latitude,
-            longitude: aisResponse.longitude,
-            raw: aisResponse.raw_message
-        });
+// Convert an AIS target Feature to VesselData format
+const convertFeatureToVesselData = (feature: AisTargetFeature): VesselData | null => {
+    const {properties, geometry} = feature;
+    if (!properties?.mmsi || geometry?.type !== 'Point') {
         return null;
     }
+    const [longitude, latitude] = geometry.coordinates;
 
     return {
-        id: aisResponse.mmsi ?? aisResponse.raw_message?.MetaData?.MMSI,
-        name: aisResponse.ship_name || `Vessel ${aisResponse.mmsi}`,
-        type: aisResponse.ship_type || 'Unknown',
-        latitude: aisResponse.latitude,
-        longitude: aisResponse.longitude,
-        heading: aisResponse.heading || 0,
-        speed: aisResponse.speed_over_ground || 0,
+        id: properties.mmsi,
+        name: properties.name || `Vessel ${properties.mmsi}`,
+        type: properties.ship_type || 'Unknown',
+        latitude,
+        longitude,
+        heading: properties.heading ?? properties.course ?? 0,
+        speed: properties.speed ?? 0,
         length: 100, // Default length
         width: 20,   // Default width
-        mmsi: aisResponse.mmsi ?? aisResponse.raw_message?.MetaData?.MMSI,
-        callSign: '', 
-        destination: '', 
-        eta: '', 
+        mmsi: properties.mmsi,
+        callSign: '',
+        destination: '',
+        eta: '',
+        vector: properties.vector ?? undefined,
         lastUpdate: new Date()
     };
 };
@@ -168,7 +165,9 @@ export const useAISProvider = (boundingBox?: BoundingBox) => {
                 wsRef.current = null;
             }
 
-            const ws = new WebSocket('ws://localhost:3000/ws');
+            // Targets relayed by the base-map server, which follows the AIS server
+            const query = boundingBox ? `?bbox=${toBbox(boundingBox).join(',')}` : '';
+            const ws = new WebSocket(`ws://localhost:8080/overlays/ais/ws${query}`);
             wsRef.current = ws;
 
             // Set connection timeout with proper cleanup
@@ -201,24 +200,6 @@ export const useAISProvider = (boundingBox?: BoundingBox) => {
                 setError(null);
                 reconnectAttemptsRef.current = 0; // Reset reconnection attempts
 
-                // Send bounding box if available
-                if (boundingBox && isMountedRef.current) {
-                    const message: WebSocketMessage = {
-                        type: 'set_bounding_box',
-                        bounding_box: boundingBox
-                    };
-                    ws.send(JSON.stringify(message));
-                    console.log('[OPEN] Sent bounding box:', boundingBox);
-                }
-
-                // Start AIS stream
-                if (isMountedRef.current) {
-                    const startMessage: WebSocketMessage = {
-                        type: 'start_ais_stream'
-                    };
-                    ws.send(JSON.stringify(startMessage));
-                    console.log('[OPEN] Started AIS stream');
-                }
             };
 
             ws.onmessage = (event) => {
@@ -234,14 +215,13 @@ export const useAISProvider = (boundingBox?: BoundingBox) => {
                         return;
                     }
                     
-                    // Handle JSON status messages
-                    if (typeof data === 'string' || data.type) {
+                    if (data.type !== 'Feature') {
                         console.log('Received message:', data);
                         return;
                     }
 
-                    // Process vessel data
-                    const vesselData = convertAisResponseToVesselData(data);
+                    // Process vessel data; our own ship never appears in the feed
+                    const vesselData = convertFeatureToVesselData(data);
                     if (vesselData) {
                         console.log('Received vessel data:', vesselData);
                         vesselMapRef.current.set(vesselData.mmsi, vesselData);
@@ -330,11 +310,7 @@ export const useAISProvider = (boundingBox?: BoundingBox) => {
     // Update bounding box
     const updateBoundingBox = useCallback((bbox: BoundingBox) => {
         if (wsRef.current?.readyState === WebSocket.OPEN) {
-            const message: WebSocketMessage = {
-                type: 'set_bounding_box',
-                bounding_box: bbox
-            };
-            wsRef.current.send(JSON.stringify(message));
+            wsRef.current.send(JSON.stringify({bbox: toBbox(bbox)}));
             console.log('Updated bounding box:', bbox);
             
             // Clear existing vessels when bounding box changes
@@ -0,0 +1,163 @@
+import {useMemo, useState} from 'react';
+import {Layer, Marker, Popup, Source} from 'react-map-gl/mapbox';
+import type {Annotation, MarkSymbol} from './types';
+
+const SYMBOLS: Record<MarkSymbol, string> = {
+    flag: '⚑',
+    anchorage: '⚓',
+    fuel: '⛽',
+    fish: '🐟',
+    dive: '🤿',
+    danger: '☠',
+};
+
+const EARTH_RADIUS_M = 6371000;
+
+/** Closed ring approximating a circle of `radiusM` meters around a point */
+function circleRing(latitude: number, longitude: number, radiusM: number, steps = 64): number[][] {
+    const latRadius = (radiusM / EARTH_RADIUS_M) * (180 / Math.PI);
+    const lonRadius = latRadius / Math.cos((latitude * Math.PI) / 180);
+    const ring: number[][] = [];
+    for (let i = 0; i <= steps; i++) {
+        const angle = (i / steps) * 2 * Math.PI;
+        ring.push([longitude + lonRadius * Math.cos(angle), latitude + latRadius * Math.sin(angle)]);
+    }
+    return ring;
+}
+
+function glyph(annotation: Annotation): string {
+    switch (annotation.type) {
+        case 'mark':
+            return SYMBOLS[annotation.symbol] ?? SYMBOLS.flag;
+        case 'hazard':
+            return '⚠';
+        case 'note':
+            return '✎';
+        case 'bridge':
+            return '⌒';
+    }
+}
+
+interface AnnotationLayerProps {
+    annotations: Annotation[];
+    onSave: (annotation: Annotation) => void;
+    onDelete: (id: number) => void;
+}
+
+export default function AnnotationLayer({annotations, onSave, onDelete}: AnnotationLayerProps) {
+    const [editing, setEditing] = useState<Annotation | null>(null);
+
+    const hazards = useMemo(() => ({
+        type: 'FeatureCollection' as const,
+        features: annotations.flatMap(annotation =>
+            annotation.type === 'hazard'
+                ? [{
+                    type: 'Feature' as const,
+                    properties: {id: annotation.id},
+                    geometry: {
+                        type: 'Polygon' as const,
+                        coordinates: [circleRing(annotation.latitude, annotation.longitude, annotation.radius_m)],
+                    },
+                }]
+                : []
+        ),
+    }), [annotations]);
+
+    return (
+        <>
+            <Source id="annotation-hazards" type="geojson" data={hazards}>
+                <Layer id="annotation-hazards-fill" type="fill" paint={{'fill-color': '#ff3333', 'fill-opacity': 0.2}}/>
+                <Layer id="annotation-hazards-outline" type="line" paint={{'line-color': '#ff3333', 'line-width': 2}}/>
+            </Source>
+
+            {annotations.map(annotation => (
+                <Marker
+                    key={`annotation-${annotation.id}`}
+                    longitude={annotation.longitude}
+                    latitude={annotation.latitude}
+                    anchor="center"
+                    draggable
+                    onDragEnd={e => onSave({...annotation, latitude: e.lngLat.lat, longitude: e.lngLat.lng})}
+                    onClick={e => {
+                        e.originalEvent.stopPropagation();
+                        setEditing(annotation);
+                    }}
+                >
+                    <div title={annotation.label} style={{cursor: 'pointer', fontSize: 20, textShadow: '0 1px 3px rgba(0,0,0,0.5)'}}>
+                        {glyph(annotation)}
+                    </div>
+                </Marker>
+            ))}
+
+            {editing && (
+                <Popup
+                    longitude={editing.longitude}
+                    latitude={editing.latitude}
+                    anchor="bottom"
+                    closeOnClick={false}
+                    onClose={() => setEditing(null)}
+                >
+                    <div style={{padding: '6px', minWidth: '200px', display: 'flex', flexDirection: 'column', gap: '6px'}}>
+                        <input
+                            value={editing.label}
+                            onChange={e => setEditing({...editing, label: e.target.value})}
+                            placeholder="Label"
+                        />
+                        {editing.type === 'mark' && (
+                            <select
+                                value={editing.symbol}
+                                onChange={e => setEditing({...editing, symbol: e.target.value as MarkSymbol})}
+                            >
+                                {Object.keys(SYMBOLS).map(symbol => (
+                                    <option key={symbol} value={symbol}>{SYMBOLS[symbol as MarkSymbol]} {symbol}</option>
+                                ))}
+                            </select>
+                        )}
+                        {editing.type === 'hazard' && (
+                            <label>
+                                Radius (m){' '}
+                                <input
+                                    type="number"
+                                    min={10}
+                                    step={10}
+                                    value={editing.radius_m}
+                                    onChange={e => setEditing({...editing, radius_m: Number(e.target.value) || 10})}
+                                />
+                            </label>
+                        )}
+                        {editing.type === 'bridge' && (
+                            <label>
+                                Clearance (m){' '}
+                                <input
+                                    type="number"
+                                    min={0}
+                                    step={0.1}
+                                    value={editing.clearance_m}
+                                    onChange={e => setEditing({...editing, clearance_m: Math.max(0, Number(e.target.value) || 0)})}
+                                />
+                            </label>
+                        )}
+                        {editing.type === 'note' && (
+                            <textarea
+                                value={editing.text}
+                                onChange={e => setEditing({...editing, text: e.target.value})}
+                                placeholder="Note"
+                                rows={3}
+                            />
+                        )}
+                        <div style={{display: 'flex', gap: '6px'}}>
+                            <button onClick={() => {
+                                onSave(editing);
+                                setEditing(null);
+                            }}>Save</button>
+                            <button onClick={() => {
+                                onDelete(editing.id);
+                                setEditing(null);
+                            }}>Delete</button>
+                        </div>
+                    </div>
+                </Popup>
+            )}
+        </>
+    );
+}
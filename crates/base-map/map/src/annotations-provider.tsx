@@ -0,0 +1,65 @@
+import {useCallback, useEffect, useState} from 'react';
+import type {Annotation} from './types';
+
+const flurx = (): any => (typeof window !== 'undefined' ? (window as any).__FLURX__ : undefined);
+
+/**
+ * Chart annotations shared with the Bevy app. Edits made in the app's chart
+ * page show up on the next poll; without the app (plain browser) annotations
+ * are kept in memory only.
+ */
+export function useAnnotations(pollIntervalMs = 5000) {
+    const [annotations, setAnnotations] = useState<Annotation[]>([]);
+
+    const refresh = useCallback(async () => {
+        const ipc = flurx();
+        if (!ipc) return;
+        try {
+            const stored: Annotation[] = await ipc.invoke('get_annotations');
+            setAnnotations(stored);
+        } catch (error) {
+            console.error('Failed to get annotations:', error);
+        }
+    }, []);
+
+    useEffect(() => {
+        refresh();
+        const interval = setInterval(refresh, pollIntervalMs);
+        return () => clearInterval(interval);
+    }, [refresh, pollIntervalMs]);
+
+    const saveAnnotation = useCallback(async (annotation: Annotation) => {
+        const ipc = flurx();
+        let saved = annotation;
+        if (ipc) {
+            try {
+                saved = await ipc.invoke('save_annotation', annotation);
+            } catch (error) {
+                console.error('Failed to save annotation:', error);
+                return;
+            }
+        } else if (!annotation.id) {
+            saved = {...annotation, id: Date.now()};
+        }
+        setAnnotations(current => {
+            const others = current.filter(existing => existing.id !== saved.id);
+            return [...others, saved];
+        });
+        return saved;
+    }, []);
+
+    const deleteAnnotation = useCallback(async (id: number) => {
+        const ipc = flurx();
+        if (ipc) {
+            try {
+                await ipc.invoke('delete_annotation', {id});
+            } catch (error) {
+                console.error('Failed to delete annotation:', error);
+                return;
+            }
+        }
+        setAnnotations(current => current.filter(annotation => annotation.id !== id));
+    }, []);
+
+    return {annotations, saveAnnotation, deleteAnnotation, refresh};
+}
@@ -0,0 +1,82 @@
+import {useCallback, useEffect, useRef, useState} from 'react';
+
+// Messages on the base-map server's /ws/position channel
+export interface PositionFix {
+    id: string;
+    lat: number;
+    lon: number;
+    accuracy?: number;
+    /** Degrees true */
+    heading?: number;
+    /** Metres per second */
+    speed?: number;
+    /** Milliseconds since the Unix epoch */
+    timestamp?: number;
+}
+
+export interface CenterRequest {
+    lat: number;
+    lon: number;
+    zoom?: number;
+}
+
+type PositionMessage =
+    | ({ type: 'position' } & PositionFix)
+    | ({ type: 'center' } & CenterRequest);
+
+const POSITION_URL = 'ws://localhost:8080/ws/position';
+const RECONNECT_DELAY_MS = 2000;
+
+// Own-ship position streamed from the backend, and the latest request to
+// centre the map. `publish` sends a fix of our own.
+export function usePositionChannel() {
+    const [position, setPosition] = useState<PositionFix | null>(null);
+    const [center, setCenter] = useState<CenterRequest | null>(null);
+    const [isConnected, setIsConnected] = useState(false);
+    const wsRef = useRef<WebSocket | null>(null);
+
+    useEffect(() => {
+        let closed = false;
+        let retry: ReturnType<typeof setTimeout> | null = null;
+
+        const connect = () => {
+            const ws = new WebSocket(POSITION_URL);
+            wsRef.current = ws;
+            ws.onopen = () => setIsConnected(true);
+            ws.onmessage = event => {
+                try {
+                    const message: PositionMessage = JSON.parse(event.data);
+                    if (message.type === 'position') {
+                        setPosition(message);
+                    } else if (message.type === 'center') {
+                        setCenter({lat: message.lat, lon: message.lon, zoom: message.zoom ?? undefined});
+                    }
+                } catch (error) {
+                    console.error('Invalid position message:', error);
+                }
+            };
+            ws.onclose = () => {
+                setIsConnected(false);
+                if (!closed) {
+                    retry = setTimeout(connect, RECONNECT_DELAY_MS);
+                }
+            };
+        };
+        connect();
+
+        return () => {
+            closed = true;
+            if (retry) clearTimeout(retry);
+            wsRef.current?.close();
+            wsRef.current = null;
+        };
+    }, []);
+
+    const publish = useCallback((fix: PositionFix) => {
+        if (wsRef.current?.readyState === WebSocket.OPEN) {
+            wsRef.current.send(JSON.stringify({type: 'position', ...fix}));
+        }
+    }, []);
+
+    return {position, center, isConnected, publish};
+}
@@ -21,4 +21,22 @@ export interface VesselStatus {
 // interface AuthParams {
 //     authenticated: boolean;
 //     token: string | null;
-// }
\ No newline at end of file
+// }
+
+// Chart annotations (matching systems::Annotation)
+export type MarkSymbol = 'flag' | 'anchorage' | 'fuel' | 'fish' | 'dive' | 'danger';
+
+export type AnnotationKind =
+    | { type: 'mark'; symbol: MarkSymbol }
+    | { type: 'hazard'; radius_m: number }
+    | { type: 'note'; text: string }
+    /** Charted vertical clearance above the chart's clearance datum */
+    | { type: 'bridge'; clearance_m: number };
+
+export type Annotation = {
+    /** 0 until stored */
+    id: number;
+    latitude: number;
+    longitude: number;
+    label: string;
+} & AnnotationKind;
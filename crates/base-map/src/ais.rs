@@ -0,0 +1,327 @@
+use crate::{parse_bbox, EARTH_RADIUS_NM};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use datalink::{DataLinkReceiver, DataMessage};
+use futures_util::StreamExt;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+/// WebSocket feed of the AIS server to follow
+pub const AIS_SERVER_URL_ENV: &str = "BASE_MAP_AIS_URL";
+/// Token for AIS servers that want one
+pub const AIS_TOKEN_ENV: &str = "BASE_MAP_AIS_TOKEN";
+
+/// The AIS server's map feed: the latest report per vessel, once a second
+const DEFAULT_AIS_SERVER_URL: &str = "ws://localhost:3000/ws/map";
+/// Targets not heard from for this long are dropped from the layer
+const TARGET_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// Heading vectors show where a target will be after this long
+const VECTOR_DURATION: Duration = Duration::from_secs(6 * 60);
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// How often an injected datalink receiver is read
+const DATALINK_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const CHANNEL_CAPACITY: usize = 1024;
+/// Targets held before stale ones are swept
+const SWEEP_THRESHOLD: usize = 10_000;
+
+// ===== Targets =====
+// Another vessel as the map draws it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AisTarget {
+    pub mmsi: String,
+    pub name: Option<String>,
+    pub ship_type: Option<String>,
+    pub lat: f64,
+    pub lon: f64,
+    /// Knots
+    pub speed: Option<f64>,
+    /// Degrees true
+    pub course: Option<f64>,
+    pub heading: Option<f64>,
+}
+
+impl AisTarget {
+    // A target from a report of the AIS server; none for our own ship or
+    // reports that place no vessel.
+    pub fn from_report(report: &Value) -> Option<Self> {
+        if report["own_ship"].as_bool() == Some(true) {
+            return None;
+        }
+        let text = |key: &str| report[key].as_str().filter(|value| !value.is_empty()).map(str::to_string);
+        Some(Self {
+            mmsi: text("mmsi")?,
+            name: text("ship_name"),
+            ship_type: text("ship_type"),
+            lat: report["latitude"].as_f64()?,
+            lon: report["longitude"].as_f64()?,
+            speed: report["speed_over_ground"].as_f64(),
+            course: report["course_over_ground"].as_f64(),
+            heading: report["heading"].as_f64(),
+        })
+    }
+
+    // A target from a datalink message carrying a vessel's position.
+    pub fn from_data_message(message: &DataMessage) -> Option<Self> {
+        let (lat, lon) = message.get_lat_lon("position").or_else(|| {
+            Some((message.get_f64("latitude")?, message.get_f64("longitude")?))
+        })?;
+        Some(Self {
+            mmsi: message.get_str("mmsi").unwrap_or(&message.source_id).to_string(),
+            name: message.get_str("vessel_name").map(str::to_string),
+            ship_type: message.get_str("ship_type").map(str::to_string),
+            lat,
+            lon,
+            speed: message.get_f64("speed"),
+            course: message.get_angle("course"),
+            heading: message.get_angle("heading"),
+        })
+    }
+
+    // Where the target will be after VECTOR_DURATION on its course and speed.
+    fn vector_end(&self) -> Option<[f64; 2]> {
+        let (course, speed) = (self.course.or(self.heading)?, self.speed?);
+        if speed <= 0.0 {
+            return None;
+        }
+        let distance = speed * VECTOR_DURATION.as_secs_f64() / 3600.0 / EARTH_RADIUS_NM;
+        let (lat, lon, bearing) = (self.lat.to_radians(), self.lon.to_radians(), course.to_radians());
+        let end_lat = (lat.sin() * distance.cos() + lat.cos() * distance.sin() * bearing.cos()).asin();
+        let end_lon = lon
+            + (bearing.sin() * distance.sin() * lat.cos()).atan2(distance.cos() - lat.sin() * end_lat.sin());
+        Some([end_lon.to_degrees(), end_lat.to_degrees()])
+    }
+
+    // The target as a GeoJSON Point; its heading vector, if moving, is in
+    // the `vector` property as a [[lon, lat], [lon, lat]] line.
+    pub fn to_feature(&self) -> Value {
+        json!({
+            "type": "Feature",
+            "id": self.mmsi,
+            "geometry": {"type": "Point", "coordinates": [self.lon, self.lat]},
+            "properties": {
+                "kind": "vessel",
+                "mmsi": self.mmsi,
+                "name": self.name,
+                "ship_type": self.ship_type,
+                "speed": self.speed,
+                "course": self.course,
+                "heading": self.heading,
+                "vector": self.vector_end().map(|end| [[self.lon, self.lat], end]),
+            },
+        })
+    }
+
+    fn vector_feature(&self) -> Option<Value> {
+        let end = self.vector_end()?;
+        Some(json!({
+            "type": "Feature",
+            "geometry": {"type": "LineString", "coordinates": [[self.lon, self.lat], end]},
+            "properties": {"kind": "vector", "mmsi": self.mmsi},
+        }))
+    }
+
+    fn within(&self, [west, south, east, north]: [f64; 4]) -> bool {
+        (west..=east).contains(&self.lon) && (south..=north).contains(&self.lat)
+    }
+}
+
+// ===== Target layer =====
+// The latest state of every target heard, from the AIS server or an
+// injected receiver, for the map to draw without a second server.
+pub struct AisTargets {
+    targets: Mutex<HashMap<String, (AisTarget, Instant)>>,
+    tx: broadcast::Sender<AisTarget>,
+}
+
+impl Default for AisTargets {
+    fn default() -> Self {
+        Self {
+            targets: Mutex::new(HashMap::new()),
+            tx: broadcast::channel(CHANNEL_CAPACITY).0,
+        }
+    }
+}
+
+impl AisTargets {
+    pub fn update(&self, target: AisTarget) {
+        let now = Instant::now();
+        let mut targets = self.lock();
+        if targets.len() >= SWEEP_THRESHOLD && !targets.contains_key(&target.mmsi) {
+            targets.retain(|_, (_, heard)| now.duration_since(*heard) < TARGET_TIMEOUT);
+        }
+        targets.insert(target.mmsi.clone(), (target.clone(), now));
+        drop(targets);
+        let _ = self.tx.send(target);
+    }
+
+    // Targets heard recently, within `bounds` if given.
+    pub fn snapshot(&self, bounds: Option<[f64; 4]>) -> Vec<AisTarget> {
+        let now = Instant::now();
+        self.lock()
+            .values()
+            .filter(|(_, heard)| now.duration_since(*heard) < TARGET_TIMEOUT)
+            .map(|(target, _)| target)
+            .filter(|target| bounds.is_none_or(|bounds| target.within(bounds)))
+            .cloned()
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, (AisTarget, Instant)>> {
+        self.targets.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+// ===== Feeds =====
+// The AIS server's feed named by BASE_MAP_AIS_URL, with BASE_MAP_AIS_TOKEN
+// when set.
+pub fn ais_server_url() -> String {
+    let url = std::env::var(AIS_SERVER_URL_ENV).unwrap_or_else(|_| DEFAULT_AIS_SERVER_URL.to_string());
+    match std::env::var(AIS_TOKEN_ENV) {
+        Ok(token) => {
+            let separator = if url.contains('?') { '&' } else { '?' };
+            format!("{url}{separator}token={token}")
+        }
+        Err(_) => url,
+    }
+}
+
+// Follow the AIS server at `url`, reconnecting whenever it goes away.
+pub async fn follow_ais_server(targets: Arc<AisTargets>, url: String) {
+    loop {
+        match tokio_tungstenite::connect_async(url.as_str()).await {
+            Ok((mut stream, _)) => {
+                tracing::info!("Following AIS server {url}");
+                while let Some(Ok(message)) = stream.next().await {
+                    let tokio_tungstenite::tungstenite::Message::Text(text) = message else {
+                        continue;
+                    };
+                    // Status lines and alerts are not reports
+                    let Ok(report) = serde_json::from_str::<Value>(&text) else {
+                        continue;
+                    };
+                    if report.get("type").is_none() {
+                        if let Some(target) = AisTarget::from_report(&report) {
+                            targets.update(target);
+                        }
+                    }
+                }
+                tracing::warn!("AIS server {url} closed the feed");
+            }
+            Err(err) => tracing::warn!("Could not reach AIS server {url}: {err}"),
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+// Feed the layer from `receiver`, for a backend that already has a datalink
+// to an AIS receiver rather than an AIS server to follow.
+pub fn spawn_datalink_feed(targets: Arc<AisTargets>, mut receiver: Box<dyn DataLinkReceiver>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut poll = tokio::time::interval(DATALINK_POLL_INTERVAL);
+        loop {
+            poll.tick().await;
+            match receiver.receive_all_messages() {
+                Ok(messages) => {
+                    for target in messages.iter().filter_map(AisTarget::from_data_message) {
+                        targets.update(target);
+                    }
+                }
+                Err(err) => tracing::warn!("Reading AIS datalink failed: {err}"),
+            }
+        }
+    })
+}
+
+// ===== /overlays/ais handlers =====
+#[derive(Deserialize, Debug)]
+pub struct OverlayQuery {
+    /// Area as "west,south,east,north"
+    bbox: Option<String>,
+}
+
+impl OverlayQuery {
+    fn bounds(&self) -> Result<Option<[f64; 4]>, &'static str> {
+        match &self.bbox {
+            Some(bbox) => parse_bbox(bbox).map(Some).ok_or("bbox must be west,south,east,north"),
+            None => Ok(None),
+        }
+    }
+}
+
+// GET /overlays/ais: the targets as a FeatureCollection of vessel points
+// and heading-vector lines.
+pub async fn ais_overlay(State(targets): State<Arc<AisTargets>>, Query(query): Query<OverlayQuery>) -> Response {
+    let bounds = match query.bounds() {
+        Ok(bounds) => bounds,
+        Err(err) => return (StatusCode::BAD_REQUEST, err).into_response(),
+    };
+    let features: Vec<Value> = targets
+        .snapshot(bounds)
+        .iter()
+        .flat_map(|target| std::iter::once(target.to_feature()).chain(target.vector_feature()))
+        .collect();
+    Json(json!({"type": "FeatureCollection", "features": features})).into_response()
+}
+
+#[derive(Deserialize, Debug)]
+struct SetBounds {
+    /// [west, south, east, north], or null for everywhere
+    bbox: Option<[f64; 4]>,
+}
+
+// GET /overlays/ais/ws: the targets in `?bbox=` now, then each update as a
+// vessel Feature. Sending {"bbox": [w, s, e, n]} moves the box.
+pub async fn ais_overlay_socket(
+    ws: WebSocketUpgrade,
+    State(targets): State<Arc<AisTargets>>,
+    Query(query): Query<OverlayQuery>,
+) -> Response {
+    let bounds = match query.bounds() {
+        Ok(bounds) => bounds,
+        Err(err) => return (StatusCode::BAD_REQUEST, err).into_response(),
+    };
+    ws.on_upgrade(move |socket| stream_targets(socket, targets, bounds))
+}
+
+async fn stream_targets(mut socket: WebSocket, targets: Arc<AisTargets>, mut bounds: Option<[f64; 4]>) {
+    let mut updates = targets.tx.subscribe();
+    let mut backlog = targets.snapshot(bounds);
+    loop {
+        for target in backlog.drain(..) {
+            if send_target(&mut socket, &target).await.is_err() {
+                return;
+            }
+        }
+        tokio::select! {
+            received = socket.recv() => match received {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<SetBounds>(&text) {
+                    Ok(set) => {
+                        bounds = set.bbox;
+                        backlog = targets.snapshot(bounds);
+                    }
+                    Err(err) => tracing::warn!("Ignoring AIS overlay message {text}: {err}"),
+                },
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+            update = updates.recv() => match update {
+                Ok(target) if bounds.is_none_or(|bounds| target.within(bounds)) => backlog.push(target),
+                Ok(_) => {}
+                // The next update of each target brings it up to date
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+        }
+    }
+}
+
+async fn send_target(socket: &mut WebSocket, target: &AisTarget) -> Result<(), axum::Error> {
+    socket.send(Message::Text(target.to_feature().to_string())).await
+}
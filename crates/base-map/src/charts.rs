@@ -0,0 +1,174 @@
+use crate::parse_bbox;
+use crate::tiles::{tile_y, TileCache};
+use axum::body::Bytes;
+use axum::extract::{Path as UrlPath, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use image::{imageops, ImageFormat, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::Arc;
+
+/// JSON file listing the raster charts to quilt
+pub const CHART_CATALOG_ENV: &str = "BASE_MAP_CHART_CATALOG";
+
+/// Charts more detailed than this many times the scale the map is drawn at
+/// are left out; their detail would not show and would cost a fetch each
+const DETAIL_LIMIT: f64 = 8.0;
+/// Most charts layered into one tile, the most detailed kept
+const MAX_QUILT_CHARTS: usize = 8;
+const TILE_SIZE: u32 = 256;
+/// Scale denominator of zoom 0 at the equator, for 0.28 mm screen pixels
+const ZOOM_0_SCALE: f64 = 559_082_264.0;
+
+// ===== Chart catalog =====
+// One official raster chart (a NOAA RNC or equivalent) published as tiles.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Chart {
+    pub id: String,
+    pub title: String,
+    /// Scale denominator, 80000 for a 1:80,000 chart
+    pub scale: u32,
+    /// Coverage as [west, south, east, north] in degrees
+    pub bounds: [f64; 4],
+    /// Tile server for the chart, with `{z}`, `{x}` and `{y}` placeholders
+    pub tiles: String,
+}
+
+impl Chart {
+    fn covers(&self, [west, south, east, north]: [f64; 4]) -> bool {
+        self.bounds[0] < east && west < self.bounds[2] && self.bounds[1] < north && south < self.bounds[3]
+    }
+}
+
+// ===== Quilting =====
+// Raster charts mosaicked into one layer: for each tile the charts that
+// cover it are drawn from the smallest scale up, so the most detailed chart
+// available shows wherever it has coverage.
+pub struct ChartQuilt {
+    charts: Vec<Chart>,
+    tiles: Arc<TileCache>,
+}
+
+impl ChartQuilt {
+    // A quilt of `charts`, fetched through and cached by `tiles`.
+    pub fn new(charts: Vec<Chart>, tiles: Arc<TileCache>) -> Self {
+        Self { charts, tiles }
+    }
+
+    // A quilt of the charts in the BASE_MAP_CHART_CATALOG file; none when it
+    // is unset or unreadable.
+    pub fn from_env(tiles: Arc<TileCache>) -> Self {
+        let charts = match std::env::var_os(CHART_CATALOG_ENV) {
+            Some(path) => read_catalog(Path::new(&path)).unwrap_or_else(|err| {
+                tracing::warn!("Ignoring chart catalog {}: {err}", path.to_string_lossy());
+                Vec::new()
+            }),
+            None => Vec::new(),
+        };
+        tracing::info!("Chart quilt has {} charts", charts.len());
+        Self::new(charts, tiles)
+    }
+
+    // Charts with coverage in `bounds`, most detailed first.
+    pub fn covering(&self, bounds: [f64; 4]) -> Vec<&Chart> {
+        let mut charts: Vec<&Chart> = self.charts.iter().filter(|chart| chart.covers(bounds)).collect();
+        charts.sort_by_key(|chart| chart.scale);
+        charts
+    }
+
+    // Tile `z/x/y` of the quilt as PNG, or None when no chart has it.
+    pub async fn tile(&self, z: u32, x: u32, y: u32) -> Option<Vec<u8>> {
+        let bounds = tile_bounds(z, x, y);
+        let latitude = (bounds[1] + bounds[3]) / 2.0;
+        let map_scale = ZOOM_0_SCALE * latitude.to_radians().cos() / f64::from(1u32 << z);
+        let charts: Vec<&Chart> = self
+            .covering(bounds)
+            .into_iter()
+            .filter(|chart| f64::from(chart.scale) * DETAIL_LIMIT >= map_scale)
+            .take(MAX_QUILT_CHARTS)
+            .collect();
+
+        let mut layers = Vec::new();
+        // Smallest scale first, so detailed charts end up on top
+        for chart in charts.into_iter().rev() {
+            let layer = format!("charts/{}", chart.id);
+            if let Some(tile) = self.tiles.get_layer(&layer, &chart.tiles, z, x, y).await {
+                layers.push(tile);
+            }
+        }
+        if layers.is_empty() {
+            return None;
+        }
+        tokio::task::spawn_blocking(move || mosaic(&layers)).await.ok().flatten()
+    }
+}
+
+// Layer chart tiles over one another, the last on top. Tiles that cannot
+// be decoded are left out.
+fn mosaic(layers: &[Bytes]) -> Option<Vec<u8>> {
+    let mut canvas = RgbaImage::new(TILE_SIZE, TILE_SIZE);
+    let mut drawn = false;
+    for layer in layers {
+        let Ok(image) = image::load_from_memory(layer) else {
+            continue;
+        };
+        let mut image = image.to_rgba8();
+        if image.dimensions() != (TILE_SIZE, TILE_SIZE) {
+            image = imageops::resize(&image, TILE_SIZE, TILE_SIZE, imageops::FilterType::Triangle);
+        }
+        imageops::overlay(&mut canvas, &image, 0, 0);
+        drawn = true;
+    }
+    let mut png = Vec::new();
+    canvas.write_to(&mut Cursor::new(&mut png), ImageFormat::Png).ok()?;
+    drawn.then_some(png)
+}
+
+// [west, south, east, north] of a Web Mercator tile.
+fn tile_bounds(z: u32, x: u32, y: u32) -> [f64; 4] {
+    let tiles = f64::from(1u32 << z);
+    let longitude = |x: u32| f64::from(x) / tiles * 360.0 - 180.0;
+    let latitude = |y: u32| (PI * (1.0 - 2.0 * f64::from(y) / tiles)).sinh().atan().to_degrees();
+    [longitude(x), latitude(y + 1), longitude(x + 1), latitude(y)]
+}
+
+fn read_catalog(path: &Path) -> Result<Vec<Chart>, Box<dyn std::error::Error>> {
+    Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+}
+
+// ===== /charts handlers =====
+#[derive(Deserialize, Debug)]
+pub struct ChartsQuery {
+    /// Viewport as "west,south,east,north"
+    bbox: Option<String>,
+}
+
+// GET /charts, optionally only those covering `?bbox=` of the viewport.
+pub async fn list_charts(State(quilt): State<Arc<ChartQuilt>>, Query(query): Query<ChartsQuery>) -> Response {
+    let Some(bbox) = query.bbox else {
+        return Json(&quilt.charts).into_response();
+    };
+    let Some(bounds) = parse_bbox(&bbox) else {
+        return (StatusCode::BAD_REQUEST, "bbox must be west,south,east,north").into_response();
+    };
+    Json(quilt.covering(bounds)).into_response()
+}
+
+// GET /charts/tiles/{z}/{x}/{y}: the quilted chart layer.
+pub async fn quilted_tile(
+    State(quilt): State<Arc<ChartQuilt>>,
+    UrlPath((z, x, y)): UrlPath<(u32, u32, String)>,
+) -> Response {
+    let Some(y) = tile_y(z, x, &y) else {
+        return (StatusCode::NOT_FOUND, "No such tile").into_response();
+    };
+    match quilt.tile(z, x, y).await {
+        Some(png) => ([(header::CONTENT_TYPE, "image/png")], png).into_response(),
+        None => (StatusCode::NOT_FOUND, "No chart covers this tile").into_response(),
+    }
+}
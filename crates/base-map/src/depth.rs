@@ -0,0 +1,235 @@
+use crate::parse_bbox;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use rstar::primitives::{GeomWithData, Rectangle};
+use rstar::{RTree, AABB};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::path::Path;
+use std::sync::Arc;
+
+/// GeoJSON file, or directory of them, holding depth areas, contours and hazards
+pub const DEPTH_DATA_ENV: &str = "BASE_MAP_DEPTH_DATA";
+
+// ===== Charted features =====
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeatureKind {
+    DepthArea,
+    Contour,
+    Rock,
+    Wreck,
+    Obstruction,
+    Restricted,
+}
+
+impl FeatureKind {
+    // The kind a `kind` property or file name stands for, including the S-57
+    // object classes of an ENC exported with ogr2ogr.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "depth_area" | "depare" | "drgare" => Some(FeatureKind::DepthArea),
+            "contour" | "depcnt" => Some(FeatureKind::Contour),
+            "rock" | "uwtroc" => Some(FeatureKind::Rock),
+            "wreck" | "wrecks" => Some(FeatureKind::Wreck),
+            "obstruction" | "obstrn" => Some(FeatureKind::Obstruction),
+            "restricted" | "resare" => Some(FeatureKind::Restricted),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            FeatureKind::DepthArea => "depth_area",
+            FeatureKind::Contour => "contour",
+            FeatureKind::Rock => "rock",
+            FeatureKind::Wreck => "wreck",
+            FeatureKind::Obstruction => "obstruction",
+            FeatureKind::Restricted => "restricted",
+        }
+    }
+}
+
+// A depth area, contour or hazard, with depths in metres below chart datum.
+#[derive(Debug, Clone)]
+pub struct ChartedFeature {
+    pub kind: FeatureKind,
+    pub name: Option<String>,
+    /// Shallowest depth of a depth area; negative where it dries
+    pub min_depth: Option<f64>,
+    pub max_depth: Option<f64>,
+    /// Depth of a contour, or the sounding over a hazard
+    pub depth: Option<f64>,
+    geometry: Value,
+}
+
+impl ChartedFeature {
+    // A feature from GeoJSON, its kind from a `kind` property or else
+    // `default_kind`. Depths are read from plain names or S-57 attributes.
+    pub fn from_geojson(feature: &Value, default_kind: Option<FeatureKind>) -> Option<Self> {
+        let properties = &feature["properties"];
+        let kind = properties["kind"].as_str().and_then(FeatureKind::from_name).or(default_kind)?;
+        let number = |names: &[&str]| names.iter().find_map(|name| properties[*name].as_f64());
+        let (min_depth, max_depth, depth) = match kind {
+            FeatureKind::DepthArea => (number(&["min_depth", "DRVAL1"]), number(&["max_depth", "DRVAL2"]), None),
+            FeatureKind::Contour => (None, None, number(&["depth", "VALDCO"])),
+            FeatureKind::Rock | FeatureKind::Wreck | FeatureKind::Obstruction => {
+                (None, None, number(&["depth", "VALSOU"]))
+            }
+            FeatureKind::Restricted => (None, None, None),
+        };
+        let name = ["name", "OBJNAM"]
+            .iter()
+            .find_map(|key| properties[*key].as_str())
+            .map(str::to_string);
+        let geometry = feature.get("geometry").filter(|geometry| geometry.is_object())?.clone();
+        Some(Self {
+            kind,
+            name,
+            min_depth,
+            max_depth,
+            depth,
+            geometry,
+        })
+    }
+
+    // Whether a vessel drawing `draft` metres should keep off. A hazard
+    // with no charted sounding is assumed to be awash.
+    pub fn shallower_than(&self, draft: f64) -> bool {
+        match self.kind {
+            FeatureKind::DepthArea => self.min_depth.is_some_and(|depth| depth < draft),
+            FeatureKind::Contour => self.depth.is_some_and(|depth| depth < draft),
+            FeatureKind::Rock | FeatureKind::Wreck | FeatureKind::Obstruction => {
+                self.depth.is_none_or(|depth| depth < draft)
+            }
+            FeatureKind::Restricted => false,
+        }
+    }
+
+    fn to_geojson(&self, draft: Option<f64>) -> Value {
+        let mut properties = Map::new();
+        properties.insert("kind".to_string(), json!(self.kind.name()));
+        let optional = [
+            ("name", json!(self.name)),
+            ("min_depth", json!(self.min_depth)),
+            ("max_depth", json!(self.max_depth)),
+            ("depth", json!(self.depth)),
+        ];
+        for (key, value) in optional {
+            if !value.is_null() {
+                properties.insert(key.to_string(), value);
+            }
+        }
+        if let Some(draft) = draft {
+            properties.insert("shallow".to_string(), json!(self.shallower_than(draft)));
+        }
+        json!({"type": "Feature", "geometry": self.geometry, "properties": properties})
+    }
+}
+
+// ===== Bounding-box index =====
+// A feature's extent, pointing into `DepthOverlay::features`.
+type IndexedExtent = GeomWithData<Rectangle<[f64; 2]>, usize>;
+
+// Charted depths and hazards, indexed by extent for bounding-box queries.
+pub struct DepthOverlay {
+    features: Vec<ChartedFeature>,
+    tree: RTree<IndexedExtent>,
+}
+
+impl DepthOverlay {
+    pub fn new(features: Vec<ChartedFeature>) -> Self {
+        let extents = features
+            .iter()
+            .enumerate()
+            .filter_map(|(index, feature)| {
+                let [west, south, east, north] = extent(&feature.geometry["coordinates"])?;
+                Some(IndexedExtent::new(Rectangle::from_corners([west, south], [east, north]), index))
+            })
+            .collect();
+        Self {
+            features,
+            tree: RTree::bulk_load(extents),
+        }
+    }
+
+    // The overlay from the BASE_MAP_DEPTH_DATA file or directory; empty when
+    // it is unset.
+    pub fn from_env() -> Self {
+        let mut features = Vec::new();
+        if let Some(path) = std::env::var_os(DEPTH_DATA_ENV) {
+            let path = Path::new(&path);
+            let files = match std::fs::read_dir(path) {
+                Ok(entries) => entries.flatten().map(|entry| entry.path()).collect(),
+                Err(_) => vec![path.to_path_buf()],
+            };
+            for file in files {
+                let is_geojson = file
+                    .extension()
+                    .is_some_and(|extension| extension == "geojson" || extension == "json");
+                if !is_geojson {
+                    continue;
+                }
+                if let Err(err) = read_features(&file, &mut features) {
+                    tracing::warn!("Skipping depth data {}: {err}", file.display());
+                }
+            }
+        }
+        tracing::info!("Depth overlay has {} features", features.len());
+        Self::new(features)
+    }
+
+    // Features reaching into `[west, south, east, north]`.
+    pub fn query(&self, [west, south, east, north]: [f64; 4]) -> impl Iterator<Item = &ChartedFeature> {
+        let envelope = AABB::from_corners([west, south], [east, north]);
+        self.tree
+            .locate_in_envelope_intersecting(&envelope)
+            .map(|extent| &self.features[extent.data])
+    }
+}
+
+// Features of a GeoJSON FeatureCollection; a file named after a kind, such
+// as DEPARE.geojson, gives that kind to features that do not say.
+fn read_features(path: &Path, features: &mut Vec<ChartedFeature>) -> Result<(), Box<dyn std::error::Error>> {
+    let collection: Value = serde_json::from_slice(&std::fs::read(path)?)?;
+    let default_kind = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(FeatureKind::from_name);
+    let Some(items) = collection["features"].as_array() else {
+        return Err("not a FeatureCollection".into());
+    };
+    features.extend(items.iter().filter_map(|item| ChartedFeature::from_geojson(item, default_kind)));
+    Ok(())
+}
+
+// [west, south, east, north] of GeoJSON coordinates nested to any depth.
+fn extent(coordinates: &Value) -> Option<[f64; 4]> {
+    let positions = coordinates.as_array()?;
+    if let (Some(lon), Some(lat)) = (positions.first().and_then(Value::as_f64), positions.get(1).and_then(Value::as_f64)) {
+        return Some([lon, lat, lon, lat]);
+    }
+    positions.iter().filter_map(extent).reduce(|a, b| {
+        [a[0].min(b[0]), a[1].min(b[1]), a[2].max(b[2]), a[3].max(b[3])]
+    })
+}
+
+// ===== GET /depth handler =====
+#[derive(Deserialize, Debug)]
+pub struct DepthQuery {
+    /// Area as "west,south,east,north"
+    bbox: String,
+    /// The vessel's draft in metres, to mark what is too shallow for her
+    draft: Option<f64>,
+}
+
+// GET /depth?bbox=...&draft=...: a FeatureCollection of the depth areas,
+// contours and hazards in the box, each marked `shallow` when a draft is given.
+pub async fn depth_overlay(State(overlay): State<Arc<DepthOverlay>>, Query(query): Query<DepthQuery>) -> Response {
+    let Some(bounds) = parse_bbox(&query.bbox) else {
+        return (StatusCode::BAD_REQUEST, "bbox must be west,south,east,north").into_response();
+    };
+    let features: Vec<Value> = overlay.query(bounds).map(|feature| feature.to_geojson(query.draft)).collect();
+    Json(json!({"type": "FeatureCollection", "features": features})).into_response()
+}
@@ -62,42 +62,67 @@ pub async fn geolocate() -> impl IntoResponse {
       return getCurrentLocation();
     }
 
+    // Own-ship fixes stream to the server over /ws/position; it echoes what
+    // other sources publish and may ask the map to centre.
+    let socket;
+    let pending;
+
+    function connectPositionSocket() {
+      const scheme = location.protocol === 'https:' ? 'wss' : 'ws';
+      socket = new WebSocket(`${scheme}://${location.host}/ws/position`);
+      socket.onopen = () => {
+        status.innerHTML += '<p style="color: green;">Streaming location to server.</p>';
+        if (pending) socket.send(JSON.stringify(pending));
+      };
+      socket.onmessage = event => {
+        const message = JSON.parse(event.data);
+        if (message.type === 'position' && message.id !== id) {
+          out.textContent = JSON.stringify(message, null, 2);
+        }
+      };
+      socket.onclose = () => setTimeout(connectPositionSocket, 2000);
+    }
+
+    function sendPosition(payload) {
+      pending = payload;
+      if (socket && socket.readyState === WebSocket.OPEN) {
+        socket.send(JSON.stringify(payload));
+      }
+    }
+
     function getCurrentLocation() {
+      connectPositionSocket();
       return new Promise((resolve, reject) => {
-        navigator.geolocation.getCurrentPosition(
-          async pos => {
+        let first = true;
+        navigator.geolocation.watchPosition(
+          pos => {
             const payload = {
+              type: 'position',
               id,
               lat: pos.coords.latitude,
               lon: pos.coords.longitude,
               accuracy: pos.coords.accuracy,
+              heading: pos.coords.heading ?? undefined,
+              speed: pos.coords.speed ?? undefined,
               timestamp: pos.timestamp
             };
 
             out.textContent = JSON.stringify(payload, null, 2);
-            status.innerHTML = '<p style="color: green;">Location obtained successfully!</p>';
-
-            try {
-              await fetch('/geolocate', {
-                method: 'POST',
-                headers: { 'Content-Type': 'application/json' },
-                body: JSON.stringify(payload)
-              });
-              status.innerHTML += '<p style="color: green;">Location sent to server.</p>';
-            } catch (fetchError) {
-              status.innerHTML += `<p style="color: orange;">Warning: Could not send location to server: ${fetchError.message}</p>`;
+            sendPosition(payload);
+            if (first) {
+              first = false;
+              status.innerHTML = '<p style="color: green;">Location obtained successfully!</p>';
+              resolve(true);
             }
-
-            resolve(true);
           },
           err => {
             handleLocationError(err);
-            reject(err);
+            if (first) reject(err);
           },
           {
             enableHighAccuracy: true,
             timeout: 15000,
-            maximumAge: 60000
+            maximumAge: 5000
           }
         );
       });
@@ -0,0 +1,629 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use std::fmt;
+
+/// Largest grid decoded, a little over a 0.1 degree global grid
+const MAX_GRID_POINTS: usize = 1 << 24;
+
+// ===== Decoded fields =====
+// A regular latitude/longitude grid. Point (i, j) lies at lat1 + j * dlat,
+// lon1 + i * dlon, with `dlat` negative for grids running north to south.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatLonGrid {
+    pub ni: usize,
+    pub nj: usize,
+    pub lat1: f64,
+    pub lon1: f64,
+    pub dlat: f64,
+    pub dlon: f64,
+}
+
+impl LatLonGrid {
+    // Whether the grid goes all the way round, so east wraps to west.
+    fn is_global(&self) -> bool {
+        (self.ni as f64 * self.dlon - 360.0).abs() < self.dlon / 2.0
+    }
+}
+
+// One GRIB2 message: a parameter on a surface at one forecast time.
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub discipline: u8,
+    pub category: u8,
+    pub number: u8,
+    /// Type of the first fixed surface, e.g. 103 for a height above ground
+    pub surface: u8,
+    /// Height or level of the surface in its own units
+    pub level: f64,
+    pub reference_time: DateTime<Utc>,
+    pub valid_time: DateTime<Utc>,
+    pub grid: LatLonGrid,
+    /// Row by row along i, NaN where the bitmap says there is no value
+    pub values: Vec<f32>,
+}
+
+impl Field {
+    // The field at `lat`/`lon`, bilinearly interpolated. None outside the
+    // grid or next to a missing point, such as land in a wave model.
+    pub fn sample(&self, lat: f64, lon: f64) -> Option<f32> {
+        let grid = &self.grid;
+        let j = (lat - grid.lat1) / grid.dlat;
+        let i = (lon - grid.lon1).rem_euclid(360.0) / grid.dlon;
+        if !(0.0..=(grid.nj - 1) as f64).contains(&j) {
+            return None;
+        }
+        let (i0, j0) = (i.floor() as usize, j.floor() as usize);
+        let j1 = (j0 + 1).min(grid.nj - 1);
+        let (i0, i1) = if grid.is_global() {
+            (i0 % grid.ni, (i0 + 1) % grid.ni)
+        } else if i <= (grid.ni - 1) as f64 {
+            (i0, (i0 + 1).min(grid.ni - 1))
+        } else {
+            return None;
+        };
+        let at = |i: usize, j: usize| self.values[j * grid.ni + i];
+        let (fi, fj) = (i.fract() as f32, j.fract() as f32);
+        let value = (at(i0, j0) * (1.0 - fi) + at(i1, j0) * fi) * (1.0 - fj) + (at(i0, j1) * (1.0 - fi) + at(i1, j1) * fi) * fj;
+        (!value.is_nan()).then_some(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GribError {
+    // Not GRIB, or cut short
+    Malformed(&'static str),
+    // Valid GRIB2 this decoder does not handle
+    Unsupported(String),
+}
+
+impl fmt::Display for GribError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed(what) => write!(f, "malformed GRIB: {what}"),
+            Self::Unsupported(what) => write!(f, "unsupported GRIB: {what}"),
+        }
+    }
+}
+
+impl std::error::Error for GribError {}
+
+// ===== Parsing =====
+// Every field in a file of GRIB2 messages. Messages this decoder cannot
+// handle (other grids, JPEG 2000 packing, ...) are skipped with a warning;
+// an error means the file itself is broken.
+pub fn parse(bytes: &[u8]) -> Result<Vec<Field>, GribError> {
+    let mut fields = Vec::new();
+    let mut rest = bytes;
+    while let Some(start) = rest.windows(4).position(|window| window == b"GRIB") {
+        rest = &rest[start..];
+        let header = rest.get(..16).ok_or(GribError::Malformed("truncated indicator section"))?;
+        if header[7] != 2 {
+            return Err(GribError::Unsupported(format!("GRIB edition {}", header[7])));
+        }
+        let length = usize::try_from(u64::from_be_bytes(header[8..16].try_into().unwrap()))
+            .map_err(|_| GribError::Malformed("message length"))?;
+        if length < header.len() {
+            return Err(GribError::Malformed("message length"));
+        }
+        let message = rest.get(..length).ok_or(GribError::Malformed("truncated message"))?;
+        match parse_message(header[6], &message[16..]) {
+            Ok(message_fields) => fields.extend(message_fields),
+            Err(GribError::Unsupported(what)) => tracing::warn!("Skipping GRIB message: {what}"),
+            Err(err) => return Err(err),
+        }
+        rest = &rest[length..];
+    }
+    Ok(fields)
+}
+
+// The sections of one message after its indicator. A message may repeat
+// sections 3 to 7 (or 4 to 7) to carry several fields.
+fn parse_message(discipline: u8, mut sections: &[u8]) -> Result<Vec<Field>, GribError> {
+    let mut fields = Vec::new();
+    let mut reference_time = None;
+    let mut grid = None;
+    let mut product = None;
+    let mut packing = None;
+    let mut bitmap: Option<Vec<bool>> = None;
+    while !sections.starts_with(b"7777") {
+        let length = sections.get(..4).map(|length| u32::from_be_bytes(length.try_into().unwrap()) as usize);
+        let section = match length {
+            Some(length) if length >= 5 => sections.get(..length).ok_or(GribError::Malformed("truncated section"))?,
+            _ => return Err(GribError::Malformed("section length")),
+        };
+        match section[4] {
+            1 => reference_time = Some(parse_reference_time(section)?),
+            2 => {}
+            3 => grid = Some(parse_grid(section)?),
+            4 => product = Some(parse_product(section)?),
+            5 => packing = Some(parse_packing(section)?),
+            6 => match byte(section, 5)? {
+                0 => bitmap = Some(bits(&section[6..], 1).map(|bit| bit == 1).collect()),
+                // 254 reuses the bitmap defined earlier in the message
+                254 => {}
+                _ => bitmap = None,
+            },
+            7 => {
+                let grid = grid.clone().ok_or(GribError::Malformed("data before grid"))?;
+                let (category, number, surface, level, forecast) = product.ok_or(GribError::Malformed("data before product"))?;
+                let packing = packing.as_ref().ok_or(GribError::Malformed("data before packing"))?;
+                let reference_time = reference_time.ok_or(GribError::Malformed("data before identification"))?;
+                let valid_time = reference_time.checked_add_signed(forecast).ok_or(GribError::Malformed("forecast time"))?;
+                let points = grid.ni * grid.nj;
+                let present = bitmap.as_ref().map_or(points, |bitmap| bitmap.iter().take(points).filter(|bit| **bit).count());
+                let mut packed = unpack(packing, &section[5..], present)?.into_iter();
+                let values = match &bitmap {
+                    Some(bitmap) => (0..points)
+                        .map(|point| match bitmap.get(point) {
+                            Some(true) => packed.next().unwrap_or(f32::NAN),
+                            _ => f32::NAN,
+                        })
+                        .collect(),
+                    None => packed.collect(),
+                };
+                fields.push(Field {
+                    discipline,
+                    category,
+                    number,
+                    surface,
+                    level,
+                    reference_time,
+                    valid_time,
+                    grid,
+                    values,
+                });
+            }
+            _ => return Err(GribError::Malformed("unknown section")),
+        }
+        sections = &sections[section.len()..];
+    }
+    Ok(fields)
+}
+
+fn parse_reference_time(section: &[u8]) -> Result<DateTime<Utc>, GribError> {
+    let field = section.get(12..19).ok_or(GribError::Malformed("truncated identification"))?;
+    NaiveDate::from_ymd_opt(i32::from(u16::from_be_bytes([field[0], field[1]])), field[2].into(), field[3].into())
+        .and_then(|date| date.and_hms_opt(field[4].into(), field[5].into(), field[6].into()))
+        .map(|time| time.and_utc())
+        .ok_or(GribError::Malformed("reference time"))
+}
+
+// Grid definition template 3.0, the regular latitude/longitude grid.
+fn parse_grid(section: &[u8]) -> Result<LatLonGrid, GribError> {
+    let template = u16_at(section, 12)?;
+    if template != 0 {
+        return Err(GribError::Unsupported(format!("grid template 3.{template}")));
+    }
+    let basic_angle = u32_at(section, 38)?;
+    let subdivisions = u32_at(section, 42)?;
+    let unit = if basic_angle == 0 || basic_angle == u32::MAX {
+        1e-6
+    } else {
+        f64::from(basic_angle) / f64::from(subdivisions)
+    };
+    let scanning = byte(section, 71)?;
+    if scanning & 0xb0 != 0 {
+        return Err(GribError::Unsupported(format!("scanning mode {scanning:#04x}")));
+    }
+    let (ni, nj) = (u32_at(section, 30)? as usize, u32_at(section, 34)? as usize);
+    if ni.checked_mul(nj).is_none_or(|points| points > MAX_GRID_POINTS) {
+        return Err(GribError::Unsupported(format!("{ni} x {nj} grid")));
+    }
+    let (dlon, dlat) = (f64::from(u32_at(section, 63)?) * unit, f64::from(u32_at(section, 67)?) * unit);
+    if ni == 0 || nj == 0 || dlon <= 0.0 || dlat <= 0.0 {
+        return Err(GribError::Unsupported("grid without regular spacing".into()));
+    }
+    Ok(LatLonGrid {
+        ni,
+        nj,
+        lat1: f64::from(i32_at(section, 46)?) * unit,
+        lon1: f64::from(i32_at(section, 50)?) * unit,
+        dlat: if scanning & 0x40 != 0 { dlat } else { -dlat },
+        dlon,
+    })
+}
+
+// Product definition templates 4.0, 4.1 and 4.8, which share the fields
+// read here: parameter category and number, the surface and forecast time.
+fn parse_product(section: &[u8]) -> Result<(u8, u8, u8, f64, Duration), GribError> {
+    let template = u16_at(section, 7)?;
+    if ![0, 1, 8].contains(&template) {
+        return Err(GribError::Unsupported(format!("product template 4.{template}")));
+    }
+    let forecast = i64::from(u32_at(section, 18)?);
+    let forecast = match byte(section, 17)? {
+        0 => Duration::minutes(forecast),
+        1 => Duration::hours(forecast),
+        2 => Duration::days(forecast),
+        10 => Duration::hours(3 * forecast),
+        11 => Duration::hours(6 * forecast),
+        12 => Duration::hours(12 * forecast),
+        13 => Duration::seconds(forecast),
+        unit => return Err(GribError::Unsupported(format!("time range unit {unit}"))),
+    };
+    let level = f64::from(i32_at(section, 24)?) / 10f64.powi(sign_magnitude(&[byte(section, 23)?]) as i32);
+    Ok((byte(section, 9)?, byte(section, 10)?, byte(section, 22)?, level, forecast))
+}
+
+// ===== Unpacking =====
+// Data representation templates 5.0 (simple packing) and 5.2/5.3 (complex
+// packing, with spatial differencing for 5.3).
+struct Packing {
+    reference: f32,
+    binary_scale: i32,
+    decimal_scale: i32,
+    bits: usize,
+    complex: Option<ComplexPacking>,
+}
+
+struct ComplexPacking {
+    missing_management: u8,
+    groups: usize,
+    width_reference: u32,
+    width_bits: usize,
+    length_reference: u32,
+    length_increment: u32,
+    last_length: u32,
+    length_bits: usize,
+    /// Order of spatial differencing and the octets of its descriptors
+    differencing: Option<(usize, usize)>,
+}
+
+impl ComplexPacking {
+    // Whether a `width`-bit value is one of the missing value markers: all
+    // ones, or all ones less one for the secondary marker.
+    fn is_missing(&self, value: u64, width: usize) -> bool {
+        if width == 0 || width >= 64 {
+            return false;
+        }
+        let all_ones = (1u64 << width) - 1;
+        match self.missing_management {
+            1 => value == all_ones,
+            2 => value == all_ones || value == all_ones - 1,
+            _ => false,
+        }
+    }
+}
+
+fn parse_packing(section: &[u8]) -> Result<Packing, GribError> {
+    let template = u16_at(section, 9)?;
+    let complex = match template {
+        0 => None,
+        2 | 3 => Some(ComplexPacking {
+            missing_management: byte(section, 22)?,
+            groups: u32_at(section, 31)? as usize,
+            width_reference: byte(section, 35)?.into(),
+            width_bits: byte(section, 36)?.into(),
+            length_reference: u32_at(section, 37)?,
+            length_increment: byte(section, 41)?.into(),
+            last_length: u32_at(section, 42)?,
+            length_bits: byte(section, 46)?.into(),
+            differencing: if template == 3 {
+                Some((byte(section, 47)?.into(), byte(section, 48)?.into()))
+            } else {
+                None
+            },
+        }),
+        _ => return Err(GribError::Unsupported(format!("data representation template 5.{template}"))),
+    };
+    Ok(Packing {
+        reference: f32::from_be_bytes(section.get(11..15).ok_or(GribError::Malformed("truncated packing"))?.try_into().unwrap()),
+        binary_scale: i16_at(section, 15)?.into(),
+        decimal_scale: i16_at(section, 17)?.into(),
+        bits: byte(section, 19)?.into(),
+        complex,
+    })
+}
+
+// The `count` values packed in `data`, NaN where complex packing marks
+// them missing.
+fn unpack(packing: &Packing, data: &[u8], count: usize) -> Result<Vec<f32>, GribError> {
+    let binary = 2f64.powi(packing.binary_scale);
+    let decimal = 10f64.powi(-packing.decimal_scale);
+    let scale = |packed: i64| ((f64::from(packing.reference) + packed as f64 * binary) * decimal) as f32;
+
+    let Some(complex) = &packing.complex else {
+        if packing.bits == 0 {
+            return Ok(vec![scale(0); count]);
+        }
+        if count.checked_mul(packing.bits).is_none_or(|bits| bits > data.len() * 8) {
+            return Err(GribError::Malformed("truncated data"));
+        }
+        let values: Vec<f32> = bits(data, packing.bits).take(count).map(|packed| scale(packed as i64)).collect();
+        return if values.len() == count { Ok(values) } else { Err(GribError::Malformed("truncated data")) };
+    };
+
+    let mut data = data;
+    let mut first_values = Vec::new();
+    let mut minimum = 0;
+    if let Some((order, octets)) = complex.differencing {
+        let descriptor = |index: usize| -> Result<i64, GribError> {
+            let bytes = data.get(index * octets..(index + 1) * octets).ok_or(GribError::Malformed("truncated descriptors"))?;
+            Ok(sign_magnitude(bytes))
+        };
+        first_values = (0..order).map(descriptor).collect::<Result<_, _>>()?;
+        minimum = descriptor(order)?;
+        data = &data[(order + 1) * octets..];
+    }
+
+    // Every group holds at least one value
+    let groups = complex.groups;
+    if groups > count {
+        return Err(GribError::Malformed("more groups than values"));
+    }
+    let mut offset = 0;
+    let mut read = |bits_each: usize| -> Result<Vec<u64>, GribError> {
+        if bits_each == 0 {
+            return Ok(vec![0; groups]);
+        }
+        let rest = data.get(offset..).unwrap_or_default();
+        if groups.checked_mul(bits_each).is_none_or(|bits| bits > rest.len() * 8) {
+            return Err(GribError::Malformed("truncated groups"));
+        }
+        offset += (groups * bits_each).div_ceil(8);
+        Ok(bits(rest, bits_each).take(groups).collect())
+    };
+    let references = read(packing.bits)?;
+    let widths = read(complex.width_bits)?;
+    let lengths = read(complex.length_bits)?;
+
+    let mut values: Vec<Option<i64>> = Vec::with_capacity(count);
+    let mut reader = BitReader::new(data.get(offset..).unwrap_or_default());
+    for group in 0..groups {
+        let width = u32::try_from(widths[group])
+            .ok()
+            .and_then(|width| width.checked_add(complex.width_reference))
+            .filter(|width| *width <= 64)
+            .ok_or(GribError::Malformed("group width"))? as usize;
+        let length = if group + 1 == groups {
+            Some(complex.last_length)
+        } else {
+            u32::try_from(lengths[group])
+                .ok()
+                .and_then(|length| length.checked_mul(complex.length_increment))
+                .and_then(|length| length.checked_add(complex.length_reference))
+        };
+        let length = length
+            .map(|length| length as usize)
+            .filter(|length| *length <= count - values.len())
+            .ok_or(GribError::Malformed("group lengths exceed the grid"))?;
+        let reference = references[group];
+        for _ in 0..length {
+            let (packed, missing) = if width == 0 {
+                (0, complex.is_missing(reference, packing.bits))
+            } else {
+                let packed = reader.read(width).ok_or(GribError::Malformed("truncated data"))?;
+                (packed, complex.is_missing(packed, width))
+            };
+            let value = if missing {
+                None
+            } else {
+                let value = reference.checked_add(packed).and_then(|value| i64::try_from(value).ok());
+                Some(value.ok_or(GribError::Malformed("packed value"))?)
+            };
+            values.push(value);
+        }
+    }
+    if values.len() != count {
+        return Err(GribError::Malformed("group lengths do not add up"));
+    }
+
+    if !first_values.is_empty() {
+        undo_differencing(&mut values, &first_values, minimum)?;
+    }
+    Ok(values.into_iter().map(|value| value.map_or(f32::NAN, scale)).collect())
+}
+
+// Undo first or second order spatial differencing over the values present.
+fn undo_differencing(values: &mut [Option<i64>], first_values: &[i64], minimum: i64) -> Result<(), GribError> {
+    let order = first_values.len();
+    let (mut last, mut before_last) = (0i64, 0i64);
+    for (n, value) in values.iter_mut().flatten().enumerate() {
+        let undone = if n < order {
+            Some(first_values[n])
+        } else if order == 1 {
+            value.checked_add(minimum).and_then(|value| value.checked_add(last))
+        } else {
+            value
+                .checked_add(minimum)
+                .and_then(|value| value.checked_add(last.checked_mul(2)?))
+                .and_then(|value| value.checked_sub(before_last))
+        };
+        *value = undone.ok_or(GribError::Malformed("spatial differencing overflows"))?;
+        (before_last, last) = (last, *value);
+    }
+    Ok(())
+}
+
+// ===== Bits and bytes =====
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    // The next `count` bits, most significant first.
+    fn read(&mut self, count: usize) -> Option<u64> {
+        if count > 64 || self.position + count > self.data.len() * 8 {
+            return None;
+        }
+        let mut value = 0u64;
+        for _ in 0..count {
+            let bit = self.data[self.position / 8] >> (7 - self.position % 8) & 1;
+            value = value << 1 | u64::from(bit);
+            self.position += 1;
+        }
+        Some(value)
+    }
+}
+
+// Consecutive `count`-bit values in `data`.
+fn bits(data: &[u8], count: usize) -> impl Iterator<Item = u64> + '_ {
+    let mut reader = BitReader::new(data);
+    std::iter::from_fn(move || if count == 0 { None } else { reader.read(count) })
+}
+
+// GRIB2 signed integers keep the sign in the top bit rather than using
+// two's complement.
+fn sign_magnitude(bytes: &[u8]) -> i64 {
+    let Some((first, rest)) = bytes.split_first() else {
+        return 0;
+    };
+    let magnitude = rest.iter().fold(i64::from(first & 0x7f), |value, byte| value << 8 | i64::from(*byte));
+    if first & 0x80 != 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+fn byte(section: &[u8], offset: usize) -> Result<u8, GribError> {
+    section.get(offset).copied().ok_or(GribError::Malformed("truncated section"))
+}
+
+fn slice(section: &[u8], offset: usize, length: usize) -> Result<&[u8], GribError> {
+    section.get(offset..offset + length).ok_or(GribError::Malformed("truncated section"))
+}
+
+fn u16_at(section: &[u8], offset: usize) -> Result<u16, GribError> {
+    Ok(u16::from_be_bytes(slice(section, offset, 2)?.try_into().unwrap()))
+}
+
+fn u32_at(section: &[u8], offset: usize) -> Result<u32, GribError> {
+    Ok(u32::from_be_bytes(slice(section, offset, 4)?.try_into().unwrap()))
+}
+
+fn i16_at(section: &[u8], offset: usize) -> Result<i16, GribError> {
+    Ok(sign_magnitude(slice(section, offset, 2)?) as i16)
+}
+
+fn i32_at(section: &[u8], offset: usize) -> Result<i32, GribError> {
+    Ok(sign_magnitude(slice(section, offset, 4)?) as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(number: u8, body: &[u8]) -> Vec<u8> {
+        let mut section = ((body.len() + 5) as u32).to_be_bytes().to_vec();
+        section.push(number);
+        section.extend_from_slice(body);
+        section
+    }
+
+    // A message on an `ni` x `nj` one degree grid with the given packing
+    // (section 5 from its template number on) and data.
+    fn message(ni: u32, nj: u32, packing: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut grid = vec![0u8; 67];
+        grid[25..29].copy_from_slice(&ni.to_be_bytes());
+        grid[29..33].copy_from_slice(&nj.to_be_bytes());
+        grid[37..41].copy_from_slice(&u32::MAX.to_be_bytes());
+        grid[41..45].copy_from_slice(&10_000_000u32.to_be_bytes());
+        grid[58..62].copy_from_slice(&1_000_000u32.to_be_bytes());
+        grid[62..66].copy_from_slice(&1_000_000u32.to_be_bytes());
+        let mut product = vec![0u8; 29];
+        product[4..6].copy_from_slice(&[3, 1]);
+        product[12] = 1;
+        let mut representation = ni.wrapping_mul(nj).to_be_bytes().to_vec();
+        representation.extend_from_slice(packing);
+
+        let mut sections = section(1, &[0, 7, 0, 0, 2, 1, 1, 0x07, 0xea, 10, 16, 0, 0, 0, 0, 1]);
+        sections.extend(section(3, &grid));
+        sections.extend(section(4, &product));
+        sections.extend(section(5, &representation));
+        sections.extend(section(6, &[255]));
+        sections.extend(section(7, data));
+        sections.extend(b"7777");
+        let mut message = b"GRIB\0\0\0\x02".to_vec();
+        message.extend(((sections.len() + 16) as u64).to_be_bytes());
+        message.extend(sections);
+        message
+    }
+
+    // Template 5.0 with `bits` bits per value and no scaling.
+    fn simple(bits: u8) -> Vec<u8> {
+        let mut packing = vec![0, 0];
+        packing.extend(0f32.to_be_bytes());
+        packing.extend([0, 0, 0, 0, bits, 0]);
+        packing
+    }
+
+    // Template 5.3 with one group, `width_bits` bits per group width and
+    // `length_bits` per scaled group length.
+    fn complex(width_bits: u8, length_bits: u8, length_increment: u8, groups: u32) -> Vec<u8> {
+        let mut packing = vec![0, 3];
+        packing.extend(0f32.to_be_bytes());
+        packing.extend([0, 0, 0, 0, 1, 0, 1, 0]);
+        packing.extend([0u8; 8]);
+        packing.extend(groups.to_be_bytes());
+        packing.extend([0, width_bits]);
+        packing.extend(0u32.to_be_bytes());
+        packing.push(length_increment);
+        packing.extend(1u32.to_be_bytes());
+        packing.extend([length_bits, 1, 2]);
+        packing
+    }
+
+    #[test]
+    fn decodes_simple_packing() {
+        let fields = parse(&message(2, 2, &simple(8), &[1, 2, 3, 4])).unwrap();
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].values, vec![1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(fields[0].sample(9.5, 0.5), Some(2.5));
+    }
+
+    #[test]
+    fn rejects_message_lengths_shorter_than_the_indicator() {
+        for length in [0u64, 8, 15] {
+            let mut bytes = b"GRIB\0\0\0\x02".to_vec();
+            bytes.extend(length.to_be_bytes());
+            bytes.extend([0u8; 32]);
+            assert_eq!(parse(&bytes).unwrap_err(), GribError::Malformed("message length"));
+        }
+    }
+
+    #[test]
+    fn skips_oversized_grids_and_rejects_short_data() {
+        assert!(parse(&message(u32::MAX, u32::MAX, &simple(8), &[0; 4])).unwrap().is_empty());
+        assert!(parse(&message(1 << 13, 1 << 12, &simple(8), &[0; 4])).unwrap().is_empty());
+        assert_eq!(parse(&message(4096, 4096, &simple(8), &[0; 4])).unwrap_err(), GribError::Malformed("truncated data"));
+    }
+
+    #[test]
+    fn rejects_group_widths_and_lengths_out_of_range() {
+        // Two-octet descriptors, the 1-bit group references, then widths
+        // and lengths
+        let data = [0, 0, 0, 0, 0, 0xff, 0, 0];
+        assert_eq!(parse(&message(2, 2, &complex(8, 0, 1, 1), &data)).unwrap_err(), GribError::Malformed("group width"));
+        let data = [0, 0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0];
+        assert_eq!(
+            parse(&message(2, 2, &complex(0, 32, 2, 2), &data)).unwrap_err(),
+            GribError::Malformed("group lengths exceed the grid")
+        );
+        assert_eq!(parse(&message(2, 2, &complex(8, 0, 1, 5), &data)).unwrap_err(), GribError::Malformed("more groups than values"));
+    }
+
+    #[test]
+    fn rejects_forecast_times_out_of_range() {
+        let mut bytes = message(2, 2, &simple(8), &[1, 2, 3, 4]);
+        // Product section body, after the header and sections 1 and 3:
+        // forecast time unit days, then the forecast time
+        let product = 16 + 21 + 72 + 5;
+        bytes[product + 12] = 2;
+        bytes[product + 13..product + 17].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert_eq!(parse(&bytes).unwrap_err(), GribError::Malformed("forecast time"));
+    }
+
+    #[test]
+    fn rejects_overflowing_spatial_differencing() {
+        let mut values = vec![Some(0), Some(i64::MAX), Some(1)];
+        assert_eq!(undo_differencing(&mut values, &[0], 1), Err(GribError::Malformed("spatial differencing overflows")));
+        let mut values = vec![Some(0), Some(5), None, Some(-3)];
+        undo_differencing(&mut values, &[0], 0).unwrap();
+        assert_eq!(values, vec![Some(0), Some(5), None, Some(2)]);
+    }
+}
@@ -1,33 +1,105 @@
 mod geolocate;
 mod app;
+pub mod ais;
+pub mod charts;
+pub mod depth;
+pub mod grib;
+pub mod position;
+pub mod tides;
+pub mod tiles;
+pub mod tracks;
+pub mod weather;
 
-use axum::response::IntoResponse;
-use axum::routing::post;
+use axum::extract::DefaultBodyLimit;
 // src/lib.rs
-use axum::{routing::get, Json, Router};
-use serde::Deserialize;
+use axum::{routing::get, Router};
+use ais::AisTargets;
+use position::PositionHub;
+use weather::WeatherStore;
+use std::sync::Arc;
 use tower_http::trace::TraceLayer;
 
-// ===== JSON coming back from the browser =====
-#[derive(Deserialize, Debug)]
-struct LocationPayload {
-    id:  String,
-    lat: f64,
-    lon: f64,
+pub(crate) const EARTH_RADIUS_NM: f64 = 3440.065;
+
+// Great-circle distance between two (lat, lon) points, in nautical miles.
+pub(crate) fn great_circle_nm((lat1, lon1): (f64, f64), (lat2, lon2): (f64, f64)) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_NM * a.sqrt().asin()
 }
 
-// ===== POST /api/location handler =====
-async fn receive_location(axum::Json(p): Json<LocationPayload>) -> impl IntoResponse {
-    println!("Got location: {p:?}");
-    axum::http::StatusCode::OK
+// "west,south,east,north" as given in `?bbox=` queries.
+pub(crate) fn parse_bbox(bbox: &str) -> Option<[f64; 4]> {
+    let bounds: Vec<f64> = bbox.split(',').map(|value| value.trim().parse().ok()).collect::<Option<_>>()?;
+    let [west, south, east, north] = <[f64; 4]>::try_from(bounds).ok()?;
+    (west <= east && south <= north).then_some([west, south, east, north])
 }
 
+// Live data the router serves and a backend publishes into: own-ship fixes
+// and centring requests, the AIS targets around and weather forecasts.
+#[derive(Default, Clone)]
+pub struct LiveFeeds {
+    pub position: Arc<PositionHub>,
+    pub ais: Arc<AisTargets>,
+    pub weather: Arc<WeatherStore>,
+}
 
 // a helper for integration tests or other binaries
 pub fn build_router() -> Router {
+    build_router_with(LiveFeeds::default())
+}
+
+// The router, serving `feeds` that the caller keeps publishing into.
+pub fn build_router_with(feeds: LiveFeeds) -> Router {
+    let tiles = Arc::new(tiles::TileCache::from_env());
+    let charts = Arc::new(charts::ChartQuilt::from_env(tiles.clone()));
     Router::new()
         .route("/status", get(|| async { "OK" }))
         .route("/geolocate", get(geolocate::geolocate))
-        .route("/geolocate", post(receive_location))
+        .route("/tiles/:z/:x/:y", get(tiles::tile))
+        .with_state(tiles)
+        .merge(
+            Router::new()
+                .route("/charts", get(charts::list_charts))
+                .route("/charts/tiles/:z/:x/:y", get(charts::quilted_tile))
+                .with_state(charts),
+        )
+        .merge(
+            Router::new()
+                .route("/ws/position", get(position::position_socket))
+                .with_state(feeds.position),
+        )
+        .merge(
+            Router::new()
+                .route("/overlays/ais", get(ais::ais_overlay))
+                .route("/overlays/ais/ws", get(ais::ais_overlay_socket))
+                .with_state(feeds.ais),
+        )
+        .merge(
+            Router::new()
+                .route("/depth", get(depth::depth_overlay))
+                .with_state(Arc::new(depth::DepthOverlay::from_env())),
+        )
+        .merge(
+            Router::new()
+                .route("/tides", get(tides::tides))
+                .with_state(Arc::new(tides::TideStations::from_env())),
+        )
+        .merge(
+            Router::new()
+                .route("/tracks", get(tracks::list_tracks).post(tracks::upload_track))
+                .route("/tracks/:id", get(tracks::get_track))
+                .layer(DefaultBodyLimit::max(tracks::MAX_TRACK_UPLOAD))
+                .with_state(Arc::new(tracks::TrackStore::from_env())),
+        )
+        .merge(
+            Router::new()
+                .route("/weather", get(weather::list_forecasts))
+                .route("/weather/points", get(weather::weather_points))
+                .route("/weather/tiles/:layer/:z/:x/:y", get(weather::weather_tile))
+                .with_state(feeds.weather),
+        )
         .layer(TraceLayer::new_for_http())
 }
@@ -1,5 +1,5 @@
 use axum_embed::ServeEmbed;
-use base_map::build_router;
+use base_map::{ais, build_router_with, weather, LiveFeeds};
 use rust_embed::RustEmbed;
 use tokio::net::TcpListener;
 
@@ -21,7 +21,15 @@ async fn main() -> anyhow::Result<()> {
     }
 
     let serve_assets = ServeEmbed::<Assets>::new();
-    let router = build_router();
+    let feeds = LiveFeeds::default();
+    tokio::spawn(ais::follow_ais_server(feeds.ais.clone(), ais::ais_server_url()));
+    if let Some(dir) = std::env::var_os(weather::GRIB_DIR_ENV) {
+        feeds.weather.load_dir(std::path::Path::new(&dir));
+    }
+    if let Some(url) = weather::grib_url() {
+        tokio::spawn(weather::follow_forecasts(feeds.weather.clone(), url));
+    }
+    let router = build_router_with(feeds);
     let app = router
         .nest_service("/", serve_assets)
         .fallback(fallback);
@@ -0,0 +1,132 @@
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::Response;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+/// Messages a slow socket may fall behind by before it skips ahead
+const CHANNEL_CAPACITY: usize = 64;
+
+// ===== Messages on /ws/position =====
+// An own-ship fix, from the webview's geolocation or a backend source.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Position {
+    /// Who reported the fix: a browser's id, or a backend source's name
+    pub id: String,
+    pub lat: f64,
+    pub lon: f64,
+    /// Metres
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accuracy: Option<f64>,
+    /// Degrees true
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heading: Option<f64>,
+    /// Metres per second, as browsers report it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f64>,
+    /// Milliseconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PositionMessage {
+    Position(Position),
+    // Ask the maps to centre on a point
+    Center {
+        lat: f64,
+        lon: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        zoom: Option<f64>,
+    },
+}
+
+// ===== Hub =====
+// Fans own-ship positions out to every connected map and remembers the
+// latest, so a map opened later starts where the boat is.
+pub struct PositionHub {
+    tx: broadcast::Sender<PositionMessage>,
+    latest: Mutex<Option<Position>>,
+}
+
+impl Default for PositionHub {
+    fn default() -> Self {
+        Self {
+            tx: broadcast::channel(CHANNEL_CAPACITY).0,
+            latest: Mutex::new(None),
+        }
+    }
+}
+
+impl PositionHub {
+    // Send `position` to every map. The first fix also centres them on it.
+    pub fn publish(&self, position: Position) {
+        let first = self.lock().replace(position.clone()).is_none();
+        let _ = self.tx.send(PositionMessage::Position(position.clone()));
+        if first {
+            self.center(position.lat, position.lon, None);
+        }
+    }
+
+    // Centre every map on `lat`/`lon`, keeping its zoom unless given one.
+    pub fn center(&self, lat: f64, lon: f64, zoom: Option<f64>) {
+        let _ = self.tx.send(PositionMessage::Center { lat, lon, zoom });
+    }
+
+    pub fn latest(&self) -> Option<Position> {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Position>> {
+        self.latest.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+// ===== GET /ws/position handler =====
+pub async fn position_socket(ws: WebSocketUpgrade, State(hub): State<Arc<PositionHub>>) -> Response {
+    ws.on_upgrade(move |socket| relay(socket, hub))
+}
+
+// Pass what the map sends to the hub and what the hub publishes to the map.
+async fn relay(mut socket: WebSocket, hub: Arc<PositionHub>) {
+    let mut updates = hub.tx.subscribe();
+    if let Some(latest) = hub.latest() {
+        let message = PositionMessage::Center { lat: latest.lat, lon: latest.lon, zoom: None };
+        for message in [PositionMessage::Position(latest), message] {
+            if send(&mut socket, &message).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    loop {
+        tokio::select! {
+            received = socket.recv() => match received {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<PositionMessage>(&text) {
+                    Ok(PositionMessage::Position(position)) => hub.publish(position),
+                    Ok(PositionMessage::Center { lat, lon, zoom }) => hub.center(lat, lon, zoom),
+                    Err(err) => tracing::warn!("Ignoring position message {text}: {err}"),
+                },
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            update = updates.recv() => match update {
+                Ok(message) => {
+                    if send(&mut socket, &message).await.is_err() {
+                        break;
+                    }
+                }
+                // Only the newest positions matter
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        }
+    }
+}
+
+async fn send(socket: &mut WebSocket, message: &PositionMessage) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(message).expect("position messages serialize");
+    socket.send(Message::Text(text)).await
+}
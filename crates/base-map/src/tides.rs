@@ -0,0 +1,411 @@
+use crate::great_circle_nm;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::{DateTime, Duration, DurationRound, NaiveDateTime, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+
+/// JSON file listing tide and current stations
+pub const TIDE_STATIONS_ENV: &str = "BASE_MAP_TIDE_STATIONS";
+
+/// Stations farther than this from the vessel are not used
+const MAX_STATION_DISTANCE_NM: f64 = 50.0;
+const DEFAULT_HOURS: u32 = 24;
+const MAX_HOURS: u32 = 48;
+/// Spacing of predictions worked out from harmonics
+const STEP_MINUTES: i64 = 10;
+const NOAA_PREDICTIONS_URL: &str = "https://api.tidesandcurrents.noaa.gov/api/prod/datagetter";
+const FETCH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+// ===== Stations =====
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StationKind {
+    #[default]
+    Tide,
+    Current,
+}
+
+// One harmonic constituent of a station: amplitude in metres (knots at
+// current stations) and Greenwich phase lag in degrees.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Constituent {
+    pub name: String,
+    pub amplitude: f64,
+    pub phase: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Station {
+    pub id: String,
+    pub name: String,
+    pub lat: f64,
+    pub lon: f64,
+    #[serde(default)]
+    pub kind: StationKind,
+    /// Datum heights are given above, e.g. "MLLW"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub datum: Option<String>,
+    /// Mean water level above the datum, in metres
+    #[serde(default, skip_serializing)]
+    pub z0: f64,
+    #[serde(default, skip_serializing)]
+    pub constituents: Vec<Constituent>,
+    /// Degrees true the flood sets toward, at current stations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flood_direction: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ebb_direction: Option<f64>,
+    /// NOAA CO-OPS station to fetch tide predictions from when no
+    /// constituents are given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub noaa_id: Option<String>,
+}
+
+// ===== Harmonic prediction =====
+// Multiples of the astronomical arguments making up a constituent's
+// equilibrium argument (Schureman): mean solar hour angle T, mean longitudes
+// of the moon s, sun h and lunar perigee p, plus a constant in degrees.
+struct Argument {
+    t: f64,
+    s: f64,
+    h: f64,
+    p: f64,
+    offset: f64,
+    nodal: Nodal,
+}
+
+// Which nodal correction a constituent takes.
+#[derive(Clone, Copy)]
+enum Nodal {
+    None,
+    M2,
+    K1,
+    O1,
+    K2,
+    Mf,
+    Mm,
+    M2Squared,
+    M2Cubed,
+}
+
+fn argument(name: &str) -> Option<Argument> {
+    let (t, s, h, p, offset, nodal) = match name.to_ascii_uppercase().as_str() {
+        "M2" => (2.0, -2.0, 2.0, 0.0, 0.0, Nodal::M2),
+        "S2" => (2.0, 0.0, 0.0, 0.0, 0.0, Nodal::None),
+        "N2" => (2.0, -3.0, 2.0, 1.0, 0.0, Nodal::M2),
+        "2N2" => (2.0, -4.0, 2.0, 2.0, 0.0, Nodal::M2),
+        "NU2" => (2.0, -3.0, 4.0, -1.0, 0.0, Nodal::M2),
+        "K2" => (2.0, 0.0, 2.0, 0.0, 0.0, Nodal::K2),
+        "K1" => (1.0, 0.0, 1.0, 0.0, -90.0, Nodal::K1),
+        "O1" => (1.0, -2.0, 1.0, 0.0, 90.0, Nodal::O1),
+        "P1" => (1.0, 0.0, -1.0, 0.0, 90.0, Nodal::None),
+        "Q1" => (1.0, -3.0, 1.0, 1.0, 90.0, Nodal::O1),
+        "M4" => (4.0, -4.0, 4.0, 0.0, 0.0, Nodal::M2Squared),
+        "MS4" => (4.0, -2.0, 2.0, 0.0, 0.0, Nodal::M2),
+        "M6" => (6.0, -6.0, 6.0, 0.0, 0.0, Nodal::M2Cubed),
+        "MF" => (0.0, 2.0, 0.0, 0.0, 0.0, Nodal::Mf),
+        "MM" => (0.0, 1.0, 0.0, -1.0, 0.0, Nodal::Mm),
+        "SSA" => (0.0, 0.0, 2.0, 0.0, 0.0, Nodal::None),
+        "SA" => (0.0, 0.0, 1.0, 0.0, 0.0, Nodal::None),
+        _ => return None,
+    };
+    Some(Argument { t, s, h, p, offset, nodal })
+}
+
+// Node factor f and nodal angle u in degrees, for the longitude of the
+// moon's ascending node `n` in radians.
+fn nodal_correction(nodal: Nodal, n: f64) -> (f64, f64) {
+    let m2 = (1.0 - 0.037 * n.cos(), -2.1 * n.sin());
+    match nodal {
+        Nodal::None => (1.0, 0.0),
+        Nodal::M2 => m2,
+        Nodal::K1 => (1.006 + 0.115 * n.cos(), -8.9 * n.sin()),
+        Nodal::O1 => (1.009 + 0.187 * n.cos(), 10.8 * n.sin()),
+        Nodal::K2 => (1.024 + 0.286 * n.cos(), -17.7 * n.sin()),
+        Nodal::Mf => (1.043 + 0.414 * n.cos(), -23.7 * n.sin()),
+        Nodal::Mm => (1.0 - 0.130 * n.cos(), 0.0),
+        Nodal::M2Squared => (m2.0.powi(2), 2.0 * m2.1),
+        Nodal::M2Cubed => (m2.0.powi(3), 3.0 * m2.1),
+    }
+}
+
+// The station's height (or current speed) at `time` above its datum.
+fn harmonic_level(station: &Station, time: DateTime<Utc>) -> f64 {
+    // Julian centuries since J2000
+    let centuries = (time.timestamp() as f64 / 86_400.0 + 2_440_587.5 - 2_451_545.0) / 36_525.0;
+    let s = 218.3164 + 481267.8812 * centuries;
+    let h = 280.4661 + 36000.7698 * centuries;
+    let p = 83.3535 + 4069.0137 * centuries;
+    let node = (125.0445 - 1934.1363 * centuries).to_radians();
+    let hours = time.num_seconds_from_midnight() as f64 / 3600.0;
+    let t = 180.0 + 15.0 * hours;
+
+    station.z0
+        + station
+            .constituents
+            .iter()
+            .filter_map(|constituent| Some((constituent, argument(&constituent.name)?)))
+            .map(|(constituent, argument)| {
+                let (f, u) = nodal_correction(argument.nodal, node);
+                let equilibrium = argument.t * t + argument.s * s + argument.h * h + argument.p * p + argument.offset;
+                f * constituent.amplitude * (equilibrium + u - constituent.phase).to_radians().cos()
+            })
+            .sum::<f64>()
+}
+
+// ===== Predictions =====
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TideHeight {
+    pub time: DateTime<Utc>,
+    /// Metres above the station's datum
+    pub height: f64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TideExtreme {
+    pub time: DateTime<Utc>,
+    pub height: f64,
+    /// "high" or "low"
+    pub kind: &'static str,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CurrentSample {
+    pub time: DateTime<Utc>,
+    /// Knots, positive on the flood
+    pub speed: f64,
+    /// Degrees true the stream sets toward
+    pub direction: Option<f64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct StationPrediction<T> {
+    pub station: Station,
+    pub distance_nm: f64,
+    /// "harmonics" when worked out here, "noaa" when fetched
+    pub source: &'static str,
+    pub predictions: Vec<T>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extremes: Vec<TideExtreme>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TidesAndCurrents {
+    pub tide: Option<StationPrediction<TideHeight>>,
+    pub current: Option<StationPrediction<CurrentSample>>,
+}
+
+// Tide and current stations, predicting from harmonic constituents and
+// falling back to NOAA for stations that have none.
+pub struct TideStations {
+    stations: Vec<Station>,
+    client: reqwest::Client,
+}
+
+impl TideStations {
+    pub fn new(stations: Vec<Station>) -> Self {
+        for station in &stations {
+            for constituent in &station.constituents {
+                if argument(&constituent.name).is_none() {
+                    tracing::warn!("Station {} has unsupported constituent {}", station.id, constituent.name);
+                }
+            }
+        }
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("yachtpit-base-map/", env!("CARGO_PKG_VERSION")))
+            .timeout(FETCH_TIMEOUT)
+            .build()
+            .expect("building the tides HTTP client");
+        Self { stations, client }
+    }
+
+    // Stations from the BASE_MAP_TIDE_STATIONS file; none when it is unset
+    // or unreadable.
+    pub fn from_env() -> Self {
+        let stations = match std::env::var_os(TIDE_STATIONS_ENV) {
+            Some(path) => read_stations(Path::new(&path)).unwrap_or_else(|err| {
+                tracing::warn!("Ignoring tide stations {}: {err}", path.to_string_lossy());
+                Vec::new()
+            }),
+            None => Vec::new(),
+        };
+        tracing::info!("Tide service has {} stations", stations.len());
+        Self::new(stations)
+    }
+
+    // The nearest station of `kind` within reach of `lat`/`lon`.
+    pub fn nearest(&self, kind: StationKind, lat: f64, lon: f64) -> Option<(&Station, f64)> {
+        self.stations
+            .iter()
+            .filter(|station| station.kind == kind)
+            .map(|station| (station, great_circle_nm((lat, lon), (station.lat, station.lon))))
+            .filter(|(_, distance)| *distance <= MAX_STATION_DISTANCE_NM)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    // Tide heights at the station nearest `lat`/`lon` from `start` for `hours`.
+    pub async fn tide(
+        &self,
+        lat: f64,
+        lon: f64,
+        start: DateTime<Utc>,
+        hours: u32,
+    ) -> Result<Option<StationPrediction<TideHeight>>, reqwest::Error> {
+        let Some((station, distance_nm)) = self.nearest(StationKind::Tide, lat, lon) else {
+            return Ok(None);
+        };
+        let (source, predictions) = match &station.noaa_id {
+            Some(noaa_id) if station.constituents.is_empty() => ("noaa", self.fetch_noaa(noaa_id, station, start, hours).await?),
+            _ => {
+                let heights = sample_times(start, hours)
+                    .map(|time| TideHeight { time, height: harmonic_level(station, time) })
+                    .collect();
+                ("harmonics", heights)
+            }
+        };
+        Ok(Some(StationPrediction {
+            station: station.clone(),
+            distance_nm,
+            source,
+            extremes: extremes(&predictions),
+            predictions,
+        }))
+    }
+
+    // Tidal stream at the current station nearest `lat`/`lon`.
+    pub fn current(&self, lat: f64, lon: f64, start: DateTime<Utc>, hours: u32) -> Option<StationPrediction<CurrentSample>> {
+        let (station, distance_nm) = self.nearest(StationKind::Current, lat, lon)?;
+        let predictions = sample_times(start, hours)
+            .map(|time| {
+                let speed = harmonic_level(station, time);
+                let direction = if speed >= 0.0 { station.flood_direction } else { station.ebb_direction };
+                CurrentSample { time, speed, direction }
+            })
+            .collect();
+        Some(StationPrediction {
+            station: station.clone(),
+            distance_nm,
+            source: "harmonics",
+            predictions,
+            extremes: Vec::new(),
+        })
+    }
+
+    async fn fetch_noaa(
+        &self,
+        noaa_id: &str,
+        station: &Station,
+        start: DateTime<Utc>,
+        hours: u32,
+    ) -> Result<Vec<TideHeight>, reqwest::Error> {
+        #[derive(Deserialize)]
+        struct NoaaPredictions {
+            #[serde(default)]
+            predictions: Vec<NoaaPrediction>,
+        }
+        #[derive(Deserialize)]
+        struct NoaaPrediction {
+            t: String,
+            v: String,
+        }
+
+        let begin = start.format("%Y%m%d %H:%M").to_string();
+        let range = hours.to_string();
+        let datum = station.datum.as_deref().unwrap_or("MLLW");
+        let response: NoaaPredictions = self
+            .client
+            .get(NOAA_PREDICTIONS_URL)
+            .query(&[
+                ("product", "predictions"),
+                ("application", "yachtpit"),
+                ("station", noaa_id),
+                ("begin_date", &begin),
+                ("range", &range),
+                ("datum", datum),
+                ("time_zone", "gmt"),
+                ("units", "metric"),
+                ("interval", "6"),
+                ("format", "json"),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(response
+            .predictions
+            .iter()
+            .filter_map(|prediction| {
+                let time = NaiveDateTime::parse_from_str(&prediction.t, "%Y-%m-%d %H:%M").ok()?.and_utc();
+                Some(TideHeight { time, height: prediction.v.parse().ok()? })
+            })
+            .collect())
+    }
+}
+
+fn sample_times(start: DateTime<Utc>, hours: u32) -> impl Iterator<Item = DateTime<Utc>> {
+    let steps = i64::from(hours) * 60 / STEP_MINUTES;
+    (0..=steps).map(move |step| start + Duration::minutes(step * STEP_MINUTES))
+}
+
+// Highs and lows: the samples higher or lower than both neighbours.
+fn extremes(heights: &[TideHeight]) -> Vec<TideExtreme> {
+    heights
+        .windows(3)
+        .filter_map(|window| {
+            let [before, at, after] = window else {
+                return None;
+            };
+            let kind = if at.height > before.height && at.height >= after.height {
+                "high"
+            } else if at.height < before.height && at.height <= after.height {
+                "low"
+            } else {
+                return None;
+            };
+            Some(TideExtreme { time: at.time, height: at.height, kind })
+        })
+        .collect()
+}
+
+fn read_stations(path: &Path) -> Result<Vec<Station>, Box<dyn std::error::Error>> {
+    Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+}
+
+// ===== GET /tides handler =====
+#[derive(Deserialize, Debug)]
+pub struct TidesQuery {
+    lat: f64,
+    lon: f64,
+    /// How far ahead to predict, 24 hours unless asked otherwise
+    hours: Option<u32>,
+}
+
+// GET /tides?lat=&lon=: tide heights and tidal stream near the vessel from
+// the start of this hour, with the highs and lows.
+pub async fn tides(State(stations): State<Arc<TideStations>>, Query(query): Query<TidesQuery>) -> Response {
+    if !(-90.0..=90.0).contains(&query.lat) || !(-180.0..=180.0).contains(&query.lon) {
+        return (StatusCode::BAD_REQUEST, "lat and lon must be a position in degrees").into_response();
+    }
+    let hours = query.hours.unwrap_or(DEFAULT_HOURS).clamp(1, MAX_HOURS);
+    let now = Utc::now();
+    let start = now.duration_trunc(Duration::hours(1)).unwrap_or(now);
+
+    let tide = match stations.tide(query.lat, query.lon, start, hours).await {
+        Ok(tide) => tide,
+        Err(err) => {
+            tracing::warn!("Fetching tide predictions failed: {err}");
+            return (StatusCode::BAD_GATEWAY, "Tide predictions are unavailable").into_response();
+        }
+    };
+    let current = stations.current(query.lat, query.lon, start, hours);
+    if tide.is_none() && current.is_none() {
+        return (StatusCode::NOT_FOUND, "No tide or current station nearby").into_response();
+    }
+    Json(TidesAndCurrents { tide, current }).into_response()
+}
@@ -0,0 +1,304 @@
+use axum::body::Bytes;
+use axum::extract::{Path as UrlPath, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// Upstream tile server, with `{z}`, `{x}` and `{y}` placeholders
+pub const TILE_URL_ENV: &str = "BASE_MAP_TILE_URL";
+/// Directory tiles are cached in
+pub const TILE_CACHE_DIR_ENV: &str = "BASE_MAP_TILE_CACHE_DIR";
+/// Size the tile cache is kept under, in megabytes
+pub const TILE_CACHE_MB_ENV: &str = "BASE_MAP_TILE_CACHE_MB";
+
+const DEFAULT_TILE_URL: &str = "https://tile.openstreetmap.org/{z}/{x}/{y}.png";
+const DEFAULT_CACHE_MB: u64 = 512;
+
+/// Cached tiles older than this are fetched again when the upstream is reachable
+const TILE_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// Give up on the upstream after this long and answer from the cache
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_ZOOM: u32 = 22;
+
+// ===== LRU bookkeeping for the files on disk =====
+#[derive(Debug)]
+struct CachedTile {
+    bytes: u64,
+    fetched_at: SystemTime,
+    used: u64,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    tiles: HashMap<PathBuf, CachedTile>,
+    /// Tiles by when they were last used, least recent first
+    by_use: BTreeMap<u64, PathBuf>,
+    total_bytes: u64,
+    clock: u64,
+}
+
+impl CacheState {
+    // Mark `path` as just used; when it was fetched, if it is cached.
+    fn touch(&mut self, path: &Path) -> Option<SystemTime> {
+        self.clock += 1;
+        let clock = self.clock;
+        let tile = self.tiles.get_mut(path)?;
+        self.by_use.remove(&tile.used);
+        tile.used = clock;
+        self.by_use.insert(clock, path.to_path_buf());
+        Some(tile.fetched_at)
+    }
+
+    fn insert(&mut self, path: PathBuf, bytes: u64, fetched_at: SystemTime) {
+        self.forget(&path);
+        self.clock += 1;
+        self.by_use.insert(self.clock, path.clone());
+        self.total_bytes += bytes;
+        self.tiles.insert(path, CachedTile { bytes, fetched_at, used: self.clock });
+    }
+
+    fn forget(&mut self, path: &Path) {
+        if let Some(tile) = self.tiles.remove(path) {
+            self.by_use.remove(&tile.used);
+            self.total_bytes -= tile.bytes;
+        }
+    }
+
+    // Drop least recently used tiles until the cache fits in `max_bytes`;
+    // the files to delete.
+    fn evict(&mut self, max_bytes: u64) -> Vec<PathBuf> {
+        let mut evicted = Vec::new();
+        while self.total_bytes > max_bytes {
+            let Some((_, path)) = self.by_use.pop_first() else {
+                break;
+            };
+            if let Some(tile) = self.tiles.remove(&path) {
+                self.total_bytes -= tile.bytes;
+            }
+            evicted.push(path);
+        }
+        evicted
+    }
+}
+
+// ===== Tile proxy =====
+// Tiles from an upstream server, kept on disk so the chart still draws
+// when the boat is out of reach of the internet.
+pub struct TileCache {
+    upstream: String,
+    dir: PathBuf,
+    max_bytes: u64,
+    client: reqwest::Client,
+    state: Mutex<CacheState>,
+}
+
+impl TileCache {
+    // A cache in `dir`, picking up the tiles already there.
+    pub fn new(upstream: impl Into<String>, dir: impl Into<PathBuf>, max_bytes: u64) -> Self {
+        let dir = dir.into();
+        let mut found = Vec::new();
+        scan(&dir, &mut found);
+        found.sort_by_key(|(_, _, fetched_at)| *fetched_at);
+        let mut state = CacheState::default();
+        for (path, bytes, fetched_at) in found {
+            state.insert(path, bytes, fetched_at);
+        }
+        tracing::info!("Tile cache {} holds {} tiles", dir.display(), state.tiles.len());
+
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("yachtpit-base-map/", env!("CARGO_PKG_VERSION")))
+            .timeout(FETCH_TIMEOUT)
+            .build()
+            .expect("building the tile HTTP client");
+        let cache = Self {
+            upstream: upstream.into(),
+            dir,
+            max_bytes,
+            client,
+            state: Mutex::new(state),
+        };
+        cache.remove_files(cache.lock().evict(max_bytes));
+        cache
+    }
+
+    // A cache configured from BASE_MAP_TILE_URL, BASE_MAP_TILE_CACHE_DIR and
+    // BASE_MAP_TILE_CACHE_MB.
+    pub fn from_env() -> Self {
+        let upstream = std::env::var(TILE_URL_ENV).unwrap_or_else(|_| DEFAULT_TILE_URL.to_string());
+        let dir = std::env::var_os(TILE_CACHE_DIR_ENV)
+            .map(PathBuf::from)
+            .unwrap_or_else(default_cache_dir);
+        let megabytes = match std::env::var(TILE_CACHE_MB_ENV) {
+            Ok(value) => value.parse().unwrap_or_else(|_| {
+                tracing::warn!("Ignoring {TILE_CACHE_MB_ENV}={value}; expected a number of megabytes");
+                DEFAULT_CACHE_MB
+            }),
+            Err(_) => DEFAULT_CACHE_MB,
+        };
+        Self::new(upstream, dir, megabytes * 1024 * 1024)
+    }
+
+    // Tile `z/x/y`: from the cache while it is fresh, otherwise from the
+    // upstream, falling back to a stale copy when the upstream is unreachable.
+    pub async fn get(&self, z: u32, x: u32, y: u32) -> Option<Bytes> {
+        let path = self.dir.join(z.to_string()).join(x.to_string()).join(y.to_string());
+        self.get_cached(&self.upstream, path, z, x, y).await
+    }
+
+    // Tile `z/x/y` of another tile server, such as a chart's, cached under
+    // `layer` alongside the base map and sharing its size limit.
+    pub async fn get_layer(&self, layer: &str, upstream: &str, z: u32, x: u32, y: u32) -> Option<Bytes> {
+        let path = self
+            .dir
+            .join(layer)
+            .join(z.to_string())
+            .join(x.to_string())
+            .join(y.to_string());
+        self.get_cached(upstream, path, z, x, y).await
+    }
+
+    async fn get_cached(&self, upstream: &str, path: PathBuf, z: u32, x: u32, y: u32) -> Option<Bytes> {
+        let fetched_at = self.lock().touch(&path);
+        let fresh = fetched_at.is_some_and(|at| at.elapsed().is_ok_and(|age| age < TILE_MAX_AGE));
+        if fresh {
+            if let Some(tile) = self.read(&path).await {
+                return Some(tile);
+            }
+        }
+
+        match self.fetch(upstream, z, x, y).await {
+            Ok(tile) => {
+                self.store(path, &tile).await;
+                Some(tile)
+            }
+            Err(err) => {
+                tracing::warn!("Fetching tile {z}/{x}/{y} failed: {err}");
+                if fetched_at.is_some() && !fresh {
+                    self.read(&path).await
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    async fn fetch(&self, upstream: &str, z: u32, x: u32, y: u32) -> reqwest::Result<Bytes> {
+        let url = upstream
+            .replace("{z}", &z.to_string())
+            .replace("{x}", &x.to_string())
+            .replace("{y}", &y.to_string());
+        self.client.get(url).send().await?.error_for_status()?.bytes().await
+    }
+
+    async fn read(&self, path: &Path) -> Option<Bytes> {
+        match tokio::fs::read(path).await {
+            Ok(tile) => Some(tile.into()),
+            Err(_) => {
+                // Deleted behind our back
+                self.lock().forget(path);
+                None
+            }
+        }
+    }
+
+    async fn store(&self, path: PathBuf, tile: &Bytes) {
+        // Written aside and renamed, so a crash never leaves half a tile
+        let partial = path.with_extension("part");
+        let written = async {
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::write(&partial, tile).await?;
+            tokio::fs::rename(&partial, &path).await
+        };
+        if let Err(err) = written.await {
+            tracing::warn!("Caching tile {} failed: {err}", path.display());
+            return;
+        }
+        let evicted = {
+            let mut state = self.lock();
+            state.insert(path, tile.len() as u64, SystemTime::now());
+            state.evict(self.max_bytes)
+        };
+        self.remove_files(evicted);
+    }
+
+    fn remove_files(&self, paths: Vec<PathBuf>) {
+        for path in paths {
+            if let Err(err) = std::fs::remove_file(&path) {
+                tracing::warn!("Evicting tile {} failed: {err}", path.display());
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+// ===== GET /tiles/{z}/{x}/{y} handler =====
+pub async fn tile(
+    State(cache): State<Arc<TileCache>>,
+    UrlPath((z, x, y)): UrlPath<(u32, u32, String)>,
+) -> Response {
+    let Some(y) = tile_y(z, x, &y) else {
+        return (StatusCode::NOT_FOUND, "No such tile").into_response();
+    };
+    match cache.get(z, x, y).await {
+        Some(tile) => ([(header::CONTENT_TYPE, content_type(&tile))], tile).into_response(),
+        None => (StatusCode::BAD_GATEWAY, "Tile not cached and upstream unreachable").into_response(),
+    }
+}
+
+// `y` of tile `z/x/y` if it names a tile of the Web Mercator pyramid. It may
+// carry the image extension map libraries like to append.
+pub fn tile_y(z: u32, x: u32, y: &str) -> Option<u32> {
+    let y = y.split('.').next()?.parse::<u32>().ok()?;
+    (z <= MAX_ZOOM && x < 1 << z && y < 1 << z).then_some(y)
+}
+
+// The cache keeps no headers, so tell the format from the tile itself.
+fn content_type(tile: &[u8]) -> &'static str {
+    match tile {
+        [0x89, b'P', b'N', b'G', ..] => "image/png",
+        [0xFF, 0xD8, ..] => "image/jpeg",
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => "image/webp",
+        _ => "application/x-protobuf",
+    }
+}
+
+fn default_cache_dir() -> PathBuf {
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("yachtpit")
+        .join("tiles")
+}
+
+// Collect the tile files under `dir`, base map and layers alike, with their
+// size and age, and clear away partial writes.
+fn scan(dir: &Path, found: &mut Vec<(PathBuf, u64, SystemTime)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            scan(&path, found);
+        } else if metadata.is_file() {
+            if path.extension().is_some_and(|extension| extension == "part") {
+                let _ = std::fs::remove_file(&path);
+                continue;
+            }
+            let fetched_at = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            found.push((path, metadata.len(), fetched_at));
+        }
+    }
+}
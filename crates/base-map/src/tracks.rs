@@ -0,0 +1,335 @@
+use crate::great_circle_nm;
+use axum::body::Bytes;
+use axum::extract::{Path as UrlPath, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Directory uploaded tracks are stored in
+pub const TRACKS_DIR_ENV: &str = "BASE_MAP_TRACKS_DIR";
+
+/// Largest track upload accepted; a GPX of a long passage logged every few
+/// seconds runs to several megabytes
+pub const MAX_TRACK_UPLOAD: usize = 32 * 1024 * 1024;
+
+// ===== Track data =====
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrackPoint {
+    pub lat: f64,
+    pub lon: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<DateTime<Utc>>,
+}
+
+// What GET /tracks lists about each track.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrackSummary {
+    pub id: String,
+    pub name: Option<String>,
+    pub points: usize,
+    pub distance_nm: f64,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub duration_secs: Option<i64>,
+}
+
+// A track as stored on disk, one file per track.
+#[derive(Serialize, Deserialize, Debug)]
+struct StoredTrack {
+    summary: TrackSummary,
+    points: Vec<TrackPoint>,
+}
+
+#[derive(Debug)]
+pub enum TrackError {
+    Parse(String),
+    Empty,
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for TrackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrackError::Parse(err) => write!(f, "Could not read track: {err}"),
+            TrackError::Empty => write!(f, "Track has fewer than two points"),
+            TrackError::Io(err) => write!(f, "Could not store track: {err}"),
+        }
+    }
+}
+
+impl IntoResponse for TrackError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            TrackError::Parse(_) | TrackError::Empty => StatusCode::BAD_REQUEST,
+            TrackError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+// ===== Parsing uploads =====
+// A track's name and points from a GPX document or GeoJSON LineString,
+// told apart by the body since clients rarely set a useful content type.
+pub fn parse_track(body: &str) -> Result<(Option<String>, Vec<TrackPoint>), TrackError> {
+    let (name, points) = if body.trim_start().starts_with('<') {
+        parse_gpx(body)?
+    } else {
+        let geojson = serde_json::from_str::<Value>(body).map_err(|err| TrackError::Parse(err.to_string()))?;
+        parse_geojson(&geojson)?
+    };
+    if points.len() < 2 {
+        return Err(TrackError::Empty);
+    }
+    Ok((name, points))
+}
+
+// Track points of every track segment, or of a route when there is no track.
+fn parse_gpx(body: &str) -> Result<(Option<String>, Vec<TrackPoint>), TrackError> {
+    let document = roxmltree::Document::parse(body).map_err(|err| TrackError::Parse(err.to_string()))?;
+    let child_text = |node: roxmltree::Node, tag: &str| {
+        node.children()
+            .find(|child| child.has_tag_name(tag))
+            .and_then(|child| child.text())
+            .map(|text| text.trim().to_string())
+    };
+    let points_tagged = |tag: &str| -> Result<Vec<TrackPoint>, TrackError> {
+        document
+            .descendants()
+            .filter(|node| node.has_tag_name(tag))
+            .map(|node| {
+                let coordinate = |name: &str| {
+                    node.attribute(name)
+                        .and_then(|value| value.parse::<f64>().ok())
+                        .ok_or_else(|| TrackError::Parse(format!("<{tag}> without a valid {name}")))
+                };
+                Ok(TrackPoint {
+                    lat: coordinate("lat")?,
+                    lon: coordinate("lon")?,
+                    time: child_text(node, "time").and_then(|time| parse_time(&time)),
+                })
+            })
+            .collect()
+    };
+    let mut points = points_tagged("trkpt")?;
+    if points.is_empty() {
+        points = points_tagged("rtept")?;
+    }
+    let name = document
+        .descendants()
+        .find(|node| node.has_tag_name("trk") || node.has_tag_name("rte"))
+        .and_then(|track| child_text(track, "name"))
+        .or_else(|| {
+            let metadata = document.descendants().find(|node| node.has_tag_name("metadata"))?;
+            child_text(metadata, "name")
+        });
+    Ok((name, points))
+}
+
+// A LineString geometry, or a Feature or FeatureCollection holding one. Times
+// come from a `coordTimes` property, as written by most GPX converters.
+fn parse_geojson(geojson: &Value) -> Result<(Option<String>, Vec<TrackPoint>), TrackError> {
+    let feature = match geojson["type"].as_str() {
+        Some("FeatureCollection") => geojson["features"]
+            .as_array()
+            .and_then(|features| features.iter().find(|feature| feature["geometry"]["type"] == "LineString"))
+            .ok_or_else(|| TrackError::Parse("FeatureCollection holds no LineString".to_string()))?,
+        Some("Feature") => geojson,
+        Some("LineString") => &json!({"type": "Feature", "geometry": geojson, "properties": {}}),
+        other => return Err(TrackError::Parse(format!("expected a LineString, got {other:?}"))),
+    };
+    let geometry = &feature["geometry"];
+    if geometry["type"] != "LineString" {
+        return Err(TrackError::Parse(format!("expected a LineString, got {}", geometry["type"])));
+    }
+    let times = feature["properties"]["coordTimes"].as_array();
+    let points = geometry["coordinates"]
+        .as_array()
+        .ok_or_else(|| TrackError::Parse("LineString without coordinates".to_string()))?
+        .iter()
+        .enumerate()
+        .map(|(index, position)| {
+            // GeoJSON positions are [longitude, latitude, elevation?]
+            match (position[0].as_f64(), position[1].as_f64()) {
+                (Some(lon), Some(lat)) => Ok(TrackPoint {
+                    lat,
+                    lon,
+                    time: times
+                        .and_then(|times| times.get(index))
+                        .and_then(Value::as_str)
+                        .and_then(parse_time),
+                }),
+                _ => Err(TrackError::Parse(format!("invalid position {position}"))),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let name = feature["properties"]["name"].as_str().map(str::to_string);
+    Ok((name, points))
+}
+
+fn parse_time(time: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(time).ok().map(|time| time.with_timezone(&Utc))
+}
+
+// Great-circle length of the track, in nautical miles.
+fn distance_nm(points: &[TrackPoint]) -> f64 {
+    points
+        .windows(2)
+        .map(|leg| great_circle_nm((leg[0].lat, leg[0].lon), (leg[1].lat, leg[1].lon)))
+        .sum()
+}
+
+fn summarize(id: String, name: Option<String>, points: &[TrackPoint]) -> TrackSummary {
+    let started_at = points.iter().find_map(|point| point.time);
+    let finished_at = points.iter().rev().find_map(|point| point.time);
+    TrackSummary {
+        id,
+        name,
+        points: points.len(),
+        distance_nm: (distance_nm(points) * 1000.0).round() / 1000.0,
+        started_at,
+        finished_at,
+        duration_secs: started_at.zip(finished_at).map(|(start, end)| (end - start).num_seconds()),
+    }
+}
+
+// ===== Storage =====
+// Uploaded tracks, one JSON file each under `dir`, with their summaries
+// kept in memory for listing.
+pub struct TrackStore {
+    dir: PathBuf,
+    summaries: Mutex<HashMap<String, TrackSummary>>,
+}
+
+impl TrackStore {
+    // A store in `dir`, picking up the tracks already there.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        let mut summaries = HashMap::new();
+        if let Ok(entries) = std::fs::read_dir(&dir) {
+            for path in entries.flatten().map(|entry| entry.path()) {
+                if path.extension().is_none_or(|extension| extension != "json") {
+                    continue;
+                }
+                match read_track(&path) {
+                    Ok(track) => {
+                        summaries.insert(track.summary.id.clone(), track.summary);
+                    }
+                    Err(err) => tracing::warn!("Skipping track {}: {err}", path.display()),
+                }
+            }
+        }
+        tracing::info!("Track store {} holds {} tracks", dir.display(), summaries.len());
+        Self {
+            dir,
+            summaries: Mutex::new(summaries),
+        }
+    }
+
+    // A store in BASE_MAP_TRACKS_DIR.
+    pub fn from_env() -> Self {
+        let dir = std::env::var_os(TRACKS_DIR_ENV)
+            .map(PathBuf::from)
+            .unwrap_or_else(default_tracks_dir);
+        Self::new(dir)
+    }
+
+    pub async fn insert(&self, name: Option<String>, points: Vec<TrackPoint>) -> Result<TrackSummary, TrackError> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let track = StoredTrack {
+            summary: summarize(id.clone(), name, &points),
+            points,
+        };
+        let contents = serde_json::to_vec(&track).map_err(|err| TrackError::Io(err.into()))?;
+        tokio::fs::create_dir_all(&self.dir).await.map_err(TrackError::Io)?;
+        tokio::fs::write(self.path(&id), contents).await.map_err(TrackError::Io)?;
+        self.lock().insert(id, track.summary.clone());
+        Ok(track.summary)
+    }
+
+    // Every stored track, most recently sailed first.
+    pub fn list(&self) -> Vec<TrackSummary> {
+        let mut summaries: Vec<TrackSummary> = self.lock().values().cloned().collect();
+        summaries.sort_by(|a, b| b.started_at.cmp(&a.started_at).then_with(|| a.id.cmp(&b.id)));
+        summaries
+    }
+
+    async fn get(&self, id: &str) -> Option<StoredTrack> {
+        if !self.lock().contains_key(id) {
+            return None;
+        }
+        let contents = tokio::fs::read(self.path(id)).await.ok()?;
+        serde_json::from_slice(&contents).ok()
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.json"))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, TrackSummary>> {
+        self.summaries.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn read_track(path: &Path) -> Result<StoredTrack, Box<dyn std::error::Error>> {
+    Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+}
+
+fn default_tracks_dir() -> PathBuf {
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("yachtpit")
+        .join("tracks")
+}
+
+// ===== /tracks handlers =====
+// POST /tracks with a GPX or GeoJSON body; an X-Track-Name header wins
+// over the name in the file.
+pub async fn upload_track(State(store): State<Arc<TrackStore>>, headers: HeaderMap, body: Bytes) -> Response {
+    let body = match std::str::from_utf8(&body) {
+        Ok(body) => body,
+        Err(_) => return TrackError::Parse("body is not UTF-8".to_string()).into_response(),
+    };
+    let (name, points) = match parse_track(body) {
+        Ok(track) => track,
+        Err(err) => return err.into_response(),
+    };
+    let name = headers
+        .get("x-track-name")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+        .or(name);
+    match store.insert(name, points).await {
+        Ok(summary) => (StatusCode::CREATED, [(header::LOCATION, format!("/tracks/{}", summary.id))], Json(summary)).into_response(),
+        Err(err) => err.into_response(),
+    }
+}
+
+pub async fn list_tracks(State(store): State<Arc<TrackStore>>) -> Json<Vec<TrackSummary>> {
+    Json(store.list())
+}
+
+// GET /tracks/{id}: the track as a GeoJSON Feature, ready to draw.
+pub async fn get_track(State(store): State<Arc<TrackStore>>, UrlPath(id): UrlPath<String>) -> Response {
+    let Some(track) = store.get(&id).await else {
+        return (StatusCode::NOT_FOUND, format!("No track {id}")).into_response();
+    };
+    let coordinates: Vec<[f64; 2]> = track.points.iter().map(|point| [point.lon, point.lat]).collect();
+    let coord_times: Vec<Option<DateTime<Utc>>> = track.points.iter().map(|point| point.time).collect();
+    let mut properties = serde_json::to_value(&track.summary).unwrap_or_default();
+    properties["coordTimes"] = json!(coord_times);
+    Json(json!({
+        "type": "Feature",
+        "id": track.summary.id,
+        "geometry": {"type": "LineString", "coordinates": coordinates},
+        "properties": properties,
+    }))
+    .into_response()
+}
@@ -0,0 +1,398 @@
+use crate::grib::{self, Field};
+use crate::parse_bbox;
+use crate::tiles::tile_y;
+use axum::extract::{Path as UrlPath, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use image::{ImageFormat, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// GRIB2 file to download, with optional `{date}`, `{cycle}` and `{hour}`
+/// placeholders for the model run and forecast hour
+pub const GRIB_URL_ENV: &str = "BASE_MAP_GRIB_URL";
+/// Directory of GRIB2 files loaded at startup
+pub const GRIB_DIR_ENV: &str = "BASE_MAP_GRIB_DIR";
+/// How many hours ahead to download
+pub const GRIB_HOURS_ENV: &str = "BASE_MAP_GRIB_HOURS";
+
+const DEFAULT_HOURS: u32 = 48;
+/// Forecast hours downloaded for a `{hour}` URL
+const HOUR_STEP: u32 = 3;
+/// Models run every six hours and take about this long to publish
+const RUN_INTERVAL_HOURS: i64 = 6;
+const PUBLISH_DELAY: Duration = Duration::hours(5);
+const REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30 * 60);
+const FETCH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(120);
+/// Forecasts for times further back than this are dropped
+const KEEP_PAST: Duration = Duration::hours(6);
+/// Points along each side of the box on /weather/points
+const POINTS_PER_SIDE: usize = 24;
+const TILE_SIZE: u32 = 256;
+const OVERLAY_ALPHA: u8 = 160;
+const MS_TO_KNOTS: f64 = 1.943_844;
+
+// ===== Forecasts =====
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WeatherLayer {
+    /// 10 m wind speed in knots
+    Wind,
+    /// Mean sea level pressure in hPa
+    Pressure,
+    /// Significant wave height in metres
+    Waves,
+}
+
+impl WeatherLayer {
+    // Colour stops for the raster overlay, in the layer's units.
+    fn ramp(self) -> &'static [(f64, [u8; 3])] {
+        match self {
+            Self::Wind => &[
+                (0.0, [60, 80, 200]),
+                (10.0, [40, 180, 170]),
+                (20.0, [90, 200, 60]),
+                (30.0, [240, 200, 40]),
+                (40.0, [230, 80, 40]),
+                (55.0, [170, 40, 140]),
+            ],
+            Self::Pressure => &[
+                (970.0, [120, 60, 180]),
+                (990.0, [60, 120, 220]),
+                (1010.0, [80, 200, 150]),
+                (1025.0, [240, 200, 60]),
+                (1040.0, [220, 80, 60]),
+            ],
+            Self::Waves => &[
+                (0.0, [60, 80, 200]),
+                (1.0, [40, 180, 170]),
+                (2.0, [90, 200, 60]),
+                (3.0, [240, 200, 40]),
+                (5.0, [230, 80, 40]),
+                (8.0, [170, 40, 140]),
+            ],
+        }
+    }
+}
+
+// The fields of one forecast time this service draws.
+#[derive(Default, Clone)]
+pub struct Forecast {
+    wind_u: Option<Arc<Field>>,
+    wind_v: Option<Arc<Field>>,
+    pressure: Option<Arc<Field>>,
+    waves: Option<Arc<Field>>,
+}
+
+impl Forecast {
+    // The slot `field` fills, if it is one of the parameters drawn.
+    fn slot(&mut self, field: &Field) -> Option<&mut Option<Arc<Field>>> {
+        match (field.discipline, field.category, field.number, field.surface) {
+            (0, 2, 2, 103) if field.level == 10.0 => Some(&mut self.wind_u),
+            (0, 2, 3, 103) if field.level == 10.0 => Some(&mut self.wind_v),
+            // PRMSL, or MSL from models that give pressure at sea level
+            (0, 3, 1, _) | (0, 3, 0, 101) => Some(&mut self.pressure),
+            // HTSGW
+            (10, 0, 3, _) => Some(&mut self.waves),
+            _ => None,
+        }
+    }
+
+    pub fn layers(&self) -> Vec<WeatherLayer> {
+        let mut layers = Vec::new();
+        if self.wind_u.is_some() && self.wind_v.is_some() {
+            layers.push(WeatherLayer::Wind);
+        }
+        if self.pressure.is_some() {
+            layers.push(WeatherLayer::Pressure);
+        }
+        if self.waves.is_some() {
+            layers.push(WeatherLayer::Waves);
+        }
+        layers
+    }
+
+    // When the model run behind this forecast started.
+    fn reference_time(&self) -> Option<DateTime<Utc>> {
+        [&self.wind_u, &self.wind_v, &self.pressure, &self.waves]
+            .into_iter()
+            .flatten()
+            .map(|field| field.reference_time)
+            .max()
+    }
+
+    // Wind speed in knots and the direction it blows from, in degrees true.
+    pub fn wind(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        let u = f64::from(self.wind_u.as_ref()?.sample(lat, lon)?);
+        let v = f64::from(self.wind_v.as_ref()?.sample(lat, lon)?);
+        Some((u.hypot(v) * MS_TO_KNOTS, (-u).atan2(-v).to_degrees().rem_euclid(360.0)))
+    }
+
+    // The layer's value at `lat`/`lon`, in the units the layer is drawn in.
+    pub fn value(&self, layer: WeatherLayer, lat: f64, lon: f64) -> Option<f64> {
+        match layer {
+            WeatherLayer::Wind => self.wind(lat, lon).map(|(speed, _)| speed),
+            WeatherLayer::Pressure => Some(f64::from(self.pressure.as_ref()?.sample(lat, lon)?) / 100.0),
+            WeatherLayer::Waves => Some(f64::from(self.waves.as_ref()?.sample(lat, lon)?)),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ForecastSummary {
+    pub time: DateTime<Utc>,
+    pub reference_time: Option<DateTime<Utc>>,
+    pub layers: Vec<WeatherLayer>,
+}
+
+// Forecasts by valid time, from GRIB files loaded or downloaded. A newer
+// model run replaces an older one's fields for the same time.
+#[derive(Default)]
+pub struct WeatherStore {
+    forecasts: Mutex<BTreeMap<DateTime<Utc>, Forecast>>,
+}
+
+impl WeatherStore {
+    // Take in the fields of a GRIB file, returning how many were kept.
+    pub fn add(&self, fields: Vec<Field>) -> usize {
+        let mut forecasts = self.lock();
+        let mut kept = 0;
+        for field in fields {
+            let forecast = forecasts.entry(field.valid_time).or_default();
+            let Some(slot) = forecast.slot(&field) else {
+                continue;
+            };
+            if slot.as_ref().is_some_and(|existing| existing.reference_time > field.reference_time) {
+                continue;
+            }
+            *slot = Some(Arc::new(field));
+            kept += 1;
+        }
+        forecasts.retain(|_, forecast| !forecast.layers().is_empty());
+        kept
+    }
+
+    // Load every GRIB file in `dir`, such as forecasts received by email.
+    pub fn load_dir(&self, dir: &Path) {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                tracing::warn!("Ignoring GRIB directory {}: {err}", dir.display());
+                return;
+            }
+        };
+        for path in entries.flatten().map(|entry| entry.path()).filter(|path| path.is_file()) {
+            match std::fs::read(&path).map_err(|err| err.to_string()).and_then(|bytes| grib::parse(&bytes).map_err(|err| err.to_string())) {
+                Ok(fields) => tracing::info!("Loaded {} weather fields from {}", self.add(fields), path.display()),
+                Err(err) => tracing::warn!("Ignoring GRIB file {}: {err}", path.display()),
+            }
+        }
+    }
+
+    // Drop forecasts for times before `before`.
+    pub fn prune(&self, before: DateTime<Utc>) {
+        self.lock().retain(|time, _| *time >= before);
+    }
+
+    // The forecast nearest `time` and the time it is for.
+    pub fn at(&self, time: DateTime<Utc>) -> Option<(DateTime<Utc>, Forecast)> {
+        let forecasts = self.lock();
+        let after = forecasts.range(time..).next();
+        let before = forecasts.range(..time).next_back();
+        [after, before]
+            .into_iter()
+            .flatten()
+            .min_by_key(|(valid, _)| (**valid - time).abs())
+            .map(|(valid, forecast)| (*valid, forecast.clone()))
+    }
+
+    pub fn times(&self) -> Vec<ForecastSummary> {
+        self.lock()
+            .iter()
+            .map(|(time, forecast)| ForecastSummary {
+                time: *time,
+                reference_time: forecast.reference_time(),
+                layers: forecast.layers(),
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<DateTime<Utc>, Forecast>> {
+        self.forecasts.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+// ===== Downloading =====
+pub fn grib_url() -> Option<String> {
+    std::env::var(GRIB_URL_ENV).ok().filter(|url| !url.is_empty())
+}
+
+// The URLs of model run `run`, one per forecast hour when `url` has an
+// `{hour}` placeholder.
+fn run_urls(url: &str, run: DateTime<Utc>, hours: u32) -> Vec<String> {
+    let url = url
+        .replace("{date}", &run.format("%Y%m%d").to_string())
+        .replace("{cycle}", &run.format("%H").to_string());
+    if !url.contains("{hour}") {
+        return vec![url];
+    }
+    (0..=hours)
+        .step_by(HOUR_STEP as usize)
+        .map(|hour| url.replace("{hour}", &format!("{hour:03}")))
+        .collect()
+}
+
+// Keep `store` up to date with the latest published run at `url`, dropping
+// forecasts that have gone by.
+pub async fn follow_forecasts(store: Arc<WeatherStore>, url: String) {
+    let hours = std::env::var(GRIB_HOURS_ENV)
+        .ok()
+        .and_then(|hours| hours.parse().ok())
+        .unwrap_or(DEFAULT_HOURS);
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("yachtpit-base-map/", env!("CARGO_PKG_VERSION")))
+        .timeout(FETCH_TIMEOUT)
+        .build()
+        .expect("building the GRIB HTTP client");
+    let mut fetched_run = None;
+    loop {
+        let now = Utc::now();
+        let run = (now - PUBLISH_DELAY).duration_trunc(Duration::hours(RUN_INTERVAL_HOURS)).unwrap_or(now);
+        if fetched_run != Some(run) {
+            let mut kept = 0;
+            for url in run_urls(&url, run, hours) {
+                match fetch_grib(&client, &url).await {
+                    Ok(fields) => kept += store.add(fields),
+                    Err(err) => tracing::warn!("Could not download GRIB {url}: {err}"),
+                }
+            }
+            tracing::info!("Loaded {kept} weather fields from the {run} run");
+            if kept > 0 {
+                fetched_run = Some(run);
+            }
+        }
+        store.prune(Utc::now() - KEEP_PAST);
+        tokio::time::sleep(REFRESH_INTERVAL).await;
+    }
+}
+
+async fn fetch_grib(client: &reqwest::Client, url: &str) -> Result<Vec<Field>, Box<dyn std::error::Error + Send + Sync>> {
+    let bytes = client.get(url).send().await?.error_for_status()?.bytes().await?;
+    Ok(tokio::task::spawn_blocking(move || grib::parse(&bytes)).await??)
+}
+
+// ===== Rendering =====
+// Tile `z/x/y` of `layer` as a translucent PNG, clear where the forecast
+// has no value.
+fn render_tile(forecast: &Forecast, layer: WeatherLayer, z: u32, x: u32, y: u32) -> Option<Vec<u8>> {
+    let world = f64::from(TILE_SIZE) * f64::from(1u32 << z);
+    let image = RgbaImage::from_fn(TILE_SIZE, TILE_SIZE, |px, py| {
+        let lon = (f64::from(x * TILE_SIZE + px) + 0.5) / world * 360.0 - 180.0;
+        let lat = (PI * (1.0 - 2.0 * (f64::from(y * TILE_SIZE + py) + 0.5) / world)).sinh().atan().to_degrees();
+        match forecast.value(layer, lat, lon) {
+            Some(value) => {
+                let [r, g, b] = colour(layer.ramp(), value);
+                Rgba([r, g, b, OVERLAY_ALPHA])
+            }
+            None => Rgba([0, 0, 0, 0]),
+        }
+    });
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png).ok()?;
+    Some(png)
+}
+
+// `value` on a ramp of colour stops, held at the ends.
+fn colour(stops: &[(f64, [u8; 3])], value: f64) -> [u8; 3] {
+    let upper = stops.iter().position(|(stop, _)| value < *stop).unwrap_or(stops.len());
+    if upper == 0 || upper == stops.len() {
+        return stops[upper.min(stops.len() - 1)].1;
+    }
+    let ((low, from), (high, to)) = (stops[upper - 1], stops[upper]);
+    let t = (value - low) / (high - low);
+    std::array::from_fn(|channel| (f64::from(from[channel]) + (f64::from(to[channel]) - f64::from(from[channel])) * t).round() as u8)
+}
+
+// ===== /weather handlers =====
+#[derive(Deserialize, Debug)]
+pub struct WeatherQuery {
+    /// Forecast time wanted, now unless given
+    time: Option<DateTime<Utc>>,
+    /// Viewport as "west,south,east,north"
+    bbox: Option<String>,
+}
+
+// GET /weather: the forecast times held and the layers each has.
+pub async fn list_forecasts(State(store): State<Arc<WeatherStore>>) -> Response {
+    Json(store.times()).into_response()
+}
+
+// GET /weather/tiles/{layer}/{z}/{x}/{y}?time=: the layer for the forecast
+// nearest `time`, drawn over the chart.
+pub async fn weather_tile(
+    State(store): State<Arc<WeatherStore>>,
+    UrlPath((layer, z, x, y)): UrlPath<(WeatherLayer, u32, u32, String)>,
+    Query(query): Query<WeatherQuery>,
+) -> Response {
+    let Some(y) = tile_y(z, x, &y) else {
+        return (StatusCode::NOT_FOUND, "No such tile").into_response();
+    };
+    let Some((time, forecast)) = store.at(query.time.unwrap_or_else(Utc::now)) else {
+        return (StatusCode::NOT_FOUND, "No forecast loaded").into_response();
+    };
+    if !forecast.layers().contains(&layer) {
+        return (StatusCode::NOT_FOUND, "The forecast has no such layer").into_response();
+    }
+    match tokio::task::spawn_blocking(move || render_tile(&forecast, layer, z, x, y)).await.ok().flatten() {
+        Some(png) => (
+            [(header::CONTENT_TYPE, "image/png".to_string()), (header::HeaderName::from_static("x-forecast-time"), time.to_rfc3339())],
+            png,
+        )
+            .into_response(),
+        None => (StatusCode::INTERNAL_SERVER_ERROR, "Could not draw the tile").into_response(),
+    }
+}
+
+// GET /weather/points?bbox=&time=: wind, pressure and waves sampled across
+// the box as GeoJSON points, for wind barbs and readouts.
+pub async fn weather_points(State(store): State<Arc<WeatherStore>>, Query(query): Query<WeatherQuery>) -> Response {
+    let Some([west, south, east, north]) = query.bbox.as_deref().and_then(parse_bbox) else {
+        return (StatusCode::BAD_REQUEST, "bbox must be west,south,east,north").into_response();
+    };
+    let Some((time, forecast)) = store.at(query.time.unwrap_or_else(Utc::now)) else {
+        return (StatusCode::NOT_FOUND, "No forecast loaded").into_response();
+    };
+    let step = |from: f64, to: f64, index: usize| from + (to - from) * (index as f64 + 0.5) / POINTS_PER_SIDE as f64;
+    let mut features = Vec::new();
+    for row in 0..POINTS_PER_SIDE {
+        for column in 0..POINTS_PER_SIDE {
+            let (lat, lon) = (step(south, north, row), step(west, east, column));
+            let mut properties = Map::new();
+            if let Some((speed, direction)) = forecast.wind(lat, lon) {
+                properties.insert("wind_speed".into(), json!(speed));
+                properties.insert("wind_direction".into(), json!(direction));
+            }
+            for (name, layer) in [("pressure", WeatherLayer::Pressure), ("wave_height", WeatherLayer::Waves)] {
+                if let Some(value) = forecast.value(layer, lat, lon) {
+                    properties.insert(name.into(), json!(value));
+                }
+            }
+            if properties.is_empty() {
+                continue;
+            }
+            features.push(json!({
+                "type": "Feature",
+                "geometry": {"type": "Point", "coordinates": [lon, lat]},
+                "properties": Value::Object(properties),
+            }));
+        }
+    }
+    Json(json!({"type": "FeatureCollection", "time": time, "features": features})).into_response()
+}
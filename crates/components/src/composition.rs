@@ -1,7 +1,11 @@
+//! Composition utilities for building instrument cluster components
+//! This module provides reusable building blocks for creating UI components
+
+pub mod template;
+
 use bevy::prelude::*;
 
-/// Composition utilities for building instrument cluster components
-/// This module provides reusable building blocks for creating UI components
+pub use template::*;
 
 /// Creates a circular gauge node bundle
 pub fn circular_gauge_node() -> Node {
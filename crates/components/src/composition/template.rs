@@ -0,0 +1,372 @@
+//! Declarative panel templates
+//!
+//! A panel is described as data: rows of gauges, readouts and labels, each
+//! readout bound to a vessel data field. Templates are written in RON, for
+//! example
+//!
+//! ```ron
+//! Panel(
+//!     name: "Passage",
+//!     rows: [
+//!         Row(height: 50.0, items: [
+//!             Gauge(label: "SOG", bind: Speed),
+//!             Readout(label: "DEPTH", bind: Depth, unit: Some(Feet), decimals: 0),
+//!         ]),
+//!         Row(height: 20.0, items: [Label(text: "ENGINE ROOM", size: Small)]),
+//!     ],
+//! )
+//! ```
+//!
+//! Panels loaded from a file remember its modification time, so
+//! [`hot_reload_panels`] can rebuild them while the file is being edited.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use crate::data_box::{DataField, DataUnit, MAX_DECIMALS};
+use crate::theme::*;
+use crate::vessel_data::VesselData;
+use super::{circular_gauge_node, create_text, row_container_node, status_panel_node};
+
+/// How often [`hot_reload_panels`] checks template files for changes
+pub const PANEL_RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Why a template could not be loaded
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateError {
+    Io(String),
+    Parse(String),
+    Invalid(String),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::Io(e) => write!(f, "failed to read panel template: {}", e),
+            TemplateError::Parse(e) => write!(f, "failed to parse panel template: {}", e),
+            TemplateError::Invalid(e) => write!(f, "invalid panel template: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// Text size of a label
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TextSize {
+    Small,
+    #[default]
+    Normal,
+    Large,
+}
+
+impl TextSize {
+    pub fn font_size(self) -> f32 {
+        match self {
+            TextSize::Small => FONT_SIZE_SMALL,
+            TextSize::Normal => FONT_SIZE_NORMAL,
+            TextSize::Large => FONT_SIZE_LARGE,
+        }
+    }
+}
+
+fn default_decimals() -> u8 {
+    1
+}
+
+fn default_padding() -> f32 {
+    20.0
+}
+
+/// One element of a row
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WidgetTemplate {
+    /// Round gauge with caption, value and unit
+    Gauge {
+        label: String,
+        bind: DataField,
+        /// Defaults to the base unit of the bound field
+        #[serde(default)]
+        unit: Option<DataUnit>,
+        #[serde(default = "default_decimals")]
+        decimals: u8,
+    },
+    /// Compact boxed value, as on the data box row
+    Readout {
+        label: String,
+        bind: DataField,
+        #[serde(default)]
+        unit: Option<DataUnit>,
+        #[serde(default = "default_decimals")]
+        decimals: u8,
+    },
+    /// Static text
+    Label {
+        text: String,
+        #[serde(default)]
+        size: TextSize,
+    },
+}
+
+impl WidgetTemplate {
+    /// The value this widget displays, if it is bound to one
+    pub fn binding(&self) -> Option<BoundValue> {
+        match self {
+            WidgetTemplate::Gauge { bind, unit, decimals, .. } | WidgetTemplate::Readout { bind, unit, decimals, .. } => {
+                Some(BoundValue {
+                    field: *bind,
+                    unit: unit.unwrap_or(DataUnit::for_quantity(bind.quantity())[0]),
+                    decimals: *decimals,
+                })
+            }
+            WidgetTemplate::Label { .. } => None,
+        }
+    }
+}
+
+/// A horizontal row of widgets
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "Row")]
+pub struct RowTemplate {
+    /// Percent of the panel height
+    pub height: f32,
+    #[serde(default = "default_padding")]
+    pub padding: f32,
+    pub items: Vec<WidgetTemplate>,
+}
+
+/// An instrument page described as data
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "Panel")]
+pub struct PanelTemplate {
+    pub name: String,
+    pub rows: Vec<RowTemplate>,
+}
+
+impl PanelTemplate {
+    /// Parse and validate a RON template
+    pub fn parse(source: &str) -> Result<Self, TemplateError> {
+        let template: Self = ron::from_str(source).map_err(|e| TemplateError::Parse(e.to_string()))?;
+        template.validate()?;
+        Ok(template)
+    }
+
+    /// Load a RON template from a file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, TemplateError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path).map_err(|e| TemplateError::Io(format!("{}: {}", path.display(), e)))?;
+        Self::parse(&source)
+    }
+
+    /// Check that rows fit the panel and every binding can be displayed as written
+    pub fn validate(&self) -> Result<(), TemplateError> {
+        let total_height: f32 = self.rows.iter().map(|row| row.height).sum();
+        if self.rows.iter().any(|row| row.height <= 0.0) || total_height > 100.0 {
+            return Err(TemplateError::Invalid(format!(
+                "row heights must be positive and add up to at most 100%, got {}%",
+                total_height
+            )));
+        }
+        for binding in self.rows.iter().flat_map(|row| &row.items).filter_map(WidgetTemplate::binding) {
+            if !DataUnit::for_quantity(binding.field.quantity()).contains(&binding.unit) {
+                return Err(TemplateError::Invalid(format!(
+                    "{} cannot be shown in {}",
+                    binding.field.name(),
+                    binding.unit.label()
+                )));
+            }
+            if binding.decimals > MAX_DECIMALS {
+                return Err(TemplateError::Invalid(format!(
+                    "{} decimals requested for {}, at most {} are shown",
+                    binding.decimals,
+                    binding.field.name(),
+                    MAX_DECIMALS
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Value text of a templated widget, refreshed by [`update_bound_values`]
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct BoundValue {
+    pub field: DataField,
+    pub unit: DataUnit,
+    pub decimals: u8,
+}
+
+impl BoundValue {
+    pub fn format(&self, vessel_data: &VesselData) -> String {
+        let value = self.unit.from_base(self.field.value(vessel_data));
+        format!("{:.*}", self.decimals as usize, value)
+    }
+}
+
+/// Root of a panel spawned from a template file
+#[derive(Component, Debug)]
+pub struct TemplatedPanel {
+    pub path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl TemplatedPanel {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let modified = modified_at(&path);
+        Self { path, modified }
+    }
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Node a template's rows are spawned into
+pub fn panel_root_node() -> Node {
+    Node {
+        width: Val::Percent(100.0),
+        flex_direction: FlexDirection::Column,
+        ..default()
+    }
+}
+
+/// Spawn the rows of a template as children of `parent`
+pub fn spawn_panel_rows(parent: &mut ChildSpawnerCommands, template: &PanelTemplate) {
+    for row in &template.rows {
+        parent.spawn(row_container_node(row.height, row.padding)).with_children(|row_node| {
+            for widget in &row.items {
+                spawn_widget(row_node, widget);
+            }
+        });
+    }
+}
+
+fn spawn_widget(parent: &mut ChildSpawnerCommands, widget: &WidgetTemplate) {
+    match widget {
+        WidgetTemplate::Gauge { label, .. } | WidgetTemplate::Readout { label, .. } => {
+            let Some(binding) = widget.binding() else {
+                return;
+            };
+            let (node, value_size) = match widget {
+                WidgetTemplate::Gauge { .. } => (circular_gauge_node(), FONT_SIZE_LARGE),
+                _ => (status_panel_node(120.0, 80.0), FONT_SIZE_NORMAL),
+            };
+            parent
+                .spawn((node, BackgroundColor(BACKGROUND_COLOR_TRANSPARENT), BorderColor(BORDER_COLOR_PRIMARY)))
+                .with_children(|panel| {
+                    panel.spawn(create_text(label, FONT_SIZE_SMALL, TEXT_COLOR_PRIMARY));
+                    panel.spawn((create_text("--", value_size, TEXT_COLOR_SUCCESS), binding));
+                    panel.spawn(create_text(binding.unit.label(), FONT_SIZE_SMALL, TEXT_COLOR_SECONDARY));
+                });
+        }
+        WidgetTemplate::Label { text, size } => {
+            parent.spawn(create_text(text, size.font_size(), TEXT_COLOR_PRIMARY));
+        }
+    }
+}
+
+/// Load a template file and spawn it as a child of `parent`
+pub fn spawn_panel_from_file(commands: &mut Commands, parent: Entity, path: impl Into<PathBuf>) -> Result<Entity, TemplateError> {
+    let panel = TemplatedPanel::new(path);
+    let template = PanelTemplate::load(&panel.path)?;
+    info!("Loaded panel template '{}' from {}", template.name, panel.path.display());
+    let root = commands
+        .spawn((panel_root_node(), panel))
+        .with_children(|root| spawn_panel_rows(root, &template))
+        .id();
+    commands.entity(parent).add_child(root);
+    Ok(root)
+}
+
+/// Refreshes the values of templated widgets
+pub fn update_bound_values(vessel_data: Res<VesselData>, mut values: Query<(&BoundValue, &mut Text)>) {
+    for (binding, mut text) in values.iter_mut() {
+        let formatted = binding.format(&vessel_data);
+        if text.0 != formatted {
+            text.0 = formatted;
+        }
+    }
+}
+
+/// Rebuilds templated panels whose file changed. A template that no longer
+/// parses is reported and the panel keeps its previous layout.
+pub fn hot_reload_panels(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut since_check: Local<Duration>,
+    mut panels: Query<(Entity, &mut TemplatedPanel)>,
+) {
+    *since_check += time.delta();
+    if *since_check < PANEL_RELOAD_INTERVAL {
+        return;
+    }
+    *since_check = Duration::ZERO;
+
+    for (entity, mut panel) in panels.iter_mut() {
+        let modified = modified_at(&panel.path);
+        if modified == panel.modified {
+            continue;
+        }
+        panel.modified = modified;
+        match PanelTemplate::load(&panel.path) {
+            Ok(template) => {
+                info!("Reloading panel template '{}'", template.name);
+                commands.entity(entity).despawn_related::<Children>();
+                commands.entity(entity).with_children(|root| spawn_panel_rows(root, &template));
+            }
+            Err(e) => warn!("Keeping previous panel layout: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSAGE: &str = r#"
+        Panel(
+            name: "Passage",
+            rows: [
+                Row(height: 50.0, items: [
+                    Gauge(label: "SOG", bind: Speed),
+                    Readout(label: "DEPTH", bind: Depth, unit: Some(Feet), decimals: 0),
+                ]),
+                Row(height: 20.0, padding: 4.0, items: [Label(text: "ENGINE ROOM", size: Small)]),
+            ],
+        )
+    "#;
+
+    #[test]
+    fn test_panel_template_parses_and_binds() {
+        let template = PanelTemplate::parse(PASSAGE).unwrap();
+        assert_eq!(template.name, "Passage");
+        assert_eq!(template.rows[0].padding, 20.0);
+        assert_eq!(template.rows[1].padding, 4.0);
+
+        let vessel_data = VesselData::default();
+        let sog = template.rows[0].items[0].binding().unwrap();
+        assert_eq!((sog.unit, sog.format(&vessel_data)), (DataUnit::Knots, "12.5".to_string()));
+        let depth = template.rows[0].items[1].binding().unwrap();
+        assert_eq!(depth.format(&vessel_data), "50");
+        assert_eq!(template.rows[1].items[0].binding(), None);
+
+        // Round trip through RON keeps the template intact
+        let written = ron::to_string(&template).unwrap();
+        assert_eq!(PanelTemplate::parse(&written).unwrap(), template);
+    }
+
+    #[test]
+    fn test_panel_template_rejects_bad_bindings() {
+        let wrong_unit = PASSAGE.replace("unit: Some(Feet)", "unit: Some(Celsius)");
+        assert!(matches!(PanelTemplate::parse(&wrong_unit), Err(TemplateError::Invalid(_))));
+
+        let too_tall = PASSAGE.replace("height: 50.0", "height: 90.0");
+        assert!(matches!(PanelTemplate::parse(&too_tall), Err(TemplateError::Invalid(_))));
+
+        let unknown_field = PASSAGE.replace("bind: Speed", "bind: Rpm");
+        assert!(matches!(PanelTemplate::parse(&unknown_field), Err(TemplateError::Parse(_))));
+    }
+}
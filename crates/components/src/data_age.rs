@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use super::theme::*;
+use super::vessel_data::{DataChannel, VesselData};
+
+/// Default age in seconds after which an instrument is shown as stale
+pub const DEFAULT_STALE_AFTER_SECS: f32 = 3.0;
+
+/// Alpha applied to the text of an instrument whose data is stale
+const STALE_TEXT_ALPHA: f32 = 0.35;
+
+/// Configurable staleness threshold for instrument data
+#[derive(Resource)]
+pub struct DataAgeConfig {
+    pub stale_after_secs: f32,
+}
+
+impl Default for DataAgeConfig {
+    fn default() -> Self {
+        Self {
+            stale_after_secs: DEFAULT_STALE_AFTER_SECS,
+        }
+    }
+}
+
+/// Binds an instrument panel to the data channel it displays
+#[derive(Component)]
+pub struct DataAgeIndicator {
+    pub channel: DataChannel,
+}
+
+impl DataAgeIndicator {
+    pub fn new(channel: DataChannel) -> Self {
+        Self { channel }
+    }
+}
+
+/// Whether a channel last updated at `updated_at` is stale at `now`
+pub fn is_stale(updated_at: Option<f32>, now: f32, stale_after_secs: f32) -> bool {
+    updated_at.is_some_and(|updated_at| now - updated_at > stale_after_secs)
+}
+
+/// Dims instruments (border and text) whose channel has not been updated recently
+pub fn update_data_age_indicators(
+    time: Res<Time>,
+    vessel_data: Res<VesselData>,
+    config: Res<DataAgeConfig>,
+    mut panels: Query<(&DataAgeIndicator, &mut BorderColor, &Children)>,
+    mut texts: Query<&mut TextColor>,
+) {
+    let now = time.elapsed_secs();
+
+    for (indicator, mut border, children) in panels.iter_mut() {
+        let updated_at = vessel_data
+            .channel_state(indicator.channel)
+            .map(|state| state.updated_at);
+        let stale = is_stale(updated_at, now, config.stale_after_secs);
+
+        let border_color = if stale { BORDER_COLOR_TERTIARY } else { BORDER_COLOR_PRIMARY };
+        if border.0 != border_color {
+            border.0 = border_color;
+        }
+
+        let alpha = if stale { STALE_TEXT_ALPHA } else { 1.0 };
+        for child in children.iter() {
+            if let Ok(mut color) = texts.get_mut(child) {
+                if color.0.alpha() != alpha {
+                    color.0.set_alpha(alpha);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_staleness_threshold() {
+        assert!(!is_stale(None, 100.0, 3.0));
+        assert!(!is_stale(Some(98.0), 100.0, 3.0));
+        assert!(is_stale(Some(96.0), 100.0, 3.0));
+    }
+}
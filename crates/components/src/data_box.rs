@@ -0,0 +1,362 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use super::theme::*;
+use super::composition::*;
+use super::vessel_data::VesselData;
+
+/// Most decimals a data box can show
+pub const MAX_DECIMALS: u8 = 3;
+
+/// Physical quantity of a field, deciding which units it can be shown in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantity {
+    Speed,
+    Depth,
+    Angle,
+    Temperature,
+    Percent,
+}
+
+/// Unit a data box displays its field in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DataUnit {
+    Knots,
+    MetersPerSecond,
+    KilometersPerHour,
+    Meters,
+    Feet,
+    Fathoms,
+    Degrees,
+    Celsius,
+    Fahrenheit,
+    Percent,
+}
+
+impl DataUnit {
+    pub fn label(self) -> &'static str {
+        match self {
+            DataUnit::Knots => "KTS",
+            DataUnit::MetersPerSecond => "M/S",
+            DataUnit::KilometersPerHour => "KM/H",
+            DataUnit::Meters => "M",
+            DataUnit::Feet => "FT",
+            DataUnit::Fathoms => "FTH",
+            DataUnit::Degrees => "°",
+            DataUnit::Celsius => "C",
+            DataUnit::Fahrenheit => "F",
+            DataUnit::Percent => "%",
+        }
+    }
+
+    /// Units available for a quantity; the first is the base unit values are stored in
+    pub fn for_quantity(quantity: Quantity) -> &'static [DataUnit] {
+        match quantity {
+            Quantity::Speed => &[DataUnit::Knots, DataUnit::MetersPerSecond, DataUnit::KilometersPerHour],
+            Quantity::Depth => &[DataUnit::Meters, DataUnit::Feet, DataUnit::Fathoms],
+            Quantity::Angle => &[DataUnit::Degrees],
+            Quantity::Temperature => &[DataUnit::Celsius, DataUnit::Fahrenheit],
+            Quantity::Percent => &[DataUnit::Percent],
+        }
+    }
+
+    /// Convert a value from its quantity's base unit
+    pub fn from_base(self, value: f32) -> f32 {
+        match self {
+            DataUnit::MetersPerSecond => value * 0.514_444,
+            DataUnit::KilometersPerHour => value * 1.852,
+            DataUnit::Feet => value * 3.280_84,
+            DataUnit::Fathoms => value * 0.546_807,
+            DataUnit::Fahrenheit => value * 9.0 / 5.0 + 32.0,
+            DataUnit::Knots | DataUnit::Meters | DataUnit::Degrees | DataUnit::Celsius | DataUnit::Percent => value,
+        }
+    }
+}
+
+/// Vessel data a data box can be bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DataField {
+    Speed,
+    Depth,
+    Heading,
+    WindSpeed,
+    WindAngle,
+    EngineTemp,
+    Fuel,
+    Battery,
+    SpeedThroughWater,
+    /// Speed over ground less speed through water, the current along the track
+    SpeedDifference,
+    WaterTemp,
+}
+
+impl DataField {
+    pub const ALL: [DataField; 11] = [
+        DataField::Speed,
+        DataField::Depth,
+        DataField::Heading,
+        DataField::WindSpeed,
+        DataField::WindAngle,
+        DataField::EngineTemp,
+        DataField::Fuel,
+        DataField::Battery,
+        DataField::SpeedThroughWater,
+        DataField::SpeedDifference,
+        DataField::WaterTemp,
+    ];
+
+    /// Identifier used by the data box page
+    pub fn name(self) -> &'static str {
+        match self {
+            DataField::Speed => "speed",
+            DataField::Depth => "depth",
+            DataField::Heading => "heading",
+            DataField::WindSpeed => "wind_speed",
+            DataField::WindAngle => "wind_angle",
+            DataField::EngineTemp => "engine_temp",
+            DataField::Fuel => "fuel",
+            DataField::Battery => "battery",
+            DataField::SpeedThroughWater => "stw",
+            DataField::SpeedDifference => "sog_stw",
+            DataField::WaterTemp => "water_temp",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|field| field.name() == value)
+    }
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|field| *field == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Caption a new box bound to this field starts with
+    pub fn default_label(self) -> &'static str {
+        match self {
+            DataField::Speed => "SOG",
+            DataField::Depth => "DEPTH",
+            DataField::Heading => "HDG",
+            DataField::WindSpeed => "AWS",
+            DataField::WindAngle => "AWA",
+            DataField::EngineTemp => "ENG TEMP",
+            DataField::Fuel => "FUEL",
+            DataField::Battery => "BATT",
+            DataField::SpeedThroughWater => "STW",
+            DataField::SpeedDifference => "SOG-STW",
+            DataField::WaterTemp => "SEA TEMP",
+        }
+    }
+
+    pub fn quantity(self) -> Quantity {
+        match self {
+            DataField::Speed | DataField::WindSpeed | DataField::SpeedThroughWater | DataField::SpeedDifference => {
+                Quantity::Speed
+            }
+            DataField::Depth => Quantity::Depth,
+            DataField::Heading | DataField::WindAngle => Quantity::Angle,
+            DataField::EngineTemp | DataField::WaterTemp => Quantity::Temperature,
+            DataField::Fuel | DataField::Battery => Quantity::Percent,
+        }
+    }
+
+    /// Current value in the quantity's base unit
+    pub fn value(self, vessel_data: &VesselData) -> f32 {
+        match self {
+            DataField::Speed => vessel_data.speed,
+            DataField::Depth => vessel_data.depth,
+            DataField::Heading => vessel_data.heading,
+            DataField::WindSpeed => vessel_data.wind_speed,
+            DataField::WindAngle => vessel_data.wind_direction,
+            DataField::EngineTemp => vessel_data.engine_temp,
+            DataField::Fuel => vessel_data.fuel_level,
+            DataField::Battery => vessel_data.battery_level,
+            DataField::SpeedThroughWater => vessel_data.speed_through_water,
+            DataField::SpeedDifference => vessel_data.speed - vessel_data.speed_through_water,
+            DataField::WaterTemp => vessel_data.water_temp,
+        }
+    }
+}
+
+/// A user-configured data box
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataBoxConfig {
+    pub field: DataField,
+    pub label: String,
+    pub unit: DataUnit,
+    pub decimals: u8,
+}
+
+impl DataBoxConfig {
+    /// A box for `field` in its base unit with one decimal
+    pub fn new(field: DataField) -> Self {
+        Self {
+            field,
+            label: field.default_label().to_string(),
+            unit: DataUnit::for_quantity(field.quantity())[0],
+            decimals: 1,
+        }
+    }
+
+    /// Rebind to another field, resetting label and unit when the quantity changes
+    pub fn set_field(&mut self, field: DataField) {
+        if field.quantity() != self.field.quantity() {
+            self.unit = DataUnit::for_quantity(field.quantity())[0];
+        }
+        if self.label == self.field.default_label() {
+            self.label = field.default_label().to_string();
+        }
+        self.field = field;
+    }
+
+    /// Cycle through the units of the bound field's quantity
+    pub fn next_unit(&mut self) {
+        let units = DataUnit::for_quantity(self.field.quantity());
+        let index = units.iter().position(|unit| *unit == self.unit).unwrap_or(0);
+        self.unit = units[(index + 1) % units.len()];
+    }
+
+    pub fn format_value(&self, vessel_data: &VesselData) -> String {
+        let value = self.unit.from_base(self.field.value(vessel_data));
+        format!("{:.*}", self.decimals as usize, value)
+    }
+}
+
+/// The data boxes shown on the dashboard, in display order
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataBoxLayout {
+    pub boxes: Vec<DataBoxConfig>,
+    /// Bumped on every structural change so the dashboard rebuilds its boxes
+    pub revision: u64,
+}
+
+impl Default for DataBoxLayout {
+    fn default() -> Self {
+        Self {
+            boxes: vec![DataBoxConfig::new(DataField::WindSpeed), DataBoxConfig::new(DataField::Battery)],
+            revision: 0,
+        }
+    }
+}
+
+impl DataBoxLayout {
+    /// Apply an edit and mark the layout for a rebuild
+    pub fn edit<R>(&mut self, edit: impl FnOnce(&mut Vec<DataBoxConfig>) -> R) -> R {
+        let result = edit(&mut self.boxes);
+        self.revision += 1;
+        result
+    }
+
+    /// Move a box one slot towards the start (`-1`) or end (`1`); returns its new index
+    pub fn move_box(&mut self, index: usize, offset: isize) -> Option<usize> {
+        let target = index.checked_add_signed(offset).filter(|target| *target < self.boxes.len())?;
+        if index >= self.boxes.len() {
+            return None;
+        }
+        self.edit(|boxes| boxes.swap(index, target));
+        Some(target)
+    }
+}
+
+/// Shared handle to the data box layout, held by the data box page and the dashboard
+#[derive(Resource, Clone, Default)]
+pub struct DataBoxes(Arc<RwLock<DataBoxLayout>>);
+
+impl DataBoxes {
+    pub fn read(&self) -> RwLockReadGuard<'_, DataBoxLayout> {
+        self.0.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, DataBoxLayout> {
+        self.0.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Container the data boxes are spawned into
+#[derive(Component, Default)]
+pub struct DataBoxRow {
+    /// Layout revision the current children were built from
+    built_revision: Option<u64>,
+}
+
+/// Value text of the data box at `index` in the layout
+#[derive(Component)]
+pub struct DataBoxValue {
+    pub index: usize,
+}
+
+/// Rebuilds the data boxes when the layout changes and refreshes their values
+pub fn update_data_boxes(
+    mut commands: Commands,
+    vessel_data: Res<VesselData>,
+    data_boxes: Res<DataBoxes>,
+    mut rows: Query<(Entity, &mut DataBoxRow)>,
+    mut values: Query<(&DataBoxValue, &mut Text)>,
+) {
+    let layout = data_boxes.read();
+
+    for (row, mut state) in rows.iter_mut() {
+        if state.built_revision == Some(layout.revision) {
+            continue;
+        }
+        state.built_revision = Some(layout.revision);
+        commands.entity(row).despawn_related::<Children>();
+        commands.entity(row).with_children(|row| {
+            for (index, config) in layout.boxes.iter().enumerate() {
+                row.spawn((
+                    status_panel_node(120.0, 80.0),
+                    BackgroundColor(BACKGROUND_COLOR_TRANSPARENT),
+                    BorderColor(BORDER_COLOR_PRIMARY),
+                ))
+                .with_children(|panel| {
+                    panel.spawn(create_text(&config.label, FONT_SIZE_SMALL, TEXT_COLOR_PRIMARY));
+                    panel.spawn((
+                        create_text(&config.format_value(&vessel_data), FONT_SIZE_NORMAL, TEXT_COLOR_SUCCESS),
+                        DataBoxValue { index },
+                    ));
+                    panel.spawn(create_text(config.unit.label(), FONT_SIZE_SMALL, TEXT_COLOR_SECONDARY));
+                });
+            }
+        });
+    }
+
+    for (value, mut text) in values.iter_mut() {
+        if let Some(config) = layout.boxes.get(value.index) {
+            let formatted = config.format_value(&vessel_data);
+            if text.0 != formatted {
+                text.0 = formatted;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_box_formatting_and_layout() {
+        let vessel_data = VesselData::default();
+
+        let mut config = DataBoxConfig::new(DataField::Depth);
+        assert_eq!(config.format_value(&vessel_data), "15.2");
+        config.next_unit();
+        config.decimals = 0;
+        assert_eq!(config.unit, DataUnit::Feet);
+        assert_eq!(config.format_value(&vessel_data), "50");
+
+        // Changing quantity resets the unit, a custom label survives
+        config.label = "KEEL".to_string();
+        config.set_field(DataField::EngineTemp);
+        assert_eq!((config.unit, config.label.as_str()), (DataUnit::Celsius, "KEEL"));
+        config.next_unit();
+        assert_eq!(config.format_value(&vessel_data), "180");
+
+        let mut layout = DataBoxLayout::default();
+        layout.edit(|boxes| boxes.push(config));
+        assert_eq!(layout.move_box(2, -1), Some(1));
+        assert_eq!(layout.move_box(0, -1), None);
+        assert_eq!(layout.boxes[1].field, DataField::EngineTemp);
+        assert_eq!(layout.revision, 2);
+    }
+}
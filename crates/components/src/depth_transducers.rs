@@ -0,0 +1,269 @@
+use bevy::prelude::*;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use super::theme::*;
+use super::vessel_data::VesselData;
+
+const FEET_PER_METER: f32 = 3.280_84;
+const FATHOMS_PER_METER: f32 = 0.546_807;
+
+/// Default depth below which the shallow alarm triggers, in meters
+pub const DEFAULT_SHALLOW_ALARM_M: f32 = 3.0;
+
+/// Unit used to display depth
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DepthUnit {
+    #[default]
+    Meters,
+    Feet,
+    Fathoms,
+}
+
+impl DepthUnit {
+    pub fn from_meters(self, meters: f32) -> f32 {
+        match self {
+            DepthUnit::Meters => meters,
+            DepthUnit::Feet => meters * FEET_PER_METER,
+            DepthUnit::Fathoms => meters * FATHOMS_PER_METER,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DepthUnit::Meters => "M",
+            DepthUnit::Feet => "FT",
+            DepthUnit::Fathoms => "FTH",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            DepthUnit::Meters => DepthUnit::Feet,
+            DepthUnit::Feet => DepthUnit::Fathoms,
+            DepthUnit::Fathoms => DepthUnit::Meters,
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "m" | "meters" | "metres" => Some(DepthUnit::Meters),
+            "ft" | "feet" => Some(DepthUnit::Feet),
+            "fth" | "fathoms" => Some(DepthUnit::Fathoms),
+            _ => None,
+        }
+    }
+}
+
+/// One depth transducer and its calibration
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthTransducer {
+    pub id: String,
+    pub label: String,
+    /// Added to the raw reading: positive for depth below the waterline
+    /// (transducer depth), negative for depth below the keel
+    pub offset_m: f32,
+    /// Last uncorrected reading
+    pub raw_depth_m: Option<f32>,
+}
+
+impl DepthTransducer {
+    pub fn new(id: &str, label: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            label: label.to_string(),
+            offset_m: 0.0,
+            raw_depth_m: None,
+        }
+    }
+
+    /// Last reading with the offset applied
+    pub fn depth_m(&self) -> Option<f32> {
+        self.raw_depth_m.map(|raw| raw + self.offset_m)
+    }
+}
+
+/// Transducer calibration and the selection feeding the primary gauge and
+/// shallow alarm
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthSettings {
+    pub transducers: Vec<DepthTransducer>,
+    pub primary: String,
+    pub unit: DepthUnit,
+    pub shallow_alarm_m: f32,
+}
+
+impl Default for DepthSettings {
+    fn default() -> Self {
+        Self {
+            transducers: vec![
+                DepthTransducer::new("FWD", "Forward"),
+                DepthTransducer::new("AFT", "Aft"),
+            ],
+            primary: "FWD".to_string(),
+            unit: DepthUnit::default(),
+            shallow_alarm_m: DEFAULT_SHALLOW_ALARM_M,
+        }
+    }
+}
+
+impl DepthSettings {
+    pub fn transducer(&self, id: &str) -> Option<&DepthTransducer> {
+        self.transducers.iter().find(|transducer| transducer.id == id)
+    }
+
+    pub fn primary_transducer(&self) -> Option<&DepthTransducer> {
+        self.transducer(&self.primary)
+    }
+
+    pub fn set_offset(&mut self, id: &str, offset_m: f32) -> bool {
+        match self.transducers.iter_mut().find(|transducer| transducer.id == id) {
+            Some(transducer) => {
+                transducer.offset_m = offset_m;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn select_primary(&mut self, id: &str) -> bool {
+        if self.transducer(id).is_some() {
+            self.primary = id.to_string();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Select the next transducer as primary
+    pub fn cycle_primary(&mut self) {
+        if self.transducers.is_empty() {
+            return;
+        }
+        let current = self.transducers.iter().position(|transducer| transducer.id == self.primary);
+        let next = current.map_or(0, |index| (index + 1) % self.transducers.len());
+        self.primary = self.transducers[next].id.clone();
+    }
+
+    /// Record a raw reading from transducer `id`, registering transducers
+    /// not seen before with no offset. A reading that names no transducer,
+    /// such as a lone sounder or the simulator, is taken as the primary's
+    /// and corrected with its offset.
+    ///
+    /// Returns the corrected depth when the reading should feed the primary
+    /// gauge: it comes from the primary transducer, or the primary has not
+    /// reported yet.
+    pub fn record(&mut self, id: Option<&str>, raw_depth_m: f32) -> Option<f32> {
+        let Some(id) = id else {
+            let offset_m = self.primary_transducer().map_or(0.0, |primary| primary.offset_m);
+            return Some(raw_depth_m + offset_m);
+        };
+        let index = match self.transducers.iter().position(|transducer| transducer.id == id) {
+            Some(index) => index,
+            None => {
+                self.transducers.push(DepthTransducer::new(id, id));
+                self.transducers.len() - 1
+            }
+        };
+        self.transducers[index].raw_depth_m = Some(raw_depth_m);
+
+        let primary_silent = self.primary_transducer().is_none_or(|primary| primary.raw_depth_m.is_none());
+        if id == self.primary || primary_silent {
+            self.transducers[index].depth_m()
+        } else {
+            None
+        }
+    }
+
+    pub fn is_shallow(&self, depth_m: f32) -> bool {
+        depth_m < self.shallow_alarm_m
+    }
+}
+
+/// Shared handle to the depth settings, held by the calibration page and the
+/// depth ingest path
+#[derive(Resource, Clone, Default)]
+pub struct DepthTransducers(Arc<RwLock<DepthSettings>>);
+
+impl DepthTransducers {
+    pub fn new(settings: DepthSettings) -> Self {
+        Self(Arc::new(RwLock::new(settings)))
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, DepthSettings> {
+        self.0.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, DepthSettings> {
+        self.0.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Depth value text inside the depth gauge
+#[derive(Component)]
+pub struct DepthReadout;
+
+/// Unit / alarm caption inside the depth gauge
+#[derive(Component)]
+pub struct DepthUnitLabel;
+
+/// Shows the primary depth in the selected unit and flags the shallow alarm
+pub fn update_depth_readout(
+    vessel_data: Res<VesselData>,
+    depth_transducers: Res<DepthTransducers>,
+    mut readouts: Query<(&mut Text, &mut TextColor), (With<DepthReadout>, Without<DepthUnitLabel>)>,
+    mut labels: Query<&mut Text, (With<DepthUnitLabel>, Without<DepthReadout>)>,
+) {
+    let settings = depth_transducers.read();
+    let shallow = settings.is_shallow(vessel_data.depth);
+
+    for (mut text, mut color) in readouts.iter_mut() {
+        text.0 = format!("{:.1}", settings.unit.from_meters(vessel_data.depth));
+        // Keep the alpha so stale dimming still applies
+        let alarm_color = if shallow { TEXT_COLOR_DANGER } else { TEXT_COLOR_SUCCESS };
+        let alarm_color = alarm_color.with_alpha(color.0.alpha());
+        if color.0 != alarm_color {
+            color.0 = alarm_color;
+        }
+    }
+
+    for mut text in labels.iter_mut() {
+        text.0 = if shallow {
+            format!("{} SHALLOW", settings.unit.label())
+        } else {
+            format!("{} {}", settings.unit.label(), settings.primary)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_primary_transducer_selection_and_offsets() {
+        let mut settings = DepthSettings::default();
+        assert!(settings.set_offset("FWD", 0.5));
+        assert!(settings.set_offset("AFT", -1.25));
+        assert!(!settings.set_offset("KEEL", 0.0));
+
+        // The aft unit fills in until the primary reports
+        assert_eq!(settings.record(Some("AFT"), 6.25), Some(5.0));
+        assert_eq!(settings.record(Some("FWD"), 8.0), Some(8.5));
+        assert_eq!(settings.record(Some("AFT"), 6.0), None);
+
+        settings.cycle_primary();
+        assert_eq!(settings.primary, "AFT");
+        assert_eq!(settings.record(Some("AFT"), 2.0), Some(0.75));
+        assert!(settings.is_shallow(0.75));
+
+        // Unknown sounders are picked up with no offset
+        assert_eq!(settings.record(Some("SOUNDER"), 4.0), None);
+        assert!(settings.select_primary("SOUNDER"));
+
+        // A reading naming no transducer is corrected like the primary's
+        // and registers nothing
+        settings.set_offset("SOUNDER", 0.25);
+        assert_eq!(settings.record(None, 4.0), Some(4.25));
+        assert_eq!(settings.transducers.len(), 3);
+        assert!((DepthUnit::Feet.from_meters(10.0) - 32.8084).abs() < 1e-3);
+    }
+}
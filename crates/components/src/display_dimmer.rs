@@ -0,0 +1,6 @@
+use bevy::prelude::*;
+
+/// Full-screen layer over the UI, tinted to dim the display and turn it
+/// red at night
+#[derive(Component)]
+pub struct DisplayDimmer;
@@ -1,5 +1,45 @@
 use bevy::prelude::*;
+use super::vessel_data::VesselData;
 
 /// Engine status component for displaying engine information
 #[derive(Component)]
-pub struct EngineStatus;
\ No newline at end of file
+pub struct EngineStatus;
+
+/// Engine speed text on the engine panel
+#[derive(Component)]
+pub struct EngineRpmReadout;
+
+/// Coolant, oil pressure and alternator text on the engine panel
+#[derive(Component)]
+pub struct EngineDetailReadout;
+
+/// Engine panel detail line: coolant temperature, oil pressure and alternator voltage
+pub fn format_engine_detail(vessel_data: &VesselData) -> String {
+    format!(
+        "{:.0} C  {:.1} BAR\n{:.1} V",
+        vessel_data.engine_temp,
+        vessel_data.oil_pressure / 100.0,
+        vessel_data.alternator_voltage
+    )
+}
+
+/// Updates the engine panel from the vessel data
+pub fn update_engine_status(
+    vessel_data: Res<VesselData>,
+    mut rpm_query: Query<&mut Text, (With<EngineRpmReadout>, Without<EngineDetailReadout>)>,
+    mut detail_query: Query<&mut Text, (With<EngineDetailReadout>, Without<EngineRpmReadout>)>,
+) {
+    let rpm = format!("{:.0} RPM", vessel_data.engine_rpm);
+    for mut text in rpm_query.iter_mut() {
+        if text.0 != rpm {
+            text.0 = rpm.clone();
+        }
+    }
+
+    let detail = format_engine_detail(&vessel_data);
+    for mut text in detail_query.iter_mut() {
+        if text.0 != detail {
+            text.0 = detail.clone();
+        }
+    }
+}
@@ -1,13 +1,21 @@
 use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
 use super::theme::*;
 use super::composition::*;
 use super::speed_gauge::SpeedGauge;
 use super::depth_gauge::DepthGauge;
 use super::compass_gauge::CompassGauge;
-use super::engine_status::EngineStatus;
+use super::engine_status::{EngineDetailReadout, EngineRpmReadout, EngineStatus};
 use super::navigation_display::NavigationDisplay;
-use super::system_display::{SystemDisplay, SystemIndicator, SystemDisplayArea};
+use super::system_display::{AlarmIndicator, SystemDisplay, SystemIndicator, SystemDisplayArea};
 use super::wind_display::WindDisplay;
+use super::watch_display::WatchDisplay;
+use super::display_dimmer::DisplayDimmer;
+use super::simulation_indicator::SimulationIndicator;
+use super::data_age::DataAgeIndicator;
+use super::depth_transducers::{DepthReadout, DepthUnitLabel};
+use super::vessel_data::DataChannel;
+use super::data_box::DataBoxRow;
 
 
 /// Main instrument cluster component
@@ -36,6 +44,7 @@ pub fn setup_instrument_cluster(mut commands: Commands) {
                 BackgroundColor(BACKGROUND_COLOR_TRANSPARENT),
                 BorderColor(BORDER_COLOR_PRIMARY),
                 SpeedGauge,
+                DataAgeIndicator::new(DataChannel::Speed),
             ))
             .with_children(|gauge| {
                 gauge.spawn(create_text("SPEED", FONT_SIZE_SMALL, TEXT_COLOR_PRIMARY));
@@ -49,6 +58,7 @@ pub fn setup_instrument_cluster(mut commands: Commands) {
                 BackgroundColor(BACKGROUND_COLOR_TRANSPARENT),
                 BorderColor(BORDER_COLOR_PRIMARY),
                 NavigationDisplay,
+                DataAgeIndicator::new(DataChannel::Heading),
             ))
             .with_children(|nav| {
                 nav.spawn(create_text("NAVIGATION", FONT_SIZE_NORMAL, TEXT_COLOR_PRIMARY));
@@ -67,11 +77,12 @@ pub fn setup_instrument_cluster(mut commands: Commands) {
                 BackgroundColor(BACKGROUND_COLOR_TRANSPARENT),
                 BorderColor(BORDER_COLOR_PRIMARY),
                 DepthGauge,
+                DataAgeIndicator::new(DataChannel::Depth),
             ))
             .with_children(|gauge| {
                 gauge.spawn(create_text("DEPTH", FONT_SIZE_SMALL, TEXT_COLOR_PRIMARY));
-                gauge.spawn(create_text("15.2", FONT_SIZE_LARGE, TEXT_COLOR_SUCCESS));
-                gauge.spawn(create_text("M", FONT_SIZE_SMALL, TEXT_COLOR_PRIMARY));
+                gauge.spawn((create_text("15.2", FONT_SIZE_LARGE, TEXT_COLOR_SUCCESS), DepthReadout));
+                gauge.spawn((create_text("M", FONT_SIZE_SMALL, TEXT_COLOR_PRIMARY), DepthUnitLabel));
             });
         });
 
@@ -84,11 +95,12 @@ pub fn setup_instrument_cluster(mut commands: Commands) {
                 BackgroundColor(BACKGROUND_COLOR_TRANSPARENT),
                 BorderColor(BORDER_COLOR_PRIMARY),
                 EngineStatus,
+                DataAgeIndicator::new(DataChannel::Engine),
             ))
             .with_children(|panel| {
                 panel.spawn(create_text("ENGINE", FONT_SIZE_NORMAL, TEXT_COLOR_PRIMARY));
-                panel.spawn(create_text("82 C", FONT_SIZE_LARGE, TEXT_COLOR_SUCCESS));
-                panel.spawn(create_text("TEMP NORMAL", FONT_SIZE_SMALL, TEXT_COLOR_PRIMARY));
+                panel.spawn((create_text("1800 RPM", FONT_SIZE_LARGE, TEXT_COLOR_SUCCESS), EngineRpmReadout));
+                panel.spawn((create_text("82 C  3.8 BAR\n14.2 V", FONT_SIZE_SMALL, TEXT_COLOR_PRIMARY), EngineDetailReadout));
             });
 
             // System Status Grid
@@ -99,6 +111,10 @@ pub fn setup_instrument_cluster(mut commands: Commands) {
             ))
             .with_children(|grid| {
                 grid.spawn(create_text("SYSTEMS", 12.0, TEXT_COLOR_PRIMARY));
+                grid.spawn((
+                    create_text("SIM", FONT_SIZE_SMALL, TEXT_COLOR_WARNING),
+                    SimulationIndicator,
+                ));
 
                 // Fuel Level Bar
                 grid.spawn(progress_bar_node())
@@ -179,6 +195,160 @@ pub fn setup_instrument_cluster(mut commands: Commands) {
                     .with_children(|indicator| {
                         indicator.spawn(create_text("AIS", FONT_SIZE_NORMAL, TEXT_COLOR_PRIMARY));
                     });
+
+                    // Calibration Indicator
+                    indicators.spawn((
+                        Button,
+                        system_indicator_node(),
+                        BackgroundColor(BACKGROUND_COLOR_SECONDARY),
+                        BorderColor(BORDER_COLOR_SECONDARY),
+                        SystemIndicator {
+                            system_id: "calibration".to_string(),
+                        },
+                    ))
+                    .with_children(|indicator| {
+                        indicator.spawn(create_text("CAL", FONT_SIZE_NORMAL, TEXT_COLOR_PRIMARY));
+                    });
+
+                    // Chart Annotations Indicator
+                    indicators.spawn((
+                        Button,
+                        system_indicator_node(),
+                        BackgroundColor(BACKGROUND_COLOR_SECONDARY),
+                        BorderColor(BORDER_COLOR_SECONDARY),
+                        SystemIndicator {
+                            system_id: "chart".to_string(),
+                        },
+                    ))
+                    .with_children(|indicator| {
+                        indicator.spawn(create_text("CHART", FONT_SIZE_NORMAL, TEXT_COLOR_PRIMARY));
+                    });
+
+                    // Event Timeline Indicator
+                    indicators.spawn((
+                        Button,
+                        system_indicator_node(),
+                        BackgroundColor(BACKGROUND_COLOR_SECONDARY),
+                        BorderColor(BORDER_COLOR_SECONDARY),
+                        SystemIndicator {
+                            system_id: "timeline".to_string(),
+                        },
+                    ))
+                    .with_children(|indicator| {
+                        indicator.spawn(create_text("TIME", FONT_SIZE_NORMAL, TEXT_COLOR_PRIMARY));
+                    });
+
+                    // Data Box Editor Indicator
+                    indicators.spawn((
+                        Button,
+                        system_indicator_node(),
+                        BackgroundColor(BACKGROUND_COLOR_SECONDARY),
+                        BorderColor(BORDER_COLOR_SECONDARY),
+                        SystemIndicator {
+                            system_id: "data_boxes".to_string(),
+                        },
+                    ))
+                    .with_children(|indicator| {
+                        indicator.spawn(create_text("BOXES", FONT_SIZE_NORMAL, TEXT_COLOR_PRIMARY));
+                    });
+
+                    // Datalink Diagnostics Indicator
+                    indicators.spawn((
+                        Button,
+                        system_indicator_node(),
+                        BackgroundColor(BACKGROUND_COLOR_SECONDARY),
+                        BorderColor(BORDER_COLOR_SECONDARY),
+                        SystemIndicator {
+                            system_id: "diagnostics".to_string(),
+                        },
+                    ))
+                    .with_children(|indicator| {
+                        indicator.spawn(create_text("DIAG", FONT_SIZE_NORMAL, TEXT_COLOR_PRIMARY));
+                    });
+
+                    // Connection Manager Indicator
+                    indicators.spawn((
+                        Button,
+                        system_indicator_node(),
+                        BackgroundColor(BACKGROUND_COLOR_SECONDARY),
+                        BorderColor(BORDER_COLOR_SECONDARY),
+                        SystemIndicator {
+                            system_id: "connections".to_string(),
+                        },
+                    ))
+                    .with_children(|indicator| {
+                        indicator.spawn(create_text("CONN", FONT_SIZE_NORMAL, TEXT_COLOR_PRIMARY));
+                    });
+
+                    // Alarms Indicator
+                    indicators.spawn((
+                        Button,
+                        system_indicator_node(),
+                        BackgroundColor(BACKGROUND_COLOR_SECONDARY),
+                        BorderColor(BORDER_COLOR_SECONDARY),
+                        SystemIndicator {
+                            system_id: "alarms".to_string(),
+                        },
+                    ))
+                    .with_children(|indicator| {
+                        indicator.spawn((create_text("ALRM", FONT_SIZE_NORMAL, TEXT_COLOR_PRIMARY), AlarmIndicator));
+                    });
+
+                    // Nav Lights & Signals Indicator
+                    indicators.spawn((
+                        Button,
+                        system_indicator_node(),
+                        BackgroundColor(BACKGROUND_COLOR_SECONDARY),
+                        BorderColor(BORDER_COLOR_SECONDARY),
+                        SystemIndicator {
+                            system_id: "nav_lights".to_string(),
+                        },
+                    ))
+                    .with_children(|indicator| {
+                        indicator.spawn(create_text("LGTS", FONT_SIZE_NORMAL, TEXT_COLOR_PRIMARY));
+                    });
+
+                    // Watch Schedule Indicator
+                    indicators.spawn((
+                        Button,
+                        system_indicator_node(),
+                        BackgroundColor(BACKGROUND_COLOR_SECONDARY),
+                        BorderColor(BORDER_COLOR_SECONDARY),
+                        SystemIndicator {
+                            system_id: "watch".to_string(),
+                        },
+                    ))
+                    .with_children(|indicator| {
+                        indicator.spawn(create_text("WTCH", FONT_SIZE_NORMAL, TEXT_COLOR_PRIMARY));
+                    });
+
+                    // Passage Checklists Indicator
+                    indicators.spawn((
+                        Button,
+                        system_indicator_node(),
+                        BackgroundColor(BACKGROUND_COLOR_SECONDARY),
+                        BorderColor(BORDER_COLOR_SECONDARY),
+                        SystemIndicator {
+                            system_id: "checklists".to_string(),
+                        },
+                    ))
+                    .with_children(|indicator| {
+                        indicator.spawn(create_text("CHKL", FONT_SIZE_NORMAL, TEXT_COLOR_PRIMARY));
+                    });
+
+                    // Display Brightness Indicator
+                    indicators.spawn((
+                        Button,
+                        system_indicator_node(),
+                        BackgroundColor(BACKGROUND_COLOR_SECONDARY),
+                        BorderColor(BORDER_COLOR_SECONDARY),
+                        SystemIndicator {
+                            system_id: "display".to_string(),
+                        },
+                    ))
+                    .with_children(|indicator| {
+                        indicator.spawn(create_text("DISP", FONT_SIZE_NORMAL, TEXT_COLOR_PRIMARY));
+                    });
                 });
             });
 
@@ -188,14 +358,39 @@ pub fn setup_instrument_cluster(mut commands: Commands) {
                 BackgroundColor(BACKGROUND_COLOR_TRANSPARENT),
                 BorderColor(BORDER_COLOR_PRIMARY),
                 WindDisplay,
+                DataAgeIndicator::new(DataChannel::Wind),
             ))
             .with_children(|panel| {
                 panel.spawn(create_text("WIND", FONT_SIZE_NORMAL, TEXT_COLOR_PRIMARY));
                 panel.spawn(create_text("8.3 KTS", FONT_SIZE_NORMAL, TEXT_COLOR_SUCCESS));
                 panel.spawn(create_text("120 deg REL", FONT_SIZE_NORMAL, TEXT_COLOR_PRIMARY));
             });
+
+            // Crew Watch, filled in by update_watch_display
+            row.spawn((
+                status_panel_node(200.0, 150.0),
+                BackgroundColor(BACKGROUND_COLOR_TRANSPARENT),
+                BorderColor(BORDER_COLOR_PRIMARY),
+            ))
+            .with_children(|panel| {
+                panel.spawn(create_text("WATCH", FONT_SIZE_NORMAL, TEXT_COLOR_PRIMARY));
+                panel.spawn((create_text("OFF", FONT_SIZE_NORMAL, TEXT_COLOR_SUCCESS), WatchDisplay));
+            });
         });
 
+        // User-configured data boxes, filled in by update_data_boxes
+        parent.spawn((
+            Node {
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Row,
+                flex_wrap: FlexWrap::Wrap,
+                justify_content: JustifyContent::SpaceEvenly,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            DataBoxRow::default(),
+        ));
+
         // System Display Area
         parent.spawn((
             Node {
@@ -223,4 +418,19 @@ pub fn setup_instrument_cluster(mut commands: Commands) {
             ));
         });
     });
+
+    // Dimming layer above everything else, tinted by update_display_dimmer;
+    // it passes clicks through to the buttons underneath
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        BackgroundColor(BACKGROUND_COLOR_TRANSPARENT),
+        FocusPolicy::Pass,
+        GlobalZIndex(i32::MAX),
+        DisplayDimmer,
+    ));
 }
\ No newline at end of file
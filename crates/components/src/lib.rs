@@ -21,6 +21,12 @@ pub mod radar_indicator;
 pub mod ais_indicator;
 pub mod system_display;
 pub mod wind_display;
+pub mod watch_display;
+pub mod display_dimmer;
+pub mod simulation_indicator;
+pub mod data_age;
+pub mod depth_transducers;
+pub mod data_box;
 
 // Re-export everything
 pub use ui::*;
@@ -38,3 +44,9 @@ pub use radar_indicator::*;
 pub use ais_indicator::*;
 pub use system_display::*;
 pub use wind_display::*;
+pub use watch_display::*;
+pub use display_dimmer::*;
+pub use simulation_indicator::*;
+pub use data_age::*;
+pub use depth_transducers::*;
+pub use data_box::*;
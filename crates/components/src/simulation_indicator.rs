@@ -0,0 +1,29 @@
+use bevy::prelude::*;
+use super::theme::*;
+use super::vessel_data::VesselData;
+
+/// Status text listing the instrument channels that are still simulated
+#[derive(Component)]
+pub struct SimulationIndicator;
+
+/// Keeps the simulation indicator in sync with the per-channel data sources
+pub fn update_simulation_indicator(
+    vessel_data: Res<VesselData>,
+    mut query: Query<(&mut Text, &mut TextColor), With<SimulationIndicator>>,
+) {
+    if !vessel_data.is_changed() {
+        return;
+    }
+
+    let simulated = vessel_data.simulated_channels();
+    for (mut text, mut color) in query.iter_mut() {
+        if simulated.is_empty() {
+            text.0 = "ALL LIVE".to_string();
+            color.0 = TEXT_COLOR_SUCCESS;
+        } else {
+            let labels: Vec<&str> = simulated.iter().map(|channel| channel.label()).collect();
+            text.0 = format!("SIM: {}", labels.join(" "));
+            color.0 = TEXT_COLOR_WARNING;
+        }
+    }
+}
@@ -10,6 +10,10 @@ pub struct SystemIndicator {
     pub system_id: String,
 }
 
+/// Label of the alarms indicator, colored by the active alarm outputs
+#[derive(Component)]
+pub struct AlarmIndicator;
+
 /// Component for marking the main system display area
 #[derive(Component)]
 pub struct SystemDisplayArea;
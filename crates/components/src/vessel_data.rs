@@ -1,8 +1,69 @@
 use bevy::prelude::*;
+use std::collections::HashMap;
 use super::speed_gauge::SpeedGauge;
 use super::depth_gauge::DepthGauge;
 use super::compass_gauge::CompassGauge;
 
+/// Seconds a live value keeps suppressing simulated data for its channel
+pub const LIVE_DATA_TIMEOUT_SECS: f32 = 5.0;
+
+/// Instrument channels that can be fed independently
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DataChannel {
+    Speed,
+    Depth,
+    Heading,
+    Engine,
+    Fuel,
+    Battery,
+    Wind,
+    SpeedLog,
+    WaterTemp,
+}
+
+impl DataChannel {
+    pub const ALL: [DataChannel; 9] = [
+        DataChannel::Speed,
+        DataChannel::Depth,
+        DataChannel::Heading,
+        DataChannel::Engine,
+        DataChannel::Fuel,
+        DataChannel::Battery,
+        DataChannel::Wind,
+        DataChannel::SpeedLog,
+        DataChannel::WaterTemp,
+    ];
+
+    /// Short label used on the instrument cluster
+    pub fn label(&self) -> &'static str {
+        match self {
+            DataChannel::Speed => "SPD",
+            DataChannel::Depth => "DPT",
+            DataChannel::Heading => "HDG",
+            DataChannel::Engine => "ENG",
+            DataChannel::Fuel => "FUEL",
+            DataChannel::Battery => "BATT",
+            DataChannel::Wind => "WIND",
+            DataChannel::SpeedLog => "STW",
+            DataChannel::WaterTemp => "SEA",
+        }
+    }
+}
+
+/// Origin of a channel value, in increasing priority
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DataSource {
+    Simulated,
+    Live,
+}
+
+/// Source and time (seconds since app start) of the last accepted value for a channel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChannelState {
+    pub source: DataSource,
+    pub updated_at: f32,
+}
+
 /// Yacht data resource containing all sensor readings
 #[derive(Resource)]
 pub struct VesselData {
@@ -10,10 +71,19 @@ pub struct VesselData {
     pub depth: f32,           // meters
     pub heading: f32,         // degrees
     pub engine_temp: f32,     // celsius
+    pub engine_rpm: f32,
+    pub oil_pressure: f32,    // kPa
+    pub alternator_voltage: f32, // volts
     pub fuel_level: f32,      // percentage
     pub battery_level: f32,   // percentage
     pub wind_speed: f32,      // knots
     pub wind_direction: f32,  // degrees
+    pub speed_through_water: f32, // knots
+    pub water_temp: f32,      // celsius
+    /// True heading from a heading sensor (HDT/HDG), `None` until one reports
+    pub heading_true: Option<f32>,
+    heading_true_at: f32,
+    channels: HashMap<DataChannel, ChannelState>,
 }
 
 impl Default for VesselData {
@@ -23,46 +93,137 @@ impl Default for VesselData {
             depth: 15.2,
             heading: 045.0,
             engine_temp: 82.0,
+            engine_rpm: 1800.0,
+            oil_pressure: 380.0,
+            alternator_voltage: 14.2,
             fuel_level: 75.0,
             battery_level: 88.0,
             wind_speed: 8.3,
             wind_direction: 120.0,
+            speed_through_water: 12.1,
+            water_temp: 18.5,
+            heading_true: None,
+            heading_true_at: 0.0,
+            channels: HashMap::new(),
         }
     }
 }
 
+impl VesselData {
+    /// Arbitrate a write to `channel` from `source` at time `now`.
+    ///
+    /// Live values always win. Simulated values are only accepted while the
+    /// channel has not seen live data for [`LIVE_DATA_TIMEOUT_SECS`], so the
+    /// simulation pauses per channel as soon as a real provider delivers it and
+    /// resumes if that provider goes quiet. Returns whether the caller should
+    /// write the new value.
+    pub fn ingest(&mut self, channel: DataChannel, source: DataSource, now: f32) -> bool {
+        if let Some(state) = self.channels.get(&channel) {
+            let live_is_fresh = state.source == DataSource::Live
+                && now - state.updated_at <= LIVE_DATA_TIMEOUT_SECS;
+            if source < state.source && live_is_fresh {
+                return false;
+            }
+        }
+        self.channels.insert(channel, ChannelState { source, updated_at: now });
+        true
+    }
+
+    /// Record a true heading reported by a heading sensor at time `now`
+    pub fn set_heading_true(&mut self, heading: f32, now: f32) {
+        self.heading_true = Some(heading.rem_euclid(360.0));
+        self.heading_true_at = now;
+    }
+
+    /// The sensor heading, unless it has not been updated for
+    /// [`LIVE_DATA_TIMEOUT_SECS`]. While it is fresh, heading is not derived
+    /// from course over ground.
+    pub fn fresh_heading_true(&self, now: f32) -> Option<f32> {
+        self.heading_true.filter(|_| now - self.heading_true_at <= LIVE_DATA_TIMEOUT_SECS)
+    }
+
+    /// Source and update time of the last accepted value for a channel
+    pub fn channel_state(&self, channel: DataChannel) -> Option<ChannelState> {
+        self.channels.get(&channel).copied()
+    }
+
+    /// Channels whose current value comes from simulation (or has never been fed)
+    pub fn simulated_channels(&self) -> Vec<DataChannel> {
+        DataChannel::ALL
+            .into_iter()
+            .filter(|channel| {
+                self.channels
+                    .get(channel)
+                    .is_none_or(|state| state.source == DataSource::Simulated)
+            })
+            .collect()
+    }
+}
+
 /// Updates yacht data with sensor readings, using real GPS data when available
-pub fn update_vessel_data(mut vessel_data: ResMut<VesselData>, time: Res<Time>) {
+pub fn update_vessel_data(vessel_data: ResMut<VesselData>, time: Res<Time>) {
     update_vessel_data_with_gps(vessel_data, time, None);
 }
 
 /// Updates yacht data with sensor readings, optionally using real GPS data
+///
+/// Simulated values go through [`VesselData::ingest`], so any channel that is
+/// currently fed by a live provider is left untouched.
 pub fn update_vessel_data_with_gps(
     mut vessel_data: ResMut<VesselData>, 
     time: Res<Time>, 
     gps_data: Option<(f64, f64)> // (speed, heading)
 ) {
     let t = time.elapsed_secs();
+    let dt = time.delta_secs();
 
     // Use real GPS data if available, otherwise simulate
     if let Some((gps_speed, gps_heading)) = gps_data {
-        vessel_data.speed = gps_speed as f32;
-        vessel_data.heading = gps_heading as f32;
+        if vessel_data.ingest(DataChannel::Speed, DataSource::Live, t) {
+            vessel_data.speed = gps_speed as f32;
+        }
+        // Course over ground only stands in for heading without a heading sensor
+        if vessel_data.fresh_heading_true(t).is_none() && vessel_data.ingest(DataChannel::Heading, DataSource::Live, t) {
+            vessel_data.heading = gps_heading as f32;
+        }
     } else {
         // Simulate realistic yacht data with some variation
-        vessel_data.speed = 12.5 + (t * 0.3).sin() * 2.0;
-        vessel_data.heading = (vessel_data.heading + time.delta_secs() * 5.0) % 360.0;
+        if vessel_data.ingest(DataChannel::Speed, DataSource::Simulated, t) {
+            vessel_data.speed = 12.5 + (t * 0.3).sin() * 2.0;
+        }
+        if vessel_data.ingest(DataChannel::Heading, DataSource::Simulated, t) {
+            vessel_data.heading = (vessel_data.heading + dt * 5.0) % 360.0;
+        }
     }
 
     // Continue simulating other sensor data
-    vessel_data.depth = 15.2 + (t * 0.1).sin() * 3.0;
-    vessel_data.engine_temp = 82.0 + (t * 0.2).sin() * 3.0;
-    vessel_data.wind_speed = 8.3 + (t * 0.4).sin() * 1.5;
-    vessel_data.wind_direction = (vessel_data.wind_direction + time.delta_secs() * 10.0) % 360.0;
+    if vessel_data.ingest(DataChannel::Depth, DataSource::Simulated, t) {
+        vessel_data.depth = 15.2 + (t * 0.1).sin() * 3.0;
+    }
+    if vessel_data.ingest(DataChannel::Engine, DataSource::Simulated, t) {
+        vessel_data.engine_temp = 82.0 + (t * 0.2).sin() * 3.0;
+        vessel_data.engine_rpm = 1800.0 + (t * 0.15).sin() * 40.0;
+        vessel_data.oil_pressure = 380.0 + (t * 0.15).sin() * 8.0;
+        vessel_data.alternator_voltage = 14.2 + (t * 0.05).sin() * 0.1;
+    }
+    if vessel_data.ingest(DataChannel::Wind, DataSource::Simulated, t) {
+        vessel_data.wind_speed = 8.3 + (t * 0.4).sin() * 1.5;
+        vessel_data.wind_direction = (vessel_data.wind_direction + dt * 10.0) % 360.0;
+    }
+    if vessel_data.ingest(DataChannel::SpeedLog, DataSource::Simulated, t) {
+        vessel_data.speed_through_water = 12.1 + (t * 0.3).sin() * 2.0;
+    }
+    if vessel_data.ingest(DataChannel::WaterTemp, DataSource::Simulated, t) {
+        vessel_data.water_temp = 18.5 + (t * 0.01).sin() * 0.5;
+    }
 
     // Slowly drain fuel and battery (very slowly for demo purposes)
-    vessel_data.fuel_level = (vessel_data.fuel_level - time.delta_secs() * 0.01).max(0.0);
-    vessel_data.battery_level = (vessel_data.battery_level - time.delta_secs() * 0.005).max(0.0);
+    if vessel_data.ingest(DataChannel::Fuel, DataSource::Simulated, t) {
+        vessel_data.fuel_level = (vessel_data.fuel_level - dt * 0.01).max(0.0);
+    }
+    if vessel_data.ingest(DataChannel::Battery, DataSource::Simulated, t) {
+        vessel_data.battery_level = (vessel_data.battery_level - dt * 0.005).max(0.0);
+    }
 }
 
 /// Updates the display values for all instrument gauges
@@ -105,4 +266,24 @@ mod tests {
         assert_eq!(vessel_data.fuel_level, 75.0);
         assert_eq!(vessel_data.battery_level, 88.0);
     }
+
+    #[test]
+    fn test_live_data_suppresses_simulation() {
+        let mut vessel_data = VesselData::default();
+        assert_eq!(vessel_data.simulated_channels().len(), DataChannel::ALL.len());
+
+        assert!(vessel_data.ingest(DataChannel::Depth, DataSource::Simulated, 0.0));
+        assert!(vessel_data.ingest(DataChannel::Depth, DataSource::Live, 1.0));
+        assert!(!vessel_data.ingest(DataChannel::Depth, DataSource::Simulated, 2.0));
+        assert!(!vessel_data.simulated_channels().contains(&DataChannel::Depth));
+
+        // Other channels keep simulating
+        assert!(vessel_data.ingest(DataChannel::Wind, DataSource::Simulated, 2.0));
+        assert!(vessel_data.simulated_channels().contains(&DataChannel::Wind));
+
+        // Simulation resumes once the live source goes quiet
+        let resume_at = 1.0 + LIVE_DATA_TIMEOUT_SECS + 0.1;
+        assert!(vessel_data.ingest(DataChannel::Depth, DataSource::Simulated, resume_at));
+        assert!(vessel_data.simulated_channels().contains(&DataChannel::Depth));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,5 @@
+use bevy::prelude::*;
+
+/// Dashboard text naming the crew on watch and the next handover
+#[derive(Component)]
+pub struct WatchDisplay;
@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use crate::{CredentialBackend, CredentialError, CredentialResult};
+
+const FILE_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+/// Associated data of the entry used to check the passphrase on open
+const VERIFIER_AAD: &str = "yachtpit-credentials";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SealedValue {
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CredentialFile {
+    version: u32,
+    salt: String,
+    verifier: SealedValue,
+    entries: BTreeMap<String, SealedValue>,
+}
+
+/// Secrets in a JSON file, each sealed with AES-256-GCM under a key derived
+/// from a passphrase with Argon2id. Entry names are bound to their
+/// ciphertext as associated data so values cannot be swapped between names.
+pub struct EncryptedFileBackend {
+    path: PathBuf,
+    cipher: Aes256Gcm,
+    file: Mutex<CredentialFile>,
+}
+
+impl EncryptedFileBackend {
+    /// Open the store at `path`, creating it on first use. Fails with
+    /// [`CredentialError::Decrypt`] if the passphrase does not match.
+    pub fn open(path: impl AsRef<Path>, passphrase: &str) -> CredentialResult<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        let existing = match std::fs::read_to_string(&path) {
+            Ok(contents) => Some(
+                serde_json::from_str::<CredentialFile>(&contents).map_err(|e| CredentialError::Format(e.to_string()))?,
+            ),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        match existing {
+            Some(file) => {
+                if file.version != FILE_VERSION {
+                    return Err(CredentialError::Format(format!("unsupported version {}", file.version)));
+                }
+                let cipher = derive_cipher(passphrase, &decode(&file.salt)?)?;
+                open_value(&cipher, VERIFIER_AAD, &file.verifier)?;
+                Ok(Self {
+                    path,
+                    cipher,
+                    file: Mutex::new(file),
+                })
+            }
+            None => {
+                let mut salt = [0u8; SALT_LEN];
+                OsRng.fill_bytes(&mut salt);
+                let cipher = derive_cipher(passphrase, &salt)?;
+                let verifier = seal_value(&cipher, VERIFIER_AAD, VERIFIER_AAD)?;
+                Ok(Self {
+                    path,
+                    cipher,
+                    file: Mutex::new(CredentialFile {
+                        version: FILE_VERSION,
+                        salt: STANDARD.encode(salt),
+                        verifier,
+                        entries: BTreeMap::new(),
+                    }),
+                })
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn save(&self, file: &CredentialFile) -> CredentialResult<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let contents = serde_json::to_string_pretty(file).map_err(|e| CredentialError::Format(e.to_string()))?;
+        // Write then rename so a crash never leaves a truncated store
+        let temp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&temp_path, contents)?;
+        restrict_permissions(&temp_path)?;
+        std::fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CredentialFile> {
+        self.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl CredentialBackend for EncryptedFileBackend {
+    fn name(&self) -> &str {
+        "encrypted file"
+    }
+
+    fn get(&self, name: &str) -> CredentialResult<Option<String>> {
+        let file = self.lock();
+        file.entries
+            .get(name)
+            .map(|sealed| open_value(&self.cipher, name, sealed))
+            .transpose()
+    }
+
+    fn set(&self, name: &str, secret: &str) -> CredentialResult<()> {
+        let mut file = self.lock();
+        let sealed = seal_value(&self.cipher, name, secret)?;
+        file.entries.insert(name.to_string(), sealed);
+        self.save(&file)
+    }
+
+    fn delete(&self, name: &str) -> CredentialResult<bool> {
+        let mut file = self.lock();
+        if file.entries.remove(name).is_none() {
+            return Ok(false);
+        }
+        self.save(&file)?;
+        Ok(true)
+    }
+}
+
+fn derive_cipher(passphrase: &str, salt: &[u8]) -> CredentialResult<Aes256Gcm> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| CredentialError::Crypto(e.to_string()))?;
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+}
+
+fn seal_value(cipher: &Aes256Gcm, name: &str, secret: &str) -> CredentialResult<SealedValue> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: secret.as_bytes(), aad: name.as_bytes() })
+        .map_err(|e| CredentialError::Crypto(e.to_string()))?;
+    Ok(SealedValue {
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+    })
+}
+
+fn open_value(cipher: &Aes256Gcm, name: &str, sealed: &SealedValue) -> CredentialResult<String> {
+    let nonce = decode(&sealed.nonce)?;
+    if nonce.len() != 12 {
+        return Err(CredentialError::Format("bad nonce length".to_string()));
+    }
+    let plaintext = cipher
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            Payload { msg: &decode(&sealed.ciphertext)?, aad: name.as_bytes() },
+        )
+        .map_err(|_| CredentialError::Decrypt)?;
+    String::from_utf8(plaintext).map_err(|e| CredentialError::Format(e.to_string()))
+}
+
+fn decode(value: &str) -> CredentialResult<Vec<u8>> {
+    STANDARD.decode(value).map_err(|e| CredentialError::Format(e.to_string()))
+}
+
+#[cfg(unix)]
+fn restrict_permissions(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
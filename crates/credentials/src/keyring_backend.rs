@@ -0,0 +1,61 @@
+use crate::{CredentialBackend, CredentialError, CredentialResult};
+
+/// Keyring service name the secrets are filed under
+const SERVICE: &str = "yachtpit";
+
+/// Secrets kept in the operating system keyring (Keychain, Credential
+/// Manager or the kernel keyring on Linux)
+#[derive(Debug, Clone)]
+pub struct KeyringBackend {
+    service: String,
+}
+
+impl KeyringBackend {
+    pub fn new() -> Self {
+        Self::with_service(SERVICE)
+    }
+
+    pub fn with_service(service: &str) -> Self {
+        Self {
+            service: service.to_string(),
+        }
+    }
+
+    fn entry(&self, name: &str) -> CredentialResult<keyring::Entry> {
+        keyring::Entry::new(&self.service, name).map_err(|e| CredentialError::Keyring(e.to_string()))
+    }
+}
+
+impl Default for KeyringBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CredentialBackend for KeyringBackend {
+    fn name(&self) -> &str {
+        "keyring"
+    }
+
+    fn get(&self, name: &str) -> CredentialResult<Option<String>> {
+        match self.entry(name)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(CredentialError::Keyring(e.to_string())),
+        }
+    }
+
+    fn set(&self, name: &str, secret: &str) -> CredentialResult<()> {
+        self.entry(name)?
+            .set_password(secret)
+            .map_err(|e| CredentialError::Keyring(e.to_string()))
+    }
+
+    fn delete(&self, name: &str) -> CredentialResult<bool> {
+        match self.entry(name)?.delete_credential() {
+            Ok(()) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(e) => Err(CredentialError::Keyring(e.to_string())),
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Credential storage for API keys
+//!
+//! Secrets such as the aisstream.io key live in a [`CredentialStore`] rather
+//! than in plaintext configuration. On desktop the store uses the operating
+//! system keyring; headless installs use an [`EncryptedFileBackend`] whose key
+//! is derived from a passphrase. Configuration values refer to a secret with
+//! `credential:<name>` and are resolved through the store when used.
+
+mod file;
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+mod keyring_backend;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+pub use file::EncryptedFileBackend;
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+pub use keyring_backend::KeyringBackend;
+
+/// aisstream.io API key
+pub const AISSTREAM_API_KEY: &str = "aisstream_api_key";
+/// Weather service API key
+pub const WEATHER_API_KEY: &str = "weather_api_key";
+/// Position reporting service API key
+pub const POSITION_REPORTING_API_KEY: &str = "position_reporting_api_key";
+
+/// Prefix marking a configuration value as a reference into the store
+pub const CREDENTIAL_REF_PREFIX: &str = "credential:";
+/// Environment variable holding the passphrase for the encrypted file store
+pub const PASSPHRASE_ENV: &str = "YACHTPIT_CREDENTIALS_PASSPHRASE";
+/// File name of the encrypted store inside the data directory
+const CREDENTIALS_FILE_NAME: &str = "credentials.json";
+
+/// Errors that can occur when reading or writing credentials
+#[derive(Error, Debug)]
+pub enum CredentialError {
+    #[error("Credential '{0}' not found")]
+    NotFound(String),
+    #[error("Keyring error: {0}")]
+    Keyring(String),
+    #[error("Credential file error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Malformed credential file: {0}")]
+    Format(String),
+    #[error("Could not decrypt credentials; wrong passphrase?")]
+    Decrypt,
+    #[error("Encryption failed: {0}")]
+    Crypto(String),
+    #[error("No credential backend available: {0}")]
+    Unavailable(String),
+}
+
+/// Result type for credential operations
+pub type CredentialResult<T> = Result<T, CredentialError>;
+
+/// Storage for named secrets
+pub trait CredentialBackend: Send + Sync {
+    /// Human readable backend name
+    fn name(&self) -> &str;
+
+    /// Look up a secret; `Ok(None)` if it is not stored
+    fn get(&self, name: &str) -> CredentialResult<Option<String>>;
+
+    /// Store or replace a secret
+    fn set(&self, name: &str, secret: &str) -> CredentialResult<()>;
+
+    /// Remove a secret, returning whether it existed
+    fn delete(&self, name: &str) -> CredentialResult<bool>;
+}
+
+/// Named secrets behind a keyring or encrypted file backend
+pub struct CredentialStore {
+    backend: Box<dyn CredentialBackend>,
+}
+
+impl CredentialStore {
+    pub fn new(backend: impl CredentialBackend + 'static) -> Self {
+        Self {
+            backend: Box::new(backend),
+        }
+    }
+
+    /// The encrypted file store when `YACHTPIT_CREDENTIALS_PASSPHRASE` is set,
+    /// otherwise the system keyring where there is one
+    pub fn from_env() -> CredentialResult<Self> {
+        if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+            let path = Self::default_path()
+                .ok_or_else(|| CredentialError::Unavailable("no data directory for the credential file".to_string()))?;
+            return Ok(Self::new(EncryptedFileBackend::open(path, &passphrase)?));
+        }
+
+        #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+        {
+            Ok(Self::new(KeyringBackend::new()))
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+        {
+            Err(CredentialError::Unavailable(format!("set {} to use the encrypted file store", PASSPHRASE_ENV)))
+        }
+    }
+
+    /// `$YACHTPIT_DATA_DIR/credentials.json`, falling back to `~/.yachtpit`
+    pub fn default_path() -> Option<PathBuf> {
+        let data_dir = std::env::var_os("YACHTPIT_DATA_DIR")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".yachtpit")))?;
+        Some(data_dir.join(CREDENTIALS_FILE_NAME))
+    }
+
+    pub fn backend_name(&self) -> &str {
+        self.backend.name()
+    }
+
+    pub fn get(&self, name: &str) -> CredentialResult<Option<String>> {
+        self.backend.get(name)
+    }
+
+    /// Look up a secret that must exist
+    pub fn require(&self, name: &str) -> CredentialResult<String> {
+        self.get(name)?.ok_or_else(|| CredentialError::NotFound(name.to_string()))
+    }
+
+    pub fn set(&self, name: &str, secret: &str) -> CredentialResult<()> {
+        self.backend.set(name, secret)
+    }
+
+    pub fn delete(&self, name: &str) -> CredentialResult<bool> {
+        self.backend.delete(name)
+    }
+
+    /// Resolve a configuration value: `credential:<name>` is replaced by the
+    /// stored secret, anything else is returned unchanged
+    pub fn resolve(&self, value: &str) -> CredentialResult<String> {
+        match credential_reference(value) {
+            Some(name) => self.require(name),
+            None => Ok(value.to_string()),
+        }
+    }
+
+    /// Resolve every credential reference in a parameter map
+    pub fn resolve_parameters(&self, parameters: &HashMap<String, String>) -> CredentialResult<HashMap<String, String>> {
+        parameters
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), self.resolve(value)?)))
+            .collect()
+    }
+}
+
+/// The secret name if `value` is a `credential:<name>` reference
+pub fn credential_reference(value: &str) -> Option<&str> {
+    value
+        .strip_prefix(CREDENTIAL_REF_PREFIX)
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+}
+
+/// Whether any parameter refers to the credential store
+pub fn has_credential_references(parameters: &HashMap<String, String>) -> bool {
+    parameters.values().any(|value| credential_reference(value).is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolves_references_through_encrypted_store() {
+        let dir = std::env::temp_dir().join(format!("yachtpit-credentials-{}", std::process::id()));
+        let path = dir.join(CREDENTIALS_FILE_NAME);
+
+        let store = CredentialStore::new(EncryptedFileBackend::open(&path, "correct horse").unwrap());
+        store.set(AISSTREAM_API_KEY, "ais-secret").unwrap();
+        store.set(WEATHER_API_KEY, "wx-secret").unwrap();
+        assert!(store.delete(WEATHER_API_KEY).unwrap());
+        assert!(!store.delete(WEATHER_API_KEY).unwrap());
+
+        // The secret never reaches the file in plaintext
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("ais-secret"));
+
+        let reopened = CredentialStore::new(EncryptedFileBackend::open(&path, "correct horse").unwrap());
+        let parameters = HashMap::from([
+            ("api_key".to_string(), "credential:aisstream_api_key".to_string()),
+            ("host".to_string(), "stream.aisstream.io".to_string()),
+        ]);
+        assert!(has_credential_references(&parameters));
+        let resolved = reopened.resolve_parameters(&parameters).unwrap();
+        assert_eq!(resolved["api_key"], "ais-secret");
+        assert_eq!(resolved["host"], "stream.aisstream.io");
+        assert!(matches!(reopened.resolve("credential:weather_api_key"), Err(CredentialError::NotFound(_))));
+
+        assert!(matches!(EncryptedFileBackend::open(&path, "wrong"), Err(CredentialError::Decrypt)));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,302 @@
+//! AIS payload decoding
+//!
+//! Decodes the 6-bit armored payload of `!AIVDM`/`!AIVDO` sentences for the
+//! position reports (types 1-3 and 18) and static data reports (types 5 and
+//! 24). Multi-sentence messages are joined by [`AisFragmentAssembler`] first.
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+use datalink::DataMessage;
+
+/// Ship dimensions relative to the position reference point, in meters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AisDimensions {
+    pub to_bow: u16,
+    pub to_stern: u16,
+    pub to_port: u8,
+    pub to_starboard: u8,
+}
+
+impl AisDimensions {
+    pub fn length(&self) -> u16 {
+        self.to_bow + self.to_stern
+    }
+
+    pub fn beam(&self) -> u16 {
+        self.to_port as u16 + self.to_starboard as u16
+    }
+}
+
+/// A decoded AIS message
+#[derive(Debug, Clone, PartialEq)]
+pub enum AisReport {
+    /// Class A (types 1-3) or class B (type 18) position report
+    Position {
+        mmsi: u32,
+        latitude: Option<f64>,
+        longitude: Option<f64>,
+        speed_kts: Option<f32>,
+        course_deg: Option<f32>,
+        heading_deg: Option<u16>,
+    },
+    /// Static data from type 5 or either part of type 24; fields a message
+    /// part does not carry are `None`
+    Static {
+        mmsi: u32,
+        name: Option<String>,
+        callsign: Option<String>,
+        ship_type: Option<u8>,
+        dimensions: Option<AisDimensions>,
+    },
+}
+
+impl AisReport {
+    pub fn mmsi(&self) -> u32 {
+        match self {
+            AisReport::Position { mmsi, .. } | AisReport::Static { mmsi, .. } => *mmsi,
+        }
+    }
+}
+
+struct Bits {
+    bits: Vec<bool>,
+}
+
+impl Bits {
+    fn from_armored(payload: &str, fill_bits: usize) -> Option<Self> {
+        let mut bits = Vec::with_capacity(payload.len() * 6);
+        for byte in payload.bytes() {
+            if !(48..=119).contains(&byte) || (88..=95).contains(&byte) {
+                return None;
+            }
+            let mut value = byte - 48;
+            if value > 40 {
+                value -= 8;
+            }
+            for shift in (0..6).rev() {
+                bits.push((value >> shift) & 1 == 1);
+            }
+        }
+        bits.truncate(bits.len().saturating_sub(fill_bits));
+        Some(Self { bits })
+    }
+
+    fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    fn unsigned(&self, start: usize, len: usize) -> u32 {
+        self.bits[start..start + len]
+            .iter()
+            .fold(0u32, |acc, bit| (acc << 1) | *bit as u32)
+    }
+
+    fn signed(&self, start: usize, len: usize) -> i32 {
+        let value = self.unsigned(start, len);
+        // Sign-extend from `len` bits
+        ((value << (32 - len)) as i32) >> (32 - len)
+    }
+
+    fn text(&self, start: usize, chars: usize) -> String {
+        let text: String = (0..chars)
+            .map(|index| {
+                let value = self.unsigned(start + index * 6, 6) as u8;
+                if value < 32 { (value + 64) as char } else { value as char }
+            })
+            .collect();
+        text.trim_end_matches('@').trim().to_string()
+    }
+}
+
+fn non_empty(text: String) -> Option<String> {
+    if text.is_empty() { None } else { Some(text) }
+}
+
+fn coordinate(raw: i32, not_available: i32, limit: f64) -> Option<f64> {
+    if raw == not_available {
+        return None;
+    }
+    let degrees = raw as f64 / 600_000.0;
+    (degrees.abs() <= limit).then_some(degrees)
+}
+
+fn position(bits: &Bits, mmsi: u32, sog: usize, lon: usize, lat: usize, cog: usize, heading: usize) -> AisReport {
+    let speed = bits.unsigned(sog, 10);
+    let course = bits.unsigned(cog, 12);
+    let true_heading = bits.unsigned(heading, 9) as u16;
+    AisReport::Position {
+        mmsi,
+        latitude: coordinate(bits.signed(lat, 27), 91 * 600_000, 90.0),
+        longitude: coordinate(bits.signed(lon, 28), 181 * 600_000, 180.0),
+        speed_kts: (speed != 1023).then_some(speed as f32 / 10.0),
+        course_deg: (course != 3600).then_some(course as f32 / 10.0),
+        heading_deg: (true_heading != 511).then_some(true_heading),
+    }
+}
+
+fn dimensions(bits: &Bits, start: usize) -> Option<AisDimensions> {
+    let dimensions = AisDimensions {
+        to_bow: bits.unsigned(start, 9) as u16,
+        to_stern: bits.unsigned(start + 9, 9) as u16,
+        to_port: bits.unsigned(start + 18, 6) as u8,
+        to_starboard: bits.unsigned(start + 24, 6) as u8,
+    };
+    (dimensions != AisDimensions::default()).then_some(dimensions)
+}
+
+/// MMSI of a complete armored payload, whatever its message type
+pub fn payload_mmsi(payload: &str, fill_bits: usize) -> Option<u32> {
+    let bits = Bits::from_armored(payload, fill_bits)?;
+    (bits.len() >= 38).then(|| bits.unsigned(8, 30))
+}
+
+/// Decode a complete armored payload
+pub fn decode_payload(payload: &str, fill_bits: usize) -> Option<AisReport> {
+    let bits = Bits::from_armored(payload, fill_bits)?;
+    if bits.len() < 38 {
+        return None;
+    }
+
+    let message_type = bits.unsigned(0, 6);
+    let mmsi = bits.unsigned(8, 30);
+
+    match message_type {
+        1..=3 if bits.len() >= 137 => Some(position(&bits, mmsi, 50, 61, 89, 116, 128)),
+        18 if bits.len() >= 133 => Some(position(&bits, mmsi, 46, 57, 85, 112, 124)),
+        5 if bits.len() >= 270 => Some(AisReport::Static {
+            mmsi,
+            callsign: non_empty(bits.text(70, 7)),
+            name: non_empty(bits.text(112, 20)),
+            ship_type: Some(bits.unsigned(232, 8) as u8).filter(|ship_type| *ship_type != 0),
+            dimensions: dimensions(&bits, 240),
+        }),
+        24 if bits.len() >= 160 => match bits.unsigned(38, 2) {
+            0 => Some(AisReport::Static {
+                mmsi,
+                name: non_empty(bits.text(40, 20)),
+                callsign: None,
+                ship_type: None,
+                dimensions: None,
+            }),
+            1 if bits.len() >= 162 => Some(AisReport::Static {
+                mmsi,
+                name: None,
+                ship_type: Some(bits.unsigned(40, 8) as u8).filter(|ship_type| *ship_type != 0),
+                callsign: non_empty(bits.text(90, 7)),
+                dimensions: dimensions(&bits, 132),
+            }),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// How long an incomplete multi-sentence group waits for its remaining fragments
+pub const DEFAULT_FRAGMENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Receive time of a group's first fragment and the fragments received so far
+type PendingFragments = (SystemTime, Vec<Option<DataMessage>>);
+
+/// Joins multi-sentence AIS messages and decodes the result
+///
+/// Fragments are grouped by sequential message ID and channel. A group that
+/// is still incomplete [`DEFAULT_FRAGMENT_TIMEOUT`] after its first fragment
+/// (by message receive time) is dropped, so a lost fragment does not leave
+/// the group waiting until the sequence ID comes round again.
+#[derive(Debug)]
+pub struct AisFragmentAssembler {
+    pending: HashMap<(String, String), PendingFragments>,
+    timeout: Duration,
+}
+
+impl Default for AisFragmentAssembler {
+    fn default() -> Self {
+        Self {
+            pending: HashMap::new(),
+            timeout: DEFAULT_FRAGMENT_TIMEOUT,
+        }
+    }
+}
+
+impl AisFragmentAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Feed an `AIS_SENTENCE` message; returns a report once all fragments of
+    /// a message have arrived
+    pub fn push(&mut self, message: &DataMessage) -> Option<AisReport> {
+        let message = self.join(message.clone())?;
+        decode_payload(message.get_str("payload")?, ais_fill_bits(&message).unwrap_or(0))
+    }
+
+    /// Feed an `AIS_SENTENCE` message; returns it once complete. Single
+    /// sentence messages pass straight through, and the last fragment of a
+    /// group comes back as one message carrying the joined `payload`, all
+    /// sentences in its raw payload and `fragment_count` 1.
+    pub fn join(&mut self, message: DataMessage) -> Option<DataMessage> {
+        let count = usize::try_from(message.get_i64("fragment_count")?).ok()?;
+        let number = usize::try_from(message.get_i64("fragment_number")?).ok()?;
+        message.get_str("payload")?;
+
+        if count <= 1 {
+            return Some(message);
+        }
+        if number == 0 || number > count {
+            return None;
+        }
+        let (now, timeout) = (message.received_at, self.timeout);
+        self.pending.retain(|_, (started, _)| now.duration_since(*started).map_or(true, |age| age <= timeout));
+
+        let key = (
+            message.get_str("message_id").unwrap_or_default().to_string(),
+            message.get_str("channel").unwrap_or_default().to_string(),
+        );
+        if number == 1 {
+            // A new first fragment restarts the sequence
+            self.pending.insert(key.clone(), (message.received_at, vec![None; count]));
+        }
+        let (_, fragments) = self.pending.get_mut(&key)?;
+        if fragments.len() != count {
+            self.pending.remove(&key);
+            return None;
+        }
+        fragments[number - 1] = Some(message);
+
+        if number < count || fragments.iter().any(Option::is_none) {
+            return None;
+        }
+        let fragments: Vec<DataMessage> = self.pending.remove(&key)?.1.into_iter().flatten().collect();
+        let payload: String = fragments.iter().filter_map(|fragment| fragment.get_str("payload")).collect();
+        let sentences: Vec<&[u8]> = fragments.iter().map(|fragment| fragment.payload.as_ref()).collect();
+        let raw = sentences.join(&b'\n');
+
+        let mut joined = fragments.last()?.clone()
+            .with_data("payload", payload)
+            .with_data("fragment_count", 1)
+            .with_data("fragment_number", 1)
+            .with_data("fragments", count);
+        joined.payload = raw.into();
+        Some(joined)
+    }
+
+    /// Incomplete groups waiting for fragments
+    pub fn pending_groups(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+/// Fill bits from the `fill_bits` field, or the field before the checksum
+pub(crate) fn ais_fill_bits(message: &DataMessage) -> Option<usize> {
+    if let Some(fill_bits) = message.get_i64("fill_bits") {
+        return usize::try_from(fill_bits).ok();
+    }
+    let sentence = std::str::from_utf8(&message.payload).ok()?;
+    let last_field = sentence.rsplit(',').next()?;
+    last_field.split('*').next()?.parse().ok()
+}
@@ -0,0 +1,78 @@
+//! Own-ship tagging and MMSI filtering of AIS messages
+//!
+//! `!AIVDO` sentences are the own transponder reporting itself and come out
+//! with `own_ship=true`; everything else is tagged `own_ship=false`. With
+//! `own_mmsi` set, `!AIVDM` reports of that MMSI, the transponder heard back
+//! through another receiver, are tagged as own ship too, so the boat never
+//! turns up as a target.
+//!
+//! `mmsi_allow` and `mmsi_deny` take comma-separated MMSIs. Traffic not on
+//! the allow list (when there is one) or on the deny list is dropped; own-ship
+//! reports always pass.
+
+use std::collections::HashSet;
+use datalink::{DataLinkConfig, DataLinkError, DataLinkResult, DataMessage};
+use super::decode::{ais_fill_bits, payload_mmsi};
+
+/// Which AIS reports a provider hands out
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MmsiFilter {
+    pub own_mmsi: Option<u32>,
+    pub allow: HashSet<u32>,
+    pub deny: HashSet<u32>,
+}
+
+impl MmsiFilter {
+    pub fn from_config(config: &DataLinkConfig) -> DataLinkResult<Self> {
+        let list = |key: &str| -> DataLinkResult<HashSet<u32>> {
+            config
+                .parameters
+                .get(key)
+                .map_or("", String::as_str)
+                .split(',')
+                .map(str::trim)
+                .filter(|mmsi| !mmsi.is_empty())
+                .map(|mmsi| parse_mmsi(mmsi).ok_or_else(|| DataLinkError::InvalidConfig(format!("Invalid MMSI in {}: {}", key, mmsi))))
+                .collect()
+        };
+        let own_mmsi = match config.parameters.get("own_mmsi") {
+            Some(mmsi) => Some(parse_mmsi(mmsi).ok_or_else(|| DataLinkError::InvalidConfig(format!("Invalid own_mmsi: {}", mmsi)))?),
+            None => None,
+        };
+        Ok(Self {
+            own_mmsi,
+            allow: list("mmsi_allow")?,
+            deny: list("mmsi_deny")?,
+        })
+    }
+
+    /// Tag a complete `AIS_SENTENCE` message with its `mmsi` and `own_ship`,
+    /// or drop it when filtered out
+    pub fn apply(&self, message: DataMessage) -> Option<DataMessage> {
+        let mmsi = message
+            .get_str("payload")
+            .and_then(|payload| payload_mmsi(payload, ais_fill_bits(&message).unwrap_or(0)));
+        let vdo = message.get_str("sentence_type").is_some_and(|sentence_type| sentence_type.ends_with("VDO"));
+        let own_ship = vdo || (mmsi.is_some() && mmsi == self.own_mmsi);
+        if !own_ship {
+            if let Some(mmsi) = mmsi {
+                if self.deny.contains(&mmsi) || (!self.allow.is_empty() && !self.allow.contains(&mmsi)) {
+                    return None;
+                }
+            }
+        }
+        let message = message.with_data("own_ship", own_ship);
+        Some(match mmsi {
+            Some(mmsi) => message.with_data("mmsi", i64::from(mmsi)),
+            None => message,
+        })
+    }
+}
+
+/// A nine-digit MMSI
+fn parse_mmsi(value: &str) -> Option<u32> {
+    let value = value.trim();
+    (value.len() == 9 && value.bytes().all(|byte| byte.is_ascii_digit()))
+        .then(|| value.parse().ok())
+        .flatten()
+}
@@ -1,13 +1,23 @@
 use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, SystemTime};
-use log::{error, info, warn};
+use std::time::SystemTime;
+use log::{error, info};
 use serde::{Deserialize, Serialize};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::net::{TcpStream, UdpSocket};
 use tokio::sync::mpsc;
-use tokio_serial::SerialPortBuilderExt;
-use datalink::{DataLinkConfig, DataLinkError, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataLinkTransmitter, DataMessage};
+use datalink::{DataLinkConfig, DataLinkError, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataLinkTransmitter, DataMessage, LinkStats};
+use crate::raw_log::RawLog;
+use crate::reconnect::{ReceiverResult, Reconnector, SharedStatus};
+use crate::replay::{ReplayFile, ReplayOptions};
+use crate::runtime::runtime_handle;
+use crate::serial;
+use crate::transport::{self, TransportConfig, TransportHub, TransportSubscription};
+
+mod decode;
+mod filter;
+pub use decode::{decode_payload, payload_mmsi, AisDimensions, AisFragmentAssembler, AisReport, DEFAULT_FRAGMENT_TIMEOUT};
+pub use filter::MmsiFilter;
 
 /// Configuration for different types of AIS data sources
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,33 +40,61 @@ pub enum AisSourceConfig {
     /// File replay configuration
     File {
         path: String,
-        replay_speed: f64, // 1.0 = real-time, 2.0 = 2x speed, etc.
+        replay: ReplayOptions,
     },
 }
 
+impl From<&AisSourceConfig> for TransportConfig {
+    fn from(source: &AisSourceConfig) -> Self {
+        match source {
+            AisSourceConfig::Serial { port, baud_rate } => TransportConfig::Serial { port: port.clone(), baud_rate: *baud_rate },
+            AisSourceConfig::Tcp { host, port } => TransportConfig::Tcp { host: host.clone(), port: *port },
+            AisSourceConfig::Udp { bind_addr, port } => TransportConfig::Udp { bind_addr: bind_addr.clone(), port: *port },
+            AisSourceConfig::File { path, replay } => TransportConfig::File { path: path.clone(), replay: replay.clone() },
+        }
+    }
+}
+
 /// Real AIS Datalink Provider
 pub struct AisDataLinkProvider {
-    status: DataLinkStatus,
+    status: SharedStatus,
     config: Option<DataLinkConfig>,
     source_config: Option<AisSourceConfig>,
     message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
     receiver_handle: Option<tokio::task::JoinHandle<()>>,
+    /// Runtime for the receiver tasks; the caller's when unset
+    runtime: Option<tokio::runtime::Handle>,
     shutdown_tx: Option<mpsc::Sender<()>>,
+    /// Set when connected with `shared=true`
+    shared: Option<TransportSubscription>,
+    /// Joins multi-sentence messages before they are handed out
+    fragments: AisFragmentAssembler,
+    filter: MmsiFilter,
 }
 
 impl AisDataLinkProvider {
     /// Create a new AIS datalink provider
     pub fn new() -> Self {
         Self {
-            status: DataLinkStatus::Disconnected,
+            status: SharedStatus::default(),
             config: None,
             source_config: None,
             message_queue: Arc::new(Mutex::new(VecDeque::new())),
             receiver_handle: None,
+            runtime: None,
             shutdown_tx: None,
+            shared: None,
+            fragments: AisFragmentAssembler::new(),
+            filter: MmsiFilter::default(),
         }
     }
 
+    /// Spawn the receiver task on `runtime` rather than the caller's
+    pub fn with_runtime(mut self, runtime: tokio::runtime::Handle) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
     /// Parse AIS source configuration from DataLinkConfig
     pub fn parse_source_config(config: &DataLinkConfig) -> DataLinkResult<AisSourceConfig> {
         let connection_type = config.parameters.get("connection_type")
@@ -66,10 +104,7 @@ impl AisDataLinkProvider {
             "serial" => {
                 let port = config.parameters.get("port")
                     .ok_or_else(|| DataLinkError::InvalidConfig("Missing port for serial connection".to_string()))?;
-                let baud_rate = config.parameters.get("baud_rate")
-                    .unwrap_or(&"4800".to_string())
-                    .parse::<u32>()
-                    .map_err(|_| DataLinkError::InvalidConfig("Invalid baud_rate".to_string()))?;
+                let baud_rate = serial::parse_baud_rate(config.parameters.get("baud_rate").map(String::as_str), 4800)?;
 
                 Ok(AisSourceConfig::Serial {
                     port: port.clone(),
@@ -106,14 +141,10 @@ impl AisDataLinkProvider {
             "file" => {
                 let path = config.parameters.get("path")
                     .ok_or_else(|| DataLinkError::InvalidConfig("Missing path for file replay".to_string()))?;
-                let replay_speed = config.parameters.get("replay_speed")
-                    .unwrap_or(&"1.0".to_string())
-                    .parse::<f64>()
-                    .map_err(|_| DataLinkError::InvalidConfig("Invalid replay_speed".to_string()))?;
 
                 Ok(AisSourceConfig::File {
                     path: path.clone(),
-                    replay_speed,
+                    replay: ReplayOptions::from_config(config)?,
                 })
             }
             _ => Err(DataLinkError::InvalidConfig(format!("Unsupported connection type: {}", connection_type))),
@@ -121,21 +152,28 @@ impl AisDataLinkProvider {
     }
 
     /// Start the data receiver task based on the source configuration
-    async fn start_receiver(&mut self) -> DataLinkResult<()> {
+    fn start_receiver(&mut self) -> DataLinkResult<()> {
         let source_config = self.source_config.as_ref()
             .ok_or_else(|| DataLinkError::InvalidConfig("No source configuration".to_string()))?;
 
+        let runtime = runtime_handle(self.runtime.as_ref())?;
         let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
         let message_queue = Arc::clone(&self.message_queue);
+        let status = self.status.clone();
+        let auto_reconnect = self.config.as_ref().is_none_or(|config| config.auto_reconnect);
 
         let receiver_handle = match source_config {
             AisSourceConfig::Serial { port, baud_rate } => {
                 let port = port.clone();
                 let baud_rate = *baud_rate;
 
-                tokio::spawn(async move {
-                    if let Err(e) = Self::serial_receiver(port, baud_rate, message_queue, &mut shutdown_rx).await {
-                        error!("Serial receiver error: {}", e);
+                runtime.spawn(async move {
+                    let mut reconnector = Reconnector::new("AIS serial", auto_reconnect, status);
+                    loop {
+                        let result = Self::serial_receiver(port.clone(), baud_rate, Arc::clone(&message_queue), &mut shutdown_rx, reconnector.status()).await;
+                        if !reconnector.retry(result, &mut shutdown_rx).await {
+                            break;
+                        }
                     }
                 })
             }
@@ -143,9 +181,13 @@ impl AisDataLinkProvider {
                 let host = host.clone();
                 let port = *port;
 
-                tokio::spawn(async move {
-                    if let Err(e) = Self::tcp_receiver(host, port, message_queue, &mut shutdown_rx).await {
-                        error!("TCP receiver error: {}", e);
+                runtime.spawn(async move {
+                    let mut reconnector = Reconnector::new("AIS TCP", auto_reconnect, status);
+                    loop {
+                        let result = Self::tcp_receiver(host.clone(), port, Arc::clone(&message_queue), &mut shutdown_rx, reconnector.status()).await;
+                        if !reconnector.retry(result, &mut shutdown_rx).await {
+                            break;
+                        }
                     }
                 })
             }
@@ -153,19 +195,27 @@ impl AisDataLinkProvider {
                 let bind_addr = bind_addr.clone();
                 let port = *port;
 
-                tokio::spawn(async move {
-                    if let Err(e) = Self::udp_receiver(bind_addr, port, message_queue, &mut shutdown_rx).await {
-                        error!("UDP receiver error: {}", e);
+                runtime.spawn(async move {
+                    let mut reconnector = Reconnector::new("AIS UDP", auto_reconnect, status);
+                    loop {
+                        let result = Self::udp_receiver(bind_addr.clone(), port, Arc::clone(&message_queue), &mut shutdown_rx, reconnector.status()).await;
+                        if !reconnector.retry(result, &mut shutdown_rx).await {
+                            break;
+                        }
                     }
                 })
             }
-            AisSourceConfig::File { path, replay_speed } => {
+            AisSourceConfig::File { path, replay } => {
                 let path = path.clone();
-                let replay_speed = *replay_speed;
+                let replay = replay.clone();
 
-                tokio::spawn(async move {
-                    if let Err(e) = Self::file_receiver(path, replay_speed, message_queue, &mut shutdown_rx).await {
-                        error!("File receiver error: {}", e);
+                runtime.spawn(async move {
+                    let mut reconnector = Reconnector::new("AIS file", auto_reconnect, status);
+                    loop {
+                        let result = Self::file_receiver(path.clone(), replay.clone(), Arc::clone(&message_queue), &mut shutdown_rx, reconnector.status()).await;
+                        if !reconnector.retry(result, &mut shutdown_rx).await {
+                            break;
+                        }
                     }
                 })
             }
@@ -183,11 +233,12 @@ impl AisDataLinkProvider {
         baud_rate: u32,
         message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
         shutdown_rx: &mut mpsc::Receiver<()>,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        info!("Starting serial receiver on port {} at {} baud", port, baud_rate);
+        status: &SharedStatus,
+    ) -> ReceiverResult {
+        info!("Starting serial receiver on port {} at {} baud", port, serial::baud_rate_label(baud_rate));
 
-        let serial_port = tokio_serial::new(&port, baud_rate)
-            .open_native_async()?;
+        let (serial_port, _) = serial::open(&port, baud_rate).await?;
+        status.set(DataLinkStatus::Connected);
 
         let mut reader = BufReader::new(serial_port);
         let mut line = String::new();
@@ -200,12 +251,9 @@ impl AisDataLinkProvider {
                 }
                 result = reader.read_line(&mut line) => {
                     match result {
-                        Ok(0) => {
-                            warn!("Serial port closed");
-                            break;
-                        }
+                        Ok(0) => return Err("Serial port closed".into()),
                         Ok(_) => {
-                            if let Some(message) = Self::parse_ais_sentence(&line.trim()) {
+                            if let Some(message) = status.stats().observe(&line, Self::parse_ais_sentence(line.trim())) {
                                 if let Ok(mut queue) = message_queue.lock() {
                                     queue.push_back(message);
                                     // Limit queue size to prevent memory issues
@@ -216,10 +264,7 @@ impl AisDataLinkProvider {
                             }
                             line.clear();
                         }
-                        Err(e) => {
-                            error!("Serial read error: {}", e);
-                            break;
-                        }
+                        Err(e) => return Err(format!("Serial read error: {}", e).into()),
                     }
                 }
             }
@@ -234,10 +279,12 @@ impl AisDataLinkProvider {
         port: u16,
         message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
         shutdown_rx: &mut mpsc::Receiver<()>,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        status: &SharedStatus,
+    ) -> ReceiverResult {
         info!("Starting TCP receiver connecting to {}:{}", host, port);
 
         let stream = TcpStream::connect(format!("{}:{}", host, port)).await?;
+        status.set(DataLinkStatus::Connected);
         let mut reader = BufReader::new(stream);
         let mut line = String::new();
 
@@ -249,12 +296,9 @@ impl AisDataLinkProvider {
                 }
                 result = reader.read_line(&mut line) => {
                     match result {
-                        Ok(0) => {
-                            warn!("TCP connection closed");
-                            break;
-                        }
+                        Ok(0) => return Err("TCP connection closed".into()),
                         Ok(_) => {
-                            if let Some(message) = Self::parse_ais_sentence(&line.trim()) {
+                            if let Some(message) = status.stats().observe(&line, Self::parse_ais_sentence(line.trim())) {
                                 if let Ok(mut queue) = message_queue.lock() {
                                     queue.push_back(message);
                                     if queue.len() > 1000 {
@@ -264,10 +308,7 @@ impl AisDataLinkProvider {
                             }
                             line.clear();
                         }
-                        Err(e) => {
-                            error!("TCP read error: {}", e);
-                            break;
-                        }
+                        Err(e) => return Err(format!("TCP read error: {}", e).into()),
                     }
                 }
             }
@@ -282,10 +323,12 @@ impl AisDataLinkProvider {
         port: u16,
         message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
         shutdown_rx: &mut mpsc::Receiver<()>,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        status: &SharedStatus,
+    ) -> ReceiverResult {
         info!("Starting UDP receiver on {}:{}", bind_addr, port);
 
         let socket = UdpSocket::bind(format!("{}:{}", bind_addr, port)).await?;
+        status.set(DataLinkStatus::Connected);
         let mut buf = [0; 1024];
 
         loop {
@@ -299,7 +342,7 @@ impl AisDataLinkProvider {
                         Ok(len) => {
                             let data = String::from_utf8_lossy(&buf[..len]);
                             for line in data.lines() {
-                                if let Some(message) = Self::parse_ais_sentence(line.trim()) {
+                                if let Some(message) = status.stats().observe(line, Self::parse_ais_sentence(line.trim())) {
                                     if let Ok(mut queue) = message_queue.lock() {
                                         queue.push_back(message);
                                         if queue.len() > 1000 {
@@ -309,10 +352,7 @@ impl AisDataLinkProvider {
                                 }
                             }
                         }
-                        Err(e) => {
-                            error!("UDP receive error: {}", e);
-                            break;
-                        }
+                        Err(e) => return Err(format!("UDP receive error: {}", e).into()),
                     }
                 }
             }
@@ -324,17 +364,15 @@ impl AisDataLinkProvider {
     /// File receiver implementation for replaying AIS data
     async fn file_receiver(
         path: String,
-        replay_speed: f64,
+        replay: ReplayOptions,
         message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
         shutdown_rx: &mut mpsc::Receiver<()>,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        info!("Starting file receiver for {} at {}x speed", path, replay_speed);
+        status: &SharedStatus,
+    ) -> ReceiverResult {
+        info!("Starting file receiver for {} at {}x speed", path, replay.speed);
 
-        let file = tokio::fs::File::open(&path).await?;
-        let reader = BufReader::new(file);
-        let mut lines = reader.lines();
-
-        let delay_duration = Duration::from_millis((1000.0 / replay_speed) as u64);
+        let mut replay = ReplayFile::open(&path, replay).await?;
+        status.set(DataLinkStatus::Connected);
 
         loop {
             tokio::select! {
@@ -342,10 +380,10 @@ impl AisDataLinkProvider {
                     info!("File receiver shutdown requested");
                     break;
                 }
-                result = lines.next_line() => {
+                result = replay.next_line() => {
                     match result {
                         Ok(Some(line)) => {
-                            if let Some(message) = Self::parse_ais_sentence(&line.trim()) {
+                            if let Some(message) = status.stats().observe(&line, Self::parse_ais_sentence(line.trim())) {
                                 if let Ok(mut queue) = message_queue.lock() {
                                     queue.push_back(message);
                                     if queue.len() > 1000 {
@@ -353,7 +391,6 @@ impl AisDataLinkProvider {
                                     }
                                 }
                             }
-                            tokio::time::sleep(delay_duration).await;
                         }
                         Ok(None) => {
                             info!("End of file reached");
@@ -398,22 +435,30 @@ impl AisDataLinkProvider {
 
         // Add parsed data if available
         if parts.len() >= 6 {
-            message = message.with_data("sentence_type".to_string(), sentence_type.to_string());
-            message = message.with_data("fragment_count".to_string(), parts[1].to_string());
-            message = message.with_data("fragment_number".to_string(), parts[2].to_string());
-            message = message.with_data("message_id".to_string(), parts[3].to_string());
-            message = message.with_data("channel".to_string(), parts[4].to_string());
-            message = message.with_data("payload".to_string(), parts[5].to_string());
+            message = message.with_data("sentence_type", sentence_type.to_string());
+            // VDO is the own transponder, VDM everyone else
+            message = message.with_data("own_ship", sentence_type.ends_with("VDO"));
+            if let Ok(fragment_count) = parts[1].parse::<i64>() {
+                message = message.with_data("fragment_count", fragment_count);
+            }
+            if let Ok(fragment_number) = parts[2].parse::<i64>() {
+                message = message.with_data("fragment_number", fragment_number);
+            }
+            message = message.with_data("message_id", parts[3].to_string());
+            message = message.with_data("channel", parts[4].to_string());
+            message = message.with_data("payload", parts[5].to_string());
+        }
+        if let Some(fill_bits) = parts.get(6).and_then(|field| field.split('*').next()?.parse::<i64>().ok()) {
+            message = message.with_data("fill_bits", fill_bits);
         }
 
         // Add timestamp
         message = message.with_data(
-            "timestamp".to_string(),
+            "timestamp",
             SystemTime::now()
                 .duration_since(SystemTime::UNIX_EPOCH)
                 .unwrap_or_default()
-                .as_secs()
-                .to_string(),
+                .as_secs(),
         );
 
         // Set signal quality based on sentence completeness
@@ -424,13 +469,13 @@ impl AisDataLinkProvider {
     }
 
     /// Stop the receiver task
-    async fn stop_receiver(&mut self) {
+    fn stop_receiver(&mut self) {
         if let Some(shutdown_tx) = self.shutdown_tx.take() {
-            let _ = shutdown_tx.send(()).await;
+            let _ = shutdown_tx.try_send(());
         }
 
         if let Some(handle) = self.receiver_handle.take() {
-            let _ = handle.await;
+            handle.abort();
         }
     }
 }
@@ -443,51 +488,78 @@ impl Default for AisDataLinkProvider {
 
 impl DataLinkReceiver for AisDataLinkProvider {
     fn status(&self) -> DataLinkStatus {
-        self.status.clone()
+        self.status.get()
     }
 
     fn receive_message(&mut self) -> DataLinkResult<Option<DataMessage>> {
         if let Ok(mut queue) = self.message_queue.lock() {
-            Ok(queue.pop_front())
+            if let Some(shared) = &self.shared {
+                for line in shared.drain() {
+                    if let Some(message) = self.status.stats().observe(&line, Self::parse_ais_sentence(&line)) {
+                        queue.push_back(message);
+                    }
+                }
+            }
+            while let Some(message) = queue.pop_front() {
+                if let Some(message) = self.fragments.join(message).and_then(|message| self.filter.apply(message)) {
+                    return Ok(Some(message));
+                }
+            }
+            Ok(None)
         } else {
             Err(DataLinkError::TransportError("Failed to access message queue".to_string()))
         }
     }
 
+    fn stats(&self) -> LinkStats {
+        self.status.stats().get()
+    }
+
     fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
         info!("Connecting AIS datalink provider");
 
-        self.status = DataLinkStatus::Connecting;
-        self.config = Some(config.clone());
-
-        // Parse source configuration
-        self.source_config = Some(Self::parse_source_config(config)?);
+        // Check the whole configuration before stopping a running receiver
+        let source_config = Self::parse_source_config(config)?;
+        let raw_log = RawLog::from_config(config)?;
+        let filter = MmsiFilter::from_config(config)?;
+        let shared = if transport::is_shared(config) {
+            Some(TransportHub::global().subscribe(&TransportConfig::from(&source_config))?)
+        } else {
+            None
+        };
+        self.stop_receiver();
 
-        // Start the receiver in a blocking context
-        let rt = tokio::runtime::Runtime::new()
-            .map_err(|e| DataLinkError::ConnectionFailed(format!("Failed to create runtime: {}", e)))?;
+        self.status.set(DataLinkStatus::Connecting);
+        self.config = Some(config.clone());
+        self.source_config = Some(source_config);
+        self.status.stats().set_raw_log(raw_log);
+        self.filter = filter;
 
-        rt.block_on(async {
-            self.start_receiver().await
-        })?;
+        self.shared = shared;
+        if self.shared.is_none() {
+            self.start_receiver()?;
+        }
 
-        self.status = DataLinkStatus::Connected;
+        self.status.set(DataLinkStatus::Connected);
         info!("AIS datalink provider connected successfully");
 
         Ok(())
     }
 
+    /// Connecting again keeps the queue, counters and partial fragments
+    fn reconfigure(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        DataLinkReceiver::connect(self, config)
+    }
+
     fn disconnect(&mut self) -> DataLinkResult<()> {
         info!("Disconnecting AIS datalink provider");
 
-        let rt = tokio::runtime::Runtime::new()
-            .map_err(|e| DataLinkError::TransportError(format!("Failed to create runtime: {}", e)))?;
-
-        rt.block_on(async {
-            self.stop_receiver().await;
-        });
+        self.stop_receiver();
+        self.shared = None;
+        self.status.stats().set_raw_log(None);
+        self.fragments = AisFragmentAssembler::new();
 
-        self.status = DataLinkStatus::Disconnected;
+        self.status.set(DataLinkStatus::Disconnected);
         self.config = None;
         self.source_config = None;
 
@@ -498,7 +570,7 @@ impl DataLinkReceiver for AisDataLinkProvider {
 
 impl DataLinkTransmitter for AisDataLinkProvider {
     fn status(&self) -> DataLinkStatus {
-        self.status.clone()
+        self.status.get()
     }
 
     fn send_message(&mut self, _message: &DataMessage) -> DataLinkResult<()> {
@@ -0,0 +1,406 @@
+//! aisstream.io datalink
+//!
+//! Streams AIS reports from aisstream.io without going through the `ais`
+//! server. The service sends decoded reports as JSON rather than `!AIVDM`
+//! sentences; each becomes an [`AISSTREAM_MESSAGE_TYPE`] message carrying the
+//! JSON as payload, and [`AisStreamIoProvider::report`] turns it back into the
+//! [`AisReport`] the AIVDM decoder produces.
+//!
+//! Parameters: `api_key` (usually `credential:aisstream`), `bounding_boxes`
+//! as `sw_lat,sw_lon,ne_lat,ne_lon` separated by `;` (the whole globe when
+//! unset), and `mmsi`, a comma separated list of vessels to follow.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use futures::{SinkExt, StreamExt};
+use log::info;
+use serde::Serialize;
+use serde_json::Value as Json;
+use tokio::sync::mpsc;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::protocol::Message;
+use datalink::{DataLinkConfig, DataLinkError, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataLinkTransmitter, DataMessage, LinkStats};
+use crate::ais::{AisDimensions, AisReport};
+use crate::raw_log::RawLog;
+use crate::reconnect::{ReceiverResult, Reconnector, SharedStatus};
+use crate::runtime::runtime_handle;
+
+/// aisstream.io streaming endpoint
+pub const AISSTREAM_URL: &str = "wss://stream.aisstream.io/v0/stream";
+
+/// Message type of reports from aisstream.io
+pub const AISSTREAM_MESSAGE_TYPE: &str = "AISSTREAM";
+
+/// Most MMSIs aisstream.io accepts in one subscription
+pub const MAX_MMSI_FILTERS: usize = 50;
+
+/// Report types [`AisStreamIoProvider::report`] decodes; the subscription asks for no others
+const REPORT_TYPES: [&str; 4] = ["PositionReport", "StandardClassBPositionReport", "ShipStaticData", "StaticDataReport"];
+
+/// Messages buffered before the oldest are dropped
+const MAX_QUEUED_MESSAGES: usize = 1000;
+
+/// Area as south-west and north-east corners, `[lat, lon]` each
+pub type BoundingBox = [[f64; 2]; 2];
+
+/// What to subscribe to
+#[derive(Debug, Clone, PartialEq)]
+pub struct AisStreamConfig {
+    pub url: String,
+    pub api_key: String,
+    pub bounding_boxes: Vec<BoundingBox>,
+    pub mmsi: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Subscription<'a> {
+    #[serde(rename = "APIKey")]
+    api_key: &'a str,
+    #[serde(rename = "BoundingBoxes")]
+    bounding_boxes: &'a [BoundingBox],
+    #[serde(rename = "FiltersShipMMSI", skip_serializing_if = "<[_]>::is_empty")]
+    mmsi: &'a [String],
+    #[serde(rename = "FilterMessageTypes")]
+    message_types: &'a [&'a str],
+}
+
+impl AisStreamConfig {
+    pub fn from_config(config: &DataLinkConfig) -> DataLinkResult<Self> {
+        let param = |key: &str| config.parameters.get(key).cloned();
+        let api_key = param("api_key")
+            .filter(|key| !key.trim().is_empty())
+            .ok_or_else(|| DataLinkError::InvalidConfig("Missing api_key parameter for aisstream.io".to_string()))?;
+
+        let bounding_boxes = match param("bounding_boxes") {
+            Some(boxes) => boxes.split(';').map(parse_bounding_box).collect::<DataLinkResult<Vec<_>>>()?,
+            None => vec![[[-90.0, -180.0], [90.0, 180.0]]],
+        };
+
+        let mmsi: Vec<String> = param("mmsi")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|mmsi| !mmsi.is_empty())
+            .map(str::to_string)
+            .collect();
+        if let Some(invalid) = mmsi.iter().find(|mmsi| mmsi.len() != 9 || !mmsi.bytes().all(|byte| byte.is_ascii_digit())) {
+            return Err(DataLinkError::InvalidConfig(format!("Invalid MMSI: {}", invalid)));
+        }
+        if mmsi.len() > MAX_MMSI_FILTERS {
+            return Err(DataLinkError::InvalidConfig(format!("aisstream.io accepts at most {} MMSIs", MAX_MMSI_FILTERS)));
+        }
+
+        Ok(Self {
+            url: param("url").unwrap_or_else(|| AISSTREAM_URL.to_string()),
+            api_key,
+            bounding_boxes,
+            mmsi,
+        })
+    }
+
+    /// The subscription message sent after connecting
+    pub fn subscription(&self) -> String {
+        let subscription = Subscription {
+            api_key: &self.api_key,
+            bounding_boxes: &self.bounding_boxes,
+            mmsi: &self.mmsi,
+            message_types: &REPORT_TYPES,
+        };
+        serde_json::to_string(&subscription).unwrap_or_default()
+    }
+}
+
+fn parse_bounding_box(text: &str) -> DataLinkResult<BoundingBox> {
+    let invalid = || DataLinkError::InvalidConfig(format!("Invalid bounding box: {}", text));
+    let values = text
+        .split(',')
+        .map(|value| value.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| invalid())?;
+    let [sw_lat, sw_lon, ne_lat, ne_lon] = values[..] else {
+        return Err(invalid());
+    };
+    let valid = [sw_lat, ne_lat].iter().all(|lat| (-90.0..=90.0).contains(lat))
+        && [sw_lon, ne_lon].iter().all(|lon| (-180.0..=180.0).contains(lon))
+        && sw_lat <= ne_lat;
+    if !valid {
+        return Err(invalid());
+    }
+    Ok([[sw_lat, sw_lon], [ne_lat, ne_lon]])
+}
+
+pub struct AisStreamIoProvider {
+    status: SharedStatus,
+    message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
+    shutdown_tx: Option<mpsc::Sender<()>>,
+    receiver_handle: Option<tokio::task::JoinHandle<()>>,
+    /// Runtime for the receiver task; the caller's when unset
+    runtime: Option<tokio::runtime::Handle>,
+}
+
+impl AisStreamIoProvider {
+    pub fn new() -> Self {
+        Self {
+            status: SharedStatus::default(),
+            message_queue: Arc::new(Mutex::new(VecDeque::new())),
+            shutdown_tx: None,
+            receiver_handle: None,
+            runtime: None,
+        }
+    }
+
+    /// Spawn the receiver task on `runtime` rather than the caller's
+    pub fn with_runtime(mut self, runtime: tokio::runtime::Handle) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// The report carried by an [`AISSTREAM_MESSAGE_TYPE`] message
+    pub fn report(message: &DataMessage) -> Option<AisReport> {
+        let json = serde_json::from_slice(&message.payload).ok()?;
+        aisstream_report(&json)
+    }
+
+    /// A message for one aisstream.io report, or `None` for report types
+    /// that are not decoded
+    pub fn parse_aisstream_message(json: &Json, received_at: SystemTime) -> Option<DataMessage> {
+        let report_type = json.get("MessageType")?.as_str()?;
+        if !REPORT_TYPES.contains(&report_type) {
+            return None;
+        }
+        let metadata = json.get("MetaData");
+        let mmsi = metadata.and_then(|metadata| metadata.get("MMSI")).and_then(json_u32)?;
+        let payload = serde_json::to_vec(json).ok()?;
+        let mut message = DataMessage::new(AISSTREAM_MESSAGE_TYPE.to_string(), mmsi.to_string(), payload)
+            .with_received_at(received_at)
+            .with_data("mmsi", mmsi)
+            .with_data("report_type", report_type.to_string());
+        if let Some(name) = metadata.and_then(|metadata| metadata.get("ShipName")).and_then(Json::as_str) {
+            message = message.with_data("ship_name", name.trim().to_string());
+        }
+        Some(message)
+    }
+
+    fn start_receiver(&mut self, config: AisStreamConfig, auto_reconnect: bool) -> DataLinkResult<()> {
+        let runtime = runtime_handle(self.runtime.as_ref())?;
+        let (shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
+        let message_queue = Arc::clone(&self.message_queue);
+        let status = self.status.clone();
+
+        let handle = runtime.spawn(async move {
+            let mut reconnector = Reconnector::new("aisstream.io", auto_reconnect, status);
+            loop {
+                let result = Self::run(&config, &message_queue, &mut shutdown_rx, reconnector.status()).await;
+                if !reconnector.retry(result, &mut shutdown_rx).await {
+                    break;
+                }
+            }
+        });
+
+        self.shutdown_tx = Some(shutdown_tx);
+        self.receiver_handle = Some(handle);
+        Ok(())
+    }
+
+    async fn run(
+        config: &AisStreamConfig,
+        message_queue: &Mutex<VecDeque<DataMessage>>,
+        shutdown_rx: &mut mpsc::Receiver<()>,
+        status: &SharedStatus,
+    ) -> ReceiverResult {
+        let (mut socket, _) = connect_async(config.url.as_str()).await?;
+        socket.send(Message::Text(config.subscription())).await?;
+        info!("Subscribed to aisstream.io for {} area(s)", config.bounding_boxes.len());
+        status.set(DataLinkStatus::Connected);
+
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => {
+                    info!("aisstream.io shutdown requested");
+                    let _ = socket.close(None).await;
+                    return Ok(());
+                }
+                frame = socket.next() => {
+                    // Reports arrive as binary frames holding JSON
+                    let text = match frame {
+                        Some(Ok(Message::Text(text))) => text,
+                        Some(Ok(Message::Binary(data))) => String::from_utf8_lossy(&data).into_owned(),
+                        Some(Ok(Message::Close(_))) | None => return Err("aisstream.io closed the connection".into()),
+                        Some(Ok(_)) => continue,
+                        Some(Err(e)) => return Err(e.into()),
+                    };
+                    status.stats().log_raw(&text);
+                    let json: Json = match serde_json::from_str(&text) {
+                        Ok(json) => json,
+                        Err(_) => {
+                            status.stats().update(|stats| stats.record_unsupported("not JSON"));
+                            continue;
+                        }
+                    };
+                    // A bad key or subscription is answered with an error and a close
+                    if let Some(error) = json.get("error").and_then(Json::as_str) {
+                        return Err(format!("aisstream.io: {}", error).into());
+                    }
+                    match Self::parse_aisstream_message(&json, SystemTime::now()) {
+                        Some(message) => {
+                            status.stats().update(|stats| stats.record_parsed());
+                            if let Ok(mut queue) = message_queue.lock() {
+                                queue.push_back(message);
+                                while queue.len() > MAX_QUEUED_MESSAGES {
+                                    queue.pop_front();
+                                }
+                            }
+                        }
+                        None => {
+                            let report_type = json.get("MessageType").and_then(Json::as_str).unwrap_or("unknown");
+                            status.stats().update(|stats| stats.record_unsupported(report_type));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn stop_receiver(&mut self) {
+        if let Some(shutdown_tx) = self.shutdown_tx.take() {
+            let _ = shutdown_tx.try_send(());
+        }
+        if let Some(handle) = self.receiver_handle.take() {
+            handle.abort();
+        }
+    }
+}
+
+/// Decode an aisstream.io report, mapping the "not available" values of
+/// the underlying AIS fields to `None`
+pub fn aisstream_report(json: &Json) -> Option<AisReport> {
+    let report_type = json.get("MessageType")?.as_str()?;
+    let body = json.get("Message")?.get(report_type)?;
+    let mmsi = body.get("UserID").and_then(json_u32)?;
+    let number = |key: &str| body.get(key).and_then(Json::as_f64);
+    let text = |value: Option<&Json>| {
+        value
+            .and_then(Json::as_str)
+            .map(|text| text.trim_end_matches('@').trim().to_string())
+            .filter(|text| !text.is_empty())
+    };
+    match report_type {
+        "PositionReport" | "StandardClassBPositionReport" => Some(AisReport::Position {
+            mmsi,
+            latitude: number("Latitude").filter(|lat| lat.abs() <= 90.0),
+            longitude: number("Longitude").filter(|lon| lon.abs() <= 180.0),
+            speed_kts: number("Sog").filter(|sog| *sog < 102.3).map(|sog| sog as f32),
+            course_deg: number("Cog").filter(|cog| *cog < 360.0).map(|cog| cog as f32),
+            heading_deg: number("TrueHeading").filter(|heading| *heading < 360.0).map(|heading| heading as u16),
+        }),
+        "ShipStaticData" => Some(AisReport::Static {
+            mmsi,
+            name: text(body.get("Name")),
+            callsign: text(body.get("CallSign")),
+            ship_type: number("Type").map(|ship_type| ship_type as u8),
+            dimensions: body.get("Dimension").and_then(dimensions),
+        }),
+        "StaticDataReport" => {
+            let valid = |part: &str| body.get(part).filter(|part| part.get("Valid").and_then(Json::as_bool).unwrap_or(true));
+            let part_a = valid("ReportA");
+            let part_b = valid("ReportB");
+            Some(AisReport::Static {
+                mmsi,
+                name: text(part_a.and_then(|part| part.get("Name"))),
+                callsign: text(part_b.and_then(|part| part.get("CallSign"))),
+                ship_type: part_b.and_then(|part| part.get("ShipType")).and_then(Json::as_u64).map(|ship_type| ship_type as u8),
+                dimensions: part_b.and_then(|part| part.get("Dimension")).and_then(dimensions),
+            })
+        }
+        _ => None,
+    }
+}
+
+fn dimensions(json: &Json) -> Option<AisDimensions> {
+    let side = |key: &str| json.get(key).and_then(Json::as_u64).unwrap_or_default();
+    let dimensions = AisDimensions {
+        to_bow: side("A") as u16,
+        to_stern: side("B") as u16,
+        to_port: side("C") as u8,
+        to_starboard: side("D") as u8,
+    };
+    (dimensions != AisDimensions::default()).then_some(dimensions)
+}
+
+/// aisstream.io sends MMSIs as numbers, older captures as strings
+fn json_u32(json: &Json) -> Option<u32> {
+    match json {
+        Json::Number(number) => number.as_u64().and_then(|value| u32::try_from(value).ok()),
+        Json::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+impl Default for AisStreamIoProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DataLinkReceiver for AisStreamIoProvider {
+    fn status(&self) -> DataLinkStatus {
+        self.status.get()
+    }
+
+    fn receive_message(&mut self) -> DataLinkResult<Option<DataMessage>> {
+        match self.message_queue.lock() {
+            Ok(mut queue) => Ok(queue.pop_front()),
+            Err(_) => Err(DataLinkError::TransportError("Failed to access message queue".to_string())),
+        }
+    }
+
+    fn stats(&self) -> LinkStats {
+        self.status.stats().get()
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        let aisstream_config = AisStreamConfig::from_config(config)?;
+        self.status.stats().set_raw_log(RawLog::from_config(config)?);
+        info!("Connecting aisstream.io datalink to {}", aisstream_config.url);
+        self.stop_receiver();
+        self.status.set(DataLinkStatus::Connecting);
+        self.start_receiver(aisstream_config, config.auto_reconnect).inspect_err(|e| {
+            self.status.set(DataLinkStatus::Error(format!("Connection failed: {}", e)));
+        })
+    }
+
+    /// Apply new bounding boxes or filters; queued reports stay
+    fn reconfigure(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        DataLinkReceiver::connect(self, config)
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        info!("Disconnecting aisstream.io datalink");
+        self.stop_receiver();
+        self.status.stats().set_raw_log(None);
+        if let Ok(mut queue) = self.message_queue.lock() {
+            queue.clear();
+        }
+        self.status.set(DataLinkStatus::Disconnected);
+        Ok(())
+    }
+}
+
+impl DataLinkTransmitter for AisStreamIoProvider {
+    fn status(&self) -> DataLinkStatus {
+        self.status.get()
+    }
+
+    fn send_message(&mut self, _message: &DataMessage) -> DataLinkResult<()> {
+        Err(DataLinkError::TransportError("aisstream.io is receive-only".to_string()))
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        DataLinkReceiver::connect(self, config)
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        DataLinkReceiver::disconnect(self)
+    }
+}
@@ -0,0 +1,472 @@
+//! Autopilot datalink
+//!
+//! Reads the steering sentences a chart plotter sends the pilot (APB, RMB and
+//! HSC) and transmits our own, so a Raymarine or Garmin pilot on an NMEA 0183
+//! input can be told which heading to steer or which leg to follow.
+//!
+//! Commands are only transmitted when the link is connected with
+//! `transmit=true`; a link configured for listening cannot steer the boat.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use log::{error, info};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use datalink::{DataLinkConfig, DataLinkError, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataLinkTransmitter, DataMessage, LinkStats, Value};
+use crate::nmea;
+use crate::raw_log::RawLog;
+use crate::reconnect::{ReceiverResult, Reconnector, SharedStatus};
+use crate::runtime::runtime_handle;
+use crate::serial;
+use crate::stats::SharedStats;
+
+/// Talker id of the sentences we transmit (electronic chart system)
+const TALKER: &str = "EC";
+
+/// Parameter enabling transmission
+pub const TRANSMIT_PARAM: &str = "transmit";
+
+/// A steering command for the pilot
+#[derive(Debug, Clone, PartialEq)]
+pub enum AutopilotCommand {
+    /// Steer a heading, sent as HSC
+    SteerHeading {
+        heading_true: f64,
+        /// Magnetic variation, east positive; adds the magnetic heading when known
+        variation: Option<f64>,
+    },
+    /// Follow a leg towards a waypoint, sent as APB
+    FollowLeg {
+        /// Cross-track error in nautical miles, positive when right of the leg
+        cross_track_nm: f64,
+        bearing_origin_to_destination: f64,
+        bearing_to_destination: f64,
+        heading_to_steer: f64,
+        destination_id: String,
+        arrived: bool,
+    },
+}
+
+impl AutopilotCommand {
+    /// Encode as an NMEA 0183 sentence, without line ending
+    pub fn to_sentence(&self) -> String {
+        match self {
+            AutopilotCommand::SteerHeading { heading_true, variation } => {
+                let heading_true = heading_true.rem_euclid(360.0);
+                let magnetic = variation
+                    .map(|variation| format!("{:.1}", (heading_true - variation).rem_euclid(360.0)))
+                    .unwrap_or_default();
+                nmea::frame_sentence(&format!("{}HSC,{:.1},T,{},M", TALKER, heading_true, magnetic))
+            }
+            AutopilotCommand::FollowLeg {
+                cross_track_nm,
+                bearing_origin_to_destination,
+                bearing_to_destination,
+                heading_to_steer,
+                destination_id,
+                arrived,
+            } => {
+                // Right of the leg means steering left to get back on it
+                let steer = if *cross_track_nm > 0.0 { "L" } else { "R" };
+                let arrival = if *arrived { "A" } else { "V" };
+                nmea::frame_sentence(&format!(
+                    "{}APB,A,A,{:.2},{},N,{},V,{:.1},T,{},{:.1},T,{:.1},T",
+                    TALKER,
+                    cross_track_nm.abs(),
+                    steer,
+                    arrival,
+                    bearing_origin_to_destination.rem_euclid(360.0),
+                    destination_id,
+                    bearing_to_destination.rem_euclid(360.0),
+                    heading_to_steer.rem_euclid(360.0)
+                ))
+            }
+        }
+    }
+
+    /// Wrap the command in an `AUTOPILOT_COMMAND` message for `send_message`
+    pub fn to_message(&self) -> DataMessage {
+        let message = DataMessage::new(
+            "AUTOPILOT_COMMAND".to_string(),
+            "AUTOPILOT_CONTROLLER".to_string(),
+            self.to_sentence().into_bytes(),
+        );
+        match self {
+            AutopilotCommand::SteerHeading { heading_true, .. } => message.with_data("heading_to_steer", Value::Angle(*heading_true)),
+            AutopilotCommand::FollowLeg { heading_to_steer, destination_id, .. } => message
+                .with_data("heading_to_steer", Value::Angle(*heading_to_steer))
+                .with_data("destination_id", destination_id.as_str()),
+        }
+    }
+}
+
+/// Where the pilot is connected
+#[derive(Debug, Clone, PartialEq)]
+pub enum AutopilotSourceConfig {
+    Serial { port: String, baud_rate: u32 },
+    /// NMEA multiplexer forwarding to the pilot
+    Tcp { host: String, port: u16 },
+}
+
+pub struct AutopilotDataLinkProvider {
+    status: SharedStatus,
+    config: Option<AutopilotSourceConfig>,
+    transmit: bool,
+    auto_reconnect: bool,
+    message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
+    shutdown_tx: Option<mpsc::Sender<()>>,
+    command_tx: Option<mpsc::UnboundedSender<String>>,
+    receiver_handle: Option<tokio::task::JoinHandle<()>>,
+    /// Runtime for the receiver task; the caller's when unset
+    runtime: Option<tokio::runtime::Handle>,
+}
+
+impl AutopilotDataLinkProvider {
+    pub fn new() -> Self {
+        Self {
+            status: SharedStatus::default(),
+            config: None,
+            transmit: false,
+            auto_reconnect: true,
+            message_queue: Arc::new(Mutex::new(VecDeque::new())),
+            shutdown_tx: None,
+            command_tx: None,
+            receiver_handle: None,
+            runtime: None,
+        }
+    }
+
+    /// Spawn the receiver task on `runtime` rather than the caller's
+    pub fn with_runtime(mut self, runtime: tokio::runtime::Handle) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Whether the link was connected with transmission enabled
+    pub fn can_transmit(&self) -> bool {
+        self.transmit && self.command_tx.is_some()
+    }
+
+    /// Send a steering command to the pilot
+    pub fn send_command(&mut self, command: &AutopilotCommand) -> DataLinkResult<()> {
+        self.send_message(&command.to_message())
+    }
+
+    pub fn parse_source_config(config: &DataLinkConfig) -> DataLinkResult<AutopilotSourceConfig> {
+        let param = |key: &str| config.parameters.get(key).map(String::as_str);
+        let required =
+            |key: &str| param(key).ok_or_else(|| DataLinkError::InvalidConfig(format!("Missing {} parameter for autopilot", key)));
+
+        match required("connection_type")? {
+            "serial" => Ok(AutopilotSourceConfig::Serial {
+                port: required("port")?.to_string(),
+                baud_rate: serial::parse_baud_rate(param("baud_rate"), 4800)?,
+            }),
+            "tcp" => Ok(AutopilotSourceConfig::Tcp {
+                host: required("host")?.to_string(),
+                port: required("port")?
+                    .parse()
+                    .map_err(|_| DataLinkError::InvalidConfig("Invalid port parameter".to_string()))?,
+            }),
+            other => Err(DataLinkError::InvalidConfig(format!("Unsupported autopilot connection type: {}", other))),
+        }
+    }
+
+    /// Parse an APB, RMB or HSC sentence into an `AUTOPILOT` message
+    pub fn parse_autopilot_sentence(sentence: &str) -> Option<DataMessage> {
+        let body = sentence.strip_prefix('$')?;
+        let body = body.split('*').next().unwrap_or(body);
+        let parts: Vec<&str> = body.split(',').collect();
+        let field = |index: usize| parts.get(index).copied().unwrap_or("");
+        let angle = |index: usize| field(index).parse::<f64>().ok().map(Value::Angle);
+        let message = || {
+            DataMessage::new("AUTOPILOT".to_string(), "AUTOPILOT".to_string(), sentence.as_bytes().to_vec())
+                .with_data("sentence_type", format!("${}", parts[0]))
+        };
+        // Signed cross-track error from a magnitude and the direction to steer
+        let cross_track = |magnitude: usize, steer: usize| {
+            let magnitude: f64 = field(magnitude).parse().ok()?;
+            match field(steer) {
+                "L" => Some(magnitude),
+                "R" => Some(-magnitude),
+                _ => None,
+            }
+        };
+
+        match parts[0].get(2..)? {
+            // $--APB,status,status,xte,L|R,N,arrival,perpendicular,bearing,T|M,dest,bearing,T|M,heading,T|M
+            "APB" => {
+                if field(1) != "A" {
+                    return None;
+                }
+                let mut apb = message()
+                    .with_data("destination_id", field(10))
+                    .with_data("arrived", field(6) == "A")
+                    .with_data("reference", field(14));
+                if let Some(xte) = cross_track(3, 4) {
+                    apb = apb.with_data("cross_track_nm", xte);
+                }
+                for (key, index) in [("bearing_origin_to_destination", 8), ("bearing_to_destination", 11), ("heading_to_steer", 13)] {
+                    if let Some(bearing) = angle(index) {
+                        apb = apb.with_data(key, bearing);
+                    }
+                }
+                Some(apb)
+            }
+            // $--RMB,status,xte,L|R,origin,dest,lat,N|S,lon,E|W,range,bearing,closing,arrival
+            "RMB" => {
+                if field(1) != "A" {
+                    return None;
+                }
+                let mut rmb = message()
+                    .with_data("destination_id", field(5))
+                    .with_data("arrived", field(13) == "A")
+                    .with_data("reference", "T");
+                if let Some(xte) = cross_track(2, 3) {
+                    rmb = rmb.with_data("cross_track_nm", xte);
+                }
+                if let Some(destination) = nmea::position(field(6), field(7), field(8), field(9)) {
+                    rmb = rmb.with_data("destination", destination);
+                }
+                if let Ok(range) = field(10).parse::<f64>() {
+                    rmb = rmb.with_data("destination_range_nm", range);
+                }
+                if let Some(bearing) = angle(11) {
+                    rmb = rmb.with_data("bearing_to_destination", bearing);
+                }
+                Some(rmb)
+            }
+            // $--HSC,heading,T,heading,M
+            "HSC" => {
+                let (heading, reference) = match (angle(1), angle(3)) {
+                    (Some(heading), _) => (heading, "T"),
+                    (None, Some(heading)) => (heading, "M"),
+                    (None, None) => return None,
+                };
+                Some(message().with_data("heading_to_steer", heading).with_data("reference", reference))
+            }
+            _ => None,
+        }
+    }
+
+    fn enqueue(message_queue: &Mutex<VecDeque<DataMessage>>, stats: &SharedStats, line: &str) {
+        if let Some(message) = stats.observe(line, Self::parse_autopilot_sentence(line.trim())) {
+            if let Ok(mut queue) = message_queue.lock() {
+                queue.push_back(message);
+            }
+        }
+    }
+
+    fn start_receiver(&mut self) -> DataLinkResult<()> {
+        let config = self
+            .config
+            .clone()
+            .ok_or_else(|| DataLinkError::InvalidConfig("No configuration set".to_string()))?;
+        let (shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
+        let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+        let message_queue = Arc::clone(&self.message_queue);
+        let status = self.status.clone();
+        let auto_reconnect = self.auto_reconnect;
+        let runtime = runtime_handle(self.runtime.as_ref())?;
+
+        let handle = runtime.spawn(async move {
+            let name = match &config {
+                AutopilotSourceConfig::Serial { .. } => "Autopilot serial",
+                AutopilotSourceConfig::Tcp { .. } => "Autopilot TCP",
+            };
+            let mut reconnector = Reconnector::new(name, auto_reconnect, status);
+            loop {
+                let result = Self::run_connection(&config, &message_queue, &mut shutdown_rx, &mut command_rx, reconnector.status()).await;
+                if !reconnector.retry(result, &mut shutdown_rx).await {
+                    break;
+                }
+            }
+        });
+
+        self.shutdown_tx = Some(shutdown_tx);
+        self.command_tx = Some(command_tx);
+        self.receiver_handle = Some(handle);
+        self.status.set(DataLinkStatus::Connected);
+        Ok(())
+    }
+
+    async fn run_connection(
+        config: &AutopilotSourceConfig,
+        message_queue: &Mutex<VecDeque<DataMessage>>,
+        shutdown_rx: &mut mpsc::Receiver<()>,
+        command_rx: &mut mpsc::UnboundedReceiver<String>,
+        status: &SharedStatus,
+    ) -> ReceiverResult {
+        match config {
+            AutopilotSourceConfig::Serial { port, baud_rate } => {
+                info!("Starting autopilot serial link on {} at {} baud", port, serial::baud_rate_label(*baud_rate));
+                let (serial_stream, _) = serial::open(port, *baud_rate).await?;
+                status.set(DataLinkStatus::Connected);
+                let (read_half, write_half) = tokio::io::split(serial_stream);
+                Self::exchange(BufReader::new(read_half), write_half, message_queue, status.stats(), shutdown_rx, command_rx).await
+            }
+            AutopilotSourceConfig::Tcp { host, port } => {
+                info!("Starting autopilot TCP link to {}:{}", host, port);
+                let stream = TcpStream::connect(format!("{}:{}", host, port)).await?;
+                status.set(DataLinkStatus::Connected);
+                let (read_half, write_half) = stream.into_split();
+                Self::exchange(BufReader::new(read_half), write_half, message_queue, status.stats(), shutdown_rx, command_rx).await
+            }
+        }
+    }
+
+    /// Read sentences and write queued commands until shutdown or the connection drops
+    async fn exchange<R, W>(
+        mut reader: R,
+        mut writer: W,
+        message_queue: &Mutex<VecDeque<DataMessage>>,
+        stats: &SharedStats,
+        shutdown_rx: &mut mpsc::Receiver<()>,
+        command_rx: &mut mpsc::UnboundedReceiver<String>,
+    ) -> ReceiverResult
+    where
+        R: AsyncBufReadExt + Unpin,
+        W: AsyncWriteExt + Unpin,
+    {
+        let mut line = String::new();
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => {
+                    info!("Autopilot link shutdown requested");
+                    return Ok(());
+                }
+                Some(command) = command_rx.recv() => {
+                    if let Err(e) = writer.write_all(command.as_bytes()).await {
+                        error!("Error writing autopilot command: {}", e);
+                        return Err(format!("Error writing autopilot command: {}", e).into());
+                    }
+                }
+                result = reader.read_line(&mut line) => {
+                    match result {
+                        Ok(0) => return Err("Autopilot connection closed".into()),
+                        Ok(_) => {
+                            Self::enqueue(message_queue, stats, &line);
+                            line.clear();
+                        }
+                        Err(e) => return Err(format!("Error reading from autopilot: {}", e).into()),
+                    }
+                }
+            }
+        }
+    }
+
+    fn stop_receiver(&mut self) {
+        if let Some(shutdown_tx) = self.shutdown_tx.take() {
+            let _ = shutdown_tx.try_send(());
+        }
+        self.command_tx = None;
+        if let Some(handle) = self.receiver_handle.take() {
+            handle.abort();
+        }
+        self.status.set(DataLinkStatus::Disconnected);
+    }
+}
+
+impl Default for AutopilotDataLinkProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DataLinkReceiver for AutopilotDataLinkProvider {
+    fn status(&self) -> DataLinkStatus {
+        self.status.get()
+    }
+
+    fn receive_message(&mut self) -> DataLinkResult<Option<DataMessage>> {
+        match self.message_queue.lock() {
+            Ok(mut queue) => Ok(queue.pop_front()),
+            Err(_) => Err(DataLinkError::TransportError("Failed to access message queue".to_string())),
+        }
+    }
+
+    fn stats(&self) -> LinkStats {
+        self.status.stats().get()
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        info!("Connecting autopilot datalink with config: {:?}", config);
+        let source_config = Self::parse_source_config(config)?;
+        let raw_log = RawLog::from_config(config)?;
+        self.stop_receiver();
+        self.status.stats().set_raw_log(raw_log);
+        self.config = Some(source_config);
+        self.transmit = config.parameters.get(TRANSMIT_PARAM).is_some_and(|value| value == "true");
+        self.auto_reconnect = config.auto_reconnect;
+        self.status.set(DataLinkStatus::Connecting);
+
+        self.start_receiver().inspect_err(|e| {
+            self.status.set(DataLinkStatus::Error(format!("Connection failed: {}", e)));
+        })
+    }
+
+    /// Switch port or transmit mode without losing queued pilot reports
+    fn reconfigure(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        DataLinkReceiver::connect(self, config)
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        info!("Disconnecting autopilot datalink");
+        self.stop_receiver();
+        self.status.stats().set_raw_log(None);
+        self.config = None;
+        self.transmit = false;
+        if let Ok(mut queue) = self.message_queue.lock() {
+            queue.clear();
+        }
+        Ok(())
+    }
+}
+
+impl DataLinkTransmitter for AutopilotDataLinkProvider {
+    fn status(&self) -> DataLinkStatus {
+        self.status.get()
+    }
+
+    fn send_message(&mut self, message: &DataMessage) -> DataLinkResult<()> {
+        // Only steering commands are forwarded to the pilot
+        if message.message_type != "AUTOPILOT_COMMAND" {
+            return Err(DataLinkError::InvalidMessage(format!(
+                "Autopilot only accepts AUTOPILOT_COMMAND messages, got {}",
+                message.message_type
+            )));
+        }
+        let sentence = std::str::from_utf8(&message.payload)
+            .map_err(|_| DataLinkError::InvalidMessage("Autopilot command is not text".to_string()))?
+            .trim();
+        let steers = serial::has_valid_checksum(sentence)
+            && Self::parse_autopilot_sentence(sentence).is_some_and(|parsed| parsed.get_angle("heading_to_steer").is_some());
+        if !steers {
+            return Err(DataLinkError::InvalidMessage(format!("Not a steering sentence: {}", sentence)));
+        }
+
+        if !self.transmit {
+            return Err(DataLinkError::InvalidConfig(format!(
+                "Autopilot link is receive-only; connect with {}=true to steer",
+                TRANSMIT_PARAM
+            )));
+        }
+        let command_tx = self
+            .command_tx
+            .as_ref()
+            .ok_or_else(|| DataLinkError::ConnectionFailed("Autopilot not connected".to_string()))?;
+        info!("Autopilot command: {}", sentence);
+        command_tx
+            .send(format!("{}\r\n", sentence))
+            .map_err(|_| DataLinkError::TransportError("Autopilot connection closed".to_string()))
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        DataLinkReceiver::connect(self, config)
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        DataLinkReceiver::disconnect(self)
+    }
+}
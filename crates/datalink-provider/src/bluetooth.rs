@@ -0,0 +1,235 @@
+//! Bluetooth classic serial (RFCOMM) connections
+//!
+//! Bluetooth GPS pucks stream NMEA over the serial port profile. On Linux
+//! the provider opens an RFCOMM socket to the device address directly. On
+//! macOS a paired serial device shows up as a `/dev/cu.*` port named after
+//! the device, which is then read like any serial port. Discovery and
+//! pairing go through `bluetoothctl` on Linux and `blueutil` on macOS.
+
+use std::fmt;
+use std::process::Command;
+use std::str::FromStr;
+use log::{info, warn};
+use tokio::io::AsyncRead;
+use datalink::{DataLinkError, DataLinkResult};
+
+/// RFCOMM channel of the serial port profile on most GPS pucks
+pub const DEFAULT_RFCOMM_CHANNEL: u8 = 1;
+
+/// Seconds spent scanning for devices
+const DISCOVERY_SECONDS: u32 = 8;
+
+/// A Bluetooth device address, e.g. `00:1B:DC:0F:2A:11`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BluetoothAddress(pub [u8; 6]);
+
+impl FromStr for BluetoothAddress {
+    type Err = DataLinkError;
+
+    /// Accepts `:` or `-` separated hex octets
+    fn from_str(address: &str) -> Result<Self, Self::Err> {
+        let invalid = || DataLinkError::InvalidConfig(format!("Invalid Bluetooth address: {}", address));
+        let octets: Vec<&str> = address.trim().split([':', '-']).collect();
+        if octets.len() != 6 {
+            return Err(invalid());
+        }
+        let mut bytes = [0u8; 6];
+        for (byte, octet) in bytes.iter_mut().zip(octets) {
+            if octet.len() != 2 {
+                return Err(invalid());
+            }
+            *byte = u8::from_str_radix(octet, 16).map_err(|_| invalid())?;
+        }
+        Ok(Self(bytes))
+    }
+}
+
+impl fmt::Display for BluetoothAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}", a, b, c, d, e, g)
+    }
+}
+
+/// A device found by discovery
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BluetoothDevice {
+    pub address: BluetoothAddress,
+    pub name: String,
+}
+
+/// Devices from `bluetoothctl devices`: `Device 00:1B:DC:0F:2A:11 GPS Puck`
+pub fn parse_bluetoothctl_devices(output: &str) -> Vec<BluetoothDevice> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.trim().splitn(3, ' ');
+            if fields.next()? != "Device" {
+                return None;
+            }
+            let address = fields.next()?.parse().ok()?;
+            let name = fields.next().unwrap_or_default().trim().to_string();
+            Some(BluetoothDevice { address, name })
+        })
+        .collect()
+}
+
+/// Devices from `blueutil`:
+/// `address: 00-1b-dc-0f-2a-11, not connected, not favourite, paired, name: "GPS Puck", ..`
+pub fn parse_blueutil_devices(output: &str) -> Vec<BluetoothDevice> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let address = line.strip_prefix("address: ")?.split(',').next()?.parse().ok()?;
+            let name = line
+                .split_once("name: \"")
+                .and_then(|(_, rest)| rest.split_once('"'))
+                .map_or(String::new(), |(name, _)| name.to_string());
+            Some(BluetoothDevice { address, name })
+        })
+        .collect()
+}
+
+fn run(program: &str, args: &[&str]) -> DataLinkResult<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| DataLinkError::ConnectionFailed(format!("Failed to run {}: {}", program, e)))?;
+    if !output.status.success() {
+        return Err(DataLinkError::ConnectionFailed(format!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Scan for nearby devices. Blocks for a few seconds.
+pub fn discover_devices() -> DataLinkResult<Vec<BluetoothDevice>> {
+    if cfg!(target_os = "linux") {
+        run("bluetoothctl", &["--timeout", &DISCOVERY_SECONDS.to_string(), "scan", "on"])?;
+        Ok(parse_bluetoothctl_devices(&run("bluetoothctl", &["devices"])?))
+    } else if cfg!(target_os = "macos") {
+        Ok(parse_blueutil_devices(&run("blueutil", &["--inquiry", &DISCOVERY_SECONDS.to_string()])?))
+    } else {
+        Err(DataLinkError::ConnectionFailed("Bluetooth discovery is only supported on Linux and macOS".to_string()))
+    }
+}
+
+/// Pair with a device and, on Linux, trust it so it reconnects without prompting
+pub fn pair_device(address: BluetoothAddress) -> DataLinkResult<()> {
+    let address = address.to_string();
+    info!("Pairing Bluetooth device {}", address);
+    if cfg!(target_os = "linux") {
+        run("bluetoothctl", &["pair", &address])?;
+        run("bluetoothctl", &["trust", &address])?;
+        Ok(())
+    } else if cfg!(target_os = "macos") {
+        run("blueutil", &["--pair", &address]).map(|_| ())
+    } else {
+        Err(DataLinkError::ConnectionFailed("Bluetooth pairing is only supported on Linux and macOS".to_string()))
+    }
+}
+
+/// Open the serial stream of a device, pairing first if the initial attempt fails
+pub async fn connect(address: BluetoothAddress, channel: u8) -> std::io::Result<Box<dyn AsyncRead + Unpin + Send>> {
+    match open_stream(address, channel).await {
+        Ok(stream) => Ok(stream),
+        Err(e) => {
+            warn!("Bluetooth connection to {} failed ({}), pairing and retrying", address, e);
+            tokio::task::spawn_blocking(move || pair_device(address))
+                .await
+                .map_err(std::io::Error::other)?
+                .map_err(std::io::Error::other)?;
+            open_stream(address, channel).await
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+async fn open_stream(address: BluetoothAddress, channel: u8) -> std::io::Result<Box<dyn AsyncRead + Unpin + Send>> {
+    let file = tokio::task::spawn_blocking(move || rfcomm::connect(address, channel))
+        .await
+        .map_err(std::io::Error::other)??;
+    Ok(Box::new(tokio::fs::File::from_std(file)))
+}
+
+#[cfg(target_os = "macos")]
+async fn open_stream(address: BluetoothAddress, _channel: u8) -> std::io::Result<Box<dyn AsyncRead + Unpin + Send>> {
+    use tokio_serial::SerialPortBuilderExt;
+
+    let info = tokio::task::spawn_blocking(move || run("blueutil", &["--info", &address.to_string()]))
+        .await
+        .map_err(std::io::Error::other)?
+        .map_err(std::io::Error::other)?;
+    let name = parse_blueutil_devices(&info).into_iter().next().map(|device| device.name).unwrap_or_default();
+    // macOS names the port after the device, with spaces replaced
+    let port_name = format!("/dev/cu.{}", name.replace(' ', "-"));
+    let port = tokio_serial::available_ports()
+        .map_err(std::io::Error::other)?
+        .into_iter()
+        .map(|port| port.port_name)
+        .find(|port| !name.is_empty() && port.starts_with(&port_name))
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, format!("No serial port for {}", address)))?;
+    info!("Bluetooth device {} is {}", address, port);
+    Ok(Box::new(tokio_serial::new(port, 9600).open_native_async()?))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+async fn open_stream(_address: BluetoothAddress, _channel: u8) -> std::io::Result<Box<dyn AsyncRead + Unpin + Send>> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "Bluetooth serial is only supported on Linux and macOS",
+    ))
+}
+
+#[cfg(target_os = "linux")]
+mod rfcomm {
+    use std::io;
+    use std::mem;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use super::BluetoothAddress;
+
+    const BTPROTO_RFCOMM: libc::c_int = 3;
+
+    /// `struct sockaddr_rc` from `<bluetooth/rfcomm.h>`
+    #[repr(C)]
+    struct SockaddrRc {
+        rc_family: libc::sa_family_t,
+        /// Address bytes in reverse order
+        rc_bdaddr: [u8; 6],
+        rc_channel: u8,
+    }
+
+    pub fn connect(address: BluetoothAddress, channel: u8) -> io::Result<std::fs::File> {
+        // SAFETY: plain socket(2) call; the result is checked before use
+        let raw = unsafe { libc::socket(libc::AF_BLUETOOTH, libc::SOCK_STREAM | libc::SOCK_CLOEXEC, BTPROTO_RFCOMM) };
+        if raw < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `raw` is a freshly created descriptor owned by nothing else
+        let fd = unsafe { OwnedFd::from_raw_fd(raw) };
+
+        let mut bdaddr = address.0;
+        bdaddr.reverse();
+        let socket_address = SockaddrRc {
+            rc_family: libc::AF_BLUETOOTH as libc::sa_family_t,
+            rc_bdaddr: bdaddr,
+            rc_channel: channel,
+        };
+        // SAFETY: `socket_address` is a valid sockaddr_rc and the length matches it
+        let result = unsafe {
+            libc::connect(
+                fd.as_raw_fd(),
+                &socket_address as *const SockaddrRc as *const libc::sockaddr,
+                mem::size_of::<SockaddrRc>() as libc::socklen_t,
+            )
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(std::fs::File::from(fd))
+    }
+}
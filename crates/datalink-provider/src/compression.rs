@@ -0,0 +1,88 @@
+//! Compressed recordings
+//!
+//! A week of AIS traffic is gigabytes of text, so recordings may be written
+//! gzip or zstd compressed, chosen by a `.gz` or `.zst` file extension.
+//! Reading goes by the magic bytes at the start of the file instead, so a
+//! compressed recording replays whatever it is called. Both formats allow
+//! several members back to back, which is what appending to an existing
+//! recording produces.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use async_compression::tokio::bufread::{GzipDecoder, ZstdDecoder};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader, Lines};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Lines of a recording, decompressed if need be
+pub type RecordingLines = Lines<Box<dyn AsyncBufRead + Unpin + Send>>;
+
+/// How a recording is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// The compression a file named `path` is written with
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|extension| extension.to_str()) {
+            Some("gz") => Self::Gzip,
+            Some("zst") => Self::Zstd,
+            _ => Self::None,
+        }
+    }
+
+    /// The compression of a file starting with `bytes`
+    pub fn from_magic(bytes: &[u8]) -> Self {
+        if bytes.starts_with(&GZIP_MAGIC) {
+            Self::Gzip
+        } else if bytes.starts_with(&ZSTD_MAGIC) {
+            Self::Zstd
+        } else {
+            Self::None
+        }
+    }
+
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Gzip => Some("gz"),
+            Self::Zstd => Some("zst"),
+        }
+    }
+
+    /// Wrap `file` in an encoder. The compressed stream is only complete
+    /// once the writer is dropped.
+    pub fn writer(self, file: File) -> std::io::Result<Box<dyn Write + Send>> {
+        Ok(match self {
+            Self::None => Box::new(file),
+            Self::Gzip => Box::new(flate2::write::GzEncoder::new(file, flate2::Compression::default())),
+            Self::Zstd => Box::new(zstd::Encoder::new(file, 0)?.auto_finish()),
+        })
+    }
+}
+
+/// Open a recording for reading line by line, compressed or not
+pub async fn open_recording(path: &str) -> std::io::Result<RecordingLines> {
+    let mut file = BufReader::new(tokio::fs::File::open(path).await?);
+    let reader: Box<dyn AsyncBufRead + Unpin + Send> = match Compression::from_magic(file.fill_buf().await?) {
+        Compression::None => Box::new(file),
+        Compression::Gzip => {
+            let mut decoder = GzipDecoder::new(file);
+            decoder.multiple_members(true);
+            Box::new(BufReader::new(decoder))
+        }
+        Compression::Zstd => {
+            let mut decoder = ZstdDecoder::new(file);
+            decoder.multiple_members(true);
+            Box::new(BufReader::new(decoder))
+        }
+    };
+    Ok(reader.lines())
+}
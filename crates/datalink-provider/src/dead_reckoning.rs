@@ -0,0 +1,196 @@
+//! Dead reckoning while the GPS fix is lost
+//!
+//! [`DeadReckoningReceiver`] watches the GGA fixes coming out of a receiver.
+//! Once the fix quality drops to 0 it follows every such GGA with an
+//! estimated position carried forward from the last fix, on the last valid
+//! RMC course and speed over ground. Heading (`HEADING`) and speed log
+//! (`SPEED_LOG`) messages on the same feed take over from those while they
+//! are fresh, since they keep following the boat after the fix is gone.
+//!
+//! Estimates are GGA sentences with fix quality 6, the NMEA code for
+//! "estimated (dead reckoning)", tagged `mode=DR` and with the seconds since
+//! the last fix in `dr_elapsed_s`, so the UI can tell them from real fixes.
+//! A fix with quality above 0 ends dead reckoning.
+
+use std::collections::VecDeque;
+use std::time::{Duration, SystemTime};
+use datalink::geo::{destination, METERS_PER_NM};
+use datalink::{DataLinkConfig, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataMessage, LinkStats, Value};
+use crate::gps::parse_sentence_at;
+use crate::nmea;
+
+/// Parameter enabling dead reckoning on a GPS link
+pub const DEAD_RECKONING_PARAM: &str = "dead_reckoning";
+
+/// GGA fix quality of a dead-reckoned position
+pub const DEAD_RECKONING_FIX_QUALITY: i64 = 6;
+
+/// Heading and log readings older than this are not used
+pub const MAX_SENSOR_AGE: Duration = Duration::from_secs(10);
+
+/// Position estimator fed with the messages of a GPS feed
+#[derive(Debug, Clone, Default)]
+pub struct DeadReckoner {
+    last_fix: Option<((f64, f64), SystemTime)>,
+    /// Course and speed over ground of the last valid RMC
+    course_over_ground: Option<(f64, f64)>,
+    heading: Option<(f64, SystemTime)>,
+    speed_through_water: Option<(f64, SystemTime)>,
+    /// Latest estimate while dead reckoning
+    estimate: Option<((f64, f64), SystemTime)>,
+}
+
+impl DeadReckoner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether positions are currently being estimated
+    pub fn is_active(&self) -> bool {
+        self.estimate.is_some()
+    }
+
+    /// Take in one message; returns an estimate when it reports a lost fix
+    pub fn observe(&mut self, message: &DataMessage) -> Option<DataMessage> {
+        let time = message.timestamp();
+        match message.message_type.as_str() {
+            "HEADING" => {
+                if let Some(heading) = message.get_angle("heading_true") {
+                    self.heading = Some((heading, time));
+                }
+                None
+            }
+            "SPEED_LOG" => {
+                if let Some(speed) = message.get_f64("speed_through_water") {
+                    self.speed_through_water = Some((speed, time));
+                }
+                None
+            }
+            "GPS_SENTENCE" => match message.get_str("formatter") {
+                Some("RMC") if message.get_str("status") == Some("A") => {
+                    if let (Some(course), Some(speed)) = (message.get_angle("course"), message.get_f64("speed")) {
+                        self.course_over_ground = Some((course, speed));
+                    }
+                    None
+                }
+                Some("GGA") => match message.get_i64("fix_quality") {
+                    Some(0) => self.estimate_at(time),
+                    Some(DEAD_RECKONING_FIX_QUALITY) | None => None,
+                    Some(_) => {
+                        if let Some(position) = message.get_lat_lon("position") {
+                            self.last_fix = Some((position, time));
+                            self.estimate = None;
+                        }
+                        None
+                    }
+                },
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Course in degrees true and speed in knots to carry the estimate on
+    fn velocity(&self, time: SystemTime) -> Option<(f64, f64)> {
+        let fresh = |reading: Option<(f64, SystemTime)>| {
+            reading
+                .filter(|(_, at)| time.duration_since(*at).map_or(true, |age| age <= MAX_SENSOR_AGE))
+                .map(|(value, _)| value)
+        };
+        let (course, speed) = self.course_over_ground.unzip();
+        Some((fresh(self.heading).or(course)?, fresh(self.speed_through_water).or(speed)?))
+    }
+
+    fn estimate_at(&mut self, time: SystemTime) -> Option<DataMessage> {
+        let (fix_position, fix_time) = self.last_fix?;
+        let (from, since) = self.estimate.unwrap_or((fix_position, fix_time));
+        let elapsed_h = time.duration_since(since).unwrap_or_default().as_secs_f64() / 3600.0;
+        let velocity = self.velocity(time);
+        let position = match velocity {
+            Some((course, speed)) => destination(from, course, speed * elapsed_h * METERS_PER_NM),
+            None => from,
+        };
+        self.estimate = Some((position, time));
+
+        let body = format!(
+            "GPGGA,{},{},{},{},00,,,M,,M,,",
+            nmea::time_field(time),
+            nmea::coordinate_fields(position.0, true),
+            nmea::coordinate_fields(position.1, false),
+            DEAD_RECKONING_FIX_QUALITY
+        );
+        let mut message = parse_sentence_at(&nmea::frame_sentence(&body), time)?
+            .with_data("mode", "DR")
+            .with_data("position", Value::LatLon { lat: position.0, lon: position.1 })
+            .with_data("dr_elapsed_s", time.duration_since(fix_time).unwrap_or_default().as_secs_f64());
+        if let Some((course, speed)) = velocity {
+            message = message.with_data("course", Value::Angle(course)).with_data("speed", speed);
+        }
+        Some(message)
+    }
+}
+
+/// Receiver wrapper adding dead-reckoned positions while the fix is lost
+pub struct DeadReckoningReceiver<R: DataLinkReceiver> {
+    inner: R,
+    reckoner: DeadReckoner,
+    ready: VecDeque<DataMessage>,
+}
+
+impl<R: DataLinkReceiver> DeadReckoningReceiver<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            reckoner: DeadReckoner::new(),
+            ready: VecDeque::new(),
+        }
+    }
+
+    pub fn reckoner(&self) -> &DeadReckoner {
+        &self.reckoner
+    }
+
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+}
+
+impl<R: DataLinkReceiver> DataLinkReceiver for DeadReckoningReceiver<R> {
+    fn status(&self) -> DataLinkStatus {
+        self.inner.status()
+    }
+
+    /// Messages pass through unchanged; an estimate follows each GGA without a fix
+    fn receive_message(&mut self) -> DataLinkResult<Option<DataMessage>> {
+        if let Some(message) = self.ready.pop_front() {
+            return Ok(Some(message));
+        }
+        let Some(message) = self.inner.receive_message()? else {
+            return Ok(None);
+        };
+        if let Some(estimate) = self.reckoner.observe(&message) {
+            self.ready.push_back(estimate);
+        }
+        Ok(Some(message))
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        self.reckoner = DeadReckoner::new();
+        self.ready.clear();
+        self.inner.connect(config)
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        self.ready.clear();
+        self.inner.disconnect()
+    }
+
+    /// The last fix survives a change of source
+    fn reconfigure(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        self.inner.reconfigure(config)
+    }
+
+    fn stats(&self) -> LinkStats {
+        self.inner.stats()
+    }
+}
@@ -0,0 +1,168 @@
+//! Engine telemetry
+//!
+//! Engines report either as NMEA 0183 `RPM` sentences from a tacho interface,
+//! or on the CAN bus as NMEA 2000 engine PGNs or plain J1939 from the engine
+//! ECU. Both arrive as `ENGINE` messages tagged with `engine_instance` (0 is
+//! the first engine) and carrying whichever of `rpm`, `coolant_temp_c`,
+//! `oil_pressure_kpa` and `alternator_voltage` the source sends.
+//!
+//! Configured with a `gateway` or `interface` parameter the provider reads
+//! the CAN bus through an [`Nmea2000DataLinkProvider`] limited to the engine
+//! PGNs; otherwise it reads `RPM` sentences through the [`TransportHub`], so
+//! a tacho interface can share its port with the other NMEA 0183 providers.
+
+use std::collections::VecDeque;
+use log::info;
+use datalink::{DataLinkConfig, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataMessage, LinkStats};
+use crate::nmea2000::{Nmea2000DataLinkProvider, ENGINE_PGNS};
+use crate::raw_log::RawLog;
+use crate::stats::SharedStats;
+use crate::transport::{TransportConfig, TransportHub, TransportSubscription};
+
+/// Engine provider over NMEA 0183 or the CAN bus
+pub struct EngineDataLinkProvider {
+    status: DataLinkStatus,
+    shared: Option<TransportSubscription>,
+    can: Option<Nmea2000DataLinkProvider>,
+    message_queue: VecDeque<DataMessage>,
+    stats: SharedStats,
+}
+
+impl EngineDataLinkProvider {
+    pub fn new() -> Self {
+        Self {
+            status: DataLinkStatus::Disconnected,
+            shared: None,
+            can: None,
+            message_queue: VecDeque::new(),
+            stats: SharedStats::default(),
+        }
+    }
+
+    /// Parse an engine `RPM` sentence into an `ENGINE` message. Shaft
+    /// readings and sentences flagged invalid are skipped.
+    pub fn parse_rpm_sentence(sentence: &str) -> Option<DataMessage> {
+        let body = sentence.strip_prefix('$')?;
+        let body = body.split('*').next().unwrap_or(body);
+        // $IIRPM,source,number,rpm,pitch,status
+        let parts: Vec<&str> = body.split(',').collect();
+        if parts[0].get(2..) != Some("RPM") || parts.get(1) != Some(&"E") || parts.get(5) != Some(&"A") {
+            return None;
+        }
+        // RPM numbers engines from 1, or 0 for a single centerline engine
+        let number: u8 = parts.get(2)?.parse().ok()?;
+        let rpm: f64 = parts.get(3)?.parse().ok()?;
+        Some(
+            DataMessage::new("ENGINE".to_string(), "ENGINE".to_string(), sentence.as_bytes().to_vec())
+                .with_data("sentence_type", format!("${}", parts[0]))
+                .with_data("engine_instance", number.saturating_sub(1))
+                .with_data("rpm", rpm),
+        )
+    }
+
+    /// Whether the config points at a CAN gateway rather than an NMEA 0183 port
+    fn uses_can_bus(config: &DataLinkConfig) -> bool {
+        config.parameters.contains_key("gateway") || config.parameters.contains_key("interface")
+    }
+}
+
+impl Default for EngineDataLinkProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DataLinkReceiver for EngineDataLinkProvider {
+    fn status(&self) -> DataLinkStatus {
+        match &self.can {
+            Some(can) => can.status(),
+            None => self.status.clone(),
+        }
+    }
+
+    fn receive_message(&mut self) -> DataLinkResult<Option<DataMessage>> {
+        if let Some(can) = &mut self.can {
+            while let Some(message) = can.receive_message()? {
+                if message.message_type == "ENGINE" {
+                    self.message_queue.push_back(message);
+                }
+            }
+        }
+        if let Some(shared) = &self.shared {
+            self.message_queue
+                .extend(shared.drain().iter().filter_map(|line| self.stats.observe(line, Self::parse_rpm_sentence(line))));
+        }
+        Ok(self.message_queue.pop_front())
+    }
+
+    fn stats(&self) -> LinkStats {
+        let mut stats = self.stats.get();
+        if let Some(can) = &self.can {
+            stats.merge(&can.stats());
+        }
+        stats
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        info!("Connecting engine datalink provider");
+        self.status = DataLinkStatus::Connecting;
+
+        if Self::uses_can_bus(config) {
+            let mut config = config.clone();
+            if !config.parameters.contains_key("pgns") {
+                let pgns: Vec<String> = ENGINE_PGNS.iter().map(u32::to_string).collect();
+                config.parameters.insert("pgns".to_string(), pgns.join(","));
+            }
+            // A bus already open is reconfigured so its queue survives
+            let mut can = self.can.take().unwrap_or_default();
+            return match can.reconfigure(&config) {
+                Ok(()) => {
+                    self.can = Some(can);
+                    self.shared = None;
+                    self.stats.set_raw_log(None);
+                    self.status = DataLinkStatus::Connected;
+                    Ok(())
+                }
+                Err(e) => {
+                    self.status = DataLinkStatus::Error(e.to_string());
+                    Err(e)
+                }
+            };
+        }
+
+        let raw_log = RawLog::from_config(config)?;
+        let subscription = TransportConfig::from_config(config).and_then(|transport| TransportHub::global().subscribe(&transport));
+        match subscription {
+            Ok(subscription) => {
+                if let Some(mut can) = self.can.take() {
+                    can.disconnect()?;
+                }
+                self.stats.set_raw_log(raw_log);
+                self.shared = Some(subscription);
+                self.status = DataLinkStatus::Connected;
+                Ok(())
+            }
+            Err(e) => {
+                self.status = DataLinkStatus::Error(e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    /// Move between CAN and NMEA 0183 sources, keeping queued readings
+    fn reconfigure(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        DataLinkReceiver::connect(self, config)
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        info!("Disconnecting engine datalink provider");
+        if let Some(mut can) = self.can.take() {
+            can.disconnect()?;
+        }
+        self.shared = None;
+        self.stats.set_raw_log(None);
+        self.message_queue.clear();
+        self.status = DataLinkStatus::Disconnected;
+        Ok(())
+    }
+}
@@ -0,0 +1,347 @@
+//! Geofence events for the own-ship position
+//!
+//! [`GeofenceMonitor`] follows the own-ship fixes (`GPS_SENTENCE` and
+//! `GPS_POSITION`) and reports a `GEOFENCE_ENTER` or `GEOFENCE_EXIT` message
+//! whenever the boat crosses one of its fences: a circle, such as the swing
+//! circle around the anchor, or a polygon, such as harbor limits or a no-go
+//! zone. The first fix after a fence is added only reports entering it when
+//! the boat is already inside.
+//!
+//! Links take their fences from the `geofences` parameter, separated by `|`:
+//! `anchor:circle:lat,lon,radius_m` or `harbor:polygon:lat,lon;lat,lon;...`.
+//! With `geofence_hysteresis_m` a crossing is only reported once the boat is
+//! that far past the boundary, so a fix wandering on the line does not raise
+//! an alarm every few seconds.
+
+use std::collections::VecDeque;
+use datalink::geo::{distance_m, LocalTangentPlane};
+use datalink::{DataLinkConfig, DataLinkError, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataMessage, LinkStats, Value};
+
+/// Parameter listing the fences of a link
+pub const GEOFENCES_PARAM: &str = "geofences";
+
+/// Message type reported when the boat moves into a fence
+pub const GEOFENCE_ENTER: &str = "GEOFENCE_ENTER";
+
+/// Message type reported when the boat leaves a fence
+pub const GEOFENCE_EXIT: &str = "GEOFENCE_EXIT";
+
+/// Area enclosed by a fence
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeofenceShape {
+    Circle { center: (f64, f64), radius_m: f64 },
+    /// Vertices in order, `(latitude, longitude)`; the last connects back to the first
+    Polygon(Vec<(f64, f64)>),
+}
+
+/// A named area whose boundary crossings are reported
+#[derive(Debug, Clone, PartialEq)]
+pub struct Geofence {
+    pub name: String,
+    pub shape: GeofenceShape,
+}
+
+impl Geofence {
+    pub fn circle(name: impl Into<String>, center: (f64, f64), radius_m: f64) -> DataLinkResult<Self> {
+        if !radius_m.is_finite() || radius_m <= 0.0 {
+            return Err(DataLinkError::InvalidConfig(format!("Invalid geofence radius: {}", radius_m)));
+        }
+        Ok(Self {
+            name: name.into(),
+            shape: GeofenceShape::Circle { center, radius_m },
+        })
+    }
+
+    pub fn polygon(name: impl Into<String>, vertices: Vec<(f64, f64)>) -> DataLinkResult<Self> {
+        let name = name.into();
+        if vertices.len() < 3 {
+            return Err(DataLinkError::InvalidConfig(format!("Geofence {} needs at least three vertices", name)));
+        }
+        Ok(Self {
+            name,
+            shape: GeofenceShape::Polygon(vertices),
+        })
+    }
+
+    /// Parse one `name:circle:lat,lon,radius_m` or `name:polygon:lat,lon;...` fence
+    pub fn parse(fence: &str) -> DataLinkResult<Self> {
+        let invalid = || DataLinkError::InvalidConfig(format!("Invalid geofence: {}", fence));
+        let mut parts = fence.trim().splitn(3, ':');
+        let (Some(name), Some(kind), Some(geometry)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(invalid());
+        };
+        if name.is_empty() {
+            return Err(invalid());
+        }
+        let numbers = |text: &str| -> DataLinkResult<Vec<f64>> {
+            text.split(',').map(|number| number.trim().parse::<f64>().map_err(|_| invalid())).collect()
+        };
+        let position = |numbers: &[f64]| -> DataLinkResult<(f64, f64)> {
+            let (latitude, longitude) = (numbers[0], numbers[1]);
+            if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+                return Err(invalid());
+            }
+            Ok((latitude, longitude))
+        };
+        match kind {
+            "circle" => match numbers(geometry)?.as_slice() {
+                numbers @ [_, _, radius_m] => Self::circle(name, position(numbers)?, *radius_m),
+                _ => Err(invalid()),
+            },
+            "polygon" => {
+                let vertices = geometry
+                    .split(';')
+                    .filter(|vertex| !vertex.trim().is_empty())
+                    .map(|vertex| match numbers(vertex)?.as_slice() {
+                        numbers @ [_, _] => position(numbers),
+                        _ => Err(invalid()),
+                    })
+                    .collect::<DataLinkResult<Vec<_>>>()?;
+                Self::polygon(name, vertices)
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    /// The fences of a link's `geofences` parameter, if it has one
+    pub fn from_config(config: &DataLinkConfig) -> DataLinkResult<Option<Vec<Self>>> {
+        config
+            .parameters
+            .get(GEOFENCES_PARAM)
+            .map(|fences| fences.split('|').filter(|fence| !fence.trim().is_empty()).map(Self::parse).collect())
+            .transpose()
+    }
+
+    /// Meters from `position` to the boundary, negative inside the fence
+    pub fn boundary_distance_m(&self, position: (f64, f64)) -> f64 {
+        match &self.shape {
+            GeofenceShape::Circle { center, radius_m } => distance_m(*center, position) - radius_m,
+            GeofenceShape::Polygon(vertices) => {
+                // Harbor-sized areas, so a flat frame around the boat is close enough
+                let plane = LocalTangentPlane::new(position);
+                let corners: Vec<(f64, f64)> = vertices.iter().map(|vertex| plane.to_local(*vertex)).collect();
+                let mut inside = false;
+                let mut nearest = f64::INFINITY;
+                for (i, &(x1, y1)) in corners.iter().enumerate() {
+                    let (x2, y2) = corners[(i + 1) % corners.len()];
+                    // Ray to the east from the boat, at the origin
+                    if (y1 > 0.0) != (y2 > 0.0) && x1 + (0.0 - y1) * (x2 - x1) / (y2 - y1) > 0.0 {
+                        inside = !inside;
+                    }
+                    let (dx, dy) = (x2 - x1, y2 - y1);
+                    let length_sq = dx * dx + dy * dy;
+                    let t = if length_sq > 0.0 { (-(x1 * dx + y1 * dy) / length_sq).clamp(0.0, 1.0) } else { 0.0 };
+                    nearest = nearest.min((x1 + t * dx).hypot(y1 + t * dy));
+                }
+                if inside {
+                    -nearest
+                } else {
+                    nearest
+                }
+            }
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self.shape {
+            GeofenceShape::Circle { .. } => "circle",
+            GeofenceShape::Polygon(_) => "polygon",
+        }
+    }
+}
+
+/// Tracks which fences the boat is inside and reports the crossings
+#[derive(Debug, Clone, Default)]
+pub struct GeofenceMonitor {
+    /// Each fence with whether the boat was last inside it, once known
+    fences: Vec<(Geofence, Option<bool>)>,
+    hysteresis_m: f64,
+}
+
+impl GeofenceMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require the boat to be `hysteresis_m` past a boundary before reporting a crossing
+    pub fn with_hysteresis(mut self, hysteresis_m: f64) -> Self {
+        self.hysteresis_m = hysteresis_m.max(0.0);
+        self
+    }
+
+    /// Add a fence, replacing any fence of the same name
+    pub fn add(&mut self, fence: Geofence) {
+        self.remove(&fence.name);
+        self.fences.push((fence, None));
+    }
+
+    /// Remove a fence by name; returns whether there was one
+    pub fn remove(&mut self, name: &str) -> bool {
+        let count = self.fences.len();
+        self.fences.retain(|(fence, _)| fence.name != name);
+        self.fences.len() != count
+    }
+
+    /// Replace the fences, keeping what is known about those that did not change
+    pub fn set_fences(&mut self, fences: Vec<Geofence>) {
+        let mut previous = std::mem::take(&mut self.fences);
+        self.fences = fences
+            .into_iter()
+            .map(|fence| {
+                let inside = previous
+                    .iter()
+                    .position(|(known, _)| *known == fence)
+                    .and_then(|i| previous.swap_remove(i).1);
+                (fence, inside)
+            })
+            .collect();
+    }
+
+    pub fn fences(&self) -> impl Iterator<Item = &Geofence> {
+        self.fences.iter().map(|(fence, _)| fence)
+    }
+
+    /// Whether the boat is inside the named fence, once a fix has been seen
+    pub fn is_inside(&self, name: &str) -> Option<bool> {
+        self.fences.iter().find(|(fence, _)| fence.name == name).and_then(|(_, inside)| *inside)
+    }
+
+    /// Forget where the boat was, as after a restart
+    pub fn reset(&mut self) {
+        for (_, inside) in &mut self.fences {
+            *inside = None;
+        }
+    }
+
+    /// Take in one message; returns the crossings an own-ship fix makes
+    pub fn observe(&mut self, message: &DataMessage) -> Vec<DataMessage> {
+        let own_ship = matches!(message.message_type.as_str(), "GPS_SENTENCE" | "GPS_POSITION");
+        let no_fix = message.get_i64("fix_quality") == Some(0) || message.get_str("status") == Some("V");
+        let Some(position) = message.get_lat_lon("position").filter(|_| own_ship && !no_fix) else {
+            return Vec::new();
+        };
+
+        let mut events = Vec::new();
+        for (fence, inside) in &mut self.fences {
+            let distance = fence.boundary_distance_m(position);
+            let now_inside = match *inside {
+                None => distance <= 0.0,
+                Some(true) => distance <= self.hysteresis_m,
+                Some(false) => distance < -self.hysteresis_m,
+            };
+            // The first fix only reports a fence the boat starts out in
+            let crossed = match *inside {
+                None => now_inside,
+                Some(was_inside) => was_inside != now_inside,
+            };
+            *inside = Some(now_inside);
+            if crossed {
+                let message_type = if now_inside { GEOFENCE_ENTER } else { GEOFENCE_EXIT };
+                let mut event = DataMessage::new(message_type.to_string(), "GEOFENCE".to_string(), Vec::new())
+                    .with_received_at(message.received_at)
+                    .with_data("fence", fence.name.as_str())
+                    .with_data("shape", fence.kind())
+                    .with_data("position", Value::LatLon { lat: position.0, lon: position.1 })
+                    .with_data("boundary_distance_m", distance);
+                if let Some(measured_at) = message.measured_at {
+                    event = event.with_measured_at(measured_at);
+                }
+                events.push(event);
+            }
+        }
+        events
+    }
+}
+
+/// Receiver wrapper adding geofence crossings after the fixes that make them
+pub struct GeofenceReceiver<R: DataLinkReceiver> {
+    inner: R,
+    monitor: GeofenceMonitor,
+    ready: VecDeque<DataMessage>,
+}
+
+impl<R: DataLinkReceiver> GeofenceReceiver<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            monitor: GeofenceMonitor::new(),
+            ready: VecDeque::new(),
+        }
+    }
+
+    pub fn monitor(&self) -> &GeofenceMonitor {
+        &self.monitor
+    }
+
+    /// Fences can be changed while connected, e.g. to set an anchor watch
+    pub fn monitor_mut(&mut self) -> &mut GeofenceMonitor {
+        &mut self.monitor
+    }
+
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    fn settings(config: &DataLinkConfig) -> DataLinkResult<(Option<Vec<Geofence>>, f64)> {
+        let hysteresis_m = match config.parameters.get("geofence_hysteresis_m") {
+            Some(value) => value
+                .parse::<f64>()
+                .ok()
+                .filter(|meters| meters.is_finite() && *meters >= 0.0)
+                .ok_or_else(|| DataLinkError::InvalidConfig(format!("Invalid geofence_hysteresis_m: {}", value)))?,
+            None => 0.0,
+        };
+        Ok((Geofence::from_config(config)?, hysteresis_m))
+    }
+
+    fn apply(&mut self, (fences, hysteresis_m): (Option<Vec<Geofence>>, f64)) {
+        self.monitor.hysteresis_m = hysteresis_m;
+        if let Some(fences) = fences {
+            self.monitor.set_fences(fences);
+        }
+    }
+}
+
+impl<R: DataLinkReceiver> DataLinkReceiver for GeofenceReceiver<R> {
+    fn status(&self) -> DataLinkStatus {
+        self.inner.status()
+    }
+
+    fn receive_message(&mut self) -> DataLinkResult<Option<DataMessage>> {
+        if let Some(message) = self.ready.pop_front() {
+            return Ok(Some(message));
+        }
+        let Some(message) = self.inner.receive_message()? else {
+            return Ok(None);
+        };
+        self.ready.extend(self.monitor.observe(&message));
+        Ok(Some(message))
+    }
+
+    /// Fences without a `geofences` parameter are kept as they are
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        let settings = Self::settings(config)?;
+        self.ready.clear();
+        self.inner.connect(config)?;
+        self.apply(settings);
+        self.monitor.reset();
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        self.ready.clear();
+        self.inner.disconnect()
+    }
+
+    /// Fences that stay the same remember which side the boat is on
+    fn reconfigure(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        let settings = Self::settings(config)?;
+        self.inner.reconfigure(config)?;
+        self.apply(settings);
+        Ok(())
+    }
+
+    fn stats(&self) -> LinkStats {
+        self.inner.stats()
+    }
+}
@@ -0,0 +1,276 @@
+//! GNSS talker IDs and multi-constellation satellite grouping
+//!
+//! Multi-constellation receivers report each system under its own talker
+//! (`GL` GLONASS, `GA` Galileo, `GB`/`BD` BeiDou, `GQ`/`QZ` QZSS) and use `GN`
+//! for fixes combining several. GSV sentences come in numbered groups per
+//! constellation, and a combined receiver emits one `GNGSA` per constellation
+//! tagged with the NMEA 4.10 system ID.
+//!
+//! Alongside the per-constellation satellites, [`SatelliteTracker`] keeps the
+//! fix mode and dilution of precision from GSA and the course and speed from
+//! VTG, which together make up the receiver status.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use datalink::DataMessage;
+
+/// Sentence formatters the GPS provider decodes
+pub const GNSS_FORMATTERS: [&str; 7] = ["GGA", "RMC", "GLL", "VTG", "GSA", "GSV", "ZDA"];
+
+/// Satellite system a sentence reports on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Constellation {
+    Gps,
+    Glonass,
+    Galileo,
+    BeiDou,
+    Qzss,
+    NavIc,
+    /// `GN` talker: solution combining several systems
+    Combined,
+}
+
+impl Constellation {
+    /// Constellation for a two-letter talker ID, `None` for non-GNSS talkers
+    pub fn from_talker(talker: &str) -> Option<Self> {
+        match talker {
+            "GP" => Some(Constellation::Gps),
+            "GL" => Some(Constellation::Glonass),
+            "GA" => Some(Constellation::Galileo),
+            "GB" | "BD" => Some(Constellation::BeiDou),
+            "GQ" | "QZ" => Some(Constellation::Qzss),
+            "GI" => Some(Constellation::NavIc),
+            "GN" => Some(Constellation::Combined),
+            _ => None,
+        }
+    }
+
+    /// Constellation for an NMEA 4.10 GNSS system ID (GSA field 18)
+    pub fn from_system_id(system_id: i64) -> Option<Self> {
+        match system_id {
+            1 => Some(Constellation::Gps),
+            2 => Some(Constellation::Glonass),
+            3 => Some(Constellation::Galileo),
+            4 => Some(Constellation::BeiDou),
+            5 => Some(Constellation::Qzss),
+            6 => Some(Constellation::NavIc),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Constellation::Gps => "GPS",
+            Constellation::Glonass => "GLONASS",
+            Constellation::Galileo => "Galileo",
+            Constellation::BeiDou => "BeiDou",
+            Constellation::Qzss => "QZSS",
+            Constellation::NavIc => "NavIC",
+            Constellation::Combined => "GNSS",
+        }
+    }
+
+    /// Resolve the constellation of a parsed GSA/GSV message, preferring the
+    /// system ID over a combined `GN` talker
+    pub fn of_message(message: &DataMessage) -> Option<Self> {
+        let talker = Constellation::from_talker(message.get_str("talker")?)?;
+        match (talker, message.get_i64("system_id").and_then(Constellation::from_system_id)) {
+            (Constellation::Combined, Some(system)) => Some(system),
+            _ => Some(talker),
+        }
+    }
+}
+
+/// Fix dimension reported in GSA field 2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixMode {
+    NoFix,
+    Fix2D,
+    Fix3D,
+}
+
+impl FixMode {
+    pub fn from_fix_type(fix_type: i64) -> Option<Self> {
+        match fix_type {
+            1 => Some(FixMode::NoFix),
+            2 => Some(FixMode::Fix2D),
+            3 => Some(FixMode::Fix3D),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FixMode::NoFix => "NO FIX",
+            FixMode::Fix2D => "2D",
+            FixMode::Fix3D => "3D",
+        }
+    }
+}
+
+/// Dilution of precision from the latest GSA
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DilutionOfPrecision {
+    pub pdop: Option<f64>,
+    pub hdop: Option<f64>,
+    pub vdop: Option<f64>,
+}
+
+/// Course and speed over ground from the latest VTG
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CourseOverGround {
+    pub true_deg: Option<f64>,
+    pub magnetic_deg: Option<f64>,
+    pub speed_kts: Option<f64>,
+    pub speed_kmh: Option<f64>,
+}
+
+/// Split `$GPGGA` (or `GPGGA`) into talker `GP` and formatter `GGA` when the
+/// talker is a GNSS one
+pub fn split_address(address: &str) -> Option<(&str, &str)> {
+    let address = address.strip_prefix('$').unwrap_or(address);
+    if address.len() != 5 || !address.is_ascii() {
+        return None;
+    }
+    let (talker, formatter) = address.split_at(2);
+    Constellation::from_talker(talker)?;
+    Some((talker, formatter))
+}
+
+/// One satellite from a GSV sentence
+#[derive(Debug, Clone, PartialEq)]
+pub struct SatelliteInView {
+    pub prn: u16,
+    pub elevation_deg: Option<u8>,
+    pub azimuth_deg: Option<u16>,
+    /// Signal to noise ratio in dB-Hz; `None` when not tracked
+    pub snr_db: Option<u8>,
+}
+
+/// Satellite blocks of a GSV sentence: four `prn,elevation,azimuth,snr`
+/// groups after the header, optionally followed by a signal ID
+pub fn gsv_satellites(parts: &[&str]) -> Vec<SatelliteInView> {
+    let blocks = parts.get(4..).unwrap_or_default();
+    blocks
+        .chunks(4)
+        .filter(|block| block.len() == 4)
+        .filter_map(|block| {
+            let field = |index: usize| block[index].split('*').next().unwrap_or("");
+            Some(SatelliteInView {
+                prn: field(0).parse().ok()?,
+                elevation_deg: field(1).parse().ok(),
+                azimuth_deg: field(2).parse().ok(),
+                snr_db: field(3).parse().ok(),
+            })
+        })
+        .collect()
+}
+
+/// Groups GSV and GSA sentences per constellation into a satellite overview,
+/// and keeps the latest fix mode, DOP and course over ground
+#[derive(Debug, Default)]
+pub struct SatelliteTracker {
+    /// GSV group still arriving, per constellation
+    pending: HashMap<Constellation, Vec<SatelliteInView>>,
+    in_view: BTreeMap<Constellation, Vec<SatelliteInView>>,
+    used: BTreeMap<Constellation, BTreeSet<u16>>,
+    fix_mode: Option<FixMode>,
+    dop: Option<DilutionOfPrecision>,
+    course: Option<CourseOverGround>,
+}
+
+impl SatelliteTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a parsed `GPS_SENTENCE`; returns the constellation whose view
+    /// changed once a GSV group completes or a GSA arrives
+    pub fn push(&mut self, message: &DataMessage) -> Option<Constellation> {
+        if message.get_str("formatter")? == "VTG" {
+            self.course = Some(CourseOverGround {
+                true_deg: message.get_f64("course"),
+                magnetic_deg: message.get_f64("course_magnetic"),
+                speed_kts: message.get_f64("speed"),
+                speed_kmh: message.get_f64("speed_kmh"),
+            });
+            return None;
+        }
+
+        let constellation = Constellation::of_message(message)?;
+        let sentence = std::str::from_utf8(&message.payload).ok()?;
+        let parts: Vec<&str> = sentence.trim_end().split(',').collect();
+
+        match message.get_str("formatter")? {
+            "GSV" => {
+                let total = message.get_i64("total_messages")?;
+                let number = message.get_i64("message_number")?;
+                if number == 1 {
+                    self.pending.insert(constellation, Vec::new());
+                }
+                let pending = self.pending.get_mut(&constellation)?;
+                pending.extend(gsv_satellites(&parts));
+                if number < total {
+                    return None;
+                }
+                let satellites = self.pending.remove(&constellation)?;
+                self.in_view.insert(constellation, satellites);
+                Some(constellation)
+            }
+            "GSA" => {
+                let prns = parts
+                    .get(3..15)
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|prn| prn.parse().ok())
+                    .collect();
+                self.used.insert(constellation, prns);
+                self.fix_mode = message.get_i64("fix_type").and_then(FixMode::from_fix_type);
+                self.dop = Some(DilutionOfPrecision {
+                    pdop: message.get_f64("pdop"),
+                    hdop: message.get_f64("hdop"),
+                    vdop: message.get_f64("vdop"),
+                });
+                Some(constellation)
+            }
+            _ => None,
+        }
+    }
+
+    /// Satellites in view from the last complete GSV group of each constellation
+    pub fn in_view(&self, constellation: Constellation) -> &[SatelliteInView] {
+        self.in_view.get(&constellation).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn satellites_in_view(&self) -> usize {
+        self.in_view.values().map(Vec::len).sum()
+    }
+
+    /// PRNs the last GSA of a constellation reported as used in the fix
+    pub fn used(&self, constellation: Constellation) -> Option<&BTreeSet<u16>> {
+        self.used.get(&constellation)
+    }
+
+    pub fn satellites_used(&self) -> usize {
+        self.used.values().map(BTreeSet::len).sum()
+    }
+
+    /// Fix mode from the latest GSA
+    pub fn fix_mode(&self) -> Option<FixMode> {
+        self.fix_mode
+    }
+
+    /// Dilution of precision from the latest GSA
+    pub fn dop(&self) -> Option<DilutionOfPrecision> {
+        self.dop
+    }
+
+    /// Course and speed from the latest VTG
+    pub fn course(&self) -> Option<CourseOverGround> {
+        self.course
+    }
+
+    /// Constellations with satellites in view, in a stable order
+    pub fn constellations(&self) -> impl Iterator<Item = Constellation> + '_ {
+        self.in_view.keys().copied()
+    }
+}
@@ -0,0 +1,687 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::mpsc;
+use datalink::{DataLinkConfig, DataLinkError, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataLinkTransmitter, DataMessage, LinkStats, SystemClock, TimeSource};
+use crate::bluetooth::{self, BluetoothAddress, DEFAULT_RFCOMM_CHANNEL};
+use crate::ntrip::{self, NtripConfig, RtcmFramer, RTCM_MESSAGE_TYPE};
+use crate::raw_log::RawLog;
+use crate::reconnect::{ReceiverResult, Reconnector, SharedStatus};
+use crate::replay::{ReplayFile, ReplayOptions};
+use crate::runtime::runtime_handle;
+use crate::serial;
+use crate::transport::{self, TransportConfig, TransportHub, TransportSubscription};
+use super::sentences::parse_sentence_at;
+
+/// Configuration for different types of GPS data sources
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum GpsSourceConfig {
+    /// Serial port configuration
+    Serial {
+        port: String,
+        baud_rate: u32,
+    },
+    /// TCP connection configuration
+    Tcp {
+        host: String,
+        port: u16,
+    },
+    /// UDP connection configuration
+    Udp {
+        bind_addr: String,
+        port: u16,
+    },
+    /// File replay configuration
+    File {
+        path: String,
+        replay: ReplayOptions,
+    },
+    /// Bluetooth classic serial (RFCOMM) configuration
+    Bluetooth {
+        address: String,
+        channel: u8,
+    },
+}
+
+impl From<&GpsSourceConfig> for TransportConfig {
+    fn from(source: &GpsSourceConfig) -> Self {
+        match source {
+            GpsSourceConfig::Serial { port, baud_rate } => TransportConfig::Serial { port: port.clone(), baud_rate: *baud_rate },
+            GpsSourceConfig::Tcp { host, port } => TransportConfig::Tcp { host: host.clone(), port: *port },
+            GpsSourceConfig::Udp { bind_addr, port } => TransportConfig::Udp { bind_addr: bind_addr.clone(), port: *port },
+            GpsSourceConfig::File { path, replay } => TransportConfig::File { path: path.clone(), replay: replay.clone() },
+            GpsSourceConfig::Bluetooth { address, channel } => TransportConfig::Bluetooth { address: address.clone(), channel: *channel },
+        }
+    }
+}
+
+/// Real GPS Datalink Provider
+pub struct GpsDataLinkProvider {
+    status: SharedStatus,
+    config: Option<DataLinkConfig>,
+    source_config: Option<GpsSourceConfig>,
+    message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
+    receiver_handle: Option<tokio::task::JoinHandle<()>>,
+    /// Runtime for the receiver tasks; the caller's when unset
+    runtime: Option<tokio::runtime::Handle>,
+    shutdown_tx: Option<mpsc::Sender<()>>,
+    /// RTCM corrections written to the receiver; serial and TCP sources only
+    corrections_tx: Option<mpsc::UnboundedSender<Vec<u8>>>,
+    ntrip_handle: Option<tokio::task::JoinHandle<()>>,
+    ntrip_shutdown_tx: Option<mpsc::Sender<()>>,
+    /// Set when connected with `shared=true`
+    shared: Option<TransportSubscription>,
+    time_source: Arc<dyn TimeSource>,
+}
+
+impl GpsDataLinkProvider {
+    /// Create a new GPS datalink provider
+    pub fn new() -> Self {
+        Self {
+            status: SharedStatus::default(),
+            config: None,
+            source_config: None,
+            message_queue: Arc::new(Mutex::new(VecDeque::new())),
+            receiver_handle: None,
+            runtime: None,
+            shutdown_tx: None,
+            corrections_tx: None,
+            ntrip_handle: None,
+            ntrip_shutdown_tx: None,
+            shared: None,
+            time_source: Arc::new(SystemClock),
+        }
+    }
+
+    /// Spawn the receiver tasks on `runtime` rather than the caller's
+    pub fn with_runtime(mut self, runtime: tokio::runtime::Handle) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Use `time_source` to stamp received messages instead of the system clock
+    pub fn with_time_source(mut self, time_source: Arc<dyn TimeSource>) -> Self {
+        self.time_source = time_source;
+        self
+    }
+
+    /// Parse GPS source configuration from DataLinkConfig
+    pub fn parse_source_config(config: &DataLinkConfig) -> DataLinkResult<GpsSourceConfig> {
+        let connection_type = config.parameters.get("connection_type")
+            .ok_or_else(|| DataLinkError::InvalidConfig("Missing connection_type".to_string()))?;
+
+        match connection_type.as_str() {
+            "serial" => {
+                let port = config.parameters.get("port")
+                    .ok_or_else(|| DataLinkError::InvalidConfig("Missing port for serial connection".to_string()))?;
+                let baud_rate = serial::parse_baud_rate(config.parameters.get("baud_rate").map(String::as_str), 4800)?;
+
+                Ok(GpsSourceConfig::Serial {
+                    port: port.clone(),
+                    baud_rate,
+                })
+            }
+            "tcp" => {
+                let host = config.parameters.get("host")
+                    .ok_or_else(|| DataLinkError::InvalidConfig("Missing host for TCP connection".to_string()))?;
+                let port = config.parameters.get("port")
+                    .ok_or_else(|| DataLinkError::InvalidConfig("Missing port for TCP connection".to_string()))?
+                    .parse::<u16>()
+                    .map_err(|_| DataLinkError::InvalidConfig("Invalid port number".to_string()))?;
+
+                Ok(GpsSourceConfig::Tcp {
+                    host: host.clone(),
+                    port,
+                })
+            }
+            "udp" => {
+                let bind_addr = config.parameters.get("bind_addr")
+                    .unwrap_or(&"0.0.0.0".to_string())
+                    .clone();
+                let port = config.parameters.get("port")
+                    .ok_or_else(|| DataLinkError::InvalidConfig("Missing port for UDP connection".to_string()))?
+                    .parse::<u16>()
+                    .map_err(|_| DataLinkError::InvalidConfig("Invalid port number".to_string()))?;
+
+                Ok(GpsSourceConfig::Udp {
+                    bind_addr,
+                    port,
+                })
+            }
+            "file" => {
+                let path = config.parameters.get("path")
+                    .ok_or_else(|| DataLinkError::InvalidConfig("Missing path for file replay".to_string()))?;
+
+                Ok(GpsSourceConfig::File {
+                    path: path.clone(),
+                    replay: ReplayOptions::from_config(config)?,
+                })
+            }
+            "bluetooth" => {
+                let address = config.parameters.get("address")
+                    .ok_or_else(|| DataLinkError::InvalidConfig("Missing address for Bluetooth connection".to_string()))?
+                    .parse::<BluetoothAddress>()?;
+                let channel = match config.parameters.get("channel") {
+                    Some(channel) => channel.parse::<u8>()
+                        .map_err(|_| DataLinkError::InvalidConfig("Invalid RFCOMM channel".to_string()))?,
+                    None => DEFAULT_RFCOMM_CHANNEL,
+                };
+
+                Ok(GpsSourceConfig::Bluetooth {
+                    address: address.to_string(),
+                    channel,
+                })
+            }
+            _ => Err(DataLinkError::InvalidConfig(format!("Unsupported connection type: {}", connection_type))),
+        }
+    }
+
+    /// Start the data receiver task based on the source configuration.
+    /// Must run inside a tokio runtime.
+    fn start_receiver(&mut self) -> DataLinkResult<()> {
+        let source_config = self.source_config.as_ref()
+            .ok_or_else(|| DataLinkError::InvalidConfig("No source configuration".to_string()))?;
+
+        let runtime = runtime_handle(self.runtime.as_ref())?;
+        let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
+        let (corrections_tx, mut corrections_rx) = mpsc::unbounded_channel::<Vec<u8>>();
+        let message_queue = Arc::clone(&self.message_queue);
+        let time_source = Arc::clone(&self.time_source);
+        let status = self.status.clone();
+        let auto_reconnect = self.config.as_ref().is_none_or(|config| config.auto_reconnect);
+
+        let receiver_handle = match source_config {
+            GpsSourceConfig::Serial { port, baud_rate } => {
+                let port = port.clone();
+                let baud_rate = *baud_rate;
+
+                runtime.spawn(async move {
+                    let mut reconnector = Reconnector::new("GPS serial", auto_reconnect, status);
+                    loop {
+                        let result = Self::serial_receiver(port.clone(), baud_rate, Arc::clone(&message_queue), Arc::clone(&time_source), &mut shutdown_rx, &mut corrections_rx, reconnector.status()).await;
+                        if !reconnector.retry(result, &mut shutdown_rx).await {
+                            break;
+                        }
+                    }
+                })
+            }
+            GpsSourceConfig::Tcp { host, port } => {
+                let host = host.clone();
+                let port = *port;
+
+                runtime.spawn(async move {
+                    let mut reconnector = Reconnector::new("GPS TCP", auto_reconnect, status);
+                    loop {
+                        let result = Self::tcp_receiver(host.clone(), port, Arc::clone(&message_queue), Arc::clone(&time_source), &mut shutdown_rx, &mut corrections_rx, reconnector.status()).await;
+                        if !reconnector.retry(result, &mut shutdown_rx).await {
+                            break;
+                        }
+                    }
+                })
+            }
+            GpsSourceConfig::Udp { bind_addr, port } => {
+                let bind_addr = bind_addr.clone();
+                let port = *port;
+
+                runtime.spawn(async move {
+                    let mut reconnector = Reconnector::new("GPS UDP", auto_reconnect, status);
+                    loop {
+                        let result = Self::udp_receiver(bind_addr.clone(), port, Arc::clone(&message_queue), Arc::clone(&time_source), &mut shutdown_rx, reconnector.status()).await;
+                        if !reconnector.retry(result, &mut shutdown_rx).await {
+                            break;
+                        }
+                    }
+                })
+            }
+            GpsSourceConfig::File { path, replay } => {
+                let path = path.clone();
+                let replay = replay.clone();
+
+                runtime.spawn(async move {
+                    let mut reconnector = Reconnector::new("GPS file", auto_reconnect, status);
+                    loop {
+                        let result = Self::file_receiver(path.clone(), replay.clone(), Arc::clone(&message_queue), Arc::clone(&time_source), &mut shutdown_rx, reconnector.status()).await;
+                        if !reconnector.retry(result, &mut shutdown_rx).await {
+                            break;
+                        }
+                    }
+                })
+            }
+            GpsSourceConfig::Bluetooth { address, channel } => {
+                let address = address.parse::<BluetoothAddress>()?;
+                let channel = *channel;
+
+                runtime.spawn(async move {
+                    let mut reconnector = Reconnector::new("GPS bluetooth", auto_reconnect, status);
+                    loop {
+                        let result = Self::bluetooth_receiver(address, channel, Arc::clone(&message_queue), Arc::clone(&time_source), &mut shutdown_rx, reconnector.status()).await;
+                        if !reconnector.retry(result, &mut shutdown_rx).await {
+                            break;
+                        }
+                    }
+                })
+            }
+        };
+
+        if matches!(source_config, GpsSourceConfig::Serial { .. } | GpsSourceConfig::Tcp { .. }) {
+            self.corrections_tx = Some(corrections_tx);
+        }
+        self.receiver_handle = Some(receiver_handle);
+        self.shutdown_tx = Some(shutdown_tx);
+
+        Ok(())
+    }
+
+    /// Start fetching corrections from an NTRIP caster into the receiver
+    fn start_ntrip_client(&mut self, ntrip_config: NtripConfig) -> DataLinkResult<()> {
+        let corrections_tx = self.corrections_tx.clone().ok_or_else(|| {
+            DataLinkError::InvalidConfig("RTCM corrections need a serial or TCP GPS receiver".to_string())
+        })?;
+        let runtime = runtime_handle(self.runtime.as_ref())?;
+        let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
+        let auto_reconnect = self.config.as_ref().is_none_or(|config| config.auto_reconnect);
+
+        self.ntrip_handle = Some(runtime.spawn(async move {
+            // The caster link has its own status so an outage does not mark
+            // the receiver itself as failed
+            let mut reconnector = Reconnector::new("NTRIP", auto_reconnect, SharedStatus::default());
+            loop {
+                let result = ntrip::stream_corrections(&ntrip_config, &corrections_tx, &mut shutdown_rx, reconnector.status()).await;
+                if !reconnector.retry(result, &mut shutdown_rx).await {
+                    break;
+                }
+            }
+        }));
+        self.ntrip_shutdown_tx = Some(shutdown_tx);
+
+        Ok(())
+    }
+
+    /// Serial port receiver implementation
+    async fn serial_receiver(
+        port: String,
+        baud_rate: u32,
+        message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
+        time_source: Arc<dyn TimeSource>,
+        shutdown_rx: &mut mpsc::Receiver<()>,
+        corrections_rx: &mut mpsc::UnboundedReceiver<Vec<u8>>,
+        status: &SharedStatus,
+    ) -> ReceiverResult {
+        info!("Starting GPS serial receiver on port {} at {} baud", port, serial::baud_rate_label(baud_rate));
+
+        let (serial_port, _) = serial::open(&port, baud_rate).await?;
+        status.set(DataLinkStatus::Connected);
+
+        let (read_half, mut write_half) = tokio::io::split(serial_port);
+        let mut reader = BufReader::new(read_half);
+        let mut line = String::new();
+
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => {
+                    info!("GPS Serial receiver shutdown requested");
+                    break;
+                }
+                Some(corrections) = corrections_rx.recv() => {
+                    write_half.write_all(&corrections).await
+                        .map_err(|e| format!("GPS Serial write error: {}", e))?;
+                }
+                result = reader.read_line(&mut line) => {
+                    match result {
+                        Ok(0) => return Err("GPS Serial port closed".into()),
+                        Ok(_) => {
+                            if let Some(message) = status.stats().observe(&line, Self::parse_gps_sentence_at(line.trim(), time_source.now())) {
+                                if let Ok(mut queue) = message_queue.lock() {
+                                    queue.push_back(message);
+                                    // Limit queue size to prevent memory issues
+                                    if queue.len() > 1000 {
+                                        queue.pop_front();
+                                    }
+                                }
+                            }
+                            line.clear();
+                        }
+                        Err(e) => return Err(format!("GPS Serial read error: {}", e).into()),
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Bluetooth RFCOMM receiver implementation
+    async fn bluetooth_receiver(
+        address: BluetoothAddress,
+        channel: u8,
+        message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
+        time_source: Arc<dyn TimeSource>,
+        shutdown_rx: &mut mpsc::Receiver<()>,
+        status: &SharedStatus,
+    ) -> ReceiverResult {
+        info!("Starting GPS Bluetooth receiver for {} on channel {}", address, channel);
+
+        let stream = bluetooth::connect(address, channel).await?;
+        status.set(DataLinkStatus::Connected);
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => {
+                    info!("GPS Bluetooth receiver shutdown requested");
+                    break;
+                }
+                result = reader.read_line(&mut line) => {
+                    match result {
+                        Ok(0) => return Err("GPS Bluetooth connection closed".into()),
+                        Ok(_) => {
+                            if let Some(message) = status.stats().observe(&line, Self::parse_gps_sentence_at(line.trim(), time_source.now())) {
+                                if let Ok(mut queue) = message_queue.lock() {
+                                    queue.push_back(message);
+                                    if queue.len() > 1000 {
+                                        queue.pop_front();
+                                    }
+                                }
+                            }
+                            line.clear();
+                        }
+                        Err(e) => return Err(format!("GPS Bluetooth read error: {}", e).into()),
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// TCP receiver implementation
+    async fn tcp_receiver(
+        host: String,
+        port: u16,
+        message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
+        time_source: Arc<dyn TimeSource>,
+        shutdown_rx: &mut mpsc::Receiver<()>,
+        corrections_rx: &mut mpsc::UnboundedReceiver<Vec<u8>>,
+        status: &SharedStatus,
+    ) -> ReceiverResult {
+        info!("Starting GPS TCP receiver connecting to {}:{}", host, port);
+
+        let stream = TcpStream::connect(format!("{}:{}", host, port)).await?;
+        status.set(DataLinkStatus::Connected);
+        let (read_half, mut write_half) = stream.into_split();
+        let mut reader = BufReader::new(read_half);
+        let mut line = String::new();
+
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => {
+                    info!("GPS TCP receiver shutdown requested");
+                    break;
+                }
+                Some(corrections) = corrections_rx.recv() => {
+                    write_half.write_all(&corrections).await
+                        .map_err(|e| format!("GPS TCP write error: {}", e))?;
+                }
+                result = reader.read_line(&mut line) => {
+                    match result {
+                        Ok(0) => return Err("GPS TCP connection closed".into()),
+                        Ok(_) => {
+                            if let Some(message) = status.stats().observe(&line, Self::parse_gps_sentence_at(line.trim(), time_source.now())) {
+                                if let Ok(mut queue) = message_queue.lock() {
+                                    queue.push_back(message);
+                                    if queue.len() > 1000 {
+                                        queue.pop_front();
+                                    }
+                                }
+                            }
+                            line.clear();
+                        }
+                        Err(e) => return Err(format!("GPS TCP read error: {}", e).into()),
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// UDP receiver implementation
+    async fn udp_receiver(
+        bind_addr: String,
+        port: u16,
+        message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
+        time_source: Arc<dyn TimeSource>,
+        shutdown_rx: &mut mpsc::Receiver<()>,
+        status: &SharedStatus,
+    ) -> ReceiverResult {
+        info!("Starting GPS UDP receiver on {}:{}", bind_addr, port);
+
+        let socket = UdpSocket::bind(format!("{}:{}", bind_addr, port)).await?;
+        status.set(DataLinkStatus::Connected);
+        let mut buf = [0; 1024];
+
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => {
+                    info!("GPS UDP receiver shutdown requested");
+                    break;
+                }
+                result = socket.recv(&mut buf) => {
+                    match result {
+                        Ok(len) => {
+                            let data = String::from_utf8_lossy(&buf[..len]);
+                            for line in data.lines() {
+                                if let Some(message) = status.stats().observe(line, Self::parse_gps_sentence_at(line.trim(), time_source.now())) {
+                                    if let Ok(mut queue) = message_queue.lock() {
+                                        queue.push_back(message);
+                                        if queue.len() > 1000 {
+                                            queue.pop_front();
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        Err(e) => return Err(format!("GPS UDP receive error: {}", e).into()),
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// File receiver implementation for replaying GPS data
+    async fn file_receiver(
+        path: String,
+        replay: ReplayOptions,
+        message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
+        time_source: Arc<dyn TimeSource>,
+        shutdown_rx: &mut mpsc::Receiver<()>,
+        status: &SharedStatus,
+    ) -> ReceiverResult {
+        info!("Starting GPS file receiver for {} at {}x speed", path, replay.speed);
+
+        let mut replay = ReplayFile::open(&path, replay).await?;
+        status.set(DataLinkStatus::Connected);
+
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => {
+                    info!("GPS File receiver shutdown requested");
+                    break;
+                }
+                result = replay.next_line() => {
+                    match result {
+                        Ok(Some(line)) => {
+                            if let Some(message) = status.stats().observe(&line, Self::parse_gps_sentence_at(line.trim(), time_source.now())) {
+                                if let Ok(mut queue) = message_queue.lock() {
+                                    queue.push_back(message);
+                                    if queue.len() > 1000 {
+                                        queue.pop_front();
+                                    }
+                                }
+                            }
+                        }
+                        Ok(None) => {
+                            info!("GPS End of file reached");
+                            break;
+                        }
+                        Err(e) => {
+                            error!("GPS File read error: {}", e);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parse a GPS NMEA sentence into a DataMessage, received now
+    pub fn parse_gps_sentence(sentence: &str) -> Option<DataMessage> {
+        Self::parse_gps_sentence_at(sentence, SystemClock.now())
+    }
+
+    /// Parse a GPS NMEA sentence received at `received_at`. RMC and ZDA
+    /// sentences also carry the UTC time of the fix as `measured_at`.
+    pub fn parse_gps_sentence_at(sentence: &str, received_at: SystemTime) -> Option<DataMessage> {
+        parse_sentence_at(sentence, received_at)
+    }
+
+    /// Stop the receiver and NTRIP tasks
+    fn stop_receiver(&mut self) {
+        for shutdown_tx in [self.ntrip_shutdown_tx.take(), self.shutdown_tx.take()].into_iter().flatten() {
+            let _ = shutdown_tx.try_send(());
+        }
+        self.corrections_tx = None;
+        for handle in [self.ntrip_handle.take(), self.receiver_handle.take()].into_iter().flatten() {
+            handle.abort();
+        }
+    }
+}
+
+impl Default for GpsDataLinkProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DataLinkReceiver for GpsDataLinkProvider {
+    fn status(&self) -> DataLinkStatus {
+        self.status.get()
+    }
+
+    fn receive_message(&mut self) -> DataLinkResult<Option<DataMessage>> {
+        if let Ok(mut queue) = self.message_queue.lock() {
+            if let Some(shared) = &self.shared {
+            let received_at = self.time_source.now();
+                for line in shared.drain() {
+                    if let Some(message) = self.status.stats().observe(&line, Self::parse_gps_sentence_at(&line, received_at)) {
+                        queue.push_back(message);
+                    }
+                }
+            }
+            Ok(queue.pop_front())
+        } else {
+            Err(DataLinkError::TransportError("Failed to access message queue".to_string()))
+        }
+    }
+
+    fn stats(&self) -> LinkStats {
+        self.status.stats().get()
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        info!("Connecting GPS datalink provider");
+
+        // Check the whole configuration before stopping a running receiver
+        let source_config = Self::parse_source_config(config)?;
+        let ntrip_config = NtripConfig::from_config(config)?;
+        let raw_log = RawLog::from_config(config)?;
+        let shared = if transport::is_shared(config) {
+            Some(TransportHub::global().subscribe(&TransportConfig::from(&source_config))?)
+        } else {
+            None
+        };
+        self.stop_receiver();
+
+        self.status.set(DataLinkStatus::Connecting);
+        self.config = Some(config.clone());
+        self.source_config = Some(source_config);
+        self.status.stats().set_raw_log(raw_log);
+
+        self.shared = shared;
+        if self.shared.is_none() {
+            self.start_receiver()?;
+            if let Some(ntrip_config) = ntrip_config {
+                self.start_ntrip_client(ntrip_config)?;
+            }
+        }
+
+        self.status.set(DataLinkStatus::Connected);
+        info!("GPS datalink provider connected successfully");
+
+        Ok(())
+    }
+
+    /// Swap the source, e.g. a new baud rate or host, keeping queued fixes and counters
+    fn reconfigure(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        DataLinkReceiver::connect(self, config)
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        info!("Disconnecting GPS datalink provider");
+
+        self.stop_receiver();
+        self.shared = None;
+        self.status.stats().set_raw_log(None);
+
+        self.status.set(DataLinkStatus::Disconnected);
+        self.config = None;
+        self.source_config = None;
+
+        info!("GPS datalink provider disconnected");
+        Ok(())
+    }
+}
+
+impl DataLinkTransmitter for GpsDataLinkProvider {
+    fn status(&self) -> DataLinkStatus {
+        self.status.get()
+    }
+
+    /// Write RTCM3 corrections to the receiver. Only `RTCM` messages are
+    /// accepted, and only on serial and TCP sources.
+    fn send_message(&mut self, message: &DataMessage) -> DataLinkResult<()> {
+        if message.message_type != RTCM_MESSAGE_TYPE {
+            return Err(DataLinkError::TransportError(format!(
+                "GPS transmission only carries RTCM corrections, not {}",
+                message.message_type
+            )));
+        }
+        if RtcmFramer::default().push(&message.payload).is_empty() {
+            return Err(DataLinkError::InvalidMessage("No valid RTCM3 frame in payload".to_string()));
+        }
+        let corrections_tx = self.corrections_tx.as_ref()
+            .ok_or_else(|| DataLinkError::TransportError("GPS source does not accept RTCM corrections".to_string()))?;
+        corrections_tx
+            .send(message.payload.to_vec())
+            .map_err(|_| DataLinkError::TransportError("GPS receiver connection closed".to_string()))
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        // Use the same connection logic as receiver
+        DataLinkReceiver::connect(self, config)
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        // Use the same disconnection logic as receiver
+        DataLinkReceiver::disconnect(self)
+    }
+}
@@ -0,0 +1,217 @@
+//! GPS NMEA 0183 sentence parsing
+//!
+//! Kept apart from the providers so builds without the native serial and
+//! network stack (wasm32) can still turn sentences into messages.
+
+use std::time::SystemTime;
+use datalink::{utc_from_nmea, utc_from_parts, DataMessage, Value};
+use super::gnss::{split_address, Constellation, FixMode, GNSS_FORMATTERS};
+use crate::nmea;
+
+/// Parse a GPS NMEA sentence received at `received_at`. RMC and ZDA
+/// sentences also carry the UTC time of the fix as `measured_at`.
+pub fn parse_sentence_at(sentence: &str, received_at: SystemTime) -> Option<DataMessage> {
+    if !sentence.starts_with('$') {
+        return None;
+    }
+
+    // Basic NMEA sentence validation
+    let parts: Vec<&str> = sentence.split(',').collect();
+    if parts.len() < 3 {
+        return None;
+    }
+
+    // Extract sentence type (first part after $)
+    let sentence_type = parts[0];
+
+    // Accept any GNSS talker (GP, GN, GL, GA, GB/BD, GQ/QZ, GI)
+    let (talker, formatter) = split_address(sentence_type)?;
+    if !GNSS_FORMATTERS.contains(&formatter) {
+        return None;
+    }
+
+    // Create a DataMessage from the GPS sentence
+    let mut message = DataMessage::new(
+        "GPS_SENTENCE".to_string(),
+        "GPS_RECEIVER".to_string(),
+        sentence.as_bytes().to_vec(),
+    )
+    .with_received_at(received_at);
+
+    // Add parsed data based on sentence type
+    message = message.with_data("sentence_type", sentence_type.to_string());
+    message = message.with_data("talker", talker);
+    message = message.with_data("formatter", formatter);
+    if let Some(constellation) = Constellation::from_talker(talker) {
+        message = message.with_data("constellation", constellation.name());
+    }
+
+    // Parse specific GPS sentence types
+    match formatter {
+        "GGA" => {
+            // Global Positioning System Fix Data
+            if parts.len() >= 15 {
+                message = message.with_data("time", parts[1].to_string());
+                message = message.with_data("latitude", parts[2].to_string());
+                message = message.with_data("lat_direction", parts[3].to_string());
+                message = message.with_data("longitude", parts[4].to_string());
+                message = message.with_data("lon_direction", parts[5].to_string());
+                if let Ok(fix_quality) = parts[6].parse::<i64>() {
+                    message = message.with_data("fix_quality", fix_quality);
+                }
+                if let Ok(satellites) = parts[7].parse::<i64>() {
+                    message = message.with_data("satellites", satellites);
+                }
+                if let Ok(hdop) = parts[8].parse::<f64>() {
+                    message = message.with_data("hdop", hdop);
+                }
+                if let Ok(altitude) = parts[9].parse::<f64>() {
+                    message = message.with_data("altitude", altitude);
+                }
+                message = message.with_data("altitude_unit", parts[10].to_string());
+                if let Some(position) = nmea::position(parts[2], parts[3], parts[4], parts[5]) {
+                    message = message.with_data("position", position);
+                }
+            }
+        }
+        "RMC" => {
+            // Recommended Minimum Course
+            if parts.len() >= 12 {
+                message = message.with_data("time", parts[1].to_string());
+                message = message.with_data("status", parts[2].to_string());
+                message = message.with_data("latitude", parts[3].to_string());
+                message = message.with_data("lat_direction", parts[4].to_string());
+                message = message.with_data("longitude", parts[5].to_string());
+                message = message.with_data("lon_direction", parts[6].to_string());
+                if let Ok(speed) = parts[7].parse::<f64>() {
+                    message = message.with_data("speed", speed);
+                }
+                if let Ok(course) = parts[8].parse::<f64>() {
+                    message = message.with_data("course", Value::Angle(course));
+                }
+                message = message.with_data("date", parts[9].to_string());
+                if let Some(position) = nmea::position(parts[3], parts[4], parts[5], parts[6]) {
+                    message = message.with_data("position", position);
+                }
+                if let Some(measured_at) = utc_from_nmea(parts[9], parts[1]) {
+                    message = message.with_measured_at(measured_at);
+                }
+                let direction = parts[11].split('*').next().unwrap_or("");
+                if let Some(variation) = nmea::east_positive(parts[10], direction) {
+                    message = message.with_data("variation", variation);
+                }
+            }
+        }
+        "ZDA" => {
+            // Time & Date
+            if parts.len() >= 5 {
+                message = message.with_data("time", parts[1].to_string());
+                if let (Ok(year), Ok(month), Ok(day)) = (parts[4].parse::<i32>(), parts[3].parse::<u32>(), parts[2].parse::<u32>()) {
+                    message = message
+                        .with_data("day", day)
+                        .with_data("month", month)
+                        .with_data("year", year);
+                    if let Some(measured_at) = utc_from_parts(year, month, day, parts[1]) {
+                        message = message.with_measured_at(measured_at);
+                    }
+                }
+            }
+        }
+        "GSA" => {
+            // DOP and active satellites; NMEA 4.10 adds the system ID
+            if parts.len() >= 18 {
+                let field = |index: usize| parts.get(index).map(|part| part.split('*').next().unwrap_or(""));
+                message = message.with_data("mode", parts[1].to_string());
+                if let Ok(fix_type) = parts[2].parse::<i64>() {
+                    message = message.with_data("fix_type", fix_type);
+                    if let Some(fix_mode) = FixMode::from_fix_type(fix_type) {
+                        message = message.with_data("fix_mode", fix_mode.label());
+                    }
+                }
+                let used = parts[3..15].iter().filter(|prn| !prn.is_empty()).count();
+                message = message.with_data("satellites_used", used);
+                for (key, index) in [("pdop", 15), ("hdop", 16), ("vdop", 17)] {
+                    if let Some(Ok(dop)) = field(index).map(str::parse::<f64>) {
+                        message = message.with_data(key, dop);
+                    }
+                }
+                if let Some(Ok(system_id)) = field(18).map(str::parse::<i64>) {
+                    message = message.with_data("system_id", system_id);
+                    if let Some(constellation) = Constellation::from_system_id(system_id) {
+                        message = message.with_data("constellation", constellation.name());
+                    }
+                }
+            }
+        }
+        "GSV" => {
+            // Satellites in view, sent as a numbered group per constellation
+            if parts.len() >= 4 {
+                if let Ok(total_messages) = parts[1].parse::<i64>() {
+                    message = message.with_data("total_messages", total_messages);
+                }
+                if let Ok(message_number) = parts[2].parse::<i64>() {
+                    message = message.with_data("message_number", message_number);
+                }
+                if let Ok(in_view) = parts[3].split('*').next().unwrap_or("").parse::<i64>() {
+                    message = message.with_data("satellites_in_view", in_view);
+                }
+            }
+        }
+        "VTG" => {
+            // Track made good and ground speed; NMEA 2.3 adds the mode indicator
+            if parts.len() >= 9 {
+                let field = |index: usize| parts.get(index).map_or("", |part| part.split('*').next().unwrap_or(""));
+                if let Ok(course) = field(1).parse::<f64>() {
+                    message = message.with_data("course", Value::Angle(course));
+                }
+                if let Ok(course_magnetic) = field(3).parse::<f64>() {
+                    message = message.with_data("course_magnetic", Value::Angle(course_magnetic));
+                }
+                if let Ok(speed) = field(5).parse::<f64>() {
+                    message = message.with_data("speed", speed);
+                }
+                if let Ok(speed_kmh) = field(7).parse::<f64>() {
+                    message = message.with_data("speed_kmh", speed_kmh);
+                }
+                if !field(9).is_empty() {
+                    message = message.with_data("mode", field(9).to_string());
+                }
+            }
+        }
+        "GLL" => {
+            // Geographic Position - Latitude/Longitude
+            if parts.len() >= 7 {
+                message = message.with_data("latitude", parts[1].to_string());
+                message = message.with_data("lat_direction", parts[2].to_string());
+                message = message.with_data("longitude", parts[3].to_string());
+                message = message.with_data("lon_direction", parts[4].to_string());
+                message = message.with_data("time", parts[5].to_string());
+                message = message.with_data("status", parts[6].to_string());
+                if let Some(position) = nmea::position(parts[1], parts[2], parts[3], parts[4]) {
+                    message = message.with_data("position", position);
+                }
+            }
+        }
+        _ => {
+            // For other sentence types, just store the raw parts
+            for (i, part) in parts.iter().enumerate() {
+                message = message.with_data(format!("field_{}", i), part.to_string());
+            }
+        }
+    }
+
+    // Add timestamp
+    message = message.with_data(
+        "timestamp",
+        received_at
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    );
+
+    // Set signal quality based on sentence completeness and checksum
+    let quality = if sentence.contains('*') { 95 } else { 75 };
+    message = message.with_signal_quality(quality);
+
+    Some(message)
+}
@@ -0,0 +1,287 @@
+//! Route-following simulated GPS
+//!
+//! Sails a list of waypoints at a set speed, turning towards the next one no
+//! faster than the configured turn rate, and reports the track as GGA and
+//! RMC sentences that go through the same parser as a real receiver's. A
+//! waypoint counts as reached once the boat is within its turning radius, so
+//! corners are rounded the way an autopilot would round them.
+//!
+//! Parameters: `waypoints` as `lat,lon;lat,lon;...`, `speed` in knots (6),
+//! `turn_rate` in degrees per second (3), `update_interval_ms` (1000) and
+//! `loop=true` to sail on to the first waypoint after the last. Without it
+//! the boat stops at the final waypoint.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use datalink::geo::{bearing_deg, destination, distance_m, METERS_PER_NM};
+use datalink::{DataLinkConfig, DataLinkError, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataMessage, LinkStats, SystemClock, TimeSource};
+use super::sentences::parse_sentence_at;
+use crate::nmea;
+
+/// Longest step the track is advanced in one go
+const MAX_STEP: f64 = 1.0;
+
+/// A route and how it is sailed
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatedRoute {
+    /// `(latitude, longitude)` in decimal degrees
+    pub waypoints: Vec<(f64, f64)>,
+    /// Knots
+    pub speed: f64,
+    /// Degrees per second
+    pub turn_rate: f64,
+    pub update_interval: Duration,
+    /// Head back to the first waypoint after the last
+    pub looped: bool,
+}
+
+impl SimulatedRoute {
+    pub fn from_config(config: &DataLinkConfig) -> DataLinkResult<Self> {
+        let param = |key: &str| config.parameters.get(key).map(String::as_str);
+        let number = |key: &str, default: f64| -> DataLinkResult<f64> {
+            match param(key) {
+                Some(value) => value
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|number| number.is_finite() && *number >= 0.0)
+                    .ok_or_else(|| DataLinkError::InvalidConfig(format!("Invalid {}: {}", key, value))),
+                None => Ok(default),
+            }
+        };
+
+        let waypoints = param("waypoints")
+            .ok_or_else(|| DataLinkError::InvalidConfig("Missing waypoints for simulated GPS".to_string()))?
+            .split(';')
+            .filter(|waypoint| !waypoint.trim().is_empty())
+            .map(parse_waypoint)
+            .collect::<DataLinkResult<Vec<_>>>()?;
+        if waypoints.is_empty() {
+            return Err(DataLinkError::InvalidConfig("Simulated GPS needs at least one waypoint".to_string()));
+        }
+        let turn_rate = number("turn_rate", 3.0)?;
+        if turn_rate == 0.0 {
+            return Err(DataLinkError::InvalidConfig("turn_rate must be above zero".to_string()));
+        }
+        Ok(Self {
+            waypoints,
+            speed: number("speed", 6.0)?,
+            turn_rate,
+            update_interval: Duration::from_millis(number("update_interval_ms", 1000.0)? as u64),
+            looped: param("loop") == Some("true"),
+        })
+    }
+
+    /// Meters per second
+    fn speed_ms(&self) -> f64 {
+        self.speed * METERS_PER_NM / 3600.0
+    }
+
+    /// Radius of the tightest turn at speed, in meters
+    fn turning_radius(&self) -> f64 {
+        self.speed_ms() / self.turn_rate.to_radians()
+    }
+}
+
+fn parse_waypoint(waypoint: &str) -> DataLinkResult<(f64, f64)> {
+    let invalid = || DataLinkError::InvalidConfig(format!("Invalid waypoint: {}", waypoint));
+    let (latitude, longitude) = waypoint.split_once(',').ok_or_else(invalid)?;
+    let latitude: f64 = latitude.trim().parse().map_err(|_| invalid())?;
+    let longitude: f64 = longitude.trim().parse().map_err(|_| invalid())?;
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return Err(invalid());
+    }
+    Ok((latitude, longitude))
+}
+
+/// The simulated boat on its way along a route
+#[derive(Debug, Clone)]
+pub struct RouteFollower {
+    route: SimulatedRoute,
+    position: (f64, f64),
+    heading: f64,
+    next: usize,
+    finished: bool,
+}
+
+impl RouteFollower {
+    /// Start at the first waypoint, pointing at the second
+    pub fn new(route: SimulatedRoute) -> Self {
+        let position = route.waypoints[0];
+        let finished = route.waypoints.len() < 2;
+        let heading = route.waypoints.get(1).map_or(0.0, |next| bearing_deg(position, *next));
+        Self {
+            route,
+            position,
+            heading,
+            next: 1,
+            finished,
+        }
+    }
+
+    pub fn position(&self) -> (f64, f64) {
+        self.position
+    }
+
+    /// Degrees true
+    pub fn heading(&self) -> f64 {
+        self.heading
+    }
+
+    /// Knots; zero once the route is done
+    pub fn speed(&self) -> f64 {
+        if self.finished {
+            0.0
+        } else {
+            self.route.speed
+        }
+    }
+
+    /// Index of the waypoint being steered for
+    pub fn next_waypoint(&self) -> usize {
+        self.next
+    }
+
+    /// Whether the boat has stopped at the last waypoint
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Sail on for `elapsed`
+    pub fn advance(&mut self, elapsed: Duration) {
+        let arrival_radius = self.route.turning_radius();
+        let mut remaining = elapsed.as_secs_f64();
+        while remaining > 0.0 && !self.finished {
+            let step = remaining.min(MAX_STEP);
+            remaining -= step;
+
+            let target = self.route.waypoints[self.next];
+            let turn = (bearing_deg(self.position, target) - self.heading + 540.0).rem_euclid(360.0) - 180.0;
+            let max_turn = self.route.turn_rate * step;
+            self.heading = (self.heading + turn.clamp(-max_turn, max_turn)).rem_euclid(360.0);
+
+            let distance = self.route.speed_ms() * step;
+            self.position = destination(self.position, self.heading, distance);
+            if distance_m(self.position, target) <= arrival_radius.max(distance) {
+                self.next += 1;
+                if self.next == self.route.waypoints.len() {
+                    self.next = 0;
+                    self.finished = !self.route.looped;
+                }
+            }
+        }
+    }
+
+    /// GGA and RMC sentences for the current position at `time`
+    pub fn sentences(&self, time: SystemTime) -> [String; 2] {
+        let utc = nmea::time_field(time);
+        let latitude = nmea::coordinate_fields(self.position.0, true);
+        let longitude = nmea::coordinate_fields(self.position.1, false);
+        [
+            nmea::frame_sentence(&format!("GPGGA,{},{},{},1,08,0.9,0.0,M,0.0,M,,", utc, latitude, longitude)),
+            nmea::frame_sentence(&format!(
+                "GPRMC,{},A,{},{},{:.1},{:.1},{},,,A",
+                utc,
+                latitude,
+                longitude,
+                self.speed(),
+                self.heading,
+                nmea::date_field(time)
+            )),
+        ]
+    }
+}
+
+/// GPS provider sailing a configured route in real time
+pub struct SimulatedGpsProvider {
+    status: DataLinkStatus,
+    follower: Option<RouteFollower>,
+    message_queue: VecDeque<DataMessage>,
+    last_update: Option<SystemTime>,
+    stats: LinkStats,
+    time_source: Arc<dyn TimeSource>,
+}
+
+impl SimulatedGpsProvider {
+    pub fn new() -> Self {
+        Self {
+            status: DataLinkStatus::Disconnected,
+            follower: None,
+            message_queue: VecDeque::new(),
+            last_update: None,
+            stats: LinkStats::default(),
+            time_source: Arc::new(SystemClock),
+        }
+    }
+
+    /// Sail by `time_source` instead of the system clock
+    pub fn with_time_source(mut self, time_source: Arc<dyn TimeSource>) -> Self {
+        self.time_source = time_source;
+        self
+    }
+
+    /// The simulated boat, while connected
+    pub fn follower(&self) -> Option<&RouteFollower> {
+        self.follower.as_ref()
+    }
+
+    fn report(&mut self, time: SystemTime) {
+        let Some(follower) = &self.follower else {
+            return;
+        };
+        for sentence in follower.sentences(time) {
+            if let Some(message) = parse_sentence_at(&sentence, time) {
+                self.stats.record_parsed();
+                self.message_queue.push_back(message);
+            }
+        }
+        self.last_update = Some(time);
+    }
+}
+
+impl Default for SimulatedGpsProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DataLinkReceiver for SimulatedGpsProvider {
+    fn status(&self) -> DataLinkStatus {
+        self.status.clone()
+    }
+
+    fn receive_message(&mut self) -> DataLinkResult<Option<DataMessage>> {
+        if self.message_queue.is_empty() {
+            let now = self.time_source.now();
+            let elapsed = self.last_update.and_then(|last| now.duration_since(last).ok());
+            if let (Some(follower), Some(elapsed)) = (&mut self.follower, elapsed) {
+                if elapsed >= follower.route.update_interval {
+                    follower.advance(elapsed);
+                    self.report(now);
+                }
+            }
+        }
+        Ok(self.message_queue.pop_front())
+    }
+
+    fn stats(&self) -> LinkStats {
+        self.stats.clone()
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        let route = SimulatedRoute::from_config(config)?;
+        self.follower = Some(RouteFollower::new(route));
+        self.status = DataLinkStatus::Connected;
+        self.report(self.time_source.now());
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        self.follower = None;
+        self.last_update = None;
+        self.message_queue.clear();
+        self.status = DataLinkStatus::Disconnected;
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+//! Web Serial GPS provider for browser builds
+//!
+//! Implements the `serial` connection type on wasm32, where the native serial
+//! stack does not compile. Browsers only hand out ports the user granted, so
+//! [`request_port`] has to run once from a click handler; afterwards
+//! `connect` reopens a granted port without prompting. With several granted
+//! ports the `port` parameter picks one by index.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use js_sys::{Array, Function, Object, Promise, Reflect, Uint8Array};
+use log::{error, info, warn};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use datalink::{DataLinkConfig, DataLinkError, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataMessage, SystemClock, TimeSource};
+use super::sentences::parse_sentence_at;
+use crate::proprietary::SentenceParsers;
+
+/// Messages buffered before the oldest are dropped
+const MAX_QUEUED_MESSAGES: usize = 1000;
+
+fn get(target: &JsValue, key: &str) -> Result<JsValue, JsValue> {
+    Reflect::get(target, &JsValue::from_str(key))
+}
+
+/// Call `method` on `target` and await the promise it returns
+async fn call(target: &JsValue, method: &str, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let function: Function = get(target, method)?.dyn_into()?;
+    let result = function.apply(target, &args.iter().collect::<Array>())?;
+    JsFuture::from(Promise::resolve(&result)).await
+}
+
+/// `navigator.serial`, or an error in browsers without Web Serial
+fn serial() -> Result<JsValue, JsValue> {
+    let serial = get(&get(&js_sys::global(), "navigator")?, "serial")?;
+    if serial.is_undefined() {
+        return Err(JsValue::from_str("Web Serial is not supported by this browser"));
+    }
+    Ok(serial)
+}
+
+/// Ask the user to grant a serial port. Only works during a user gesture.
+pub async fn request_port() -> Result<(), JsValue> {
+    call(&serial()?, "requestPort", &[]).await.map(|_| ())
+}
+
+/// GPS provider reading NMEA from a USB receiver through Web Serial
+pub struct WebSerialGpsProvider {
+    status: Arc<Mutex<DataLinkStatus>>,
+    message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
+    running: Arc<AtomicBool>,
+}
+
+impl WebSerialGpsProvider {
+    pub fn new() -> Self {
+        Self {
+            status: Arc::new(Mutex::new(DataLinkStatus::Disconnected)),
+            message_queue: Arc::new(Mutex::new(VecDeque::new())),
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    fn set_status(status: &Mutex<DataLinkStatus>, value: DataLinkStatus) {
+        if let Ok(mut status) = status.lock() {
+            *status = value;
+        }
+    }
+
+    /// Open the granted port and read lines until disconnected or the port closes
+    async fn read_port(
+        index: u32,
+        baud_rate: u32,
+        queue: Arc<Mutex<VecDeque<DataMessage>>>,
+        running: Arc<AtomicBool>,
+        status: Arc<Mutex<DataLinkStatus>>,
+    ) -> Result<(), JsValue> {
+        let ports: Array = call(&serial()?, "getPorts", &[]).await?.dyn_into()?;
+        let port = ports.get(index);
+        if port.is_undefined() {
+            return Err(JsValue::from_str("No granted serial port; call request_port from a user gesture first"));
+        }
+
+        let options = Object::new();
+        Reflect::set(&options, &JsValue::from_str("baudRate"), &JsValue::from(baud_rate))?;
+        call(&port, "open", &[options.into()]).await?;
+        let reader = call(&get(&port, "readable")?, "getReader", &[]).await?;
+        Self::set_status(&status, DataLinkStatus::Connected);
+        info!("Web Serial port {} open at {} baud", index, baud_rate);
+
+        let mut pending = Vec::new();
+        while running.load(Ordering::Relaxed) {
+            let chunk = call(&reader, "read", &[]).await?;
+            if get(&chunk, "done")?.is_truthy() {
+                warn!("Web Serial port closed");
+                break;
+            }
+            pending.extend(Uint8Array::new(&get(&chunk, "value")?).to_vec());
+            while let Some(end) = pending.iter().position(|byte| *byte == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                let (line, now) = (line.trim(), SystemClock.now());
+                let parsed = parse_sentence_at(line, now).or_else(|| SentenceParsers::global().parse(line, now));
+                if let Some(message) = parsed {
+                    if let Ok(mut queue) = queue.lock() {
+                        queue.push_back(message);
+                        if queue.len() > MAX_QUEUED_MESSAGES {
+                            queue.pop_front();
+                        }
+                    }
+                }
+            }
+        }
+
+        // A reader keeps the port locked until released
+        let _ = call(&reader, "cancel", &[]).await;
+        let _ = get(&reader, "releaseLock")
+            .and_then(|release| release.dyn_into::<Function>())
+            .and_then(|release| release.call0(&reader));
+        call(&port, "close", &[]).await.map(|_| ())
+    }
+}
+
+impl Default for WebSerialGpsProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DataLinkReceiver for WebSerialGpsProvider {
+    fn status(&self) -> DataLinkStatus {
+        self.status.lock().map(|status| status.clone()).unwrap_or(DataLinkStatus::Disconnected)
+    }
+
+    fn receive_message(&mut self) -> DataLinkResult<Option<DataMessage>> {
+        match self.message_queue.lock() {
+            Ok(mut queue) => Ok(queue.pop_front()),
+            Err(_) => Err(DataLinkError::TransportError("Failed to access message queue".to_string())),
+        }
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        match config.parameters.get("connection_type").map(String::as_str) {
+            Some("serial") => {}
+            other => {
+                return Err(DataLinkError::InvalidConfig(format!(
+                    "Unsupported connection type in the browser: {}",
+                    other.unwrap_or("none")
+                )))
+            }
+        }
+        let baud_rate = config
+            .parameters
+            .get("baud_rate")
+            .map_or(Ok(4800), |rate| rate.parse::<u32>())
+            .map_err(|_| DataLinkError::InvalidConfig("Invalid baud_rate".to_string()))?;
+        let index = config
+            .parameters
+            .get("port")
+            .map_or(Ok(0), |port| port.parse::<u32>())
+            .map_err(|_| DataLinkError::InvalidConfig("Web Serial port must be an index".to_string()))?;
+        serial().map_err(|e| DataLinkError::ConnectionFailed(format!("{:?}", e)))?;
+
+        // The port opens asynchronously; status turns Connected once it does
+        Self::set_status(&self.status, DataLinkStatus::Connecting);
+        self.running.store(true, Ordering::Relaxed);
+        let queue = Arc::clone(&self.message_queue);
+        let running = Arc::clone(&self.running);
+        let status = Arc::clone(&self.status);
+        spawn_local(async move {
+            let result = Self::read_port(index, baud_rate, queue, Arc::clone(&running), Arc::clone(&status)).await;
+            running.store(false, Ordering::Relaxed);
+            match result {
+                Ok(()) => Self::set_status(&status, DataLinkStatus::Disconnected),
+                Err(e) => {
+                    error!("Web Serial error: {:?}", e);
+                    Self::set_status(&status, DataLinkStatus::Error(format!("{:?}", e)));
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Stops after the next chunk; receivers send at least once a second
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        info!("Disconnecting Web Serial GPS provider");
+        self.running.store(false, Ordering::Relaxed);
+        if let Ok(mut queue) = self.message_queue.lock() {
+            queue.clear();
+        }
+        Self::set_status(&self.status, DataLinkStatus::Disconnected);
+        Ok(())
+    }
+}
@@ -3,7 +3,8 @@ use std::sync::{Arc, Mutex};
 use log::info;
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
-use datalink::{DataLinkConfig, DataLinkError, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataLinkTransmitter, DataMessage};
+use datalink::{DataLinkConfig, DataLinkError, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataLinkTransmitter, DataMessage, Value};
+use crate::gps::Constellation;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct LocationData {
@@ -14,6 +15,8 @@ pub struct LocationData {
     pub timestamp: Option<String>,
     pub fix_quality: Option<u8>,
     pub satellites: Option<u8>,
+    /// Satellite system named by the talker ID
+    pub constellation: Option<Constellation>,
 }
 
 impl Default for LocationData {
@@ -26,6 +29,7 @@ impl Default for LocationData {
             timestamp: None,
             fix_quality: None,
             satellites: None,
+            constellation: None,
         }
     }
 }
@@ -47,13 +51,17 @@ impl GnssParser {
             return None;
         }
 
-        let sentence_type = parts[0];
+        // Any GNSS talker: `$GAGGA`, `$GLRMC`, `$BDGGA`, ...
+        let address = parts[0].strip_prefix('$').filter(|address| address.len() == 5 && address.is_ascii())?;
+        let (talker, formatter) = address.split_at(2);
+        let constellation = Constellation::from_talker(talker)?;
 
-        match sentence_type {
-            "$GPGGA" | "$GNGGA" => self.parse_gpgga(&parts),
-            "$GPRMC" | "$GNRMC" => self.parse_gprmc(&parts),
+        let location = match formatter {
+            "GGA" => self.parse_gpgga(&parts),
+            "RMC" => self.parse_gprmc(&parts),
             _ => None,
-        }
+        }?;
+        Some(LocationData { constellation: Some(constellation), ..location })
     }
 
     fn parse_gpgga(&self, parts: &[&str]) -> Option<LocationData> {
@@ -285,25 +293,31 @@ impl GpyesDataLinkProvider {
         );
 
         if let Some(lat) = location.latitude {
-            message = message.with_data("latitude".to_string(), lat.to_string());
+            message = message.with_data("latitude", lat);
         }
         if let Some(lon) = location.longitude {
-            message = message.with_data("longitude".to_string(), lon.to_string());
+            message = message.with_data("longitude", lon);
+        }
+        if let (Some(lat), Some(lon)) = (location.latitude, location.longitude) {
+            message = message.with_data("position", Value::LatLon { lat, lon });
         }
         if let Some(alt) = location.altitude {
-            message = message.with_data("altitude".to_string(), alt.to_string());
+            message = message.with_data("altitude", alt);
         }
         if let Some(speed) = location.speed {
-            message = message.with_data("speed".to_string(), speed.to_string());
+            message = message.with_data("speed", speed);
         }
         if let Some(timestamp) = &location.timestamp {
-            message = message.with_data("timestamp".to_string(), timestamp.clone());
+            message = message.with_data("timestamp", timestamp.clone());
         }
         if let Some(quality) = location.fix_quality {
-            message = message.with_data("fix_quality".to_string(), quality.to_string());
+            message = message.with_data("fix_quality", quality);
         }
         if let Some(sats) = location.satellites {
-            message = message.with_data("satellites".to_string(), sats.to_string());
+            message = message.with_data("satellites", sats);
+        }
+        if let Some(constellation) = location.constellation {
+            message = message.with_data("constellation", constellation.name());
         }
 
         message
@@ -539,11 +553,12 @@ mod tests {
         let message = provider.location_data_to_message(&location);
         assert_eq!(message.message_type, "GPYES_LOCATION");
         assert_eq!(message.source_id, "GPYES_RECEIVER");
-        assert_eq!(message.get_data("latitude"), Some(&"48.1173".to_string()));
-        assert_eq!(message.get_data("longitude"), Some(&"11.5167".to_string()));
-        assert_eq!(message.get_data("altitude"), Some(&"545.4".to_string()));
-        assert_eq!(message.get_data("fix_quality"), Some(&"1".to_string()));
-        assert_eq!(message.get_data("satellites"), Some(&"8".to_string()));
+        assert_eq!(message.get_f64("latitude"), Some(48.1173));
+        assert_eq!(message.get_f64("longitude"), Some(11.5167));
+        assert_eq!(message.get_lat_lon("position"), Some((48.1173, 11.5167)));
+        assert_eq!(message.get_f64("altitude"), Some(545.4));
+        assert_eq!(message.get_i64("fix_quality"), Some(1));
+        assert_eq!(message.get_i64("satellites"), Some(8));
     }
 
     #[test]
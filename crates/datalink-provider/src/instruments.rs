@@ -0,0 +1,216 @@
+//! Depth, wind, heading, speed log and water temperature instrument sentences
+//!
+//! Echo sounders and wind transducers usually share a serial or TCP feed
+//! with the GPS and AIS, so this provider always reads through the
+//! [`TransportHub`]: connecting it next to `shared=true` AIS/GPS providers
+//! on the same port opens the connection only once.
+
+use std::collections::VecDeque;
+use log::info;
+use datalink::{DataLinkConfig, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataMessage, LinkStats, Value};
+use crate::nmea::east_positive;
+use crate::raw_log::RawLog;
+use crate::stats::SharedStats;
+use crate::transport::{TransportConfig, TransportHub, TransportSubscription};
+
+/// Knots per meter per second
+const KNOTS_PER_MPS: f64 = 1.943_844;
+
+/// Kilometers per hour per knot
+const KPH_PER_KNOT: f64 = 1.852;
+
+/// Depth (DPT, DBT), apparent wind (MWV), true wind direction (MWD), heading
+/// (HDT, HDG), speed through water (VHW) and water temperature (MTW) provider
+/// over a shared transport
+pub struct InstrumentDataLinkProvider {
+    status: DataLinkStatus,
+    /// Reported as the `transducer` of depth messages when configured
+    transducer: Option<String>,
+    shared: Option<TransportSubscription>,
+    message_queue: VecDeque<DataMessage>,
+    stats: SharedStats,
+}
+
+impl InstrumentDataLinkProvider {
+    pub fn new() -> Self {
+        Self {
+            status: DataLinkStatus::Disconnected,
+            transducer: None,
+            shared: None,
+            message_queue: VecDeque::new(),
+            stats: SharedStats::default(),
+        }
+    }
+
+    /// Parse an instrument sentence into a `DEPTH`, `WIND`, `HEADING`,
+    /// `SPEED_LOG` or `WATER_TEMPERATURE` message
+    pub fn parse_instrument_sentence(sentence: &str) -> Option<DataMessage> {
+        let body = sentence.strip_prefix('$')?;
+        let body = body.split('*').next().unwrap_or(body);
+        let parts: Vec<&str> = body.split(',').collect();
+        let formatter = parts[0].get(2..)?;
+        let message = |message_type: &str| {
+            DataMessage::new(message_type.to_string(), "INSTRUMENTS".to_string(), sentence.as_bytes().to_vec())
+                .with_data("sentence_type", format!("${}", parts[0]))
+        };
+
+        match formatter {
+            // $SDDPT,depth_m,offset_m[,max_range]
+            "DPT" => {
+                let depth: f64 = parts.get(1)?.parse().ok()?;
+                let mut depth_message = message("DEPTH").with_data("depth_m", depth);
+                if let Some(Ok(offset)) = parts.get(2).map(|offset| offset.parse::<f64>()) {
+                    depth_message = depth_message.with_data("offset_m", offset);
+                }
+                Some(depth_message)
+            }
+            // $SDDBT,feet,f,meters,M,fathoms,F
+            "DBT" => {
+                let depth: f64 = parts.get(3)?.parse().ok()?;
+                Some(message("DEPTH").with_data("depth_m", depth))
+            }
+            // $WIMWV,angle,R|T,speed,N|M|K,A; only relative (apparent) wind is reported
+            "MWV" => {
+                if parts.get(2) != Some(&"R") || parts.get(5) != Some(&"A") {
+                    return None;
+                }
+                let angle: f64 = parts.get(1)?.parse().ok()?;
+                let speed: f64 = parts.get(3)?.parse().ok()?;
+                let speed_kts = match *parts.get(4)? {
+                    "N" => speed,
+                    "M" => speed * KNOTS_PER_MPS,
+                    "K" => speed / KPH_PER_KNOT,
+                    _ => return None,
+                };
+                Some(
+                    message("WIND")
+                        .with_data("apparent_wind_speed", speed_kts)
+                        .with_data("apparent_wind_angle", Value::Angle(angle))
+                        .with_data("reference", "R"),
+                )
+            }
+            // $HEHDT,heading,T
+            "HDT" => {
+                let heading: f64 = parts.get(1)?.parse().ok()?;
+                Some(message("HEADING").with_data("heading_true", Value::Angle(heading)))
+            }
+            // $HCHDG,sensor,deviation,E|W,variation,E|W; the sensor heading
+            // plus deviation is magnetic, plus variation on top of that true
+            "HDG" => {
+                let sensor: f64 = parts.get(1)?.parse().ok()?;
+                let deviation = east_positive(parts.get(2)?, parts.get(3)?);
+                let variation = east_positive(parts.get(4)?, parts.get(5)?);
+                let magnetic = (sensor + deviation.unwrap_or(0.0)).rem_euclid(360.0);
+                let mut heading = message("HEADING")
+                    .with_data("heading_compass", Value::Angle(sensor))
+                    .with_data("heading_magnetic", Value::Angle(magnetic));
+                if let Some(deviation) = deviation {
+                    heading = heading.with_data("deviation", deviation);
+                }
+                if let Some(variation) = variation {
+                    heading = heading
+                        .with_data("variation", variation)
+                        .with_data("heading_true", Value::Angle((magnetic + variation).rem_euclid(360.0)));
+                }
+                Some(heading)
+            }
+            // $WIMWD,direction,T,direction,M,speed,N,speed,M; where the true wind blows from
+            "MWD" => {
+                let field = |index: usize, unit: &str| {
+                    parts.get(index + 1).filter(|field| **field == unit)?;
+                    parts.get(index)?.parse::<f64>().ok()
+                };
+                let speed_kts = field(5, "N").or(field(7, "M").map(|speed| speed * KNOTS_PER_MPS))?;
+                let mut wind = message("WIND").with_data("true_wind_speed", speed_kts);
+                if let Some(direction) = field(1, "T") {
+                    wind = wind.with_data("wind_direction_true", Value::Angle(direction));
+                }
+                if let Some(direction) = field(3, "M") {
+                    wind = wind.with_data("wind_direction_magnetic", Value::Angle(direction));
+                }
+                Some(wind)
+            }
+            // $VWVHW,heading,T,heading,M,speed,N,speed,K; the log usually leaves the headings empty
+            "VHW" => {
+                let knots = parts.get(5).and_then(|speed| speed.parse::<f64>().ok());
+                let kph = parts.get(7).and_then(|speed| speed.parse::<f64>().ok());
+                let speed_kts = knots.or(kph.map(|speed| speed / KPH_PER_KNOT))?;
+                Some(message("SPEED_LOG").with_data("speed_through_water", speed_kts))
+            }
+            // $YXMTW,temperature,C
+            "MTW" => {
+                if parts.get(2) != Some(&"C") {
+                    return None;
+                }
+                let temperature: f64 = parts.get(1)?.parse().ok()?;
+                Some(message("WATER_TEMPERATURE").with_data("water_temp_c", temperature))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Default for InstrumentDataLinkProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DataLinkReceiver for InstrumentDataLinkProvider {
+    fn status(&self) -> DataLinkStatus {
+        self.status.clone()
+    }
+
+    fn receive_message(&mut self) -> DataLinkResult<Option<DataMessage>> {
+        if let Some(shared) = &self.shared {
+            for line in shared.drain() {
+                if let Some(mut message) = self.stats.observe(&line, Self::parse_instrument_sentence(&line)) {
+                    if let (Some(transducer), "DEPTH") = (&self.transducer, message.message_type.as_str()) {
+                        message = message.with_data("transducer", transducer.as_str());
+                    }
+                    self.message_queue.push_back(message);
+                }
+            }
+        }
+        Ok(self.message_queue.pop_front())
+    }
+
+    fn stats(&self) -> LinkStats {
+        self.stats.get()
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        info!("Connecting instrument datalink provider");
+        self.status = DataLinkStatus::Connecting;
+
+        let raw_log = RawLog::from_config(config)?;
+        let subscription = TransportConfig::from_config(config).and_then(|transport| TransportHub::global().subscribe(&transport));
+        match subscription {
+            Ok(subscription) => {
+                self.transducer = config.parameters.get("transducer").cloned();
+                self.stats.set_raw_log(raw_log);
+                self.shared = Some(subscription);
+                self.status = DataLinkStatus::Connected;
+                Ok(())
+            }
+            Err(e) => {
+                self.status = DataLinkStatus::Error(e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    /// Connecting again swaps the subscription and keeps the queue and counters
+    fn reconfigure(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        DataLinkReceiver::connect(self, config)
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        info!("Disconnecting instrument datalink provider");
+        self.shared = None;
+        self.stats.set_raw_log(None);
+        self.message_queue.clear();
+        self.status = DataLinkStatus::Disconnected;
+        Ok(())
+    }
+}
@@ -0,0 +1,318 @@
+//! MQTT datalink
+//!
+//! Subscribes to topics on a boat's MQTT broker and publishes outbound
+//! messages to it. Payloads may hold NMEA sentences or JSON; see
+//! [`FeedDecoder`]. Every received message carries the `topic` it arrived on,
+//! so JSON values such as Victron's `{"value": 13.2}` on
+//! `N/<portal id>/battery/512/Dc/0/Voltage` can be told apart.
+//!
+//! Parameters: `host`, `port` (1883), `topics` (comma separated, wildcards
+//! allowed), `client_id`, `username` / `password`, `qos` (0-2),
+//! `publish_topic` for outbound messages without a `topic` of their own, and
+//! `keepalive_topic`, published every [`KEEPALIVE_INTERVAL`]; Venus OS stops
+//! publishing unless `R/<portal id>/keepalive` is written regularly.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use log::info;
+use rumqttc::{AsyncClient, Event, EventLoop, MqttOptions, Packet, QoS, SubscribeFilter};
+use tokio::sync::mpsc;
+use datalink::{DataLinkConfig, DataLinkError, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataLinkTransmitter, DataMessage, LinkStats};
+use crate::multiplex::FeedDecoder;
+use crate::raw_log::RawLog;
+use crate::reconnect::{ReceiverResult, Reconnector, SharedStatus};
+use crate::runtime::runtime_handle;
+
+/// Registered MQTT port
+pub const DEFAULT_MQTT_PORT: u16 = 1883;
+
+/// How often `keepalive_topic` is published
+pub const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Requests buffered between the client and its event loop
+const CLIENT_CAPACITY: usize = 64;
+
+/// Messages buffered before the oldest are dropped
+const MAX_QUEUED_MESSAGES: usize = 1000;
+
+/// Broker connection settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct MqttConfig {
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub topics: Vec<String>,
+    pub qos: u8,
+    pub publish_topic: Option<String>,
+    pub keepalive_topic: Option<String>,
+}
+
+impl MqttConfig {
+    pub fn from_config(config: &DataLinkConfig) -> DataLinkResult<Self> {
+        let param = |key: &str| config.parameters.get(key).cloned();
+        let host = param("host").ok_or_else(|| DataLinkError::InvalidConfig("Missing host parameter for MQTT".to_string()))?;
+        let port = match param("port") {
+            Some(port) => port.parse().map_err(|_| DataLinkError::InvalidConfig("Invalid port parameter".to_string()))?,
+            None => DEFAULT_MQTT_PORT,
+        };
+        let qos = match param("qos").as_deref() {
+            None => 0,
+            Some(qos @ ("0" | "1" | "2")) => qos.parse().unwrap_or_default(),
+            Some(other) => return Err(DataLinkError::InvalidConfig(format!("Invalid qos: {}", other))),
+        };
+        let topics: Vec<String> = param("topics")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|topic| !topic.is_empty())
+            .map(str::to_string)
+            .collect();
+        let publish_topic = param("publish_topic");
+        if topics.is_empty() && publish_topic.is_none() {
+            return Err(DataLinkError::InvalidConfig("MQTT needs topics to subscribe to or a publish_topic".to_string()));
+        }
+        Ok(Self {
+            host,
+            port,
+            client_id: param("client_id").unwrap_or_else(|| format!("yachtpit-{}", std::process::id())),
+            username: param("username"),
+            password: param("password"),
+            topics,
+            qos,
+            publish_topic,
+            keepalive_topic: param("keepalive_topic"),
+        })
+    }
+
+    fn options(&self) -> MqttOptions {
+        let mut options = MqttOptions::new(self.client_id.clone(), self.host.clone(), self.port);
+        options.set_keep_alive(Duration::from_secs(30));
+        if let Some(username) = &self.username {
+            options.set_credentials(username.clone(), self.password.clone().unwrap_or_default());
+        }
+        options
+    }
+
+    fn qos(&self) -> QoS {
+        match self.qos {
+            1 => QoS::AtLeastOnce,
+            2 => QoS::ExactlyOnce,
+            _ => QoS::AtMostOnce,
+        }
+    }
+}
+
+pub struct MqttDataLinkProvider {
+    status: SharedStatus,
+    config: Option<MqttConfig>,
+    client: Option<AsyncClient>,
+    message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
+    shutdown_tx: Option<mpsc::Sender<()>>,
+    receiver_handle: Option<tokio::task::JoinHandle<()>>,
+    /// Runtime for the client task; the caller's when unset
+    runtime: Option<tokio::runtime::Handle>,
+}
+
+impl MqttDataLinkProvider {
+    pub fn new() -> Self {
+        Self {
+            status: SharedStatus::default(),
+            config: None,
+            client: None,
+            message_queue: Arc::new(Mutex::new(VecDeque::new())),
+            shutdown_tx: None,
+            receiver_handle: None,
+            runtime: None,
+        }
+    }
+
+    /// Spawn the client task on `runtime` rather than the caller's
+    pub fn with_runtime(mut self, runtime: tokio::runtime::Handle) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    fn start_client(&mut self, config: MqttConfig, auto_reconnect: bool) -> DataLinkResult<()> {
+        let runtime = runtime_handle(self.runtime.as_ref())?;
+        let (client, mut event_loop) = AsyncClient::new(config.options(), CLIENT_CAPACITY);
+        let (shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
+        let message_queue = Arc::clone(&self.message_queue);
+        let status = self.status.clone();
+        let task_client = client.clone();
+        let task_config = config.clone();
+
+        let handle = runtime.spawn(async move {
+            let mut decoder = FeedDecoder::new("MQTT", "MQTT");
+            let mut reconnector = Reconnector::new("MQTT", auto_reconnect, status);
+            loop {
+                let result = Self::run(
+                    &task_config,
+                    &task_client,
+                    &mut event_loop,
+                    &mut decoder,
+                    &message_queue,
+                    &mut shutdown_rx,
+                    reconnector.status(),
+                )
+                .await;
+                if !reconnector.retry(result, &mut shutdown_rx).await {
+                    break;
+                }
+            }
+        });
+
+        self.client = Some(client);
+        self.config = Some(config);
+        self.shutdown_tx = Some(shutdown_tx);
+        self.receiver_handle = Some(handle);
+        Ok(())
+    }
+
+    /// Drive the event loop until shutdown or until the broker connection fails.
+    /// rumqttc reconnects on the next poll, so the loop is reused across runs.
+    async fn run(
+        config: &MqttConfig,
+        client: &AsyncClient,
+        event_loop: &mut EventLoop,
+        decoder: &mut FeedDecoder,
+        message_queue: &Mutex<VecDeque<DataMessage>>,
+        shutdown_rx: &mut mpsc::Receiver<()>,
+        status: &SharedStatus,
+    ) -> ReceiverResult {
+        let mut keepalive = tokio::time::interval(KEEPALIVE_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => {
+                    info!("MQTT client shutdown requested");
+                    let _ = client.try_disconnect();
+                    return Ok(());
+                }
+                _ = keepalive.tick(), if config.keepalive_topic.is_some() && status.get() == DataLinkStatus::Connected => {
+                    if let Some(topic) = &config.keepalive_topic {
+                        client.try_publish(topic.clone(), QoS::AtMostOnce, false, Vec::new())?;
+                    }
+                }
+                event = event_loop.poll() => match event? {
+                    Event::Incoming(Packet::ConnAck(_)) => {
+                        info!("Connected to MQTT broker {}:{}", config.host, config.port);
+                        status.set(DataLinkStatus::Connected);
+                        // Subscriptions do not survive a clean session reconnect
+                        if !config.topics.is_empty() {
+                            let filters = config.topics.iter().map(|topic| SubscribeFilter::new(topic.clone(), config.qos()));
+                            client.try_subscribe_many(filters)?;
+                        }
+                    }
+                    Event::Incoming(Packet::Publish(publish)) => {
+                        let text = String::from_utf8_lossy(&publish.payload);
+                        let messages = decoder.decode(&text, SystemTime::now(), status.stats());
+                        if let Ok(mut queue) = message_queue.lock() {
+                            queue.extend(messages.into_iter().map(|message| message.with_data("topic", publish.topic.as_str())));
+                            while queue.len() > MAX_QUEUED_MESSAGES {
+                                queue.pop_front();
+                            }
+                        }
+                    }
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    fn stop_client(&mut self) {
+        if let Some(shutdown_tx) = self.shutdown_tx.take() {
+            let _ = shutdown_tx.try_send(());
+        }
+        self.client = None;
+        if let Some(handle) = self.receiver_handle.take() {
+            handle.abort();
+        }
+    }
+}
+
+impl Default for MqttDataLinkProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DataLinkReceiver for MqttDataLinkProvider {
+    fn status(&self) -> DataLinkStatus {
+        self.status.get()
+    }
+
+    fn receive_message(&mut self) -> DataLinkResult<Option<DataMessage>> {
+        match self.message_queue.lock() {
+            Ok(mut queue) => Ok(queue.pop_front()),
+            Err(_) => Err(DataLinkError::TransportError("Failed to access message queue".to_string())),
+        }
+    }
+
+    fn stats(&self) -> LinkStats {
+        self.status.stats().get()
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        let mqtt_config = MqttConfig::from_config(config)?;
+        self.status.stats().set_raw_log(RawLog::from_config(config)?);
+        info!("Connecting MQTT datalink to {}:{} for {:?}", mqtt_config.host, mqtt_config.port, mqtt_config.topics);
+        self.stop_client();
+        self.status.set(DataLinkStatus::Connecting);
+        self.start_client(mqtt_config, config.auto_reconnect).inspect_err(|e| {
+            self.status.set(DataLinkStatus::Error(format!("Connection failed: {}", e)));
+        })
+    }
+
+    /// Reconnect to a new broker or topics; queued messages stay
+    fn reconfigure(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        DataLinkReceiver::connect(self, config)
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        info!("Disconnecting MQTT datalink");
+        self.stop_client();
+        self.config = None;
+        self.status.stats().set_raw_log(None);
+        if let Ok(mut queue) = self.message_queue.lock() {
+            queue.clear();
+        }
+        self.status.set(DataLinkStatus::Disconnected);
+        Ok(())
+    }
+}
+
+impl DataLinkTransmitter for MqttDataLinkProvider {
+    fn status(&self) -> DataLinkStatus {
+        self.status.get()
+    }
+
+    /// Publish to the message's `topic`, else to `publish_topic`. The raw
+    /// payload is sent as is; a message without one goes out as JSON.
+    fn send_message(&mut self, message: &DataMessage) -> DataLinkResult<()> {
+        let (Some(client), Some(config)) = (&self.client, &self.config) else {
+            return Err(DataLinkError::ConnectionFailed("MQTT not connected".to_string()));
+        };
+        let topic = message
+            .get_str("topic")
+            .or(config.publish_topic.as_deref())
+            .ok_or_else(|| DataLinkError::InvalidConfig("No topic to publish to; set publish_topic".to_string()))?;
+        let payload = if message.payload.is_empty() {
+            serde_json::to_vec(message).map_err(|e| DataLinkError::InvalidMessage(e.to_string()))?
+        } else {
+            message.payload.to_vec()
+        };
+        client
+            .try_publish(topic, config.qos(), false, payload)
+            .map_err(|e| DataLinkError::TransportError(format!("MQTT publish failed: {}", e)))
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        DataLinkReceiver::connect(self, config)
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        DataLinkReceiver::disconnect(self)
+    }
+}
@@ -0,0 +1,124 @@
+//! Mixed feeds
+//!
+//! Brokers and servers that aggregate a boat's data carry whatever their
+//! publishers put on them: NMEA sentences of every kind, or JSON documents.
+//! [`FeedDecoder`] turns one text payload into messages. Each NMEA line goes
+//! to the parser of the provider that handles its sentence type; a JSON
+//! object is taken as a serialized [`DataMessage`] when it is one, and
+//! otherwise flattened, its scalar fields becoming message data.
+
+use std::time::SystemTime;
+use serde_json::{Map, Value as Json};
+use datalink::{DataMessage, Value};
+use crate::ais::{AisDataLinkProvider, AisFragmentAssembler};
+use crate::autopilot::AutopilotDataLinkProvider;
+use crate::engine::EngineDataLinkProvider;
+use crate::gps::parse_sentence_at;
+use crate::instruments::InstrumentDataLinkProvider;
+use crate::radar::RadarDataLinkProvider;
+use crate::stats::SharedStats;
+
+/// Parse a line of any NMEA sentence a built-in provider understands
+pub fn parse_any_sentence(line: &str, received_at: SystemTime) -> Option<DataMessage> {
+    let line = line.trim();
+    if line.starts_with('!') {
+        return AisDataLinkProvider::parse_ais_sentence(line);
+    }
+    parse_sentence_at(line, received_at)
+        .or_else(|| InstrumentDataLinkProvider::parse_instrument_sentence(line))
+        .or_else(|| EngineDataLinkProvider::parse_rpm_sentence(line))
+        .or_else(|| AutopilotDataLinkProvider::parse_autopilot_sentence(line))
+        .or_else(|| RadarDataLinkProvider::parse_radar_sentence(line))
+}
+
+/// Decodes the text payloads of a mixed feed
+#[derive(Debug)]
+pub struct FeedDecoder {
+    source_id: String,
+    json_message_type: String,
+    fragments: AisFragmentAssembler,
+}
+
+impl FeedDecoder {
+    /// Flattened JSON objects become `json_message_type` messages from `source_id`
+    pub fn new(source_id: &str, json_message_type: &str) -> Self {
+        Self {
+            source_id: source_id.to_string(),
+            json_message_type: json_message_type.to_string(),
+            fragments: AisFragmentAssembler::new(),
+        }
+    }
+
+    /// Messages in one payload, counting each line or document in `stats`
+    pub fn decode(&mut self, text: &str, received_at: SystemTime, stats: &SharedStats) -> Vec<DataMessage> {
+        let trimmed = text.trim();
+        if trimmed.starts_with('{') || trimmed.starts_with('[') {
+            stats.log_raw(trimmed);
+            let messages = self.decode_json(trimmed, received_at);
+            stats.update(|stats| {
+                if messages.is_empty() {
+                    stats.record_unsupported("JSON");
+                } else {
+                    stats.record_parsed();
+                }
+            });
+            return messages;
+        }
+
+        let mut messages = Vec::new();
+        for line in trimmed.lines() {
+            let Some(message) = stats.observe(line, parse_any_sentence(line, received_at)) else {
+                continue;
+            };
+            if message.message_type == "AIS_SENTENCE" {
+                messages.extend(self.fragments.join(message));
+            } else {
+                messages.push(message);
+            }
+        }
+        messages
+    }
+
+    /// Messages in a JSON document: one object, or an array of them
+    pub fn decode_json(&self, text: &str, received_at: SystemTime) -> Vec<DataMessage> {
+        let objects = match serde_json::from_str(text) {
+            Ok(Json::Object(object)) => vec![object],
+            Ok(Json::Array(items)) => items
+                .into_iter()
+                .filter_map(|item| match item {
+                    Json::Object(object) => Some(object),
+                    _ => None,
+                })
+                .collect(),
+            _ => return Vec::new(),
+        };
+        objects.into_iter().filter_map(|object| self.json_message(object, received_at)).collect()
+    }
+
+    fn json_message(&self, object: Map<String, Json>, received_at: SystemTime) -> Option<DataMessage> {
+        let is_message = ["message_type", "source_id", "data"].iter().all(|key| object.contains_key(*key));
+        if is_message {
+            if let Ok(message) = serde_json::from_value::<DataMessage>(Json::Object(object.clone())) {
+                return Some(message);
+            }
+        }
+
+        let payload = serde_json::to_vec(&object).ok()?;
+        let mut message = DataMessage::new(self.json_message_type.clone(), self.source_id.clone(), payload)
+            .with_received_at(received_at);
+        for (key, value) in object {
+            let value = match value {
+                Json::Bool(value) => Value::Bool(value),
+                Json::Number(number) => match (number.as_i64(), number.as_f64()) {
+                    (Some(value), _) => Value::Int(value),
+                    (None, Some(value)) => Value::Float(value),
+                    (None, None) => continue,
+                },
+                Json::String(value) => Value::Text(value),
+                _ => continue,
+            };
+            message = message.with_data(key, value);
+        }
+        (!message.data.is_empty()).then_some(message)
+    }
+}
@@ -0,0 +1,86 @@
+//! NMEA 0183 framing helpers shared by the providers
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// XOR checksum over the characters between the start delimiter and `*`
+pub fn checksum(body: &str) -> u8 {
+    body.bytes().fold(0u8, |acc, byte| acc ^ byte)
+}
+
+/// Frame a sentence body (without delimiter) as `$body*hh`
+pub fn frame_sentence(body: &str) -> String {
+    format!("${}*{:02X}", body, checksum(body))
+}
+
+/// Decimal degrees from an NMEA `ddmm.mmmm` / `dddmm.mmmm` field and its
+/// hemisphere letter
+pub fn coordinate(value: &str, hemisphere: &str) -> Option<f64> {
+    let dot = value.find('.').unwrap_or(value.len());
+    if dot < 3 {
+        return None;
+    }
+    let degrees: f64 = value[..dot - 2].parse().ok()?;
+    let minutes: f64 = value[dot - 2..].parse().ok()?;
+    let decimal = degrees + minutes / 60.0;
+    match hemisphere {
+        "N" | "E" => Some(decimal),
+        "S" | "W" => Some(-decimal),
+        _ => None,
+    }
+}
+
+/// Position value from NMEA latitude/longitude fields
+pub fn position(latitude: &str, lat_hemisphere: &str, longitude: &str, lon_hemisphere: &str) -> Option<datalink::Value> {
+    Some(datalink::Value::LatLon {
+        lat: coordinate(latitude, lat_hemisphere)?,
+        lon: coordinate(longitude, lon_hemisphere)?,
+    })
+}
+
+/// Signed degrees from a magnitude field and its `E`/`W` field, east positive,
+/// as variation and deviation are sent
+pub fn east_positive(magnitude: &str, direction: &str) -> Option<f64> {
+    let value: f64 = magnitude.parse().ok()?;
+    match direction {
+        "E" => Some(value),
+        "W" => Some(-value),
+        _ => None,
+    }
+}
+
+/// `hhmmss.ss` UTC time field
+pub fn time_field(time: SystemTime) -> String {
+    let centis = (seconds_since_epoch(time) * 100.0).round() as u64 % 8_640_000;
+    let seconds = centis / 100;
+    format!("{:02}{:02}{:02}.{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60, centis % 100)
+}
+
+/// `ddmmyy` UTC date field of RMC
+pub fn date_field(time: SystemTime) -> String {
+    let days = (seconds_since_epoch(time) / 86_400.0).floor() as i64;
+    // Civil date from days since 1970-01-01, proleptic Gregorian
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:02}{:02}{:02}", day, month, year.rem_euclid(100))
+}
+
+/// Latitude as `ddmm.mmmm,N` or longitude as `dddmm.mmmm,E`
+pub fn coordinate_fields(value: f64, is_latitude: bool) -> String {
+    let (positive, negative, width) = if is_latitude { ('N', 'S', 2) } else { ('E', 'W', 3) };
+    let hemisphere = if value < 0.0 { negative } else { positive };
+    let ten_thousandths = (value.abs() * 600_000.0).round() as u64;
+    let degrees = ten_thousandths / 600_000;
+    let minutes = (ten_thousandths % 600_000) as f64 / 10_000.0;
+    format!("{:0width$}{:07.4},{}", degrees, minutes, hemisphere, width = width)
+}
+
+fn seconds_since_epoch(time: SystemTime) -> f64 {
+    time.duration_since(UNIX_EPOCH).map_or(0.0, |since| since.as_secs_f64())
+}
@@ -0,0 +1,122 @@
+//! Actisense NGT-1 serial framing
+//!
+//! The NGT-1 wraps every message as `DLE STX <command> <length> <body>
+//! <checksum> DLE ETX`, doubling any DLE byte inside the frame. Received
+//! NMEA 2000 messages carry the PGN header and a payload whose fast packets
+//! the gateway has already joined, so they go straight to [`decode_pgn`].
+//!
+//! [`decode_pgn`]: super::decode_pgn
+
+use super::CanId;
+
+const DLE: u8 = 0x10;
+const STX: u8 = 0x02;
+const ETX: u8 = 0x03;
+
+/// NMEA 2000 message received from the bus
+const N2K_MESSAGE_RECEIVED: u8 = 0x93;
+/// Command to the NGT-1 itself
+const NGT_MESSAGE_SEND: u8 = 0xA1;
+
+/// NGT-1 command forwarding every received PGN instead of the stored list
+const NGT_RECEIVE_ALL: [u8; 3] = [0x11, 0x02, 0x00];
+
+/// Longest frame accepted before the decoder resynchronizes
+const MAX_FRAME_LENGTH: usize = 600;
+
+/// Default NGT-1 serial speed
+pub const ACTISENSE_BAUD_RATE: u32 = 115_200;
+
+/// Frame a command and body for the NGT-1, with checksum and DLE stuffing
+pub fn frame_message(command: u8, body: &[u8]) -> Vec<u8> {
+    let mut content = Vec::with_capacity(body.len() + 3);
+    content.push(command);
+    content.push(body.len() as u8);
+    content.extend_from_slice(body);
+    let sum = content.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+    content.push(0u8.wrapping_sub(sum));
+
+    let mut frame = vec![DLE, STX];
+    for byte in content {
+        frame.push(byte);
+        if byte == DLE {
+            frame.push(DLE);
+        }
+    }
+    frame.extend_from_slice(&[DLE, ETX]);
+    frame
+}
+
+/// Message switching a freshly opened NGT-1 to forward all PGNs
+pub fn startup_message() -> Vec<u8> {
+    frame_message(NGT_MESSAGE_SEND, &NGT_RECEIVE_ALL)
+}
+
+/// Splits an NGT-1 byte stream into NMEA 2000 messages
+#[derive(Debug, Default)]
+pub struct ActisenseDecoder {
+    frame: Vec<u8>,
+    in_frame: bool,
+    escaped: bool,
+}
+
+impl ActisenseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed received bytes; returns the header and payload of every complete
+    /// NMEA 2000 message. Frames with a bad checksum are dropped.
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> Vec<(CanId, Vec<u8>)> {
+        let mut messages = Vec::new();
+        for &byte in bytes {
+            if self.escaped {
+                self.escaped = false;
+                match byte {
+                    STX => {
+                        self.frame.clear();
+                        self.in_frame = true;
+                    }
+                    ETX if self.in_frame => {
+                        self.in_frame = false;
+                        messages.extend(Self::parse_frame(&self.frame));
+                    }
+                    DLE if self.in_frame => self.frame.push(DLE),
+                    _ => self.in_frame = false,
+                }
+            } else if byte == DLE {
+                self.escaped = true;
+            } else if self.in_frame {
+                self.frame.push(byte);
+                if self.frame.len() > MAX_FRAME_LENGTH {
+                    self.in_frame = false;
+                }
+            }
+        }
+        messages
+    }
+
+    /// `<command> <length> <body> <checksum>`, checksum making the sum zero
+    fn parse_frame(frame: &[u8]) -> Option<(CanId, Vec<u8>)> {
+        let (&command, rest) = frame.split_first()?;
+        let (&length, rest) = rest.split_first()?;
+        if rest.len() != length as usize + 1 || frame.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)) != 0 {
+            return None;
+        }
+        if command != N2K_MESSAGE_RECEIVED {
+            return None;
+        }
+
+        // priority, PGN (3), destination, source, timestamp (4), data length, data
+        let body = &rest[..length as usize];
+        let header = body.get(..11)?;
+        let data = body.get(11..11 + header[10] as usize)?;
+        let id = CanId {
+            priority: header[0],
+            pgn: u32::from_le_bytes([header[1], header[2], header[3], 0]),
+            destination: header[4],
+            source: header[5],
+        };
+        Some((id, data.to_vec()))
+    }
+}
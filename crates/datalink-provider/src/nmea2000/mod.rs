@@ -0,0 +1,300 @@
+//! NMEA 2000 providers
+//!
+//! Reads the bus through a Linux SocketCAN interface (`interface=can0`) or,
+//! where SocketCAN is unavailable, through an Actisense NGT-1 USB gateway
+//! (`gateway=actisense`, `port=/dev/tty.usbserial-..`), and decodes the core
+//! navigation and engine PGNs, including J1939 engine ECUs. A `pgns` parameter such as
+//! `pgns=129029,128267` limits decoding to the listed PGNs.
+
+mod actisense;
+mod pgn;
+#[cfg(target_os = "linux")]
+mod socketcan;
+
+pub use actisense::{frame_message as actisense_frame, startup_message as actisense_startup, ActisenseDecoder, ACTISENSE_BAUD_RATE};
+pub use pgn::{
+    decode_pgn, CanId, Nmea2000Decoder, ENGINE_PGNS, PGN_ENGINE_DYNAMIC, PGN_ENGINE_RAPID, PGN_GNSS_POSITION, PGN_J1939_EEC1,
+    PGN_J1939_EFLP1, PGN_J1939_ET1, PGN_J1939_VEP1, PGN_POSITION_RAPID, PGN_WATER_DEPTH, PGN_WIND, SUPPORTED_PGNS,
+};
+
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use log::{error, info, warn};
+use datalink::{DataLinkConfig, DataLinkError, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataMessage, LinkStats};
+use crate::raw_log::{can_frame_line, hex, RawLog};
+use crate::stats::SharedStats;
+/// CAN interface used when the config names none
+pub const DEFAULT_INTERFACE: &str = "can0";
+
+/// Messages buffered before the oldest are dropped
+const MAX_QUEUED_MESSAGES: usize = 1000;
+
+/// Parse the `pgns` filter; all supported PGNs when absent
+pub fn parse_pgn_filter(config: &DataLinkConfig) -> DataLinkResult<BTreeSet<u32>> {
+    let Some(list) = config.parameters.get("pgns") else {
+        return Ok(SUPPORTED_PGNS.into_iter().collect());
+    };
+    list.split(',')
+        .map(str::trim)
+        .filter(|pgn| !pgn.is_empty())
+        .map(|pgn| match pgn.parse::<u32>() {
+            Ok(pgn) if SUPPORTED_PGNS.contains(&pgn) => Ok(pgn),
+            Ok(pgn) => Err(DataLinkError::InvalidConfig(format!("Unsupported PGN: {}", pgn))),
+            Err(_) => Err(DataLinkError::InvalidConfig(format!("Invalid PGN: {}", pgn))),
+        })
+        .collect()
+}
+
+/// How the provider reaches the NMEA 2000 bus
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Nmea2000Gateway {
+    /// Linux SocketCAN interface
+    SocketCan { interface: String },
+    /// Actisense NGT-1 on a serial port
+    Actisense { port: String, baud_rate: u32 },
+}
+
+impl Nmea2000Gateway {
+    /// Gateway from the `gateway` parameter, SocketCAN when absent
+    pub fn from_config(config: &DataLinkConfig) -> DataLinkResult<Self> {
+        match config.parameters.get("gateway").map_or("socketcan", String::as_str) {
+            "socketcan" => Ok(Self::SocketCan {
+                interface: config.parameters.get("interface").map_or(DEFAULT_INTERFACE, String::as_str).to_string(),
+            }),
+            "actisense" | "ngt1" => {
+                let port = config
+                    .parameters
+                    .get("port")
+                    .ok_or_else(|| DataLinkError::InvalidConfig("Missing port for Actisense gateway".to_string()))?
+                    .clone();
+                let baud_rate = match config.parameters.get("baud_rate") {
+                    Some(rate) => rate
+                        .parse::<u32>()
+                        .map_err(|_| DataLinkError::InvalidConfig("Invalid baud rate".to_string()))?,
+                    None => ACTISENSE_BAUD_RATE,
+                };
+                Ok(Self::Actisense { port, baud_rate })
+            }
+            other => Err(DataLinkError::InvalidConfig(format!("Unknown NMEA 2000 gateway: {}", other))),
+        }
+    }
+}
+
+/// Read timeout letting reader threads notice a disconnect
+const READ_TIMEOUT: Duration = Duration::from_millis(200);
+
+fn enqueue(queue: &Mutex<VecDeque<DataMessage>>, message: DataMessage) {
+    if let Ok(mut queue) = queue.lock() {
+        queue.push_back(message);
+        if queue.len() > MAX_QUEUED_MESSAGES {
+            queue.pop_front();
+        }
+    }
+}
+
+/// Count a PGN read from the bus; true when it is one to decode
+fn wanted_pgn(stats: &SharedStats, pgn: u32, pgns: &BTreeSet<u32>) -> bool {
+    if !SUPPORTED_PGNS.contains(&pgn) {
+        stats.update(|stats| stats.record_unsupported(&format!("PGN {}", pgn)));
+        return false;
+    }
+    stats.update(|stats| stats.lines_read += 1);
+    pgns.contains(&pgn)
+}
+
+/// NMEA 2000 provider reading a SocketCAN interface or an Actisense gateway
+pub struct Nmea2000DataLinkProvider {
+    status: DataLinkStatus,
+    message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
+    running: Arc<AtomicBool>,
+    reader: Option<std::thread::JoinHandle<()>>,
+    stats: SharedStats,
+}
+
+impl Nmea2000DataLinkProvider {
+    pub fn new() -> Self {
+        Self {
+            status: DataLinkStatus::Disconnected,
+            message_queue: Arc::new(Mutex::new(VecDeque::new())),
+            running: Arc::new(AtomicBool::new(false)),
+            reader: None,
+            stats: SharedStats::default(),
+        }
+    }
+
+    fn start_reader(&mut self, gateway: &Nmea2000Gateway, pgns: BTreeSet<u32>) -> DataLinkResult<()> {
+        let handle = match gateway {
+            Nmea2000Gateway::SocketCan { interface } => self.spawn_socketcan_reader(interface, pgns)?,
+            Nmea2000Gateway::Actisense { port, baud_rate } => self.spawn_actisense_reader(port, *baud_rate, pgns)?,
+        };
+        self.reader = Some(handle);
+        Ok(())
+    }
+
+    /// Stop the reader thread and wait for it to let go of the device
+    fn stop_reader(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn spawn_socketcan_reader(&self, interface: &str, pgns: BTreeSet<u32>) -> DataLinkResult<std::thread::JoinHandle<()>> {
+        let socket = socketcan::CanSocket::open(interface)
+            .and_then(|socket| socket.set_read_timeout(READ_TIMEOUT).map(|()| socket))
+            .map_err(|e| DataLinkError::ConnectionFailed(format!("{}: {}", interface, e)))?;
+
+        let queue = Arc::clone(&self.message_queue);
+        let stats = self.stats.clone();
+        let running = Arc::clone(&self.running);
+        running.store(true, Ordering::Relaxed);
+        std::thread::Builder::new()
+            .name(format!("nmea2000 {}", interface))
+            .spawn(move || {
+                let mut decoder = Nmea2000Decoder::new();
+                while running.load(Ordering::Relaxed) {
+                    let frame = match socket.read_frame() {
+                        Ok(Some(frame)) => frame,
+                        Ok(None) => continue,
+                        Err(e) => {
+                            error!("NMEA 2000 read error: {}", e);
+                            break;
+                        }
+                    };
+                    stats.log_raw(&can_frame_line(frame.id, &frame.data));
+                    if !wanted_pgn(&stats, CanId::parse(frame.id).pgn, &pgns) {
+                        continue;
+                    }
+                    if let Some(message) = decoder.push_frame(frame.id, &frame.data, SystemTime::now()) {
+                        stats.update(|stats| stats.sentences_parsed += 1);
+                        enqueue(&queue, message);
+                    }
+                }
+                running.store(false, Ordering::Relaxed);
+            })
+            .map_err(|e| DataLinkError::ConnectionFailed(format!("Failed to start reader: {}", e)))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn spawn_socketcan_reader(&self, _interface: &str, _pgns: BTreeSet<u32>) -> DataLinkResult<std::thread::JoinHandle<()>> {
+        Err(DataLinkError::ConnectionFailed(
+            "SocketCAN is only available on Linux; use gateway=actisense".to_string(),
+        ))
+    }
+
+    fn spawn_actisense_reader(&self, port: &str, baud_rate: u32, pgns: BTreeSet<u32>) -> DataLinkResult<std::thread::JoinHandle<()>> {
+        use std::io::{ErrorKind, Read, Write};
+
+        let mut serial = tokio_serial::new(port, baud_rate)
+            .timeout(READ_TIMEOUT)
+            .open()
+            .map_err(|e| DataLinkError::ConnectionFailed(format!("{}: {}", port, e)))?;
+        serial
+            .write_all(&actisense::startup_message())
+            .map_err(|e| DataLinkError::ConnectionFailed(format!("Failed to configure NGT-1 on {}: {}", port, e)))?;
+
+        let queue = Arc::clone(&self.message_queue);
+        let stats = self.stats.clone();
+        let running = Arc::clone(&self.running);
+        running.store(true, Ordering::Relaxed);
+        std::thread::Builder::new()
+            .name(format!("nmea2000 {}", port))
+            .spawn(move || {
+                let mut decoder = ActisenseDecoder::new();
+                let mut buffer = [0u8; 512];
+                while running.load(Ordering::Relaxed) {
+                    let read = match serial.read(&mut buffer) {
+                        Ok(0) => {
+                            warn!("NGT-1 serial port closed");
+                            break;
+                        }
+                        Ok(read) => read,
+                        Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::Interrupted) => continue,
+                        Err(e) => {
+                            error!("NGT-1 read error: {}", e);
+                            break;
+                        }
+                    };
+                    stats.log_raw(&hex(&buffer[..read]));
+                    for (id, data) in decoder.push_bytes(&buffer[..read]) {
+                        if !wanted_pgn(&stats, id.pgn, &pgns) {
+                            continue;
+                        }
+                        if let Some(message) = decode_pgn(id, &data) {
+                            stats.update(|stats| stats.sentences_parsed += 1);
+                            enqueue(&queue, message.with_received_at(SystemTime::now()));
+                        }
+                    }
+                }
+                running.store(false, Ordering::Relaxed);
+            })
+            .map_err(|e| DataLinkError::ConnectionFailed(format!("Failed to start reader: {}", e)))
+    }
+}
+
+impl Default for Nmea2000DataLinkProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DataLinkReceiver for Nmea2000DataLinkProvider {
+    fn status(&self) -> DataLinkStatus {
+        if matches!(self.status, DataLinkStatus::Connected) && !self.running.load(Ordering::Relaxed) {
+            return DataLinkStatus::Error("NMEA 2000 reader stopped".to_string());
+        }
+        self.status.clone()
+    }
+
+    fn receive_message(&mut self) -> DataLinkResult<Option<DataMessage>> {
+        match self.message_queue.lock() {
+            Ok(mut queue) => Ok(queue.pop_front()),
+            Err(_) => Err(DataLinkError::TransportError("Failed to access message queue".to_string())),
+        }
+    }
+
+    fn stats(&self) -> LinkStats {
+        self.stats.get()
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        let gateway = Nmea2000Gateway::from_config(config)?;
+        let pgns = parse_pgn_filter(config)?;
+        let raw_log = RawLog::from_config(config)?;
+        info!("Connecting NMEA 2000 provider through {:?} for PGNs {:?}", gateway, pgns);
+        // The gateway is released before it is opened again
+        self.stop_reader();
+        self.stats.set_raw_log(raw_log);
+
+        self.status = DataLinkStatus::Connecting;
+        match self.start_reader(&gateway, pgns) {
+            Ok(()) => {
+                self.status = DataLinkStatus::Connected;
+                Ok(())
+            }
+            Err(e) => {
+                self.status = DataLinkStatus::Error(e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    /// Reopen the gateway with new settings; queued PGNs and counters stay
+    fn reconfigure(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        DataLinkReceiver::connect(self, config)
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        info!("Disconnecting NMEA 2000 provider");
+        self.stop_reader();
+        self.stats.set_raw_log(None);
+        if let Ok(mut queue) = self.message_queue.lock() {
+            queue.clear();
+        }
+        self.status = DataLinkStatus::Disconnected;
+        Ok(())
+    }
+}
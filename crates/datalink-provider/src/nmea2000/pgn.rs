@@ -0,0 +1,336 @@
+//! NMEA 2000 frame decoding
+//!
+//! Splits 29-bit CAN identifiers into priority, PGN, source and destination,
+//! joins fast-packet PGNs that span several frames, and decodes the core
+//! navigation and engine PGNs into `DataMessage`s. Engine ECUs speaking plain
+//! SAE J1939 on the same bus are decoded too, since J1939 shares the
+//! identifier layout. Fields holding the "not available" value of their type
+//! are left out of the message.
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use datalink::{DataMessage, Value};
+
+/// GNSS Position Data (fast packet)
+pub const PGN_GNSS_POSITION: u32 = 129029;
+/// Position, Rapid Update
+pub const PGN_POSITION_RAPID: u32 = 129025;
+/// Water Depth
+pub const PGN_WATER_DEPTH: u32 = 128267;
+/// Wind Data
+pub const PGN_WIND: u32 = 130306;
+/// Engine Parameters, Rapid Update
+pub const PGN_ENGINE_RAPID: u32 = 127488;
+/// Engine Parameters, Dynamic (fast packet)
+pub const PGN_ENGINE_DYNAMIC: u32 = 127489;
+/// J1939 Electronic Engine Controller 1, carrying engine speed
+pub const PGN_J1939_EEC1: u32 = 61444;
+/// J1939 Engine Temperature 1
+pub const PGN_J1939_ET1: u32 = 65262;
+/// J1939 Engine Fluid Level/Pressure 1
+pub const PGN_J1939_EFLP1: u32 = 65263;
+/// J1939 Vehicle Electrical Power 1
+pub const PGN_J1939_VEP1: u32 = 65271;
+
+/// Engine PGNs, NMEA 2000 and J1939
+pub const ENGINE_PGNS: [u32; 6] =
+    [PGN_ENGINE_RAPID, PGN_ENGINE_DYNAMIC, PGN_J1939_EEC1, PGN_J1939_ET1, PGN_J1939_EFLP1, PGN_J1939_VEP1];
+
+/// PGNs this module decodes
+pub const SUPPORTED_PGNS: [u32; 10] = [
+    PGN_GNSS_POSITION,
+    PGN_POSITION_RAPID,
+    PGN_WATER_DEPTH,
+    PGN_WIND,
+    PGN_ENGINE_RAPID,
+    PGN_ENGINE_DYNAMIC,
+    PGN_J1939_EEC1,
+    PGN_J1939_ET1,
+    PGN_J1939_EFLP1,
+    PGN_J1939_VEP1,
+];
+
+/// Kelvin at 0 degrees Celsius
+const KELVIN_OFFSET: f64 = 273.15;
+
+/// Knots per meter per second
+const KNOTS_PER_MPS: f64 = 1.943_844;
+
+/// Fast-packet transfers older than this are dropped
+const FAST_PACKET_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Fields of a 29-bit NMEA 2000 CAN identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CanId {
+    pub priority: u8,
+    pub pgn: u32,
+    pub source: u8,
+    /// 255 for broadcast (PDU2) PGNs
+    pub destination: u8,
+}
+
+impl CanId {
+    pub fn parse(id: u32) -> Self {
+        let pdu_format = (id >> 16) & 0xFF;
+        let pdu_specific = ((id >> 8) & 0xFF) as u8;
+        let (pgn, destination) = if pdu_format < 240 {
+            ((id >> 8) & 0x3_FF00, pdu_specific)
+        } else {
+            ((id >> 8) & 0x3_FFFF, 0xFF)
+        };
+        Self {
+            priority: ((id >> 26) & 0x7) as u8,
+            pgn,
+            source: (id & 0xFF) as u8,
+            destination,
+        }
+    }
+}
+
+/// Whether a PGN is sent as a fast packet
+fn is_fast_packet(pgn: u32) -> bool {
+    matches!(pgn, PGN_GNSS_POSITION | PGN_ENGINE_DYNAMIC)
+}
+
+fn u8_at(data: &[u8], offset: usize) -> Option<u8> {
+    data.get(offset).copied().filter(|value| *value != 0xFF)
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    let value = u16::from_le_bytes(data.get(offset..offset + 2)?.try_into().ok()?);
+    (value < 0xFFFD).then_some(value)
+}
+
+fn i16_at(data: &[u8], offset: usize) -> Option<i16> {
+    let value = i16::from_le_bytes(data.get(offset..offset + 2)?.try_into().ok()?);
+    (value < 0x7FFD).then_some(value)
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    let value = u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?);
+    (value < 0xFFFF_FFFD).then_some(value)
+}
+
+fn i32_at(data: &[u8], offset: usize) -> Option<i32> {
+    let value = i32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?);
+    (value < 0x7FFF_FFFD).then_some(value)
+}
+
+fn i64_at(data: &[u8], offset: usize) -> Option<i64> {
+    let value = i64::from_le_bytes(data.get(offset..offset + 8)?.try_into().ok()?);
+    (value < 0x7FFF_FFFF_FFFF_FFFD).then_some(value)
+}
+
+/// J1939 byte parameter; 0xFB and up flag errors or missing values
+fn j1939_u8_at(data: &[u8], offset: usize) -> Option<u8> {
+    data.get(offset).copied().filter(|value| *value <= 0xFA)
+}
+
+/// J1939 two-byte parameter; 0xFB00 and up flag errors or missing values
+fn j1939_u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    let value = u16::from_le_bytes(data.get(offset..offset + 2)?.try_into().ok()?);
+    (value <= 0xFAFF).then_some(value)
+}
+
+fn message(message_type: &str, id: CanId, data: &[u8]) -> DataMessage {
+    DataMessage::new(message_type.to_string(), format!("N2K:{}", id.source), data.to_vec())
+        .with_data("pgn", id.pgn)
+        .with_data("source_address", id.source)
+}
+
+/// Decode a complete PGN payload; `None` for unsupported PGNs or payloads
+/// without usable data
+pub fn decode_pgn(id: CanId, data: &[u8]) -> Option<DataMessage> {
+    match id.pgn {
+        PGN_POSITION_RAPID => {
+            let latitude = i32_at(data, 0)? as f64 * 1e-7;
+            let longitude = i32_at(data, 4)? as f64 * 1e-7;
+            Some(
+                message("GPS_POSITION", id, data)
+                    .with_data("latitude", latitude)
+                    .with_data("longitude", longitude)
+                    .with_data("position", Value::LatLon { lat: latitude, lon: longitude }),
+            )
+        }
+        PGN_GNSS_POSITION => {
+            let latitude = i64_at(data, 7)? as f64 * 1e-16;
+            let longitude = i64_at(data, 15)? as f64 * 1e-16;
+            let mut gnss = message("GPS_POSITION", id, data)
+                .with_data("latitude", latitude)
+                .with_data("longitude", longitude)
+                .with_data("position", Value::LatLon { lat: latitude, lon: longitude });
+            if let Some(altitude) = i64_at(data, 23) {
+                gnss = gnss.with_data("altitude", altitude as f64 * 1e-6);
+            }
+            if let Some(method) = data.get(31).map(|byte| byte >> 4) {
+                gnss = gnss.with_data("fix_quality", method);
+            }
+            if let Some(satellites) = u8_at(data, 33) {
+                gnss = gnss.with_data("satellites", satellites);
+            }
+            if let Some(hdop) = i16_at(data, 34) {
+                gnss = gnss.with_data("hdop", hdop as f64 * 0.01);
+            }
+            if let (Some(days), Some(time)) = (u16_at(data, 1), u32_at(data, 3)) {
+                let seconds = days as f64 * 86_400.0 + time as f64 * 1e-4;
+                gnss = gnss.with_measured_at(UNIX_EPOCH + Duration::from_secs_f64(seconds));
+            }
+            Some(gnss)
+        }
+        PGN_WATER_DEPTH => {
+            let depth = u32_at(data, 1)? as f64 * 0.01;
+            let mut depth_message = message("DEPTH", id, data).with_data("depth_m", depth);
+            if let Some(offset) = i16_at(data, 5) {
+                depth_message = depth_message.with_data("offset_m", offset as f64 * 0.001);
+            }
+            Some(depth_message)
+        }
+        PGN_WIND => {
+            let speed_kts = u16_at(data, 1)? as f64 * 0.01 * KNOTS_PER_MPS;
+            let angle = (u16_at(data, 3)? as f64 * 1e-4).to_degrees();
+            // 0 and 1 give the direction the wind blows from, true or magnetic;
+            // 2 is apparent; 3 and 4 are true wind relative to the bow
+            let wind = match data.get(5)? & 0x07 {
+                2 => message("WIND", id, data)
+                    .with_data("apparent_wind_speed", speed_kts)
+                    .with_data("apparent_wind_angle", Value::Angle(angle))
+                    .with_data("reference", "R"),
+                0 => message("WIND", id, data)
+                    .with_data("true_wind_speed", speed_kts)
+                    .with_data("wind_direction_true", Value::Angle(angle)),
+                1 => message("WIND", id, data)
+                    .with_data("true_wind_speed", speed_kts)
+                    .with_data("wind_direction_magnetic", Value::Angle(angle)),
+                _ => message("WIND", id, data)
+                    .with_data("true_wind_speed", speed_kts)
+                    .with_data("true_wind_angle", Value::Angle(angle))
+                    .with_data("reference", "T"),
+            };
+            Some(wind)
+        }
+        PGN_ENGINE_RAPID => {
+            let mut engine = message("ENGINE", id, data).with_data("engine_instance", *data.first()?);
+            if let Some(speed) = u16_at(data, 1) {
+                engine = engine.with_data("rpm", speed as f64 * 0.25);
+            }
+            if let Some(boost) = u16_at(data, 3) {
+                engine = engine.with_data("boost_pressure_pa", boost as f64 * 100.0);
+            }
+            if let Some(tilt) = data.get(5).map(|tilt| *tilt as i8).filter(|tilt| *tilt != 0x7F) {
+                engine = engine.with_data("tilt_trim", tilt as i64);
+            }
+            Some(engine)
+        }
+        PGN_ENGINE_DYNAMIC => {
+            let mut engine = message("ENGINE", id, data).with_data("engine_instance", *data.first()?);
+            if let Some(oil_pressure) = u16_at(data, 1) {
+                engine = engine.with_data("oil_pressure_kpa", oil_pressure as f64 * 0.1);
+            }
+            if let Some(coolant) = u16_at(data, 5) {
+                engine = engine.with_data("coolant_temp_c", coolant as f64 * 0.01 - KELVIN_OFFSET);
+            }
+            if let Some(alternator) = i16_at(data, 7) {
+                engine = engine.with_data("alternator_voltage", alternator as f64 * 0.01);
+            }
+            Some(engine)
+        }
+        // J1939 engines are told apart by source address: 0 is the first engine
+        PGN_J1939_EEC1 => {
+            let speed = j1939_u16_at(data, 3)?;
+            Some(message("ENGINE", id, data).with_data("engine_instance", id.source).with_data("rpm", speed as f64 * 0.125))
+        }
+        PGN_J1939_ET1 => {
+            let coolant = j1939_u8_at(data, 0)?;
+            Some(
+                message("ENGINE", id, data)
+                    .with_data("engine_instance", id.source)
+                    .with_data("coolant_temp_c", coolant as f64 - 40.0),
+            )
+        }
+        PGN_J1939_EFLP1 => {
+            let oil_pressure = j1939_u8_at(data, 3)?;
+            Some(
+                message("ENGINE", id, data)
+                    .with_data("engine_instance", id.source)
+                    .with_data("oil_pressure_kpa", oil_pressure as f64 * 4.0),
+            )
+        }
+        PGN_J1939_VEP1 => {
+            let alternator = j1939_u16_at(data, 2)?;
+            Some(
+                message("ENGINE", id, data)
+                    .with_data("engine_instance", id.source)
+                    .with_data("alternator_voltage", alternator as f64 * 0.05),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// A fast-packet transfer being joined
+#[derive(Debug)]
+struct FastPacket {
+    length: usize,
+    data: Vec<u8>,
+    next_frame: u8,
+    started: SystemTime,
+}
+
+/// Turns CAN frames into decoded messages, joining fast packets
+#[derive(Debug, Default)]
+pub struct Nmea2000Decoder {
+    /// Transfers in progress keyed by source, PGN and sequence counter
+    fast_packets: HashMap<(u8, u32, u8), FastPacket>,
+}
+
+impl Nmea2000Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a received frame; returns a message once a supported PGN is complete
+    pub fn push_frame(&mut self, can_id: u32, data: &[u8], received_at: SystemTime) -> Option<DataMessage> {
+        let id = CanId::parse(can_id);
+        if !SUPPORTED_PGNS.contains(&id.pgn) {
+            return None;
+        }
+        if !is_fast_packet(id.pgn) {
+            return decode_pgn(id, data).map(|message| message.with_received_at(received_at));
+        }
+
+        let header = *data.first()?;
+        let key = (id.source, id.pgn, header >> 5);
+        let frame = header & 0x1F;
+        self.fast_packets
+            .retain(|_, packet| received_at.duration_since(packet.started).map_or(true, |age| age <= FAST_PACKET_TIMEOUT));
+
+        if frame == 0 {
+            let length = *data.get(1)? as usize;
+            let mut packet = FastPacket {
+                length,
+                data: Vec::with_capacity(length),
+                next_frame: 1,
+                started: received_at,
+            };
+            packet.data.extend_from_slice(data.get(2..)?);
+            self.fast_packets.insert(key, packet);
+        } else {
+            let packet = self.fast_packets.get_mut(&key)?;
+            if frame != packet.next_frame {
+                // A lost frame spoils the whole transfer
+                self.fast_packets.remove(&key);
+                return None;
+            }
+            packet.data.extend_from_slice(data.get(1..)?);
+            packet.next_frame += 1;
+        }
+
+        let packet = self.fast_packets.get(&key)?;
+        if packet.data.len() < packet.length {
+            return None;
+        }
+        let mut packet = self.fast_packets.remove(&key)?;
+        packet.data.truncate(packet.length);
+        decode_pgn(id, &packet.data).map(|message| message.with_received_at(received_at))
+    }
+}
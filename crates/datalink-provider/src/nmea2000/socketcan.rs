@@ -0,0 +1,109 @@
+//! Minimal raw SocketCAN socket for reading extended CAN frames
+
+use std::ffi::CString;
+use std::io;
+use std::mem;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::time::Duration;
+
+/// A received extended-format CAN frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanFrame {
+    /// 29-bit identifier without flag bits
+    pub id: u32,
+    pub data: Vec<u8>,
+}
+
+/// A `CAN_RAW` socket bound to one interface
+pub struct CanSocket {
+    fd: OwnedFd,
+}
+
+impl CanSocket {
+    /// Open a raw CAN socket on `interface`, e.g. `can0`
+    pub fn open(interface: &str) -> io::Result<Self> {
+        let name = CString::new(interface).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        // SAFETY: `name` is a valid NUL-terminated string
+        let index = unsafe { libc::if_nametoindex(name.as_ptr()) };
+        if index == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SAFETY: plain socket(2) call; the result is checked before use
+        let raw = unsafe { libc::socket(libc::PF_CAN, libc::SOCK_RAW | libc::SOCK_CLOEXEC, libc::CAN_RAW) };
+        if raw < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `raw` is a freshly created descriptor owned by nothing else
+        let fd = unsafe { OwnedFd::from_raw_fd(raw) };
+
+        // SAFETY: sockaddr_can is plain data for which all zeroes is valid
+        let mut address: libc::sockaddr_can = unsafe { mem::zeroed() };
+        address.can_family = libc::AF_CAN as libc::sa_family_t;
+        address.can_ifindex = index as libc::c_int;
+        // SAFETY: `address` is a valid sockaddr_can and the length matches it
+        let result = unsafe {
+            libc::bind(
+                fd.as_raw_fd(),
+                &address as *const libc::sockaddr_can as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_can>() as libc::socklen_t,
+            )
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { fd })
+    }
+
+    /// Make [`read_frame`](Self::read_frame) give up after `timeout`
+    pub fn set_read_timeout(&self, timeout: Duration) -> io::Result<()> {
+        let timeval = libc::timeval {
+            tv_sec: timeout.as_secs() as libc::time_t,
+            tv_usec: timeout.subsec_micros() as libc::suseconds_t,
+        };
+        // SAFETY: `timeval` is valid for the duration of the call and the length matches it
+        let result = unsafe {
+            libc::setsockopt(
+                self.fd.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &timeval as *const libc::timeval as *const libc::c_void,
+                mem::size_of::<libc::timeval>() as libc::socklen_t,
+            )
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Read the next frame. Returns `None` on timeout and for standard,
+    /// remote and error frames, which NMEA 2000 does not use.
+    pub fn read_frame(&self) -> io::Result<Option<CanFrame>> {
+        // SAFETY: can_frame is plain data for which all zeroes is valid
+        let mut frame: libc::can_frame = unsafe { mem::zeroed() };
+        // SAFETY: the buffer is a can_frame of exactly the length passed
+        let read = unsafe {
+            libc::read(
+                self.fd.as_raw_fd(),
+                &mut frame as *mut libc::can_frame as *mut libc::c_void,
+                mem::size_of::<libc::can_frame>(),
+            )
+        };
+        if read < 0 {
+            let error = io::Error::last_os_error();
+            return match error.kind() {
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut | io::ErrorKind::Interrupted => Ok(None),
+                _ => Err(error),
+            };
+        }
+        if frame.can_id & libc::CAN_EFF_FLAG == 0 || frame.can_id & (libc::CAN_RTR_FLAG | libc::CAN_ERR_FLAG) != 0 {
+            return Ok(None);
+        }
+        let length = (frame.can_dlc as usize).min(frame.data.len());
+        Ok(Some(CanFrame {
+            id: frame.can_id & libc::CAN_EFF_MASK,
+            data: frame.data[..length].to_vec(),
+        }))
+    }
+}
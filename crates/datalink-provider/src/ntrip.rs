@@ -0,0 +1,241 @@
+//! RTCM3 corrections from an NTRIP caster
+//!
+//! RTK-capable receivers reach centimeter fixes when fed RTCM3 correction
+//! frames from a base station. NTRIP casters serve those frames over TCP:
+//! the client asks for a mountpoint with an HTTP-style GET and the caster
+//! answers `ICY 200 OK` followed by the raw stream. [`RtcmFramer`] cuts the
+//! stream into checked frames so a dropped byte never reaches the receiver
+//! as a corrupt message.
+//!
+//! The GPS provider starts a client when connected with `ntrip_host` and
+//! `ntrip_mountpoint`; `ntrip_port` defaults to 2101 and `ntrip_username` /
+//! `ntrip_password` add basic authentication. The password can be given as
+//! `credential:<name>`. Mountpoints that need the rover position (VRS) are
+//! not supported.
+
+use log::{info, warn};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use datalink::{DataLinkConfig, DataLinkError, DataLinkResult, DataLinkStatus, DataMessage};
+use crate::reconnect::{ReceiverResult, SharedStatus};
+
+/// Message type carrying RTCM3 frames to the GPS transmitter
+pub const RTCM_MESSAGE_TYPE: &str = "RTCM";
+
+/// Registered NTRIP port
+pub const DEFAULT_NTRIP_PORT: u16 = 2101;
+
+/// First byte of every RTCM3 frame
+pub const RTCM3_PREAMBLE: u8 = 0xd3;
+
+const RTCM3_HEADER_LEN: usize = 3;
+const RTCM3_CRC_LEN: usize = 3;
+
+/// Frames are never longer than this, so a stream that never syncs cannot
+/// grow the buffer without bound
+const MAX_BUFFERED: usize = 4 * (RTCM3_HEADER_LEN + 1023 + RTCM3_CRC_LEN);
+
+/// CRC-24Q as used by RTCM3 and SBAS
+pub fn crc24q(data: &[u8]) -> u32 {
+    let mut crc = 0u32;
+    for &byte in data {
+        crc ^= (byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x0100_0000 != 0 {
+                crc ^= 0x0186_4cfb;
+            }
+        }
+    }
+    crc & 0x00ff_ffff
+}
+
+/// Message number of a complete RTCM3 frame, e.g. 1005 or 1077
+pub fn rtcm_message_number(frame: &[u8]) -> Option<u16> {
+    let payload = frame.get(RTCM3_HEADER_LEN..RTCM3_HEADER_LEN + 2)?;
+    Some((payload[0] as u16) << 4 | (payload[1] as u16) >> 4)
+}
+
+/// Wrap RTCM3 frames for [`crate::GpsDataLinkProvider`]'s transmitter
+pub fn rtcm_message(frames: Vec<u8>) -> DataMessage {
+    DataMessage::new(RTCM_MESSAGE_TYPE.to_string(), "NTRIP".to_string(), frames)
+}
+
+/// Splits a byte stream into CRC-checked RTCM3 frames
+#[derive(Debug, Default)]
+pub struct RtcmFramer {
+    buffer: Vec<u8>,
+}
+
+impl RtcmFramer {
+    /// Add received bytes and take every frame completed by them
+    pub fn push(&mut self, data: &[u8]) -> Vec<Vec<u8>> {
+        self.buffer.extend_from_slice(data);
+        let mut frames = Vec::new();
+        loop {
+            let Some(start) = self.buffer.iter().position(|&byte| byte == RTCM3_PREAMBLE) else {
+                self.buffer.clear();
+                break;
+            };
+            self.buffer.drain(..start);
+            if self.buffer.len() < RTCM3_HEADER_LEN {
+                break;
+            }
+            // Six reserved bits must be zero, otherwise this was not a preamble
+            if self.buffer[1] & 0xfc != 0 {
+                self.buffer.drain(..1);
+                continue;
+            }
+            let length = ((self.buffer[1] as usize & 0x03) << 8) | self.buffer[2] as usize;
+            let frame_len = RTCM3_HEADER_LEN + length + RTCM3_CRC_LEN;
+            if self.buffer.len() < frame_len {
+                break;
+            }
+            let crc_at = RTCM3_HEADER_LEN + length;
+            let crc = u32::from_be_bytes([0, self.buffer[crc_at], self.buffer[crc_at + 1], self.buffer[crc_at + 2]]);
+            if crc24q(&self.buffer[..crc_at]) == crc {
+                frames.push(self.buffer.drain(..frame_len).collect());
+            } else {
+                self.buffer.drain(..1);
+            }
+        }
+        if self.buffer.len() > MAX_BUFFERED {
+            self.buffer.clear();
+        }
+        frames
+    }
+}
+
+/// Where to fetch corrections from
+#[derive(Debug, Clone, PartialEq)]
+pub struct NtripConfig {
+    pub host: String,
+    pub port: u16,
+    pub mountpoint: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl NtripConfig {
+    /// Read the `ntrip_*` parameters; `None` when no caster is configured
+    pub fn from_config(config: &DataLinkConfig) -> DataLinkResult<Option<Self>> {
+        let param = |key: &str| config.parameters.get(key).cloned();
+        let Some(host) = param("ntrip_host") else {
+            return Ok(None);
+        };
+        let port = match param("ntrip_port") {
+            Some(port) => port
+                .parse()
+                .map_err(|_| DataLinkError::InvalidConfig("Invalid ntrip_port".to_string()))?,
+            None => DEFAULT_NTRIP_PORT,
+        };
+        let mountpoint = param("ntrip_mountpoint")
+            .map(|mountpoint| mountpoint.trim_start_matches('/').to_string())
+            .filter(|mountpoint| !mountpoint.is_empty())
+            .ok_or_else(|| DataLinkError::InvalidConfig("Missing ntrip_mountpoint".to_string()))?;
+        Ok(Some(Self {
+            host,
+            port,
+            mountpoint,
+            username: param("ntrip_username"),
+            password: param("ntrip_password"),
+        }))
+    }
+
+    /// NTRIP 1.0 request for the mountpoint. Version 1 keeps the response
+    /// free of HTTP chunking, which every caster still accepts.
+    pub fn request(&self) -> String {
+        let mut request = format!(
+            "GET /{} HTTP/1.0\r\nUser-Agent: NTRIP yachtpit/{}\r\nAccept: */*\r\n",
+            self.mountpoint,
+            env!("CARGO_PKG_VERSION")
+        );
+        if let Some(username) = &self.username {
+            let credentials = format!("{}:{}", username, self.password.as_deref().unwrap_or_default());
+            request.push_str(&format!("Authorization: Basic {}\r\n", base64(credentials.as_bytes())));
+        }
+        request.push_str("\r\n");
+        request
+    }
+}
+
+/// Connect to the caster and forward each correction frame to `corrections`
+/// until shutdown or until the caster hangs up
+pub async fn stream_corrections(
+    config: &NtripConfig,
+    corrections: &mpsc::UnboundedSender<Vec<u8>>,
+    shutdown_rx: &mut mpsc::Receiver<()>,
+    status: &SharedStatus,
+) -> ReceiverResult {
+    info!("Requesting RTCM corrections from {}:{}/{}", config.host, config.port, config.mountpoint);
+
+    let mut stream = TcpStream::connect((config.host.as_str(), config.port)).await?;
+    stream.write_all(config.request().as_bytes()).await?;
+    let mut reader = BufReader::new(stream);
+
+    let mut response = String::new();
+    reader.read_line(&mut response).await?;
+    let response = response.trim();
+    if response.starts_with("SOURCETABLE") {
+        return Err(format!("NTRIP mountpoint {} not found on caster", config.mountpoint).into());
+    }
+    if !(response == "ICY 200 OK" || response.starts_with("HTTP/") && response.split_whitespace().nth(1) == Some("200")) {
+        return Err(format!("NTRIP caster refused the request: {}", response).into());
+    }
+    // HTTP answers carry headers; ICY answers usually end with a blank line too
+    if response.starts_with("HTTP/") {
+        let mut header = String::new();
+        loop {
+            header.clear();
+            if reader.read_line(&mut header).await? == 0 || header.trim().is_empty() {
+                break;
+            }
+        }
+    }
+    status.set(DataLinkStatus::Connected);
+    info!("NTRIP stream {} connected", config.mountpoint);
+
+    let mut framer = RtcmFramer::default();
+    let mut buf = [0u8; 2048];
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.recv() => {
+                info!("NTRIP client shutdown requested");
+                break;
+            }
+            result = reader.read(&mut buf) => {
+                let len = result?;
+                if len == 0 {
+                    return Err("NTRIP caster closed the connection".into());
+                }
+                for frame in framer.push(&buf[..len]) {
+                    if corrections.send(frame).is_err() {
+                        warn!("GPS receiver gone, dropping RTCM corrections");
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Standard base64 with padding, for the authorization header
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let bits = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
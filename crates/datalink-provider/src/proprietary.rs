@@ -0,0 +1,125 @@
+//! Parsers for proprietary and other unsupported sentences
+//!
+//! Receivers mix manufacturer sentences (`$PGRME` position error from
+//! Garmin, `$PSRF` from SiRF, `$PMTK` from MediaTek) in with the standard
+//! ones. Applications can decode them by installing a parser for a sentence
+//! prefix in [`SentenceParsers::global`]. Every provider offers the lines its
+//! own parser turned down to the registry, so installed parsers work on any
+//! link without changes to the providers. The longest matching prefix wins.
+//!
+//! Lines with a checksum are only offered when it matches.
+
+use std::sync::{Arc, OnceLock, RwLock, PoisonError};
+use std::time::SystemTime;
+use datalink::DataMessage;
+use crate::nmea;
+
+/// A sentence offered to a custom parser
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomSentence<'a> {
+    /// Address without the start delimiter, e.g. `PGRME`
+    pub address: &'a str,
+    /// Fields after the address, checksum removed
+    pub fields: Vec<&'a str>,
+    /// The whole line as received
+    pub sentence: &'a str,
+    pub received_at: SystemTime,
+}
+
+impl CustomSentence<'_> {
+    /// Field `index` after the address; empty fields are `None`
+    pub fn field(&self, index: usize) -> Option<&str> {
+        self.fields.get(index).copied().filter(|field| !field.is_empty())
+    }
+
+    /// Field `index` as a number
+    pub fn number(&self, index: usize) -> Option<f64> {
+        self.field(index)?.parse().ok()
+    }
+
+    /// A message of `message_type` carrying the sentence, to add parsed data to
+    pub fn message(&self, message_type: &str) -> DataMessage {
+        DataMessage::new(message_type.to_string(), "PROPRIETARY".to_string(), self.sentence.as_bytes().to_vec())
+            .with_received_at(self.received_at)
+            .with_data("sentence_type", format!("{}{}", &self.sentence[..1], self.address))
+    }
+}
+
+/// Callback decoding a sentence, or declining it with `None`
+pub type SentenceParser = Arc<dyn Fn(&CustomSentence) -> Option<DataMessage> + Send + Sync>;
+
+/// Custom sentence parsers keyed on address prefix
+#[derive(Default)]
+pub struct SentenceParsers {
+    parsers: RwLock<Vec<(String, SentenceParser)>>,
+}
+
+impl SentenceParsers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The registry every provider consults
+    pub fn global() -> &'static SentenceParsers {
+        static PARSERS: OnceLock<SentenceParsers> = OnceLock::new();
+        PARSERS.get_or_init(SentenceParsers::new)
+    }
+
+    /// Install `parser` for addresses starting with `prefix`, e.g. `PGRM`,
+    /// replacing any parser already installed for that prefix
+    pub fn register(&self, prefix: &str, parser: impl Fn(&CustomSentence) -> Option<DataMessage> + Send + Sync + 'static) {
+        let mut parsers = self.parsers.write().unwrap_or_else(PoisonError::into_inner);
+        parsers.retain(|(installed, _)| installed != prefix);
+        parsers.push((prefix.to_string(), Arc::new(parser)));
+        // Longest prefix first, so the most specific parser is tried first
+        parsers.sort_by_key(|(installed, _)| std::cmp::Reverse(installed.len()));
+    }
+
+    /// Remove the parser for `prefix`; returns whether there was one
+    pub fn unregister(&self, prefix: &str) -> bool {
+        let mut parsers = self.parsers.write().unwrap_or_else(PoisonError::into_inner);
+        let count = parsers.len();
+        parsers.retain(|(installed, _)| installed != prefix);
+        parsers.len() != count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parsers.read().unwrap_or_else(PoisonError::into_inner).is_empty()
+    }
+
+    /// Decode `line` with the parser for its address, if one is installed
+    pub fn parse(&self, line: &str, received_at: SystemTime) -> Option<DataMessage> {
+        let line = line.trim();
+        let body = line.strip_prefix('$').or_else(|| line.strip_prefix('!'))?;
+        let body = match body.rsplit_once('*') {
+            Some((body, sum)) => {
+                if u8::from_str_radix(sum, 16).ok()? != nmea::checksum(body) {
+                    return None;
+                }
+                body
+            }
+            None => body,
+        };
+        let mut fields = body.split(',');
+        let address = fields.next()?;
+
+        let parser = {
+            let parsers = self.parsers.read().unwrap_or_else(PoisonError::into_inner);
+            let (_, parser) = parsers.iter().find(|(prefix, _)| address.starts_with(prefix.as_str()))?;
+            Arc::clone(parser)
+        };
+        parser(&CustomSentence {
+            address,
+            fields: fields.collect(),
+            sentence: line,
+            received_at,
+        })
+    }
+}
+
+impl std::fmt::Debug for SentenceParsers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parsers = self.parsers.read().unwrap_or_else(PoisonError::into_inner);
+        f.debug_list().entries(parsers.iter().map(|(prefix, _)| prefix)).finish()
+    }
+}
@@ -1,13 +1,105 @@
+mod navico;
+
 use std::collections::VecDeque;
+use std::net::Ipv4Addr;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
 use log::{error, info};
 use serde::{Deserialize, Serialize};
-use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{TcpStream, UdpSocket};
 use tokio::sync::mpsc;
-use tokio_serial::SerialPortBuilderExt;
-use datalink::{DataLinkConfig, DataLinkError, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataLinkTransmitter, DataMessage};
+use datalink::{DataLinkConfig, DataLinkError, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataLinkTransmitter, DataMessage, LinkStats, Value};
+use crate::nmea;
+use crate::raw_log::{hex, RawLog};
+use crate::reconnect::{ReceiverResult, Reconnector, SharedStatus};
+use crate::replay::{ReplayFile, ReplayOptions};
+use crate::runtime::runtime_handle;
+use crate::serial;
+
+pub use navico::{decode_frame as decode_navico_frame, NavicoModel, RadarSpoke, RETURNS_PER_SPOKE};
+
+/// Address of the control sentence: proprietary (`P`), under the `YPT`
+/// manufacturer mnemonic, sentence `RC`. No standard NMEA 0183 sentence sets
+/// radar range or gain, so a radar or interface taking these must be set up
+/// to read it.
+pub const CONTROL_SENTENCE: &str = "PYPTRC";
+
+/// Control commands for radars that accept remote configuration
+#[derive(Debug, Clone, PartialEq)]
+pub enum RadarControl {
+    /// Display range in nautical miles
+    Range(f32),
+    /// Receiver gain, either "AUTO" or a manual level
+    Gain(String),
+    /// Sea clutter suppression in dB
+    SeaClutter(i8),
+    /// Rain clutter filter on/off
+    RainClutter(bool),
+    /// Transmit (true) or standby (false)
+    Transmit(bool),
+}
+
+impl RadarControl {
+    fn command_and_value(&self) -> (&'static str, String) {
+        match self {
+            RadarControl::Range(range) => ("RANGE", range.to_string()),
+            RadarControl::Gain(gain) => ("GAIN", gain.to_uppercase()),
+            RadarControl::SeaClutter(db) => ("SEA", db.to_string()),
+            RadarControl::RainClutter(on) => ("RAIN", if *on { "ON" } else { "OFF" }.to_string()),
+            RadarControl::Transmit(on) => ("POWER", if *on { "TRANSMIT" } else { "STANDBY" }.to_string()),
+        }
+    }
+
+    /// Encode as a [`CONTROL_SENTENCE`], e.g. `$PYPTRC,RANGE,24*hh`
+    pub fn to_sentence(&self) -> String {
+        let (command, value) = self.command_and_value();
+        nmea::frame_sentence(&format!("{},{},{}", CONTROL_SENTENCE, command, value))
+    }
+
+    /// Wrap the control in a `RADAR_CONTROL` message for `send_message`
+    pub fn to_message(&self) -> DataMessage {
+        let (command, value) = self.command_and_value();
+        DataMessage::new(
+            "RADAR_CONTROL".to_string(),
+            "RADAR_CONTROLLER".to_string(),
+            self.to_sentence().into_bytes(),
+        )
+        .with_data("command", command.to_string())
+        .with_data("value", value)
+    }
+
+    /// Decode a `RADAR_CONTROL` message
+    pub fn from_message(message: &DataMessage) -> DataLinkResult<Self> {
+        if message.message_type != "RADAR_CONTROL" {
+            return Err(DataLinkError::InvalidConfig(format!(
+                "Radar cannot transmit {} messages",
+                message.message_type
+            )));
+        }
+
+        let command = message.get_str("command")
+            .ok_or_else(|| DataLinkError::InvalidConfig("Missing radar control command".to_string()))?;
+        let value = message.get_data("value")
+            .map(ToString::to_string)
+            .ok_or_else(|| DataLinkError::InvalidConfig("Missing radar control value".to_string()))?;
+        let value = value.as_str();
+        let invalid = || DataLinkError::InvalidConfig(format!("Invalid value for {}: {}", command, value));
+        let on_off = |value: &str| match value.to_uppercase().as_str() {
+            "ON" | "TRUE" | "TRANSMIT" => Ok(true),
+            "OFF" | "FALSE" | "STANDBY" => Ok(false),
+            _ => Err(invalid()),
+        };
+
+        match command.to_uppercase().as_str() {
+            "RANGE" => value.parse::<f32>().ok().filter(|r| *r > 0.0).map(RadarControl::Range).ok_or_else(invalid),
+            "GAIN" => Ok(RadarControl::Gain(value.to_uppercase())),
+            "SEA" => value.parse::<i8>().map(RadarControl::SeaClutter).map_err(|_| invalid()),
+            "RAIN" => on_off(value).map(RadarControl::RainClutter),
+            "POWER" => on_off(value).map(RadarControl::Transmit),
+            other => Err(DataLinkError::InvalidConfig(format!("Unknown radar control command: {}", other))),
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum RadarSourceConfig {
@@ -29,27 +121,67 @@ pub enum RadarSourceConfig {
     /// File-based radar data replay
     File {
         path: String,
-        replay_speed: f64,
+        replay: ReplayOptions,
+    },
+    /// Navico BR24/3G/4G/HALO spoke multicast
+    Navico {
+        group: Ipv4Addr,
+        port: u16,
+        /// Address of the network interface facing the radar
+        interface: Ipv4Addr,
     },
 }
 
 pub struct RadarDataLinkProvider {
-    status: DataLinkStatus,
+    status: SharedStatus,
     config: Option<RadarSourceConfig>,
+    /// Reopen the connection after it drops
+    auto_reconnect: bool,
     message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
     shutdown_tx: Option<mpsc::Sender<()>>,
+    command_tx: Option<mpsc::UnboundedSender<String>>,
     receiver_handle: Option<tokio::task::JoinHandle<()>>,
+    /// Runtime for the receiver task; the caller's when unset
+    runtime: Option<tokio::runtime::Handle>,
 }
 
 impl RadarDataLinkProvider {
     pub fn new() -> Self {
         Self {
-            status: DataLinkStatus::Disconnected,
+            status: SharedStatus::default(),
             config: None,
+            auto_reconnect: true,
             message_queue: Arc::new(Mutex::new(VecDeque::new())),
             shutdown_tx: None,
+            command_tx: None,
             receiver_handle: None,
+            runtime: None,
+        }
+    }
+
+    /// Spawn the receiver task on `runtime` rather than the caller's
+    pub fn with_runtime(mut self, runtime: tokio::runtime::Handle) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Send a control command to the radar
+    pub fn send_control(&mut self, control: &RadarControl) -> DataLinkResult<()> {
+        match self.config {
+            Some(RadarSourceConfig::File { .. }) => {
+                return Err(DataLinkError::TransportError("Radar file replay cannot be controlled".to_string()));
+            }
+            Some(RadarSourceConfig::Navico { .. }) => {
+                return Err(DataLinkError::TransportError("Navico radars are only received, not controlled".to_string()));
+            }
+            _ => {}
         }
+
+        let command_tx = self.command_tx.as_ref()
+            .ok_or_else(|| DataLinkError::ConnectionFailed("Radar not connected".to_string()))?;
+        command_tx
+            .send(format!("{}\r\n", control.to_sentence()))
+            .map_err(|_| DataLinkError::TransportError("Radar connection closed".to_string()))
     }
 
     pub fn parse_source_config(config: &DataLinkConfig) -> DataLinkResult<RadarSourceConfig> {
@@ -62,9 +194,8 @@ impl RadarDataLinkProvider {
                     .ok_or_else(|| DataLinkError::InvalidConfig("Missing port parameter for serial connection".to_string()))?
                     .clone();
                 let baud_rate = config.parameters.get("baud_rate")
-                    .ok_or_else(|| DataLinkError::InvalidConfig("Missing baud_rate parameter for serial connection".to_string()))?
-                    .parse::<u32>()
-                    .map_err(|_| DataLinkError::InvalidConfig("Invalid baud_rate parameter".to_string()))?;
+                    .ok_or_else(|| DataLinkError::InvalidConfig("Missing baud_rate parameter for serial connection".to_string()))?;
+                let baud_rate = serial::parse_baud_rate(Some(baud_rate), 4800)?;
 
                 Ok(RadarSourceConfig::Serial { port, baud_rate })
             }
@@ -94,12 +225,32 @@ impl RadarDataLinkProvider {
                 let path = config.parameters.get("path")
                     .ok_or_else(|| DataLinkError::InvalidConfig("Missing path parameter for file connection".to_string()))?
                     .clone();
-                let replay_speed = config.parameters.get("replay_speed")
-                    .unwrap_or(&"1.0".to_string())
-                    .parse::<f64>()
-                    .map_err(|_| DataLinkError::InvalidConfig("Invalid replay_speed parameter".to_string()))?;
-
-                Ok(RadarSourceConfig::File { path, replay_speed })
+                Ok(RadarSourceConfig::File { path, replay: ReplayOptions::from_config(config)? })
+            }
+            "navico" => {
+                let model = match config.parameters.get("model") {
+                    Some(model) => NavicoModel::parse(model)
+                        .ok_or_else(|| DataLinkError::InvalidConfig(format!("Unknown Navico radar model: {}", model)))?,
+                    None => NavicoModel::Halo,
+                };
+                let second_range = config.parameters.get("range_channel").is_some_and(|channel| channel.eq_ignore_ascii_case("b"));
+                let (default_group, default_port) = model.spoke_group(second_range);
+                let address = |key: &str, default: Ipv4Addr| match config.parameters.get(key) {
+                    Some(value) => value.parse::<Ipv4Addr>()
+                        .map_err(|_| DataLinkError::InvalidConfig(format!("Invalid {} parameter", key))),
+                    None => Ok(default),
+                };
+                let port = match config.parameters.get("port") {
+                    Some(port) => port.parse::<u16>()
+                        .map_err(|_| DataLinkError::InvalidConfig("Invalid port parameter".to_string()))?,
+                    None => default_port,
+                };
+
+                Ok(RadarSourceConfig::Navico {
+                    group: address("multicast_addr", default_group)?,
+                    port,
+                    interface: address("interface", Ipv4Addr::UNSPECIFIED)?,
+                })
             }
             _ => Err(DataLinkError::InvalidConfig(format!("Unsupported connection type: {}", connection_type))),
         }
@@ -108,50 +259,83 @@ impl RadarDataLinkProvider {
     fn start_receiver(&mut self) -> DataLinkResult<()> {
         if let Some(config) = &self.config {
             let (shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
+            let (command_tx, mut command_rx) = mpsc::unbounded_channel();
             let message_queue = Arc::clone(&self.message_queue);
+            let status = self.status.clone();
+            let auto_reconnect = self.auto_reconnect;
+            let runtime = runtime_handle(self.runtime.as_ref())?;
 
             let handle = match config {
                 RadarSourceConfig::Serial { port, baud_rate } => {
                     let port = port.clone();
                     let baud_rate = *baud_rate;
-                    tokio::spawn(async move {
-                        if let Err(e) = Self::serial_receiver(port, baud_rate, message_queue, &mut shutdown_rx).await {
-                            error!("Radar serial receiver error: {}", e);
+                    runtime.spawn(async move {
+                        let mut reconnector = Reconnector::new("Radar serial", auto_reconnect, status);
+                        loop {
+                            let result = Self::serial_receiver(port.clone(), baud_rate, Arc::clone(&message_queue), &mut shutdown_rx, &mut command_rx, reconnector.status()).await;
+                            if !reconnector.retry(result, &mut shutdown_rx).await {
+                                break;
+                            }
                         }
                     })
                 }
                 RadarSourceConfig::Tcp { host, port } => {
                     let host = host.clone();
                     let port = *port;
-                    tokio::spawn(async move {
-                        if let Err(e) = Self::tcp_receiver(host, port, message_queue, &mut shutdown_rx).await {
-                            error!("Radar TCP receiver error: {}", e);
+                    runtime.spawn(async move {
+                        let mut reconnector = Reconnector::new("Radar TCP", auto_reconnect, status);
+                        loop {
+                            let result = Self::tcp_receiver(host.clone(), port, Arc::clone(&message_queue), &mut shutdown_rx, &mut command_rx, reconnector.status()).await;
+                            if !reconnector.retry(result, &mut shutdown_rx).await {
+                                break;
+                            }
                         }
                     })
                 }
                 RadarSourceConfig::Udp { bind_addr, port } => {
                     let bind_addr = bind_addr.clone();
                     let port = *port;
-                    tokio::spawn(async move {
-                        if let Err(e) = Self::udp_receiver(bind_addr, port, message_queue, &mut shutdown_rx).await {
-                            error!("Radar UDP receiver error: {}", e);
+                    runtime.spawn(async move {
+                        let mut reconnector = Reconnector::new("Radar UDP", auto_reconnect, status);
+                        loop {
+                            let result = Self::udp_receiver(bind_addr.clone(), port, Arc::clone(&message_queue), &mut shutdown_rx, &mut command_rx, reconnector.status()).await;
+                            if !reconnector.retry(result, &mut shutdown_rx).await {
+                                break;
+                            }
                         }
                     })
                 }
-                RadarSourceConfig::File { path, replay_speed } => {
+                RadarSourceConfig::File { path, replay } => {
                     let path = path.clone();
-                    let replay_speed = *replay_speed;
-                    tokio::spawn(async move {
-                        if let Err(e) = Self::file_receiver(path, replay_speed, message_queue, &mut shutdown_rx).await {
-                            error!("Radar file receiver error: {}", e);
+                    let replay = replay.clone();
+                    runtime.spawn(async move {
+                        let mut reconnector = Reconnector::new("Radar file", auto_reconnect, status);
+                        loop {
+                            let result = Self::file_receiver(path.clone(), replay.clone(), Arc::clone(&message_queue), &mut shutdown_rx, reconnector.status()).await;
+                            if !reconnector.retry(result, &mut shutdown_rx).await {
+                                break;
+                            }
+                        }
+                    })
+                }
+                RadarSourceConfig::Navico { group, port, interface } => {
+                    let (group, port, interface) = (*group, *port, *interface);
+                    runtime.spawn(async move {
+                        let mut reconnector = Reconnector::new("Radar Navico", auto_reconnect, status);
+                        loop {
+                            let result = Self::navico_receiver(group, port, interface, Arc::clone(&message_queue), &mut shutdown_rx, reconnector.status()).await;
+                            if !reconnector.retry(result, &mut shutdown_rx).await {
+                                break;
+                            }
                         }
                     })
                 }
             };
 
             self.shutdown_tx = Some(shutdown_tx);
+            self.command_tx = Some(command_tx);
             self.receiver_handle = Some(handle);
-            self.status = DataLinkStatus::Connected;
+            self.status.set(DataLinkStatus::Connected);
             Ok(())
         } else {
             Err(DataLinkError::InvalidConfig("No configuration set".to_string()))
@@ -163,13 +347,16 @@ impl RadarDataLinkProvider {
         baud_rate: u32,
         message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
         shutdown_rx: &mut mpsc::Receiver<()>,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        info!("Starting radar serial receiver on {} at {} baud", port, baud_rate);
+        command_rx: &mut mpsc::UnboundedReceiver<String>,
+        status: &SharedStatus,
+    ) -> ReceiverResult {
+        info!("Starting radar serial receiver on {} at {} baud", port, serial::baud_rate_label(baud_rate));
 
-        let serial_stream = tokio_serial::new(&port, baud_rate)
-            .open_native_async()?;
+        let (serial_stream, _) = serial::open(&port, baud_rate).await?;
+        status.set(DataLinkStatus::Connected);
 
-        let mut reader = BufReader::new(serial_stream);
+        let (read_half, mut write_half) = tokio::io::split(serial_stream);
+        let mut reader = BufReader::new(read_half);
         let mut line = String::new();
 
         loop {
@@ -178,22 +365,24 @@ impl RadarDataLinkProvider {
                     info!("Radar serial receiver shutdown requested");
                     break;
                 }
+                Some(command) = command_rx.recv() => {
+                    if let Err(e) = write_half.write_all(command.as_bytes()).await {
+                        error!("Error writing radar command to serial port: {}", e);
+                    }
+                }
                 result = reader.read_line(&mut line) => {
                     match result {
-                        Ok(0) => break, // EOF
+                        Ok(0) => return Err("Radar serial port closed".into()),
                         Ok(_) => {
                             let trimmed = line.trim();
-                            if let Some(message) = Self::parse_radar_sentence(trimmed) {
+                            if let Some(message) = status.stats().observe(trimmed, Self::parse_radar_sentence(trimmed)) {
                                 if let Ok(mut queue) = message_queue.lock() {
                                     queue.push_back(message);
                                 }
                             }
                             line.clear();
                         }
-                        Err(e) => {
-                            error!("Error reading from radar serial port: {}", e);
-                            break;
-                        }
+                        Err(e) => return Err(format!("Error reading from radar serial port: {}", e).into()),
                     }
                 }
             }
@@ -207,11 +396,15 @@ impl RadarDataLinkProvider {
         port: u16,
         message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
         shutdown_rx: &mut mpsc::Receiver<()>,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        command_rx: &mut mpsc::UnboundedReceiver<String>,
+        status: &SharedStatus,
+    ) -> ReceiverResult {
         info!("Starting radar TCP receiver on {}:{}", host, port);
 
         let stream = TcpStream::connect(format!("{}:{}", host, port)).await?;
-        let mut reader = BufReader::new(stream);
+        status.set(DataLinkStatus::Connected);
+        let (read_half, mut write_half) = stream.into_split();
+        let mut reader = BufReader::new(read_half);
         let mut line = String::new();
 
         loop {
@@ -220,22 +413,24 @@ impl RadarDataLinkProvider {
                     info!("Radar TCP receiver shutdown requested");
                     break;
                 }
+                Some(command) = command_rx.recv() => {
+                    if let Err(e) = write_half.write_all(command.as_bytes()).await {
+                        error!("Error writing radar command to TCP connection: {}", e);
+                    }
+                }
                 result = reader.read_line(&mut line) => {
                     match result {
-                        Ok(0) => break, // EOF
+                        Ok(0) => return Err("Radar TCP connection closed".into()),
                         Ok(_) => {
                             let trimmed = line.trim();
-                            if let Some(message) = Self::parse_radar_sentence(trimmed) {
+                            if let Some(message) = status.stats().observe(trimmed, Self::parse_radar_sentence(trimmed)) {
                                 if let Ok(mut queue) = message_queue.lock() {
                                     queue.push_back(message);
                                 }
                             }
                             line.clear();
                         }
-                        Err(e) => {
-                            error!("Error reading from radar TCP connection: {}", e);
-                            break;
-                        }
+                        Err(e) => return Err(format!("Error reading from radar TCP connection: {}", e).into()),
                     }
                 }
             }
@@ -249,11 +444,16 @@ impl RadarDataLinkProvider {
         port: u16,
         message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
         shutdown_rx: &mut mpsc::Receiver<()>,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        command_rx: &mut mpsc::UnboundedReceiver<String>,
+        status: &SharedStatus,
+    ) -> ReceiverResult {
         info!("Starting radar UDP receiver on {}:{}", bind_addr, port);
 
         let socket = UdpSocket::bind(format!("{}:{}", bind_addr, port)).await?;
+        status.set(DataLinkStatus::Connected);
         let mut buf = [0; 1024];
+        // Commands go back to whichever address the radar data arrives from
+        let mut radar_addr = None;
 
         loop {
             tokio::select! {
@@ -261,22 +461,77 @@ impl RadarDataLinkProvider {
                     info!("Radar UDP receiver shutdown requested");
                     break;
                 }
-                result = socket.recv(&mut buf) => {
+                Some(command) = command_rx.recv() => {
+                    match radar_addr {
+                        Some(addr) => {
+                            if let Err(e) = socket.send_to(command.as_bytes(), addr).await {
+                                error!("Error sending radar command over UDP: {}", e);
+                            }
+                        }
+                        None => error!("Dropping radar command, no radar heard on UDP yet"),
+                    }
+                }
+                result = socket.recv_from(&mut buf) => {
                     match result {
-                        Ok(len) => {
+                        Ok((len, addr)) => {
+                            radar_addr = Some(addr);
                             let data = String::from_utf8_lossy(&buf[..len]);
                             for line in data.lines() {
-                                if let Some(message) = Self::parse_radar_sentence(line.trim()) {
+                                if let Some(message) = status.stats().observe(line, Self::parse_radar_sentence(line.trim())) {
                                     if let Ok(mut queue) = message_queue.lock() {
                                         queue.push_back(message);
                                     }
                                 }
                             }
                         }
-                        Err(e) => {
-                            error!("Error reading from radar UDP socket: {}", e);
-                            break;
+                        Err(e) => return Err(format!("Error reading from radar UDP socket: {}", e).into()),
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn navico_receiver(
+        group: Ipv4Addr,
+        port: u16,
+        interface: Ipv4Addr,
+        message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
+        shutdown_rx: &mut mpsc::Receiver<()>,
+        status: &SharedStatus,
+    ) -> ReceiverResult {
+        info!("Starting Navico radar receiver on {}:{} via {}", group, port, interface);
+
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port)).await?;
+        socket.join_multicast_v4(group, interface)?;
+        status.set(DataLinkStatus::Connected);
+        // A frame of 32 spokes is a little over 17 KB
+        let mut buf = vec![0; 65536];
+
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => {
+                    info!("Navico radar receiver shutdown requested");
+                    break;
+                }
+                result = socket.recv_from(&mut buf) => {
+                    match result {
+                        Ok((len, _)) => {
+                            status.stats().log_raw(&hex(&buf[..len]));
+                            let spokes = navico::decode_frame(&buf[..len]);
+                            status.stats().update(|stats| {
+                                if spokes.is_empty() {
+                                    stats.record_unsupported("Navico frame without spokes");
+                                } else {
+                                    stats.record_parsed();
+                                }
+                            });
+                            if let Ok(mut queue) = message_queue.lock() {
+                                queue.extend(spokes.iter().map(RadarSpoke::to_message));
+                            }
                         }
+                        Err(e) => return Err(format!("Error reading from Navico radar: {}", e).into()),
                     }
                 }
             }
@@ -287,17 +542,15 @@ impl RadarDataLinkProvider {
 
     async fn file_receiver(
         path: String,
-        replay_speed: f64,
+        replay: ReplayOptions,
         message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
         shutdown_rx: &mut mpsc::Receiver<()>,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        info!("Starting radar file receiver from {} at {}x speed", path, replay_speed);
-
-        let file = tokio::fs::File::open(&path).await?;
-        let mut reader = BufReader::new(file);
-        let mut line = String::new();
+        status: &SharedStatus,
+    ) -> ReceiverResult {
+        info!("Starting radar file receiver from {} at {}x speed", path, replay.speed);
 
-        let delay_duration = Duration::from_millis((1000.0 / replay_speed) as u64);
+        let mut replay = ReplayFile::open(&path, replay).await?;
+        status.set(DataLinkStatus::Connected);
 
         loop {
             tokio::select! {
@@ -305,21 +558,18 @@ impl RadarDataLinkProvider {
                     info!("Radar file receiver shutdown requested");
                     break;
                 }
-                result = reader.read_line(&mut line) => {
+                result = replay.next_line() => {
                     match result {
-                        Ok(0) => {
+                        Ok(None) => {
                             info!("Radar file replay completed");
                             break;
                         }
-                        Ok(_) => {
-                            let trimmed = line.trim();
-                            if let Some(message) = Self::parse_radar_sentence(trimmed) {
+                        Ok(Some(line)) => {
+                            if let Some(message) = status.stats().observe(&line, Self::parse_radar_sentence(line.trim())) {
                                 if let Ok(mut queue) = message_queue.lock() {
                                     queue.push_back(message);
                                 }
                             }
-                            line.clear();
-                            tokio::time::sleep(delay_duration).await;
                         }
                         Err(e) => {
                             error!("Error reading from radar file: {}", e);
@@ -364,22 +614,22 @@ impl RadarDataLinkProvider {
             );
 
             if let Ok(range) = parts[1].parse::<f32>() {
-                message = message.with_data("range_nm".to_string(), range.to_string());
+                message = message.with_data("range_nm", range);
             }
-            if let Ok(bearing) = parts[2].parse::<f32>() {
-                message = message.with_data("bearing_deg".to_string(), bearing.to_string());
+            if let Ok(bearing) = parts[2].parse::<f64>() {
+                message = message.with_data("bearing_deg", Value::Angle(bearing));
             }
             if let Ok(speed) = parts[3].parse::<f32>() {
-                message = message.with_data("speed_kts".to_string(), speed.to_string());
+                message = message.with_data("speed_kts", speed);
             }
-            if let Ok(course) = parts[4].parse::<f32>() {
-                message = message.with_data("course_deg".to_string(), course.to_string());
+            if let Ok(course) = parts[4].parse::<f64>() {
+                message = message.with_data("course_deg", Value::Angle(course));
             }
             if let Ok(cpa) = parts[5].split('*').next().unwrap_or("").parse::<f32>() {
-                message = message.with_data("cpa_nm".to_string(), cpa.to_string());
+                message = message.with_data("cpa_nm", cpa);
             }
 
-            message = message.with_data("sentence_type".to_string(), "$RADTG".to_string());
+            message = message.with_data("sentence_type", "$RADTG".to_string());
             Some(message)
         } else {
             None
@@ -397,19 +647,19 @@ impl RadarDataLinkProvider {
                 sentence.as_bytes().to_vec(),
             );
 
-            if let Ok(sweep_angle) = parts[1].parse::<f32>() {
-                message = message.with_data("sweep_angle".to_string(), sweep_angle.to_string());
+            if let Ok(sweep_angle) = parts[1].parse::<f64>() {
+                message = message.with_data("sweep_angle", Value::Angle(sweep_angle));
             }
             if let Ok(range) = parts[2].parse::<f32>() {
-                message = message.with_data("range_nm".to_string(), range.to_string());
+                message = message.with_data("range_nm", range);
             }
-            message = message.with_data("gain".to_string(), parts[3].to_string());
+            message = message.with_data("gain", parts[3].to_string());
             if let Ok(sea_clutter) = parts[4].parse::<i8>() {
-                message = message.with_data("sea_clutter_db".to_string(), sea_clutter.to_string());
+                message = message.with_data("sea_clutter_db", sea_clutter);
             }
-            message = message.with_data("rain_clutter".to_string(), parts[5].split('*').next().unwrap_or("").to_string());
+            message = message.with_data("rain_clutter", parts[5].split('*').next().unwrap_or("").to_string());
 
-            message = message.with_data("sentence_type".to_string(), "$RADSC".to_string());
+            message = message.with_data("sentence_type", "$RADSC".to_string());
             Some(message)
         } else {
             None
@@ -428,15 +678,15 @@ impl RadarDataLinkProvider {
             );
 
             if let Ok(range) = parts[1].parse::<f32>() {
-                message = message.with_data("range_nm".to_string(), range.to_string());
+                message = message.with_data("range_nm", range);
             }
-            message = message.with_data("gain".to_string(), parts[2].to_string());
+            message = message.with_data("gain", parts[2].to_string());
             if let Ok(sea_clutter) = parts[3].parse::<i8>() {
-                message = message.with_data("sea_clutter_db".to_string(), sea_clutter.to_string());
+                message = message.with_data("sea_clutter_db", sea_clutter);
             }
-            message = message.with_data("rain_clutter".to_string(), parts[4].split('*').next().unwrap_or("").to_string());
+            message = message.with_data("rain_clutter", parts[4].split('*').next().unwrap_or("").to_string());
 
-            message = message.with_data("sentence_type".to_string(), "$RADCF".to_string());
+            message = message.with_data("sentence_type", "$RADCF".to_string());
             Some(message)
         } else {
             None
@@ -454,9 +704,9 @@ impl RadarDataLinkProvider {
                 sentence.as_bytes().to_vec(),
             );
 
-            message = message.with_data("status".to_string(), parts[1].to_string());
-            message = message.with_data("health".to_string(), parts[2].split('*').next().unwrap_or("").to_string());
-            message = message.with_data("sentence_type".to_string(), "$RADST".to_string());
+            message = message.with_data("status", parts[1].to_string());
+            message = message.with_data("health", parts[2].split('*').next().unwrap_or("").to_string());
+            message = message.with_data("sentence_type", "$RADST".to_string());
             Some(message)
         } else {
             None
@@ -467,10 +717,11 @@ impl RadarDataLinkProvider {
         if let Some(shutdown_tx) = self.shutdown_tx.take() {
             let _ = shutdown_tx.try_send(());
         }
+        self.command_tx = None;
         if let Some(handle) = self.receiver_handle.take() {
             handle.abort();
         }
-        self.status = DataLinkStatus::Disconnected;
+        self.status.set(DataLinkStatus::Disconnected);
     }
 }
 
@@ -482,7 +733,7 @@ impl Default for RadarDataLinkProvider {
 
 impl DataLinkReceiver for RadarDataLinkProvider {
     fn status(&self) -> DataLinkStatus {
-        self.status.clone()
+        self.status.get()
     }
 
     fn receive_message(&mut self) -> DataLinkResult<Option<DataMessage>> {
@@ -493,12 +744,20 @@ impl DataLinkReceiver for RadarDataLinkProvider {
         }
     }
 
+    fn stats(&self) -> LinkStats {
+        self.status.stats().get()
+    }
+
     fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
         info!("Connecting radar datalink with config: {:?}", config);
 
         let source_config = Self::parse_source_config(config)?;
+        let raw_log = RawLog::from_config(config)?;
+        self.stop_receiver();
+        self.status.stats().set_raw_log(raw_log);
         self.config = Some(source_config);
-        self.status = DataLinkStatus::Connecting;
+        self.auto_reconnect = config.auto_reconnect;
+        self.status.set(DataLinkStatus::Connecting);
 
         match self.start_receiver() {
             Ok(()) => {
@@ -506,15 +765,21 @@ impl DataLinkReceiver for RadarDataLinkProvider {
                 Ok(())
             }
             Err(e) => {
-                self.status = DataLinkStatus::Error(format!("Connection failed: {}", e));
+                self.status.set(DataLinkStatus::Error(format!("Connection failed: {}", e)));
                 Err(e)
             }
         }
     }
 
+    /// Swap the radar source; queued spokes and counters stay
+    fn reconfigure(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        DataLinkReceiver::connect(self, config)
+    }
+
     fn disconnect(&mut self) -> DataLinkResult<()> {
         info!("Disconnecting radar datalink");
         self.stop_receiver();
+        self.status.stats().set_raw_log(None);
         self.config = None;
 
         // Clear message queue
@@ -529,12 +794,13 @@ impl DataLinkReceiver for RadarDataLinkProvider {
 
 impl DataLinkTransmitter for RadarDataLinkProvider {
     fn status(&self) -> DataLinkStatus {
-        self.status.clone()
+        self.status.get()
     }
 
-    fn send_message(&mut self, _message: &DataMessage) -> DataLinkResult<()> {
-        // Radar is typically receive-only, but we could implement radar control commands here
-        Err(DataLinkError::TransportError("Radar transmission not supported".to_string()))
+    fn send_message(&mut self, message: &DataMessage) -> DataLinkResult<()> {
+        // Only control commands can be transmitted to the radar
+        let control = RadarControl::from_message(message)?;
+        self.send_control(&control)
     }
 
     fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
@@ -0,0 +1,147 @@
+//! Navico BR24 / 3G / 4G / HALO spoke protocol
+//!
+//! The radar multicasts its image as UDP frames of 32 spokes. Each spoke is a
+//! 24 byte header followed by 1024 returns packed two to a byte, low nibble
+//! first. BR24 and 3G headers carry a fixed marker and a 24 bit range; 4G and
+//! HALO split the range over two fields instead. Angles are in 1/4096ths of a
+//! revolution relative to the bow.
+
+use std::net::Ipv4Addr;
+use serde::{Deserialize, Serialize};
+use datalink::{DataLinkError, DataLinkResult, DataMessage, Value};
+
+/// Returns in one spoke
+pub const RETURNS_PER_SPOKE: usize = 1024;
+
+/// Raw angle units in one revolution
+const ANGLE_UNITS: f64 = 4096.0;
+
+const FRAME_HEADER_LEN: usize = 8;
+const SPOKE_HEADER_LEN: usize = 24;
+const SPOKE_LEN: usize = SPOKE_HEADER_LEN + RETURNS_PER_SPOKE / 2;
+
+/// Marker at bytes 4..8 of a BR24 / 3G spoke header
+const BR24_MARK: [u8; 4] = [0x00, 0x44, 0x0d, 0x0e];
+
+/// Heading field flag set when the radar has a true heading
+const HEADING_TRUE_FLAG: u16 = 0x4000;
+const HEADING_MASK: u16 = 0x0fff;
+
+/// Radar models, which differ in where they multicast their image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NavicoModel {
+    Br24,
+    ThreeG,
+    FourG,
+    Halo,
+}
+
+impl NavicoModel {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "br24" => Some(NavicoModel::Br24),
+            "3g" => Some(NavicoModel::ThreeG),
+            "4g" => Some(NavicoModel::FourG),
+            "halo" => Some(NavicoModel::Halo),
+            _ => None,
+        }
+    }
+
+    /// Default multicast group and port of the spoke stream. 4G and HALO
+    /// run two ranges at once; `second_range` picks the B channel.
+    pub fn spoke_group(&self, second_range: bool) -> (Ipv4Addr, u16) {
+        match self {
+            NavicoModel::FourG | NavicoModel::Halo if second_range => (Ipv4Addr::new(236, 6, 7, 13), 6657),
+            _ => (Ipv4Addr::new(236, 6, 7, 8), 6678),
+        }
+    }
+}
+
+/// One radar spoke
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadarSpoke {
+    /// Degrees clockwise from the bow
+    pub angle_deg: f64,
+    /// Heading the radar was given, when it has one
+    pub heading_true: Option<f64>,
+    /// Distance covered by the returns, in meters
+    pub range_m: f64,
+    /// Return strength 0-15 per sample, outwards from the antenna
+    pub intensities: Vec<u8>,
+}
+
+impl RadarSpoke {
+    /// Wrap as a `RADAR_SPOKE` message; the intensities are the payload
+    pub fn to_message(&self) -> DataMessage {
+        let message = DataMessage::new("RADAR_SPOKE".to_string(), "RADAR_RECEIVER".to_string(), self.intensities.clone())
+            .with_data("angle_deg", Value::Angle(self.angle_deg))
+            .with_data("range_m", self.range_m);
+        match self.heading_true {
+            Some(heading) => message.with_data("heading_true", Value::Angle(heading)),
+            None => message,
+        }
+    }
+
+    /// Decode a `RADAR_SPOKE` message
+    pub fn from_message(message: &DataMessage) -> DataLinkResult<Self> {
+        if message.message_type != "RADAR_SPOKE" {
+            return Err(DataLinkError::InvalidMessage(format!("Not a radar spoke: {}", message.message_type)));
+        }
+        let missing = |key: &str| DataLinkError::InvalidMessage(format!("Radar spoke without {}", key));
+        Ok(Self {
+            angle_deg: message.get_angle("angle_deg").ok_or_else(|| missing("angle_deg"))?,
+            heading_true: message.get_angle("heading_true"),
+            range_m: message.get_f64("range_m").ok_or_else(|| missing("range_m"))?,
+            intensities: message.payload.to_vec(),
+        })
+    }
+
+    /// Bearing of the spoke in degrees true, when the heading is known
+    pub fn bearing_true(&self) -> Option<f64> {
+        self.heading_true.map(|heading| (heading + self.angle_deg).rem_euclid(360.0))
+    }
+}
+
+/// Decode every valid spoke in a multicast frame
+pub fn decode_frame(frame: &[u8]) -> Vec<RadarSpoke> {
+    frame
+        .get(FRAME_HEADER_LEN..)
+        .unwrap_or_default()
+        .chunks_exact(SPOKE_LEN)
+        .filter_map(decode_spoke)
+        .collect()
+}
+
+fn decode_spoke(spoke: &[u8]) -> Option<RadarSpoke> {
+    let u16_at = |offset: usize| u16::from_le_bytes([spoke[offset], spoke[offset + 1]]);
+    // 0x02 and 0x12 mark a valid spoke; anything else is a partial or test line
+    if !matches!(spoke[1], 0x02 | 0x12) {
+        return None;
+    }
+
+    let range_m = if spoke[4..8] == BR24_MARK {
+        let raw = u32::from_le_bytes([spoke[12], spoke[13], spoke[14], 0]);
+        raw as f64 * 10.0 / std::f64::consts::SQRT_2
+    } else {
+        let (large, small) = (u16_at(6), u16_at(12));
+        match (large, small) {
+            (0x80, 0xffff) => 0.0,
+            (0x80, small) => small as f64 / 4.0,
+            (large, small) => large as f64 * small as f64 / 512.0,
+        }
+    };
+
+    let heading = u16_at(10);
+    let heading_valid = heading & HEADING_TRUE_FLAG != 0 && heading & !(HEADING_TRUE_FLAG | HEADING_MASK) == 0;
+    let intensities = spoke[SPOKE_HEADER_LEN..]
+        .iter()
+        .flat_map(|byte| [byte & 0x0f, byte >> 4])
+        .collect();
+
+    Some(RadarSpoke {
+        angle_deg: (u16_at(8) % ANGLE_UNITS as u16) as f64 * 360.0 / ANGLE_UNITS,
+        heading_true: heading_valid.then(|| (heading & HEADING_MASK) as f64 * 360.0 / ANGLE_UNITS),
+        range_m,
+        intensities,
+    })
+}
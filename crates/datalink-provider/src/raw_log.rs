@@ -0,0 +1,94 @@
+//! Raw input logging
+//!
+//! With `raw_log_path` set, a provider appends everything it reads to that
+//! file before parsing, one line per sentence, JSON document or datagram, and
+//! the receive time of each line, in seconds since the Unix epoch, to a
+//! `.timing` file beside it. The pair replays as a file source:
+//! `path=<raw_log_path>` with `timing_log=<raw_log_path>.timing`. Binary
+//! input is written as hex: CAN frames as `<id>#<data>` the way `candump`
+//! prints them, other datagrams as plain hex.
+//!
+//! A `raw_log_path` ending in `.gz` or `.zst` is compressed, and so is its
+//! timing file, `capture.nmea.timing.gz` for `capture.nmea.gz`. Compressed
+//! logs are finished when the provider disconnects.
+
+use std::fmt::Write as _;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use datalink::{DataLinkConfig, DataLinkError, DataLinkResult};
+use crate::compression::Compression;
+
+/// Config parameter naming the raw log file
+pub const RAW_LOG_PARAM: &str = "raw_log_path";
+
+/// Appends raw lines and their receive times
+pub struct RawLog {
+    path: String,
+    lines: Box<dyn Write + Send>,
+    times: Box<dyn Write + Send>,
+}
+
+impl std::fmt::Debug for RawLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RawLog").field("path", &self.path).finish_non_exhaustive()
+    }
+}
+
+impl RawLog {
+    /// Open `path` and its timing file for appending, creating them if needed
+    pub fn open(path: &str) -> std::io::Result<Self> {
+        let compression = Compression::from_path(path);
+        let append = |path: &str| compression.writer(OpenOptions::new().create(true).append(true).open(Path::new(path))?);
+        Ok(Self {
+            path: path.to_string(),
+            lines: append(path)?,
+            times: append(&Self::timing_path(path))?,
+        })
+    }
+
+    /// The log named by [`RAW_LOG_PARAM`], if any
+    pub fn from_config(config: &DataLinkConfig) -> DataLinkResult<Option<Self>> {
+        match config.parameters.get(RAW_LOG_PARAM) {
+            Some(path) => Self::open(path)
+                .map(Some)
+                .map_err(|e| DataLinkError::InvalidConfig(format!("Cannot open {} {}: {}", RAW_LOG_PARAM, path, e))),
+            None => Ok(None),
+        }
+    }
+
+    /// Where the receive times of `path` go
+    pub fn timing_path(path: &str) -> String {
+        match Compression::from_path(path).extension() {
+            Some(extension) => format!("{}.timing.{}", &path[..path.len() - extension.len() - 1], extension),
+            None => format!("{}.timing", path),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Append one line. Line breaks inside it are replaced so the log stays
+    /// one entry per line and in step with its timing file.
+    pub fn write_line(&mut self, line: &str, received_at: SystemTime) -> std::io::Result<()> {
+        let line = line.trim().replace(['\r', '\n'], " ");
+        let seconds = received_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64();
+        self.lines.write_all(format!("{}\n", line).as_bytes())?;
+        self.times.write_all(format!("{:.3}\n", seconds).as_bytes())
+    }
+}
+
+/// Bytes as lowercase hex
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::with_capacity(bytes.len() * 2), |mut text, byte| {
+        let _ = write!(text, "{:02x}", byte);
+        text
+    })
+}
+
+/// A CAN frame as `candump` prints it, e.g. `09F80100#a0b1c2d3e4f50617`
+pub fn can_frame_line(id: u32, data: &[u8]) -> String {
+    format!("{:08X}#{}", id, hex(data))
+}
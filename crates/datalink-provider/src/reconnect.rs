@@ -0,0 +1,132 @@
+//! Restarting receiver tasks after the link drops
+//!
+//! A receiver returns `Ok` when asked to shut down or when a replay file runs
+//! out, and an error when its port or socket fails. [`Reconnector`] reopens it
+//! after an error while `auto_reconnect` is set, waiting longer after each
+//! failed attempt, and keeps the provider status and reconnect count current
+//! in between.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use log::{error, info};
+use tokio::sync::mpsc;
+use datalink::DataLinkStatus;
+use crate::stats::SharedStats;
+
+/// What a receiver task returns
+pub type ReceiverResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+/// Wait before the first reconnect attempt
+pub const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest wait between reconnect attempts
+pub const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Doubling delay between reconnect attempts, capped at [`MAX_BACKOFF`]
+#[derive(Debug)]
+pub struct Backoff {
+    next: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self { next: INITIAL_BACKOFF }
+    }
+}
+
+impl Backoff {
+    /// Delay before the next attempt
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.next;
+        self.next = (self.next * 2).min(MAX_BACKOFF);
+        delay
+    }
+
+    /// Start over from [`INITIAL_BACKOFF`] once a connection has been made
+    pub fn reset(&mut self) {
+        self.next = INITIAL_BACKOFF;
+    }
+}
+
+/// Provider status and line counters shared with its receiver task
+#[derive(Debug, Clone)]
+pub struct SharedStatus {
+    status: Arc<Mutex<DataLinkStatus>>,
+    stats: SharedStats,
+}
+
+impl Default for SharedStatus {
+    fn default() -> Self {
+        Self {
+            status: Arc::new(Mutex::new(DataLinkStatus::Disconnected)),
+            stats: SharedStats::default(),
+        }
+    }
+}
+
+impl SharedStatus {
+    pub fn get(&self) -> DataLinkStatus {
+        self.status.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    pub fn set(&self, status: DataLinkStatus) {
+        *self.status.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = status;
+    }
+
+    pub fn stats(&self) -> &SharedStats {
+        &self.stats
+    }
+}
+
+/// Decides after each receiver run whether to open it again
+#[derive(Debug)]
+pub struct Reconnector {
+    name: &'static str,
+    auto_reconnect: bool,
+    status: SharedStatus,
+    backoff: Backoff,
+}
+
+impl Reconnector {
+    pub fn new(name: &'static str, auto_reconnect: bool, status: SharedStatus) -> Self {
+        Self {
+            name,
+            auto_reconnect,
+            status,
+            backoff: Backoff::default(),
+        }
+    }
+
+    /// Status the receiver marks `Connected` once its port or socket is open
+    pub fn status(&self) -> &SharedStatus {
+        &self.status
+    }
+
+    /// Whether to open the receiver again after it returned `result`.
+    /// Waits out the backoff first, giving up early if shutdown is requested.
+    pub async fn retry(&mut self, result: ReceiverResult, shutdown_rx: &mut mpsc::Receiver<()>) -> bool {
+        let Err(e) = result else {
+            return false;
+        };
+        error!("{} receiver error: {}", self.name, e);
+        if !self.auto_reconnect {
+            self.status.set(DataLinkStatus::Error(e.to_string()));
+            return false;
+        }
+
+        // A receiver that got as far as connecting starts the backoff over
+        if self.status.get() == DataLinkStatus::Connected {
+            self.backoff.reset();
+        }
+        let delay = self.backoff.next_delay();
+        self.status.set(DataLinkStatus::Error(format!("{}; reconnecting in {}s", e, delay.as_secs())));
+        tokio::select! {
+            _ = shutdown_rx.recv() => return false,
+            _ = tokio::time::sleep(delay) => {}
+        }
+        info!("Reconnecting {} receiver", self.name);
+        self.status.set(DataLinkStatus::Connecting);
+        self.status.stats().update(|stats| stats.record_reconnect());
+        true
+    }
+}
@@ -0,0 +1,166 @@
+//! Provider registry
+//!
+//! Maps protocol / connection type strings to provider constructors so callers
+//! can build a datalink from configuration alone, without naming the concrete
+//! provider type. Downstream crates can register their own providers.
+//! Parameters written as `credential:<name>` are looked up in the registry's
+//! [`CredentialStore`] at connect time, so API keys stay out of configuration.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use credentials::{has_credential_references, CredentialStore};
+use datalink::{DataLinkConfig, DataLinkError, DataLinkReceiver, DataLinkResult, RateLimitedReceiver, RateLimiter, SchemaRegistry, SimulationDataLink, ValidatingReceiver, VALIDATE_PARAM};
+use crate::ais::AisDataLinkProvider;
+use crate::aisstream::AisStreamIoProvider;
+use crate::autopilot::AutopilotDataLinkProvider;
+use crate::dead_reckoning::{DeadReckoningReceiver, DEAD_RECKONING_PARAM};
+use crate::geofence::{GeofenceReceiver, GEOFENCES_PARAM};
+use crate::variation::VariationReceiver;
+use crate::smoothing::{SmoothingReceiver, SMOOTHING_PARAM};
+use crate::engine::EngineDataLinkProvider;
+use crate::gps::{GpsDataLinkProvider, SimulatedGpsProvider};
+use crate::instruments::InstrumentDataLinkProvider;
+use crate::mqtt::MqttDataLinkProvider;
+use crate::nmea2000::Nmea2000DataLinkProvider;
+use crate::radar::RadarDataLinkProvider;
+use crate::websocket::WebSocketDataLinkProvider;
+
+/// Constructor closure producing a fresh, unconnected provider
+pub type ProviderConstructor = Arc<dyn Fn() -> Box<dyn DataLinkReceiver> + Send + Sync>;
+
+#[derive(Clone)]
+pub struct ProviderRegistry {
+    constructors: HashMap<String, ProviderConstructor>,
+    credentials: Option<Arc<CredentialStore>>,
+}
+
+impl ProviderRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self {
+            constructors: HashMap::new(),
+            credentials: None,
+        }
+    }
+
+    /// Create a registry with the built-in AIS, aisstream.io, autopilot, engine, GPS, instrument, MQTT, NMEA 2000, radar, simulated GPS, simulation and WebSocket providers
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        registry.register("ais", || Box::new(AisDataLinkProvider::new()));
+        registry.register("aisstream", || Box::new(AisStreamIoProvider::new()));
+        registry.register("autopilot", || Box::new(AutopilotDataLinkProvider::new()));
+        registry.register("engine", || Box::new(EngineDataLinkProvider::new()));
+        registry.register("gps", || Box::new(GpsDataLinkProvider::new()));
+        registry.register("instruments", || Box::new(InstrumentDataLinkProvider::new()));
+        registry.register("mqtt", || Box::new(MqttDataLinkProvider::new()));
+        registry.register("nmea2000", || Box::new(Nmea2000DataLinkProvider::new()));
+        registry.register("radar", || Box::new(RadarDataLinkProvider::new()));
+        registry.register("simulated_gps", || Box::new(SimulatedGpsProvider::new()));
+        registry.register("simulation", || Box::new(SimulationDataLink::new()));
+        registry.register("websocket", || Box::new(WebSocketDataLinkProvider::new()));
+        registry
+    }
+
+    /// Resolve `credential:` parameters through `store` when connecting
+    pub fn with_credentials(mut self, store: Arc<CredentialStore>) -> Self {
+        self.credentials = Some(store);
+        self
+    }
+
+    /// Register a provider constructor, replacing any existing one for the key
+    pub fn register<F>(&mut self, key: &str, constructor: F)
+    where
+        F: Fn() -> Box<dyn DataLinkReceiver> + Send + Sync + 'static,
+    {
+        self.constructors.insert(key.to_string(), Arc::new(constructor));
+    }
+
+    /// Remove a provider, returning whether it was registered
+    pub fn unregister(&mut self, key: &str) -> bool {
+        self.constructors.remove(key).is_some()
+    }
+
+    /// Whether a provider is registered for the key
+    pub fn contains(&self, key: &str) -> bool {
+        self.constructors.contains_key(key)
+    }
+
+    /// Registered keys, sorted
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self.constructors.keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+
+    /// Build an unconnected provider for the key
+    pub fn create(&self, key: &str) -> DataLinkResult<Box<dyn DataLinkReceiver>> {
+        self.constructors
+            .get(key)
+            .map(|constructor| constructor())
+            .ok_or_else(|| DataLinkError::InvalidConfig(format!("No provider registered for '{}'", key)))
+    }
+
+    /// The registry key for a configuration: the `protocol` parameter if set,
+    /// otherwise the configuration's connection type
+    pub fn key_for(config: &DataLinkConfig) -> &str {
+        config
+            .parameters
+            .get("protocol")
+            .map(String::as_str)
+            .unwrap_or(config.connection_type.as_str())
+    }
+
+    /// Build the provider for a configuration and connect it, applying any
+    /// `rate_limit*` parameters, schema validation when `validate=true`,
+    /// position smoothing by `smoothing`, dead reckoning through fix
+    /// outages when `dead_reckoning=true` and crossings of `geofences`.
+    /// Headings, courses and wind directions always come in both the true
+    /// and the magnetic frame once the variation is known.
+    pub fn connect(&self, config: &DataLinkConfig) -> DataLinkResult<Box<dyn DataLinkReceiver>> {
+        let resolved;
+        let config = if has_credential_references(&config.parameters) {
+            resolved = self.resolve_credentials(config)?;
+            &resolved
+        } else {
+            config
+        };
+
+        let mut provider = self.create(Self::key_for(config))?;
+        if config.parameters.get(VALIDATE_PARAM).is_some_and(|value| value == "true") {
+            provider = Box::new(ValidatingReceiver::new(provider, SchemaRegistry::with_defaults()));
+        }
+        if config.parameters.contains_key(SMOOTHING_PARAM) {
+            provider = Box::new(SmoothingReceiver::new(provider));
+        }
+        if config.parameters.get(DEAD_RECKONING_PARAM).is_some_and(|value| value == "true") {
+            provider = Box::new(DeadReckoningReceiver::new(provider));
+        }
+        if config.parameters.contains_key(GEOFENCES_PARAM) {
+            provider = Box::new(GeofenceReceiver::new(provider));
+        }
+        provider = Box::new(VariationReceiver::new(provider));
+        if RateLimiter::from_config(config)?.is_active() {
+            provider = Box::new(RateLimitedReceiver::new(provider));
+        }
+        provider.connect(config)?;
+        Ok(provider)
+    }
+
+    /// A copy of the configuration with credential references replaced by their secrets
+    pub fn resolve_credentials(&self, config: &DataLinkConfig) -> DataLinkResult<DataLinkConfig> {
+        let store = self.credentials.as_ref().ok_or_else(|| {
+            DataLinkError::InvalidConfig("Configuration references credentials but no credential store is set".to_string())
+        })?;
+        let mut resolved = config.clone();
+        resolved.parameters = store
+            .resolve_parameters(&config.parameters)
+            .map_err(|e| DataLinkError::InvalidConfig(e.to_string()))?;
+        Ok(resolved)
+    }
+}
+
+impl Default for ProviderRegistry {
+    fn default() -> Self {
+        Self::with_defaults()
+    }
+}
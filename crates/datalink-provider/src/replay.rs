@@ -0,0 +1,212 @@
+//! File replay pacing
+//!
+//! Recorded NMEA files replay one line per `1 / replay_speed` seconds by
+//! default. With `replay_timing=timestamps` the gaps are reproduced from the
+//! UTC times in RMC, GGA and ZDA sentences instead, so a burst of sentences
+//! from one fix arrives together and a one-second receiver plays back at one
+//! fix per second. Loggers that record their own receive times can write
+//! them to a side file, one seconds value per line of the recording, named
+//! with `timing_log`. `loop=true` starts the file over at its end. Either
+//! file may be gzip or zstd compressed.
+
+use std::time::{Duration, UNIX_EPOCH};
+use log::info;
+use serde::{Deserialize, Serialize};
+use datalink::{utc_from_parts, DataLinkConfig, DataLinkError, DataLinkResult};
+use crate::compression::{open_recording, RecordingLines};
+
+/// Longest pause reproduced from timestamps; longer gaps in a recording
+/// (the logger was paused, the receiver lost its fix) are cut short
+pub const MAX_REPLAY_GAP: Duration = Duration::from_secs(10);
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// How the pause between replayed lines is chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ReplayTiming {
+    /// The same pause after every line
+    #[default]
+    Fixed,
+    /// Pauses taken from the recorded times
+    Timestamps,
+}
+
+/// Replay settings of a file source
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayOptions {
+    /// 1.0 = real time, 2.0 = twice as fast
+    pub speed: f64,
+    pub timing: ReplayTiming,
+    /// Side file with the receive time of each line, in seconds
+    pub timing_log: Option<String>,
+    /// Start over at the end of the file
+    pub looped: bool,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self::with_speed(1.0)
+    }
+}
+
+impl ReplayOptions {
+    pub fn with_speed(speed: f64) -> Self {
+        Self {
+            speed,
+            timing: ReplayTiming::Fixed,
+            timing_log: None,
+            looped: false,
+        }
+    }
+
+    /// Parse `replay_speed`, `replay_timing`, `timing_log` and `loop`
+    pub fn from_config(config: &DataLinkConfig) -> DataLinkResult<Self> {
+        let param = |key: &str| config.parameters.get(key).map(String::as_str);
+        let speed: f64 = param("replay_speed")
+            .unwrap_or("1.0")
+            .parse()
+            .ok()
+            .filter(|speed: &f64| *speed > 0.0)
+            .ok_or_else(|| DataLinkError::InvalidConfig("Invalid replay_speed".to_string()))?;
+        let timing_log = param("timing_log").map(str::to_string);
+        let timing = match param("replay_timing") {
+            Some("fixed") => ReplayTiming::Fixed,
+            Some("timestamps") => ReplayTiming::Timestamps,
+            None if timing_log.is_some() => ReplayTiming::Timestamps,
+            None => ReplayTiming::Fixed,
+            Some(other) => return Err(DataLinkError::InvalidConfig(format!("Unknown replay_timing: {}", other))),
+        };
+        Ok(Self {
+            speed,
+            timing,
+            timing_log,
+            looped: param("loop") == Some("true"),
+        })
+    }
+}
+
+/// Seconds since midnight UTC of an RMC, GGA or ZDA sentence
+pub fn sentence_time(sentence: &str) -> Option<f64> {
+    let body = sentence.strip_prefix('$')?;
+    let mut fields = body.split(['*', ',']);
+    if !matches!(fields.next()?.get(2..)?, "RMC" | "GGA" | "ZDA") {
+        return None;
+    }
+    let time = utc_from_parts(1970, 1, 1, fields.next()?)?;
+    Some(time.duration_since(UNIX_EPOCH).ok()?.as_secs_f64())
+}
+
+/// Chooses the pause before each replayed line
+#[derive(Debug, Clone)]
+pub struct ReplayPacer {
+    options: ReplayOptions,
+    recorded: Option<Vec<Option<f64>>>,
+    index: usize,
+    last_time: Option<f64>,
+}
+
+impl ReplayPacer {
+    /// `recorded` holds the side-channel times, one per line
+    pub fn new(options: ReplayOptions, recorded: Option<Vec<Option<f64>>>) -> Self {
+        Self {
+            options,
+            recorded,
+            index: 0,
+            last_time: None,
+        }
+    }
+
+    /// Pause to take before handing out `line`
+    pub fn delay_before(&mut self, line: &str) -> Duration {
+        let index = self.index;
+        self.index += 1;
+        if self.options.timing == ReplayTiming::Fixed {
+            return match index {
+                0 => Duration::ZERO,
+                _ => Duration::from_secs_f64(1.0 / self.options.speed),
+            };
+        }
+
+        let time = match &self.recorded {
+            Some(times) => times.get(index).copied().flatten(),
+            None => sentence_time(line),
+        };
+        // Lines without a time belong to the fix before them
+        let Some(time) = time else {
+            return Duration::ZERO;
+        };
+        let Some(last) = self.last_time.replace(time) else {
+            return Duration::ZERO;
+        };
+        let mut gap = time - last;
+        if self.recorded.is_none() && gap < -SECONDS_PER_DAY / 2.0 {
+            // Sentence times roll over at midnight
+            gap += SECONDS_PER_DAY;
+        }
+        Duration::from_secs_f64(gap.max(0.0) / self.options.speed).min(MAX_REPLAY_GAP)
+    }
+
+    /// Start pacing from the top of the file again
+    pub fn restart(&mut self) {
+        self.index = 0;
+        self.last_time = None;
+    }
+
+    /// Lines handed out since the last restart
+    pub fn lines_read(&self) -> usize {
+        self.index
+    }
+}
+
+/// A recorded file replayed line by line at its pace
+pub struct ReplayFile {
+    path: String,
+    lines: RecordingLines,
+    pacer: ReplayPacer,
+    looped: bool,
+}
+
+impl ReplayFile {
+    pub async fn open(path: &str, options: ReplayOptions) -> std::io::Result<Self> {
+        let recorded = match &options.timing_log {
+            Some(timing_log) => {
+                let mut times = Vec::new();
+                let mut lines = open_recording(timing_log).await?;
+                while let Some(line) = lines.next_line().await? {
+                    times.push(line.trim().parse().ok());
+                }
+                Some(times)
+            }
+            None => None,
+        };
+        Ok(Self {
+            path: path.to_string(),
+            lines: open_recording(path).await?,
+            looped: options.looped,
+            pacer: ReplayPacer::new(options, recorded),
+        })
+    }
+
+    /// The next line, once its pause has passed; `None` at the end of a
+    /// file that does not loop
+    pub async fn next_line(&mut self) -> std::io::Result<Option<String>> {
+        loop {
+            match self.lines.next_line().await? {
+                Some(line) => {
+                    let delay = self.pacer.delay_before(&line);
+                    if !delay.is_zero() {
+                        tokio::time::sleep(delay).await;
+                    }
+                    return Ok(Some(line));
+                }
+                // An empty file would spin forever
+                None if self.looped && self.pacer.lines_read() > 0 => {
+                    info!("Replay of {} reached the end, starting over", self.path);
+                    self.lines = open_recording(&self.path).await?;
+                    self.pacer.restart();
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! Runtime for provider receiver tasks
+//!
+//! Providers spawn their receivers on a tokio runtime and return from
+//! `connect` straight away. The runtime is the one handed to the provider,
+//! else the one the caller is running on. Callers with neither, such as Bevy
+//! systems on the task pool, share a single background runtime that lives
+//! for the rest of the process.
+
+use std::sync::OnceLock;
+use tokio::runtime::{Builder, Handle, Runtime};
+use datalink::{DataLinkError, DataLinkResult};
+
+/// Worker threads of the shared background runtime
+const BACKGROUND_WORKERS: usize = 2;
+
+/// The runtime to spawn receiver tasks on
+pub fn runtime_handle(preferred: Option<&Handle>) -> DataLinkResult<Handle> {
+    if let Some(handle) = preferred {
+        return Ok(handle.clone());
+    }
+    if let Ok(handle) = Handle::try_current() {
+        return Ok(handle);
+    }
+    background_runtime().map(|runtime| runtime.handle().clone())
+}
+
+fn background_runtime() -> DataLinkResult<&'static Runtime> {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    if let Some(runtime) = RUNTIME.get() {
+        return Ok(runtime);
+    }
+    let runtime = Builder::new_multi_thread()
+        .worker_threads(BACKGROUND_WORKERS)
+        .thread_name("datalink-provider")
+        .enable_all()
+        .build()
+        .map_err(|e| DataLinkError::ConnectionFailed(format!("Failed to create runtime: {}", e)))?;
+    // A racing caller may have won; its runtime is kept and ours dropped
+    Ok(RUNTIME.get_or_init(|| runtime))
+}
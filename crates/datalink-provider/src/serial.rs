@@ -0,0 +1,237 @@
+//! Serial port discovery and automatic baud-rate detection
+//!
+//! NMEA talkers usually run at 4800 baud and AIS receivers at 38400, but
+//! neither is guaranteed. With `baud_rate=auto` the port is opened at each of
+//! [`AUTO_BAUD_RATES`] in turn until lines with good checksums arrive; at a
+//! wrong rate the bytes come through as noise that never passes a checksum.
+//!
+//! [`list_serial_ports`] names the ports present and guesses from the USB
+//! IDs and product strings whether a GPS or an AIS receiver sits behind each.
+
+use std::time::Duration;
+use log::{debug, info};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio_serial::{SerialPortBuilderExt, SerialPortType, SerialStream};
+use datalink::{DataLinkError, DataLinkResult};
+use crate::nmea;
+
+/// `baud_rate` stored for a port whose rate is detected on connect
+pub const AUTO_BAUD: u32 = 0;
+
+/// Rates tried by auto-baud, most common first
+pub const AUTO_BAUD_RATES: [u32; 6] = [4800, 38400, 9600, 19200, 57600, 115_200];
+
+/// Valid sentences needed to lock onto a rate
+const LOCK_LINES: usize = 3;
+
+/// Invalid lines after which a rate is abandoned
+const MAX_BAD_LINES: usize = 8;
+
+/// How long to listen at each rate; talkers send at least once a second
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Parse a `baud_rate` parameter, where `auto` selects detection
+pub fn parse_baud_rate(value: Option<&str>, default: u32) -> DataLinkResult<u32> {
+    match value {
+        None => Ok(default),
+        Some("auto") => Ok(AUTO_BAUD),
+        Some(value) => value
+            .parse()
+            .map_err(|_| DataLinkError::InvalidConfig(format!("Invalid baud_rate: {}", value))),
+    }
+}
+
+/// `4800` or `auto`, for logs
+pub fn baud_rate_label(baud_rate: u32) -> String {
+    if baud_rate == AUTO_BAUD {
+        "auto".to_string()
+    } else {
+        baud_rate.to_string()
+    }
+}
+
+/// Whether `line` is a `$` or `!` sentence whose checksum matches
+pub fn has_valid_checksum(line: &str) -> bool {
+    let line = line.trim();
+    let Some(body) = line.strip_prefix('$').or_else(|| line.strip_prefix('!')) else {
+        return false;
+    };
+    let Some((body, sum)) = body.rsplit_once('*') else {
+        return false;
+    };
+    sum.len() == 2 && u8::from_str_radix(sum, 16).is_ok_and(|sum| sum == nmea::checksum(body))
+}
+
+/// Outcome of listening at one baud rate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeVerdict {
+    Locked,
+    Rejected,
+    Undecided,
+}
+
+/// Tallies the lines read at one baud rate
+#[derive(Debug, Default)]
+pub struct BaudProbe {
+    good: usize,
+    bad: usize,
+}
+
+impl BaudProbe {
+    /// Count a raw line; bytes need not be valid UTF-8 at a wrong rate
+    pub fn push(&mut self, line: &[u8]) -> ProbeVerdict {
+        let line = String::from_utf8_lossy(line);
+        if line.trim().is_empty() {
+            return ProbeVerdict::Undecided;
+        }
+        if has_valid_checksum(&line) {
+            self.good += 1;
+        } else {
+            self.bad += 1;
+        }
+        if self.good >= LOCK_LINES {
+            ProbeVerdict::Locked
+        } else if self.bad >= MAX_BAD_LINES {
+            ProbeVerdict::Rejected
+        } else {
+            ProbeVerdict::Undecided
+        }
+    }
+}
+
+/// Listen at `baud_rate`, returning the open port if it carries NMEA
+async fn probe(port: &str, baud_rate: u32) -> Result<Option<SerialStream>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut reader = BufReader::new(tokio_serial::new(port, baud_rate).open_native_async()?);
+    let mut probe = BaudProbe::default();
+    let mut line = Vec::new();
+    let verdict = tokio::time::timeout(PROBE_TIMEOUT, async {
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line).await? == 0 {
+                return Ok::<_, std::io::Error>(ProbeVerdict::Rejected);
+            }
+            match probe.push(&line) {
+                ProbeVerdict::Undecided => continue,
+                verdict => return Ok(verdict),
+            }
+        }
+    })
+    .await;
+
+    match verdict {
+        Ok(Ok(ProbeVerdict::Locked)) => Ok(Some(reader.into_inner())),
+        Ok(Err(e)) => {
+            debug!("Read error on {} at {} baud: {}", port, baud_rate, e);
+            Ok(None)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Open `port`, detecting the rate first when `baud_rate` is [`AUTO_BAUD`].
+/// Returns the port and the rate it was opened at.
+pub async fn open(port: &str, baud_rate: u32) -> Result<(SerialStream, u32), Box<dyn std::error::Error + Send + Sync>> {
+    if baud_rate != AUTO_BAUD {
+        return Ok((tokio_serial::new(port, baud_rate).open_native_async()?, baud_rate));
+    }
+
+    for baud_rate in AUTO_BAUD_RATES {
+        debug!("Probing {} at {} baud", port, baud_rate);
+        if let Some(stream) = probe(port, baud_rate).await? {
+            info!("Detected {} baud on {}", baud_rate, port);
+            return Ok((stream, baud_rate));
+        }
+    }
+    Err(format!("No valid NMEA on {} at any of {:?} baud", port, AUTO_BAUD_RATES).into())
+}
+
+/// USB vendor and product IDs of known receivers; `None` matches any product
+const KNOWN_DEVICES: [(u16, Option<u16>, DeviceClass); 3] = [
+    // u-blox GNSS receivers
+    (0x1546, None, DeviceClass::Gps),
+    // Garmin
+    (0x091e, None, DeviceClass::Gps),
+    // Prolific PL2303, the bridge in most USB GPS pucks
+    (0x067b, Some(0x2303), DeviceClass::Gps),
+];
+
+/// What a serial port most likely carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceClass {
+    Gps,
+    Ais,
+    Unknown,
+}
+
+impl DeviceClass {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DeviceClass::Gps => "GPS",
+            DeviceClass::Ais => "AIS",
+            DeviceClass::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+/// A serial port present on the system
+#[derive(Debug, Clone, PartialEq)]
+pub struct SerialPortEntry {
+    /// Device path or name, e.g. `/dev/ttyUSB0` or `COM3`
+    pub name: String,
+    pub vid: Option<u16>,
+    pub pid: Option<u16>,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    pub class: DeviceClass,
+}
+
+/// Guess the device class, trusting product strings over the ID table
+pub fn classify_device(vid: u16, pid: u16, description: &str) -> DeviceClass {
+    let description = description.to_uppercase();
+    if description.split(|c: char| !c.is_ascii_alphanumeric()).any(|word| word == "AIS") || description.contains("DAISY") {
+        return DeviceClass::Ais;
+    }
+    if ["GPS", "GNSS", "U-BLOX", "UBLOX"].iter().any(|keyword| description.contains(keyword)) {
+        return DeviceClass::Gps;
+    }
+    KNOWN_DEVICES
+        .iter()
+        .find(|(known_vid, known_pid, _)| *known_vid == vid && known_pid.is_none_or(|known_pid| known_pid == pid))
+        .map_or(DeviceClass::Unknown, |(_, _, class)| *class)
+}
+
+/// Serial ports on this system, USB devices first
+pub fn list_serial_ports() -> DataLinkResult<Vec<SerialPortEntry>> {
+    let ports = tokio_serial::available_ports()
+        .map_err(|e| DataLinkError::TransportError(format!("Failed to enumerate serial ports: {}", e)))?;
+    let mut entries: Vec<SerialPortEntry> = ports
+        .into_iter()
+        .map(|port| match port.port_type {
+            SerialPortType::UsbPort(usb) => {
+                let description = format!(
+                    "{} {}",
+                    usb.manufacturer.as_deref().unwrap_or_default(),
+                    usb.product.as_deref().unwrap_or_default()
+                );
+                SerialPortEntry {
+                    class: classify_device(usb.vid, usb.pid, &description),
+                    name: port.port_name,
+                    vid: Some(usb.vid),
+                    pid: Some(usb.pid),
+                    manufacturer: usb.manufacturer,
+                    product: usb.product,
+                }
+            }
+            _ => SerialPortEntry {
+                name: port.port_name,
+                vid: None,
+                pid: None,
+                manufacturer: None,
+                product: None,
+                class: DeviceClass::Unknown,
+            },
+        })
+        .collect();
+    entries.sort_by(|a, b| b.vid.is_some().cmp(&a.vid.is_some()).then_with(|| a.name.cmp(&b.name)));
+    Ok(entries)
+}
@@ -0,0 +1,343 @@
+//! GPS position smoothing
+//!
+//! Cheap GPS pucks wander by several meters from fix to fix, which makes the
+//! boat marker shake on the chart. [`SmoothingReceiver`] runs the positions,
+//! speeds and courses of `GPS_SENTENCE` messages through a filter and
+//! replaces them with the filtered values, keeping the receiver's own
+//! position as `raw_position`.
+//!
+//! `smoothing=alpha` blends each fix into the previous estimate by
+//! `smoothing_alpha` (0.3); lower is smoother but lags more. `smoothing=kalman`
+//! tracks position and velocity with a constant-velocity Kalman filter,
+//! tuned by `kalman_process_noise`, the expected acceleration in m/s² (0.5),
+//! and `kalman_measurement_noise`, the fix scatter in meters (5). It follows
+//! a steady course without lag and takes RMC speed and course as velocity
+//! measurements. Both start over after a gap of [`MAX_FIX_GAP`].
+
+use std::time::{Duration, SystemTime};
+use datalink::geo::{LocalTangentPlane, METERS_PER_NM};
+use datalink::{DataLinkConfig, DataLinkError, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataMessage, LinkStats, Value};
+
+/// Parameter choosing the filter, `alpha` or `kalman`
+pub const SMOOTHING_PARAM: &str = "smoothing";
+
+/// Fixes further apart than this restart the filter
+pub const MAX_FIX_GAP: Duration = Duration::from_secs(10);
+
+/// Distance from the local plane's origin at which it is moved to the boat
+const REORIGIN_M: f64 = 5_000.0;
+
+/// Scatter of RMC speed over ground, in m/s
+const VELOCITY_NOISE_MS: f64 = 0.3;
+
+const MS_PER_KNOT: f64 = METERS_PER_NM / 3600.0;
+
+/// Filter applied to GPS fixes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Smoothing {
+    /// Exponential smoothing with weight `alpha` for each new fix
+    Alpha { alpha: f64 },
+    /// Constant-velocity Kalman filter
+    Kalman { process_noise: f64, measurement_noise: f64 },
+}
+
+impl Smoothing {
+    /// The filter named by `smoothing`, if any
+    pub fn from_config(config: &DataLinkConfig) -> DataLinkResult<Option<Self>> {
+        let number = |key: &str, default: f64| -> DataLinkResult<f64> {
+            match config.parameters.get(key) {
+                Some(value) => value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|number| *number > 0.0)
+                    .ok_or_else(|| DataLinkError::InvalidConfig(format!("Invalid {}: {}", key, value))),
+                None => Ok(default),
+            }
+        };
+        match config.parameters.get(SMOOTHING_PARAM).map(String::as_str) {
+            None | Some("none") => Ok(None),
+            Some("alpha") => {
+                let alpha = number("smoothing_alpha", 0.3)?;
+                if alpha > 1.0 {
+                    return Err(DataLinkError::InvalidConfig("smoothing_alpha must be at most 1".to_string()));
+                }
+                Ok(Some(Self::Alpha { alpha }))
+            }
+            Some("kalman") => Ok(Some(Self::Kalman {
+                process_noise: number("kalman_process_noise", 0.5)?,
+                measurement_noise: number("kalman_measurement_noise", 5.0)?,
+            })),
+            Some(other) => Err(DataLinkError::InvalidConfig(format!("Unknown smoothing: {}", other))),
+        }
+    }
+}
+
+/// Position and velocity along one axis of the local plane
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Axis {
+    position: f64,
+    velocity: f64,
+    /// Covariance [[pp, pv], [pv, vv]]
+    covariance: [f64; 3],
+}
+
+impl Axis {
+    fn new(position: f64, measurement_noise: f64) -> Self {
+        Self {
+            position,
+            velocity: 0.0,
+            // Velocity unknown at the first fix: a boat at up to ~10 m/s
+            covariance: [measurement_noise.powi(2), 0.0, 100.0],
+        }
+    }
+
+    fn predict(&mut self, dt: f64, process_noise: f64) {
+        let [pp, pv, vv] = self.covariance;
+        let q = process_noise.powi(2);
+        self.position += self.velocity * dt;
+        self.covariance = [
+            pp + 2.0 * dt * pv + dt * dt * vv + q * dt.powi(4) / 4.0,
+            pv + dt * vv + q * dt.powi(3) / 2.0,
+            vv + q * dt * dt,
+        ];
+    }
+
+    fn update_position(&mut self, measured: f64, noise: f64) {
+        let [pp, pv, vv] = self.covariance;
+        let innovation = measured - self.position;
+        let s = pp + noise * noise;
+        let (k_p, k_v) = (pp / s, pv / s);
+        self.position += k_p * innovation;
+        self.velocity += k_v * innovation;
+        self.covariance = [(1.0 - k_p) * pp, (1.0 - k_p) * pv, vv - k_v * pv];
+    }
+
+    fn update_velocity(&mut self, measured: f64, noise: f64) {
+        let [pp, pv, vv] = self.covariance;
+        let innovation = measured - self.velocity;
+        let s = vv + noise * noise;
+        let (k_p, k_v) = (pv / s, vv / s);
+        self.position += k_p * innovation;
+        self.velocity += k_v * innovation;
+        self.covariance = [pp - k_p * pv, (1.0 - k_v) * pv, (1.0 - k_v) * vv];
+    }
+}
+
+/// Smoothed track, in meters east and north of `plane`'s origin
+#[derive(Debug, Clone)]
+struct Track {
+    plane: LocalTangentPlane,
+    east: Axis,
+    north: Axis,
+    /// Speed in knots and course in degrees, for the alpha filter
+    speed: Option<f64>,
+    course: Option<f64>,
+    last_fix: SystemTime,
+}
+
+/// Smooths the fixes of one GPS feed
+#[derive(Debug, Clone)]
+pub struct PositionSmoother {
+    smoothing: Smoothing,
+    track: Option<Track>,
+}
+
+impl PositionSmoother {
+    pub fn new(smoothing: Smoothing) -> Self {
+        Self { smoothing, track: None }
+    }
+
+    /// Smoothed position, while tracking
+    pub fn position(&self) -> Option<(f64, f64)> {
+        self.track.as_ref().map(|track| track.plane.to_position(track.east.position, track.north.position))
+    }
+
+    /// Replace the position, speed and course of a fix with filtered values.
+    /// Other messages, fixes without a position or validity and dead-reckoned estimates
+    /// pass through untouched.
+    pub fn smooth(&mut self, message: DataMessage) -> DataMessage {
+        let no_fix = message.get_i64("fix_quality") == Some(0) || message.get_str("status") == Some("V");
+        if message.message_type != "GPS_SENTENCE" || no_fix || message.get_str("mode") == Some("DR") {
+            return message;
+        }
+        let Some(measured) = message.get_lat_lon("position") else {
+            return message;
+        };
+        // The local clock; receiver UTC in RMC may be offset from it
+        let time = message.received_at;
+        let speed = message.get_f64("speed");
+        let course = message.get_angle("course");
+
+        let restart = self.track.as_ref().is_none_or(|track| {
+            time.duration_since(track.last_fix).map_or(true, |gap| gap > MAX_FIX_GAP)
+        });
+        if restart {
+            self.track = Some(self.start(measured, speed, course, time));
+        } else if let Some(track) = &mut self.track {
+            let dt = time.duration_since(track.last_fix).unwrap_or_default().as_secs_f64();
+            track.last_fix = time;
+            Self::update(self.smoothing, track, measured, dt, speed, course);
+        }
+
+        let Some(track) = &mut self.track else {
+            return message;
+        };
+        let (east, north) = (track.east.position, track.north.position);
+        if east.hypot(north) > REORIGIN_M {
+            let origin = track.plane.to_position(east, north);
+            track.plane = LocalTangentPlane::new(origin);
+            track.east.position = 0.0;
+            track.north.position = 0.0;
+        }
+        let (latitude, longitude) = track.plane.to_position(track.east.position, track.north.position);
+        let mut message = message
+            .with_data("raw_position", Value::LatLon { lat: measured.0, lon: measured.1 })
+            .with_data("position", Value::LatLon { lat: latitude, lon: longitude });
+        let (speed, course) = match self.smoothing {
+            Smoothing::Alpha { .. } => (track.speed.filter(|_| speed.is_some()), track.course.filter(|_| course.is_some())),
+            Smoothing::Kalman { .. } => {
+                let (east, north) = (track.east.velocity, track.north.velocity);
+                (
+                    speed.map(|_| east.hypot(north) / MS_PER_KNOT),
+                    course.map(|measured| match east.hypot(north) {
+                        // Too slow for the filter to know which way the boat points
+                        ms if ms < 0.1 => measured,
+                        _ => east.atan2(north).to_degrees().rem_euclid(360.0),
+                    }),
+                )
+            }
+        };
+        if let Some(speed) = speed {
+            message = message.with_data("speed", speed);
+        }
+        if let Some(course) = course {
+            message = message.with_data("course", Value::Angle(course));
+        }
+        message
+    }
+
+    fn start(&self, measured: (f64, f64), speed: Option<f64>, course: Option<f64>, time: SystemTime) -> Track {
+        let noise = match self.smoothing {
+            Smoothing::Alpha { .. } => 0.0,
+            Smoothing::Kalman { measurement_noise, .. } => measurement_noise,
+        };
+        let mut track = Track {
+            plane: LocalTangentPlane::new(measured),
+            east: Axis::new(0.0, noise),
+            north: Axis::new(0.0, noise),
+            speed,
+            course,
+            last_fix: time,
+        };
+        if let (Smoothing::Kalman { .. }, Some(speed), Some(course)) = (self.smoothing, speed, course) {
+            let (east, north) = velocity(speed, course);
+            track.east.update_velocity(east, VELOCITY_NOISE_MS);
+            track.north.update_velocity(north, VELOCITY_NOISE_MS);
+        }
+        track
+    }
+
+    fn update(smoothing: Smoothing, track: &mut Track, measured: (f64, f64), dt: f64, speed: Option<f64>, course: Option<f64>) {
+        let (east, north) = track.plane.to_local(measured);
+        // GGA and RMC of the same fix carry the same position; take it once
+        match smoothing {
+            Smoothing::Alpha { alpha } => {
+                if dt > 0.0 {
+                    track.east.position += alpha * (east - track.east.position);
+                    track.north.position += alpha * (north - track.north.position);
+                }
+                if let Some(speed) = speed {
+                    track.speed = Some(track.speed.map_or(speed, |last| last + alpha * (speed - last)));
+                }
+                if let Some(course) = course {
+                    track.course = Some(track.course.map_or(course, |last| {
+                        let turn = (course - last + 540.0).rem_euclid(360.0) - 180.0;
+                        (last + alpha * turn).rem_euclid(360.0)
+                    }));
+                }
+            }
+            Smoothing::Kalman { process_noise, measurement_noise } => {
+                for axis in [&mut track.east, &mut track.north] {
+                    axis.predict(dt, process_noise);
+                }
+                if dt > 0.0 {
+                    track.east.update_position(east, measurement_noise);
+                    track.north.update_position(north, measurement_noise);
+                }
+                if let (Some(speed), Some(course)) = (speed, course) {
+                    let (east, north) = velocity(speed, course);
+                    track.east.update_velocity(east, VELOCITY_NOISE_MS);
+                    track.north.update_velocity(north, VELOCITY_NOISE_MS);
+                }
+            }
+        }
+    }
+}
+
+/// East and north velocity in m/s
+fn velocity(speed_knots: f64, course_deg: f64) -> (f64, f64) {
+    let speed = speed_knots * MS_PER_KNOT;
+    let course = course_deg.to_radians();
+    (speed * course.sin(), speed * course.cos())
+}
+
+/// Receiver wrapper smoothing GPS fixes
+pub struct SmoothingReceiver<R: DataLinkReceiver> {
+    inner: R,
+    smoother: Option<PositionSmoother>,
+}
+
+impl<R: DataLinkReceiver> SmoothingReceiver<R> {
+    /// Wrap `inner`; the filter is read from the config passed to `connect`
+    pub fn new(inner: R) -> Self {
+        Self { inner, smoother: None }
+    }
+
+    pub fn smoother(&self) -> Option<&PositionSmoother> {
+        self.smoother.as_ref()
+    }
+
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+}
+
+impl<R: DataLinkReceiver> DataLinkReceiver for SmoothingReceiver<R> {
+    fn status(&self) -> DataLinkStatus {
+        self.inner.status()
+    }
+
+    fn receive_message(&mut self) -> DataLinkResult<Option<DataMessage>> {
+        let message = self.inner.receive_message()?;
+        Ok(match &mut self.smoother {
+            Some(smoother) => message.map(|message| smoother.smooth(message)),
+            None => message,
+        })
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        self.smoother = Smoothing::from_config(config)?.map(PositionSmoother::new);
+        self.inner.connect(config)
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        if let Some(smoother) = &mut self.smoother {
+            smoother.track = None;
+        }
+        self.inner.disconnect()
+    }
+
+    /// A new filter setting starts over; the same one keeps its track
+    fn reconfigure(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        let smoothing = Smoothing::from_config(config)?;
+        self.inner.reconfigure(config)?;
+        if self.smoother.as_ref().map(|smoother| smoother.smoothing) != smoothing {
+            self.smoother = smoothing.map(PositionSmoother::new);
+        }
+        Ok(())
+    }
+
+    fn stats(&self) -> LinkStats {
+        self.inner.stats()
+    }
+}
@@ -0,0 +1,75 @@
+//! Line counters kept by providers
+//!
+//! Receiver tasks hand every line they read to [`SharedStats::observe`]
+//! together with what the parser made of it. A line that did not parse is a
+//! checksum failure when it carries a checksum that does not match, and
+//! otherwise counts as unsupported under its sentence address. Lines the
+//! provider's parser turns down are offered to the installed
+//! [`SentenceParsers`] first. Observed lines also go to the provider's
+//! [`RawLog`] when one is set.
+
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::SystemTime;
+use log::error;
+use datalink::{wall_clock_now, DataMessage, LinkStats};
+use crate::proprietary::SentenceParsers;
+use crate::raw_log::RawLog;
+use crate::serial::has_valid_checksum;
+
+/// Counters shared between a provider and its receiver task
+#[derive(Debug, Clone, Default)]
+pub struct SharedStats {
+    counters: Arc<Mutex<LinkStats>>,
+    raw_log: Arc<Mutex<Option<RawLog>>>,
+}
+
+impl SharedStats {
+    pub fn get(&self) -> LinkStats {
+        self.counters.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    pub fn update(&self, f: impl FnOnce(&mut LinkStats)) {
+        f(&mut self.counters.lock().unwrap_or_else(PoisonError::into_inner));
+    }
+
+    /// Log raw input to `raw_log`, or stop logging with `None`
+    pub fn set_raw_log(&self, raw_log: Option<RawLog>) {
+        *self.raw_log.lock().unwrap_or_else(PoisonError::into_inner) = raw_log;
+    }
+
+    /// Append `line` to the raw log, if there is one. The log is closed after
+    /// a failed write rather than failing every line after it.
+    pub fn log_raw(&self, line: &str) {
+        let mut raw_log = self.raw_log.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(log) = raw_log.as_mut() {
+            if let Err(e) = log.write_line(line, SystemTime::now()) {
+                error!("Raw log {} failed, no longer logging: {}", log.path(), e);
+                *raw_log = None;
+            }
+        }
+    }
+
+    /// Count `line`, given the message parsed from it, and pass the message on
+    pub fn observe(&self, line: &str, parsed: Option<DataMessage>) -> Option<DataMessage> {
+        let line = line.trim();
+        if line.is_empty() {
+            return parsed;
+        }
+        self.log_raw(line);
+        let parsed = parsed.or_else(|| SentenceParsers::global().parse(line, wall_clock_now()));
+        self.update(|stats| match &parsed {
+            Some(_) => stats.record_parsed(),
+            None if line.contains('*') && !has_valid_checksum(line) => stats.record_checksum_failure(),
+            None => stats.record_unsupported(sentence_address(line)),
+        });
+        parsed
+    }
+}
+
+/// Talker and sentence type of an NMEA line, e.g. `GNRMC`
+pub fn sentence_address(line: &str) -> &str {
+    match line.strip_prefix('$').or_else(|| line.strip_prefix('!')) {
+        Some(body) => body.split([',', '*']).next().unwrap_or_default(),
+        None => "not NMEA",
+    }
+}
@@ -0,0 +1,349 @@
+//! Shared transport connections
+//!
+//! AIS, GPS and instrument sentences often arrive multiplexed on one serial
+//! port or TCP feed. The [`TransportHub`] opens each physical connection once
+//! and fans every received line out to all of its subscribers, so providers
+//! connected with `shared=true` read from the same socket instead of each
+//! opening their own. A connection closes when its last subscription drops.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError, Weak};
+use std::time::Duration;
+use log::{error, info};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::Notify;
+use datalink::{DataLinkConfig, DataLinkError, DataLinkResult};
+use crate::bluetooth::{self, BluetoothAddress, DEFAULT_RFCOMM_CHANNEL};
+use crate::replay::{ReplayFile, ReplayOptions};
+use crate::serial;
+
+/// Parameter that routes a provider through the [`TransportHub`]: `shared=true`
+pub const SHARED_PARAM: &str = "shared";
+
+/// Lines buffered per subscriber before new ones are dropped
+const SUBSCRIBER_QUEUE_LEN: usize = 1000;
+
+/// Whether a configuration asks for a shared transport
+pub fn is_shared(config: &DataLinkConfig) -> bool {
+    config.parameters.get(SHARED_PARAM).is_some_and(|value| value == "true")
+}
+
+/// Physical connection carrying NMEA lines
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransportConfig {
+    Serial { port: String, baud_rate: u32 },
+    Tcp { host: String, port: u16 },
+    Udp { bind_addr: String, port: u16 },
+    File { path: String, replay: ReplayOptions },
+    Bluetooth { address: String, channel: u8 },
+}
+
+impl TransportConfig {
+    /// Parse the `connection_type` parameters shared by the NMEA providers
+    pub fn from_config(config: &DataLinkConfig) -> DataLinkResult<Self> {
+        let param = |key: &str| config.parameters.get(key).map(String::as_str);
+        let required = |key: &str| {
+            param(key).ok_or_else(|| DataLinkError::InvalidConfig(format!("Missing {} for shared transport", key)))
+        };
+        let port_number = || {
+            required("port")?
+                .parse::<u16>()
+                .map_err(|_| DataLinkError::InvalidConfig("Invalid port number".to_string()))
+        };
+
+        match required("connection_type")? {
+            "serial" => Ok(TransportConfig::Serial {
+                port: required("port")?.to_string(),
+                baud_rate: serial::parse_baud_rate(param("baud_rate"), 4800)?,
+            }),
+            "tcp" => Ok(TransportConfig::Tcp {
+                host: required("host")?.to_string(),
+                port: port_number()?,
+            }),
+            "udp" => Ok(TransportConfig::Udp {
+                bind_addr: param("bind_addr").unwrap_or("0.0.0.0").to_string(),
+                port: port_number()?,
+            }),
+            "file" => Ok(TransportConfig::File {
+                path: required("path")?.to_string(),
+                replay: ReplayOptions::from_config(config)?,
+            }),
+            "bluetooth" => Ok(TransportConfig::Bluetooth {
+                address: required("address")?.parse::<BluetoothAddress>()?.to_string(),
+                channel: param("channel")
+                    .map_or(Ok(DEFAULT_RFCOMM_CHANNEL), str::parse)
+                    .map_err(|_| DataLinkError::InvalidConfig("Invalid RFCOMM channel".to_string()))?,
+            }),
+            other => Err(DataLinkError::InvalidConfig(format!("Unsupported connection type: {}", other))),
+        }
+    }
+
+    /// Identity of the physical connection. Settings that cannot differ
+    /// between two users of the same device (baud rate, replay speed) are
+    /// left out, so the first subscriber's settings win.
+    pub fn key(&self) -> String {
+        match self {
+            TransportConfig::Serial { port, .. } => format!("serial:{}", port),
+            TransportConfig::Tcp { host, port } => format!("tcp:{}:{}", host, port),
+            TransportConfig::Udp { bind_addr, port } => format!("udp:{}:{}", bind_addr, port),
+            TransportConfig::File { path, .. } => format!("file:{}", path),
+            TransportConfig::Bluetooth { address, .. } => format!("bluetooth:{}", address),
+        }
+    }
+}
+
+/// State shared between a transport's reader thread and its subscriptions
+#[derive(Default)]
+struct TransportState {
+    subscribers: Mutex<Vec<SyncSender<String>>>,
+    stop: Notify,
+    closed: AtomicBool,
+}
+
+impl TransportState {
+    fn subscribers(&self) -> MutexGuard<'_, Vec<SyncSender<String>>> {
+        self.subscribers.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Send a line to every subscriber, forgetting the ones that went away
+    fn publish(&self, line: &str) {
+        if line.is_empty() {
+            return;
+        }
+        self.subscribers().retain(|subscriber| match subscriber.try_send(line.to_string()) {
+            Ok(()) | Err(TrySendError::Full(_)) => true,
+            Err(TrySendError::Disconnected(_)) => false,
+        });
+    }
+}
+
+/// Keeps a transport open; dropping the last one stops the reader
+struct TransportHandle {
+    key: String,
+    state: Arc<TransportState>,
+}
+
+impl Drop for TransportHandle {
+    fn drop(&mut self) {
+        info!("Closing shared transport {}", self.key);
+        self.state.stop.notify_one();
+    }
+}
+
+/// One reader's view of a shared transport
+pub struct TransportSubscription {
+    handle: Arc<TransportHandle>,
+    /// Behind a mutex so providers holding a subscription stay `Sync`
+    lines: Mutex<Receiver<String>>,
+}
+
+impl TransportSubscription {
+    /// Key of the connection this subscription reads from
+    pub fn key(&self) -> &str {
+        &self.handle.key
+    }
+
+    /// Lines received since the last call
+    pub fn drain(&self) -> Vec<String> {
+        self.lines().try_iter().collect()
+    }
+
+    /// Wait up to `timeout` for the next line
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<String> {
+        self.lines().recv_timeout(timeout).ok()
+    }
+
+    /// Whether the underlying connection has ended
+    pub fn is_closed(&self) -> bool {
+        self.handle.state.closed.load(Ordering::Relaxed)
+    }
+
+    fn lines(&self) -> MutexGuard<'_, Receiver<String>> {
+        self.lines.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Open shared transports keyed by [`TransportConfig::key`]
+#[derive(Default)]
+pub struct TransportHub {
+    transports: Mutex<HashMap<String, Weak<TransportHandle>>>,
+}
+
+impl TransportHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The process-wide hub used by providers connected with `shared=true`
+    pub fn global() -> &'static TransportHub {
+        static HUB: OnceLock<TransportHub> = OnceLock::new();
+        HUB.get_or_init(TransportHub::new)
+    }
+
+    /// Subscribe to the connection, opening it if nobody holds it yet
+    pub fn subscribe(&self, config: &TransportConfig) -> DataLinkResult<TransportSubscription> {
+        let key = config.key();
+        let (sender, lines) = sync_channel(SUBSCRIBER_QUEUE_LEN);
+        let open = {
+            let mut transports = self.transports.lock().unwrap_or_else(PoisonError::into_inner);
+            transports.retain(|_, handle| handle.strong_count() > 0);
+            open_handle(&transports, &key)
+        };
+        if let Some(handle) = open {
+            handle.state.subscribers().push(sender);
+            return Ok(TransportSubscription { handle, lines: Mutex::new(lines) });
+        }
+
+        // Connect without holding the lock, so subscribers of other
+        // connections are not kept waiting on this one
+        let state = Arc::new(TransportState::default());
+        let connected = spawn_reader(config.clone(), Arc::clone(&state));
+
+        let mut transports = self.transports.lock().unwrap_or_else(PoisonError::into_inner);
+        // Another subscriber may have opened the connection meanwhile; the
+        // reader opened here, if any, stops as its handle drops
+        if let Some(handle) = open_handle(&transports, &key) {
+            if connected.is_ok() {
+                drop(TransportHandle { key, state });
+            }
+            handle.state.subscribers().push(sender);
+            return Ok(TransportSubscription { handle, lines: Mutex::new(lines) });
+        }
+        connected?;
+        state.subscribers().push(sender);
+        info!("Opened shared transport {}", key);
+
+        let handle = Arc::new(TransportHandle { key: key.clone(), state });
+        transports.insert(key, Arc::downgrade(&handle));
+        Ok(TransportSubscription { handle, lines: Mutex::new(lines) })
+    }
+
+    /// Number of connections currently open
+    pub fn open_transports(&self) -> usize {
+        let transports = self.transports.lock().unwrap_or_else(PoisonError::into_inner);
+        transports.values().filter(|handle| handle.strong_count() > 0).count()
+    }
+}
+
+/// The connection under `key` if it is held and still open
+fn open_handle(transports: &HashMap<String, Weak<TransportHandle>>, key: &str) -> Option<Arc<TransportHandle>> {
+    transports
+        .get(key)
+        .and_then(Weak::upgrade)
+        .filter(|handle| !handle.state.closed.load(Ordering::Relaxed))
+}
+
+enum Connection {
+    Lines(Box<dyn AsyncBufRead + Unpin + Send>),
+    Replay(Box<ReplayFile>),
+    Datagrams(UdpSocket),
+}
+
+async fn open(config: &TransportConfig) -> Result<Connection, Box<dyn std::error::Error + Send + Sync>> {
+    Ok(match config {
+        TransportConfig::Serial { port, baud_rate } => {
+            Connection::Lines(Box::new(BufReader::new(serial::open(port, *baud_rate).await?.0)))
+        }
+        TransportConfig::Tcp { host, port } => {
+            Connection::Lines(Box::new(BufReader::new(TcpStream::connect(format!("{}:{}", host, port)).await?)))
+        }
+        TransportConfig::Udp { bind_addr, port } => {
+            Connection::Datagrams(UdpSocket::bind(format!("{}:{}", bind_addr, port)).await?)
+        }
+        TransportConfig::File { path, replay } => Connection::Replay(Box::new(ReplayFile::open(path, replay.clone()).await?)),
+        TransportConfig::Bluetooth { address, channel } => {
+            Connection::Lines(Box::new(BufReader::new(bluetooth::connect(address.parse()?, *channel).await?)))
+        }
+    })
+}
+
+/// Read lines until the connection ends or the last subscription drops
+async fn run(connection: Connection, state: &TransportState) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    match connection {
+        Connection::Lines(mut reader) => {
+            let mut line = String::new();
+            loop {
+                tokio::select! {
+                    _ = state.stop.notified() => break,
+                    result = reader.read_line(&mut line) => {
+                        if result? == 0 {
+                            info!("Shared transport reached end of stream");
+                            break;
+                        }
+                        state.publish(line.trim());
+                        line.clear();
+                    }
+                }
+            }
+        }
+        Connection::Replay(mut replay) => loop {
+            tokio::select! {
+                _ = state.stop.notified() => break,
+                result = replay.next_line() => match result? {
+                    Some(line) => state.publish(line.trim()),
+                    None => {
+                        info!("Shared transport replay reached end of file");
+                        break;
+                    }
+                },
+            }
+        },
+        Connection::Datagrams(socket) => {
+            let mut buf = [0; 1024];
+            loop {
+                tokio::select! {
+                    _ = state.stop.notified() => break,
+                    result = socket.recv(&mut buf) => {
+                        let len = result?;
+                        for line in String::from_utf8_lossy(&buf[..len]).lines() {
+                            state.publish(line.trim());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Open the connection on a dedicated thread, returning once it is
+/// connected or failed
+fn spawn_reader(config: TransportConfig, state: Arc<TransportState>) -> DataLinkResult<()> {
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel::<Result<(), String>>();
+    let key = config.key();
+
+    std::thread::Builder::new()
+        .name(format!("transport {}", key))
+        .spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(format!("Failed to create runtime: {}", e)));
+                    return;
+                }
+            };
+            runtime.block_on(async {
+                let connection = match open(&config).await {
+                    Ok(connection) => connection,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e.to_string()));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(()));
+                if let Err(e) = run(connection, &state).await {
+                    error!("Shared transport {} error: {}", config.key(), e);
+                }
+            });
+            state.closed.store(true, Ordering::Relaxed);
+        })
+        .map_err(|e| DataLinkError::ConnectionFailed(format!("Failed to start transport thread: {}", e)))?;
+
+    ready_rx
+        .recv()
+        .map_err(|_| DataLinkError::ConnectionFailed(format!("Transport {} stopped before connecting", key)))?
+        .map_err(|e| DataLinkError::ConnectionFailed(format!("{}: {}", key, e)))
+}
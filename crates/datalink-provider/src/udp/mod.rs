@@ -0,0 +1,114 @@
+use std::net::UdpSocket;
+use bytes::Bytes;
+use log::{info, warn};
+use datalink::{DataLinkConfig, DataLinkError, DataLinkResult, DataLinkStatus, DataLinkTransmitter, DataMessage};
+
+/// Default port for NMEA 0183 over UDP
+pub const DEFAULT_NMEA_UDP_PORT: u16 = 10110;
+
+/// Transmits raw message payloads (NMEA sentences) as UDP datagrams.
+///
+/// Configured with `host` (default `255.255.255.255`, i.e. broadcast),
+/// `port` (default 10110) and an optional local `bind_addr`.
+pub struct UdpDataLinkTransmitter {
+    status: DataLinkStatus,
+    socket: Option<UdpSocket>,
+    target: Option<String>,
+}
+
+impl UdpDataLinkTransmitter {
+    pub fn new() -> Self {
+        Self {
+            status: DataLinkStatus::Disconnected,
+            socket: None,
+            target: None,
+        }
+    }
+
+    /// Destination address the transmitter sends to, once connected
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+
+    /// Payload with a trailing CRLF, as expected by NMEA listeners
+    fn frame_payload(message: &DataMessage) -> Bytes {
+        if message.payload.ends_with(b"\r\n") {
+            return message.payload.clone();
+        }
+        let mut datagram = message.payload.to_vec();
+        while datagram.last().is_some_and(|b| *b == b'\n' || *b == b'\r') {
+            datagram.pop();
+        }
+        datagram.extend_from_slice(b"\r\n");
+        datagram.into()
+    }
+}
+
+impl Default for UdpDataLinkTransmitter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DataLinkTransmitter for UdpDataLinkTransmitter {
+    fn status(&self) -> DataLinkStatus {
+        self.status.clone()
+    }
+
+    fn send_message(&mut self, message: &DataMessage) -> DataLinkResult<()> {
+        let (Some(socket), Some(target)) = (&self.socket, &self.target) else {
+            return Err(DataLinkError::ConnectionFailed("Not connected".to_string()));
+        };
+
+        if message.payload.is_empty() {
+            return Err(DataLinkError::InvalidConfig(format!(
+                "Message {} has no raw payload to transmit",
+                message.message_type
+            )));
+        }
+
+        socket
+            .send_to(&Self::frame_payload(message), target.as_str())
+            .map_err(|e| DataLinkError::TransportError(format!("UDP send to {} failed: {}", target, e)))?;
+        Ok(())
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        let host = config
+            .parameters
+            .get("host")
+            .cloned()
+            .unwrap_or_else(|| "255.255.255.255".to_string());
+        let port = match config.parameters.get("port") {
+            Some(port) => port
+                .parse::<u16>()
+                .map_err(|_| DataLinkError::InvalidConfig("Invalid port".to_string()))?,
+            None => DEFAULT_NMEA_UDP_PORT,
+        };
+        let bind_addr = config
+            .parameters
+            .get("bind_addr")
+            .cloned()
+            .unwrap_or_else(|| "0.0.0.0:0".to_string());
+
+        let socket = UdpSocket::bind(&bind_addr)
+            .map_err(|e| DataLinkError::ConnectionFailed(format!("Failed to bind UDP socket {}: {}", bind_addr, e)))?;
+        if let Err(e) = socket.set_broadcast(true) {
+            warn!("Failed to enable UDP broadcast: {}", e);
+        }
+
+        let target = format!("{}:{}", host, port);
+        info!("UDP transmitter sending to {}", target);
+        self.socket = Some(socket);
+        self.target = Some(target);
+        self.status = DataLinkStatus::Connected;
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        self.socket = None;
+        self.target = None;
+        self.status = DataLinkStatus::Disconnected;
+        Ok(())
+    }
+}
@@ -0,0 +1,204 @@
+//! Magnetic variation and the true/magnetic reference frames
+//!
+//! Compasses report magnetic headings, GPS receivers true courses, and wind
+//! sensors either. [`VariationReceiver`] fills in the missing frame on own-ship
+//! messages so every heading, course and wind direction arrives both ways:
+//! `heading_true`/`heading_magnetic`, `course`/`course_magnetic` and
+//! `wind_direction_true`/`wind_direction_magnetic`. Messages it completes also
+//! carry the `variation` used and its `variation_source`.
+//!
+//! The variation is learned from RMC and HDG sentences into a
+//! [`MagneticVariation`] shared by all links, so the heading sensor's link
+//! can use what the GPS reported. A manual override, either on the shared
+//! value or as a link's `variation` parameter (`-2.5` or `2.5W`), takes
+//! precedence, including over the variation a sentence carries itself.
+
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use datalink::{DataLinkConfig, DataLinkError, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataMessage, LinkStats, Value};
+
+/// Parameter overriding the variation of a link, east positive
+pub const VARIATION_PARAM: &str = "variation";
+
+/// True and magnetic keys of the angles kept in both frames
+const FRAME_PAIRS: [(&str, &str); 3] = [
+    ("heading_true", "heading_magnetic"),
+    ("course", "course_magnetic"),
+    ("wind_direction_true", "wind_direction_magnetic"),
+];
+
+/// Parse a variation given as signed degrees or with an `E`/`W` suffix
+pub fn parse_variation(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let (magnitude, sign) = match text.char_indices().last()? {
+        (end, 'E' | 'e') => (&text[..end], 1.0),
+        (end, 'W' | 'w') => (&text[..end], -1.0),
+        _ => (text, 1.0),
+    };
+    let variation = magnitude.trim().parse::<f64>().ok()? * sign;
+    (variation.is_finite() && variation.abs() <= 180.0).then_some(variation)
+}
+
+#[derive(Debug, Default)]
+struct VariationState {
+    manual: Option<f64>,
+    /// Last reported variation and the sentence it came from
+    observed: Option<(f64, &'static str)>,
+}
+
+/// Variation known across links
+#[derive(Debug, Clone, Default)]
+pub struct MagneticVariation {
+    state: Arc<Mutex<VariationState>>,
+}
+
+impl MagneticVariation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The variation the registry's links share
+    pub fn global() -> &'static MagneticVariation {
+        static VARIATION: OnceLock<MagneticVariation> = OnceLock::new();
+        VARIATION.get_or_init(MagneticVariation::new)
+    }
+
+    /// Override the reported variation, or go back to it with `None`
+    pub fn set_manual(&self, variation: Option<f64>) {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner).manual = variation;
+    }
+
+    /// Variation in degrees east and where it comes from: `manual`, `RMC` or `HDG`
+    pub fn current(&self) -> Option<(f64, &'static str)> {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.manual.map(|variation| (variation, "manual")).or(state.observed)
+    }
+
+    /// Learn the variation from an RMC or HDG message carrying one
+    pub fn observe(&self, message: &DataMessage) {
+        let source = match (message.message_type.as_str(), message.get_str("formatter")) {
+            ("GPS_SENTENCE", Some("RMC")) if message.get_str("status") == Some("A") => "RMC",
+            ("HEADING", _) => "HDG",
+            _ => return,
+        };
+        if let Some(variation) = message.get_f64("variation") {
+            self.state.lock().unwrap_or_else(PoisonError::into_inner).observed = Some((variation, source));
+        }
+    }
+
+    /// Complete the true/magnetic pairs of an own-ship message. `link_override`
+    /// wins over everything else.
+    pub fn apply(&self, message: DataMessage, link_override: Option<f64>) -> DataMessage {
+        if !matches!(message.message_type.as_str(), "GPS_SENTENCE" | "GPS_POSITION" | "HEADING" | "WIND") {
+            return message;
+        }
+        self.observe(&message);
+        let manual = link_override.map(|variation| (variation, "manual")).or_else(|| {
+            let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            state.manual.map(|variation| (variation, "manual"))
+        });
+        let own = message.get_f64("variation").map(|variation| (variation, "sentence"));
+        let Some((variation, source)) = manual.or(own).or_else(|| self.current()) else {
+            return message;
+        };
+
+        let mut message = message;
+        let mut completed = false;
+        for (true_key, magnetic_key) in FRAME_PAIRS {
+            let true_angle = message.get_angle(true_key);
+            let magnetic_angle = message.get_angle(magnetic_key);
+            let derived = match (true_angle, magnetic_angle) {
+                (Some(true_angle), None) => Some((magnetic_key, true_angle - variation)),
+                (None, Some(magnetic)) => Some((true_key, magnetic + variation)),
+                // A sentence that derived its true angle with its own variation,
+                // as HDG does, is corrected with the override
+                (Some(_), Some(magnetic)) if own.is_some() && manual.is_some() => Some((true_key, magnetic + variation)),
+                _ => None,
+            };
+            if let Some((key, angle)) = derived {
+                message = message.with_data(key, Value::Angle(angle.rem_euclid(360.0)));
+                completed = true;
+            }
+        }
+        if completed {
+            message = message.with_data("variation", variation).with_data("variation_source", source);
+        }
+        message
+    }
+}
+
+/// Receiver wrapper giving headings, courses and wind directions in both frames
+pub struct VariationReceiver<R: DataLinkReceiver> {
+    inner: R,
+    variation: MagneticVariation,
+    link_override: Option<f64>,
+}
+
+impl<R: DataLinkReceiver> VariationReceiver<R> {
+    /// Wrap `inner`, sharing the [`MagneticVariation::global`] value
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            variation: MagneticVariation::global().clone(),
+            link_override: None,
+        }
+    }
+
+    /// Keep the variation in `variation` instead of the global one
+    pub fn with_variation(mut self, variation: MagneticVariation) -> Self {
+        self.variation = variation;
+        self
+    }
+
+    pub fn variation(&self) -> &MagneticVariation {
+        &self.variation
+    }
+
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    fn link_override(config: &DataLinkConfig) -> DataLinkResult<Option<f64>> {
+        config
+            .parameters
+            .get(VARIATION_PARAM)
+            .map(|value| {
+                parse_variation(value).ok_or_else(|| DataLinkError::InvalidConfig(format!("Invalid variation: {}", value)))
+            })
+            .transpose()
+    }
+}
+
+impl<R: DataLinkReceiver> DataLinkReceiver for VariationReceiver<R> {
+    fn status(&self) -> DataLinkStatus {
+        self.inner.status()
+    }
+
+    fn receive_message(&mut self) -> DataLinkResult<Option<DataMessage>> {
+        Ok(self
+            .inner
+            .receive_message()?
+            .map(|message| self.variation.apply(message, self.link_override)))
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        let link_override = Self::link_override(config)?;
+        self.inner.connect(config)?;
+        self.link_override = link_override;
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        self.inner.disconnect()
+    }
+
+    fn reconfigure(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        let link_override = Self::link_override(config)?;
+        self.inner.reconfigure(config)?;
+        self.link_override = link_override;
+        Ok(())
+    }
+
+    fn stats(&self) -> LinkStats {
+        self.inner.stats()
+    }
+}
@@ -0,0 +1,244 @@
+//! WebSocket datalink
+//!
+//! Connects to a `ws://` or `wss://` URL and treats every text frame as a
+//! payload of NMEA sentences or JSON; see [`FeedDecoder`]. The bundled AIS
+//! server's `/ws` endpoint is one such feed: it greets with a line of plain
+//! text and then sends a JSON object per vessel report.
+//!
+//! Parameters: `url`, `subscribe`, a text frame sent after every (re)connect
+//! (e.g. `{"type":"set_bounding_box","bounding_box":{...}}`), and
+//! `json_message_type` for flattened JSON objects (`WEBSOCKET`).
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use futures::{SinkExt, StreamExt};
+use log::info;
+use tokio::sync::mpsc;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::protocol::Message;
+use datalink::{DataLinkConfig, DataLinkError, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataLinkTransmitter, DataMessage, LinkStats};
+use crate::multiplex::FeedDecoder;
+use crate::raw_log::RawLog;
+use crate::reconnect::{ReceiverResult, Reconnector, SharedStatus};
+use crate::runtime::runtime_handle;
+
+/// Message type of flattened JSON objects unless configured otherwise
+pub const DEFAULT_WEBSOCKET_MESSAGE_TYPE: &str = "WEBSOCKET";
+
+/// Messages buffered before the oldest are dropped
+const MAX_QUEUED_MESSAGES: usize = 1000;
+
+/// Where to connect and what to say first
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebSocketConfig {
+    pub url: String,
+    pub subscribe: Option<String>,
+    pub json_message_type: String,
+}
+
+impl WebSocketConfig {
+    pub fn from_config(config: &DataLinkConfig) -> DataLinkResult<Self> {
+        let param = |key: &str| config.parameters.get(key).cloned();
+        let url = param("url").ok_or_else(|| DataLinkError::InvalidConfig("Missing url parameter for WebSocket".to_string()))?;
+        if !(url.starts_with("ws://") || url.starts_with("wss://")) {
+            return Err(DataLinkError::InvalidConfig(format!("Not a WebSocket URL: {}", url)));
+        }
+        let subscribe = param("subscribe");
+        if let Some(subscribe) = &subscribe {
+            serde_json::from_str::<serde_json::Value>(subscribe)
+                .map_err(|e| DataLinkError::InvalidConfig(format!("subscribe is not JSON: {}", e)))?;
+        }
+        Ok(Self {
+            url,
+            subscribe,
+            json_message_type: param("json_message_type").unwrap_or_else(|| DEFAULT_WEBSOCKET_MESSAGE_TYPE.to_string()),
+        })
+    }
+}
+
+pub struct WebSocketDataLinkProvider {
+    status: SharedStatus,
+    message_queue: Arc<Mutex<VecDeque<DataMessage>>>,
+    shutdown_tx: Option<mpsc::Sender<()>>,
+    frame_tx: Option<mpsc::UnboundedSender<String>>,
+    receiver_handle: Option<tokio::task::JoinHandle<()>>,
+    /// Runtime for the receiver task; the caller's when unset
+    runtime: Option<tokio::runtime::Handle>,
+}
+
+impl WebSocketDataLinkProvider {
+    pub fn new() -> Self {
+        Self {
+            status: SharedStatus::default(),
+            message_queue: Arc::new(Mutex::new(VecDeque::new())),
+            shutdown_tx: None,
+            frame_tx: None,
+            receiver_handle: None,
+            runtime: None,
+        }
+    }
+
+    /// Spawn the receiver task on `runtime` rather than the caller's
+    pub fn with_runtime(mut self, runtime: tokio::runtime::Handle) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    fn start_receiver(&mut self, config: WebSocketConfig, auto_reconnect: bool) -> DataLinkResult<()> {
+        let runtime = runtime_handle(self.runtime.as_ref())?;
+        let (shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
+        let (frame_tx, mut frame_rx) = mpsc::unbounded_channel();
+        let message_queue = Arc::clone(&self.message_queue);
+        let status = self.status.clone();
+
+        let handle = runtime.spawn(async move {
+            let mut decoder = FeedDecoder::new("WEBSOCKET", &config.json_message_type);
+            let mut reconnector = Reconnector::new("WebSocket", auto_reconnect, status);
+            loop {
+                let result = Self::run(&config, &mut decoder, &message_queue, &mut shutdown_rx, &mut frame_rx, reconnector.status()).await;
+                if !reconnector.retry(result, &mut shutdown_rx).await {
+                    break;
+                }
+            }
+        });
+
+        self.shutdown_tx = Some(shutdown_tx);
+        self.frame_tx = Some(frame_tx);
+        self.receiver_handle = Some(handle);
+        Ok(())
+    }
+
+    /// Read frames and write queued ones until shutdown or the socket closes
+    async fn run(
+        config: &WebSocketConfig,
+        decoder: &mut FeedDecoder,
+        message_queue: &Mutex<VecDeque<DataMessage>>,
+        shutdown_rx: &mut mpsc::Receiver<()>,
+        frame_rx: &mut mpsc::UnboundedReceiver<String>,
+        status: &SharedStatus,
+    ) -> ReceiverResult {
+        info!("Connecting to WebSocket {}", config.url);
+        let (mut socket, _) = connect_async(config.url.as_str()).await?;
+        if let Some(subscribe) = &config.subscribe {
+            socket.send(Message::Text(subscribe.clone())).await?;
+        }
+        status.set(DataLinkStatus::Connected);
+
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => {
+                    info!("WebSocket link shutdown requested");
+                    let _ = socket.close(None).await;
+                    return Ok(());
+                }
+                Some(frame) = frame_rx.recv() => socket.send(Message::Text(frame)).await?,
+                frame = socket.next() => match frame {
+                    Some(Ok(Message::Text(text))) => {
+                        let messages = decoder.decode(&text, SystemTime::now(), status.stats());
+                        if let Ok(mut queue) = message_queue.lock() {
+                            queue.extend(messages);
+                            while queue.len() > MAX_QUEUED_MESSAGES {
+                                queue.pop_front();
+                            }
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | None => return Err("WebSocket closed".into()),
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return Err(e.into()),
+                },
+            }
+        }
+    }
+
+    fn stop_receiver(&mut self) {
+        if let Some(shutdown_tx) = self.shutdown_tx.take() {
+            let _ = shutdown_tx.try_send(());
+        }
+        self.frame_tx = None;
+        if let Some(handle) = self.receiver_handle.take() {
+            handle.abort();
+        }
+    }
+}
+
+impl Default for WebSocketDataLinkProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DataLinkReceiver for WebSocketDataLinkProvider {
+    fn status(&self) -> DataLinkStatus {
+        self.status.get()
+    }
+
+    fn receive_message(&mut self) -> DataLinkResult<Option<DataMessage>> {
+        match self.message_queue.lock() {
+            Ok(mut queue) => Ok(queue.pop_front()),
+            Err(_) => Err(DataLinkError::TransportError("Failed to access message queue".to_string())),
+        }
+    }
+
+    fn stats(&self) -> LinkStats {
+        self.status.stats().get()
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        let websocket_config = WebSocketConfig::from_config(config)?;
+        self.status.stats().set_raw_log(RawLog::from_config(config)?);
+        info!("Connecting WebSocket datalink to {}", websocket_config.url);
+        self.stop_receiver();
+        self.status.set(DataLinkStatus::Connecting);
+        self.start_receiver(websocket_config, config.auto_reconnect).inspect_err(|e| {
+            self.status.set(DataLinkStatus::Error(format!("Connection failed: {}", e)));
+        })
+    }
+
+    /// Reconnect to a new URL; queued messages stay
+    fn reconfigure(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        DataLinkReceiver::connect(self, config)
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        info!("Disconnecting WebSocket datalink");
+        self.stop_receiver();
+        self.status.stats().set_raw_log(None);
+        if let Ok(mut queue) = self.message_queue.lock() {
+            queue.clear();
+        }
+        self.status.set(DataLinkStatus::Disconnected);
+        Ok(())
+    }
+}
+
+impl DataLinkTransmitter for WebSocketDataLinkProvider {
+    fn status(&self) -> DataLinkStatus {
+        self.status.get()
+    }
+
+    /// Send the payload as a text frame; a message without one goes out as JSON
+    fn send_message(&mut self, message: &DataMessage) -> DataLinkResult<()> {
+        let frame_tx = self
+            .frame_tx
+            .as_ref()
+            .ok_or_else(|| DataLinkError::ConnectionFailed("WebSocket not connected".to_string()))?;
+        let frame = if message.payload.is_empty() {
+            serde_json::to_string(message).map_err(|e| DataLinkError::InvalidMessage(e.to_string()))?
+        } else {
+            String::from_utf8(message.payload.to_vec())
+                .map_err(|_| DataLinkError::InvalidMessage("WebSocket frames are text".to_string()))?
+        };
+        frame_tx
+            .send(frame)
+            .map_err(|_| DataLinkError::TransportError("WebSocket connection closed".to_string()))
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        DataLinkReceiver::connect(self, config)
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        DataLinkReceiver::disconnect(self)
+    }
+}
@@ -0,0 +1,93 @@
+//! Compares `DataMessage` against the previous `Vec<u8>` / `String`-keyed
+//! layout for the two hot paths on high-rate links: building a parsed radar
+//! message and fanning it out to several consumers.
+//!
+//! Run with `cargo bench -p datalink`.
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::SystemTime;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use datalink::DataMessage;
+
+/// Bytes in one simulated radar spoke
+const SPOKE_LEN: usize = 2048;
+/// Consumers each receiving a clone of the message
+const FANOUT: usize = 8;
+
+/// The message layout before payloads moved to `Bytes` and keys were interned
+#[derive(Clone)]
+#[allow(dead_code)]
+struct LegacyMessage {
+    message_type: String,
+    source_id: String,
+    received_at: SystemTime,
+    payload: Vec<u8>,
+    data: HashMap<String, String>,
+}
+
+const SCAN_FIELDS: [(&str, &str); 6] = [
+    ("sweep_angle", "123.45"),
+    ("range_nm", "12"),
+    ("gain", "AUTO"),
+    ("sea_clutter_db", "-15"),
+    ("rain_clutter", "OFF"),
+    ("sentence_type", "$RADSC"),
+];
+
+fn spoke() -> Vec<u8> {
+    (0..SPOKE_LEN).map(|i| (i % 251) as u8).collect()
+}
+
+fn legacy_message(payload: Vec<u8>) -> LegacyMessage {
+    let mut data = HashMap::new();
+    for (key, value) in SCAN_FIELDS {
+        data.insert(key.to_string(), value.to_string());
+    }
+    LegacyMessage {
+        message_type: "RADAR_SCAN".to_string(),
+        source_id: "RADAR_RECEIVER".to_string(),
+        received_at: SystemTime::now(),
+        payload,
+        data,
+    }
+}
+
+fn message(payload: Vec<u8>) -> DataMessage {
+    let mut message = DataMessage::new("RADAR_SCAN".to_string(), "RADAR_RECEIVER".to_string(), payload);
+    for (key, value) in SCAN_FIELDS {
+        message = message.with_data(key, value);
+    }
+    message
+}
+
+fn bench_build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build_radar_scan");
+    group.bench_function("legacy", |b| b.iter(|| black_box(legacy_message(spoke()))));
+    group.bench_function("bytes_interned", |b| b.iter(|| black_box(message(spoke()))));
+    group.finish();
+}
+
+fn bench_fanout(c: &mut Criterion) {
+    let legacy = legacy_message(spoke());
+    let current = message(spoke());
+
+    let mut group = c.benchmark_group("fanout_clone");
+    group.bench_function("legacy", |b| {
+        b.iter(|| {
+            let clones: Vec<LegacyMessage> = (0..FANOUT).map(|_| legacy.clone()).collect();
+            black_box(clones)
+        })
+    });
+    group.bench_function("bytes_interned", |b| {
+        b.iter(|| {
+            let clones: Vec<DataMessage> = (0..FANOUT).map(|_| current.clone()).collect();
+            black_box(clones)
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_build, bench_fanout);
+criterion_main!(benches);
@@ -0,0 +1,209 @@
+//! Data-link bridging
+//!
+//! A [`DataLinkBridge`] forwards everything a receiver produces to one or more
+//! transmitters, turning yachtpit into a gateway between links (for example a
+//! serial GPS re-broadcast over UDP to the rest of the boat network).
+
+use crate::{DataLinkReceiver, DataLinkResult, DataLinkTransmitter, DataMessage};
+
+/// Predicate deciding whether a message is forwarded
+pub type MessageFilter = Box<dyn Fn(&DataMessage) -> bool + Send + Sync>;
+
+struct BridgeOutput {
+    transmitter: Box<dyn DataLinkTransmitter>,
+    filter: Option<MessageFilter>,
+}
+
+/// Counters describing the traffic through a bridge
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BridgeStats {
+    /// Messages read from the source
+    pub received: u64,
+    /// Successful deliveries, counted once per output
+    pub forwarded: u64,
+    /// Messages rejected by the bridge or output filters
+    pub filtered: u64,
+    /// Deliveries that failed at the transmitter
+    pub send_errors: u64,
+}
+
+/// Pipes messages from one receiver to any number of transmitters
+pub struct DataLinkBridge {
+    source: Box<dyn DataLinkReceiver>,
+    outputs: Vec<BridgeOutput>,
+    filter: Option<MessageFilter>,
+    stats: BridgeStats,
+}
+
+impl DataLinkBridge {
+    /// Create a bridge reading from `source`
+    pub fn new(source: Box<dyn DataLinkReceiver>) -> Self {
+        Self {
+            source,
+            outputs: Vec::new(),
+            filter: None,
+            stats: BridgeStats::default(),
+        }
+    }
+
+    /// Only forward messages matching the filter, for every output
+    pub fn with_filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&DataMessage) -> bool + Send + Sync + 'static,
+    {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Add an output receiving every message that passes the bridge filter
+    pub fn add_output(&mut self, transmitter: Box<dyn DataLinkTransmitter>) {
+        self.outputs.push(BridgeOutput {
+            transmitter,
+            filter: None,
+        });
+    }
+
+    /// Add an output with its own additional filter
+    pub fn add_filtered_output<F>(&mut self, transmitter: Box<dyn DataLinkTransmitter>, filter: F)
+    where
+        F: Fn(&DataMessage) -> bool + Send + Sync + 'static,
+    {
+        self.outputs.push(BridgeOutput {
+            transmitter,
+            filter: Some(Box::new(filter)),
+        });
+    }
+
+    /// Number of configured outputs
+    pub fn output_count(&self) -> usize {
+        self.outputs.len()
+    }
+
+    /// Traffic counters since the bridge was created
+    pub fn stats(&self) -> BridgeStats {
+        self.stats
+    }
+
+    /// Access the source receiver, e.g. to check its status
+    pub fn source(&self) -> &dyn DataLinkReceiver {
+        self.source.as_ref()
+    }
+
+    /// Forward a single message to the outputs, returning the number of deliveries
+    pub fn forward(&mut self, message: &DataMessage) -> usize {
+        if let Some(filter) = &self.filter {
+            if !filter(message) {
+                self.stats.filtered += 1;
+                return 0;
+            }
+        }
+
+        let mut delivered = 0;
+        for output in self.outputs.iter_mut() {
+            if let Some(filter) = &output.filter {
+                if !filter(message) {
+                    self.stats.filtered += 1;
+                    continue;
+                }
+            }
+
+            // A failing output must not stall the others
+            match output.transmitter.send_message(message) {
+                Ok(()) => {
+                    self.stats.forwarded += 1;
+                    delivered += 1;
+                }
+                Err(_) => self.stats.send_errors += 1,
+            }
+        }
+        delivered
+    }
+
+    /// Drain the source and forward every pending message.
+    ///
+    /// Returns the number of messages read from the source. Errors from the
+    /// source are returned; delivery errors are only counted in [`BridgeStats`].
+    pub fn pump(&mut self) -> DataLinkResult<usize> {
+        let mut count = 0;
+        while let Some(message) = self.source.receive_message()? {
+            self.stats.received += 1;
+            self.forward(&message);
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataLinkConfig, DataLinkError, DataLinkStatus, SimulationDataLink};
+    use std::sync::{Arc, Mutex};
+
+    /// Transmitter recording message types, optionally failing every send
+    struct RecordingTransmitter {
+        sent: Arc<Mutex<Vec<String>>>,
+        fail: bool,
+    }
+
+    impl DataLinkTransmitter for RecordingTransmitter {
+        fn status(&self) -> DataLinkStatus {
+            DataLinkStatus::Connected
+        }
+
+        fn send_message(&mut self, message: &DataMessage) -> DataLinkResult<()> {
+            if self.fail {
+                return Err(DataLinkError::TransportError("unplugged".to_string()));
+            }
+            self.sent.lock().unwrap().push(message.message_type.clone());
+            Ok(())
+        }
+
+        fn connect(&mut self, _config: &DataLinkConfig) -> DataLinkResult<()> {
+            Ok(())
+        }
+
+        fn disconnect(&mut self) -> DataLinkResult<()> {
+            Ok(())
+        }
+    }
+
+    fn connected_simulation() -> Box<dyn DataLinkReceiver> {
+        let mut link = SimulationDataLink::new();
+        DataLinkReceiver::connect(&mut link, &DataLinkConfig::new("simulation".to_string())).unwrap();
+        Box::new(link)
+    }
+
+    #[test]
+    fn test_bridge_forwards_filtered_messages() {
+        let gps_out = Arc::new(Mutex::new(Vec::new()));
+        let all_out = Arc::new(Mutex::new(Vec::new()));
+
+        let mut bridge = DataLinkBridge::new(connected_simulation())
+            .with_filter(|message| message.message_type != "RADAR_TARGET");
+        bridge.add_filtered_output(
+            Box::new(RecordingTransmitter { sent: gps_out.clone(), fail: false }),
+            |message| message.message_type == "GPS_POSITION",
+        );
+        bridge.add_output(Box::new(RecordingTransmitter { sent: all_out.clone(), fail: false }));
+
+        let received = bridge.pump().unwrap();
+        assert!(received > 0);
+        assert_eq!(*gps_out.lock().unwrap(), vec!["GPS_POSITION".to_string()]);
+        assert!(!all_out.lock().unwrap().iter().any(|t| t == "RADAR_TARGET"));
+        assert!(all_out.lock().unwrap().iter().any(|t| t == "AIS_POSITION"));
+        assert_eq!(bridge.stats().received, received as u64);
+    }
+
+    #[test]
+    fn test_bridge_survives_failing_output() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let mut bridge = DataLinkBridge::new(connected_simulation());
+        bridge.add_output(Box::new(RecordingTransmitter { sent: Arc::new(Mutex::new(Vec::new())), fail: true }));
+        bridge.add_output(Box::new(RecordingTransmitter { sent: sent.clone(), fail: false }));
+
+        let received = bridge.pump().unwrap();
+        assert_eq!(sent.lock().unwrap().len(), received);
+        assert_eq!(bridge.stats().send_errors, received as u64);
+    }
+}
@@ -0,0 +1,251 @@
+//! Geodesy helpers shared by the vessel systems
+//!
+//! Positions are `(latitude, longitude)` in decimal degrees on the WGS84
+//! datum, the datum GNSS receivers, AIS and electronic charts report in.
+//! Distances are great-circle distances on the mean earth sphere, accurate to
+//! about 0.5%, which is well inside what a GNSS fix offers at sea. Geometry
+//! over a few miles (route corridors, radar targets, closest approach, overlays) is done in a
+//! [`LocalTangentPlane`] around a nearby origin.
+
+/// WGS84 semi-major axis in meters
+pub const WGS84_A: f64 = 6_378_137.0;
+/// WGS84 flattening
+pub const WGS84_F: f64 = 1.0 / 298.257_223_563;
+/// WGS84 first eccentricity squared
+pub const WGS84_E2: f64 = WGS84_F * (2.0 - WGS84_F);
+/// Mean earth radius in meters, used for great-circle math
+pub const EARTH_MEAN_RADIUS_M: f64 = 6_371_008.8;
+pub const METERS_PER_NM: f64 = 1852.0;
+/// Relative speeds below this are treated as no relative motion, in knots
+pub const MIN_RELATIVE_SPEED_KTS: f64 = 0.05;
+/// Web Mercator cuts the map off at this latitude, north and south
+pub const MAX_MERCATOR_LATITUDE: f64 = 85.051_128_779_806_59;
+
+/// Great-circle distance in meters
+pub fn distance_m(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lat2) = (from.0.to_radians(), to.0.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (to.1 - from.1).to_radians();
+    let h = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_MEAN_RADIUS_M * h.sqrt().min(1.0).asin()
+}
+
+/// Initial great-circle bearing in degrees true
+pub fn bearing_deg(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lat2) = (from.0.to_radians(), to.0.to_radians());
+    let d_lon = (to.1 - from.1).to_radians();
+    let y = d_lon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * d_lon.cos();
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+/// Range in nautical miles and initial bearing in degrees true
+pub fn range_and_bearing_nm(from: (f64, f64), to: (f64, f64)) -> (f64, f64) {
+    (distance_m(from, to) / METERS_PER_NM, bearing_deg(from, to))
+}
+
+/// Position `distance_m` along the great circle leaving `from` on `bearing_deg`.
+/// Also places a radar or AIS target given its range and bearing from own ship.
+pub fn destination(from: (f64, f64), bearing_deg: f64, distance_m: f64) -> (f64, f64) {
+    let lat1 = from.0.to_radians();
+    let bearing = bearing_deg.to_radians();
+    let delta = distance_m / EARTH_MEAN_RADIUS_M;
+    let lat2 = (lat1.sin() * delta.cos() + lat1.cos() * delta.sin() * bearing.cos()).asin();
+    let d_lon = (bearing.sin() * delta.sin() * lat1.cos()).atan2(delta.cos() - lat1.sin() * lat2.sin());
+    (lat2.to_degrees(), normalize_longitude(from.1 + d_lon.to_degrees()))
+}
+
+/// Wrap a longitude into -180..180 degrees
+pub fn normalize_longitude(longitude: f64) -> f64 {
+    (longitude + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Earth-centered, earth-fixed coordinates in meters
+pub fn to_ecef(position: (f64, f64), height_m: f64) -> (f64, f64, f64) {
+    let (lat, lon) = (position.0.to_radians(), position.1.to_radians());
+    let n = prime_vertical_radius(lat);
+    (
+        (n + height_m) * lat.cos() * lon.cos(),
+        (n + height_m) * lat.cos() * lon.sin(),
+        (n * (1.0 - WGS84_E2) + height_m) * lat.sin(),
+    )
+}
+
+/// Position and height above the ellipsoid of an ECEF point
+pub fn from_ecef(x: f64, y: f64, z: f64) -> ((f64, f64), f64) {
+    let lon = y.atan2(x);
+    let p = x.hypot(y);
+    // Converges to well under a millimeter in a handful of rounds near the surface
+    let mut lat = z.atan2(p * (1.0 - WGS84_E2));
+    let mut height_m = 0.0;
+    for _ in 0..5 {
+        let n = prime_vertical_radius(lat);
+        height_m = if lat.cos().abs() > 1e-9 { p / lat.cos() - n } else { z.abs() - n * (1.0 - WGS84_E2) };
+        lat = z.atan2(p * (1.0 - WGS84_E2 * n / (n + height_m)));
+    }
+    ((lat.to_degrees(), lon.to_degrees()), height_m)
+}
+
+/// Radius of curvature in the prime vertical at `lat` (radians)
+fn prime_vertical_radius(lat: f64) -> f64 {
+    WGS84_A / (1.0 - WGS84_E2 * lat.sin().powi(2)).sqrt()
+}
+
+/// Radius of curvature along the meridian at `lat` (radians)
+fn meridian_radius(lat: f64) -> f64 {
+    WGS84_A * (1.0 - WGS84_E2) / (1.0 - WGS84_E2 * lat.sin().powi(2)).powf(1.5)
+}
+
+/// Flat east/north frame in meters around an origin, using the WGS84 radii
+/// of curvature there. Good to a few meters within about ten miles of the origin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LocalTangentPlane {
+    origin: (f64, f64),
+    meters_per_deg_lat: f64,
+    meters_per_deg_lon: f64,
+}
+
+impl LocalTangentPlane {
+    pub fn new(origin: (f64, f64)) -> Self {
+        let lat = origin.0.to_radians();
+        Self {
+            origin,
+            meters_per_deg_lat: meridian_radius(lat).to_radians(),
+            meters_per_deg_lon: (prime_vertical_radius(lat) * lat.cos()).to_radians(),
+        }
+    }
+
+    pub fn origin(&self) -> (f64, f64) {
+        self.origin
+    }
+
+    /// Meters (east, north) of the origin
+    pub fn to_local(&self, position: (f64, f64)) -> (f64, f64) {
+        (
+            normalize_longitude(position.1 - self.origin.1) * self.meters_per_deg_lon,
+            (position.0 - self.origin.0) * self.meters_per_deg_lat,
+        )
+    }
+
+    /// Position of a point given in meters east and north of the origin
+    pub fn to_position(&self, east_m: f64, north_m: f64) -> (f64, f64) {
+        let longitude = match self.meters_per_deg_lon {
+            meters if meters > 0.0 => self.origin.1 + east_m / meters,
+            _ => self.origin.1,
+        };
+        (self.origin.0 + north_m / self.meters_per_deg_lat, normalize_longitude(longitude))
+    }
+}
+
+/// Closest point of approach between two vessels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Approach {
+    /// Current range in nautical miles
+    pub range_nm: f64,
+    pub cpa_nm: f64,
+    /// Minutes until the CPA; negative once it has passed, zero without relative motion
+    pub tcpa_min: f64,
+}
+
+/// CPA and TCPA of a vessel at `target` from one at `own`, each holding the
+/// `(speed_kts, course_deg)` it is given. A vessel past its CPA, or with no
+/// relative motion, has its current range as CPA.
+pub fn closest_approach(own: (f64, f64), own_motion: (f64, f64), target: (f64, f64), target_motion: (f64, f64)) -> Approach {
+    let (east_m, north_m) = LocalTangentPlane::new(own).to_local(target);
+    let (x, y) = (east_m / METERS_PER_NM, north_m / METERS_PER_NM);
+    let (own_east, own_north) = velocity_kts(own_motion);
+    let (target_east, target_north) = velocity_kts(target_motion);
+    let (vx, vy) = (target_east - own_east, target_north - own_north);
+
+    let range_nm = x.hypot(y);
+    let relative_speed_sq = vx * vx + vy * vy;
+    if relative_speed_sq < MIN_RELATIVE_SPEED_KTS * MIN_RELATIVE_SPEED_KTS {
+        return Approach { range_nm, cpa_nm: range_nm, tcpa_min: 0.0 };
+    }
+    let tcpa_h = -(x * vx + y * vy) / relative_speed_sq;
+    let cpa_nm = if tcpa_h > 0.0 { (x + vx * tcpa_h).hypot(y + vy * tcpa_h) } else { range_nm };
+    Approach { range_nm, cpa_nm, tcpa_min: tcpa_h * 60.0 }
+}
+
+/// Velocity east and north in knots of `(speed_kts, course_deg)`
+fn velocity_kts((speed_kts, course_deg): (f64, f64)) -> (f64, f64) {
+    let course = course_deg.to_radians();
+    (speed_kts * course.sin(), speed_kts * course.cos())
+}
+
+/// Web Mercator (EPSG:3857) meters of a position, the projection of the chart
+/// tiles. Latitudes are clamped to [`MAX_MERCATOR_LATITUDE`].
+pub fn to_web_mercator(position: (f64, f64)) -> (f64, f64) {
+    let lat = position.0.clamp(-MAX_MERCATOR_LATITUDE, MAX_MERCATOR_LATITUDE).to_radians();
+    (
+        WGS84_A * position.1.to_radians(),
+        WGS84_A * (std::f64::consts::FRAC_PI_4 + lat / 2.0).tan().ln(),
+    )
+}
+
+/// Position of a Web Mercator point
+pub fn from_web_mercator(x: f64, y: f64) -> (f64, f64) {
+    let lat = 2.0 * (y / WGS84_A).exp().atan() - std::f64::consts::FRAC_PI_2;
+    (lat.to_degrees(), (x / WGS84_A).to_degrees())
+}
+
+/// Web Mercator meters per screen pixel at `zoom` for 512 px tiles, as the map view uses
+pub fn mercator_meters_per_pixel(latitude: f64, zoom: f64) -> f64 {
+    2.0 * std::f64::consts::PI * WGS84_A * latitude.to_radians().cos() / (512.0 * 2f64.powf(zoom))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MONACO: (f64, f64) = (43.7384, 7.4246);
+
+    #[test]
+    fn test_great_circle_distance_and_bearing() {
+        // One minute of latitude is a nautical mile, to within the ellipsoid's flattening
+        let north = (MONACO.0 + 1.0 / 60.0, MONACO.1);
+        assert!((distance_m(MONACO, north) - METERS_PER_NM).abs() < 10.0);
+        assert!(bearing_deg(MONACO, north).abs() < 1e-9);
+
+        let corsica = (42.5, 8.5);
+        let (range_nm, bearing) = range_and_bearing_nm(MONACO, corsica);
+        let there = destination(MONACO, bearing, range_nm * METERS_PER_NM);
+        assert!(distance_m(there, corsica) < 0.01);
+        assert_eq!(normalize_longitude(181.0), -179.0);
+        assert!((destination((0.0, 179.9), 90.0, 30_000.0).1 + 179.83).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_closest_approach_head_on() {
+        // 3 NM apart on reciprocal courses at 10 kts each: closing at 20 kts
+        let ahead = destination(MONACO, 180.0, 3.0 * METERS_PER_NM);
+        let approach = closest_approach(MONACO, (10.0, 180.0), ahead, (10.0, 0.0));
+        assert!((approach.range_nm - 3.0).abs() < 0.01);
+        assert!(approach.cpa_nm < 0.01);
+        assert!((approach.tcpa_min - 9.0).abs() < 0.05);
+
+        let stopped = closest_approach(MONACO, (0.0, 0.0), ahead, (0.0, 90.0));
+        assert_eq!(stopped.tcpa_min, 0.0);
+        assert_eq!(stopped.cpa_nm, stopped.range_nm);
+    }
+
+    #[test]
+    fn test_projections_round_trip() {
+        let (x, y, z) = to_ecef(MONACO, 25.0);
+        let (position, height_m) = from_ecef(x, y, z);
+        assert!((position.0 - MONACO.0).abs() < 1e-9 && (position.1 - MONACO.1).abs() < 1e-9);
+        assert!((height_m - 25.0).abs() < 1e-3);
+
+        let plane = LocalTangentPlane::new(MONACO);
+        let buoy = destination(MONACO, 60.0, 2_000.0);
+        let (east, north) = plane.to_local(buoy);
+        assert!((east.hypot(north) - 2_000.0).abs() < 10.0);
+        let back = plane.to_position(east, north);
+        assert!(distance_m(back, buoy) < 1e-6);
+
+        let (x, y) = to_web_mercator(MONACO);
+        let back = from_web_mercator(x, y);
+        assert!(distance_m(back, MONACO) < 1e-6);
+        assert!((mercator_meters_per_pixel(0.0, 0.0) - 78_271.517).abs() < 0.01);
+    }
+}
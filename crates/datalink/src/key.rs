@@ -0,0 +1,201 @@
+//! Interned data keys
+//!
+//! Most messages use the same few dozen `data` keys. A [`DataKey`] built from
+//! one of them borrows a static string instead of allocating, so building and
+//! cloning messages on high-rate links (radar scans, AIS bursts) does not
+//! allocate a `String` per key.
+
+use std::borrow::{Borrow, Cow};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Keys produced by the bundled parsers, sorted for binary search
+pub const COMMON_KEYS: &[&str] = &[
+    "alternator_voltage",
+    "altitude",
+    "altitude_unit",
+    "angle_deg",
+    "apparent_wind_angle",
+    "apparent_wind_speed",
+    "arrived",
+    "bearing_deg",
+    "bearing_origin_to_destination",
+    "bearing_to_destination",
+    "channel",
+    "command",
+    "coolant_temp_c",
+    "course",
+    "course_deg",
+    "cpa_nm",
+    "cross_track_nm",
+    "date",
+    "day",
+    "depth_m",
+    "destination",
+    "destination_id",
+    "destination_range_nm",
+    "engine_instance",
+    "fill_bits",
+    "fix_quality",
+    "fragment_count",
+    "fragment_number",
+    "gain",
+    "hdop",
+    "heading_magnetic",
+    "heading_to_steer",
+    "heading_true",
+    "health",
+    "heel_deg",
+    "lat_direction",
+    "latitude",
+    "lon_direction",
+    "longitude",
+    "mast_rotation_deg",
+    "message_id",
+    "mmsi",
+    "month",
+    "oil_pressure_kpa",
+    "payload",
+    "rain_clutter",
+    "range_m",
+    "range_nm",
+    "reference",
+    "rpm",
+    "satellites",
+    "sea_clutter_db",
+    "sentence_type",
+    "speed",
+    "speed_kts",
+    "speed_through_water",
+    "status",
+    "sweep_angle",
+    "target_id",
+    "time",
+    "timestamp",
+    "transducer",
+    "value",
+    "vessel_name",
+    "water_temp_c",
+    "year",
+];
+
+/// Key of a [`DataMessage`](crate::DataMessage) data entry
+#[derive(Clone, Eq)]
+pub struct DataKey(Cow<'static, str>);
+
+impl DataKey {
+    /// Intern `key` if it is a common key, otherwise allocate
+    pub fn new(key: &str) -> Self {
+        match Self::interned(key) {
+            Some(common) => Self(Cow::Borrowed(common)),
+            None => Self(Cow::Owned(key.to_string())),
+        }
+    }
+
+    /// A key borrowing a static string, never allocating
+    pub const fn from_static(key: &'static str) -> Self {
+        Self(Cow::Borrowed(key))
+    }
+
+    fn interned(key: &str) -> Option<&'static str> {
+        COMMON_KEYS.binary_search(&key).ok().map(|index| COMMON_KEYS[index])
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the key borrows static storage rather than owning a `String`
+    pub fn is_static(&self) -> bool {
+        matches!(self.0, Cow::Borrowed(_))
+    }
+}
+
+impl From<&'static str> for DataKey {
+    fn from(key: &'static str) -> Self {
+        Self::from_static(key)
+    }
+}
+
+impl From<String> for DataKey {
+    fn from(key: String) -> Self {
+        match Self::interned(&key) {
+            Some(common) => Self(Cow::Borrowed(common)),
+            None => Self(Cow::Owned(key)),
+        }
+    }
+}
+
+impl PartialEq for DataKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+// Must hash like `str` so maps can be queried with `&str` through `Borrow`
+impl Hash for DataKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl Borrow<str> for DataKey {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Deref for DataKey {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Debug for DataKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for DataKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for DataKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for DataKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_common_keys_are_interned() {
+        assert!(COMMON_KEYS.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(DataKey::new("sweep_angle").is_static());
+        assert!(DataKey::from("latitude".to_string()).is_static());
+        assert!(!DataKey::new("engine_rpm").is_static());
+
+        let mut data = HashMap::new();
+        data.insert(DataKey::from("range_nm"), "12".to_string());
+        data.insert(DataKey::new("engine_rpm"), "1800".to_string());
+        assert_eq!(data.get("range_nm").map(String::as_str), Some("12"));
+        assert_eq!(data.get("engine_rpm").map(String::as_str), Some("1800"));
+    }
+}
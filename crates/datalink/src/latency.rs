@@ -0,0 +1,172 @@
+//! Per-link latency and jitter measurement
+//!
+//! Latency is the time from when a source measured its data to when the
+//! message arrived, so it is only known for messages carrying a
+//! `measured_at` (GPS RMC/ZDA time, for example) and includes any offset
+//! between the device clock and the local clock.
+//!
+//! Jitter is the smoothed variation between consecutive messages, estimated
+//! as in RFC 3550: on the transit time of timestamped messages, and on the
+//! arrival interval of each message type otherwise. A WiFi bridge that
+//! buffers and bursts NMEA shows up as high jitter even when no source
+//! reports a timestamp.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+
+use crate::time::signed_secs;
+use crate::DataMessage;
+
+/// Default number of latency samples kept by a [`LatencyTracker`]
+pub const DEFAULT_LATENCY_SAMPLES: usize = 64;
+
+/// Weight of each new difference in the jitter estimate (RFC 3550 uses 1/16)
+const JITTER_GAIN: f64 = 1.0 / 16.0;
+
+/// Snapshot of a link's latency and jitter, in milliseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyStats {
+    /// Messages observed
+    pub messages: u64,
+    /// Messages carrying a device timestamp
+    pub timestamped: u64,
+    pub latency_mean_ms: Option<f64>,
+    pub latency_min_ms: Option<f64>,
+    pub latency_max_ms: Option<f64>,
+    pub jitter_ms: Option<f64>,
+}
+
+/// Measures latency and jitter of the messages received on one link
+#[derive(Debug, Clone)]
+pub struct LatencyTracker {
+    /// Recent latencies in milliseconds
+    samples: VecDeque<f64>,
+    max_samples: usize,
+    messages: u64,
+    timestamped: u64,
+    jitter_ms: Option<f64>,
+    /// Transit time of the last timestamped message
+    last_transit_ms: Option<f64>,
+    /// Last arrival and arrival interval per message type
+    arrivals: HashMap<String, (SystemTime, Option<f64>)>,
+}
+
+impl LatencyTracker {
+    pub fn new(max_samples: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(max_samples),
+            max_samples: max_samples.max(1),
+            messages: 0,
+            timestamped: 0,
+            jitter_ms: None,
+            last_transit_ms: None,
+            arrivals: HashMap::new(),
+        }
+    }
+
+    /// Record a received message; returns its latency in milliseconds when
+    /// the message carries a device timestamp
+    pub fn observe(&mut self, message: &DataMessage) -> Option<f64> {
+        self.messages += 1;
+        let arrival = message.received_at;
+
+        let (previous, interval) = match self.arrivals.get(&message.message_type) {
+            Some(&(last_arrival, last_interval)) => {
+                let interval = signed_secs(arrival, last_arrival) * 1000.0;
+                (last_interval, Some(interval))
+            }
+            None => (None, None),
+        };
+        self.arrivals.insert(message.message_type.clone(), (arrival, interval));
+
+        let Some(measured_at) = message.measured_at else {
+            if let (Some(previous), Some(interval)) = (previous, interval) {
+                self.update_jitter(interval - previous);
+            }
+            return None;
+        };
+
+        self.timestamped += 1;
+        let transit = signed_secs(arrival, measured_at) * 1000.0;
+        if let Some(last_transit) = self.last_transit_ms.replace(transit) {
+            self.update_jitter(transit - last_transit);
+        }
+        if self.samples.len() == self.max_samples {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(transit);
+        Some(transit)
+    }
+
+    fn update_jitter(&mut self, difference_ms: f64) {
+        let jitter = self.jitter_ms.unwrap_or(0.0);
+        self.jitter_ms = Some(jitter + (difference_ms.abs() - jitter) * JITTER_GAIN);
+    }
+
+    pub fn stats(&self) -> LatencyStats {
+        let latency_mean_ms = if self.samples.is_empty() {
+            None
+        } else {
+            Some(self.samples.iter().sum::<f64>() / self.samples.len() as f64)
+        };
+        LatencyStats {
+            messages: self.messages,
+            timestamped: self.timestamped,
+            latency_mean_ms,
+            latency_min_ms: self.samples.iter().copied().reduce(f64::min),
+            latency_max_ms: self.samples.iter().copied().reduce(f64::max),
+            jitter_ms: self.jitter_ms,
+        }
+    }
+
+    /// Forget every sample, e.g. after the link reconnects
+    pub fn reset(&mut self) {
+        *self = Self::new(self.max_samples);
+    }
+}
+
+impl Default for LatencyTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_LATENCY_SAMPLES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_latency_and_jitter() {
+        let mut tracker = LatencyTracker::new(2);
+        let start = UNIX_EPOCH + Duration::from_secs(1_711_197_319);
+
+        // One RMC a second, delayed 100, 300 and 200 ms on the way in
+        for (second, delay_ms) in [(0, 100), (1, 300), (2, 200)] {
+            let measured = start + Duration::from_secs(second);
+            let message = DataMessage::new("GPS_SENTENCE".to_string(), "GPS".to_string(), Vec::new())
+                .with_measured_at(measured)
+                .with_received_at(measured + Duration::from_millis(delay_ms));
+            assert_eq!(tracker.observe(&message).map(f64::round), Some(delay_ms as f64));
+        }
+
+        let stats = tracker.stats();
+        assert_eq!((stats.messages, stats.timestamped), (3, 3));
+        assert_eq!(stats.latency_mean_ms.map(f64::round), Some(250.0));
+        assert_eq!(stats.latency_min_ms.map(f64::round), Some(200.0));
+        // 200/16, then + (100 - 12.5)/16
+        assert_eq!(stats.jitter_ms.map(|jitter| (jitter * 1000.0).round()), Some(17_969.0));
+
+        // Untimed messages only contribute arrival jitter
+        let mut untimed = LatencyTracker::default();
+        for arrival_ms in [0, 1_000, 2_500] {
+            let message = DataMessage::new("DEPTH".to_string(), "SOUNDER".to_string(), Vec::new())
+                .with_received_at(start + Duration::from_millis(arrival_ms));
+            assert!(untimed.observe(&message).is_none());
+        }
+        let stats = untimed.stats();
+        assert_eq!(stats.latency_mean_ms, None);
+        assert_eq!(stats.jitter_ms.map(f64::round), Some(31.0));
+    }
+}
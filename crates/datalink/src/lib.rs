@@ -7,11 +7,34 @@
 //! different transport mechanisms (serial, network, simulation, etc.)
 //! without being tightly coupled to the specific implementation.
 
+use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::time::{Duration, SystemTime};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime};
 use thiserror::Error;
 
+mod bridge;
+pub mod geo;
+mod key;
+mod latency;
+mod loopback;
+mod rate_limit;
+mod schema;
+mod stats;
+mod time;
+mod value;
+
+pub use bridge::{BridgeStats, DataLinkBridge, MessageFilter};
+pub use key::{DataKey, COMMON_KEYS};
+pub use latency::{LatencyStats, LatencyTracker, DEFAULT_LATENCY_SAMPLES};
+pub use loopback::{LoopbackConfig, LoopbackDataLink, LoopbackStats, DEFAULT_LOOPBACK_SEED};
+pub use rate_limit::{RateLimitedReceiver, RateLimiter, RATE_LIMIT_PARAM, RATE_LIMIT_PREFIX};
+pub use schema::{FieldSpec, MessageSchema, SchemaRegistry, ValidatingReceiver, ValueType, VALIDATE_PARAM};
+pub use stats::LinkStats;
+pub use value::Value;
+pub use time::{utc_from_nmea, utc_from_parts, wall_clock_now, ManualClock, SystemClock, TimeReconciler, TimeSource, DEFAULT_RECONCILER_SAMPLES};
+
 /// Errors that can occur in the data-link layer
 #[derive(Error, Debug)]
 pub enum DataLinkError {
@@ -25,6 +48,8 @@ pub enum DataLinkError {
     InvalidConfig(String),
     #[error("Transport error: {0}")]
     TransportError(String),
+    #[error("Invalid message: {0}")]
+    InvalidMessage(String),
 }
 
 /// Result type for data-link operations
@@ -37,35 +62,56 @@ pub struct DataMessage {
     pub message_type: String,
     /// Source identifier (e.g., MMSI for AIS, device ID for GPS)
     pub source_id: String,
-    /// Timestamp when the message was created/received
-    pub timestamp: SystemTime,
-    /// Raw message payload
-    pub payload: Vec<u8>,
+    /// Local time the message was received
+    pub received_at: SystemTime,
+    /// Time the source measured the data, when it reports one (e.g. GPS UTC)
+    #[serde(default)]
+    pub measured_at: Option<SystemTime>,
+    /// Raw message payload; clones share the buffer
+    pub payload: Bytes,
     /// Parsed message data as key-value pairs
-    pub data: HashMap<String, String>,
+    pub data: HashMap<DataKey, Value>,
     /// Signal strength or quality indicator (0-100)
     pub signal_quality: Option<u8>,
 }
 
 impl DataMessage {
     /// Create a new data message
-    pub fn new(message_type: String, source_id: String, payload: Vec<u8>) -> Self {
+    pub fn new(message_type: String, source_id: String, payload: impl Into<Bytes>) -> Self {
         Self {
             message_type,
             source_id,
-            timestamp: SystemTime::now(),
-            payload,
+            received_at: wall_clock_now(),
+            measured_at: None,
+            payload: payload.into(),
             data: HashMap::new(),
             signal_quality: None,
         }
     }
 
     /// Add parsed data to the message
-    pub fn with_data(mut self, key: String, value: String) -> Self {
-        self.data.insert(key, value);
+    pub fn with_data(mut self, key: impl Into<DataKey>, value: impl Into<Value>) -> Self {
+        self.data.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set the local receive time, e.g. from a [`TimeSource`]
+    pub fn with_received_at(mut self, received_at: SystemTime) -> Self {
+        self.received_at = received_at;
+        self
+    }
+
+    /// Set the time the source measured the data
+    pub fn with_measured_at(mut self, measured_at: SystemTime) -> Self {
+        self.measured_at = Some(measured_at);
         self
     }
 
+    /// Best known time for the data: measured if reported, otherwise received
+    pub fn timestamp(&self) -> SystemTime {
+        self.measured_at.unwrap_or(self.received_at)
+    }
+
     /// Set signal quality
     pub fn with_signal_quality(mut self, quality: u8) -> Self {
         self.signal_quality = Some(quality.min(100));
@@ -73,9 +119,45 @@ impl DataMessage {
     }
 
     /// Get a data value by key
-    pub fn get_data(&self, key: &str) -> Option<&String> {
+    pub fn get_data(&self, key: &str) -> Option<&Value> {
         self.data.get(key)
     }
+
+    /// Numeric value of a field, including numeric text
+    pub fn get_f64(&self, key: &str) -> Option<f64> {
+        self.get_data(key)?.as_f64()
+    }
+
+    pub fn get_f32(&self, key: &str) -> Option<f32> {
+        self.get_f64(key).map(|value| value as f32)
+    }
+
+    pub fn get_i64(&self, key: &str) -> Option<i64> {
+        self.get_data(key)?.as_i64()
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        self.get_data(key)?.as_bool()
+    }
+
+    /// Text of a `Text` field
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get_data(key)?.as_str()
+    }
+
+    pub fn get_lat_lon(&self, key: &str) -> Option<(f64, f64)> {
+        self.get_data(key)?.as_lat_lon()
+    }
+
+    /// Degrees of an angle field
+    pub fn get_angle(&self, key: &str) -> Option<f64> {
+        self.get_data(key)?.as_angle()
+    }
+
+    /// Check the data against the schema registered for this message type
+    pub fn validate(&self, registry: &SchemaRegistry) -> DataLinkResult<()> {
+        registry.validate(self)
+    }
 }
 
 /// Configuration for a data-link connection
@@ -151,10 +233,61 @@ pub trait DataLinkReceiver: Send + Sync {
     /// Disconnect from the data source
     fn disconnect(&mut self) -> DataLinkResult<()>;
 
+    /// Switch a connected receiver to `config`, e.g. another baud rate or
+    /// host. Providers that support it check the new configuration before
+    /// replacing their transport and keep queued messages and [`LinkStats`].
+    /// The default disconnects and connects again, dropping the queue.
+    fn reconfigure(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        self.disconnect()?;
+        self.connect(config)
+    }
+
     /// Check if the connection is active
     fn is_connected(&self) -> bool {
         matches!(self.status(), DataLinkStatus::Connected)
     }
+
+    /// What the receiver has read so far; providers that do not keep
+    /// counts report zeros
+    fn stats(&self) -> LinkStats {
+        LinkStats::default()
+    }
+}
+
+/// Boxed receivers forward to the boxed value so wrappers such as
+/// [`RateLimitedReceiver`] can sit on top of a `Box<dyn DataLinkReceiver>`
+impl<R: DataLinkReceiver + ?Sized> DataLinkReceiver for Box<R> {
+    fn status(&self) -> DataLinkStatus {
+        (**self).status()
+    }
+
+    fn receive_message(&mut self) -> DataLinkResult<Option<DataMessage>> {
+        (**self).receive_message()
+    }
+
+    fn receive_all_messages(&mut self) -> DataLinkResult<Vec<DataMessage>> {
+        (**self).receive_all_messages()
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        (**self).connect(config)
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        (**self).disconnect()
+    }
+
+    fn reconfigure(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        (**self).reconfigure(config)
+    }
+
+    fn is_connected(&self) -> bool {
+        (**self).is_connected()
+    }
+
+    fn stats(&self) -> LinkStats {
+        (**self).stats()
+    }
 }
 
 /// Trait for data-link transmitters that can send messages
@@ -183,74 +316,348 @@ pub trait DataLink: DataLinkReceiver + DataLinkTransmitter {}
 /// Automatic implementation for types that implement both receiver and transmitter
 impl<T> DataLink for T where T: DataLinkReceiver + DataLinkTransmitter {}
 
+/// Simulated traffic drifting further than this from own-ship is brought
+/// back on the opposite side, so there is always traffic nearby
+pub const SIMULATED_TRAFFIC_RADIUS_NM: f64 = 6.0;
+
+/// Live own-ship position the simulation anchors its traffic to
+pub trait PositionSource: Send + Sync {
+    /// Latest (latitude, longitude), if there is a fix
+    fn position(&self) -> Option<(f64, f64)>;
+}
+
+/// Position source updated by whoever receives the live GPS fixes
+#[derive(Debug, Clone, Default)]
+pub struct SharedPosition(Arc<RwLock<Option<(f64, f64)>>>);
+
+impl SharedPosition {
+    pub fn set(&self, latitude: f64, longitude: f64) {
+        *self.0.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some((latitude, longitude));
+    }
+
+    pub fn clear(&self) {
+        *self.0.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+    }
+}
+
+impl PositionSource for SharedPosition {
+    fn position(&self) -> Option<(f64, f64)> {
+        *self.0.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A simulated vessel moving on a constant course and speed
+#[derive(Debug, Clone)]
+pub struct SimulatedVessel {
+    pub mmsi: String,
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Speed over ground in knots
+    pub speed: f64,
+    /// Course over ground in degrees true
+    pub course: f64,
+    pub signal_quality: u8,
+}
+
+impl SimulatedVessel {
+    fn advance(&mut self, seconds: f64) {
+        let (latitude, longitude) =
+            project_position(self.latitude, self.longitude, self.course, self.speed * seconds / 3600.0);
+        self.latitude = latitude;
+        self.longitude = longitude;
+    }
+}
+
+/// Move a position `distance_nm` along `course_deg`
+fn project_position(latitude: f64, longitude: f64, course_deg: f64, distance_nm: f64) -> (f64, f64) {
+    geo::destination((latitude, longitude), course_deg, distance_nm * geo::METERS_PER_NM)
+}
+
 /// A simulation data-link for testing and demonstration purposes
+///
+/// Besides AIS traffic the link simulates an own-ship GPS on a configurable
+/// course, radar returns for the AIS targets, depth soundings and apparent wind,
+/// so the whole instrument cluster can be driven without hardware. The own-ship
+/// track can be configured with the `latitude`, `longitude`, `course` and `speed`
+/// parameters, and `update_interval_ms` controls how often a new batch of
+/// messages is produced.
+///
+/// AIS traffic is placed by range and bearing from own-ship. With a
+/// [`PositionSource`] attached, own-ship follows the live GPS fix and the
+/// traffic moves along with it, so demos show nearby vessels wherever the
+/// boat actually is.
 pub struct SimulationDataLink {
     status: DataLinkStatus,
     config: Option<DataLinkConfig>,
     message_queue: Vec<DataMessage>,
+    own_ship: SimulatedVessel,
+    targets: Vec<SimulatedVessel>,
+    /// True wind direction (degrees) and speed (knots) used for the wind generator
+    true_wind: (f64, f64),
+    elapsed_secs: f64,
+    update_interval: Duration,
+    last_update: Option<Instant>,
+    position_source: Option<Arc<dyn PositionSource>>,
 }
 
 impl SimulationDataLink {
     /// Create a new simulation data-link
     pub fn new() -> Self {
+        let own_ship = SimulatedVessel {
+            mmsi: "000000000".to_string(),
+            name: "OWN SHIP".to_string(),
+            latitude: 37.7699,
+            longitude: -122.4194,
+            speed: 6.5,
+            course: 45.0,
+            signal_quality: 95,
+        };
+        let targets = Self::default_targets((own_ship.latitude, own_ship.longitude));
         Self {
             status: DataLinkStatus::Disconnected,
             config: None,
             message_queue: Vec::new(),
+            own_ship,
+            targets,
+            true_wind: (225.0, 14.0),
+            elapsed_secs: 0.0,
+            update_interval: Duration::from_secs(1),
+            last_update: None,
+            position_source: None,
         }
     }
 
+    /// Follow the live own-ship position from `source` whenever it has a fix
+    pub fn with_position_source(mut self, source: Arc<dyn PositionSource>) -> Self {
+        self.position_source = Some(source);
+        self
+    }
+
+    /// Traffic around `own_position`, each target at a range (nm) and bearing
+    fn default_targets(own_position: (f64, f64)) -> Vec<SimulatedVessel> {
+        let place = |mmsi: &str, name: &str, range_nm: f64, bearing: f64, speed: f64, course: f64, signal_quality: u8| {
+            let (latitude, longitude) = project_position(own_position.0, own_position.1, bearing, range_nm);
+            SimulatedVessel {
+                mmsi: mmsi.to_string(),
+                name: name.to_string(),
+                latitude,
+                longitude,
+                speed,
+                course,
+                signal_quality,
+            }
+        };
+        vec![
+            place("987654321", "M/Y SERENITY", 0.3, 0.0, 12.5, 180.0, 85),
+            place("456789123", "CARGO VESSEL ATLANTIS", 1.0, 28.0, 18.2, 90.0, 92),
+            place("789123456", "S/Y WIND DANCER", 0.6, 237.0, 6.8, 225.0, 78),
+        ]
+    }
+
+    /// Move own-ship to a position, carrying the traffic along at the same
+    /// ranges and bearings
+    pub fn set_own_ship_position(&mut self, latitude: f64, longitude: f64) {
+        let previous = self.own_ship_position();
+        for target in &mut self.targets {
+            let (range, bearing) = geo::range_and_bearing_nm(previous, (target.latitude, target.longitude));
+            (target.latitude, target.longitude) = project_position(latitude, longitude, bearing, range);
+        }
+        self.own_ship.latitude = latitude;
+        self.own_ship.longitude = longitude;
+    }
+
+    /// Bring targets that left the traffic radius back on the far side of own-ship
+    fn recycle_distant_traffic(&mut self) {
+        let own_position = self.own_ship_position();
+        for target in &mut self.targets {
+            let (range, bearing) = geo::range_and_bearing_nm(own_position, (target.latitude, target.longitude));
+            if range > SIMULATED_TRAFFIC_RADIUS_NM {
+                (target.latitude, target.longitude) = project_position(
+                    own_position.0,
+                    own_position.1,
+                    (bearing + 180.0).rem_euclid(360.0),
+                    SIMULATED_TRAFFIC_RADIUS_NM * 0.9,
+                );
+            }
+        }
+    }
+
+    /// Apply own-ship parameters from the configuration, keeping defaults for missing keys
+    fn apply_config(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        let parse = |key: &str| -> DataLinkResult<Option<f64>> {
+            config
+                .parameters
+                .get(key)
+                .map(|value| {
+                    value.parse::<f64>().map_err(|_| {
+                        DataLinkError::InvalidConfig(format!("Invalid simulation parameter {}: {}", key, value))
+                    })
+                })
+                .transpose()
+        };
+
+        if let Some(latitude) = parse("latitude")? {
+            self.own_ship.latitude = latitude;
+        }
+        if let Some(longitude) = parse("longitude")? {
+            self.own_ship.longitude = longitude;
+        }
+        if let Some(course) = parse("course")? {
+            self.own_ship.course = course.rem_euclid(360.0);
+        }
+        if let Some(speed) = parse("speed")? {
+            self.own_ship.speed = speed;
+        }
+        self.targets = Self::default_targets(self.own_ship_position());
+        if let Some(interval) = parse("update_interval_ms")? {
+            self.update_interval = Duration::from_millis(interval.max(0.0) as u64);
+        }
+        Ok(())
+    }
+
     /// Add a simulated message to the queue
     pub fn add_simulated_message(&mut self, message: DataMessage) {
         self.message_queue.push(message);
     }
 
+    /// Current simulated own-ship position as (latitude, longitude)
+    pub fn own_ship_position(&self) -> (f64, f64) {
+        (self.own_ship.latitude, self.own_ship.longitude)
+    }
+
+    /// Advance own-ship and traffic along their courses by the given simulated
+    /// time, then snap own-ship to the live position if there is one
+    pub fn advance(&mut self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        self.own_ship.advance(seconds);
+        for target in &mut self.targets {
+            target.advance(seconds);
+        }
+        self.elapsed_secs += seconds;
+
+        if let Some((latitude, longitude)) = self.position_source.as_ref().and_then(|source| source.position()) {
+            self.set_own_ship_position(latitude, longitude);
+        }
+        self.recycle_distant_traffic();
+    }
+
+    /// Generate one batch of AIS, GPS, radar, depth and wind messages
+    pub fn generate_sample_messages(&mut self) {
+        self.generate_sample_ais_messages();
+        self.generate_sample_gps_messages();
+        self.generate_sample_radar_messages();
+        self.generate_sample_depth_messages();
+        self.generate_sample_wind_messages();
+    }
+
     /// Generate sample AIS messages for testing
     pub fn generate_sample_ais_messages(&mut self) {
-        let messages = vec![
-            DataMessage::new(
-                "AIS_POSITION".to_string(),
-                "987654321".to_string(),
-                b"!AIVDM,1,1,,A,15M8J7001G?UJH@E=4R0S>0@0<0M,0*7B".to_vec(),
-            )
-            .with_data("vessel_name".to_string(), "M/Y SERENITY".to_string())
-            .with_data("mmsi".to_string(), "987654321".to_string())
-            .with_data("latitude".to_string(), "37.7749".to_string())
-            .with_data("longitude".to_string(), "-122.4194".to_string())
-            .with_data("speed".to_string(), "12.5".to_string())
-            .with_data("course".to_string(), "180".to_string())
-            .with_signal_quality(85),
-
-            DataMessage::new(
-                "AIS_POSITION".to_string(),
-                "456789123".to_string(),
-                b"!AIVDM,1,1,,A,15M8J7001G?UJH@E=4R0S>0@0<0M,0*7B".to_vec(),
-            )
-            .with_data("vessel_name".to_string(), "CARGO VESSEL ATLANTIS".to_string())
-            .with_data("mmsi".to_string(), "456789123".to_string())
-            .with_data("latitude".to_string(), "37.7849".to_string())
-            .with_data("longitude".to_string(), "-122.4094".to_string())
-            .with_data("speed".to_string(), "18.2".to_string())
-            .with_data("course".to_string(), "090".to_string())
-            .with_signal_quality(92),
-
-            DataMessage::new(
-                "AIS_POSITION".to_string(),
-                "789123456".to_string(),
-                b"!AIVDM,1,1,,A,15M8J7001G?UJH@E=4R0S>0@0M,0*7B".to_vec(),
-            )
-            .with_data("vessel_name".to_string(), "S/Y WIND DANCER".to_string())
-            .with_data("mmsi".to_string(), "789123456".to_string())
-            .with_data("latitude".to_string(), "37.7649".to_string())
-            .with_data("longitude".to_string(), "-122.4294".to_string())
-            .with_data("speed".to_string(), "6.8".to_string())
-            .with_data("course".to_string(), "225".to_string())
-            .with_signal_quality(78),
-        ];
-
-        for message in messages {
-            self.message_queue.push(message);
-        }
+        let messages: Vec<DataMessage> = self
+            .targets
+            .iter()
+            .map(|target| {
+                DataMessage::new(
+                    "AIS_POSITION".to_string(),
+                    target.mmsi.clone(),
+                    b"!AIVDM,1,1,,A,15M8J7001G?UJH@E=4R0S>0@0<0M,0*7B".to_vec(),
+                )
+                .with_data("vessel_name", target.name.clone())
+                .with_data("mmsi", target.mmsi.clone())
+                .with_data("latitude", target.latitude)
+                .with_data("longitude", target.longitude)
+                .with_data("speed", target.speed)
+                .with_data("course", Value::Angle(target.course))
+                .with_signal_quality(target.signal_quality)
+            })
+            .collect();
+
+        self.message_queue.extend(messages);
+    }
+
+    /// Generate an own-ship GPS fix at the current simulated position
+    pub fn generate_sample_gps_messages(&mut self) {
+        let message = DataMessage::new(
+            "GPS_POSITION".to_string(),
+            "SIM_GPS".to_string(),
+            Vec::new(),
+        )
+        .with_data("position", Value::LatLon { lat: self.own_ship.latitude, lon: self.own_ship.longitude })
+        .with_data("latitude", self.own_ship.latitude)
+        .with_data("longitude", self.own_ship.longitude)
+        .with_data("speed", self.own_ship.speed)
+        .with_data("course", Value::Angle(self.own_ship.course))
+        .with_data("fix_quality", 1)
+        .with_data("satellites", 9)
+        .with_signal_quality(self.own_ship.signal_quality);
+
+        self.message_queue.push(message);
+    }
+
+    /// Generate radar returns for the simulated AIS traffic, relative to own-ship
+    pub fn generate_sample_radar_messages(&mut self) {
+        let own_position = self.own_ship_position();
+        let messages: Vec<DataMessage> = self
+            .targets
+            .iter()
+            .enumerate()
+            .map(|(index, target)| {
+                let (range, bearing) = geo::range_and_bearing_nm(own_position, (target.latitude, target.longitude));
+                DataMessage::new(
+                    "RADAR_TARGET".to_string(),
+                    format!("SIM_RADAR_{}", index + 1),
+                    Vec::new(),
+                )
+                .with_data("target_id", index + 1)
+                .with_data("range_nm", range)
+                .with_data("bearing_deg", Value::Angle(bearing))
+                .with_data("speed_kts", target.speed)
+                .with_data("course_deg", Value::Angle(target.course))
+                .with_signal_quality(80)
+            })
+            .collect();
+
+        self.message_queue.extend(messages);
+    }
+
+    /// Generate a depth sounding that slowly varies with simulated time
+    pub fn generate_sample_depth_messages(&mut self) {
+        let depth = 12.0 + 3.0 * (self.elapsed_secs / 60.0).sin();
+        let message = DataMessage::new(
+            "DEPTH".to_string(),
+            "SIM_DEPTH".to_string(),
+            Vec::new(),
+        )
+        .with_data("depth_m", depth)
+        .with_signal_quality(90);
+
+        self.message_queue.push(message);
+    }
+
+    /// Generate apparent wind as seen from own-ship for the simulated true wind
+    pub fn generate_sample_wind_messages(&mut self) {
+        let (true_direction, true_speed) = self.true_wind;
+        let true_speed = true_speed + 2.0 * (self.elapsed_secs / 20.0).sin();
+
+        // Wind vector blowing *from* true_direction, expressed in the boat frame,
+        // plus the headwind caused by the boat's own motion.
+        let relative = (true_direction - self.own_ship.course).to_radians();
+        let ahead = true_speed * relative.cos() + self.own_ship.speed;
+        let across = true_speed * relative.sin();
+        let apparent_speed = (ahead * ahead + across * across).sqrt();
+        let apparent_angle = across.atan2(ahead).to_degrees().rem_euclid(360.0);
+
+        let message = DataMessage::new(
+            "WIND".to_string(),
+            "SIM_WIND".to_string(),
+            Vec::new(),
+        )
+        .with_data("apparent_wind_speed", apparent_speed)
+        .with_data("apparent_wind_angle", Value::Angle(apparent_angle))
+        .with_data("reference", "R")
+        .with_signal_quality(90);
+
+        self.message_queue.push(message);
     }
 }
 
@@ -266,6 +673,19 @@ impl DataLinkReceiver for SimulationDataLink {
     }
 
     fn receive_message(&mut self) -> DataLinkResult<Option<DataMessage>> {
+        if matches!(self.status, DataLinkStatus::Connected) && self.message_queue.is_empty() {
+            // Produce the next batch once the update interval has passed
+            let now = Instant::now();
+            if let Some(last_update) = self.last_update {
+                let elapsed = now.duration_since(last_update);
+                if elapsed >= self.update_interval {
+                    self.advance(elapsed);
+                    self.generate_sample_messages();
+                    self.last_update = Some(now);
+                }
+            }
+        }
+
         if matches!(self.status, DataLinkStatus::Connected) && !self.message_queue.is_empty() {
             Ok(Some(self.message_queue.remove(0)))
         } else {
@@ -275,10 +695,15 @@ impl DataLinkReceiver for SimulationDataLink {
 
     fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
         if config.connection_type == "simulation" {
+            self.apply_config(config)?;
+            if let Some((latitude, longitude)) = self.position_source.as_ref().and_then(|source| source.position()) {
+                self.set_own_ship_position(latitude, longitude);
+            }
             self.config = Some(config.clone());
             self.status = DataLinkStatus::Connected;
             // Generate some sample messages when connecting
-            self.generate_sample_ais_messages();
+            self.generate_sample_messages();
+            self.last_update = Some(Instant::now());
             Ok(())
         } else {
             Err(DataLinkError::InvalidConfig(
@@ -287,10 +712,16 @@ impl DataLinkReceiver for SimulationDataLink {
         }
     }
 
+    /// Move own ship and change the update interval; queued messages stay
+    fn reconfigure(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        DataLinkReceiver::connect(self, config)
+    }
+
     fn disconnect(&mut self) -> DataLinkResult<()> {
         self.status = DataLinkStatus::Disconnected;
         self.config = None;
         self.message_queue.clear();
+        self.last_update = None;
         Ok(())
     }
 }
@@ -337,7 +768,7 @@ mod tests {
 
         assert_eq!(message.message_type, "TEST");
         assert_eq!(message.source_id, "123");
-        assert_eq!(message.get_data("key1"), Some(&"value1".to_string()));
+        assert_eq!(message.get_str("key1"), Some("value1"));
         assert_eq!(message.signal_quality, Some(75));
     }
 
@@ -362,6 +793,92 @@ mod tests {
         assert_eq!(<SimulationDataLink as DataLinkReceiver>::status(&datalink), DataLinkStatus::Disconnected);
     }
 
+    #[test]
+    fn test_simulation_covers_all_instruments() {
+        let mut datalink = SimulationDataLink::new();
+        let config = DataLinkConfig::new("simulation".to_string())
+            .with_parameter("latitude".to_string(), "37.8".to_string())
+            .with_parameter("longitude".to_string(), "-122.4".to_string())
+            .with_parameter("course".to_string(), "90".to_string())
+            .with_parameter("speed".to_string(), "10".to_string());
+        <SimulationDataLink as DataLinkReceiver>::connect(&mut datalink, &config).unwrap();
+
+        let messages = <SimulationDataLink as DataLinkReceiver>::receive_all_messages(&mut datalink).unwrap();
+        for message_type in ["AIS_POSITION", "GPS_POSITION", "RADAR_TARGET", "DEPTH", "WIND"] {
+            assert!(messages.iter().any(|m| m.message_type == message_type), "missing {}", message_type);
+        }
+
+        let ais_count = messages.iter().filter(|m| m.message_type == "AIS_POSITION").count();
+        let radar_count = messages.iter().filter(|m| m.message_type == "RADAR_TARGET").count();
+        assert_eq!(ais_count, radar_count);
+
+        let gps = messages.iter().find(|m| m.message_type == "GPS_POSITION").unwrap();
+        assert_eq!(gps.get_f64("latitude"), Some(37.8));
+        assert_eq!(gps.get_data("course"), Some(&Value::Angle(90.0)));
+        assert_eq!(gps.get_lat_lon("position"), Some((37.8, -122.4)));
+    }
+
+    #[test]
+    fn test_simulation_own_ship_follows_course() {
+        let mut datalink = SimulationDataLink::new();
+        let config = DataLinkConfig::new("simulation".to_string())
+            .with_parameter("latitude".to_string(), "37.0".to_string())
+            .with_parameter("longitude".to_string(), "-122.0".to_string())
+            .with_parameter("course".to_string(), "0".to_string())
+            .with_parameter("speed".to_string(), "6".to_string());
+        <SimulationDataLink as DataLinkReceiver>::connect(&mut datalink, &config).unwrap();
+
+        // Six knots due north for an hour is six nautical miles, about a tenth of a degree
+        datalink.advance(Duration::from_secs(3600));
+        let (latitude, longitude) = datalink.own_ship_position();
+        assert!((geo::distance_m((37.0, -122.0), (latitude, longitude)) - 6.0 * geo::METERS_PER_NM).abs() < 1e-6);
+        assert!((latitude - 37.1).abs() < 1e-3);
+        assert!((longitude + 122.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_simulation_traffic_follows_live_position() {
+        let live = SharedPosition::default();
+        let mut datalink = SimulationDataLink::new().with_position_source(Arc::new(live.clone()));
+        live.set(59.9, 10.7);
+        <SimulationDataLink as DataLinkReceiver>::connect(&mut datalink, &DataLinkConfig::new("simulation".to_string())).unwrap();
+
+        let traffic_ranges = |datalink: &mut SimulationDataLink| -> Vec<f64> {
+            let own = datalink.own_ship_position();
+            datalink.receive_all_messages().unwrap();
+            datalink.generate_sample_ais_messages();
+            datalink
+                .receive_all_messages()
+                .unwrap()
+                .iter()
+                .filter(|message| message.message_type == "AIS_POSITION")
+                .map(|message| {
+                    geo::range_and_bearing_nm(own, (message.get_f64("latitude").unwrap(), message.get_f64("longitude").unwrap())).0
+                })
+                .collect()
+        };
+
+        assert_eq!(datalink.own_ship_position(), (59.9, 10.7));
+        let ranges = traffic_ranges(&mut datalink);
+        assert_eq!(ranges.len(), 3);
+        assert!(ranges.iter().all(|range| (range - 0.3).abs() < 1e-6 || (range - 1.0).abs() < 1e-6 || (range - 0.6).abs() < 1e-6));
+
+        // The boat moves; traffic keeps its place around it, and after hours
+        // of simulated time none of it has wandered off
+        live.set(60.0, 10.8);
+        datalink.advance(Duration::from_secs(4 * 3600));
+        assert_eq!(datalink.own_ship_position(), (60.0, 10.8));
+        assert!(traffic_ranges(&mut datalink).iter().all(|range| *range <= SIMULATED_TRAFFIC_RADIUS_NM));
+    }
+
+    #[test]
+    fn test_simulation_rejects_invalid_parameters() {
+        let mut datalink = SimulationDataLink::new();
+        let config = DataLinkConfig::new("simulation".to_string())
+            .with_parameter("speed".to_string(), "fast".to_string());
+        assert!(<SimulationDataLink as DataLinkReceiver>::connect(&mut datalink, &config).is_err());
+    }
+
     #[test]
     fn test_datalink_config() {
         let config = DataLinkConfig::new("tcp".to_string())
@@ -0,0 +1,409 @@
+//! Loopback data-link pair for tests
+//!
+//! [`LoopbackDataLink::pair`] returns two connected ends: whatever one end
+//! transmits, the other receives. Each direction can add latency, jitter,
+//! random drops and payload corruption, all driven by a seeded generator and
+//! an injectable [`TimeSource`], so a test replays the same impairments on
+//! every run. Ends can also be severed to exercise reconnect handling.
+//!
+//! Impairments come from the [`LoopbackConfig`] or, on `connect`, from the
+//! `latency_ms`, `jitter_ms`, `drop_rate`, `corruption_rate` and `seed`
+//! parameters.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+
+use crate::time::{SystemClock, TimeSource};
+use crate::{DataLinkConfig, DataLinkError, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataLinkTransmitter, DataMessage};
+
+/// Seed used when none is configured
+pub const DEFAULT_LOOPBACK_SEED: u64 = 0x5EED_1005_BAC4;
+
+/// Impairments applied to each direction of a loopback pair
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoopbackConfig {
+    pub latency: Duration,
+    /// Extra delay drawn uniformly from zero up to this; can reorder messages
+    pub jitter: Duration,
+    /// Probability of losing a message, 0.0 to 1.0
+    pub drop_rate: f64,
+    /// Probability of damaging a delivered message, 0.0 to 1.0
+    pub corruption_rate: f64,
+    pub seed: u64,
+}
+
+impl Default for LoopbackConfig {
+    fn default() -> Self {
+        Self {
+            latency: Duration::ZERO,
+            jitter: Duration::ZERO,
+            drop_rate: 0.0,
+            corruption_rate: 0.0,
+            seed: DEFAULT_LOOPBACK_SEED,
+        }
+    }
+}
+
+impl LoopbackConfig {
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn with_drop_rate(mut self, drop_rate: f64) -> Self {
+        self.drop_rate = drop_rate.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_corruption_rate(mut self, corruption_rate: f64) -> Self {
+        self.corruption_rate = corruption_rate.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Override impairments with those given as `config` parameters
+    pub fn apply(mut self, config: &DataLinkConfig) -> DataLinkResult<Self> {
+        let param = |key: &str| config.parameters.get(key).map(String::as_str);
+        let invalid = |key: &str, value: &str| DataLinkError::InvalidConfig(format!("Invalid {}: {}", key, value));
+        let millis = |key: &str, value: &str| value.parse::<u64>().map(Duration::from_millis).map_err(|_| invalid(key, value));
+        let rate = |key: &str, value: &str| {
+            value
+                .parse::<f64>()
+                .ok()
+                .filter(|rate| (0.0..=1.0).contains(rate))
+                .ok_or_else(|| invalid(key, value))
+        };
+
+        if let Some(value) = param("latency_ms") {
+            self.latency = millis("latency_ms", value)?;
+        }
+        if let Some(value) = param("jitter_ms") {
+            self.jitter = millis("jitter_ms", value)?;
+        }
+        if let Some(value) = param("drop_rate") {
+            self.drop_rate = rate("drop_rate", value)?;
+        }
+        if let Some(value) = param("corruption_rate") {
+            self.corruption_rate = rate("corruption_rate", value)?;
+        }
+        if let Some(value) = param("seed") {
+            self.seed = value.parse().map_err(|_| invalid("seed", value))?;
+        }
+        Ok(self)
+    }
+}
+
+/// What happened to the messages sent in one direction
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoopbackStats {
+    pub sent: u64,
+    pub dropped: u64,
+    pub corrupted: u64,
+    pub delivered: u64,
+}
+
+/// SplitMix64, small and good enough to draw impairments from
+#[derive(Debug, Clone)]
+struct SeededRng(u64);
+
+impl SeededRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in 0.0..1.0
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound.max(1) as u64) as usize
+    }
+}
+
+/// One direction of the pair: messages in flight and their impairments
+#[derive(Debug)]
+struct Direction {
+    config: LoopbackConfig,
+    rng: SeededRng,
+    /// Keyed by delivery time, then send order
+    in_flight: BTreeMap<(SystemTime, u64), DataMessage>,
+    stats: LoopbackStats,
+    receiver_connected: bool,
+}
+
+impl Direction {
+    fn new(config: LoopbackConfig, stream: u64) -> Self {
+        let rng = SeededRng(config.seed ^ stream);
+        Self {
+            config,
+            rng,
+            in_flight: BTreeMap::new(),
+            stats: LoopbackStats::default(),
+            receiver_connected: true,
+        }
+    }
+
+    fn reconfigure(&mut self, config: LoopbackConfig, stream: u64) {
+        self.rng = SeededRng(config.seed ^ stream);
+        self.config = config;
+    }
+
+    fn send(&mut self, mut message: DataMessage, now: SystemTime) {
+        self.stats.sent += 1;
+        // Draw every number up front so a message's fate does not depend on the others
+        let lost = self.rng.next_f64() < self.config.drop_rate;
+        let damaged = self.rng.next_f64() < self.config.corruption_rate;
+        let jitter = self.config.jitter.mul_f64(self.rng.next_f64());
+        if lost || !self.receiver_connected {
+            self.stats.dropped += 1;
+            return;
+        }
+        if damaged {
+            self.corrupt(&mut message);
+            self.stats.corrupted += 1;
+        }
+        let deliver_at = now + self.config.latency + jitter;
+        self.in_flight.insert((deliver_at, self.stats.sent), message);
+    }
+
+    /// Flip one bit of the payload; a message without payload loses a data field instead
+    fn corrupt(&mut self, message: &mut DataMessage) {
+        if !message.payload.is_empty() {
+            let mut payload = message.payload.to_vec();
+            let index = self.rng.below(payload.len());
+            payload[index] ^= 1 << self.rng.below(8);
+            message.payload = payload.into();
+        } else if !message.data.is_empty() {
+            let mut keys: Vec<_> = message.data.keys().cloned().collect();
+            keys.sort_by(|a, b| str::cmp(a, b));
+            let key = keys.swap_remove(self.rng.below(keys.len()));
+            message.data.remove(&key);
+        }
+    }
+
+    fn next_due(&mut self, now: SystemTime) -> Option<DataMessage> {
+        let entry = self.in_flight.first_entry().filter(|entry| entry.key().0 <= now)?;
+        self.stats.delivered += 1;
+        Some(entry.remove())
+    }
+}
+
+fn lock(direction: &Mutex<Direction>) -> MutexGuard<'_, Direction> {
+    direction.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// One end of a loopback pair
+pub struct LoopbackDataLink {
+    outbound: Arc<Mutex<Direction>>,
+    inbound: Arc<Mutex<Direction>>,
+    /// Distinguishes the two directions' random streams
+    stream: u64,
+    clock: Arc<dyn TimeSource>,
+    connected: bool,
+    severed: bool,
+}
+
+impl LoopbackDataLink {
+    /// Two connected ends on the system clock
+    pub fn pair(config: LoopbackConfig) -> (Self, Self) {
+        Self::pair_with_clock(config, Arc::new(SystemClock))
+    }
+
+    /// Two connected ends timed by `clock`, e.g. a [`ManualClock`](crate::ManualClock)
+    pub fn pair_with_clock(config: LoopbackConfig, clock: Arc<dyn TimeSource>) -> (Self, Self) {
+        let a_to_b = Arc::new(Mutex::new(Direction::new(config.clone(), 0)));
+        let b_to_a = Arc::new(Mutex::new(Direction::new(config, 1)));
+        let end = |outbound: &Arc<Mutex<Direction>>, inbound: &Arc<Mutex<Direction>>, stream| Self {
+            outbound: Arc::clone(outbound),
+            inbound: Arc::clone(inbound),
+            stream,
+            clock: Arc::clone(&clock),
+            connected: true,
+            severed: false,
+        };
+        (end(&a_to_b, &b_to_a, 0), end(&b_to_a, &a_to_b, 1))
+    }
+
+    /// Cut the link: sends fail and the status reports an error until [`restore`](Self::restore)
+    pub fn sever(&mut self) {
+        self.severed = true;
+    }
+
+    pub fn restore(&mut self) {
+        self.severed = false;
+    }
+
+    /// Counts for the messages this end has sent
+    pub fn stats(&self) -> LoopbackStats {
+        lock(&self.outbound).stats
+    }
+
+    /// Messages sent towards this end that have not been delivered yet
+    pub fn in_flight(&self) -> usize {
+        lock(&self.inbound).in_flight.len()
+    }
+}
+
+impl DataLinkReceiver for LoopbackDataLink {
+    fn status(&self) -> DataLinkStatus {
+        match (self.connected, self.severed) {
+            (false, _) => DataLinkStatus::Disconnected,
+            (true, true) => DataLinkStatus::Error("Loopback link severed".to_string()),
+            (true, false) => DataLinkStatus::Connected,
+        }
+    }
+
+    fn receive_message(&mut self) -> DataLinkResult<Option<DataMessage>> {
+        if !self.connected {
+            return Err(DataLinkError::ConnectionFailed("Loopback end not connected".to_string()));
+        }
+        if self.severed {
+            return Ok(None);
+        }
+        Ok(lock(&self.inbound).next_due(self.clock.now()))
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        let mut outbound = lock(&self.outbound);
+        let impairments = outbound.config.clone().apply(config)?;
+        outbound.reconfigure(impairments, self.stream);
+        drop(outbound);
+        lock(&self.inbound).receiver_connected = true;
+        self.connected = true;
+        self.severed = false;
+        Ok(())
+    }
+
+    /// Change the impairments; messages in flight are kept
+    fn reconfigure(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        DataLinkReceiver::connect(self, config)
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        let mut inbound = lock(&self.inbound);
+        inbound.receiver_connected = false;
+        inbound.in_flight.clear();
+        self.connected = false;
+        Ok(())
+    }
+}
+
+impl DataLinkTransmitter for LoopbackDataLink {
+    fn status(&self) -> DataLinkStatus {
+        DataLinkReceiver::status(self)
+    }
+
+    fn send_message(&mut self, message: &DataMessage) -> DataLinkResult<()> {
+        if !self.connected {
+            return Err(DataLinkError::ConnectionFailed("Loopback end not connected".to_string()));
+        }
+        if self.severed {
+            return Err(DataLinkError::TransportError("Loopback link severed".to_string()));
+        }
+        lock(&self.outbound).send(message.clone(), self.clock.now());
+        Ok(())
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        DataLinkReceiver::connect(self, config)
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        DataLinkReceiver::disconnect(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::ManualClock;
+    use std::time::UNIX_EPOCH;
+
+    fn sentence(index: usize) -> DataMessage {
+        DataMessage::new("DEPTH".to_string(), "SOUNDER".to_string(), format!("$SDDPT,{}.0,0.5", index).into_bytes())
+            .with_data("depth_m", index as f64)
+    }
+
+    fn run(config: LoopbackConfig) -> (Vec<DataMessage>, LoopbackStats) {
+        let clock = ManualClock::new(UNIX_EPOCH);
+        let (mut sender, mut receiver) = LoopbackDataLink::pair_with_clock(config, Arc::new(clock.clone()));
+        for index in 0..200 {
+            sender.send_message(&sentence(index)).unwrap();
+            clock.advance(Duration::from_millis(10));
+        }
+        clock.advance(Duration::from_secs(1));
+        (receiver.receive_all_messages().unwrap(), sender.stats())
+    }
+
+    #[test]
+    fn test_loopback_latency_and_deterministic_impairments() {
+        let clock = ManualClock::new(UNIX_EPOCH);
+        let config = LoopbackConfig::default().with_latency(Duration::from_millis(100));
+        let (mut a, mut b) = LoopbackDataLink::pair_with_clock(config, Arc::new(clock.clone()));
+        a.send_message(&sentence(1)).unwrap();
+        b.send_message(&sentence(2)).unwrap();
+        assert!(b.receive_message().unwrap().is_none(), "still in flight");
+        assert_eq!(b.in_flight(), 1);
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(b.receive_message().unwrap().unwrap().get_f64("depth_m"), Some(1.0));
+        assert_eq!(a.receive_message().unwrap().unwrap().get_f64("depth_m"), Some(2.0));
+
+        // The same seed loses and damages the same messages on every run
+        let lossy = LoopbackConfig::default()
+            .with_jitter(Duration::from_millis(50))
+            .with_drop_rate(0.2)
+            .with_corruption_rate(0.1)
+            .with_seed(7);
+        let (first, stats) = run(lossy.clone());
+        let (second, _) = run(lossy.clone());
+        let payloads = |messages: &[DataMessage]| messages.iter().map(|m| m.payload.clone()).collect::<Vec<_>>();
+        assert_eq!(payloads(&first), payloads(&second));
+        assert_eq!(stats.sent, 200);
+        assert_eq!(stats.delivered as usize, first.len());
+        assert_eq!(stats.dropped + stats.delivered, 200);
+        assert!((20..60).contains(&stats.dropped), "dropped {}", stats.dropped);
+        assert!(stats.corrupted > 0);
+        assert!(first.windows(2).any(|pair| pair[0].get_f64("depth_m") > pair[1].get_f64("depth_m")), "jitter reorders");
+        assert_ne!(run(lossy.with_seed(8)).1, stats);
+    }
+
+    #[test]
+    fn test_loopback_sever_and_reconnect() {
+        let (mut a, mut b) = LoopbackDataLink::pair(LoopbackConfig::default());
+        a.sever();
+        assert!(a.send_message(&sentence(1)).is_err());
+        assert!(matches!(DataLinkReceiver::status(&a), DataLinkStatus::Error(_)));
+        a.restore();
+
+        // Messages towards a disconnected end are lost
+        DataLinkReceiver::disconnect(&mut b).unwrap();
+        a.send_message(&sentence(2)).unwrap();
+        assert!(b.receive_message().is_err());
+        let config = DataLinkConfig::new("loopback".to_string()).with_parameter("drop_rate".to_string(), "1.0".to_string());
+        DataLinkReceiver::connect(&mut b, &config).unwrap();
+        assert!(b.receive_message().unwrap().is_none());
+        assert_eq!(a.stats().dropped, 1);
+
+        // b now drops everything it sends
+        b.send_message(&sentence(3)).unwrap();
+        assert!(a.receive_message().unwrap().is_none());
+        assert_eq!(b.stats().dropped, 1);
+        let bad = DataLinkConfig::new("loopback".to_string()).with_parameter("drop_rate".to_string(), "2".to_string());
+        assert!(DataLinkReceiver::connect(&mut b, &bad).is_err());
+    }
+}
@@ -0,0 +1,310 @@
+//! Rate limiting for high-rate sources
+//!
+//! A 10 Hz GPS produces far more position fixes than the UI can use. The
+//! [`RateLimiter`] lets at most one message per interval through for each
+//! message type and source, holding back only the most recent message seen in
+//! between so nothing stale is delivered once the interval elapses.
+//!
+//! Intervals come from [`DataLinkConfig`] parameters:
+//!
+//! * `rate_limit_ms` - interval applied to every message type
+//! * `rate_limit.<MESSAGE_TYPE>` - interval for one message type, e.g.
+//!   `rate_limit.GPS_POSITION = 500`; overrides `rate_limit_ms`
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::{DataLinkConfig, DataLinkError, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataMessage, LinkStats};
+
+/// Parameter holding the interval applied to all message types
+pub const RATE_LIMIT_PARAM: &str = "rate_limit_ms";
+
+/// Prefix of the per-message-type interval parameters
+pub const RATE_LIMIT_PREFIX: &str = "rate_limit.";
+
+type StreamKey = (String, String);
+
+fn stream_key(message: &DataMessage) -> StreamKey {
+    (message.message_type.clone(), message.source_id.clone())
+}
+
+/// Coalesces messages so each stream emits at most once per interval
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    default_interval: Option<Duration>,
+    intervals: HashMap<String, Duration>,
+    last_emitted: HashMap<StreamKey, Instant>,
+    pending: HashMap<StreamKey, DataMessage>,
+    pending_order: VecDeque<StreamKey>,
+    coalesced: u64,
+}
+
+impl RateLimiter {
+    /// Create a limiter that lets everything through
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read intervals from the `rate_limit*` parameters of `config`
+    pub fn from_config(config: &DataLinkConfig) -> DataLinkResult<Self> {
+        let parse_ms = |key: &str, value: &str| {
+            value.parse::<u64>().map(Duration::from_millis).map_err(|_| {
+                DataLinkError::InvalidConfig(format!("Invalid {}: {}", key, value))
+            })
+        };
+
+        let mut limiter = Self::new();
+        for (key, value) in &config.parameters {
+            if key == RATE_LIMIT_PARAM {
+                limiter.default_interval = Some(parse_ms(key, value)?);
+            } else if let Some(message_type) = key.strip_prefix(RATE_LIMIT_PREFIX) {
+                limiter.intervals.insert(message_type.to_string(), parse_ms(key, value)?);
+            }
+        }
+        Ok(limiter)
+    }
+
+    /// Apply `interval` to every message type without its own setting
+    pub fn with_default_interval(mut self, interval: Duration) -> Self {
+        self.default_interval = Some(interval);
+        self
+    }
+
+    /// Limit one message type to one message per `interval`
+    pub fn with_interval(mut self, message_type: &str, interval: Duration) -> Self {
+        self.intervals.insert(message_type.to_string(), interval);
+        self
+    }
+
+    /// Interval in effect for `message_type`, if it is limited at all
+    pub fn interval_for(&self, message_type: &str) -> Option<Duration> {
+        self.intervals
+            .get(message_type)
+            .copied()
+            .or(self.default_interval)
+            .filter(|interval| !interval.is_zero())
+    }
+
+    /// Whether any limit is configured
+    pub fn is_active(&self) -> bool {
+        self.default_interval
+            .iter()
+            .chain(self.intervals.values())
+            .any(|interval| !interval.is_zero())
+    }
+
+    /// Number of messages replaced by a newer one before delivery
+    pub fn coalesced(&self) -> u64 {
+        self.coalesced
+    }
+
+    /// Offer a message; returns it if it may be delivered now, otherwise it
+    /// is held back until [`release_due`](Self::release_due) lets it out
+    pub fn offer(&mut self, message: DataMessage, now: Instant) -> Option<DataMessage> {
+        let Some(interval) = self.interval_for(&message.message_type) else {
+            return Some(message);
+        };
+
+        let key = stream_key(&message);
+        let due = self
+            .last_emitted
+            .get(&key)
+            .is_none_or(|last| now.duration_since(*last) >= interval);
+
+        if due {
+            if self.pending.remove(&key).is_some() {
+                self.pending_order.retain(|pending| pending != &key);
+                self.coalesced += 1;
+            }
+            self.last_emitted.insert(key, now);
+            Some(message)
+        } else {
+            if self.pending.insert(key.clone(), message).is_some() {
+                self.coalesced += 1;
+            } else {
+                self.pending_order.push_back(key);
+            }
+            None
+        }
+    }
+
+    /// Held-back messages whose interval has elapsed, oldest stream first
+    pub fn release_due(&mut self, now: Instant) -> Vec<DataMessage> {
+        let mut released = Vec::new();
+        let mut waiting = VecDeque::with_capacity(self.pending_order.len());
+
+        while let Some(key) = self.pending_order.pop_front() {
+            let interval = self.interval_for(&key.0).unwrap_or_default();
+            let due = self
+                .last_emitted
+                .get(&key)
+                .is_none_or(|last| now.duration_since(*last) >= interval);
+
+            if due {
+                if let Some(message) = self.pending.remove(&key) {
+                    released.push(message);
+                }
+                self.last_emitted.insert(key, now);
+            } else {
+                waiting.push_back(key);
+            }
+        }
+
+        self.pending_order = waiting;
+        released
+    }
+
+    /// Drop all held-back messages and timing state
+    pub fn reset(&mut self) {
+        self.last_emitted.clear();
+        self.pending.clear();
+        self.pending_order.clear();
+    }
+}
+
+/// Receiver wrapper applying a [`RateLimiter`] configured on connect
+pub struct RateLimitedReceiver<R: DataLinkReceiver> {
+    inner: R,
+    limiter: RateLimiter,
+    ready: VecDeque<DataMessage>,
+}
+
+impl<R: DataLinkReceiver> RateLimitedReceiver<R> {
+    /// Wrap `inner`; limits are read from the config passed to `connect`
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            limiter: RateLimiter::new(),
+            ready: VecDeque::new(),
+        }
+    }
+
+    /// Access the rate limiter, e.g. to read the coalesced count
+    pub fn limiter(&self) -> &RateLimiter {
+        &self.limiter
+    }
+
+    /// Access the wrapped receiver
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwrap, discarding any held-back messages
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: DataLinkReceiver> DataLinkReceiver for RateLimitedReceiver<R> {
+    fn status(&self) -> DataLinkStatus {
+        self.inner.status()
+    }
+
+    fn receive_message(&mut self) -> DataLinkResult<Option<DataMessage>> {
+        let now = Instant::now();
+        self.ready.extend(self.limiter.release_due(now));
+
+        while self.ready.is_empty() {
+            match self.inner.receive_message()? {
+                Some(message) => {
+                    if let Some(message) = self.limiter.offer(message, now) {
+                        self.ready.push_back(message);
+                    }
+                }
+                None => break,
+            }
+        }
+
+        Ok(self.ready.pop_front())
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        self.limiter = RateLimiter::from_config(config)?;
+        self.ready.clear();
+        self.inner.connect(config)
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        self.limiter.reset();
+        self.ready.clear();
+        self.inner.disconnect()
+    }
+
+    /// Apply the new limits from here on. Held-back messages are released
+    /// rather than dropped, and the coalesced count carries over.
+    fn reconfigure(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        let mut limiter = RateLimiter::from_config(config)?;
+        self.inner.reconfigure(config)?;
+        let held = std::mem::take(&mut self.limiter.pending_order);
+        self.ready.extend(held.into_iter().filter_map(|key| self.limiter.pending.remove(&key)));
+        limiter.coalesced = self.limiter.coalesced;
+        self.limiter = limiter;
+        Ok(())
+    }
+
+    fn stats(&self) -> LinkStats {
+        self.inner.stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fix(source: &str, latitude: &str) -> DataMessage {
+        DataMessage::new("GPS_POSITION".to_string(), source.to_string(), Vec::new())
+            .with_data("latitude", latitude.to_string())
+    }
+
+    #[test]
+    fn test_rate_limiter_keeps_latest_per_interval() {
+        let config = DataLinkConfig::new("gps".to_string())
+            .with_parameter("rate_limit.GPS_POSITION".to_string(), "500".to_string());
+        let mut limiter = RateLimiter::from_config(&config).unwrap();
+        let start = Instant::now();
+
+        // 10 Hz fixes: first goes through, the rest collapse into the latest
+        assert!(limiter.offer(fix("GPS", "1"), start).is_some());
+        for (tick, latitude) in ["2", "3", "4", "5"].iter().enumerate() {
+            let now = start + Duration::from_millis(100 * (tick as u64 + 1));
+            assert!(limiter.offer(fix("GPS", latitude), now).is_none());
+        }
+        assert!(limiter.release_due(start + Duration::from_millis(450)).is_empty());
+
+        let released = limiter.release_due(start + Duration::from_millis(500));
+        assert_eq!(released.len(), 1);
+        assert_eq!(released[0].get_f64("latitude"), Some(5.0));
+        assert_eq!(limiter.coalesced(), 3);
+
+        // Other sources and unlimited types are independent
+        assert!(limiter.offer(fix("GPS2", "9"), start + Duration::from_millis(600)).is_some());
+        let depth = DataMessage::new("DEPTH".to_string(), "SIM".to_string(), Vec::new());
+        assert!(limiter.offer(depth.clone(), start).is_some());
+        assert!(limiter.offer(depth, start).is_some());
+
+        let invalid = DataLinkConfig::new("gps".to_string())
+            .with_parameter(RATE_LIMIT_PARAM.to_string(), "fast".to_string());
+        assert!(RateLimiter::from_config(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_reconfigure_releases_held_back_messages() {
+        use crate::{DataLinkTransmitter, LoopbackConfig, LoopbackDataLink};
+
+        let (mut gps, receiver) = LoopbackDataLink::pair(LoopbackConfig::default());
+        let mut limited = RateLimitedReceiver::new(receiver);
+        let limit = |ms: &str| DataLinkConfig::new("loopback".to_string()).with_parameter(RATE_LIMIT_PARAM.to_string(), ms.to_string());
+        limited.connect(&limit("60000")).unwrap();
+        for latitude in ["1", "2", "3"] {
+            gps.send_message(&fix("GPS", latitude)).unwrap();
+        }
+        assert_eq!(limited.receive_message().unwrap().unwrap().get_f64("latitude"), Some(1.0));
+        assert!(limited.receive_message().unwrap().is_none());
+
+        // The held-back fix comes out under the new limits instead of being lost
+        assert!(limited.reconfigure(&limit("soon")).is_err());
+        limited.reconfigure(&limit("0")).unwrap();
+        assert_eq!(limited.receive_message().unwrap().unwrap().get_f64("latitude"), Some(3.0));
+        assert_eq!(limited.limiter().coalesced(), 1);
+    }
+}
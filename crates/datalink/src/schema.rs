@@ -0,0 +1,308 @@
+//! Message schemas
+//!
+//! A [`SchemaRegistry`] records which `data` keys each message type carries
+//! and what kind of value they hold. Providers can validate their parser
+//! output against it, or wrap a receiver in a [`ValidatingReceiver`] to drop
+//! malformed messages before they reach the UI.
+
+use std::collections::HashMap;
+
+use crate::{DataLinkConfig, DataLinkError, DataLinkReceiver, DataLinkResult, DataLinkStatus, DataMessage, LinkStats, Value};
+
+/// Parameter enabling validation on a datalink (`"true"` to enable)
+pub const VALIDATE_PARAM: &str = "validate";
+
+/// Kind of value stored under a data key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    /// Any value, including empty text
+    Text,
+    Integer,
+    Float,
+    Boolean,
+}
+
+impl ValueType {
+    /// Whether a value is of this type; numeric and boolean text is accepted
+    /// for sources that report text
+    pub fn accepts(self, value: &Value) -> bool {
+        match self {
+            ValueType::Text => true,
+            ValueType::Integer => value.as_i64().is_some(),
+            ValueType::Float => value.as_f64().is_some_and(f64::is_finite),
+            ValueType::Boolean => value.as_bool().is_some(),
+        }
+    }
+}
+
+/// One declared data key
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldSpec {
+    pub key: String,
+    pub value_type: ValueType,
+    pub required: bool,
+}
+
+/// Declared data keys of one message type
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageSchema {
+    pub message_type: String,
+    pub fields: Vec<FieldSpec>,
+}
+
+impl MessageSchema {
+    pub fn new(message_type: &str) -> Self {
+        Self {
+            message_type: message_type.to_string(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Declare a key every message must carry
+    pub fn required(mut self, key: &str, value_type: ValueType) -> Self {
+        self.fields.push(FieldSpec { key: key.to_string(), value_type, required: true });
+        self
+    }
+
+    /// Declare a key that is checked only when present
+    pub fn optional(mut self, key: &str, value_type: ValueType) -> Self {
+        self.fields.push(FieldSpec { key: key.to_string(), value_type, required: false });
+        self
+    }
+
+    /// Check a message's data against the declared keys. Undeclared keys are allowed.
+    pub fn validate(&self, message: &DataMessage) -> DataLinkResult<()> {
+        for field in &self.fields {
+            match message.get_data(&field.key) {
+                Some(value) if !field.value_type.accepts(value) => {
+                    return Err(DataLinkError::InvalidMessage(format!(
+                        "{} field '{}' is not {:?}: {:?}",
+                        self.message_type, field.key, field.value_type, value
+                    )));
+                }
+                None if field.required => {
+                    return Err(DataLinkError::InvalidMessage(format!(
+                        "{} is missing required field '{}'",
+                        self.message_type, field.key
+                    )));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Schemas by message type
+#[derive(Debug, Clone, Default)]
+pub struct SchemaRegistry {
+    schemas: HashMap<String, MessageSchema>,
+}
+
+impl SchemaRegistry {
+    /// An empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry with the message types produced by the bundled datalinks
+    pub fn with_defaults() -> Self {
+        use ValueType::*;
+
+        let mut registry = Self::new();
+        registry.register(
+            MessageSchema::new("GPS_POSITION")
+                .required("latitude", Float)
+                .required("longitude", Float)
+                .optional("speed", Float)
+                .optional("course", Float)
+                .optional("fix_quality", Integer)
+                .optional("satellites", Integer),
+        );
+        registry.register(MessageSchema::new("GPS_SENTENCE").required("sentence_type", Text));
+        registry.register(
+            MessageSchema::new("AIS_SENTENCE")
+                .required("sentence_type", Text)
+                .required("fragment_count", Integer)
+                .required("fragment_number", Integer)
+                .required("payload", Text)
+                .optional("fill_bits", Integer),
+        );
+        registry.register(
+            MessageSchema::new("RADAR_TARGET")
+                .required("range_nm", Float)
+                .required("bearing_deg", Float)
+                .optional("speed_kts", Float)
+                .optional("course_deg", Float)
+                .optional("cpa_nm", Float),
+        );
+        registry.register(
+            MessageSchema::new("RADAR_CONTROL")
+                .required("command", Text)
+                .required("value", Text),
+        );
+        registry.register(
+            MessageSchema::new("RADAR_SPOKE")
+                .required("angle_deg", Float)
+                .required("range_m", Float)
+                .optional("heading_true", Float),
+        );
+        registry.register(
+            MessageSchema::new("DEPTH")
+                .required("depth_m", Float)
+                .optional("transducer", Text),
+        );
+        registry.register(
+            MessageSchema::new("WIND")
+                .optional("apparent_wind_speed", Float)
+                .optional("apparent_wind_angle", Float)
+                .optional("true_wind_speed", Float)
+                .optional("true_wind_angle", Float)
+                .optional("wind_direction_true", Float)
+                .optional("wind_direction_magnetic", Float)
+                .optional("reference", Text),
+        );
+        registry.register(
+            MessageSchema::new("HEADING")
+                .optional("heading_true", Float)
+                .optional("heading_magnetic", Float)
+                .optional("heading_compass", Float)
+                .optional("deviation", Float)
+                .optional("variation", Float),
+        );
+        registry.register(MessageSchema::new("SPEED_LOG").required("speed_through_water", Float));
+        registry.register(MessageSchema::new("WATER_TEMPERATURE").required("water_temp_c", Float));
+        registry.register(
+            MessageSchema::new("AUTOPILOT")
+                .required("sentence_type", Text)
+                .optional("cross_track_nm", Float)
+                .optional("bearing_to_destination", Float)
+                .optional("heading_to_steer", Float)
+                .optional("destination_range_nm", Float)
+                .optional("arrived", Boolean),
+        );
+        registry.register(
+            MessageSchema::new("AUTOPILOT_COMMAND")
+                .required("heading_to_steer", Float)
+                .optional("destination_id", Text),
+        );
+        registry
+    }
+
+    /// Add or replace the schema for its message type
+    pub fn register(&mut self, schema: MessageSchema) {
+        self.schemas.insert(schema.message_type.clone(), schema);
+    }
+
+    pub fn get(&self, message_type: &str) -> Option<&MessageSchema> {
+        self.schemas.get(message_type)
+    }
+
+    /// Validate a message; types without a schema pass
+    pub fn validate(&self, message: &DataMessage) -> DataLinkResult<()> {
+        match self.get(&message.message_type) {
+            Some(schema) => schema.validate(message),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Receiver wrapper that drops messages failing schema validation
+pub struct ValidatingReceiver<R: DataLinkReceiver> {
+    inner: R,
+    registry: SchemaRegistry,
+    rejected: u64,
+    last_error: Option<String>,
+}
+
+impl<R: DataLinkReceiver> ValidatingReceiver<R> {
+    pub fn new(inner: R, registry: SchemaRegistry) -> Self {
+        Self {
+            inner,
+            registry,
+            rejected: 0,
+            last_error: None,
+        }
+    }
+
+    /// Number of messages dropped as invalid
+    pub fn rejected(&self) -> u64 {
+        self.rejected
+    }
+
+    /// Reason the most recent message was dropped
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+}
+
+impl<R: DataLinkReceiver> DataLinkReceiver for ValidatingReceiver<R> {
+    fn status(&self) -> DataLinkStatus {
+        self.inner.status()
+    }
+
+    fn receive_message(&mut self) -> DataLinkResult<Option<DataMessage>> {
+        while let Some(message) = self.inner.receive_message()? {
+            match self.registry.validate(&message) {
+                Ok(()) => return Ok(Some(message)),
+                Err(e) => {
+                    self.rejected += 1;
+                    self.last_error = Some(e.to_string());
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn connect(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        self.inner.connect(config)
+    }
+
+    fn disconnect(&mut self) -> DataLinkResult<()> {
+        self.inner.disconnect()
+    }
+
+    fn reconfigure(&mut self, config: &DataLinkConfig) -> DataLinkResult<()> {
+        self.inner.reconfigure(config)
+    }
+
+    fn stats(&self) -> LinkStats {
+        self.inner.stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SimulationDataLink;
+
+    #[test]
+    fn test_schema_validation() {
+        let registry = SchemaRegistry::with_defaults();
+        let depth = |value: &str| {
+            DataMessage::new("DEPTH".to_string(), "SOUNDER".to_string(), Vec::new())
+                .with_data("depth_m", value.to_string())
+        };
+
+        assert!(depth("12.5").validate(&registry).is_ok());
+        assert!(matches!(depth("deep").validate(&registry), Err(DataLinkError::InvalidMessage(_))));
+        let missing = DataMessage::new("DEPTH".to_string(), "SOUNDER".to_string(), Vec::new());
+        assert!(missing.validate(&registry).is_err());
+        let unknown = DataMessage::new("ENGINE".to_string(), "ECU".to_string(), Vec::new());
+        assert!(unknown.validate(&registry).is_ok());
+
+        // Everything the simulator produces conforms to the default schemas
+        let mut simulation = SimulationDataLink::new();
+        simulation.connect(&DataLinkConfig::new("simulation".to_string())).unwrap();
+        simulation.add_simulated_message(depth("NaN"));
+        let mut receiver = ValidatingReceiver::new(simulation, registry);
+        let messages = receiver.receive_all_messages().unwrap();
+        assert!(!messages.is_empty());
+        assert_eq!(receiver.rejected(), 1);
+        assert!(receiver.last_error().unwrap().contains("depth_m"));
+    }
+}
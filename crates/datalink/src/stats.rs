@@ -0,0 +1,64 @@
+//! Provider statistics
+//!
+//! "Connected but nothing shows" usually means the provider reads lines it
+//! cannot use: a talker or sentence type it does not handle, or a noisy line
+//! failing its checksum. [`LinkStats`] counts what happened to each line so
+//! that can be told apart from a silent source.
+
+use std::collections::BTreeMap;
+
+/// What a provider has read since it was created
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinkStats {
+    /// Lines, datagrams or frames taken from the source
+    pub lines_read: u64,
+    /// Lines that became messages
+    pub sentences_parsed: u64,
+    /// Lines that did not parse and whose checksum did not match
+    pub checksum_failures: u64,
+    /// Lines the provider does not handle, by sentence address (`GNRMC`,
+    /// `PGN 130306`, ...)
+    pub unsupported: BTreeMap<String, u64>,
+    /// Times the source was reopened after the link dropped
+    pub reconnects: u64,
+}
+
+impl LinkStats {
+    /// Count a line that became a message
+    pub fn record_parsed(&mut self) {
+        self.lines_read += 1;
+        self.sentences_parsed += 1;
+    }
+
+    /// Count a line whose checksum failed
+    pub fn record_checksum_failure(&mut self) {
+        self.lines_read += 1;
+        self.checksum_failures += 1;
+    }
+
+    /// Count a line of a kind the provider does not handle
+    pub fn record_unsupported(&mut self, kind: &str) {
+        self.lines_read += 1;
+        *self.unsupported.entry(kind.to_string()).or_default() += 1;
+    }
+
+    pub fn record_reconnect(&mut self) {
+        self.reconnects += 1;
+    }
+
+    /// Unsupported lines of every kind
+    pub fn unsupported_sentences(&self) -> u64 {
+        self.unsupported.values().sum()
+    }
+
+    /// Add another provider's counts, for providers built on others
+    pub fn merge(&mut self, other: &LinkStats) {
+        self.lines_read += other.lines_read;
+        self.sentences_parsed += other.sentences_parsed;
+        self.checksum_failures += other.checksum_failures;
+        self.reconnects += other.reconnects;
+        for (kind, count) in &other.unsupported {
+            *self.unsupported.entry(kind.clone()).or_default() += count;
+        }
+    }
+}
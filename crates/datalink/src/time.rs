@@ -0,0 +1,227 @@
+//! Time sources and message time reconciliation
+//!
+//! Every [`DataMessage`](crate::DataMessage) carries the local time it was
+//! received and, when the source reports one, the time it was measured (for
+//! example the UTC time in a GPS RMC or ZDA sentence). The
+//! [`TimeReconciler`] compares the two to estimate how far the local clock is
+//! from GPS time.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::DataMessage;
+
+/// Provides the current time to data-links
+pub trait TimeSource: Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// The local system clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl TimeSource for SystemClock {
+    fn now(&self) -> SystemTime {
+        wall_clock_now()
+    }
+}
+
+/// Current wall-clock time. `SystemTime::now` panics on wasm32, so browser
+/// builds read the JavaScript clock instead.
+pub fn wall_clock_now() -> SystemTime {
+    #[cfg(target_arch = "wasm32")]
+    {
+        UNIX_EPOCH + Duration::from_secs_f64(js_sys::Date::now() / 1000.0)
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        SystemTime::now()
+    }
+}
+
+/// A clock that only moves when told to, for replays and tests
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<SystemTime>>,
+}
+
+impl ManualClock {
+    pub fn new(start: SystemTime) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(start)),
+        }
+    }
+
+    pub fn set(&self, time: SystemTime) {
+        if let Ok(mut now) = self.now.lock() {
+            *now = time;
+        }
+    }
+
+    pub fn advance(&self, elapsed: Duration) {
+        if let Ok(mut now) = self.now.lock() {
+            *now += elapsed;
+        }
+    }
+}
+
+impl TimeSource for ManualClock {
+    fn now(&self) -> SystemTime {
+        self.now.lock().map(|now| *now).unwrap_or(UNIX_EPOCH)
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Build a UTC time from calendar parts and an NMEA `hhmmss.ss` time field
+pub fn utc_from_parts(year: i32, month: u32, day: u32, hhmmss: &str) -> Option<SystemTime> {
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hhmmss.len() < 6 {
+        return None;
+    }
+
+    let hours: u64 = hhmmss.get(0..2)?.parse().ok()?;
+    let minutes: u64 = hhmmss.get(2..4)?.parse().ok()?;
+    let seconds: f64 = hhmmss.get(4..)?.parse().ok()?;
+    if hours > 23 || minutes > 59 || !(0.0..61.0).contains(&seconds) {
+        return None;
+    }
+
+    let days = days_from_civil(year as i64, month, day);
+    let day_start = u64::try_from(days).ok()? * 86_400;
+    let secs = day_start + hours * 3_600 + minutes * 60;
+    Some(UNIX_EPOCH + Duration::from_secs(secs) + Duration::from_secs_f64(seconds))
+}
+
+/// UTC time from the `ddmmyy` date and `hhmmss.ss` time fields of an RMC sentence
+pub fn utc_from_nmea(ddmmyy: &str, hhmmss: &str) -> Option<SystemTime> {
+    if ddmmyy.len() != 6 {
+        return None;
+    }
+    let day: u32 = ddmmyy.get(0..2)?.parse().ok()?;
+    let month: u32 = ddmmyy.get(2..4)?.parse().ok()?;
+    let year: i32 = ddmmyy.get(4..6)?.parse().ok()?;
+    // Two-digit years pivot at 1980, the start of the GPS epoch
+    let year = if year < 80 { 2000 + year } else { 1900 + year };
+    utc_from_parts(year, month, day, hhmmss)
+}
+
+/// Signed seconds from `earlier` to `later`
+pub(crate) fn signed_secs(later: SystemTime, earlier: SystemTime) -> f64 {
+    match later.duration_since(earlier) {
+        Ok(ahead) => ahead.as_secs_f64(),
+        Err(behind) => -behind.duration().as_secs_f64(),
+    }
+}
+
+/// Default number of offset samples kept by a [`TimeReconciler`]
+pub const DEFAULT_RECONCILER_SAMPLES: usize = 16;
+
+/// Estimates the offset of the local clock from measured (GPS) time
+#[derive(Debug, Clone)]
+pub struct TimeReconciler {
+    samples: VecDeque<f64>,
+    max_samples: usize,
+}
+
+impl TimeReconciler {
+    pub fn new(max_samples: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(max_samples),
+            max_samples: max_samples.max(1),
+        }
+    }
+
+    /// Record the offset of a message carrying a measured time; returns the
+    /// sample (measured minus received, in seconds) if there was one
+    pub fn observe(&mut self, message: &DataMessage) -> Option<f64> {
+        let offset = signed_secs(message.measured_at?, message.received_at);
+        if self.samples.len() == self.max_samples {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(offset);
+        Some(offset)
+    }
+
+    /// Median offset in seconds; positive when GPS time is ahead of the local clock
+    pub fn offset_secs(&self) -> Option<f64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<f64> = self.samples.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        let middle = sorted.len() / 2;
+        Some(if sorted.len().is_multiple_of(2) {
+            (sorted[middle - 1] + sorted[middle]) / 2.0
+        } else {
+            sorted[middle]
+        })
+    }
+
+    /// Convert a local timestamp to estimated GPS time
+    pub fn to_reference_time(&self, local: SystemTime) -> SystemTime {
+        match self.offset_secs() {
+            Some(offset) if offset >= 0.0 => local + Duration::from_secs_f64(offset),
+            Some(offset) => local - Duration::from_secs_f64(-offset),
+            None => local,
+        }
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.samples.len()
+    }
+}
+
+impl Default for TimeReconciler {
+    fn default() -> Self {
+        Self::new(DEFAULT_RECONCILER_SAMPLES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nmea_time_parsing() {
+        // 2024-03-23 12:35:19 UTC
+        let time = utc_from_nmea("230324", "123519.00").unwrap();
+        assert_eq!(time.duration_since(UNIX_EPOCH).unwrap().as_secs(), 1_711_197_319);
+        assert_eq!(utc_from_parts(1970, 1, 1, "000000"), Some(UNIX_EPOCH));
+        assert!(utc_from_nmea("320124", "123519").is_none());
+        assert!(utc_from_nmea("230324", "25").is_none());
+    }
+
+    #[test]
+    fn test_reconciler_median_offset() {
+        let clock = ManualClock::new(utc_from_nmea("230324", "123519").unwrap());
+        let mut reconciler = TimeReconciler::new(3);
+
+        for (lag_ms, skew_secs) in [(0, 2.0), (1_000, 2.5), (2_000, 40.0), (3_000, 2.0)] {
+            clock.advance(Duration::from_millis(lag_ms));
+            let received = clock.now();
+            let message = DataMessage::new("GPS_SENTENCE".to_string(), "GPS".to_string(), Vec::new())
+                .with_received_at(received)
+                .with_measured_at(received - Duration::from_secs_f64(skew_secs));
+            assert!(reconciler.observe(&message).is_some());
+        }
+
+        // The 40 s outlier does not drag the median; the oldest sample was evicted
+        assert_eq!(reconciler.sample_count(), 3);
+        assert_eq!(reconciler.offset_secs(), Some(-2.5));
+        let local = clock.now();
+        assert_eq!(reconciler.to_reference_time(local), local - Duration::from_millis(2_500));
+
+        let untimed = DataMessage::new("DEPTH".to_string(), "SOUNDER".to_string(), Vec::new());
+        assert!(reconciler.observe(&untimed).is_none());
+    }
+}
@@ -0,0 +1,190 @@
+//! Typed data values
+//!
+//! Parsers store each field as a [`Value`] so consumers read numbers with
+//! [`DataMessage::get_f64`](crate::DataMessage::get_f64) and friends instead
+//! of re-parsing strings. Text values that hold a number still convert, so
+//! fields from sources that only report text work with the typed getters.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// A parsed data field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Value {
+    Float(f64),
+    Int(i64),
+    Bool(bool),
+    Text(String),
+    /// Decimal degrees, north and east positive
+    LatLon { lat: f64, lon: f64 },
+    /// Degrees
+    Angle(f64),
+}
+
+impl Value {
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Float(value) | Value::Angle(value) => Some(*value),
+            Value::Int(value) => Some(*value as f64),
+            Value::Text(text) => text.trim().parse().ok(),
+            Value::Bool(_) | Value::LatLon { .. } => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Int(value) => Some(*value),
+            Value::Float(value) if value.fract() == 0.0 => Some(*value as i64),
+            Value::Text(text) => text.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(value) => Some(*value),
+            Value::Text(text) => match text.trim() {
+                "true" => Some(true),
+                "false" => Some(false),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The text of a `Text` value; other variants have no borrowed text, use `to_string`
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn as_lat_lon(&self) -> Option<(f64, f64)> {
+        match self {
+            Value::LatLon { lat, lon } => Some((*lat, *lon)),
+            _ => None,
+        }
+    }
+
+    /// Degrees from an `Angle`, or any numeric value
+    pub fn as_angle(&self) -> Option<f64> {
+        self.as_f64()
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Float(value) | Value::Angle(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Text(text) => f.write_str(text),
+            Value::LatLon { lat, lon } => write!(f, "{:.6},{:.6}", lat, lon),
+        }
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<f32> for Value {
+    /// Keeps the shortest decimal form of the `f32` (12.3 stays 12.3, not 12.300000190734863)
+    fn from(value: f32) -> Self {
+        Value::Float(value.to_string().parse().unwrap_or(value as f64))
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Value::Int(value as i64)
+    }
+}
+
+impl From<i8> for Value {
+    fn from(value: i8) -> Self {
+        Value::Int(value as i64)
+    }
+}
+
+impl From<u8> for Value {
+    fn from(value: u8) -> Self {
+        Value::Int(value as i64)
+    }
+}
+
+impl From<u16> for Value {
+    fn from(value: u16) -> Self {
+        Value::Int(value as i64)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(value: u32) -> Self {
+        Value::Int(value as i64)
+    }
+}
+
+impl From<u64> for Value {
+    /// Saturates at `i64::MAX`
+    fn from(value: u64) -> Self {
+        Value::Int(i64::try_from(value).unwrap_or(i64::MAX))
+    }
+}
+
+impl From<usize> for Value {
+    fn from(value: usize) -> Self {
+        Value::Int(value as i64)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<String> for Value {
+    fn from(text: String) -> Self {
+        Value::Text(text)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(text: &str) -> Self {
+        Value::Text(text.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_conversions() {
+        assert_eq!(Value::from(12.3f32), Value::Float(12.3));
+        assert_eq!(Value::from(12.3f32).to_string(), "12.3");
+        assert_eq!(Value::from(-15i8).as_f64(), Some(-15.0));
+        assert_eq!(Value::from("08").as_i64(), Some(8));
+        assert_eq!(Value::from(" 4.5").as_f64(), Some(4.5));
+        assert_eq!(Value::from("AUTO").as_f64(), None);
+        assert_eq!(Value::Float(2.5).as_i64(), None);
+        assert_eq!(Value::from(true).as_bool(), Some(true));
+        assert_eq!(Value::Angle(270.0).as_angle(), Some(270.0));
+
+        let position = Value::LatLon { lat: 48.1173, lon: 11.516_666 };
+        assert_eq!(position.as_lat_lon(), Some((48.1173, 11.516_666)));
+        assert_eq!(position.as_f64(), None);
+        assert_eq!(position.to_string(), "48.117300,11.516666");
+    }
+}
@@ -2,7 +2,8 @@
 //! 
 //! Provides a communication infrastructure for virtual hardware devices
 
-use crate::{HardwareError, Result};
+use crate::recorder::{BusEvent, BusRecorder, RecordedEvent};
+use crate::{DeviceStatus, HardwareError, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -36,7 +37,7 @@ impl BusAddress {
 }
 
 /// Message types that can be sent over the hardware bus
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum BusMessage {
     /// Data message with payload
     Data {
@@ -66,7 +67,7 @@ pub enum BusMessage {
 }
 
 /// Control commands for bus management
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ControlCommand {
     /// Register a device on the bus
     Register { address: BusAddress },
@@ -115,6 +116,10 @@ pub struct DeviceConnection {
 pub struct HardwareBus {
     devices: Arc<RwLock<HashMap<BusAddress, mpsc::UnboundedSender<BusMessage>>>>,
     message_log: Arc<RwLock<Vec<BusMessage>>>,
+    /// Last status each device reported, to record only transitions
+    device_status: Arc<RwLock<HashMap<BusAddress, DeviceStatus>>>,
+    /// Ring log of bus traffic, when recording is enabled
+    recorder: Option<Arc<RwLock<BusRecorder>>>,
 }
 
 impl Default for HardwareBus {
@@ -129,6 +134,27 @@ impl HardwareBus {
         Self {
             devices: Arc::new(RwLock::new(HashMap::new())),
             message_log: Arc::new(RwLock::new(Vec::new())),
+            device_status: Arc::new(RwLock::new(HashMap::new())),
+            recorder: None,
+        }
+    }
+
+    /// Create a hardware bus that records its traffic into `recorder`
+    pub fn with_recorder(recorder: BusRecorder) -> Self {
+        Self {
+            recorder: Some(Arc::new(RwLock::new(recorder))),
+            ..Self::new()
+        }
+    }
+
+    /// The traffic recorder, if recording is enabled
+    pub fn recorder(&self) -> Option<&Arc<RwLock<BusRecorder>>> {
+        self.recorder.as_ref()
+    }
+
+    async fn record(&self, event: BusEvent) {
+        if let Some(recorder) = &self.recorder {
+            recorder.write().await.record(event);
         }
     }
 
@@ -147,6 +173,7 @@ impl HardwareBus {
         }
 
         info!("Device {} connected to bus", address.name);
+        self.record(BusEvent::Connected { address: address.clone() }).await;
 
         // Send registration message to all other devices
         let register_msg = BusMessage::Control {
@@ -172,8 +199,10 @@ impl HardwareBus {
             let mut devices = self.devices.write().await;
             devices.remove(address);
         }
+        self.device_status.write().await.remove(address);
 
         info!("Device {} disconnected from bus", address.name);
+        self.record(BusEvent::Disconnected { address: address.clone() }).await;
 
         // Send unregistration message to all other devices
         let unregister_msg = BusMessage::Control {
@@ -196,6 +225,7 @@ impl HardwareBus {
             let mut log = self.message_log.write().await;
             log.push(message.clone());
         }
+        self.record(BusEvent::Message { message: message.clone() }).await;
 
         match &message {
             BusMessage::Data { to, .. } => {
@@ -242,7 +272,7 @@ impl HardwareBus {
                 }
             }
 
-            if let Err(_) = sender.send(message.clone()) {
+            if sender.send(message.clone()).is_err() {
                 error!("Failed to broadcast message to device: {}", address.name);
             }
         }
@@ -273,12 +303,56 @@ impl HardwareBus {
         let devices = self.devices.read().await;
         devices.contains_key(address)
     }
+
+    /// Note a device's status, recording it when it differs from the last report
+    pub async fn report_status(&self, address: &BusAddress, status: DeviceStatus) {
+        {
+            let mut statuses = self.device_status.write().await;
+            if statuses.get(address) == Some(&status) {
+                return;
+            }
+            statuses.insert(address.clone(), status.clone());
+        }
+        debug!("Device {} status changed to {:?}", address.name, status);
+        self.record(BusEvent::StatusChanged {
+            address: address.clone(),
+            status,
+        })
+        .await;
+    }
+
+    /// Play a recording back onto this bus in order, returning the
+    /// connections of devices still on the bus at the end. Messages that
+    /// fail to deliver are logged and skipped, as they were when recorded.
+    pub async fn replay(&self, events: &[RecordedEvent]) -> Result<HashMap<BusAddress, DeviceConnection>> {
+        let mut connections = HashMap::new();
+        for recorded in events {
+            match &recorded.event {
+                BusEvent::Connected { address } => {
+                    let connection = self.connect_device(address.clone()).await?;
+                    connections.insert(address.clone(), connection);
+                }
+                BusEvent::Disconnected { address } => {
+                    self.disconnect_device(address).await?;
+                    connections.remove(address);
+                }
+                BusEvent::StatusChanged { address, status } => {
+                    self.report_status(address, status.clone()).await;
+                }
+                BusEvent::Message { message } => {
+                    if let Err(e) = self.send_message(message.clone()).await {
+                        warn!("Replayed message {} not delivered: {}", recorded.sequence, e);
+                    }
+                }
+            }
+        }
+        Ok(connections)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tokio_test;
 
     #[tokio::test]
     async fn test_bus_creation() {
@@ -326,6 +400,12 @@ mod tests {
         
         bus.send_message(message.clone()).await.unwrap();
         
+        // device2 registering is announced first
+        match conn1.receiver.recv().await.unwrap() {
+            BusMessage::Control { command: ControlCommand::Register { address }, .. } => assert_eq!(address, addr2),
+            other => panic!("Expected registration, got {:?}", other),
+        }
+
         // Check if message was received
         let received = conn1.receiver.recv().await.unwrap();
         match received {
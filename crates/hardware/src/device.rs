@@ -7,7 +7,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::{Duration, SystemTime};
 use tokio::sync::mpsc;
-use tracing::{debug, info, warn};
+use tracing::{debug, info};
 use uuid::Uuid;
 
 /// Device capabilities that can be advertised
@@ -238,25 +238,17 @@ impl SystemDevice for BaseSystemDevice {
     async fn handle_message(&mut self, message: BusMessage) -> Result<Option<BusMessage>> {
         debug!("Device {} received message: {:?}", self.info.config.name, message);
 
-        match message {
-            BusMessage::Control { command, .. } => {
-                match command {
-                    crate::bus::ControlCommand::Ping { target } => {
-                        if target == self.info.address {
-                            let pong = BusMessage::Control {
-                                from: self.info.address.clone(),
-                                command: crate::bus::ControlCommand::Pong {
-                                    from: self.info.address.clone(),
-                                },
-                                message_id: Uuid::new_v4(),
-                            };
-                            return Ok(Some(pong));
-                        }
-                    }
-                    _ => {}
-                }
+        if let BusMessage::Control { command: crate::bus::ControlCommand::Ping { target }, .. } = message {
+            if target == self.info.address {
+                let pong = BusMessage::Control {
+                    from: self.info.address.clone(),
+                    command: crate::bus::ControlCommand::Pong {
+                        from: self.info.address.clone(),
+                    },
+                    message_id: Uuid::new_v4(),
+                };
+                return Ok(Some(pong));
             }
-            _ => {}
         }
 
         Ok(None)
@@ -540,8 +540,10 @@ mod tests {
     #[tokio::test]
     async fn test_device_cleanup() {
         let device_info = create_test_device_info("test_device");
-        let mut config = DiscoveryConfig::default();
-        config.device_timeout = Duration::from_millis(100);
+        let config = DiscoveryConfig {
+            device_timeout: Duration::from_millis(100),
+            ..Default::default()
+        };
         
         let protocol = DiscoveryProtocol::new(device_info, config);
 
@@ -8,6 +8,7 @@ use crate::{
     BusAddress, BusMessage, DeviceCapability, DeviceConfig, DeviceInfo, DeviceStatus,
     HardwareError, Result, SystemDevice,
 };
+use datalink_provider::Constellation;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader, ErrorKind};
@@ -27,6 +28,9 @@ pub struct LocationData {
     pub timestamp: Option<String>,
     pub fix_quality: Option<u8>,
     pub satellites: Option<u8>,
+    /// Satellite system named by the talker ID; `GNSS` for combined fixes
+    #[serde(default)]
+    pub constellation: Option<String>,
 }
 
 impl Default for LocationData {
@@ -39,6 +43,7 @@ impl Default for LocationData {
             timestamp: None,
             fix_quality: None,
             satellites: None,
+            constellation: None,
         }
     }
 }
@@ -61,13 +66,17 @@ impl GnssParser {
             return None;
         }
 
-        let sentence_type = parts[0];
+        // Any GNSS talker: `$GAGGA`, `$GLRMC`, `$BDGGA`, ...
+        let address = parts[0].strip_prefix('$').filter(|address| address.len() == 5 && address.is_ascii())?;
+        let (talker, formatter) = address.split_at(2);
+        let constellation = Constellation::from_talker(talker)?;
 
-        match sentence_type {
-            "$GPGGA" | "$GNGGA" => self.parse_gpgga(&parts),
-            "$GPRMC" | "$GNRMC" => self.parse_gprmc(&parts),
+        let location = match formatter {
+            "GGA" => self.parse_gpgga(&parts),
+            "RMC" => self.parse_gprmc(&parts),
             _ => None,
-        }
+        }?;
+        Some(LocationData { constellation: Some(constellation.name().to_string()), ..location })
     }
 
     fn parse_gpgga(&self, parts: &[&str]) -> Option<LocationData> {
@@ -507,6 +516,30 @@ mod tests {
         assert!((location.longitude.unwrap() - 11.5167).abs() < 0.001);
     }
 
+    #[test]
+    fn test_parse_sentences_from_every_gnss_talker() {
+        let parser = GnssParser::new();
+        let galileo = parser
+            .parse_sentence("$GAGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*55")
+            .unwrap();
+        assert_eq!(galileo.constellation.as_deref(), Some("Galileo"));
+        assert!((galileo.latitude.unwrap() - 48.1173).abs() < 0.001);
+
+        let beidou = parser
+            .parse_sentence("$BDRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*7B")
+            .unwrap();
+        assert_eq!(beidou.constellation.as_deref(), Some("BeiDou"));
+        assert_eq!(beidou.speed, Some(22.4));
+
+        let combined = parser
+            .parse_sentence("$GNGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*59")
+            .unwrap();
+        assert_eq!(combined.constellation.as_deref(), Some("GNSS"));
+
+        // Depth sounder talker, not a GNSS receiver
+        assert!(parser.parse_sentence("$SDGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47").is_none());
+    }
+
     #[test]
     fn test_gps_device_creation() {
         let config = GpsDeviceConfig::default();
@@ -525,6 +558,7 @@ mod tests {
             timestamp: Some("123519".to_string()),
             fix_quality: Some(1),
             satellites: Some(8),
+            constellation: Some("GPS".to_string()),
         };
 
         let serialized = serde_json::to_string(&location).unwrap();
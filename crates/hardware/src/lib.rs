@@ -9,12 +9,14 @@ pub mod bus;
 pub mod device;
 pub mod discovery_protocol;
 pub mod error;
+pub mod recorder;
 
 // Re-export main types
 pub use bus::{HardwareBus, BusMessage, BusAddress};
 pub use device::{SystemDevice, DeviceCapability, DeviceStatus, DeviceInfo, DeviceConfig};
 pub use discovery_protocol::{DiscoveryProtocol, DiscoveryMessage};
 pub use error::{HardwareError, Result};
+pub use recorder::{BusEvent, BusRecorder, RecordedEvent};
 
 /// Common traits and types used throughout the hardware abstraction layer
 pub mod prelude {
@@ -23,5 +25,6 @@ pub mod prelude {
         SystemDevice, DeviceCapability, DeviceStatus, DeviceInfo, DeviceConfig,
         DiscoveryProtocol, DiscoveryMessage,
         HardwareError, Result,
+        BusEvent, BusRecorder, RecordedEvent,
     };
 }
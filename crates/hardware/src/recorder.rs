@@ -0,0 +1,213 @@
+//! Bus Traffic Recorder Module
+//!
+//! Keeps the most recent hardware bus traffic in a ring log so intermittent
+//! device dropouts can be examined after the fact. Recordings are exported as
+//! JSON lines, one event per line, and can be replayed onto a fresh bus in
+//! tests to reproduce the sequence that led to a fault.
+
+use crate::{BusAddress, BusMessage, DeviceStatus, HardwareError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Events kept when no capacity is given
+pub const DEFAULT_RECORDER_CAPACITY: usize = 10_000;
+
+/// Something that happened on the bus
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BusEvent {
+    /// A device joined the bus
+    Connected { address: BusAddress },
+    /// A device left the bus
+    Disconnected { address: BusAddress },
+    /// A device reported a new status
+    StatusChanged { address: BusAddress, status: DeviceStatus },
+    /// A message was sent over the bus
+    Message { message: BusMessage },
+}
+
+/// A bus event with its place in the recording
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    /// Position in the recording, counting from zero; gaps mean events were
+    /// dropped from the ring
+    pub sequence: u64,
+    /// Milliseconds since the Unix epoch
+    pub timestamp_ms: u64,
+    pub event: BusEvent,
+}
+
+/// Ring log of bus events, dropping the oldest once full
+#[derive(Debug)]
+pub struct BusRecorder {
+    events: VecDeque<RecordedEvent>,
+    capacity: usize,
+    next_sequence: u64,
+}
+
+impl Default for BusRecorder {
+    fn default() -> Self {
+        Self::new(DEFAULT_RECORDER_CAPACITY)
+    }
+}
+
+impl BusRecorder {
+    /// Create a recorder keeping at most `capacity` events
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            events: VecDeque::new(),
+            capacity: capacity.max(1),
+            next_sequence: 0,
+        }
+    }
+
+    /// Append an event, dropping the oldest if the ring is full
+    pub fn record(&mut self, event: BusEvent) {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as u64);
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(RecordedEvent {
+            sequence: self.next_sequence,
+            timestamp_ms,
+            event,
+        });
+        self.next_sequence += 1;
+    }
+
+    /// Recorded events, oldest first
+    pub fn events(&self) -> impl Iterator<Item = &RecordedEvent> {
+        self.events.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Number of events dropped from the front of the ring
+    pub fn dropped(&self) -> u64 {
+        self.events.front().map_or(self.next_sequence, |first| first.sequence)
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// Write the recording as JSON lines
+    pub fn export<W: Write>(&self, mut writer: W) -> Result<()> {
+        for event in &self.events {
+            serde_json::to_writer(&mut writer, event)?;
+            writer
+                .write_all(b"\n")
+                .map_err(|e| HardwareError::generic(format!("Failed to write recording: {}", e)))?;
+        }
+        Ok(())
+    }
+
+    /// Write the recording to a JSON lines file
+    pub fn export_to_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let file = std::fs::File::create(path)
+            .map_err(|e| HardwareError::generic(format!("Failed to create {}: {}", path.display(), e)))?;
+        let mut writer = std::io::BufWriter::new(file);
+        self.export(&mut writer)?;
+        writer
+            .flush()
+            .map_err(|e| HardwareError::generic(format!("Failed to write {}: {}", path.display(), e)))
+    }
+}
+
+/// Read a recording written by [`BusRecorder::export`], skipping blank lines
+pub fn load_recording<R: BufRead>(reader: R) -> Result<Vec<RecordedEvent>> {
+    let mut events = Vec::new();
+    for line in reader.lines() {
+        let line = line.map_err(|e| HardwareError::generic(format!("Failed to read recording: {}", e)))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        events.push(serde_json::from_str(&line)?);
+    }
+    Ok(events)
+}
+
+/// Read a recording from a JSON lines file
+pub fn load_recording_file(path: impl AsRef<Path>) -> Result<Vec<RecordedEvent>> {
+    let path = path.as_ref();
+    let file = std::fs::File::open(path)
+        .map_err(|e| HardwareError::generic(format!("Failed to open {}: {}", path.display(), e)))?;
+    load_recording(std::io::BufReader::new(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HardwareBus;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_recording_export_and_replay() {
+        let bus = HardwareBus::with_recorder(BusRecorder::new(5));
+        let gps = BusAddress::new("gps");
+        let plotter = BusAddress::new("plotter");
+        let _gps_connection = bus.connect_device(gps.clone()).await.unwrap();
+        let _plotter_connection = bus.connect_device(plotter.clone()).await.unwrap();
+        bus.report_status(&gps, DeviceStatus::Online).await;
+        bus.report_status(&gps, DeviceStatus::Online).await;
+        bus.send_message(BusMessage::Data {
+            from: plotter.clone(),
+            to: gps.clone(),
+            payload: b"$PMTK220,1000*1F".to_vec(),
+            message_id: Uuid::new_v4(),
+        })
+        .await
+        .unwrap();
+        bus.report_status(&gps, DeviceStatus::Error { message: "no fix".to_string() }).await;
+        bus.disconnect_device(&gps).await.unwrap();
+
+        // Six events into a ring of five drops the first connection
+        let mut exported = Vec::new();
+        {
+            let recorder = bus.recorder().unwrap().read().await;
+            assert_eq!(recorder.len(), 5);
+            assert_eq!(recorder.dropped(), 1);
+            recorder.export(&mut exported).unwrap();
+        }
+        let events = load_recording(exported.as_slice()).unwrap();
+        assert_eq!(events.len(), 5);
+        assert_eq!(events[0].event, BusEvent::Connected { address: plotter.clone() });
+        assert_eq!(events[1].event, BusEvent::StatusChanged { address: gps.clone(), status: DeviceStatus::Online });
+        assert!(matches!(events[2].event, BusEvent::Message { .. }));
+        assert_eq!(events[4].event, BusEvent::Disconnected { address: gps.clone() });
+
+        // Replay the full sequence onto a fresh bus, including the dropped connect
+        let mut full = vec![RecordedEvent {
+            sequence: 0,
+            timestamp_ms: events[0].timestamp_ms,
+            event: BusEvent::Connected { address: gps.clone() },
+        }];
+        full.extend(events);
+        let replayed = HardwareBus::with_recorder(BusRecorder::default());
+        let mut connections = replayed.replay(&full).await.unwrap();
+        assert!(!replayed.is_device_connected(&gps).await);
+        assert!(replayed.is_device_connected(&plotter).await);
+        assert!(!connections.contains_key(&gps));
+        assert_eq!(replayed.recorder().unwrap().read().await.len(), full.len());
+
+        let plotter_connection = connections.get_mut(&plotter).unwrap();
+        let mut unregistered = false;
+        while let Ok(message) = plotter_connection.receiver.try_recv() {
+            if let BusMessage::Control { command: crate::bus::ControlCommand::Unregister { address }, .. } = message {
+                unregistered = address == gps;
+            }
+        }
+        assert!(unregistered, "the plotter saw the GPS drop off the bus");
+    }
+}
@@ -0,0 +1,228 @@
+//! Air-draft checks against charted bridge clearances
+//!
+//! Charted clearances are measured from a high-water datum (HAT or MHWS,
+//! depending on the chart) while tide heights are given above chart datum,
+//! so the clearance under a bridge grows by however far the tide sits below
+//! the clearance datum. Bridges within [`ROUTE_CORRIDOR_M`] of the remaining
+//! route (own ship, then the stored waypoints in order) count as upcoming.
+
+use bevy::prelude::{Res, Resource, Time};
+use datalink::geo::LocalTangentPlane;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use crate::alarms::escalation::Alarms;
+use crate::chart::store::{AnnotationKind, ChartAnnotations, ChartStore};
+
+/// Alarm raised while an upcoming bridge is too low
+pub const AIR_DRAFT_ALARM: &str = "air_draft";
+
+/// Distance either side of a route leg within which a bridge is on the route
+pub const ROUTE_CORRIDOR_M: f64 = 100.0;
+
+/// Vessel height and tide settings used for the clearance check
+#[derive(Debug, Clone, PartialEq)]
+pub struct AirDraftSettings {
+    /// Height of the highest point of the vessel above the waterline; 0 when not set
+    pub vessel_height_m: f32,
+    /// Extra clearance required above the vessel height
+    pub safety_margin_m: f32,
+    /// Current tide height above chart datum
+    pub tide_height_m: f32,
+    /// Height of the chart's clearance datum above chart datum
+    pub clearance_datum_m: f32,
+}
+
+impl Default for AirDraftSettings {
+    fn default() -> Self {
+        Self {
+            vessel_height_m: 0.0,
+            safety_margin_m: 1.0,
+            tide_height_m: 0.0,
+            clearance_datum_m: 0.0,
+        }
+    }
+}
+
+impl AirDraftSettings {
+    /// Whether a vessel height has been entered
+    pub fn is_configured(&self) -> bool {
+        self.vessel_height_m > 0.0
+    }
+
+    /// Clearance under a bridge charted at `charted_m` at the current tide
+    pub fn available_clearance(&self, charted_m: f64) -> f64 {
+        charted_m + (self.clearance_datum_m - self.tide_height_m) as f64
+    }
+
+    /// Clearance the vessel needs, margin included
+    pub fn required_clearance(&self) -> f64 {
+        (self.vessel_height_m + self.safety_margin_m) as f64
+    }
+}
+
+/// A bridge on the route ahead
+#[derive(Debug, Clone, PartialEq)]
+pub struct BridgeClearance {
+    pub annotation_id: u64,
+    pub label: String,
+    pub charted_m: f64,
+    /// Clearance at the current tide
+    pub available_m: f64,
+    /// Distance along the route from own ship
+    pub distance_m: f64,
+    pub sufficient: bool,
+}
+
+/// Distance from `point` to the leg `from`-`to`, and how far along the leg its closest point lies
+fn leg_offset(point: (f64, f64), from: (f64, f64), to: (f64, f64)) -> (f64, f64) {
+    let leg = (to.0 - from.0, to.1 - from.1);
+    let length = leg.0.hypot(leg.1);
+    let along = if length > 0.0 {
+        (((point.0 - from.0) * leg.0 + (point.1 - from.1) * leg.1) / length).clamp(0.0, length)
+    } else {
+        0.0
+    };
+    let closest = if length > 0.0 {
+        (from.0 + leg.0 * along / length, from.1 + leg.1 * along / length)
+    } else {
+        from
+    };
+    ((point.0 - closest.0).hypot(point.1 - closest.1), along)
+}
+
+/// Bridges on the route from `position` through the stored waypoints, nearest first
+pub fn upcoming_bridges(settings: &AirDraftSettings, store: &ChartStore, position: (f64, f64)) -> Vec<BridgeClearance> {
+    let plane = LocalTangentPlane::new(position);
+    let route: Vec<(f64, f64)> = std::iter::once(position)
+        .chain(store.waypoints().iter().map(|waypoint| (waypoint.latitude, waypoint.longitude)))
+        .map(|point| plane.to_local(point))
+        .collect();
+
+    let mut bridges: Vec<BridgeClearance> = store
+        .annotations()
+        .iter()
+        .filter_map(|annotation| {
+            let AnnotationKind::Bridge { clearance_m } = annotation.kind else {
+                return None;
+            };
+            let point = plane.to_local((annotation.latitude, annotation.longitude));
+            let mut travelled = 0.0;
+            let distance_m = route.windows(2).find_map(|leg| {
+                let (offset, along) = leg_offset(point, leg[0], leg[1]);
+                let distance = travelled + along;
+                travelled += (leg[1].0 - leg[0].0).hypot(leg[1].1 - leg[0].1);
+                (offset <= ROUTE_CORRIDOR_M).then_some(distance)
+            })?;
+            let available_m = settings.available_clearance(clearance_m);
+            Some(BridgeClearance {
+                annotation_id: annotation.id,
+                label: annotation.label.clone(),
+                charted_m: clearance_m,
+                available_m,
+                distance_m,
+                sufficient: available_m >= settings.required_clearance(),
+            })
+        })
+        .collect();
+    bridges.sort_by(|a, b| a.distance_m.total_cmp(&b.distance_m));
+    bridges
+}
+
+/// Settings, own-ship position and the bridges found by the last check
+#[derive(Debug, Default)]
+pub struct AirDraftState {
+    pub settings: AirDraftSettings,
+    position: Option<(f64, f64)>,
+    upcoming: Vec<BridgeClearance>,
+}
+
+impl AirDraftState {
+    pub fn set_position(&mut self, latitude: f64, longitude: f64) {
+        self.position = Some((latitude, longitude));
+    }
+
+    pub fn upcoming(&self) -> &[BridgeClearance] {
+        &self.upcoming
+    }
+
+    /// Re-run the check against `store`; nothing is checked until both the
+    /// vessel height and a position are known
+    pub fn refresh(&mut self, store: &ChartStore) {
+        self.upcoming = match self.position {
+            Some(position) if self.settings.is_configured() => upcoming_bridges(&self.settings, store, position),
+            _ => Vec::new(),
+        };
+    }
+
+    /// Whether any upcoming bridge is too low
+    pub fn is_blocked(&self) -> bool {
+        self.upcoming.iter().any(|bridge| !bridge.sufficient)
+    }
+}
+
+/// Shared handle to the air-draft state, edited from the calibration page
+#[derive(Resource, Clone, Default)]
+pub struct AirDraft(Arc<RwLock<AirDraftState>>);
+
+impl AirDraft {
+    pub fn read(&self) -> RwLockReadGuard<'_, AirDraftState> {
+        self.0.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, AirDraftState> {
+        self.0.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Check the route for low bridges and raise the air-draft alarm
+pub fn update_air_draft(air_draft: Res<AirDraft>, chart: Res<ChartAnnotations>, alarms: Res<Alarms>, time: Res<Time>) {
+    let mut state = air_draft.write();
+    state.refresh(&chart.read());
+    alarms.write().set_condition(AIR_DRAFT_ALARM, state.is_blocked(), time.elapsed_secs_f64());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chart::store::Annotation;
+
+    #[test]
+    fn test_bridges_on_route_checked_against_tide() {
+        let mut store = ChartStore::in_memory();
+        store.add_waypoint("Lock", 52.01, 4.0);
+        store.add_waypoint("Marina", 52.01, 4.02);
+        // On the second leg, ~680 m past the lock
+        let low = store.upsert_annotation(Annotation::new(52.0101, 4.01, "Rail bridge", AnnotationKind::Bridge { clearance_m: 14.0 }));
+        // On the first leg
+        store.upsert_annotation(Annotation::new(52.005, 4.0, "Road bridge", AnnotationKind::Bridge { clearance_m: 20.0 }));
+        // Well off the route
+        store.upsert_annotation(Annotation::new(52.05, 4.05, "Canal bridge", AnnotationKind::Bridge { clearance_m: 2.0 }));
+
+        let settings = AirDraftSettings {
+            vessel_height_m: 15.0,
+            safety_margin_m: 1.0,
+            tide_height_m: 1.5,
+            clearance_datum_m: 2.0,
+        };
+        let bridges = upcoming_bridges(&settings, &store, (52.0, 4.0));
+        assert_eq!(bridges.len(), 2);
+        assert_eq!(bridges[0].label, "Road bridge");
+        assert!(bridges[0].sufficient);
+        assert_eq!(bridges[1].annotation_id, low.id);
+        assert_eq!(bridges[1].available_m, 14.5);
+        assert!(!bridges[1].sufficient);
+        assert_eq!((bridges[1].distance_m / 100.0).round(), 18.0);
+
+        // At low water the rail bridge clears
+        let mut state = AirDraftState { settings, ..Default::default() };
+        state.settings.tide_height_m = -0.5;
+        state.refresh(&store);
+        assert!(!state.is_blocked(), "no position yet");
+        state.set_position(52.0, 4.0);
+        state.refresh(&store);
+        assert_eq!(state.upcoming().len(), 2);
+        assert!(!state.is_blocked());
+        state.settings.tide_height_m = 2.0;
+        state.refresh(&store);
+        assert!(state.is_blocked());
+    }
+}
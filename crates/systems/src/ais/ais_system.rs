@@ -1,21 +1,50 @@
+use bevy::log::info;
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::log::warn;
 use bevy::prelude::Time;
 use components::VesselData;
 use crate::{SystemInteraction, SystemStatus, VesselSystem};
 use datalink::DataMessage;
 #[cfg(not(target_arch = "wasm32"))]
-use datalink::{DataLink, DataLinkConfig, DataLinkReceiver};
+use datalink::{DataLinkConfig, DataLinkReceiver, Value};
 #[cfg(not(target_arch = "wasm32"))]
-use datalink_provider::AisDataLinkProvider;
+use datalink_provider::{AisDataLinkProvider, AisFragmentAssembler, AisReport};
 use std::collections::HashMap;
+use super::static_cache::AisStaticCache;
+use crate::collision::cpa::CollisionWatch;
+#[cfg(not(target_arch = "wasm32"))]
+use super::static_cache::AisStaticData;
+
+/// Environment variable holding the own-ship MMSI, shared with the AIS server
+pub const OWN_MMSI_ENV: &str = "YACHTPIT_OWN_MMSI";
+
+/// A nine-digit MMSI
+pub fn parse_mmsi(value: &str) -> Option<u32> {
+    let value = value.trim();
+    (value.len() == 9 && value.bytes().all(|byte| byte.is_ascii_digit()))
+        .then(|| value.parse().ok())
+        .flatten()
+}
 
 /// AIS (Automatic Identification System) implementation
 pub struct AisSystem {
     status: SystemStatus,
-    own_mmsi: u32,
+    /// Reports from this MMSI are our own transponder echoing back
+    own_mmsi: Option<u32>,
+    /// Latest position report from our own transponder
+    own_echo: Option<DataMessage>,
     receiving: bool,
     #[cfg(not(target_arch = "wasm32"))]
     datalink: AisDataLinkProvider,
+    #[cfg(not(target_arch = "wasm32"))]
+    assembler: AisFragmentAssembler,
     vessel_data: HashMap<String, DataMessage>,
+    static_cache: AisStaticCache,
+    /// Targets are passed on for CPA/TCPA
+    collision: CollisionWatch,
+    /// Elapsed app time of the latest update, in seconds
+    #[cfg(not(target_arch = "wasm32"))]
+    clock_s: f64,
 }
 
 impl AisSystem {
@@ -29,7 +58,7 @@ impl AisSystem {
             let config = DataLinkConfig::new("ais".to_string())
                 .with_parameter("connection_type".to_string(), "serial".to_string())
                 .with_parameter("port".to_string(), "/dev/ttyUSB0".to_string())
-                .with_parameter("baud_rate".to_string(), "38400".to_string());
+                .with_parameter("baud_rate".to_string(), "auto".to_string());
 
             // Try to connect to the AIS datalink
             // If it fails, the system will still work but won't receive real AIS data
@@ -42,15 +71,127 @@ impl AisSystem {
 
         Self {
             status: SystemStatus::Active,
-            own_mmsi: 123456789,
+            own_mmsi: Self::own_mmsi_from_env(),
+            own_echo: None,
             receiving: true,
             #[cfg(not(target_arch = "wasm32"))]
             datalink,
+            #[cfg(not(target_arch = "wasm32"))]
+            assembler: AisFragmentAssembler::new(),
             vessel_data: HashMap::new(),
+            static_cache: AisStaticCache::in_memory(),
+            collision: CollisionWatch::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            clock_s: 0.0,
+        }
+    }
+
+    fn own_mmsi_from_env() -> Option<u32> {
+        std::env::var(OWN_MMSI_ENV).ok().as_deref().and_then(parse_mmsi)
+    }
+
+    pub fn own_mmsi(&self) -> Option<u32> {
+        self.own_mmsi
+    }
+
+    /// Set the own-ship MMSI, moving any echo already in the target list out of it
+    pub fn set_own_mmsi(&mut self, mmsi: Option<u32>) {
+        self.own_mmsi = mmsi;
+        self.own_echo = mmsi.and_then(|mmsi| self.vessel_data.remove(&mmsi.to_string()));
+    }
+
+    /// Latest position report from our own transponder
+    pub fn own_echo(&self) -> Option<&DataMessage> {
+        self.own_echo.as_ref()
+    }
+
+    /// Other vessels' latest position reports, keyed by MMSI
+    pub fn targets(&self) -> &HashMap<String, DataMessage> {
+        &self.vessel_data
+    }
+
+    /// Use `cache` for static data; a new system keeps it in memory only
+    pub fn with_static_cache(mut self, cache: AisStaticCache) -> Self {
+        self.static_cache = cache;
+        self
+    }
+
+    pub fn static_cache(&self) -> &AisStaticCache {
+        &self.static_cache
+    }
+
+    /// Feed other vessels' positions to `watch` for collision assessment
+    pub fn with_collision_watch(mut self, watch: CollisionWatch) -> Self {
+        self.collision = watch;
+        self
+    }
+
+    pub fn collision_watch(&self) -> &CollisionWatch {
+        &self.collision
+    }
+
+    /// Apply a decoded AIS report to the target list and static cache
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn apply_report(&mut self, report: AisReport, source: &DataMessage) {
+        // VDO sentences are tagged own ship by the provider, whatever the MMSI setting
+        let own_ship = |mmsi| Some(mmsi) == self.own_mmsi || source.get_bool("own_ship") == Some(true);
+        match report {
+            AisReport::Position { mmsi, latitude, longitude, speed_kts, course_deg, .. } => {
+                let mut position = DataMessage::new(
+                    "AIS_POSITION".to_string(),
+                    mmsi.to_string(),
+                    source.payload.clone(),
+                )
+                .with_received_at(source.received_at)
+                .with_data("mmsi", mmsi);
+                position.signal_quality = source.signal_quality;
+
+                let fields = [
+                    ("latitude", latitude.map(Value::Float)),
+                    ("longitude", longitude.map(Value::Float)),
+                    ("speed", speed_kts.map(Value::from)),
+                    ("course", course_deg.map(|course| Value::Angle(course.into()))),
+                ];
+                for (key, value) in fields {
+                    if let Some(value) = value {
+                        position = position.with_data(key, value);
+                    }
+                }
+                if let (Some(lat), Some(lon)) = (latitude, longitude) {
+                    position = position.with_data("position", Value::LatLon { lat, lon });
+                }
+
+                if own_ship(mmsi) {
+                    if self.own_echo.is_none() {
+                        info!("Receiving own ship AIS echo (MMSI {})", mmsi);
+                    }
+                    position.message_type = "AIS_OWN_POSITION".to_string();
+                    self.own_echo = Some(position);
+                } else {
+                    self.collision.write().observe(&position, self.clock_s);
+                    self.vessel_data.insert(mmsi.to_string(), position);
+                }
+            }
+            AisReport::Static { mmsi, .. } if own_ship(mmsi) => {}
+            AisReport::Static { mmsi, name, callsign, ship_type, dimensions } => {
+                self.static_cache.update(mmsi, AisStaticData {
+                    name,
+                    callsign,
+                    ship_type,
+                    length_m: dimensions.map(|dimensions| dimensions.length()),
+                    beam_m: dimensions.map(|dimensions| dimensions.beam()),
+                });
+            }
         }
     }
 }
 
+impl Default for AisSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl VesselSystem for AisSystem {
     fn id(&self) -> &'static str {
         "ais"
@@ -61,34 +202,27 @@ impl VesselSystem for AisSystem {
     }
 
     fn update(&mut self, _yacht_data: &VesselData, _time: &Time) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.clock_s = _time.elapsed_secs_f64();
+        }
+
         // Receive new AIS messages from the datalink
         #[cfg(not(target_arch = "wasm32"))]
         if self.receiving && self.datalink.is_connected() {
             if let Ok(messages) = self.datalink.receive_all_messages() {
                 for message in messages {
                     if message.message_type == "AIS_SENTENCE" {
-                        // Process AIS sentence and extract vessel information
-                        // For now, we'll create a mock vessel entry based on the sentence
-                        // In a real implementation, you would decode the AIS payload
-                        let mmsi = format!("AIS_{}", message.source_id);
-
-                        // Create a processed message with basic vessel data
-                        let mut processed_message = message.clone();
-                        processed_message.message_type = "AIS_POSITION".to_string();
-
-                        // Add mock vessel data (in real implementation, decode from payload)
-                        processed_message = processed_message
-                            .with_data("mmsi".to_string(), mmsi.clone())
-                            .with_data("vessel_name".to_string(), format!("VESSEL_{}", message.source_id))
-                            .with_data("latitude".to_string(), "37.7749".to_string())
-                            .with_data("longitude".to_string(), "-122.4194".to_string())
-                            .with_data("speed".to_string(), "0.0".to_string())
-                            .with_data("course".to_string(), "0".to_string());
-
-                        self.vessel_data.insert(mmsi, processed_message);
+                        if let Some(report) = self.assembler.push(&message) {
+                            self.apply_report(report, &message);
+                        }
                     }
                 }
             }
+
+            if let Err(e) = self.static_cache.save_if_due(self.clock_s) {
+                warn!("Failed to save AIS static cache: {}", e);
+            }
         }
     }
 
@@ -104,15 +238,24 @@ impl VesselSystem for AisSystem {
             }
         };
 
+        let own_echo = match &self.own_echo {
+            Some(echo) => match (echo.get_f64("latitude"), echo.get_f64("longitude")) {
+                (Some(lat), Some(lon)) => format!("{:.5}, {:.5}", lat, lon),
+                _ => "NO POSITION".to_string(),
+            },
+            None => "NONE".to_string(),
+        };
         let mut display = format!(
             "AIS - AUTOMATIC IDENTIFICATION SYSTEM\n\n\
             Status: {}\n\
             Own Ship MMSI: {}\n\
+            Own Ship Echo: {}\n\
             Datalink: {}\n\
             \n\
             NEARBY VESSELS:\n",
             if self.receiving { "RECEIVING" } else { "STANDBY" },
-            self.own_mmsi,
+            self.own_mmsi.map_or_else(|| "NOT SET".to_string(), |mmsi| mmsi.to_string()),
+            own_echo,
             datalink_status
         );
 
@@ -120,14 +263,30 @@ impl VesselSystem for AisSystem {
             display.push_str("\nNo vessels detected");
         } else {
             for (mmsi, message) in &self.vessel_data {
-                let vessel_name = message.get_data("vessel_name").unwrap_or(mmsi);
-                let speed = message.get_data("speed").map(|s| s.as_str()).unwrap_or("N/A");
-                let course = message.get_data("course").map(|s| s.as_str()).unwrap_or("N/A");
-                let lat = message.get_data("latitude").map(|s| s.as_str()).unwrap_or("N/A");
-                let lon = message.get_data("longitude").map(|s| s.as_str()).unwrap_or("N/A");
-
-                // Determine vessel icon based on name
-                let icon = if vessel_name.contains("M/Y") || vessel_name.contains("YACHT") {
+                let static_data = mmsi.parse::<u32>().ok().and_then(|mmsi| self.static_cache.get(mmsi));
+                let vessel_name = match mmsi.parse::<u32>() {
+                    Ok(mmsi) => self.static_cache.display_name(mmsi),
+                    Err(_) => mmsi.clone(),
+                };
+                let field = |key: &str, precision: usize| {
+                    message
+                        .get_f64(key)
+                        .map_or_else(|| "N/A".to_string(), |value| format!("{:.*}", precision, value))
+                };
+                let speed = field("speed", 1);
+                let course = field("course", 0);
+                let lat = field("latitude", 5);
+                let lon = field("longitude", 5);
+
+                // Determine vessel icon from the reported ship type, else the name
+                let icon = if let Some(ship_type) = static_data.and_then(|data| data.ship_type) {
+                    match ship_type {
+                        36 => "⛵",
+                        37 => "🛥️",
+                        70..=89 => "🚢",
+                        _ => "🚤",
+                    }
+                } else if vessel_name.contains("M/Y") || vessel_name.contains("YACHT") {
                     "🛥️"
                 } else if vessel_name.contains("CARGO") || vessel_name.contains("SHIP") {
                     "🚢"
@@ -140,7 +299,7 @@ impl VesselSystem for AisSystem {
                 display.push_str(&format!(
                     "\n{} {}\n\
                     MMSI: {}\n\
-                    Position: {}°N, {}°W\n\
+                    Position: {}, {}\n\
                     Speed: {} kts\n\
                     Course: {}°\n",
                     icon, vessel_name, mmsi, lat, lon, speed, course
@@ -165,14 +324,19 @@ impl VesselSystem for AisSystem {
             }
             SystemInteraction::Configure(key, value) => {
                 match key.as_str() {
-                    "mmsi" => {
-                        if let Ok(mmsi) = value.parse::<u32>() {
-                            self.own_mmsi = mmsi;
+                    "mmsi" => match value.as_str() {
+                        "" | "clear" => {
+                            self.set_own_mmsi(None);
                             true
-                        } else {
-                            false
                         }
-                    }
+                        value => match parse_mmsi(value) {
+                            Some(mmsi) => {
+                                self.set_own_mmsi(Some(mmsi));
+                                true
+                            }
+                            None => false,
+                        },
+                    },
                     _ => false,
                 }
             }
@@ -186,7 +350,7 @@ impl VesselSystem for AisSystem {
                 true
             }
             SystemInteraction::Reset => {
-                self.own_mmsi = 123456789;
+                self.set_own_mmsi(Self::own_mmsi_from_env());
                 self.receiving = true;
                 self.status = SystemStatus::Active;
                 true
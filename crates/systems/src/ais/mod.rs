@@ -1 +1,2 @@
-pub mod ais_system;
\ No newline at end of file
+pub mod ais_system;
+pub mod static_cache;
@@ -0,0 +1,184 @@
+use bevy::log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// File name of the persisted cache inside the data directory
+const CACHE_FILE_NAME: &str = "ais_static_cache.json";
+
+/// Least time between writes while new static data keeps arriving, in seconds
+pub const SAVE_INTERVAL_S: f64 = 30.0;
+
+/// Static (voyage-independent) data reported by an AIS target
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AisStaticData {
+    pub name: Option<String>,
+    pub callsign: Option<String>,
+    pub ship_type: Option<u8>,
+    pub length_m: Option<u16>,
+    pub beam_m: Option<u16>,
+}
+
+impl AisStaticData {
+    /// Overlay the fields present in `update`; returns whether anything changed
+    pub fn merge(&mut self, update: AisStaticData) -> bool {
+        let before = self.clone();
+        self.name = update.name.or(self.name.take());
+        self.callsign = update.callsign.or(self.callsign.take());
+        self.ship_type = update.ship_type.or(self.ship_type);
+        self.length_m = update.length_m.or(self.length_m);
+        self.beam_m = update.beam_m.or(self.beam_m);
+        *self != before
+    }
+}
+
+/// AIS static data keyed by MMSI, persisted between trips so targets that
+/// have only sent a position report so far can still be shown by name
+#[derive(Debug, Default)]
+pub struct AisStaticCache {
+    entries: HashMap<u32, AisStaticData>,
+    path: Option<PathBuf>,
+    dirty: bool,
+    /// App time of the last write, in seconds
+    saved_at_s: Option<f64>,
+}
+
+impl AisStaticCache {
+    /// A cache that is never written to disk
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Load the cache from `path`, starting empty if the file is missing or unreadable
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable AIS static cache {}: {}", path.display(), e);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+
+        Self {
+            entries,
+            path: Some(path),
+            ..Default::default()
+        }
+    }
+
+    /// The cache at the default path, or an in-memory one where there is no data directory
+    pub fn load_default() -> Self {
+        match Self::default_path() {
+            Some(path) => Self::load(path),
+            None => Self::in_memory(),
+        }
+    }
+
+    /// `$YACHTPIT_DATA_DIR/ais_static_cache.json`, falling back to `~/.yachtpit`
+    pub fn default_path() -> Option<PathBuf> {
+        Some(crate::storage::data_dir()?.join(CACHE_FILE_NAME))
+    }
+
+    /// Merge newly received static data for `mmsi`
+    pub fn update(&mut self, mmsi: u32, data: AisStaticData) -> bool {
+        let changed = self.entries.entry(mmsi).or_default().merge(data);
+        self.dirty |= changed;
+        changed
+    }
+
+    pub fn get(&self, mmsi: u32) -> Option<&AisStaticData> {
+        self.entries.get(&mmsi)
+    }
+
+    /// Vessel name if known, otherwise "MMSI <number>"
+    pub fn display_name(&self, mmsi: u32) -> String {
+        self.get(mmsi)
+            .and_then(|data| data.name.clone())
+            .unwrap_or_else(|| format!("MMSI {}", mmsi))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Write the cache if it changed and the last write was at least
+    /// [`SAVE_INTERVAL_S`] before `now_s`, so a busy channel does not
+    /// rewrite the file on every update
+    pub fn save_if_due(&mut self, now_s: f64) -> std::io::Result<()> {
+        if !self.dirty || self.saved_at_s.is_some_and(|saved| now_s - saved < SAVE_INTERVAL_S) {
+            return Ok(());
+        }
+        self.saved_at_s = Some(now_s);
+        self.save()
+    }
+
+    /// Write the cache if it changed since the last save
+    pub fn save(&mut self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            self.dirty = false;
+            return Ok(());
+        };
+        if !self.dirty {
+            return Ok(());
+        }
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let contents = serde_json::to_string_pretty(&self.entries)?;
+        // Write then rename so a crash never leaves a truncated cache
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, contents)?;
+        std::fs::rename(&temp_path, path)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_static_cache_persists_between_sessions() {
+        let path = std::env::temp_dir()
+            .join(format!("yachtpit-ais-cache-{}", std::process::id()))
+            .join(CACHE_FILE_NAME);
+
+        let mut cache = AisStaticCache::load(&path);
+        assert_eq!(cache.display_name(351759000), "MMSI 351759000");
+
+        // Type 24 part A then part B each carry half of the static data
+        assert!(cache.update(351759000, AisStaticData { name: Some("EVER DIADEM".to_string()), ..Default::default() }));
+        assert!(cache.update(351759000, AisStaticData { callsign: Some("3FOF8".to_string()), length_m: Some(295), ..Default::default() }));
+        assert!(!cache.update(351759000, AisStaticData { callsign: Some("3FOF8".to_string()), ..Default::default() }));
+        cache.save().unwrap();
+        assert!(!cache.is_dirty());
+
+        let reloaded = AisStaticCache::load(&path);
+        assert_eq!(reloaded.display_name(351759000), "EVER DIADEM");
+        assert_eq!(reloaded.get(351759000).unwrap().length_m, Some(295));
+
+        // Further updates are written at most once per interval
+        cache.update(351759000, AisStaticData { beam_m: Some(48), ..Default::default() });
+        cache.save_if_due(100.0).unwrap();
+        assert!(!cache.is_dirty());
+        cache.update(351759000, AisStaticData { ship_type: Some(70), ..Default::default() });
+        cache.save_if_due(100.0 + SAVE_INTERVAL_S / 2.0).unwrap();
+        assert!(cache.is_dirty());
+        cache.save_if_due(100.0 + SAVE_INTERVAL_S).unwrap();
+        assert!(!cache.is_dirty());
+        assert_eq!(AisStaticCache::load(&path).get(351759000).unwrap().ship_type, Some(70));
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,146 @@
+use bevy::prelude::Time;
+use components::VesselData;
+use super::escalation::{Alarms, EscalationStage};
+use crate::{SystemInteraction, SystemStatus, VesselSystem};
+
+/// Escalation delay adjustment per `delay_step`, in seconds
+const DELAY_STEP_S: f32 = 5.0;
+
+/// Alarm settings page: escalation stages, delay and acknowledgement per alarm
+pub struct AlarmsSystem {
+    status: SystemStatus,
+    alarms: Alarms,
+    selected: usize,
+}
+
+impl AlarmsSystem {
+    /// Create the alarms page for the shared alarm table
+    pub fn new(alarms: Alarms) -> Self {
+        Self {
+            status: SystemStatus::Active,
+            alarms,
+            selected: 0,
+        }
+    }
+
+    fn configure(&mut self, key: &str, value: &str) -> bool {
+        let mut table = self.alarms.write();
+        let count = table.alarms().len();
+        if key == "select" {
+            if count == 0 {
+                return false;
+            }
+            self.selected = match value {
+                "next" => (self.selected + 1) % count,
+                "previous" => (self.selected + count - 1) % count,
+                _ => return false,
+            };
+            return true;
+        }
+        if key == "acknowledge" && value == "all" {
+            return table.acknowledge_all();
+        }
+
+        let Some(id) = table.alarms().get(self.selected).map(|alarm| alarm.id.clone()) else {
+            return false;
+        };
+        if key == "acknowledge" {
+            return table.acknowledge(&id);
+        }
+        let Some(policy) = table.policy_mut(&id) else {
+            return false;
+        };
+        match key {
+            "toggle_stage" => match EscalationStage::parse(value) {
+                Some(stage) => {
+                    policy.toggle_stage(stage);
+                    true
+                }
+                None => false,
+            },
+            "delay" => match value.parse::<f32>() {
+                Ok(delay) => {
+                    policy.set_delay(delay);
+                    true
+                }
+                Err(_) => false,
+            },
+            "delay_step" => {
+                let step = if value == "up" { DELAY_STEP_S } else { -DELAY_STEP_S };
+                let delay = policy.delay_s + step;
+                policy.set_delay(delay);
+                true
+            }
+            "requires_ack" => {
+                policy.requires_ack = match value {
+                    "toggle" => !policy.requires_ack,
+                    "on" => true,
+                    "off" => false,
+                    _ => return false,
+                };
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Index of the alarm being edited
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+}
+
+impl VesselSystem for AlarmsSystem {
+    fn id(&self) -> &'static str {
+        "alarms"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Alarms"
+    }
+
+    fn update(&mut self, _yacht_data: &VesselData, _time: &Time) {}
+
+    fn render_display(&self, _yacht_data: &VesselData) -> String {
+        let table = self.alarms.read();
+
+        let mut display = String::from("ALARMS\n\n");
+        for (index, alarm) in table.alarms().iter().enumerate() {
+            let stages: Vec<&str> = alarm.policy.stages.iter().map(EscalationStage::label).collect();
+            display.push_str(&format!(
+                "{} {:<14} {:<8} {}\n",
+                if index == self.selected { "▶" } else { " " },
+                alarm.label,
+                alarm.state_label(),
+                alarm.stage().map_or("", |stage| stage.label())
+            ));
+            display.push_str(&format!(
+                "    Escalation: {} every {:.0} s, {}\n",
+                if stages.is_empty() { "NONE".to_string() } else { stages.join(" > ") },
+                alarm.policy.delay_s,
+                if alarm.policy.requires_ack { "ACK REQUIRED" } else { "CLEARS ITSELF" }
+            ));
+        }
+        display.push_str(
+            "\n[Up/Down] Select  [1-4] Visual/Audible/Relay/Shore  [ [ / ] ] Delay\n\
+            [K] Ack Required  [Enter] Acknowledge  [Shift+Enter] Acknowledge All",
+        );
+        display
+    }
+
+    fn handle_interaction(&mut self, interaction: SystemInteraction) -> bool {
+        match interaction {
+            SystemInteraction::Select => {
+                self.status = SystemStatus::Active;
+                true
+            }
+            SystemInteraction::Configure(key, value) => self.configure(&key, &value),
+            SystemInteraction::Reset => self.alarms.write().acknowledge_all(),
+            SystemInteraction::Toggle => false,
+        }
+    }
+
+    fn status(&self) -> SystemStatus {
+        self.status.clone()
+    }
+}
@@ -0,0 +1,416 @@
+//! Alarm escalation
+//!
+//! While an alarm stays unacknowledged it climbs the stages of its policy,
+//! one stage every `delay_s`: shown on the dashboard, then the buzzer, then
+//! the external relay, then a position report to shore. Acknowledging stops
+//! the climb and releases the outputs.
+
+use bevy::prelude::*;
+use components::{DepthTransducers, VesselData, AlarmIndicator, TEXT_COLOR_DANGER, TEXT_COLOR_PRIMARY, TEXT_COLOR_WARNING};
+use std::collections::BTreeSet;
+use crate::air_draft::clearance::AIR_DRAFT_ALARM;
+use crate::collision::cpa::COLLISION_ALARM;
+use crate::gps::integrity::GNSS_INTEGRITY_ALARM;
+use crate::watch::schedule::WATCH_HANDOVER_ALARM;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Id of the shallow water alarm, raised from the depth settings
+pub const SHALLOW_WATER_ALARM: &str = "shallow_water";
+
+/// Longest delay between escalation stages, in seconds
+pub const MAX_ESCALATION_DELAY_S: f32 = 600.0;
+
+/// Ways of getting the crew's attention, in escalation order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EscalationStage {
+    Visual,
+    Audible,
+    /// External GPIO or relay output, e.g. a cockpit strobe or horn
+    Relay,
+    ShoreReport,
+}
+
+impl EscalationStage {
+    pub const ALL: [EscalationStage; 4] = [
+        EscalationStage::Visual,
+        EscalationStage::Audible,
+        EscalationStage::Relay,
+        EscalationStage::ShoreReport,
+    ];
+
+    /// Name used in configuration
+    pub fn name(&self) -> &'static str {
+        match self {
+            EscalationStage::Visual => "visual",
+            EscalationStage::Audible => "audible",
+            EscalationStage::Relay => "relay",
+            EscalationStage::ShoreReport => "shore",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|stage| stage.name() == name)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EscalationStage::Visual => "VISUAL",
+            EscalationStage::Audible => "AUDIBLE",
+            EscalationStage::Relay => "RELAY",
+            EscalationStage::ShoreReport => "SHORE",
+        }
+    }
+}
+
+/// How one alarm escalates
+#[derive(Debug, Clone, PartialEq)]
+pub struct EscalationPolicy {
+    /// Enabled stages, in escalation order
+    pub stages: Vec<EscalationStage>,
+    /// Seconds spent in each stage before moving to the next
+    pub delay_s: f32,
+    /// Whether the alarm stays raised after its condition clears until acknowledged
+    pub requires_ack: bool,
+}
+
+impl Default for EscalationPolicy {
+    fn default() -> Self {
+        Self {
+            stages: vec![EscalationStage::Visual, EscalationStage::Audible],
+            delay_s: 30.0,
+            requires_ack: true,
+        }
+    }
+}
+
+impl EscalationPolicy {
+    /// Stage reached `elapsed_s` seconds after the alarm was raised
+    pub fn stage_after(&self, elapsed_s: f64) -> Option<EscalationStage> {
+        let last = self.stages.len().checked_sub(1)?;
+        let index = if self.delay_s > 0.0 {
+            ((elapsed_s.max(0.0) / self.delay_s as f64) as usize).min(last)
+        } else {
+            last
+        };
+        Some(self.stages[index])
+    }
+
+    /// Enable or disable a stage, keeping escalation order
+    pub fn toggle_stage(&mut self, stage: EscalationStage) {
+        match self.stages.iter().position(|enabled| *enabled == stage) {
+            Some(index) => {
+                self.stages.remove(index);
+            }
+            None => {
+                self.stages.push(stage);
+                self.stages.sort();
+            }
+        }
+    }
+
+    pub fn set_delay(&mut self, delay_s: f32) {
+        self.delay_s = delay_s.clamp(0.0, MAX_ESCALATION_DELAY_S);
+    }
+}
+
+/// An alarm with its escalation policy and current state
+#[derive(Debug, Clone)]
+pub struct Alarm {
+    pub id: String,
+    pub label: String,
+    pub policy: EscalationPolicy,
+    /// Whether the alarm condition currently holds
+    condition: bool,
+    /// Elapsed seconds when the alarm was raised
+    raised_at: Option<f64>,
+    acknowledged: bool,
+    /// Highest stage reached since being raised
+    stage: Option<EscalationStage>,
+}
+
+impl Alarm {
+    fn new(id: &str, label: &str, policy: EscalationPolicy) -> Self {
+        Self {
+            id: id.to_string(),
+            label: label.to_string(),
+            policy,
+            condition: false,
+            raised_at: None,
+            acknowledged: false,
+            stage: None,
+        }
+    }
+
+    pub fn is_raised(&self) -> bool {
+        self.raised_at.is_some()
+    }
+
+    pub fn is_acknowledged(&self) -> bool {
+        self.acknowledged
+    }
+
+    /// Stage currently driving the outputs; `None` once acknowledged
+    pub fn stage(&self) -> Option<EscalationStage> {
+        if self.acknowledged {
+            None
+        } else {
+            self.stage
+        }
+    }
+
+    pub fn state_label(&self) -> &'static str {
+        match (self.is_raised(), self.acknowledged, self.condition) {
+            (false, _, _) => "OK",
+            (true, true, _) => "ACK",
+            (true, false, true) => "ACTIVE",
+            // Cleared but waiting for acknowledgement
+            (true, false, false) => "LATCHED",
+        }
+    }
+
+    fn clear(&mut self) {
+        self.raised_at = None;
+        self.acknowledged = false;
+        self.stage = None;
+    }
+}
+
+/// An alarm reaching a new escalation stage
+#[derive(Debug, Clone, PartialEq)]
+pub struct Escalation {
+    pub alarm_id: String,
+    pub label: String,
+    /// 1-based alarm number, as used in NMEA ALR sentences
+    pub number: usize,
+    pub stage: EscalationStage,
+}
+
+/// Every alarm and the escalations not yet handed to the outputs
+#[derive(Debug)]
+pub struct AlarmTable {
+    alarms: Vec<Alarm>,
+    escalations: Vec<Escalation>,
+}
+
+impl Default for AlarmTable {
+    fn default() -> Self {
+        let mut table = Self {
+            alarms: Vec::new(),
+            escalations: Vec::new(),
+        };
+        table.register(SHALLOW_WATER_ALARM, "SHALLOW WATER", EscalationPolicy::default());
+        table.register(AIR_DRAFT_ALARM, "AIR DRAFT", EscalationPolicy::default());
+        table.register(GNSS_INTEGRITY_ALARM, "GNSS INTEGRITY", EscalationPolicy::default());
+        table.register(COLLISION_ALARM, "COLLISION", EscalationPolicy::default());
+        // A reminder, not an emergency: shown only, and gone once the watch changes
+        table.register(
+            WATCH_HANDOVER_ALARM,
+            "WATCH HANDOVER",
+            EscalationPolicy {
+                stages: vec![EscalationStage::Visual],
+                requires_ack: false,
+                ..Default::default()
+            },
+        );
+        table
+    }
+}
+
+impl AlarmTable {
+    /// Add an alarm, replacing the policy of an existing one with the same id
+    pub fn register(&mut self, id: &str, label: &str, policy: EscalationPolicy) {
+        match self.alarms.iter_mut().find(|alarm| alarm.id == id) {
+            Some(alarm) => alarm.policy = policy,
+            None => self.alarms.push(Alarm::new(id, label, policy)),
+        }
+    }
+
+    pub fn alarms(&self) -> &[Alarm] {
+        &self.alarms
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Alarm> {
+        self.alarms.iter().find(|alarm| alarm.id == id)
+    }
+
+    pub fn policy_mut(&mut self, id: &str) -> Option<&mut EscalationPolicy> {
+        self.alarms.iter_mut().find(|alarm| alarm.id == id).map(|alarm| &mut alarm.policy)
+    }
+
+    /// Report whether an alarm condition holds at `now`; returns false for
+    /// unknown alarms
+    pub fn set_condition(&mut self, id: &str, active: bool, now: f64) -> bool {
+        let Some(alarm) = self.alarms.iter_mut().find(|alarm| alarm.id == id) else {
+            return false;
+        };
+        alarm.condition = active;
+        if active && !alarm.is_raised() {
+            alarm.raised_at = Some(now);
+            alarm.acknowledged = false;
+            alarm.stage = None;
+        } else if !active && alarm.is_raised() && (alarm.acknowledged || !alarm.policy.requires_ack) {
+            alarm.clear();
+        }
+        true
+    }
+
+    /// Acknowledge a raised alarm, stopping its escalation
+    pub fn acknowledge(&mut self, id: &str) -> bool {
+        let Some(alarm) = self.alarms.iter_mut().find(|alarm| alarm.id == id && alarm.is_raised()) else {
+            return false;
+        };
+        alarm.acknowledged = true;
+        if !alarm.condition {
+            alarm.clear();
+        }
+        true
+    }
+
+    /// Acknowledge every raised alarm; returns whether any was raised
+    pub fn acknowledge_all(&mut self) -> bool {
+        let ids: Vec<String> = self.alarms.iter().filter(|alarm| alarm.is_raised()).map(|alarm| alarm.id.clone()).collect();
+        for id in &ids {
+            self.acknowledge(id);
+        }
+        !ids.is_empty()
+    }
+
+    /// Advance the escalation of unacknowledged alarms to `now`
+    pub fn update(&mut self, now: f64) {
+        for (index, alarm) in self.alarms.iter_mut().enumerate() {
+            let Some(raised_at) = alarm.raised_at else {
+                continue;
+            };
+            if alarm.acknowledged {
+                continue;
+            }
+            let stage = alarm.policy.stage_after(now - raised_at);
+            if stage > alarm.stage {
+                alarm.stage = stage;
+                if let Some(stage) = stage {
+                    self.escalations.push(Escalation {
+                        alarm_id: alarm.id.clone(),
+                        label: alarm.label.clone(),
+                        number: index + 1,
+                        stage,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Escalations since the last call, oldest first
+    pub fn take_escalations(&mut self) -> Vec<Escalation> {
+        std::mem::take(&mut self.escalations)
+    }
+
+    /// Stages the outputs should currently show. Acknowledged alarms that
+    /// still hold stay visible but release everything else.
+    pub fn active_outputs(&self) -> BTreeSet<EscalationStage> {
+        let mut outputs = BTreeSet::new();
+        for alarm in self.alarms.iter().filter(|alarm| alarm.is_raised()) {
+            match alarm.stage() {
+                Some(stage) => outputs.extend(alarm.policy.stages.iter().copied().filter(|enabled| *enabled <= stage)),
+                None if alarm.policy.stages.contains(&EscalationStage::Visual) => {
+                    outputs.insert(EscalationStage::Visual);
+                }
+                None => {}
+            }
+        }
+        outputs
+    }
+}
+
+/// Shared handle to the alarm table, held by the alarms page, the condition
+/// checks and the output drivers
+#[derive(Resource, Clone, Default)]
+pub struct Alarms(Arc<RwLock<AlarmTable>>);
+
+impl Alarms {
+    pub fn read(&self) -> RwLockReadGuard<'_, AlarmTable> {
+        self.0.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, AlarmTable> {
+        self.0.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Raise and clear alarms from the vessel data and advance their escalation
+pub fn update_alarms(
+    alarms: Res<Alarms>,
+    vessel_data: Res<VesselData>,
+    depth_transducers: Res<DepthTransducers>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs_f64();
+    let shallow = depth_transducers.read().is_shallow(vessel_data.depth);
+    let mut table = alarms.write();
+    table.set_condition(SHALLOW_WATER_ALARM, shallow, now);
+    table.update(now);
+}
+
+/// Color the alarm indicator by the strongest active output, flashing once
+/// the alarm is past the visual stage
+pub fn update_alarm_indicator(
+    alarms: Res<Alarms>,
+    time: Res<Time>,
+    mut indicators: Query<&mut TextColor, With<AlarmIndicator>>,
+) {
+    let outputs = alarms.read().active_outputs();
+    let flash_off = time.elapsed_secs().fract() < 0.5;
+    let color = match outputs.last() {
+        None => TEXT_COLOR_PRIMARY,
+        Some(EscalationStage::Visual) => TEXT_COLOR_WARNING,
+        Some(_) if flash_off => TEXT_COLOR_PRIMARY,
+        Some(_) => TEXT_COLOR_DANGER,
+    };
+    for mut text_color in indicators.iter_mut() {
+        if text_color.0 != color {
+            text_color.0 = color;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escalation_and_acknowledgement() {
+        let mut table = AlarmTable::default();
+        table.policy_mut(SHALLOW_WATER_ALARM).unwrap().toggle_stage(EscalationStage::ShoreReport);
+        assert_eq!(
+            table.get(SHALLOW_WATER_ALARM).unwrap().policy.stages,
+            vec![EscalationStage::Visual, EscalationStage::Audible, EscalationStage::ShoreReport]
+        );
+
+        table.set_condition(SHALLOW_WATER_ALARM, true, 10.0);
+        table.update(10.0);
+        table.update(45.0);
+        let stages: Vec<_> = table.take_escalations().into_iter().map(|escalation| escalation.stage).collect();
+        assert_eq!(stages, vec![EscalationStage::Visual, EscalationStage::Audible]);
+        assert!(table.active_outputs().contains(&EscalationStage::Audible));
+
+        // Clearing the condition leaves the alarm latched until acknowledged
+        table.set_condition(SHALLOW_WATER_ALARM, false, 50.0);
+        table.update(75.0);
+        assert_eq!(table.get(SHALLOW_WATER_ALARM).unwrap().state_label(), "LATCHED");
+        assert_eq!(table.take_escalations()[0].stage, EscalationStage::ShoreReport);
+        assert!(table.acknowledge_all());
+        assert!(table.active_outputs().is_empty());
+        assert!(!table.get(SHALLOW_WATER_ALARM).unwrap().is_raised());
+
+        // Without an acknowledgement requirement the alarm clears with its
+        // condition; an acknowledged alarm that still holds stays visible
+        table.policy_mut(SHALLOW_WATER_ALARM).unwrap().requires_ack = false;
+        table.set_condition(SHALLOW_WATER_ALARM, true, 100.0);
+        table.update(100.0);
+        assert!(table.acknowledge(SHALLOW_WATER_ALARM));
+        table.update(200.0);
+        assert_eq!(table.take_escalations().len(), 1);
+        assert_eq!(table.active_outputs().into_iter().collect::<Vec<_>>(), vec![EscalationStage::Visual]);
+        table.set_condition(SHALLOW_WATER_ALARM, false, 210.0);
+        assert_eq!(table.get(SHALLOW_WATER_ALARM).unwrap().state_label(), "OK");
+    }
+}
@@ -0,0 +1,3 @@
+pub(crate) mod escalation;
+pub(crate) mod outputs;
+pub(crate) mod alarms_system;
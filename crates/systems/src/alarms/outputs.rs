@@ -0,0 +1,115 @@
+//! Hardware and network outputs driven by alarm escalation
+
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use datalink::{DataLinkConfig, DataLinkResult, DataLinkTransmitter, DataMessage};
+use datalink_provider::{frame_sentence, UdpDataLinkTransmitter};
+use super::escalation::Escalation;
+
+/// A GPIO line switched through its sysfs `value` file, e.g.
+/// `/sys/class/gpio/gpio17/value`
+#[derive(Debug)]
+pub struct GpioOutput {
+    path: PathBuf,
+    /// Last state written; `None` until the first write
+    state: Option<bool>,
+}
+
+impl GpioOutput {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            state: None,
+        }
+    }
+
+    /// Switch the line, writing only when the state changes
+    pub fn set(&mut self, on: bool) -> std::io::Result<()> {
+        if self.state == Some(on) {
+            return Ok(());
+        }
+        std::fs::write(&self.path, if on { "1" } else { "0" })?;
+        self.state = Some(on);
+        Ok(())
+    }
+}
+
+/// `hhmmss.ss` UTC time field
+fn nmea_time(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map_or(0.0, |since| since.as_secs_f64()) % 86_400.0;
+    let whole = seconds as u64;
+    format!("{:02}{:02}{:05.2}", whole / 3600, whole / 60 % 60, seconds % 60.0)
+}
+
+/// `ddmm.mmmm` (or `dddmm.mmmm`) and hemisphere fields
+fn nmea_coordinate(value: f64, degree_digits: usize, positive: char, negative: char) -> String {
+    let hemisphere = if value < 0.0 { negative } else { positive };
+    let value = value.abs();
+    let degrees = value.trunc();
+    let minutes = (value - degrees) * 60.0;
+    format!("{:0width$}{:07.4},{}", degrees as u32, minutes, hemisphere, width = degree_digits)
+}
+
+/// NMEA 0183 sentences reporting an escalated alarm and the vessel position:
+/// an ALR alarm state followed by a GLL position
+pub fn shore_report_sentences(escalation: &Escalation, latitude: f64, longitude: f64, time: SystemTime) -> [String; 2] {
+    let utc = nmea_time(time);
+    let description: String = escalation.label.chars().filter(|c| !matches!(c, ',' | '*' | '$')).collect();
+    let alarm = format!("IIALR,{},{:03},A,V,{}", utc, escalation.number, description);
+    let position = format!(
+        "IIGLL,{},{},{},A,A",
+        nmea_coordinate(latitude, 2, 'N', 'S'),
+        nmea_coordinate(longitude, 3, 'E', 'W'),
+        utc
+    );
+    [frame_sentence(&alarm), frame_sentence(&position)]
+}
+
+/// Sends alarm reports to a shore station over UDP
+pub struct ShoreReporter {
+    transmitter: UdpDataLinkTransmitter,
+}
+
+impl ShoreReporter {
+    /// Connect to `host:port`
+    pub fn connect(target: &str) -> DataLinkResult<Self> {
+        let (host, port) = target.rsplit_once(':').unwrap_or((target, ""));
+        let mut config = DataLinkConfig::new("udp".to_string()).with_parameter("host".to_string(), host.to_string());
+        if !port.is_empty() {
+            config = config.with_parameter("port".to_string(), port.to_string());
+        }
+        let mut transmitter = UdpDataLinkTransmitter::new();
+        transmitter.connect(&config)?;
+        Ok(Self { transmitter })
+    }
+
+    pub fn report(&mut self, escalation: &Escalation, latitude: f64, longitude: f64) -> DataLinkResult<()> {
+        for sentence in shore_report_sentences(escalation, latitude, longitude, SystemTime::now()) {
+            let message = DataMessage::new("ALARM_REPORT".to_string(), "YACHTPIT".to_string(), sentence)
+                .with_data("alarm", escalation.alarm_id.as_str());
+            self.transmitter.send_message(&message)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alarms::escalation::EscalationStage;
+    use std::time::Duration;
+
+    #[test]
+    fn test_shore_report_sentences() {
+        let escalation = Escalation {
+            alarm_id: "shallow_water".to_string(),
+            label: "SHALLOW WATER".to_string(),
+            number: 1,
+            stage: EscalationStage::ShoreReport,
+        };
+        let time = UNIX_EPOCH + Duration::from_secs(19_805 * 86_400 + 12 * 3600 + 35 * 60 + 19);
+        let [alarm, position] = shore_report_sentences(&escalation, 48.1173, -11.516_666_67, time);
+        assert!(alarm.starts_with("$IIALR,123519.00,001,A,V,SHALLOW WATER*"));
+        assert!(position.starts_with("$IIGLL,4807.0380,N,01131.0000,W,123519.00,A,A*"));
+    }
+}
@@ -0,0 +1,235 @@
+use bevy::log::warn;
+use bevy::prelude::Time;
+use components::{DepthTransducers, DepthUnit, VesselData};
+use crate::air_draft::clearance::{AirDraft, AirDraftSettings};
+use super::depth_store::DepthCalibrationStore;
+use crate::{SystemInteraction, SystemStatus, VesselSystem};
+
+/// Offset adjustment per `depth_offset_step`, in meters
+const DEPTH_OFFSET_STEP_M: f32 = 0.1;
+
+/// Shallow alarm adjustment per `shallow_alarm_step`, in meters
+const SHALLOW_ALARM_STEP_M: f32 = 0.5;
+
+/// Vessel height and tide adjustment per step, in meters
+const AIR_DRAFT_STEP_M: f32 = 0.1;
+
+/// Calibration screen for instrument offsets and source selection
+pub struct CalibrationSystem {
+    status: SystemStatus,
+    depth: DepthTransducers,
+    /// Where depth calibration changes are saved
+    depth_store: DepthCalibrationStore,
+    air_draft: AirDraft,
+}
+
+impl CalibrationSystem {
+    /// Create the calibration page editing the shared depth settings
+    pub fn new(depth: DepthTransducers) -> Self {
+        Self {
+            status: SystemStatus::Active,
+            depth,
+            depth_store: DepthCalibrationStore::in_memory(),
+            air_draft: AirDraft::default(),
+        }
+    }
+
+    /// Save depth calibration changes to `store`
+    pub fn with_depth_store(mut self, store: DepthCalibrationStore) -> Self {
+        self.depth_store = store;
+        self
+    }
+
+    /// Also edit the shared air-draft settings
+    pub fn with_air_draft(mut self, air_draft: AirDraft) -> Self {
+        self.air_draft = air_draft;
+        self
+    }
+
+    /// Air-draft settings keys; `None` for keys this page handles elsewhere
+    fn configure_air_draft(&mut self, key: &str, value: &str) -> Option<bool> {
+        let field: fn(&mut AirDraftSettings) -> &mut f32 = match key.trim_end_matches("_step") {
+            "vessel_height" => |settings| &mut settings.vessel_height_m,
+            "tide_height" => |settings| &mut settings.tide_height_m,
+            "clearance_datum" => |settings| &mut settings.clearance_datum_m,
+            "safety_margin" => |settings| &mut settings.safety_margin_m,
+            _ => return None,
+        };
+        // Only the tide may go below chart datum
+        let min = if key.starts_with("tide_height") { f32::MIN } else { 0.0 };
+        let mut state = self.air_draft.write();
+        let value = if key.ends_with("_step") {
+            let step = if value == "up" { AIR_DRAFT_STEP_M } else { -AIR_DRAFT_STEP_M };
+            (((*field(&mut state.settings) + step) * 10.0).round() / 10.0).max(min)
+        } else {
+            match value.parse::<f32>() {
+                Ok(value) if value.is_finite() && value >= min => value,
+                _ => return Some(false),
+            }
+        };
+        *field(&mut state.settings) = value;
+        Some(true)
+    }
+
+    fn save_depth(&self) {
+        if let Err(e) = self.depth_store.save(&self.depth.read()) {
+            warn!("Failed to save depth calibration: {}", e);
+        }
+    }
+
+    fn configure(&mut self, key: &str, value: &str) -> bool {
+        if let Some(handled) = self.configure_air_draft(key, value) {
+            return handled;
+        }
+        let changed = self.configure_depth(key, value);
+        if changed {
+            self.save_depth();
+        }
+        changed
+    }
+
+    fn configure_depth(&mut self, key: &str, value: &str) -> bool {
+        let mut settings = self.depth.write();
+        match key {
+            "primary_depth" => {
+                if value == "next" {
+                    settings.cycle_primary();
+                    true
+                } else {
+                    settings.select_primary(value)
+                }
+            }
+            // "<transducer>=<meters>"
+            "depth_offset" => {
+                let Some((id, offset)) = value.split_once('=') else {
+                    return false;
+                };
+                match offset.trim().parse::<f32>() {
+                    Ok(offset) => settings.set_offset(id.trim(), offset),
+                    Err(_) => false,
+                }
+            }
+            "depth_offset_step" => {
+                let primary = settings.primary.clone();
+                let step = if value == "up" { DEPTH_OFFSET_STEP_M } else { -DEPTH_OFFSET_STEP_M };
+                let offset = settings.primary_transducer().map_or(0.0, |transducer| transducer.offset_m);
+                settings.set_offset(&primary, ((offset + step) * 10.0).round() / 10.0)
+            }
+            "depth_unit" => {
+                let unit = if value == "next" { Some(settings.unit.next()) } else { DepthUnit::parse(value) };
+                match unit {
+                    Some(unit) => {
+                        settings.unit = unit;
+                        true
+                    }
+                    None => false,
+                }
+            }
+            "shallow_alarm" => match value.parse::<f32>() {
+                Ok(depth) if depth >= 0.0 => {
+                    settings.shallow_alarm_m = depth;
+                    true
+                }
+                _ => false,
+            },
+            "shallow_alarm_step" => {
+                let step = if value == "up" { SHALLOW_ALARM_STEP_M } else { -SHALLOW_ALARM_STEP_M };
+                settings.shallow_alarm_m = (settings.shallow_alarm_m + step).max(0.0);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl VesselSystem for CalibrationSystem {
+    fn id(&self) -> &'static str {
+        "calibration"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Calibration"
+    }
+
+    fn update(&mut self, _yacht_data: &VesselData, _time: &Time) {}
+
+    fn render_display(&self, _yacht_data: &VesselData) -> String {
+        let settings = self.depth.read();
+        let unit = settings.unit;
+
+        let mut display = String::from("CALIBRATION\n\nDEPTH TRANSDUCERS\n");
+        for transducer in &settings.transducers {
+            let reading = transducer
+                .depth_m()
+                .map(|depth| format!("{:.1} {}", unit.from_meters(depth), unit.label()))
+                .unwrap_or_else(|| "NO DATA".to_string());
+            display.push_str(&format!(
+                "{} {} ({}): offset {:+.1} m, {}\n",
+                if transducer.id == settings.primary { "▶" } else { " " },
+                transducer.label,
+                transducer.id,
+                transducer.offset_m,
+                reading
+            ));
+        }
+        display.push_str(&format!(
+            "\nDepth Unit: {}\n\
+            Shallow Alarm: {:.1} {}\n\
+            \n\
+            [P] Primary  [ [ / ] ] Offset  [U] Unit  [Up/Down] Shallow Alarm\n",
+            unit.label(),
+            unit.from_meters(settings.shallow_alarm_m),
+            unit.label()
+        ));
+
+        let air_draft = self.air_draft.read();
+        let air = &air_draft.settings;
+        display.push_str("\nAIR DRAFT\n");
+        if air.is_configured() {
+            display.push_str(&format!(
+                "Vessel Height: {:.1} m (+{:.1} m margin)\n",
+                air.vessel_height_m, air.safety_margin_m
+            ));
+        } else {
+            display.push_str("Vessel Height: NOT SET\n");
+        }
+        display.push_str(&format!(
+            "Tide: {:+.1} m  Clearance Datum: {:.1} m\n",
+            air.tide_height_m, air.clearance_datum_m
+        ));
+        for bridge in air_draft.upcoming() {
+            display.push_str(&format!(
+                "{} {} in {:.1} NM: {:.1} m clear{}\n",
+                if bridge.sufficient { " " } else { "!" },
+                bridge.label,
+                bridge.distance_m / 1852.0,
+                bridge.available_m,
+                if bridge.sufficient { "" } else { " TOO LOW" }
+            ));
+        }
+        display.push_str("\n[H/Shift+H] Vessel Height  [T/Shift+T] Tide");
+        display
+    }
+
+    fn handle_interaction(&mut self, interaction: SystemInteraction) -> bool {
+        match interaction {
+            SystemInteraction::Select => {
+                self.status = SystemStatus::Active;
+                true
+            }
+            SystemInteraction::Configure(key, value) => self.configure(&key, &value),
+            SystemInteraction::Reset => {
+                for transducer in self.depth.write().transducers.iter_mut() {
+                    transducer.offset_m = 0.0;
+                }
+                self.save_depth();
+                true
+            }
+            SystemInteraction::Toggle => false,
+        }
+    }
+
+    fn status(&self) -> SystemStatus {
+        self.status.clone()
+    }
+}
@@ -0,0 +1,140 @@
+use bevy::log::warn;
+use components::{DepthSettings, DepthTransducer, DepthUnit};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// File name of the persisted depth calibration inside the data directory
+const STORE_FILE_NAME: &str = "depth_calibration.json";
+
+/// On-disk layout of the depth calibration
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoredDepthCalibration {
+    /// Offset of each transducer in meters, by transducer id
+    #[serde(default)]
+    offsets: BTreeMap<String, f32>,
+    #[serde(default)]
+    primary: Option<String>,
+    /// Unit label, as [`DepthUnit::label`]
+    #[serde(default)]
+    unit: Option<String>,
+    #[serde(default)]
+    shallow_alarm_m: Option<f32>,
+}
+
+/// Transducer offsets, primary selection, unit and shallow alarm set on the
+/// calibration page, kept between trips
+#[derive(Debug, Clone, Default)]
+pub struct DepthCalibrationStore {
+    path: Option<PathBuf>,
+}
+
+impl DepthCalibrationStore {
+    /// A store that is never read from or written to disk
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        Self { path: Some(path.into()) }
+    }
+
+    /// The store at the default path, or an in-memory one where there is no data directory
+    pub fn at_default_path() -> Self {
+        Self { path: Self::default_path() }
+    }
+
+    /// `$YACHTPIT_DATA_DIR/depth_calibration.json`, falling back to `~/.yachtpit`
+    pub fn default_path() -> Option<PathBuf> {
+        Some(crate::storage::data_dir()?.join(STORE_FILE_NAME))
+    }
+
+    /// The saved settings over the defaults. Transducers that were calibrated
+    /// but are not among the defaults are listed again with their offset.
+    pub fn load(&self) -> DepthSettings {
+        let mut settings = DepthSettings::default();
+        let Some(path) = &self.path else {
+            return settings;
+        };
+        let stored: StoredDepthCalibration = match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable depth calibration {}: {}", path.display(), e);
+                StoredDepthCalibration::default()
+            }),
+            Err(_) => return settings,
+        };
+
+        for (id, offset_m) in stored.offsets {
+            if !settings.set_offset(&id, offset_m) {
+                let mut transducer = DepthTransducer::new(&id, &id);
+                transducer.offset_m = offset_m;
+                settings.transducers.push(transducer);
+            }
+        }
+        if let Some(primary) = stored.primary {
+            settings.select_primary(&primary);
+        }
+        if let Some(unit) = stored.unit.as_deref().and_then(DepthUnit::parse) {
+            settings.unit = unit;
+        }
+        if let Some(shallow_alarm_m) = stored.shallow_alarm_m.filter(|depth| *depth >= 0.0) {
+            settings.shallow_alarm_m = shallow_alarm_m;
+        }
+        settings
+    }
+
+    pub fn save(&self, settings: &DepthSettings) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let stored = StoredDepthCalibration {
+            offsets: settings.transducers.iter().map(|transducer| (transducer.id.clone(), transducer.offset_m)).collect(),
+            primary: Some(settings.primary.clone()),
+            unit: Some(settings.unit.label().to_string()),
+            shallow_alarm_m: Some(settings.shallow_alarm_m),
+        };
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let contents = serde_json::to_string_pretty(&stored)?;
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, contents)?;
+        std::fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_depth_calibration_persists_between_sessions() {
+        let path = std::env::temp_dir()
+            .join(format!("yachtpit-depth-calibration-{}", std::process::id()))
+            .join(STORE_FILE_NAME);
+        let store = DepthCalibrationStore::at(&path);
+        assert_eq!(store.load(), DepthSettings::default());
+
+        let mut settings = store.load();
+        settings.set_offset("FWD", 0.4);
+        settings.record(Some("KEEL"), 3.0);
+        settings.set_offset("KEEL", -1.2);
+        settings.select_primary("KEEL");
+        settings.unit = DepthUnit::Fathoms;
+        settings.shallow_alarm_m = 4.5;
+        store.save(&settings).unwrap();
+
+        // Offsets and selection survive a restart; readings do not
+        let reloaded = store.load();
+        assert_eq!(reloaded.transducer("FWD").unwrap().offset_m, 0.4);
+        assert_eq!(reloaded.transducer("KEEL").unwrap().offset_m, -1.2);
+        assert_eq!(reloaded.transducer("KEEL").unwrap().raw_depth_m, None);
+        assert_eq!(reloaded.primary, "KEEL");
+        assert_eq!(reloaded.unit, DepthUnit::Fathoms);
+        assert_eq!(reloaded.shallow_alarm_m, 4.5);
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
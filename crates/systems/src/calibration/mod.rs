@@ -0,0 +1,2 @@
+pub(crate) mod calibration_system;
+pub(crate) mod depth_store;
@@ -0,0 +1,269 @@
+use bevy::log::warn;
+use bevy::prelude::Time;
+use components::VesselData;
+use std::path::PathBuf;
+use super::store::{Annotation, AnnotationKind, ChartAnnotations, ChartStore, MarkSymbol};
+use crate::{SystemInteraction, SystemStatus, VesselSystem};
+
+/// Radius of a newly placed hazard, in meters
+const DEFAULT_HAZARD_RADIUS_M: f64 = 100.0;
+
+/// Hazard radius adjustment per `hazard_radius_step`, in meters
+const HAZARD_RADIUS_STEP_M: f64 = 50.0;
+
+/// Charted clearance of a newly placed bridge, in meters
+const DEFAULT_BRIDGE_CLEARANCE_M: f64 = 10.0;
+
+/// Bridge clearance adjustment per `clearance_step`, in meters
+const BRIDGE_CLEARANCE_STEP_M: f64 = 0.5;
+
+/// File name used by `export_gpx` when no path is given
+const GPX_EXPORT_FILE_NAME: &str = "annotations.gpx";
+
+/// Chart page listing user annotations, with editing of the selected one
+pub struct ChartSystem {
+    status: SystemStatus,
+    chart: ChartAnnotations,
+    /// Id of the selected annotation
+    selected: Option<u64>,
+    last_export: Option<String>,
+}
+
+impl ChartSystem {
+    /// Create the chart page editing the shared chart store
+    pub fn new(chart: ChartAnnotations) -> Self {
+        Self {
+            status: SystemStatus::Active,
+            chart,
+            selected: None,
+            last_export: None,
+        }
+    }
+
+    pub fn selected(&self) -> Option<u64> {
+        self.selected
+    }
+
+    fn parse_position(value: &str) -> Option<(f64, f64)> {
+        let (latitude, longitude) = value.split_once(',')?;
+        let latitude: f64 = latitude.trim().parse().ok()?;
+        let longitude: f64 = longitude.trim().parse().ok()?;
+        ((-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude)).then_some((latitude, longitude))
+    }
+
+    fn add(&mut self, value: &str, kind: AnnotationKind) -> bool {
+        let Some((latitude, longitude)) = Self::parse_position(value) else {
+            return false;
+        };
+        let mut store = self.chart.write();
+        let label = format!("{} {}", kind.name().to_uppercase(), store.annotations().len() + 1);
+        let annotation = store.upsert_annotation(Annotation::new(latitude, longitude, &label, kind));
+        self.selected = Some(annotation.id);
+        true
+    }
+
+    fn select_step(&mut self, forward: bool) -> bool {
+        let store = self.chart.read();
+        let annotations = store.annotations();
+        if annotations.is_empty() {
+            self.selected = None;
+            return false;
+        }
+        let current = self
+            .selected
+            .and_then(|id| annotations.iter().position(|annotation| annotation.id == id));
+        let index = match (current, forward) {
+            (None, true) => 0,
+            (None, false) => annotations.len() - 1,
+            (Some(index), true) => (index + 1) % annotations.len(),
+            (Some(index), false) => (index + annotations.len() - 1) % annotations.len(),
+        };
+        self.selected = Some(annotations[index].id);
+        true
+    }
+
+    fn edit_selected(&mut self, edit: impl FnOnce(&mut Annotation) -> bool) -> bool {
+        let Some(id) = self.selected else {
+            return false;
+        };
+        let mut store = self.chart.write();
+        store.annotation_mut(id).is_some_and(edit)
+    }
+
+    fn export_path(value: &str) -> Option<PathBuf> {
+        if value.is_empty() || value == "default" {
+            ChartStore::default_path().map(|path| path.with_file_name(GPX_EXPORT_FILE_NAME))
+        } else {
+            Some(PathBuf::from(value))
+        }
+    }
+
+    fn configure(&mut self, key: &str, value: &str) -> bool {
+        match key {
+            // "<lat>,<lon>"
+            "add_mark" => self.add(value, AnnotationKind::Mark { symbol: MarkSymbol::default() }),
+            "add_hazard" => self.add(value, AnnotationKind::Hazard { radius_m: DEFAULT_HAZARD_RADIUS_M }),
+            "add_note" => self.add(value, AnnotationKind::Note { text: String::new() }),
+            "add_bridge" => self.add(value, AnnotationKind::Bridge { clearance_m: DEFAULT_BRIDGE_CLEARANCE_M }),
+            "select" => match value {
+                "next" => self.select_step(true),
+                "prev" => self.select_step(false),
+                id => match id.parse::<u64>() {
+                    Ok(id) if self.chart.read().annotation(id).is_some() => {
+                        self.selected = Some(id);
+                        true
+                    }
+                    _ => false,
+                },
+            },
+            "label" => self.edit_selected(|annotation| {
+                annotation.label = value.to_string();
+                true
+            }),
+            "note" => self.edit_selected(|annotation| match &mut annotation.kind {
+                AnnotationKind::Note { text } => {
+                    *text = value.to_string();
+                    true
+                }
+                _ => false,
+            }),
+            "symbol" => self.edit_selected(|annotation| match &mut annotation.kind {
+                AnnotationKind::Mark { symbol } => {
+                    *symbol = if value == "next" {
+                        symbol.next()
+                    } else {
+                        match MarkSymbol::ALL.iter().find(|candidate| candidate.name() == value) {
+                            Some(candidate) => *candidate,
+                            None => return false,
+                        }
+                    };
+                    true
+                }
+                _ => false,
+            }),
+            "hazard_radius_step" => self.edit_selected(|annotation| match &mut annotation.kind {
+                AnnotationKind::Hazard { radius_m } => {
+                    let step = if value == "up" { HAZARD_RADIUS_STEP_M } else { -HAZARD_RADIUS_STEP_M };
+                    *radius_m = (*radius_m + step).max(HAZARD_RADIUS_STEP_M);
+                    true
+                }
+                _ => false,
+            }),
+            // Charted clearance in meters
+            "clearance" => match value.parse::<f64>() {
+                Ok(clearance) if clearance >= 0.0 => self.edit_selected(|annotation| match &mut annotation.kind {
+                    AnnotationKind::Bridge { clearance_m } => {
+                        *clearance_m = clearance;
+                        true
+                    }
+                    _ => false,
+                }),
+                _ => false,
+            },
+            "clearance_step" => self.edit_selected(|annotation| match &mut annotation.kind {
+                AnnotationKind::Bridge { clearance_m } => {
+                    let step = if value == "up" { BRIDGE_CLEARANCE_STEP_M } else { -BRIDGE_CLEARANCE_STEP_M };
+                    *clearance_m = (*clearance_m + step).max(0.0);
+                    true
+                }
+                _ => false,
+            }),
+            "delete" => {
+                let Some(id) = self.selected else {
+                    return false;
+                };
+                let removed = self.chart.write().remove_annotation(id);
+                self.selected = None;
+                self.select_step(true);
+                removed
+            }
+            "export_gpx" => {
+                let Some(path) = Self::export_path(value) else {
+                    return false;
+                };
+                let result = self.chart.read().export_gpx(&path);
+                self.last_export = Some(match &result {
+                    Ok(()) => format!("Exported to {}", path.display()),
+                    Err(e) => format!("Export failed: {}", e),
+                });
+                result.is_ok()
+            }
+            _ => false,
+        }
+    }
+}
+
+impl VesselSystem for ChartSystem {
+    fn id(&self) -> &'static str {
+        "chart"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Chart"
+    }
+
+    fn update(&mut self, _yacht_data: &VesselData, _time: &Time) {
+        let mut store = self.chart.write();
+        if store.is_dirty() {
+            if let Err(e) = store.save() {
+                warn!("Failed to save chart annotations: {}", e);
+            }
+        }
+    }
+
+    fn render_display(&self, _yacht_data: &VesselData) -> String {
+        let store = self.chart.read();
+
+        let mut display = format!(
+            "CHART ANNOTATIONS\n\nWaypoints: {}\nAnnotations: {}\n\n",
+            store.waypoints().len(),
+            store.annotations().len()
+        );
+        if store.annotations().is_empty() {
+            display.push_str("No annotations\n");
+        }
+        for annotation in store.annotations() {
+            let detail = match &annotation.kind {
+                AnnotationKind::Mark { symbol } => symbol.name().to_uppercase(),
+                AnnotationKind::Hazard { radius_m } => format!("HAZARD {:.0} m", radius_m),
+                AnnotationKind::Note { text } if text.is_empty() => "NOTE".to_string(),
+                AnnotationKind::Note { text } => format!("NOTE \"{}\"", text),
+                AnnotationKind::Bridge { clearance_m } => format!("BRIDGE {:.1} m", clearance_m),
+            };
+            display.push_str(&format!(
+                "{} {} ({:.4}, {:.4}) {}\n",
+                if Some(annotation.id) == self.selected { "▶" } else { " " },
+                annotation.label,
+                annotation.latitude,
+                annotation.longitude,
+                detail
+            ));
+        }
+        if let Some(last_export) = &self.last_export {
+            display.push_str(&format!("\n{}\n", last_export));
+        }
+        display.push_str(
+            "\n[M] Mark  [H] Hazard  [N] Note  [B] Bridge  [Up/Down] Select  [S] Symbol  [+/-] Radius/Clearance  [Del] Delete  [X] Export GPX",
+        );
+        display
+    }
+
+    fn handle_interaction(&mut self, interaction: SystemInteraction) -> bool {
+        match interaction {
+            SystemInteraction::Select => {
+                self.status = SystemStatus::Active;
+                true
+            }
+            SystemInteraction::Configure(key, value) => self.configure(&key, &value),
+            SystemInteraction::Reset => {
+                self.selected = None;
+                true
+            }
+            SystemInteraction::Toggle => false,
+        }
+    }
+
+    fn status(&self) -> SystemStatus {
+        self.status.clone()
+    }
+}
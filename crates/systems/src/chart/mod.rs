@@ -0,0 +1,2 @@
+pub(crate) mod chart_system;
+pub(crate) mod store;
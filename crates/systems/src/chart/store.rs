@@ -0,0 +1,397 @@
+use bevy::log::warn;
+use bevy::prelude::Resource;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// File name of the persisted store inside the data directory
+const STORE_FILE_NAME: &str = "chart_store.json";
+
+/// Namespace of the GPX extension elements describing annotations
+pub const GPX_EXTENSION_NS: &str = "https://github.com/seemueller-io/yachtpit/gpx/1";
+
+/// Symbol drawn for a user mark
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkSymbol {
+    #[default]
+    Flag,
+    Anchorage,
+    Fuel,
+    Fish,
+    Dive,
+    Danger,
+}
+
+impl MarkSymbol {
+    pub const ALL: [MarkSymbol; 6] = [
+        MarkSymbol::Flag,
+        MarkSymbol::Anchorage,
+        MarkSymbol::Fuel,
+        MarkSymbol::Fish,
+        MarkSymbol::Dive,
+        MarkSymbol::Danger,
+    ];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|symbol| *symbol == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MarkSymbol::Flag => "flag",
+            MarkSymbol::Anchorage => "anchorage",
+            MarkSymbol::Fuel => "fuel",
+            MarkSymbol::Fish => "fish",
+            MarkSymbol::Dive => "dive",
+            MarkSymbol::Danger => "danger",
+        }
+    }
+
+    /// GPX `<sym>` value, using the names common chartplotters recognise
+    pub fn gpx_symbol(self) -> &'static str {
+        match self {
+            MarkSymbol::Flag => "Flag, Blue",
+            MarkSymbol::Anchorage => "Anchor",
+            MarkSymbol::Fuel => "Gas Station",
+            MarkSymbol::Fish => "Fishing Area",
+            MarkSymbol::Dive => "Diver Down Flag 1",
+            MarkSymbol::Danger => "Skull and Crossbones",
+        }
+    }
+}
+
+/// What an annotation marks on the chart
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnnotationKind {
+    Mark { symbol: MarkSymbol },
+    /// Area to keep clear of, drawn as a circle
+    Hazard { radius_m: f64 },
+    Note { text: String },
+    /// Fixed bridge or overhead cable with its charted vertical clearance,
+    /// measured from the chart's clearance datum
+    Bridge { clearance_m: f64 },
+}
+
+impl AnnotationKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AnnotationKind::Mark { .. } => "mark",
+            AnnotationKind::Hazard { .. } => "hazard",
+            AnnotationKind::Note { .. } => "note",
+            AnnotationKind::Bridge { .. } => "bridge",
+        }
+    }
+}
+
+/// A user annotation placed on the chart
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    /// Assigned by the store; 0 for an annotation not yet stored
+    #[serde(default)]
+    pub id: u64,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub label: String,
+    #[serde(flatten)]
+    pub kind: AnnotationKind,
+}
+
+impl Annotation {
+    pub fn new(latitude: f64, longitude: f64, label: &str, kind: AnnotationKind) -> Self {
+        Self {
+            id: 0,
+            latitude,
+            longitude,
+            label: label.to_string(),
+            kind,
+        }
+    }
+}
+
+/// A named route point
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Waypoint {
+    #[serde(default)]
+    pub id: u64,
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoredChart {
+    #[serde(default)]
+    waypoints: Vec<Waypoint>,
+    #[serde(default)]
+    annotations: Vec<Annotation>,
+}
+
+/// Waypoints and user annotations, persisted together
+#[derive(Debug, Default)]
+pub struct ChartStore {
+    waypoints: Vec<Waypoint>,
+    annotations: Vec<Annotation>,
+    next_id: u64,
+    path: Option<PathBuf>,
+    dirty: bool,
+}
+
+impl ChartStore {
+    /// A store that is never written to disk
+    pub fn in_memory() -> Self {
+        Self {
+            next_id: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Load the store from `path`, starting empty if the file is missing or unreadable
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let stored = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable chart store {}: {}", path.display(), e);
+                StoredChart::default()
+            }),
+            Err(_) => StoredChart::default(),
+        };
+
+        let max_id = stored
+            .waypoints
+            .iter()
+            .map(|waypoint| waypoint.id)
+            .chain(stored.annotations.iter().map(|annotation| annotation.id))
+            .max()
+            .unwrap_or(0);
+        Self {
+            waypoints: stored.waypoints,
+            annotations: stored.annotations,
+            next_id: max_id + 1,
+            path: Some(path),
+            dirty: false,
+        }
+    }
+
+    /// The store at the default path, or an in-memory one where there is no data directory
+    pub fn load_default() -> Self {
+        match Self::default_path() {
+            Some(path) => Self::load(path),
+            None => Self::in_memory(),
+        }
+    }
+
+    /// `$YACHTPIT_DATA_DIR/chart_store.json`, falling back to `~/.yachtpit`
+    pub fn default_path() -> Option<PathBuf> {
+        Some(crate::storage::data_dir()?.join(STORE_FILE_NAME))
+    }
+
+    fn allocate_id(&mut self) -> u64 {
+        let id = self.next_id.max(1);
+        self.next_id = id + 1;
+        id
+    }
+
+    pub fn waypoints(&self) -> &[Waypoint] {
+        &self.waypoints
+    }
+
+    pub fn add_waypoint(&mut self, name: &str, latitude: f64, longitude: f64) -> u64 {
+        let id = self.allocate_id();
+        self.waypoints.push(Waypoint { id, name: name.to_string(), latitude, longitude });
+        self.dirty = true;
+        id
+    }
+
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.annotations
+    }
+
+    pub fn annotation(&self, id: u64) -> Option<&Annotation> {
+        self.annotations.iter().find(|annotation| annotation.id == id)
+    }
+
+    pub fn annotation_mut(&mut self, id: u64) -> Option<&mut Annotation> {
+        let annotation = self.annotations.iter_mut().find(|annotation| annotation.id == id);
+        self.dirty |= annotation.is_some();
+        annotation
+    }
+
+    /// Store a new annotation, or replace the stored one with the same id.
+    /// Returns the annotation as stored.
+    pub fn upsert_annotation(&mut self, mut annotation: Annotation) -> Annotation {
+        self.dirty = true;
+        if let Some(existing) = self.annotations.iter_mut().find(|existing| existing.id == annotation.id && annotation.id != 0) {
+            *existing = annotation.clone();
+            return annotation;
+        }
+        annotation.id = self.allocate_id();
+        self.annotations.push(annotation.clone());
+        annotation
+    }
+
+    pub fn remove_annotation(&mut self, id: u64) -> bool {
+        let before = self.annotations.len();
+        self.annotations.retain(|annotation| annotation.id != id);
+        let removed = self.annotations.len() != before;
+        self.dirty |= removed;
+        removed
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Write the store if it changed since the last save
+    pub fn save(&mut self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            self.dirty = false;
+            return Ok(());
+        };
+        if !self.dirty {
+            return Ok(());
+        }
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let stored = StoredChart {
+            waypoints: self.waypoints.clone(),
+            annotations: self.annotations.clone(),
+        };
+        let contents = serde_json::to_string_pretty(&stored)?;
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, contents)?;
+        std::fs::rename(&temp_path, path)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// GPX 1.1 document with waypoints and annotations as `<wpt>` elements;
+    /// annotation details go in a `yachtpit:annotation` extension
+    pub fn to_gpx(&self) -> String {
+        let mut gpx = String::new();
+        gpx.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(
+            gpx,
+            "<gpx version=\"1.1\" creator=\"yachtpit\" xmlns=\"http://www.topografix.com/GPX/1/1\" xmlns:yachtpit=\"{}\">",
+            GPX_EXTENSION_NS
+        );
+
+        for waypoint in &self.waypoints {
+            let _ = writeln!(gpx, "  <wpt lat=\"{:.6}\" lon=\"{:.6}\">", waypoint.latitude, waypoint.longitude);
+            let _ = writeln!(gpx, "    <name>{}</name>", xml_escape(&waypoint.name));
+            gpx.push_str("  </wpt>\n");
+        }
+
+        for annotation in &self.annotations {
+            let _ = writeln!(gpx, "  <wpt lat=\"{:.6}\" lon=\"{:.6}\">", annotation.latitude, annotation.longitude);
+            let _ = writeln!(gpx, "    <name>{}</name>", xml_escape(&annotation.label));
+            let (symbol, attributes, text) = match &annotation.kind {
+                AnnotationKind::Mark { symbol } => (symbol.gpx_symbol(), format!(" symbol=\"{}\"", symbol.name()), None),
+                AnnotationKind::Hazard { radius_m } => ("Danger Area", format!(" radius_m=\"{:.1}\"", radius_m), None),
+                AnnotationKind::Note { text } => ("Information", String::new(), Some(text)),
+                AnnotationKind::Bridge { clearance_m } => ("Bridge", format!(" clearance_m=\"{:.1}\"", clearance_m), None),
+            };
+            if let Some(text) = text {
+                let _ = writeln!(gpx, "    <desc>{}</desc>", xml_escape(text));
+            }
+            let _ = writeln!(gpx, "    <sym>{}</sym>", symbol);
+            let _ = writeln!(gpx, "    <type>{}</type>", annotation.kind.name());
+            gpx.push_str("    <extensions>\n");
+            let _ = writeln!(
+                gpx,
+                "      <yachtpit:annotation id=\"{}\" kind=\"{}\"{}/>",
+                annotation.id,
+                annotation.kind.name(),
+                attributes
+            );
+            gpx.push_str("    </extensions>\n");
+            gpx.push_str("  </wpt>\n");
+        }
+
+        gpx.push_str("</gpx>\n");
+        gpx
+    }
+
+    /// Write [`to_gpx`](Self::to_gpx) to `path`
+    pub fn export_gpx(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.to_gpx())
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Shared handle to the chart store, held by the chart page and the map webview
+#[derive(Resource, Clone)]
+pub struct ChartAnnotations(Arc<RwLock<ChartStore>>);
+
+impl ChartAnnotations {
+    pub fn new(store: ChartStore) -> Self {
+        Self(Arc::new(RwLock::new(store)))
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, ChartStore> {
+        self.0.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, ChartStore> {
+        self.0.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for ChartAnnotations {
+    fn default() -> Self {
+        Self::new(ChartStore::in_memory())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotations_persist_with_waypoints_and_export_to_gpx() {
+        let path = std::env::temp_dir()
+            .join(format!("yachtpit-chart-store-{}", std::process::id()))
+            .join(STORE_FILE_NAME);
+
+        let mut store = ChartStore::load(&path);
+        store.add_waypoint("Harbor Entrance", 43.7347, 7.4206);
+        let mark = store.upsert_annotation(Annotation::new(43.73, 7.42, "Good holding", AnnotationKind::Mark { symbol: MarkSymbol::Anchorage }));
+        let hazard = store.upsert_annotation(Annotation::new(43.72, 7.43, "Rocks", AnnotationKind::Hazard { radius_m: 150.0 }));
+        store.upsert_annotation(Annotation::new(43.71, 7.44, "Fuel dock", AnnotationKind::Note { text: "Closes at 18:00 & Sundays".to_string() }));
+        assert_ne!(mark.id, hazard.id);
+
+        // Editing keeps the id
+        let mut edited = hazard.clone();
+        edited.kind = AnnotationKind::Hazard { radius_m: 200.0 };
+        assert_eq!(store.upsert_annotation(edited).id, hazard.id);
+        assert!(store.remove_annotation(mark.id));
+        store.save().unwrap();
+
+        let reloaded = ChartStore::load(&path);
+        assert_eq!(reloaded.waypoints().len(), 1);
+        assert_eq!(reloaded.annotations().len(), 2);
+        assert_eq!(reloaded.annotation(hazard.id).unwrap().kind, AnnotationKind::Hazard { radius_m: 200.0 });
+
+        let gpx = reloaded.to_gpx();
+        assert_eq!(gpx.matches("<wpt ").count(), 3);
+        assert!(gpx.contains("<name>Harbor Entrance</name>"));
+        assert!(gpx.contains(&format!("<yachtpit:annotation id=\"{}\" kind=\"hazard\" radius_m=\"200.0\"/>", hazard.id)));
+        assert!(gpx.contains("<desc>Closes at 18:00 &amp; Sundays</desc>"));
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,142 @@
+use bevy::log::warn;
+use bevy::prelude::Time;
+use components::VesselData;
+use super::store::{ChecklistKind, Checklists};
+use crate::{SystemInteraction, SystemStatus, VesselSystem};
+
+/// Pre-departure and arrival checklist page
+pub struct ChecklistSystem {
+    status: SystemStatus,
+    checklists: Checklists,
+    kind: ChecklistKind,
+    selected: usize,
+}
+
+impl ChecklistSystem {
+    /// Create the checklist page for the shared checklists
+    pub fn new(checklists: Checklists) -> Self {
+        Self {
+            status: SystemStatus::Active,
+            checklists,
+            kind: ChecklistKind::Departure,
+            selected: 0,
+        }
+    }
+
+    fn configure(&mut self, key: &str, value: &str) -> bool {
+        let mut store = self.checklists.write();
+        let count = store.items(self.kind).len();
+        match key {
+            "list" => {
+                self.kind = match value {
+                    "departure" => ChecklistKind::Departure,
+                    "arrival" => ChecklistKind::Arrival,
+                    "toggle" => match self.kind {
+                        ChecklistKind::Departure => ChecklistKind::Arrival,
+                        ChecklistKind::Arrival => ChecklistKind::Departure,
+                    },
+                    _ => return false,
+                };
+                self.selected = 0;
+                true
+            }
+            "select" if count > 0 => {
+                self.selected = match value {
+                    "next" => (self.selected + 1) % count,
+                    "previous" => (self.selected + count - 1) % count,
+                    _ => return false,
+                };
+                true
+            }
+            // An item number from 1, or "selected"
+            "check" => {
+                let index = match value {
+                    "selected" => self.selected,
+                    number => match number.parse::<usize>() {
+                        Ok(number) if number > 0 => number - 1,
+                        _ => return false,
+                    },
+                };
+                store.toggle_item(self.kind, index)
+            }
+            "add_item" => store.add_item(self.kind, value),
+            "remove_item" => {
+                let removed = store.remove_item(self.kind, self.selected);
+                self.selected = self.selected.min(store.items(self.kind).len().saturating_sub(1));
+                removed
+            }
+            "reset" => {
+                store.reset(self.kind);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl VesselSystem for ChecklistSystem {
+    fn id(&self) -> &'static str {
+        "checklists"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Checklists"
+    }
+
+    fn update(&mut self, _yacht_data: &VesselData, _time: &Time) {
+        let mut store = self.checklists.write();
+        if store.is_dirty() {
+            if let Err(e) = store.save() {
+                warn!("Failed to save checklists: {}", e);
+            }
+        }
+    }
+
+    fn render_display(&self, _yacht_data: &VesselData) -> String {
+        let store = self.checklists.read();
+        let items = store.items(self.kind);
+        let done = items.iter().filter(|item| item.done).count();
+
+        let mut display = format!(
+            "PASSAGE CHECKLISTS\n\n\
+            Passage: {}\n\
+            {} ({}/{})\n\n",
+            if store.passage() == 0 { "NONE YET".to_string() } else { store.passage().to_string() },
+            self.kind.label(),
+            done,
+            items.len()
+        );
+        if items.is_empty() {
+            display.push_str("No items\n");
+        }
+        for (index, item) in items.iter().enumerate() {
+            display.push_str(&format!(
+                "{} [{}] {}\n",
+                if index == self.selected { "▶" } else { " " },
+                if item.done { "x" } else { " " },
+                item.text
+            ));
+        }
+        if store.is_complete(self.kind) {
+            display.push_str("\nCOMPLETE\n");
+        }
+        display.push_str("\n[Tab] Departure/Arrival  [Up/Down] Select  [Space] Check  [R] Reset  [Del] Remove");
+        display
+    }
+
+    fn handle_interaction(&mut self, interaction: SystemInteraction) -> bool {
+        match interaction {
+            SystemInteraction::Select => {
+                self.status = SystemStatus::Active;
+                true
+            }
+            SystemInteraction::Configure(key, value) => self.configure(&key, &value),
+            SystemInteraction::Toggle => self.configure("check", "selected"),
+            SystemInteraction::Reset => self.configure("reset", ""),
+        }
+    }
+
+    fn status(&self) -> SystemStatus {
+        self.status.clone()
+    }
+}
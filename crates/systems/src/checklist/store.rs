@@ -0,0 +1,306 @@
+use bevy::log::warn;
+use bevy::prelude::Resource;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// File name of the persisted checklists inside the data directory
+const STORE_FILE_NAME: &str = "checklists.json";
+
+/// Items a new departure checklist starts with
+const DEFAULT_DEPARTURE_ITEMS: [&str; 7] = [
+    "Engine oil, coolant and belts checked",
+    "Engine and heads seacocks open",
+    "Weather forecast reviewed",
+    "Passage plan and tides reviewed",
+    "Lifejackets and jackstays ready",
+    "Navigation lights tested",
+    "Shore power disconnected",
+];
+
+/// Items a new arrival checklist starts with
+const DEFAULT_ARRIVAL_ITEMS: [&str; 5] = [
+    "Engine hours and fuel logged",
+    "Seacocks closed",
+    "Instruments and radios off",
+    "Shore power connected",
+    "Lines, fenders and chafe checked",
+];
+
+/// Which end of a passage a checklist belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecklistKind {
+    Departure,
+    Arrival,
+}
+
+impl ChecklistKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChecklistKind::Departure => "PRE-DEPARTURE",
+            ChecklistKind::Arrival => "ARRIVAL",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChecklistItem {
+    pub text: String,
+    #[serde(default)]
+    pub done: bool,
+}
+
+impl ChecklistItem {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            done: false,
+        }
+    }
+}
+
+/// On-disk layout of the checklists
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredChecklists {
+    departure: Vec<ChecklistItem>,
+    arrival: Vec<ChecklistItem>,
+    /// Passages started so far
+    #[serde(default)]
+    passage: u32,
+}
+
+impl Default for StoredChecklists {
+    fn default() -> Self {
+        Self {
+            departure: DEFAULT_DEPARTURE_ITEMS.iter().map(|text| ChecklistItem::new(text)).collect(),
+            arrival: DEFAULT_ARRIVAL_ITEMS.iter().map(|text| ChecklistItem::new(text)).collect(),
+            passage: 0,
+        }
+    }
+}
+
+/// Pre-departure and arrival checklists with their completion state.
+/// Completing the departure list starts a passage and completing the arrival
+/// list ends it; both are written to the logbook.
+#[derive(Debug, Default)]
+pub struct ChecklistStore {
+    stored: StoredChecklists,
+    path: Option<PathBuf>,
+    dirty: bool,
+    /// Logbook entries not yet taken by the timeline
+    log_entries: Vec<String>,
+}
+
+impl ChecklistStore {
+    /// A store with the default items that is never written to disk
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Load the store from `path`, starting from the defaults if the file is missing or unreadable
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let stored = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable checklists {}: {}", path.display(), e);
+                StoredChecklists::default()
+            }),
+            Err(_) => StoredChecklists::default(),
+        };
+        Self {
+            stored,
+            path: Some(path),
+            ..Default::default()
+        }
+    }
+
+    /// The store at the default path, or an in-memory one where there is no data directory
+    pub fn load_default() -> Self {
+        match Self::default_path() {
+            Some(path) => Self::load(path),
+            None => Self::in_memory(),
+        }
+    }
+
+    /// `$YACHTPIT_DATA_DIR/checklists.json`, falling back to `~/.yachtpit`
+    pub fn default_path() -> Option<PathBuf> {
+        Some(crate::storage::data_dir()?.join(STORE_FILE_NAME))
+    }
+
+    pub fn items(&self, kind: ChecklistKind) -> &[ChecklistItem] {
+        match kind {
+            ChecklistKind::Departure => &self.stored.departure,
+            ChecklistKind::Arrival => &self.stored.arrival,
+        }
+    }
+
+    fn items_mut(&mut self, kind: ChecklistKind) -> &mut Vec<ChecklistItem> {
+        match kind {
+            ChecklistKind::Departure => &mut self.stored.departure,
+            ChecklistKind::Arrival => &mut self.stored.arrival,
+        }
+    }
+
+    /// Number of the current passage; 0 before the first departure
+    pub fn passage(&self) -> u32 {
+        self.stored.passage
+    }
+
+    pub fn is_complete(&self, kind: ChecklistKind) -> bool {
+        let items = self.items(kind);
+        !items.is_empty() && items.iter().all(|item| item.done)
+    }
+
+    pub fn add_item(&mut self, kind: ChecklistKind, text: &str) -> bool {
+        let text = text.trim();
+        if text.is_empty() {
+            return false;
+        }
+        self.items_mut(kind).push(ChecklistItem::new(text));
+        self.dirty = true;
+        true
+    }
+
+    pub fn remove_item(&mut self, kind: ChecklistKind, index: usize) -> bool {
+        let items = self.items_mut(kind);
+        if index >= items.len() {
+            return false;
+        }
+        items.remove(index);
+        self.dirty = true;
+        true
+    }
+
+    /// Tick or untick an item, logging the list once every item is done
+    pub fn toggle_item(&mut self, kind: ChecklistKind, index: usize) -> bool {
+        let Some(item) = self.items_mut(kind).get_mut(index) else {
+            return false;
+        };
+        item.done = !item.done;
+        self.dirty = true;
+        if self.is_complete(kind) {
+            self.complete(kind);
+        }
+        true
+    }
+
+    fn complete(&mut self, kind: ChecklistKind) {
+        if kind == ChecklistKind::Departure {
+            self.stored.passage += 1;
+        }
+        let count = self.items(kind).len();
+        self.log_entries.push(format!(
+            "Passage {}: {} checklist complete ({} items)",
+            self.stored.passage,
+            kind.label().to_lowercase(),
+            count
+        ));
+        // Arriving closes the passage; both lists start fresh for the next one
+        if kind == ChecklistKind::Arrival {
+            self.reset(ChecklistKind::Departure);
+            self.reset(ChecklistKind::Arrival);
+        }
+    }
+
+    /// Untick every item of a list
+    pub fn reset(&mut self, kind: ChecklistKind) {
+        for item in self.items_mut(kind) {
+            item.done = false;
+        }
+        self.dirty = true;
+    }
+
+    /// Logbook entries since the last call, oldest first
+    pub fn take_log_entries(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.log_entries)
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Write the store if it changed since the last save
+    pub fn save(&mut self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            self.dirty = false;
+            return Ok(());
+        };
+        if !self.dirty {
+            return Ok(());
+        }
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let contents = serde_json::to_string_pretty(&self.stored)?;
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, contents)?;
+        std::fs::rename(&temp_path, path)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+/// Shared handle to the checklists, held by the checklist page and the timeline
+#[derive(Resource, Clone)]
+pub struct Checklists(Arc<RwLock<ChecklistStore>>);
+
+impl Checklists {
+    pub fn new(store: ChecklistStore) -> Self {
+        Self(Arc::new(RwLock::new(store)))
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, ChecklistStore> {
+        self.0.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, ChecklistStore> {
+        self.0.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for Checklists {
+    fn default() -> Self {
+        Self::new(ChecklistStore::in_memory())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passage_checklists_persist_and_log() {
+        let path = std::env::temp_dir()
+            .join(format!("yachtpit-checklists-{}", std::process::id()))
+            .join(STORE_FILE_NAME);
+
+        let mut store = ChecklistStore::load(&path);
+        assert_eq!(store.items(ChecklistKind::Departure).len(), DEFAULT_DEPARTURE_ITEMS.len());
+        assert!(store.add_item(ChecklistKind::Departure, " Dinghy stowed "));
+        assert!(!store.add_item(ChecklistKind::Departure, "  "));
+        assert!(store.toggle_item(ChecklistKind::Departure, 0));
+        store.save().unwrap();
+
+        // Ticked items survive a restart
+        let mut store = ChecklistStore::load(&path);
+        assert!(store.items(ChecklistKind::Departure)[0].done);
+        assert_eq!(store.items(ChecklistKind::Departure).last().unwrap().text, "Dinghy stowed");
+        for index in 1..store.items(ChecklistKind::Departure).len() {
+            store.toggle_item(ChecklistKind::Departure, index);
+        }
+        assert!(store.is_complete(ChecklistKind::Departure));
+        assert_eq!(store.passage(), 1);
+        assert_eq!(store.take_log_entries(), ["Passage 1: pre-departure checklist complete (8 items)"]);
+
+        for index in 0..store.items(ChecklistKind::Arrival).len() {
+            store.toggle_item(ChecklistKind::Arrival, index);
+        }
+        assert_eq!(store.take_log_entries(), ["Passage 1: arrival checklist complete (5 items)"]);
+        assert!(!store.items(ChecklistKind::Departure)[0].done, "lists reset for the next passage");
+        assert!(!store.is_complete(ChecklistKind::Arrival));
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,332 @@
+//! Closest point of approach to AIS targets
+//!
+//! Own ship and every target are taken to hold their course and speed over
+//! ground. Both are carried forward from their last report to the time of
+//! the check, then the relative motion gives the time to the closest point
+//! of approach (TCPA) and the distance then (CPA). A target moving away has
+//! already passed its CPA; its TCPA is negative and its CPA is the current
+//! range. Each target gets a [`ThreatLevel`] from [`CollisionThresholds`],
+//! and every change of level is queued as a [`ThreatEvent`].
+
+use bevy::prelude::*;
+use datalink::geo::{self, destination, METERS_PER_NM};
+pub use datalink::geo::Approach;
+use datalink::DataMessage;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use crate::alarms::escalation::Alarms;
+
+/// Alarm raised while any target is a danger
+pub const COLLISION_ALARM: &str = "collision";
+
+/// Targets not heard from for this long are dropped, in seconds
+pub const TARGET_TIMEOUT_S: f64 = 360.0;
+
+/// Position, course and speed over ground at a point in time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Kinematics {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub speed_kts: f64,
+    pub course_deg: f64,
+    /// Seconds on any monotonic clock
+    pub time_s: f64,
+}
+
+impl Kinematics {
+    /// Where the vessel will be at `time_s` on its current course and speed
+    pub fn at(&self, time_s: f64) -> (f64, f64) {
+        let hours = (time_s - self.time_s) / 3600.0;
+        destination((self.latitude, self.longitude), self.course_deg, self.speed_kts * hours * METERS_PER_NM)
+    }
+
+    /// Read from a `GPS_*` or `AIS_POSITION` message; course and speed
+    /// default to zero when not reported
+    pub fn from_message(message: &DataMessage, time_s: f64) -> Option<Self> {
+        let (latitude, longitude) = message
+            .get_lat_lon("position")
+            .or_else(|| Some((message.get_f64("latitude")?, message.get_f64("longitude")?)))?;
+        Some(Self {
+            latitude,
+            longitude,
+            speed_kts: message.get_f64("speed").unwrap_or(0.0),
+            course_deg: message.get_angle("course").unwrap_or(0.0),
+            time_s,
+        })
+    }
+}
+
+/// CPA and TCPA of `target` from `own`, both carried forward to `time_s`
+pub fn closest_approach(own: &Kinematics, target: &Kinematics, time_s: f64) -> Approach {
+    geo::closest_approach(own.at(time_s), (own.speed_kts, own.course_deg), target.at(time_s), (target.speed_kts, target.course_deg))
+}
+
+/// How worried to be about a target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum ThreatLevel {
+    #[default]
+    Safe,
+    Caution,
+    Danger,
+}
+
+impl ThreatLevel {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ThreatLevel::Safe => "SAFE",
+            ThreatLevel::Caution => "CAUTION",
+            ThreatLevel::Danger => "DANGER",
+        }
+    }
+}
+
+/// CPA and TCPA limits of each threat level
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CollisionThresholds {
+    pub danger_cpa_nm: f64,
+    pub danger_tcpa_min: f64,
+    pub caution_cpa_nm: f64,
+    pub caution_tcpa_min: f64,
+}
+
+impl Default for CollisionThresholds {
+    fn default() -> Self {
+        Self {
+            danger_cpa_nm: 0.5,
+            danger_tcpa_min: 12.0,
+            caution_cpa_nm: 1.0,
+            caution_tcpa_min: 30.0,
+        }
+    }
+}
+
+impl CollisionThresholds {
+    /// Level of an approach; only closing targets can be a threat
+    pub fn classify(&self, approach: &Approach) -> ThreatLevel {
+        let within = |cpa_nm: f64, tcpa_min: f64| {
+            approach.cpa_nm <= cpa_nm && (0.0..=tcpa_min).contains(&approach.tcpa_min)
+        };
+        if within(self.danger_cpa_nm, self.danger_tcpa_min) {
+            ThreatLevel::Danger
+        } else if within(self.caution_cpa_nm, self.caution_tcpa_min) {
+            ThreatLevel::Caution
+        } else {
+            ThreatLevel::Safe
+        }
+    }
+}
+
+/// A target whose threat level changed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThreatEvent {
+    pub mmsi: u32,
+    pub previous: ThreatLevel,
+    pub level: ThreatLevel,
+    /// The approach that caused the change; `None` when the target was lost
+    pub approach: Option<Approach>,
+}
+
+/// An AIS target as last assessed
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackedTarget {
+    pub kinematics: Kinematics,
+    pub approach: Option<Approach>,
+    pub level: ThreatLevel,
+}
+
+/// Own ship and the AIS targets around it
+#[derive(Debug, Default)]
+pub struct CollisionMonitor {
+    pub thresholds: CollisionThresholds,
+    own: Option<Kinematics>,
+    targets: HashMap<u32, TrackedTarget>,
+    events: Vec<ThreatEvent>,
+}
+
+impl CollisionMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update_own(&mut self, own: Kinematics) {
+        self.own = Some(own);
+    }
+
+    pub fn update_target(&mut self, mmsi: u32, kinematics: Kinematics) {
+        match self.targets.get_mut(&mmsi) {
+            Some(target) => target.kinematics = kinematics,
+            None => {
+                self.targets.insert(mmsi, TrackedTarget { kinematics, approach: None, level: ThreatLevel::Safe });
+            }
+        }
+    }
+
+    /// Take in a datalink message: own-ship fixes from `GPS_POSITION` and
+    /// `GPS_SENTENCE`, targets from `AIS_POSITION`
+    pub fn observe(&mut self, message: &DataMessage, time_s: f64) {
+        match message.message_type.as_str() {
+            "GPS_POSITION" | "GPS_SENTENCE" => {
+                let no_fix = message.get_i64("fix_quality") == Some(0) || message.get_str("status") == Some("V");
+                // Fixes without course and speed (GGA) would stop the boat dead
+                if no_fix || message.get_f64("speed").is_none() {
+                    return;
+                }
+                if let Some(own) = Kinematics::from_message(message, time_s) {
+                    self.update_own(own);
+                }
+            }
+            "AIS_POSITION" => {
+                let mmsi = message.get_i64("mmsi").and_then(|mmsi| u32::try_from(mmsi).ok());
+                if let (Some(mmsi), Some(target)) = (mmsi, Kinematics::from_message(message, time_s)) {
+                    self.update_target(mmsi, target);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn own(&self) -> Option<&Kinematics> {
+        self.own.as_ref()
+    }
+
+    pub fn targets(&self) -> &HashMap<u32, TrackedTarget> {
+        &self.targets
+    }
+
+    /// Reassess every target at `time_s`, dropping stale ones, and queue an
+    /// event for each change of threat level
+    pub fn evaluate(&mut self, time_s: f64) {
+        let stale: Vec<u32> = self
+            .targets
+            .iter()
+            .filter(|(_, target)| time_s - target.kinematics.time_s > TARGET_TIMEOUT_S)
+            .map(|(mmsi, _)| *mmsi)
+            .collect();
+        for mmsi in stale {
+            if let Some(target) = self.targets.remove(&mmsi) {
+                if target.level != ThreatLevel::Safe {
+                    self.events.push(ThreatEvent { mmsi, previous: target.level, level: ThreatLevel::Safe, approach: None });
+                }
+            }
+        }
+
+        let Some(own) = self.own else {
+            return;
+        };
+        for (mmsi, target) in self.targets.iter_mut() {
+            let approach = closest_approach(&own, &target.kinematics, time_s);
+            let level = self.thresholds.classify(&approach);
+            if level != target.level {
+                self.events.push(ThreatEvent { mmsi: *mmsi, previous: target.level, level, approach: Some(approach) });
+            }
+            target.approach = Some(approach);
+            target.level = level;
+        }
+    }
+
+    /// Highest threat level among the targets
+    pub fn highest_level(&self) -> ThreatLevel {
+        self.targets.values().map(|target| target.level).max().unwrap_or_default()
+    }
+
+    /// Events queued since the last call
+    pub fn take_events(&mut self) -> Vec<ThreatEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+/// Shared collision monitor, fed by the data feeds and the AIS page
+#[derive(Resource, Clone, Default)]
+pub struct CollisionWatch(Arc<RwLock<CollisionMonitor>>);
+
+impl CollisionWatch {
+    pub fn read(&self) -> RwLockReadGuard<'_, CollisionMonitor> {
+        self.0.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, CollisionMonitor> {
+        self.0.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Reassess the targets, log threat changes and raise the collision alarm
+pub fn update_collision_watch(watch: Res<CollisionWatch>, alarms: Res<Alarms>, time: Res<Time>) {
+    let now = time.elapsed_secs_f64();
+    let mut monitor = watch.write();
+    monitor.evaluate(now);
+    for event in monitor.take_events() {
+        match event.approach {
+            Some(approach) => info!(
+                "AIS target {} {} -> {}: CPA {:.2} NM in {:.1} min",
+                event.mmsi,
+                event.previous.label(),
+                event.level.label(),
+                approach.cpa_nm,
+                approach.tcpa_min
+            ),
+            None => info!("AIS target {} lost while {}", event.mmsi, event.previous.label()),
+        }
+    }
+    alarms.write().set_condition(COLLISION_ALARM, monitor.highest_level() == ThreatLevel::Danger, now);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vessel(latitude: f64, longitude: f64, speed_kts: f64, course_deg: f64) -> Kinematics {
+        Kinematics { latitude, longitude, speed_kts, course_deg, time_s: 0.0 }
+    }
+
+    #[test]
+    fn test_crossing_target_approach() {
+        // Own ship north at 10 kts; target 2 NM east, heading west at 10 kts
+        let own = vessel(50.0, -1.0, 10.0, 0.0);
+        let east = destination((50.0, -1.0), 90.0, 2.0 * METERS_PER_NM);
+        let target = vessel(east.0, east.1, 10.0, 270.0);
+        let approach = closest_approach(&own, &target, 0.0);
+        // Spherical placement against the ellipsoidal plane: within a few tenths of a percent
+        assert!((approach.range_nm - 2.0).abs() < 0.01);
+        // Relative motion is south-west at 14.1 kts: closest after 6 minutes at √2 NM
+        assert!((approach.tcpa_min - 6.0).abs() < 0.05);
+        assert!((approach.cpa_nm - 2f64.sqrt()).abs() < 0.01);
+
+        // Opening target: CPA has passed
+        let opening = vessel(east.0, east.1, 10.0, 90.0);
+        let passed = closest_approach(&own, &opening, 0.0);
+        assert!(passed.tcpa_min < 0.0);
+        assert!((passed.cpa_nm - passed.range_nm).abs() < 1e-9);
+
+        // Same course and speed: no relative motion
+        let convoy = closest_approach(&own, &vessel(east.0, east.1, 10.0, 0.0), 0.0);
+        assert_eq!(convoy.tcpa_min, 0.0);
+    }
+
+    #[test]
+    fn test_threat_events_on_level_changes() {
+        let mut monitor = CollisionMonitor::new();
+        // Target 3 NM ahead, coming straight at own ship: closing at 20 kts
+        let ahead = destination((50.0, -1.0), 0.0, 3.0 * METERS_PER_NM);
+        monitor.update_target(235000002, vessel(ahead.0, ahead.1, 10.0, 180.0));
+        monitor.evaluate(0.0);
+        assert!(monitor.take_events().is_empty(), "nothing is assessed without own ship");
+
+        monitor.update_own(vessel(50.0, -1.0, 10.0, 0.0));
+        monitor.evaluate(0.0);
+        let events = monitor.take_events();
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].previous, events[0].level), (ThreatLevel::Safe, ThreatLevel::Danger));
+        assert!((events[0].approach.unwrap().tcpa_min - 9.0).abs() < 0.05);
+        assert_eq!(monitor.highest_level(), ThreatLevel::Danger);
+
+        // Same picture a second later: no new event
+        monitor.evaluate(1.0);
+        assert!(monitor.take_events().is_empty());
+
+        // Silence drops the target
+        monitor.evaluate(TARGET_TIMEOUT_S + 1.0);
+        let events = monitor.take_events();
+        assert_eq!(events, vec![ThreatEvent { mmsi: 235000002, previous: ThreatLevel::Danger, level: ThreatLevel::Safe, approach: None }]);
+        assert!(monitor.targets().is_empty());
+    }
+}
@@ -0,0 +1,2 @@
+pub(crate) mod signals;
+pub(crate) mod nav_lights_system;
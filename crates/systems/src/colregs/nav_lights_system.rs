@@ -0,0 +1,146 @@
+use bevy::prelude::Time;
+use components::VesselData;
+use super::signals::{ColregsAdvisor, NavState, Propulsion};
+use crate::{SystemInteraction, SystemStatus, VesselSystem};
+
+/// Length adjustment per `length_step`, in meters
+const LENGTH_STEP_M: f32 = 1.0;
+
+/// Navigation lights and fog signal advisor page
+pub struct NavLightsSystem {
+    status: SystemStatus,
+    advisor: ColregsAdvisor,
+}
+
+impl NavLightsSystem {
+    /// Create the advisor page editing the shared vessel profile
+    pub fn new(advisor: ColregsAdvisor) -> Self {
+        Self {
+            status: SystemStatus::Active,
+            advisor,
+        }
+    }
+
+    fn configure(&mut self, key: &str, value: &str) -> bool {
+        let mut state = self.advisor.write();
+        match key {
+            "propulsion" => {
+                state.profile.propulsion = match value {
+                    "power" => Propulsion::PowerDriven,
+                    "sail" => Propulsion::Sailing,
+                    "toggle" => match state.profile.propulsion {
+                        Propulsion::PowerDriven => Propulsion::Sailing,
+                        Propulsion::Sailing => Propulsion::PowerDriven,
+                    },
+                    _ => return false,
+                };
+                true
+            }
+            "state" => {
+                let count = NavState::ALL.len();
+                let current = NavState::ALL.iter().position(|nav| *nav == state.profile.state).unwrap_or(0);
+                state.profile.state = match value {
+                    "next" => NavState::ALL[(current + 1) % count],
+                    "previous" => NavState::ALL[(current + count - 1) % count],
+                    "under_way" => NavState::UnderWay,
+                    "stopped" => NavState::Stopped,
+                    "anchored" => NavState::AtAnchor,
+                    "restricted" => NavState::Restricted,
+                    _ => return false,
+                };
+                true
+            }
+            "length_step" => {
+                let step = if value == "up" { LENGTH_STEP_M } else { -LENGTH_STEP_M };
+                state.profile.length_m = (state.profile.length_m + step).max(LENGTH_STEP_M);
+                true
+            }
+            "length" => match value.parse::<f32>() {
+                Ok(length) if length.is_finite() && length > 0.0 => {
+                    state.profile.length_m = length;
+                    true
+                }
+                _ => false,
+            },
+            "restricted_visibility" => {
+                state.restricted_visibility = match value {
+                    "on" => true,
+                    "off" => false,
+                    "toggle" => !state.restricted_visibility,
+                    _ => return false,
+                };
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl VesselSystem for NavLightsSystem {
+    fn id(&self) -> &'static str {
+        "nav_lights"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Nav Lights & Signals"
+    }
+
+    fn update(&mut self, _yacht_data: &VesselData, _time: &Time) {}
+
+    fn render_display(&self, _yacht_data: &VesselData) -> String {
+        let state = self.advisor.read();
+        let profile = &state.profile;
+        let (lights, notes) = profile.lights();
+        let signal = profile.sound_signal();
+
+        let mut display = format!(
+            "NAV LIGHTS & SOUND SIGNALS\n\n\
+            Vessel: {} {:.0} m\n\
+            State: {}\n\
+            \n\
+            LIGHTS (SUNSET TO SUNRISE)\n",
+            profile.propulsion.label(),
+            profile.length_m,
+            profile.state.label()
+        );
+        for light in &lights {
+            display.push_str(&format!("  {}\n", light));
+        }
+        for note in &notes {
+            display.push_str(&format!("  * {}\n", note));
+        }
+
+        display.push_str(&format!(
+            "\nRESTRICTED VISIBILITY: {}\n\
+            Signal: {}\n",
+            if state.restricted_visibility { "SOUNDING" } else { "OFF" },
+            signal.description
+        ));
+        if !signal.blasts.is_empty() {
+            display.push_str(&format!("Pattern: {}\n", signal.pattern()));
+        }
+        display.push_str(&format!("Every {:.0} s at most\n", signal.interval_s));
+
+        display.push_str("\n[P] Power/Sail  [Up/Down] State  [+/-] Length  [F] Fog signals");
+        display
+    }
+
+    fn handle_interaction(&mut self, interaction: SystemInteraction) -> bool {
+        match interaction {
+            SystemInteraction::Select => {
+                self.status = SystemStatus::Active;
+                true
+            }
+            SystemInteraction::Configure(key, value) => self.configure(&key, &value),
+            SystemInteraction::Toggle => self.configure("restricted_visibility", "toggle"),
+            SystemInteraction::Reset => {
+                *self.advisor.write() = Default::default();
+                true
+            }
+        }
+    }
+
+    fn status(&self) -> SystemStatus {
+        self.status.clone()
+    }
+}
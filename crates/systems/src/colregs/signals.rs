@@ -0,0 +1,265 @@
+//! Navigation lights and restricted-visibility sound signals (COLREGS Part C and D)
+//!
+//! Covers the common cases for a yacht: a power-driven or sailing vessel under
+//! way, stopped, at anchor, or restricted in her ability to manoeuvre. Where
+//! the rules allow a small-vessel alternative (an all-round white under 12 m,
+//! a masthead tricolour under 20 m) it is listed as a note rather than
+//! replacing the standard lights.
+
+use bevy::prelude::Resource;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Length at which a second masthead or anchor light becomes mandatory
+const TWO_LIGHT_LENGTH_M: f32 = 50.0;
+
+/// How the vessel is propelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Propulsion {
+    PowerDriven,
+    Sailing,
+}
+
+impl Propulsion {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Propulsion::PowerDriven => "POWER",
+            Propulsion::Sailing => "SAIL",
+        }
+    }
+}
+
+/// What the vessel is doing, as far as the lights and signals care
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavState {
+    /// Under way and making way through the water
+    UnderWay,
+    /// Under way but stopped
+    Stopped,
+    AtAnchor,
+    /// Restricted in her ability to manoeuvre, e.g. diving operations
+    Restricted,
+}
+
+impl NavState {
+    pub const ALL: [NavState; 4] = [NavState::UnderWay, NavState::Stopped, NavState::AtAnchor, NavState::Restricted];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NavState::UnderWay => "UNDER WAY",
+            NavState::Stopped => "STOPPED",
+            NavState::AtAnchor => "AT ANCHOR",
+            NavState::Restricted => "RESTRICTED (RAM)",
+        }
+    }
+}
+
+/// Length of a horn blast
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Blast {
+    /// About one second
+    Short,
+    /// Four to six seconds
+    Prolonged,
+}
+
+impl Blast {
+    pub fn duration_s(&self) -> f64 {
+        match self {
+            Blast::Short => 1.0,
+            Blast::Prolonged => 5.0,
+        }
+    }
+
+    fn symbol(&self) -> char {
+        match self {
+            Blast::Short => '•',
+            Blast::Prolonged => '—',
+        }
+    }
+}
+
+/// Silence between blasts of one signal
+const BLAST_GAP_S: f64 = 1.0;
+
+/// A sound signal for restricted visibility
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoundSignal {
+    pub description: &'static str,
+    /// Horn blasts in one round; empty for bell signals
+    pub blasts: Vec<Blast>,
+    /// Longest allowed time between rounds
+    pub interval_s: f64,
+}
+
+impl SoundSignal {
+    /// Blast pattern, e.g. `— • •`
+    pub fn pattern(&self) -> String {
+        self.blasts.iter().map(|blast| blast.symbol().to_string()).collect::<Vec<_>>().join(" ")
+    }
+
+    /// Whether the horn sounds `elapsed_s` after the signal started
+    pub fn horn_on(&self, elapsed_s: f64) -> bool {
+        let mut phase = elapsed_s.max(0.0) % self.interval_s;
+        for blast in &self.blasts {
+            if phase < blast.duration_s() {
+                return true;
+            }
+            phase -= blast.duration_s() + BLAST_GAP_S;
+            if phase < 0.0 {
+                return false;
+            }
+        }
+        false
+    }
+}
+
+/// Vessel particulars and state the advice is based on
+#[derive(Debug, Clone, PartialEq)]
+pub struct VesselProfile {
+    pub propulsion: Propulsion,
+    pub length_m: f32,
+    pub state: NavState,
+}
+
+impl Default for VesselProfile {
+    fn default() -> Self {
+        Self {
+            propulsion: Propulsion::Sailing,
+            length_m: 12.0,
+            state: NavState::UnderWay,
+        }
+    }
+}
+
+impl VesselProfile {
+    /// Lights required between sunset and sunrise, then any permitted alternatives
+    pub fn lights(&self) -> (Vec<&'static str>, Vec<&'static str>) {
+        let long = self.length_m >= TWO_LIGHT_LENGTH_M;
+        let mut lights = Vec::new();
+        let mut notes = Vec::new();
+
+        let under_way = |lights: &mut Vec<&'static str>, notes: &mut Vec<&'static str>| {
+            if self.propulsion == Propulsion::PowerDriven || self.state == NavState::Restricted {
+                lights.push("Masthead light (white, 225°)");
+                if long {
+                    lights.push("Second masthead light aft, higher");
+                }
+            }
+            lights.push("Sidelights (red port, green starboard)");
+            lights.push("Sternlight (white, 135°)");
+            if self.propulsion == Propulsion::PowerDriven && self.length_m < 12.0 {
+                notes.push("Under 12 m: all-round white plus sidelights may replace masthead and sternlight");
+            }
+            if self.propulsion == Propulsion::Sailing && self.state != NavState::Restricted {
+                if self.length_m < 20.0 {
+                    notes.push("Under 20 m: sidelights and sternlight may be combined in a masthead tricolour");
+                }
+                notes.push("When motoring, show power-driven lights and a cone point down by day");
+            }
+        };
+
+        match self.state {
+            NavState::UnderWay | NavState::Stopped => under_way(&mut lights, &mut notes),
+            NavState::AtAnchor => {
+                lights.push("All-round white forward");
+                if long {
+                    lights.push("All-round white aft, lower");
+                } else {
+                    notes.push("Under 50 m: a single all-round white where best seen is enough");
+                }
+                notes.push("By day: one ball forward");
+            }
+            NavState::Restricted => {
+                lights.push("All-round red-white-red, vertical");
+                under_way(&mut lights, &mut notes);
+                notes.push("Masthead, side and sternlights only when making way");
+                notes.push("By day: ball-diamond-ball");
+            }
+        }
+        (lights, notes)
+    }
+
+    /// Signal to sound in or near an area of restricted visibility
+    pub fn sound_signal(&self) -> SoundSignal {
+        match (self.state, self.propulsion) {
+            (NavState::AtAnchor, _) => SoundSignal {
+                description: "Rapid bell for 5 s; may add short-prolonged-short on the horn",
+                blasts: Vec::new(),
+                interval_s: 60.0,
+            },
+            (NavState::Restricted, _) | (_, Propulsion::Sailing) => SoundSignal {
+                description: "One prolonged and two short blasts",
+                blasts: vec![Blast::Prolonged, Blast::Short, Blast::Short],
+                interval_s: 120.0,
+            },
+            (NavState::UnderWay, Propulsion::PowerDriven) => SoundSignal {
+                description: "One prolonged blast",
+                blasts: vec![Blast::Prolonged],
+                interval_s: 120.0,
+            },
+            (NavState::Stopped, Propulsion::PowerDriven) => SoundSignal {
+                description: "Two prolonged blasts",
+                blasts: vec![Blast::Prolonged, Blast::Prolonged],
+                interval_s: 120.0,
+            },
+        }
+    }
+}
+
+/// Vessel profile and whether restricted-visibility signals are being sounded
+#[derive(Debug, Default)]
+pub struct ColregsState {
+    pub profile: VesselProfile,
+    pub restricted_visibility: bool,
+}
+
+/// Shared handle to the advisor state, edited from the nav lights page and
+/// read by the fog horn output
+#[derive(Resource, Clone, Default)]
+pub struct ColregsAdvisor(Arc<RwLock<ColregsState>>);
+
+impl ColregsAdvisor {
+    pub fn read(&self) -> RwLockReadGuard<'_, ColregsState> {
+        self.0.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, ColregsState> {
+        self.0.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lights_and_signals_follow_state() {
+        let mut profile = VesselProfile::default();
+        let (lights, notes) = profile.lights();
+        assert_eq!(lights.len(), 2, "sailing: sidelights and sternlight");
+        assert!(notes.iter().any(|note| note.contains("tricolour")));
+        assert_eq!(profile.sound_signal().pattern(), "— • •");
+
+        profile.propulsion = Propulsion::PowerDriven;
+        profile.state = NavState::Stopped;
+        assert_eq!(profile.lights().0.len(), 3);
+        let signal = profile.sound_signal();
+        assert_eq!(signal.pattern(), "— —");
+        assert!(signal.horn_on(0.0));
+        assert!(!signal.horn_on(5.5));
+        assert!(signal.horn_on(6.5));
+        assert!(!signal.horn_on(11.5));
+        assert!(signal.horn_on(120.5), "next round after the interval");
+
+        profile.state = NavState::AtAnchor;
+        profile.length_m = 60.0;
+        assert_eq!(profile.lights().0.len(), 2);
+        assert!(profile.sound_signal().blasts.is_empty());
+        assert!(!profile.sound_signal().horn_on(0.0));
+
+        profile.state = NavState::Restricted;
+        let (lights, _) = profile.lights();
+        assert_eq!(lights[0], "All-round red-white-red, vertical");
+        assert_eq!(lights.len(), 5);
+    }
+}
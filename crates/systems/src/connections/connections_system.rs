@@ -0,0 +1,152 @@
+use bevy::prelude::Time;
+use components::VesselData;
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::log::warn;
+#[cfg(not(target_arch = "wasm32"))]
+use datalink_provider::{list_serial_ports, SerialPortEntry};
+use super::services::BackendServices;
+use crate::{SystemInteraction, SystemStatus, VesselSystem};
+
+/// Connection manager page listing backend services and retrying failed ones
+pub struct ConnectionsSystem {
+    status: SystemStatus,
+    services: BackendServices,
+    selected: usize,
+    /// Ports found by the last scan
+    #[cfg(not(target_arch = "wasm32"))]
+    serial_ports: Vec<SerialPortEntry>,
+}
+
+impl ConnectionsSystem {
+    /// Create the connections page for the shared backend services
+    pub fn new(services: BackendServices) -> Self {
+        Self {
+            status: SystemStatus::Active,
+            services,
+            selected: 0,
+            #[cfg(not(target_arch = "wasm32"))]
+            serial_ports: Vec::new(),
+        }
+    }
+
+    /// Enumerate the serial ports again
+    fn scan_serial_ports(&mut self) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            match list_serial_ports() {
+                Ok(ports) => {
+                    self.serial_ports = ports;
+                    true
+                }
+                Err(e) => {
+                    warn!("Serial port scan failed: {e}");
+                    false
+                }
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            false
+        }
+    }
+
+    fn configure(&mut self, key: &str, value: &str) -> bool {
+        if key == "scan_ports" {
+            return self.scan_serial_ports();
+        }
+        let mut registry = self.services.write();
+        let count = registry.services().len();
+        match key {
+            "select" if count > 0 => {
+                self.selected = match value {
+                    "next" => (self.selected + 1) % count,
+                    "previous" => (self.selected + count - 1) % count,
+                    _ => return false,
+                };
+                true
+            }
+            // A service id, or "selected"
+            "retry" => {
+                let id = match value {
+                    "selected" => match registry.services().get(self.selected) {
+                        Some(service) => service.id.clone(),
+                        None => return false,
+                    },
+                    id => id.to_string(),
+                };
+                registry.request_retry(&id)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl VesselSystem for ConnectionsSystem {
+    fn id(&self) -> &'static str {
+        "connections"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Connections"
+    }
+
+    fn update(&mut self, _yacht_data: &VesselData, _time: &Time) {}
+
+    fn render_display(&self, _yacht_data: &VesselData) -> String {
+        let registry = self.services.read();
+
+        let mut display = String::from("CONNECTIONS\n\nBACKEND SERVICES\n");
+        if registry.services().is_empty() {
+            display.push_str("No backend services\n");
+        }
+        for (index, service) in registry.services().iter().enumerate() {
+            display.push_str(&format!(
+                "{} {:<12} {} (attempts: {})\n",
+                if index == self.selected { "▶" } else { " " },
+                service.label,
+                service.state.label(),
+                service.attempts
+            ));
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            display.push_str("\nSERIAL PORTS\n");
+            if self.serial_ports.is_empty() {
+                display.push_str("No serial ports found\n");
+            }
+            for port in &self.serial_ports {
+                let usb_id = match (port.vid, port.pid) {
+                    (Some(vid), Some(pid)) => format!("{:04x}:{:04x}", vid, pid),
+                    _ => "-".to_string(),
+                };
+                display.push_str(&format!(
+                    "  {:<16} {:<7} {:<9} {}\n",
+                    port.name,
+                    port.class.label(),
+                    usb_id,
+                    port.product.as_deref().unwrap_or_default()
+                ));
+            }
+        }
+
+        display.push_str("\n[Up/Down] Select  [R] Retry  [P] Scan ports");
+        display
+    }
+
+    fn handle_interaction(&mut self, interaction: SystemInteraction) -> bool {
+        match interaction {
+            SystemInteraction::Select => {
+                self.status = SystemStatus::Active;
+                self.scan_serial_ports();
+                true
+            }
+            SystemInteraction::Configure(key, value) => self.configure(&key, &value),
+            SystemInteraction::Reset | SystemInteraction::Toggle => false,
+        }
+    }
+
+    fn status(&self) -> SystemStatus {
+        self.status.clone()
+    }
+}
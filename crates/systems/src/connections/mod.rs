@@ -0,0 +1,2 @@
+pub(crate) mod services;
+pub(crate) mod connections_system;
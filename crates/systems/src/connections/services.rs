@@ -0,0 +1,121 @@
+use bevy::prelude::Resource;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Run state of an optional backend service
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServiceState {
+    Starting,
+    Running,
+    /// Could not be started, e.g. the executable is missing
+    Unavailable(String),
+    /// Was running and stopped
+    Exited(String),
+}
+
+impl ServiceState {
+    pub fn label(&self) -> String {
+        match self {
+            ServiceState::Starting => "STARTING".to_string(),
+            ServiceState::Running => "RUNNING".to_string(),
+            ServiceState::Unavailable(reason) => format!("UNAVAILABLE ({})", reason),
+            ServiceState::Exited(reason) => format!("EXITED ({})", reason),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self, ServiceState::Starting | ServiceState::Running)
+    }
+}
+
+/// A backend service the app can run without, such as the AIS server
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackendService {
+    pub id: String,
+    pub label: String,
+    pub state: ServiceState,
+    /// Start attempts so far
+    pub attempts: u32,
+    retry_requested: bool,
+}
+
+/// Backend services known to the connection manager
+#[derive(Debug, Default)]
+pub struct ServiceRegistry {
+    services: Vec<BackendService>,
+}
+
+impl ServiceRegistry {
+    /// Add a service, or return the existing one with the same id
+    pub fn register(&mut self, id: &str, label: &str) -> &mut BackendService {
+        let index = match self.services.iter().position(|service| service.id == id) {
+            Some(index) => index,
+            None => {
+                self.services.push(BackendService {
+                    id: id.to_string(),
+                    label: label.to_string(),
+                    state: ServiceState::Starting,
+                    attempts: 0,
+                    retry_requested: false,
+                });
+                self.services.len() - 1
+            }
+        };
+        &mut self.services[index]
+    }
+
+    pub fn get(&self, id: &str) -> Option<&BackendService> {
+        self.services.iter().find(|service| service.id == id)
+    }
+
+    pub fn services(&self) -> &[BackendService] {
+        &self.services
+    }
+
+    /// Record a start attempt and its outcome
+    pub fn set_state(&mut self, id: &str, state: ServiceState) -> bool {
+        match self.services.iter_mut().find(|service| service.id == id) {
+            Some(service) => {
+                if state == ServiceState::Starting {
+                    service.attempts += 1;
+                }
+                service.state = state;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Ask the owner of a stopped service to start it again
+    pub fn request_retry(&mut self, id: &str) -> bool {
+        match self.services.iter_mut().find(|service| service.id == id) {
+            Some(service) if !service.state.is_running() => {
+                service.retry_requested = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether a retry was requested since the last call
+    pub fn take_retry(&mut self, id: &str) -> bool {
+        self.services
+            .iter_mut()
+            .find(|service| service.id == id)
+            .is_some_and(|service| std::mem::take(&mut service.retry_requested))
+    }
+}
+
+/// Shared handle to the backend services, held by the services themselves and
+/// the connections page
+#[derive(Resource, Clone, Default)]
+pub struct BackendServices(Arc<RwLock<ServiceRegistry>>);
+
+impl BackendServices {
+    pub fn read(&self) -> RwLockReadGuard<'_, ServiceRegistry> {
+        self.0.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, ServiceRegistry> {
+        self.0.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
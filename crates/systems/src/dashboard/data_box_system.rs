@@ -0,0 +1,180 @@
+use bevy::prelude::Time;
+use components::{DataBoxConfig, DataBoxes, DataField, VesselData, MAX_DECIMALS};
+use crate::{SystemInteraction, SystemStatus, VesselSystem};
+
+/// Editor for the data boxes shown on the dashboard
+pub struct DataBoxSystem {
+    status: SystemStatus,
+    boxes: DataBoxes,
+    /// Index of the box being edited
+    selected: usize,
+}
+
+impl DataBoxSystem {
+    /// Create the data box page editing the shared dashboard layout
+    pub fn new(boxes: DataBoxes) -> Self {
+        Self {
+            status: SystemStatus::Active,
+            boxes,
+            selected: 0,
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    fn configure(&mut self, key: &str, value: &str) -> bool {
+        let mut layout = self.boxes.write();
+        let count = layout.boxes.len();
+        if key == "add" {
+            let field = if value.is_empty() {
+                layout.boxes.get(self.selected).map_or(DataField::Speed, |config| config.field.next())
+            } else {
+                match DataField::parse(value) {
+                    Some(field) => field,
+                    None => return false,
+                }
+            };
+            layout.edit(|boxes| boxes.push(DataBoxConfig::new(field)));
+            self.selected = count;
+            return true;
+        }
+        if key == "select" {
+            if count == 0 {
+                return false;
+            }
+            self.selected = match value {
+                "next" => (self.selected + 1) % count,
+                "previous" => (self.selected + count - 1) % count,
+                index => match index.parse::<usize>() {
+                    Ok(index) if index < count => index,
+                    _ => return false,
+                },
+            };
+            return true;
+        }
+        if self.selected >= count {
+            return false;
+        }
+
+        let selected = self.selected;
+        match key {
+            "field" => {
+                let field = match value {
+                    "next" => layout.boxes[selected].field.next(),
+                    name => match DataField::parse(name) {
+                        Some(field) => field,
+                        None => return false,
+                    },
+                };
+                layout.edit(|boxes| boxes[selected].set_field(field));
+                true
+            }
+            "unit" => {
+                layout.edit(|boxes| boxes[selected].next_unit());
+                true
+            }
+            "decimals" => {
+                let decimals = layout.boxes[selected].decimals;
+                let decimals = match value {
+                    "up" if decimals < MAX_DECIMALS => decimals + 1,
+                    "down" if decimals > 0 => decimals - 1,
+                    _ => return false,
+                };
+                layout.edit(|boxes| boxes[selected].decimals = decimals);
+                true
+            }
+            "label" => {
+                let label = if value.trim().is_empty() {
+                    layout.boxes[selected].field.default_label().to_string()
+                } else {
+                    value.trim().to_uppercase()
+                };
+                layout.edit(|boxes| boxes[selected].label = label);
+                true
+            }
+            "move" => {
+                let offset = match value {
+                    "left" => -1,
+                    "right" => 1,
+                    _ => return false,
+                };
+                match layout.move_box(selected, offset) {
+                    Some(index) => {
+                        self.selected = index;
+                        true
+                    }
+                    None => false,
+                }
+            }
+            "remove" => {
+                layout.edit(|boxes| boxes.remove(selected));
+                self.selected = selected.min(count.saturating_sub(2));
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl VesselSystem for DataBoxSystem {
+    fn id(&self) -> &'static str {
+        "data_boxes"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Data Boxes"
+    }
+
+    fn update(&mut self, _yacht_data: &VesselData, _time: &Time) {}
+
+    fn render_display(&self, yacht_data: &VesselData) -> String {
+        let layout = self.boxes.read();
+
+        let mut display = String::from("DATA BOXES\n\n");
+        if layout.boxes.is_empty() {
+            display.push_str("No data boxes, press [A] to add one\n");
+        }
+        for (index, config) in layout.boxes.iter().enumerate() {
+            display.push_str(&format!(
+                "{} {}. {:<10} {:<12} {} {} ({} decimals)\n",
+                if index == self.selected { "▶" } else { " " },
+                index + 1,
+                config.label,
+                config.field.name(),
+                config.format_value(yacht_data),
+                config.unit.label(),
+                config.decimals
+            ));
+        }
+        display.push_str(
+            "\n[A] Add  [Up/Down] Select  [F] Field  [U] Unit  [ [ / ] ] Decimals\n\
+            [Left/Right] Move  [Del] Remove",
+        );
+        display
+    }
+
+    fn handle_interaction(&mut self, interaction: SystemInteraction) -> bool {
+        match interaction {
+            SystemInteraction::Select => {
+                self.status = SystemStatus::Active;
+                true
+            }
+            SystemInteraction::Configure(key, value) => self.configure(&key, &value),
+            SystemInteraction::Reset => {
+                let mut layout = self.boxes.write();
+                let revision = layout.revision;
+                *layout = Default::default();
+                layout.revision = revision + 1;
+                self.selected = 0;
+                true
+            }
+            SystemInteraction::Toggle => false,
+        }
+    }
+
+    fn status(&self) -> SystemStatus {
+        self.status.clone()
+    }
+}
@@ -0,0 +1,3 @@
+pub(crate) mod data_box_system;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod panel_templates;
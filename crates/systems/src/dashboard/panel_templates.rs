@@ -0,0 +1,47 @@
+use bevy::prelude::*;
+use components::{spawn_panel_from_file, InstrumentCluster};
+use std::path::PathBuf;
+
+/// Environment variable naming a RON panel template to show below the
+/// instrument cluster
+pub const PANEL_TEMPLATE_ENV: &str = "YACHTPIT_PANEL_TEMPLATE";
+
+/// Panel template configured for this run
+#[derive(Resource, Debug, Default)]
+pub struct PanelTemplateSource {
+    path: Option<PathBuf>,
+    spawned: bool,
+}
+
+impl PanelTemplateSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: Some(path.into()),
+            spawned: false,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        std::env::var_os(PANEL_TEMPLATE_ENV).map(Self::new).unwrap_or_default()
+    }
+}
+
+/// Spawn the configured template into the instrument cluster once it exists.
+/// A template that fails to load is reported once and not retried; fix the
+/// file and restart, or edit a loaded one to have it hot-reloaded.
+pub fn spawn_configured_panel(
+    mut commands: Commands,
+    mut source: ResMut<PanelTemplateSource>,
+    clusters: Query<Entity, With<InstrumentCluster>>,
+) {
+    if source.spawned {
+        return;
+    }
+    let (Some(path), Ok(cluster)) = (source.path.clone(), clusters.single()) else {
+        return;
+    };
+    source.spawned = true;
+    if let Err(e) = spawn_panel_from_file(&mut commands, cluster, path) {
+        warn!("{}", e);
+    }
+}
@@ -0,0 +1,103 @@
+use bevy::prelude::Time;
+use components::VesselData;
+use crate::ingest::link_diagnostics::LinkDiagnostics;
+use crate::{SystemInteraction, SystemStatus, VesselSystem};
+
+/// Latency above which a link is flagged, in milliseconds
+const HIGH_LATENCY_MS: f64 = 1000.0;
+
+/// Jitter above which a link is flagged, in milliseconds
+const HIGH_JITTER_MS: f64 = 250.0;
+
+/// Diagnostics page showing latency and jitter per datalink
+pub struct DiagnosticsSystem {
+    status: SystemStatus,
+    links: LinkDiagnostics,
+}
+
+impl DiagnosticsSystem {
+    /// Create the diagnostics page reading the shared link latencies
+    pub fn new(links: LinkDiagnostics) -> Self {
+        Self {
+            status: SystemStatus::Active,
+            links,
+        }
+    }
+
+    fn format_ms(value: Option<f64>) -> String {
+        value.map_or_else(|| "--".to_string(), |value| format!("{:.0}", value))
+    }
+}
+
+impl VesselSystem for DiagnosticsSystem {
+    fn id(&self) -> &'static str {
+        "diagnostics"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Diagnostics"
+    }
+
+    fn update(&mut self, _yacht_data: &VesselData, _time: &Time) {}
+
+    fn render_display(&self, _yacht_data: &VesselData) -> String {
+        let latencies = self.links.read();
+
+        let mut display = String::from("DATALINK DIAGNOSTICS\n\n");
+        display.push_str(&format!(
+            "{:<12} {:>7} {:>7} {:>15} {:>8}\n",
+            "LINK", "MSGS", "TIMED", "LAT AVG/MIN/MAX", "JITTER"
+        ));
+        let mut warnings = Vec::new();
+        for (link, stats) in latencies.all_stats() {
+            let latency = format!(
+                "{}/{}/{}",
+                Self::format_ms(stats.latency_mean_ms),
+                Self::format_ms(stats.latency_min_ms),
+                Self::format_ms(stats.latency_max_ms)
+            );
+            display.push_str(&format!(
+                "{:<12} {:>7} {:>7} {:>15} {:>8}\n",
+                link,
+                stats.messages,
+                stats.timestamped,
+                latency,
+                Self::format_ms(stats.jitter_ms)
+            ));
+            if stats.latency_mean_ms.is_some_and(|latency| latency > HIGH_LATENCY_MS) {
+                warnings.push(format!("{}: HIGH LATENCY", link));
+            }
+            if stats.jitter_ms.is_some_and(|jitter| jitter > HIGH_JITTER_MS) {
+                warnings.push(format!("{}: HIGH JITTER", link));
+            }
+        }
+        if latencies.all_stats().next().is_none() {
+            display.push_str("No datalinks connected\n");
+        }
+        if !warnings.is_empty() {
+            display.push('\n');
+            display.push_str(&warnings.join("\n"));
+            display.push('\n');
+        }
+        display.push_str("\nTimes in ms. Latency needs device timestamps (e.g. GPS RMC/ZDA).");
+        display
+    }
+
+    fn handle_interaction(&mut self, interaction: SystemInteraction) -> bool {
+        match interaction {
+            SystemInteraction::Select => {
+                self.status = SystemStatus::Active;
+                true
+            }
+            SystemInteraction::Reset => {
+                self.links.write().reset();
+                true
+            }
+            SystemInteraction::Configure(_, _) | SystemInteraction::Toggle => false,
+        }
+    }
+
+    fn status(&self) -> SystemStatus {
+        self.status.clone()
+    }
+}
@@ -0,0 +1 @@
+pub(crate) mod diagnostics_system;
@@ -0,0 +1,251 @@
+//! Branding packs
+//!
+//! Charter fleets and boat builders shipping yachtpit preinstalled drop a
+//! pack into the config directory: a `branding.json` naming the vessel, the
+//! accent colors and a logo file next to it. The accent replaces the theme's
+//! primary cyan on the dashboard, the vessel name is shown above the
+//! instruments and the logo on the menu and next to the name.
+//!
+//! ```json
+//! { "vessel_name": "Sea Breeze", "logo": "logo.png", "accent": "#d4a017", "accent_secondary": "#1f4e79" }
+//! ```
+
+use bevy::asset::RenderAssetUsages;
+use bevy::image::{CompressedImageFormats, ImageSampler, ImageType};
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use components::{InstrumentCluster, BORDER_COLOR_PRIMARY, BORDER_COLOR_SECONDARY, FONT_SIZE_NORMAL, TEXT_COLOR_PRIMARY};
+use crate::storage::data_dir;
+
+/// Directory of the branding pack, overriding `<data dir>/branding`
+pub const BRANDING_DIR_ENV: &str = "YACHTPIT_BRANDING";
+
+/// File describing the pack inside its directory
+pub const BRANDING_FILE: &str = "branding.json";
+
+/// Height of the logo beside the vessel name on the dashboard
+const BANNER_LOGO_HEIGHT: f32 = 28.0;
+
+/// Contents of `branding.json`; everything is optional
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct BrandingPack {
+    pub vessel_name: Option<String>,
+    /// Logo image, relative to the pack directory
+    pub logo: Option<String>,
+    /// Replaces the primary text and border color, as `#rrggbb`
+    pub accent: Option<String>,
+    /// Replaces the secondary border color
+    pub accent_secondary: Option<String>,
+}
+
+/// The branding in effect for this run
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Branding {
+    pack: BrandingPack,
+    dir: Option<PathBuf>,
+    accent: Option<Color>,
+    accent_secondary: Option<Color>,
+}
+
+impl Branding {
+    /// Brand with a parsed pack whose files live in `dir`. Colors that do
+    /// not parse are ignored with a warning.
+    pub fn new(pack: BrandingPack, dir: impl Into<PathBuf>) -> Self {
+        let color = |field: &str, value: &Option<String>| {
+            let value = value.as_deref()?;
+            let color = parse_color(value);
+            if color.is_none() {
+                warn!("Branding {} '{}' is not a #rrggbb color", field, value);
+            }
+            color
+        };
+        Self {
+            accent: color("accent", &pack.accent),
+            accent_secondary: color("accent_secondary", &pack.accent_secondary),
+            dir: Some(dir.into()),
+            pack,
+        }
+    }
+
+    /// Load the pack in `dir`; a directory without `branding.json` is unbranded
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(BRANDING_FILE);
+        let json = match std::fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        let pack = serde_json::from_str(&json).map_err(|e| format!("Invalid branding pack {}: {}", path.display(), e))?;
+        Ok(Self::new(pack, dir))
+    }
+
+    /// Load the pack from `$YACHTPIT_BRANDING` or `<data dir>/branding`,
+    /// falling back to the stock look when there is none or it is broken
+    pub fn load_default() -> Self {
+        let dir = std::env::var_os(BRANDING_DIR_ENV)
+            .map(PathBuf::from)
+            .or_else(|| data_dir().map(|dir| dir.join("branding")));
+        let Some(dir) = dir else {
+            return Self::default();
+        };
+        Self::load(&dir).unwrap_or_else(|e| {
+            warn!("{}", e);
+            Self::default()
+        })
+    }
+
+    pub fn vessel_name(&self) -> Option<&str> {
+        self.pack.vessel_name.as_deref().map(str::trim).filter(|name| !name.is_empty())
+    }
+
+    pub fn accent(&self) -> Option<Color> {
+        self.accent
+    }
+
+    /// Path of the logo file, if the pack has one
+    pub fn logo_path(&self) -> Option<PathBuf> {
+        Some(self.dir.as_ref()?.join(self.pack.logo.as_ref()?))
+    }
+
+    /// Decode the logo. Packs live outside the asset folder, so the file is
+    /// read directly rather than through the asset server.
+    pub fn load_logo(&self) -> Option<Image> {
+        let path = self.logo_path()?;
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        let decoded = std::fs::read(&path).map_err(|e| e.to_string()).and_then(|bytes| {
+            Image::from_buffer(
+                &bytes,
+                ImageType::Extension(&extension),
+                CompressedImageFormats::NONE,
+                true,
+                ImageSampler::Default,
+                RenderAssetUsages::default(),
+            )
+            .map_err(|e| e.to_string())
+        });
+        decoded.inspect_err(|e| warn!("Failed to load branding logo {}: {}", path.display(), e)).ok()
+    }
+
+    /// Theme color to use in place of `color`
+    pub fn recolor(&self, color: Color) -> Color {
+        match (self.accent, self.accent_secondary) {
+            (Some(accent), _) if color == TEXT_COLOR_PRIMARY || color == BORDER_COLOR_PRIMARY => accent,
+            (_, Some(accent)) if color == BORDER_COLOR_SECONDARY => accent,
+            _ => color,
+        }
+    }
+}
+
+/// `#rrggbb` or `#rrggbbaa`
+pub fn parse_color(value: &str) -> Option<Color> {
+    Srgba::hex(value.trim()).ok().map(Color::from)
+}
+
+/// Marks the vessel name banner on the dashboard
+#[derive(Component)]
+pub struct VesselNameBanner;
+
+/// Swap the theme's primary colors for the pack's accents on newly spawned UI
+pub fn apply_branding_accent(
+    branding: Res<Branding>,
+    mut texts: Query<&mut TextColor, Added<TextColor>>,
+    mut borders: Query<&mut BorderColor, Added<BorderColor>>,
+) {
+    if branding.accent.is_none() && branding.accent_secondary.is_none() {
+        return;
+    }
+    for mut text in &mut texts {
+        let color = branding.recolor(text.0);
+        if color != text.0 {
+            text.0 = color;
+        }
+    }
+    for mut border in &mut borders {
+        let color = branding.recolor(border.0);
+        if color != border.0 {
+            border.0 = color;
+        }
+    }
+}
+
+/// Show the vessel name, and the logo beside it, above the instrument cluster
+pub fn spawn_vessel_name_banner(
+    mut commands: Commands,
+    branding: Res<Branding>,
+    mut images: ResMut<Assets<Image>>,
+    clusters: Query<Entity, Added<InstrumentCluster>>,
+) {
+    let Some(name) = branding.vessel_name() else {
+        return;
+    };
+    for cluster in &clusters {
+        let logo = branding.load_logo().map(|logo| images.add(logo));
+        commands.entity(cluster).with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(8.0),
+                        left: Val::Px(16.0),
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(8.0),
+                        ..default()
+                    },
+                    VesselNameBanner,
+                ))
+                .with_children(|banner| {
+                    if let Some(logo) = logo.clone() {
+                        banner.spawn((
+                            ImageNode::new(logo),
+                            Node {
+                                height: Val::Px(BANNER_LOGO_HEIGHT),
+                                ..default()
+                            },
+                        ));
+                    }
+                    banner.spawn((
+                        Text::new(name.to_uppercase()),
+                        TextFont {
+                            font_size: FONT_SIZE_NORMAL,
+                            ..default()
+                        },
+                        TextColor(branding.accent.unwrap_or(TEXT_COLOR_PRIMARY)),
+                    ));
+                });
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_branding_pack_loads_and_recolors() {
+        let dir = std::env::temp_dir().join(format!("yachtpit-branding-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(Branding::load(&dir).unwrap().vessel_name().is_none());
+
+        std::fs::write(
+            dir.join(BRANDING_FILE),
+            r##"{ "vessel_name": " Sea Breeze ", "logo": "logo.png", "accent": "#d4a017", "accent_secondary": "teal" }"##,
+        )
+        .unwrap();
+        let branding = Branding::load(&dir).unwrap();
+        assert_eq!(branding.vessel_name(), Some("Sea Breeze"));
+        assert_eq!(branding.logo_path(), Some(dir.join("logo.png")));
+        assert!(branding.load_logo().is_none());
+
+        let accent = parse_color("#d4a017").unwrap();
+        assert_eq!(branding.recolor(TEXT_COLOR_PRIMARY), accent);
+        assert_eq!(branding.recolor(BORDER_COLOR_PRIMARY), accent);
+        // The secondary accent did not parse, so the stock color stays
+        assert_eq!(branding.recolor(BORDER_COLOR_SECONDARY), BORDER_COLOR_SECONDARY);
+
+        std::fs::write(dir.join(BRANDING_FILE), "{ not json").unwrap();
+        assert!(Branding::load(&dir).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
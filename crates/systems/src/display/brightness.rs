@@ -0,0 +1,438 @@
+//! Display brightness and day/dusk/night theme from ambient light
+//!
+//! An I2C light sensor (BH1750, VEML7700, TSL2561 and the like) shows up
+//! under the Linux IIO subsystem as an `in_illuminance` sysfs file; in the
+//! browser the `devicelight` event reports the same value where the platform
+//! exposes it. Readings are smoothed so a passing shadow does not flip the
+//! theme, and the darker themes only lift once the light has clearly risen
+//! past their threshold. The helm can override the theme and brightness
+//! from the display page, which holds until it is switched back to auto.
+
+use bevy::color::Color;
+use bevy::prelude::{Query, Res, Resource, With};
+use bevy::ui::BackgroundColor;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use components::DisplayDimmer;
+
+/// Path of the sensor's `in_illuminance_input` or `in_illuminance_raw` file
+pub const LIGHT_SENSOR_ENV: &str = "YACHTPIT_LIGHT_SENSOR";
+
+/// Below this the display switches to the dusk theme, in lux
+pub const DUSK_LUX: f32 = 400.0;
+
+/// Below this the display switches to the red night theme, in lux
+pub const NIGHT_LUX: f32 = 10.0;
+
+/// Factor the light must rise past a threshold before a darker theme lifts
+const HYSTERESIS: f32 = 1.5;
+
+/// Weight of a new reading in the smoothed level
+const SMOOTHING: f32 = 0.3;
+
+/// Light level at which the display runs at full brightness, in lux
+const FULL_BRIGHTNESS_LUX: f32 = 1000.0;
+
+/// Dimmest the display goes, as a fraction of full brightness
+pub const MIN_BRIGHTNESS: f32 = 0.15;
+
+/// Opacity of the dimming layer at minimum brightness
+const MAX_DIM_ALPHA: f32 = 0.85;
+
+/// Opacity of the red night tint at full brightness
+const NIGHT_TINT_ALPHA: f32 = 0.35;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayTheme {
+    Day,
+    Dusk,
+    /// Red tint that keeps the crew's night vision
+    Night,
+}
+
+impl DisplayTheme {
+    pub const ALL: [DisplayTheme; 3] = [DisplayTheme::Day, DisplayTheme::Dusk, DisplayTheme::Night];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DisplayTheme::Day => "DAY",
+            DisplayTheme::Dusk => "DUSK",
+            DisplayTheme::Night => "NIGHT",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|theme| theme.label().eq_ignore_ascii_case(name))
+    }
+
+    /// Theme for `lux` coming from this one, lifting darker themes only past
+    /// their threshold times [`HYSTERESIS`]
+    pub fn next(self, lux: f32) -> Self {
+        let (dusk, night) = match self {
+            DisplayTheme::Day => (DUSK_LUX, NIGHT_LUX),
+            DisplayTheme::Dusk => (DUSK_LUX * HYSTERESIS, NIGHT_LUX),
+            DisplayTheme::Night => (DUSK_LUX * HYSTERESIS, NIGHT_LUX * HYSTERESIS),
+        };
+        if lux < night {
+            DisplayTheme::Night
+        } else if lux < dusk {
+            DisplayTheme::Dusk
+        } else {
+            DisplayTheme::Day
+        }
+    }
+}
+
+/// Brightness for a light level, on a log scale between [`MIN_BRIGHTNESS`]
+/// and full brightness at [`FULL_BRIGHTNESS_LUX`]
+pub fn brightness_for_lux(lux: f32) -> f32 {
+    ((lux.max(0.0) + 1.0).log10() / (FULL_BRIGHTNESS_LUX + 1.0).log10()).clamp(MIN_BRIGHTNESS, 1.0)
+}
+
+/// Whether the sensor or the helm sets the display
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DisplayMode {
+    Auto,
+    Manual { theme: DisplayTheme, brightness: f32 },
+}
+
+/// Smoothed light level and the theme and brightness it selects
+#[derive(Debug)]
+pub struct DisplayState {
+    mode: DisplayMode,
+    /// Smoothed reading; `None` until the sensor first reports
+    lux: Option<f32>,
+    auto_theme: DisplayTheme,
+    /// Where readings come from, for the display page
+    source: Option<String>,
+}
+
+impl Default for DisplayState {
+    fn default() -> Self {
+        Self {
+            mode: DisplayMode::Auto,
+            lux: None,
+            auto_theme: DisplayTheme::Day,
+            source: None,
+        }
+    }
+}
+
+impl DisplayState {
+    pub fn mode(&self) -> DisplayMode {
+        self.mode
+    }
+
+    pub fn is_auto(&self) -> bool {
+        self.mode == DisplayMode::Auto
+    }
+
+    pub fn lux(&self) -> Option<f32> {
+        self.lux
+    }
+
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    pub fn set_source(&mut self, source: impl Into<String>) {
+        self.source = Some(source.into());
+    }
+
+    /// Take a sensor reading, smoothing it into the level the theme follows
+    pub fn set_lux(&mut self, lux: f32) {
+        if !lux.is_finite() || lux < 0.0 {
+            return;
+        }
+        let lux = match self.lux {
+            Some(previous) => previous + (lux - previous) * SMOOTHING,
+            None => lux,
+        };
+        self.lux = Some(lux);
+        self.auto_theme = self.auto_theme.next(lux);
+    }
+
+    pub fn theme(&self) -> DisplayTheme {
+        match self.mode {
+            DisplayMode::Auto => self.auto_theme,
+            DisplayMode::Manual { theme, .. } => theme,
+        }
+    }
+
+    /// Fraction of full brightness; full until the sensor first reports
+    pub fn brightness(&self) -> f32 {
+        match self.mode {
+            DisplayMode::Auto => self.lux.map_or(1.0, brightness_for_lux),
+            DisplayMode::Manual { brightness, .. } => brightness,
+        }
+    }
+
+    /// Hold `theme`, keeping the current brightness
+    pub fn override_theme(&mut self, theme: DisplayTheme) {
+        self.mode = DisplayMode::Manual {
+            theme,
+            brightness: self.brightness(),
+        };
+    }
+
+    /// Hold `brightness`, keeping the current theme
+    pub fn override_brightness(&mut self, brightness: f32) -> bool {
+        if !brightness.is_finite() {
+            return false;
+        }
+        self.mode = DisplayMode::Manual {
+            theme: self.theme(),
+            brightness: brightness.clamp(MIN_BRIGHTNESS, 1.0),
+        };
+        true
+    }
+
+    /// Hand the display back to the sensor
+    pub fn resume_auto(&mut self) {
+        self.mode = DisplayMode::Auto;
+    }
+
+    /// Colour of the layer laid over the whole UI: black to dim, red at night
+    pub fn dimmer_color(&self) -> Color {
+        let dim = (1.0 - self.brightness()) / (1.0 - MIN_BRIGHTNESS);
+        match self.theme() {
+            DisplayTheme::Day | DisplayTheme::Dusk => Color::srgba(0.0, 0.0, 0.0, dim * MAX_DIM_ALPHA),
+            DisplayTheme::Night => {
+                Color::srgba(0.3, 0.0, 0.0, NIGHT_TINT_ALPHA + dim * (MAX_DIM_ALPHA - NIGHT_TINT_ALPHA))
+            }
+        }
+    }
+}
+
+/// Shared handle to the display state, fed by the light sensor and edited
+/// from the display page
+#[derive(Resource, Clone, Default)]
+pub struct DisplayBrightness(Arc<RwLock<DisplayState>>);
+
+impl DisplayBrightness {
+    pub fn read(&self) -> RwLockReadGuard<'_, DisplayState> {
+        self.0.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, DisplayState> {
+        self.0.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Tint the dimming layer for the current theme and brightness
+pub fn update_display_dimmer(
+    display: Res<DisplayBrightness>,
+    mut dimmers: Query<&mut BackgroundColor, With<DisplayDimmer>>,
+) {
+    let color = display.read().dimmer_color();
+    for mut background in dimmers.iter_mut() {
+        if background.0 != color {
+            background.0 = color;
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use self::iio::{read_light_sensor, AmbientLightSensor, LightSensor};
+
+/// Light sensors on the Linux IIO subsystem
+#[cfg(not(target_arch = "wasm32"))]
+mod iio {
+    use bevy::log::warn;
+    use bevy::prelude::{Local, Res, Resource, Time};
+    use std::path::{Path, PathBuf};
+    use super::{DisplayBrightness, LIGHT_SENSOR_ENV};
+
+    /// Where the kernel lists IIO devices
+    const IIO_DEVICES_DIR: &str = "/sys/bus/iio/devices";
+
+    /// Seconds between sensor reads
+    const READ_INTERVAL_S: f64 = 1.0;
+
+    /// An illuminance channel read through sysfs
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct LightSensor {
+        path: PathBuf,
+        /// Lux per count for `_raw` channels
+        scale: f32,
+    }
+
+    impl LightSensor {
+        /// A sensor read from `path`, scaled by the sibling
+        /// `in_illuminance_scale` file when it is a `_raw` channel
+        pub fn new(path: impl Into<PathBuf>) -> Self {
+            let path = path.into();
+            let scale = if path.to_string_lossy().ends_with("_raw") {
+                path.parent()
+                    .and_then(|dir| std::fs::read_to_string(dir.join("in_illuminance_scale")).ok())
+                    .and_then(|scale| scale.trim().parse().ok())
+                    .unwrap_or(1.0)
+            } else {
+                1.0
+            };
+            Self { path, scale }
+        }
+
+        /// The sensor named by [`LIGHT_SENSOR_ENV`], else the first IIO
+        /// device with an illuminance channel
+        pub fn from_env() -> Option<Self> {
+            match std::env::var(LIGHT_SENSOR_ENV) {
+                Ok(path) => Some(Self::new(path)),
+                Err(_) => Self::detect(Path::new(IIO_DEVICES_DIR)),
+            }
+        }
+
+        /// First device under `devices_dir` with an illuminance channel
+        pub fn detect(devices_dir: &Path) -> Option<Self> {
+            let mut devices: Vec<PathBuf> = std::fs::read_dir(devices_dir).ok()?.flatten().map(|entry| entry.path()).collect();
+            devices.sort();
+            devices.iter().find_map(|device| {
+                ["in_illuminance_input", "in_illuminance_raw"]
+                    .iter()
+                    .map(|channel| device.join(channel))
+                    .find(|path| path.exists())
+                    .map(Self::new)
+            })
+        }
+
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+
+        pub fn read_lux(&self) -> std::io::Result<f32> {
+            let contents = std::fs::read_to_string(&self.path)?;
+            let value: f32 = contents
+                .trim()
+                .parse()
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{}: {}", contents.trim(), e)))?;
+            Ok(value * self.scale)
+        }
+    }
+
+    /// The light sensor found at startup, if any
+    #[derive(Resource, Default)]
+    pub struct AmbientLightSensor(pub Option<LightSensor>);
+
+    impl AmbientLightSensor {
+        pub fn from_env() -> Self {
+            Self(LightSensor::from_env())
+        }
+    }
+
+    /// Feed sensor readings into the display state once a second
+    pub fn read_light_sensor(
+        sensor: Res<AmbientLightSensor>,
+        display: Res<DisplayBrightness>,
+        time: Res<Time>,
+        mut last_read: Local<Option<f64>>,
+    ) {
+        let Some(sensor) = &sensor.0 else {
+            return;
+        };
+        let now = time.elapsed_secs_f64();
+        if last_read.is_some_and(|last_read| now - last_read < READ_INTERVAL_S) {
+            return;
+        }
+        let first_read = last_read.is_none();
+        *last_read = Some(now);
+
+        match sensor.read_lux() {
+            Ok(lux) => {
+                let mut display = display.write();
+                if first_read {
+                    display.set_source(format!("IIO {}", sensor.path().display()));
+                }
+                display.set_lux(lux);
+            }
+            Err(e) if first_read => warn!("Failed to read light sensor {}: {}", sensor.path().display(), e),
+            Err(_) => {}
+        }
+    }
+}
+
+/// Feed the browser's `devicelight` readings into the display state
+#[cfg(target_arch = "wasm32")]
+pub fn listen_for_device_light(display: Res<DisplayBrightness>) {
+    use wasm_bindgen::{closure::Closure, JsCast};
+
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let shared = display.clone();
+    let on_light = Closure::<dyn FnMut(web_sys::DeviceLightEvent)>::new(move |event: web_sys::DeviceLightEvent| {
+        let mut display = shared.write();
+        if display.source().is_none() {
+            display.set_source("browser devicelight");
+        }
+        display.set_lux(event.value() as f32);
+    });
+    if window
+        .add_event_listener_with_callback("devicelight", on_light.as_ref().unchecked_ref())
+        .is_err()
+    {
+        bevy::log::warn!("Ambient light events not available");
+    }
+    on_light.forget();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::color::Alpha;
+
+    #[test]
+    fn test_theme_follows_light_with_hysteresis_and_override() {
+        let mut state = DisplayState::default();
+        assert_eq!(state.theme(), DisplayTheme::Day);
+        assert_eq!(state.brightness(), 1.0, "full brightness without a sensor");
+
+        state.set_lux(5.0);
+        assert_eq!(state.theme(), DisplayTheme::Night);
+        assert!((state.brightness() - brightness_for_lux(5.0)).abs() < 1e-6);
+
+        // Smoothed towards 20 lux, still under the lifted night threshold
+        state.set_lux(20.0);
+        assert!((state.lux().unwrap() - 9.5).abs() < 1e-4);
+        assert_eq!(state.theme(), DisplayTheme::Night);
+        for _ in 0..10 {
+            state.set_lux(100.0);
+        }
+        assert_eq!(state.theme(), DisplayTheme::Dusk);
+        for _ in 0..20 {
+            state.set_lux(500.0);
+        }
+        assert_eq!(state.theme(), DisplayTheme::Dusk, "500 lux is under the lifted dusk threshold");
+        for _ in 0..20 {
+            state.set_lux(2000.0);
+        }
+        assert_eq!(state.theme(), DisplayTheme::Day);
+        assert_eq!(state.brightness(), 1.0);
+
+        state.override_theme(DisplayTheme::Night);
+        assert!(state.override_brightness(0.0));
+        state.set_lux(5000.0);
+        assert_eq!(state.theme(), DisplayTheme::Night, "override holds against the sensor");
+        assert_eq!(state.brightness(), MIN_BRIGHTNESS);
+        assert!((state.dimmer_color().alpha() - MAX_DIM_ALPHA).abs() < 1e-6);
+        state.resume_auto();
+        assert_eq!(state.theme(), DisplayTheme::Day);
+        assert_eq!(state.dimmer_color().alpha(), 0.0);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_iio_light_sensor_detected_and_scaled() {
+        let devices = std::env::temp_dir().join(format!("yachtpit-iio-{}", std::process::id()));
+        let device = devices.join("iio:device1");
+        std::fs::create_dir_all(devices.join("iio:device0")).unwrap();
+        std::fs::create_dir_all(&device).unwrap();
+        std::fs::write(device.join("in_illuminance_raw"), "240\n").unwrap();
+        std::fs::write(device.join("in_illuminance_scale"), "0.25\n").unwrap();
+
+        let sensor = LightSensor::detect(&devices).unwrap();
+        assert_eq!(sensor.path(), device.join("in_illuminance_raw"));
+        assert_eq!(sensor.read_lux().unwrap(), 60.0);
+        assert!(LightSensor::detect(&device).is_none());
+
+        let _ = std::fs::remove_dir_all(&devices);
+    }
+}
@@ -0,0 +1,111 @@
+use bevy::prelude::Time;
+use components::VesselData;
+use super::brightness::{DisplayBrightness, DisplayTheme, DUSK_LUX, NIGHT_LUX};
+use crate::{SystemInteraction, SystemStatus, VesselSystem};
+
+/// Brightness adjustment per `brightness_step`
+const BRIGHTNESS_STEP: f32 = 0.1;
+
+/// Display page showing the ambient light reading and overriding the
+/// automatic theme and brightness
+pub struct DisplaySystem {
+    status: SystemStatus,
+    display: DisplayBrightness,
+}
+
+impl DisplaySystem {
+    /// Create the display page editing the shared display state
+    pub fn new(display: DisplayBrightness) -> Self {
+        Self {
+            status: SystemStatus::Active,
+            display,
+        }
+    }
+
+    fn configure(&mut self, key: &str, value: &str) -> bool {
+        let mut display = self.display.write();
+        match key {
+            // "auto", a theme name, or "cycle" through auto and each theme
+            "theme" => match value {
+                "auto" => {
+                    display.resume_auto();
+                    true
+                }
+                "cycle" => {
+                    match (display.is_auto(), display.theme()) {
+                        (true, _) => display.override_theme(DisplayTheme::Day),
+                        (false, DisplayTheme::Day) => display.override_theme(DisplayTheme::Dusk),
+                        (false, DisplayTheme::Dusk) => display.override_theme(DisplayTheme::Night),
+                        (false, DisplayTheme::Night) => display.resume_auto(),
+                    }
+                    true
+                }
+                name => match DisplayTheme::from_name(name) {
+                    Some(theme) => {
+                        display.override_theme(theme);
+                        true
+                    }
+                    None => false,
+                },
+            },
+            "brightness" => value.parse().is_ok_and(|brightness| display.override_brightness(brightness)),
+            "brightness_step" => {
+                let step = if value == "up" { BRIGHTNESS_STEP } else { -BRIGHTNESS_STEP };
+                let brightness = display.brightness() + step;
+                display.override_brightness(brightness)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl VesselSystem for DisplaySystem {
+    fn id(&self) -> &'static str {
+        "display"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Display"
+    }
+
+    fn update(&mut self, _yacht_data: &VesselData, _time: &Time) {}
+
+    fn render_display(&self, _yacht_data: &VesselData) -> String {
+        let display = self.display.read();
+        let light = match (display.lux(), display.source()) {
+            (Some(lux), Some(source)) => format!("{:.1} lux ({})", lux, source),
+            (Some(lux), None) => format!("{:.1} lux", lux),
+            (None, _) => "NO SENSOR".to_string(),
+        };
+        format!(
+            "DISPLAY\n\n\
+            Theme: {} ({})\n\
+            Brightness: {:.0}%\n\
+            Ambient Light: {}\n\n\
+            Auto: dusk below {:.0} lux, night below {:.0} lux\n\n\
+            [M] Cycle override  [Up/Down] Brightness  [A] Auto",
+            display.theme().label(),
+            if display.is_auto() { "AUTO" } else { "MANUAL" },
+            display.brightness() * 100.0,
+            light,
+            DUSK_LUX,
+            NIGHT_LUX
+        )
+    }
+
+    fn handle_interaction(&mut self, interaction: SystemInteraction) -> bool {
+        match interaction {
+            SystemInteraction::Select => {
+                self.status = SystemStatus::Active;
+                true
+            }
+            SystemInteraction::Configure(key, value) => self.configure(&key, &value),
+            SystemInteraction::Toggle => self.configure("theme", "cycle"),
+            SystemInteraction::Reset => self.configure("theme", "auto"),
+        }
+    }
+
+    fn status(&self) -> SystemStatus {
+        self.status.clone()
+    }
+}
@@ -0,0 +1,3 @@
+pub(crate) mod branding;
+pub(crate) mod brightness;
+pub(crate) mod display_system;
@@ -19,6 +19,12 @@ impl GpsSystem {
     }
 }
 
+impl Default for GpsSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl VesselSystem for GpsSystem {
     fn id(&self) -> &'static str {
         "gps"
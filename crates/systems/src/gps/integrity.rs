@@ -0,0 +1,282 @@
+//! GNSS integrity checks
+//!
+//! Jamming shows up as every satellite's signal fading at once and the
+//! dilution of precision blowing up; spoofing as a position that jumps
+//! further than the boat could have moved, often with suspiciously uniform
+//! signal strengths since one transmitter fakes the whole constellation.
+//! While any of these hold, positions are dead reckoned from the last fix
+//! that passed, using its course and speed.
+
+use bevy::prelude::*;
+use datalink::geo::{destination, distance_m, METERS_PER_NM};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use crate::alarms::escalation::Alarms;
+
+/// Alarm raised while the receiver is suspected of being jammed or spoofed
+pub const GNSS_INTEGRITY_ALARM: &str = "gnss_integrity";
+
+/// Fastest the boat is assumed to move; anything faster is a jump
+pub const MAX_PLAUSIBLE_SPEED_KTS: f64 = 50.0;
+
+/// Position noise tolerated before a jump is judged on speed
+const JUMP_TOLERANCE_M: f64 = 100.0;
+
+/// Below this every satellite is considered jammed, in dB-Hz
+pub const JAMMED_SNR_DB: u8 = 20;
+
+/// Satellites needed before signal strengths are judged
+const MIN_SNR_SATELLITES: usize = 4;
+
+/// Signal spread at or below which a constellation looks like one transmitter
+const UNIFORM_SNR_SPREAD_DB: u8 = 2;
+
+/// Satellites needed before uniform signal strengths are judged
+const MIN_UNIFORM_SATELLITES: usize = 6;
+
+/// HDOP above which a fix is not trusted
+pub const MAX_HDOP: f64 = 5.0;
+
+/// Factor over the running HDOP baseline that counts as a sudden degradation
+const HDOP_JUMP_FACTOR: f64 = 3.0;
+
+/// Weight of a new HDOP in the running baseline
+const HDOP_SMOOTHING: f64 = 0.1;
+
+/// Consecutive clean fixes needed before the receiver is trusted again
+pub const RECOVERY_FIXES: u32 = 5;
+
+/// One fix as reported by the receiver
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GnssFix {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Seconds on any monotonic clock
+    pub time_s: f64,
+    pub speed_kts: Option<f64>,
+    pub course_deg: Option<f64>,
+    pub hdop: Option<f64>,
+    /// SNR of each tracked satellite in dB-Hz; empty when not reported
+    pub snr_db: Vec<u8>,
+}
+
+/// Why a fix was not trusted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IntegrityFlag {
+    /// Moved faster than the boat can from the dead-reckoned position
+    ImpossibleJump { speed_kts: f64 },
+    /// Every satellite faded below [`JAMMED_SNR_DB`]
+    SnrCollapse { strongest_db: u8 },
+    /// All satellites at nearly the same strength
+    UniformSnr { spread_db: u8 },
+    /// HDOP too high or suddenly much worse than usual
+    HdopAnomaly { hdop: f64 },
+}
+
+impl IntegrityFlag {
+    pub fn label(&self) -> String {
+        match self {
+            IntegrityFlag::ImpossibleJump { speed_kts } => format!("position jump ({:.0} kts)", speed_kts),
+            IntegrityFlag::SnrCollapse { strongest_db } => format!("signal collapse (best {} dB-Hz)", strongest_db),
+            IntegrityFlag::UniformSnr { spread_db } => format!("uniform signals ({} dB spread)", spread_db),
+            IntegrityFlag::HdopAnomaly { hdop } => format!("HDOP {:.1}", hdop),
+        }
+    }
+}
+
+/// Tracks whether receiver fixes can be trusted
+#[derive(Debug, Default)]
+pub struct GnssIntegrity {
+    last_trusted: Option<GnssFix>,
+    hdop_baseline: Option<f64>,
+    flags: Vec<IntegrityFlag>,
+    suspect_since: Option<f64>,
+    clean_fixes: u32,
+}
+
+impl GnssIntegrity {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check a fix; returns whether it can be used. Once suspect, the receiver
+    /// needs [`RECOVERY_FIXES`] clean fixes in a row to be trusted again.
+    pub fn check(&mut self, fix: GnssFix) -> bool {
+        let flags = self.flags_for(&fix);
+        if let Some(hdop) = fix.hdop.filter(|hdop| *hdop <= MAX_HDOP) {
+            if flags.is_empty() {
+                let baseline = self.hdop_baseline.unwrap_or(hdop);
+                self.hdop_baseline = Some(baseline + (hdop - baseline) * HDOP_SMOOTHING);
+            }
+        }
+
+        if !flags.is_empty() {
+            self.flags = flags;
+            self.clean_fixes = 0;
+            self.suspect_since.get_or_insert(fix.time_s);
+            return false;
+        }
+
+        self.clean_fixes += 1;
+        if self.suspect_since.is_some() && self.clean_fixes < RECOVERY_FIXES {
+            return false;
+        }
+        self.suspect_since = None;
+        self.flags.clear();
+        self.last_trusted = Some(fix);
+        true
+    }
+
+    fn flags_for(&self, fix: &GnssFix) -> Vec<IntegrityFlag> {
+        let mut flags = Vec::new();
+
+        if let Some(trusted) = &self.last_trusted {
+            let elapsed_s = fix.time_s - trusted.time_s;
+            let expected = self.dead_reckoning(fix.time_s).unwrap_or((trusted.latitude, trusted.longitude));
+            let off_m = distance_m(expected, (fix.latitude, fix.longitude));
+            if elapsed_s > 0.0 && off_m > JUMP_TOLERANCE_M {
+                let speed_kts = off_m / METERS_PER_NM / (elapsed_s / 3600.0);
+                if speed_kts > MAX_PLAUSIBLE_SPEED_KTS {
+                    flags.push(IntegrityFlag::ImpossibleJump { speed_kts });
+                }
+            }
+        }
+
+        if fix.snr_db.len() >= MIN_SNR_SATELLITES {
+            let strongest_db = fix.snr_db.iter().copied().max().unwrap_or(0);
+            let weakest_db = fix.snr_db.iter().copied().min().unwrap_or(0);
+            if strongest_db < JAMMED_SNR_DB {
+                flags.push(IntegrityFlag::SnrCollapse { strongest_db });
+            } else if fix.snr_db.len() >= MIN_UNIFORM_SATELLITES && strongest_db - weakest_db <= UNIFORM_SNR_SPREAD_DB {
+                flags.push(IntegrityFlag::UniformSnr {
+                    spread_db: strongest_db - weakest_db,
+                });
+            }
+        }
+
+        if let Some(hdop) = fix.hdop {
+            let jumped = self.hdop_baseline.is_some_and(|baseline| hdop > baseline * HDOP_JUMP_FACTOR && hdop > 2.0);
+            if hdop > MAX_HDOP || jumped {
+                flags.push(IntegrityFlag::HdopAnomaly { hdop });
+            }
+        }
+
+        flags
+    }
+
+    /// Whether fixes are currently distrusted
+    pub fn is_suspect(&self) -> bool {
+        self.suspect_since.is_some()
+    }
+
+    /// Time the current suspicion started
+    pub fn suspect_since(&self) -> Option<f64> {
+        self.suspect_since
+    }
+
+    /// What made the latest rejected fix suspect
+    pub fn flags(&self) -> &[IntegrityFlag] {
+        &self.flags
+    }
+
+    pub fn last_trusted(&self) -> Option<&GnssFix> {
+        self.last_trusted.as_ref()
+    }
+
+    /// Position at `time_s` dead reckoned from the last trusted fix
+    pub fn dead_reckoning(&self, time_s: f64) -> Option<(f64, f64)> {
+        let trusted = self.last_trusted.as_ref()?;
+        let elapsed_h = (time_s - trusted.time_s).max(0.0) / 3600.0;
+        match (trusted.speed_kts, trusted.course_deg) {
+            (Some(speed_kts), Some(course_deg)) => Some(destination(
+                (trusted.latitude, trusted.longitude),
+                course_deg,
+                speed_kts * elapsed_h * METERS_PER_NM,
+            )),
+            _ => Some((trusted.latitude, trusted.longitude)),
+        }
+    }
+}
+
+/// Shared GNSS integrity state, fed by the GPS service
+#[derive(Resource, Clone, Default)]
+pub struct GnssIntegrityMonitor(Arc<RwLock<GnssIntegrity>>);
+
+impl GnssIntegrityMonitor {
+    pub fn read(&self) -> RwLockReadGuard<'_, GnssIntegrity> {
+        self.0.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, GnssIntegrity> {
+        self.0.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Raise the GNSS integrity alarm while fixes are distrusted
+pub fn update_gnss_integrity_alarm(monitor: Res<GnssIntegrityMonitor>, alarms: Res<Alarms>, time: Res<Time>) {
+    let suspect = monitor.read().is_suspect();
+    alarms.write().set_condition(GNSS_INTEGRITY_ALARM, suspect, time.elapsed_secs_f64());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fix(time_s: f64, latitude: f64) -> GnssFix {
+        GnssFix {
+            latitude,
+            longitude: 7.42,
+            time_s,
+            speed_kts: Some(6.0),
+            course_deg: Some(0.0),
+            hdop: Some(0.9),
+            snr_db: vec![42, 38, 45, 31, 40, 36],
+        }
+    }
+
+    /// Latitude after `time_s` seconds at six knots due north from 43.7
+    fn on_track(time_s: f64) -> f64 {
+        destination((43.7, 7.42), 0.0, 6.0 * METERS_PER_NM * time_s / 3600.0).0
+    }
+
+    #[test]
+    fn test_jump_falls_back_to_dead_reckoning_until_recovered() {
+        let mut integrity = GnssIntegrity::new();
+        for t in 0..5 {
+            assert!(integrity.check(fix(t as f64, on_track(t as f64))));
+        }
+
+        // A spoofer drags the fix two miles (twenty minutes of sailing) ahead in one second
+        assert!(!integrity.check(fix(5.0, on_track(5.0 + 1200.0))));
+        assert!(integrity.is_suspect());
+        assert!(matches!(integrity.flags()[0], IntegrityFlag::ImpossibleJump { .. }));
+        let (latitude, _) = integrity.dead_reckoning(65.0).unwrap();
+        assert!((latitude - on_track(65.0)).abs() < 1e-7);
+
+        // Back on track, but trusted only after enough clean fixes
+        for t in 6..(6 + RECOVERY_FIXES - 1) {
+            assert!(!integrity.check(fix(t as f64, on_track(t as f64))));
+        }
+        assert!(integrity.check(fix(20.0, on_track(20.0))));
+        assert!(!integrity.is_suspect());
+    }
+
+    #[test]
+    fn test_signal_and_hdop_anomalies_flagged() {
+        let mut integrity = GnssIntegrity::new();
+        for t in 0..10 {
+            assert!(integrity.check(fix(t as f64, on_track(t as f64))));
+        }
+
+        let jammed = GnssFix { snr_db: vec![12, 9, 15, 11], hdop: Some(4.0), ..fix(10.0, on_track(10.0)) };
+        assert!(!integrity.check(jammed));
+        assert_eq!(integrity.flags(), &[
+            IntegrityFlag::SnrCollapse { strongest_db: 15 },
+            IntegrityFlag::HdopAnomaly { hdop: 4.0 },
+        ]);
+
+        let mut spoofed = GnssIntegrity::new();
+        let uniform = GnssFix { snr_db: vec![44, 45, 44, 45, 44, 46], ..fix(0.0, 43.7) };
+        assert!(!spoofed.check(uniform));
+        assert_eq!(spoofed.flags(), &[IntegrityFlag::UniformSnr { spread_db: 2 }]);
+    }
+}
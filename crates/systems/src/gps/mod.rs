@@ -1 +1,2 @@
-pub mod gps_system;
\ No newline at end of file
+pub mod gps_system;
+pub mod integrity;
@@ -0,0 +1,388 @@
+use bevy::prelude::*;
+use components::{DataChannel, DataSource, DepthSettings, DepthTransducers, VesselData};
+use datalink::{DataLinkReceiver, DataMessage};
+use std::collections::VecDeque;
+use crate::collision::cpa::{CollisionMonitor, CollisionWatch};
+use super::link_diagnostics::{LinkDiagnostics, LinkLatencies};
+use super::pressure::{LoadMetrics, SystemLoad};
+
+/// Messages a throttled feed applies per frame
+pub const THROTTLED_MESSAGES_PER_FRAME: usize = 5;
+
+/// Messages queued per throttled feed before the oldest are dropped
+const MAX_PENDING_MESSAGES: usize = 1000;
+
+/// Whether a feed keeps flowing under load
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FeedPriority {
+    #[default]
+    High,
+    /// Throttled while the app is under pressure
+    Low,
+}
+
+impl FeedPriority {
+    /// Priority a feed is registered with. Simulated values and AIS
+    /// traffic (the `ais` and `aisstream` providers) give way first.
+    pub fn for_feed(name: &str, source: DataSource) -> Self {
+        if source == DataSource::Simulated || name.to_ascii_lowercase().starts_with("ais") {
+            FeedPriority::Low
+        } else {
+            FeedPriority::High
+        }
+    }
+}
+
+/// A datalink feeding the vessel data, tagged with the priority of its values
+pub struct DataFeed {
+    /// Name the feed is reported under on the diagnostics page
+    pub name: String,
+    pub link: Box<dyn DataLinkReceiver>,
+    pub source: DataSource,
+    pub priority: FeedPriority,
+    /// Received messages not yet applied because the feed is throttled
+    pending: VecDeque<DataMessage>,
+}
+
+/// Datalinks polled every frame and merged into [`VesselData`]
+#[derive(Resource, Default)]
+pub struct DataFeeds {
+    feeds: Vec<DataFeed>,
+}
+
+impl DataFeeds {
+    /// Register a connected datalink as a data feed, prioritised by
+    /// [`FeedPriority::for_feed`]
+    pub fn add_feed(&mut self, name: impl Into<String>, link: Box<dyn DataLinkReceiver>, source: DataSource) {
+        let name = name.into();
+        self.feeds.push(DataFeed {
+            priority: FeedPriority::for_feed(&name, source),
+            name,
+            link,
+            source,
+            pending: VecDeque::new(),
+        });
+    }
+
+    /// Change a feed's priority; returns false for unknown feeds
+    pub fn set_priority(&mut self, name: &str, priority: FeedPriority) -> bool {
+        match self.feeds.iter_mut().find(|feed| feed.name == name) {
+            Some(feed) => {
+                feed.priority = priority;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drain every feed into the vessel data, recording the backlog in
+    /// `load`. While `load` is throttling, low-priority feeds apply only a
+    /// few queued messages per frame.
+    pub fn ingest(
+        &mut self,
+        vessel_data: &mut VesselData,
+        depth_settings: &mut DepthSettings,
+        latencies: &mut LinkLatencies,
+        collision: &mut CollisionMonitor,
+        load: &mut LoadMetrics,
+        now: f32,
+    ) {
+        let throttling = load.is_throttling();
+        let mut incoming = 0;
+        let mut pending = 0;
+        for feed in self.feeds.iter_mut() {
+            match feed.link.receive_all_messages() {
+                Ok(messages) => {
+                    incoming += messages.len();
+                    for message in &messages {
+                        latencies.observe(&feed.name, message);
+                        collision.observe(message, now as f64);
+                    }
+                    feed.pending.extend(messages);
+                }
+                Err(e) => warn!("Failed to read data feed: {}", e),
+            }
+
+            let budget = if throttling && feed.priority == FeedPriority::Low {
+                THROTTLED_MESSAGES_PER_FRAME
+            } else {
+                usize::MAX
+            };
+            let applied = feed.pending.len().min(budget);
+            for message in feed.pending.drain(..applied) {
+                apply_data_message(vessel_data, depth_settings, &message, feed.source, now);
+            }
+            if feed.pending.len() > MAX_PENDING_MESSAGES {
+                let dropped = feed.pending.len() - MAX_PENDING_MESSAGES;
+                feed.pending.drain(..dropped);
+            }
+            pending += feed.pending.len();
+        }
+        load.record_backlog(incoming, pending);
+    }
+
+    pub fn len(&self) -> usize {
+        self.feeds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.feeds.is_empty()
+    }
+}
+
+fn parse_value(message: &DataMessage, key: &str) -> Option<f32> {
+    message.get_f32(key)
+}
+
+/// Apply a datalink message to the vessel data.
+///
+/// Every write is arbitrated per channel through [`VesselData::ingest`], so a
+/// simulated message never overwrites a channel a live provider is feeding.
+/// Depth readings are identified by their `transducer` field, readings
+/// without one counting as the primary's, and only the primary transducer
+/// drives the depth channel.
+/// Returns whether any channel was updated.
+pub fn apply_data_message(
+    vessel_data: &mut VesselData,
+    depth_settings: &mut DepthSettings,
+    message: &DataMessage,
+    source: DataSource,
+    now: f32,
+) -> bool {
+    let mut updated = false;
+
+    match message.message_type.as_str() {
+        "GPS_POSITION" => {
+            if let Some(speed) = parse_value(message, "speed") {
+                if vessel_data.ingest(DataChannel::Speed, source, now) {
+                    vessel_data.speed = speed;
+                    updated = true;
+                }
+            }
+            if let Some(course) = parse_value(message, "course") {
+                if vessel_data.fresh_heading_true(now).is_none() && vessel_data.ingest(DataChannel::Heading, source, now) {
+                    vessel_data.heading = course;
+                    updated = true;
+                }
+            }
+        }
+        "HEADING" => {
+            if let Some(heading) = parse_value(message, "heading_true") {
+                if vessel_data.ingest(DataChannel::Heading, source, now) {
+                    vessel_data.set_heading_true(heading, now);
+                    vessel_data.heading = heading.rem_euclid(360.0);
+                    updated = true;
+                }
+            }
+        }
+        "DEPTH" => {
+            let transducer = message.get_str("transducer");
+            let depth = parse_value(message, "depth_m")
+                .and_then(|raw_depth| depth_settings.record(transducer, raw_depth));
+            if let Some(depth) = depth {
+                if vessel_data.ingest(DataChannel::Depth, source, now) {
+                    vessel_data.depth = depth;
+                    updated = true;
+                }
+            }
+        }
+        "WIND" => {
+            let speed = parse_value(message, "apparent_wind_speed");
+            let angle = parse_value(message, "apparent_wind_angle");
+            if let (Some(speed), Some(angle)) = (speed, angle) {
+                if vessel_data.ingest(DataChannel::Wind, source, now) {
+                    vessel_data.wind_speed = speed;
+                    vessel_data.wind_direction = angle;
+                    updated = true;
+                }
+            }
+        }
+        // Only the first engine drives the engine panel
+        "ENGINE" if message.get_i64("engine_instance").unwrap_or(0) == 0 => {
+            let rpm = parse_value(message, "rpm");
+            let coolant = parse_value(message, "coolant_temp_c");
+            let oil_pressure = parse_value(message, "oil_pressure_kpa");
+            let alternator = parse_value(message, "alternator_voltage");
+            let any_value = rpm.or(coolant).or(oil_pressure).or(alternator).is_some();
+            if any_value && vessel_data.ingest(DataChannel::Engine, source, now) {
+                vessel_data.engine_rpm = rpm.unwrap_or(vessel_data.engine_rpm);
+                vessel_data.engine_temp = coolant.unwrap_or(vessel_data.engine_temp);
+                vessel_data.oil_pressure = oil_pressure.unwrap_or(vessel_data.oil_pressure);
+                vessel_data.alternator_voltage = alternator.unwrap_or(vessel_data.alternator_voltage);
+                updated = true;
+            }
+        }
+        "SPEED_LOG" => {
+            if let Some(speed) = parse_value(message, "speed_through_water") {
+                if vessel_data.ingest(DataChannel::SpeedLog, source, now) {
+                    vessel_data.speed_through_water = speed;
+                    updated = true;
+                }
+            }
+        }
+        "WATER_TEMPERATURE" => {
+            if let Some(temperature) = parse_value(message, "water_temp_c") {
+                if vessel_data.ingest(DataChannel::WaterTemp, source, now) {
+                    vessel_data.water_temp = temperature;
+                    updated = true;
+                }
+            }
+        }
+        _ => {}
+    }
+
+    updated
+}
+
+/// Drain every registered feed into the vessel data
+pub fn ingest_data_feeds(
+    mut feeds: ResMut<DataFeeds>,
+    mut vessel_data: ResMut<VesselData>,
+    depth_transducers: Res<DepthTransducers>,
+    diagnostics: Res<LinkDiagnostics>,
+    collision: Res<CollisionWatch>,
+    load: Res<SystemLoad>,
+    time: Res<Time>,
+) {
+    feeds.ingest(
+        &mut vessel_data,
+        &mut depth_transducers.write(),
+        &mut diagnostics.write(),
+        &mut collision.write(),
+        &mut load.write(),
+        time.elapsed_secs(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulated_message_yields_to_live() {
+        let mut vessel_data = VesselData::default();
+        let mut depth_settings = DepthSettings::default();
+        let depth = |value: &str| {
+            DataMessage::new("DEPTH".to_string(), "SOUNDER".to_string(), Vec::new())
+                .with_data("depth_m", value.to_string())
+        };
+
+        assert!(apply_data_message(&mut vessel_data, &mut depth_settings, &depth("8.5"), DataSource::Live, 1.0));
+        assert!(!apply_data_message(&mut vessel_data, &mut depth_settings, &depth("12.0"), DataSource::Simulated, 1.5));
+        assert_eq!(vessel_data.depth, 8.5);
+        assert!(!vessel_data.simulated_channels().contains(&DataChannel::Depth));
+    }
+
+    #[test]
+    fn test_heading_sensor_takes_precedence_over_course() {
+        let mut vessel_data = VesselData::default();
+        let mut depth_settings = DepthSettings::default();
+        let heading = DataMessage::new("HEADING".to_string(), "INSTRUMENTS".to_string(), Vec::new())
+            .with_data("heading_true", datalink::Value::Angle(274.0));
+        let fix = DataMessage::new("GPS_POSITION".to_string(), "GPS".to_string(), Vec::new())
+            .with_data("course", datalink::Value::Angle(268.0));
+
+        assert!(apply_data_message(&mut vessel_data, &mut depth_settings, &heading, DataSource::Live, 1.0));
+        assert!(!apply_data_message(&mut vessel_data, &mut depth_settings, &fix, DataSource::Live, 2.0));
+        assert_eq!((vessel_data.heading, vessel_data.heading_true), (274.0, Some(274.0)));
+
+        // Course over ground takes over once the heading sensor goes quiet
+        assert!(apply_data_message(&mut vessel_data, &mut depth_settings, &fix, DataSource::Live, 10.0));
+        assert_eq!(vessel_data.heading, 268.0);
+        assert_eq!(vessel_data.fresh_heading_true(10.0), None);
+    }
+
+    #[test]
+    fn test_engine_messages_fill_engine_panel() {
+        let mut vessel_data = VesselData::default();
+        let mut depth_settings = DepthSettings::default();
+        let engine = |instance: u8| {
+            DataMessage::new("ENGINE".to_string(), "N2K:40".to_string(), Vec::new()).with_data("engine_instance", instance)
+        };
+
+        let rpm = engine(0).with_data("rpm", 2150.0);
+        assert!(apply_data_message(&mut vessel_data, &mut depth_settings, &rpm, DataSource::Live, 1.0));
+        let dynamic = engine(0).with_data("coolant_temp_c", 88.0).with_data("alternator_voltage", 13.8);
+        assert!(apply_data_message(&mut vessel_data, &mut depth_settings, &dynamic, DataSource::Live, 1.5));
+        assert_eq!((vessel_data.engine_rpm, vessel_data.engine_temp), (2150.0, 88.0));
+        assert_eq!((vessel_data.oil_pressure, vessel_data.alternator_voltage), (380.0, 13.8));
+
+        // The second engine does not overwrite the first
+        let starboard = engine(1).with_data("rpm", 900.0);
+        assert!(!apply_data_message(&mut vessel_data, &mut depth_settings, &starboard, DataSource::Live, 2.0));
+        assert_eq!(vessel_data.engine_rpm, 2150.0);
+    }
+
+    #[test]
+    fn test_only_primary_transducer_drives_depth() {
+        let mut vessel_data = VesselData::default();
+        let mut depth_settings = DepthSettings::default();
+        depth_settings.set_offset("AFT", 0.5);
+        let depth = |transducer: &str, value: &str| {
+            DataMessage::new("DEPTH".to_string(), "NMEA2000".to_string(), Vec::new())
+                .with_data("transducer", transducer.to_string())
+                .with_data("depth_m", value.to_string())
+        };
+
+        assert!(apply_data_message(&mut vessel_data, &mut depth_settings, &depth("FWD", "7.0"), DataSource::Live, 1.0));
+        assert!(!apply_data_message(&mut vessel_data, &mut depth_settings, &depth("AFT", "5.0"), DataSource::Live, 1.1));
+        assert_eq!(vessel_data.depth, 7.0);
+
+        depth_settings.select_primary("AFT");
+        assert!(apply_data_message(&mut vessel_data, &mut depth_settings, &depth("AFT", "5.0"), DataSource::Live, 1.2));
+        assert_eq!(vessel_data.depth, 5.5);
+
+        // The simulator names no transducer: it is corrected like the primary
+        // and not listed as a transducer of its own
+        let simulated = DataMessage::new("DEPTH".to_string(), "SIM_DEPTH".to_string(), Vec::new())
+            .with_data("depth_m", "9.0".to_string());
+        let mut simulated_data = VesselData::default();
+        assert!(apply_data_message(&mut simulated_data, &mut depth_settings, &simulated, DataSource::Simulated, 1.3));
+        assert_eq!(simulated_data.depth, 9.5);
+        assert_eq!(depth_settings.transducers.len(), 2);
+    }
+
+    #[test]
+    fn test_low_priority_feed_throttled_under_pressure() {
+        let mut feeds = DataFeeds::default();
+        let mut wind = datalink::SimulationDataLink::new();
+        wind.connect(&datalink::DataLinkConfig::new("simulation".to_string())).unwrap();
+        wind.receive_all_messages().unwrap();
+        for _ in 0..20 {
+            wind.generate_sample_wind_messages();
+        }
+        // Simulated feeds are registered at low priority
+        feeds.add_feed("wind", Box::new(wind), DataSource::Simulated);
+        assert!(!feeds.set_priority("loran", FeedPriority::Low));
+
+        let mut load = LoadMetrics::default();
+        load.set_budgets(4.0, 10);
+        load.record_backlog(10, 0);
+        assert!(load.is_throttling());
+
+        let mut vessel_data = VesselData::default();
+        let mut depth_settings = DepthSettings::default();
+        let mut latencies = LinkLatencies::default();
+        let mut collision = CollisionMonitor::new();
+        feeds.ingest(&mut vessel_data, &mut depth_settings, &mut latencies, &mut collision, &mut load, 1.0);
+        assert_eq!((load.incoming(), load.pending()), (20, 20 - THROTTLED_MESSAGES_PER_FRAME));
+        assert_eq!(latencies.stats("wind").unwrap().messages, 20);
+
+        // The queue keeps the pressure up on quiet frames until it drains
+        feeds.ingest(&mut vessel_data, &mut depth_settings, &mut latencies, &mut collision, &mut load, 1.1);
+        assert!(load.is_throttling());
+        assert_eq!(load.pending(), 20 - 2 * THROTTLED_MESSAGES_PER_FRAME);
+        feeds.ingest(&mut vessel_data, &mut depth_settings, &mut latencies, &mut collision, &mut load, 1.2);
+        feeds.ingest(&mut vessel_data, &mut depth_settings, &mut latencies, &mut collision, &mut load, 1.3);
+        assert_eq!(load.pending(), 0);
+        assert!(!load.is_throttling());
+    }
+
+    #[test]
+    fn test_feed_priority_by_source_and_provider() {
+        assert_eq!(FeedPriority::for_feed("gps", DataSource::Live), FeedPriority::High);
+        assert_eq!(FeedPriority::for_feed("gps", DataSource::Simulated), FeedPriority::Low);
+        assert_eq!(FeedPriority::for_feed("ais", DataSource::Live), FeedPriority::Low);
+        assert_eq!(FeedPriority::for_feed("AISstream", DataSource::Live), FeedPriority::Low);
+    }
+}
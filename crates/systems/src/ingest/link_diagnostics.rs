@@ -0,0 +1,87 @@
+use bevy::diagnostic::{Diagnostic, DiagnosticMeasurement, DiagnosticPath, DiagnosticsStore};
+use bevy::platform::time::Instant;
+use bevy::prelude::*;
+use datalink::{DataMessage, LatencyStats, LatencyTracker};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Latency and jitter of every data feed, keyed by feed name
+#[derive(Debug, Default)]
+pub struct LinkLatencies {
+    links: BTreeMap<String, LatencyTracker>,
+}
+
+impl LinkLatencies {
+    /// Record a message received on `link`
+    pub fn observe(&mut self, link: &str, message: &DataMessage) -> Option<f64> {
+        if !self.links.contains_key(link) {
+            self.links.insert(link.to_string(), LatencyTracker::default());
+        }
+        self.links.get_mut(link)?.observe(message)
+    }
+
+    pub fn stats(&self, link: &str) -> Option<LatencyStats> {
+        self.links.get(link).map(LatencyTracker::stats)
+    }
+
+    /// Stats of every link, in name order
+    pub fn all_stats(&self) -> impl Iterator<Item = (&str, LatencyStats)> + '_ {
+        self.links.iter().map(|(link, tracker)| (link.as_str(), tracker.stats()))
+    }
+
+    pub fn reset(&mut self) {
+        self.links.values_mut().for_each(LatencyTracker::reset);
+    }
+
+    /// Snapshot for the metrics export: `{ "<link>": LatencyStats, .. }`
+    pub fn to_json(&self) -> serde_json::Value {
+        let links: BTreeMap<&str, LatencyStats> = self.all_stats().collect();
+        serde_json::to_value(links).unwrap_or_default()
+    }
+}
+
+/// Shared handle to the link latencies, written by feed ingestion and read by
+/// the diagnostics page and the metrics export
+#[derive(Resource, Clone, Default)]
+pub struct LinkDiagnostics(Arc<RwLock<LinkLatencies>>);
+
+impl LinkDiagnostics {
+    pub fn read(&self) -> RwLockReadGuard<'_, LinkLatencies> {
+        self.0.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, LinkLatencies> {
+        self.0.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Diagnostic path of a link metric, e.g. `datalink/gps/latency_ms`
+fn link_metric_path(link: &str, metric: &str) -> DiagnosticPath {
+    let link = link.replace('/', "_");
+    let link = if link.is_empty() { "unnamed" } else { link.as_str() };
+    DiagnosticPath::from_components(["datalink", link, metric])
+}
+
+/// Publish each link's latency and jitter as Bevy diagnostics, registering
+/// them as links appear, so they are logged and exported with the other metrics
+pub fn export_link_diagnostics(diagnostics: Res<LinkDiagnostics>, store: Option<ResMut<DiagnosticsStore>>) {
+    let Some(mut store) = store else {
+        return;
+    };
+    let latencies = diagnostics.read();
+    let now = Instant::now();
+    for (link, stats) in latencies.all_stats() {
+        for (metric, value) in [("latency_ms", stats.latency_mean_ms), ("jitter_ms", stats.jitter_ms)] {
+            let Some(value) = value else {
+                continue;
+            };
+            let path = link_metric_path(link, metric);
+            if store.get(&path).is_none() {
+                store.add(Diagnostic::new(path.clone()).with_suffix("ms"));
+            }
+            if let Some(diagnostic) = store.get_mut(&path) {
+                diagnostic.add_measurement(DiagnosticMeasurement { time: now, value });
+            }
+        }
+    }
+}
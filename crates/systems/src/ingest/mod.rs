@@ -0,0 +1,3 @@
+pub(crate) mod data_feeds;
+pub(crate) mod link_diagnostics;
+pub(crate) mod pressure;
@@ -0,0 +1,180 @@
+//! Frame load and the pressure signal ingestion throttles on
+//!
+//! The system manager records how long each vessel system takes to update
+//! and ingestion records how many datalink messages arrive per frame and
+//! how many wait queued behind throttled feeds. When
+//! either approaches its budget the pressure rises and low-priority feeds
+//! are drained a few messages per frame until it falls again.
+
+use bevy::prelude::*;
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
+
+/// Time all vessel system updates may take per frame, in milliseconds
+pub const DEFAULT_UPDATE_BUDGET_MS: f64 = 4.0;
+
+/// Incoming datalink messages per frame the app handles comfortably
+pub const DEFAULT_BACKLOG_LIMIT: usize = 500;
+
+/// Pressure at which low-priority feeds are throttled
+pub const THROTTLE_PRESSURE: f32 = 0.8;
+
+/// Pressure below which throttling stops again
+pub const RELEASE_PRESSURE: f32 = 0.5;
+
+/// Weight of each new sample in the running mean
+const MEAN_GAIN: f64 = 0.1;
+
+/// Update cost of one vessel system, in milliseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct UpdateCost {
+    pub last_ms: f64,
+    pub mean_ms: f64,
+    pub max_ms: f64,
+    pub updates: u64,
+}
+
+impl UpdateCost {
+    fn record(&mut self, elapsed: Duration) {
+        let ms = elapsed.as_secs_f64() * 1000.0;
+        self.mean_ms = if self.updates == 0 { ms } else { self.mean_ms + (ms - self.mean_ms) * MEAN_GAIN };
+        self.last_ms = ms;
+        self.max_ms = self.max_ms.max(ms);
+        self.updates += 1;
+    }
+}
+
+/// Update costs, message backlog and the resulting pressure
+#[derive(Debug)]
+pub struct LoadMetrics {
+    costs: BTreeMap<String, UpdateCost>,
+    /// Messages received from datalinks in the last frame
+    incoming: usize,
+    /// Messages queued behind throttled feeds
+    pending: usize,
+    update_budget_ms: f64,
+    backlog_limit: usize,
+    throttling: bool,
+}
+
+impl Default for LoadMetrics {
+    fn default() -> Self {
+        Self {
+            costs: BTreeMap::new(),
+            incoming: 0,
+            pending: 0,
+            update_budget_ms: DEFAULT_UPDATE_BUDGET_MS,
+            backlog_limit: DEFAULT_BACKLOG_LIMIT,
+            throttling: false,
+        }
+    }
+}
+
+impl LoadMetrics {
+    pub fn record_update(&mut self, system_id: &str, elapsed: Duration) {
+        if !self.costs.contains_key(system_id) {
+            self.costs.insert(system_id.to_string(), UpdateCost::default());
+        }
+        if let Some(cost) = self.costs.get_mut(system_id) {
+            cost.record(elapsed);
+        }
+    }
+
+    pub fn update_cost(&self, system_id: &str) -> Option<UpdateCost> {
+        self.costs.get(system_id).copied()
+    }
+
+    /// Update costs of every system, in id order
+    pub fn update_costs(&self) -> impl Iterator<Item = (&str, UpdateCost)> + '_ {
+        self.costs.iter().map(|(id, cost)| (id.as_str(), *cost))
+    }
+
+    /// Mean time all systems take to update per frame
+    pub fn total_update_ms(&self) -> f64 {
+        self.costs.values().map(|cost| cost.mean_ms).sum()
+    }
+
+    /// Record one frame of ingestion: messages received and messages left queued
+    pub fn record_backlog(&mut self, incoming: usize, pending: usize) {
+        self.incoming = incoming;
+        self.pending = pending;
+        let pressure = self.pressure();
+        if pressure >= THROTTLE_PRESSURE {
+            self.throttling = true;
+        } else if pressure < RELEASE_PRESSURE {
+            self.throttling = false;
+        }
+    }
+
+    pub fn incoming(&self) -> usize {
+        self.incoming
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+
+    pub fn set_budgets(&mut self, update_budget_ms: f64, backlog_limit: usize) {
+        self.update_budget_ms = update_budget_ms.max(f64::EPSILON);
+        self.backlog_limit = backlog_limit.max(1);
+    }
+
+    /// Load between 0 (idle) and 1 (at or over budget), from whichever of
+    /// update cost and backlog is closer to its budget. Messages still
+    /// queued behind throttled feeds count against the backlog limit along
+    /// with those just received, so throttling holds until the queue drains.
+    pub fn pressure(&self) -> f32 {
+        let update = self.total_update_ms() / self.update_budget_ms;
+        let backlog = (self.incoming + self.pending) as f64 / self.backlog_limit as f64;
+        update.max(backlog).clamp(0.0, 1.0) as f32
+    }
+
+    /// Whether low-priority feeds are being throttled. Switches on at
+    /// [`THROTTLE_PRESSURE`] and off below [`RELEASE_PRESSURE`].
+    pub fn is_throttling(&self) -> bool {
+        self.throttling
+    }
+}
+
+/// Shared handle to the load metrics, written by the system manager and
+/// ingestion and read by ingestion to decide on throttling
+#[derive(Resource, Clone, Default)]
+pub struct SystemLoad(Arc<RwLock<LoadMetrics>>);
+
+impl SystemLoad {
+    pub fn read(&self) -> RwLockReadGuard<'_, LoadMetrics> {
+        self.0.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, LoadMetrics> {
+        self.0.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pressure_and_throttle_hysteresis() {
+        let mut load = LoadMetrics::default();
+        load.record_update("radar", Duration::from_millis(1));
+        load.record_update("radar", Duration::from_millis(3));
+        let cost = load.update_cost("radar").unwrap();
+        assert_eq!((cost.updates, cost.max_ms.round()), (2, 3.0));
+        assert!((cost.mean_ms - 1.2).abs() < 1e-3);
+        assert!((load.pressure() - 0.3).abs() < 1e-3);
+
+        load.record_backlog(450, 0);
+        assert!(load.is_throttling());
+        // Still above the release level
+        load.record_backlog(300, 120);
+        assert!(load.is_throttling());
+        load.record_backlog(100, 0);
+        assert!(!load.is_throttling());
+        // A queue left behind throttled feeds raises the pressure by itself
+        load.record_backlog(50, 400);
+        assert!(load.is_throttling());
+    }
+}
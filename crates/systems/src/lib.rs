@@ -5,17 +5,63 @@ mod vessel;
 mod ais;
 mod gps;
 mod radar;
+mod calibration;
+mod chart;
+mod timeline;
+mod dashboard;
+mod diagnostics;
+mod connections;
+mod alarms;
+mod air_draft;
+mod colregs;
+mod collision;
+mod watch;
+mod checklist;
+mod display;
+mod wind;
+mod ingest;
 mod geo_plugin;
+mod storage;
 
 // Re-export components from the components crate
 pub use components::{
     setup_instrument_cluster, update_instrument_displays, update_vessel_data, update_vessel_data_with_gps, VesselData,
     SpeedGauge, DepthGauge, CompassGauge, EngineStatus, NavigationDisplay,
-    InstrumentCluster, GpsIndicator, RadarIndicator, AisIndicator, SystemDisplay
+    InstrumentCluster, GpsIndicator, RadarIndicator, AisIndicator, SystemDisplay,
+    DataChannel, DataSource, SimulationIndicator, DataAgeConfig, DataAgeIndicator,
+    DepthSettings, DepthTransducer, DepthTransducers, DepthUnit,
+    DataBoxConfig, DataBoxLayout, DataBoxes, DataField, DataUnit,
+    BoundValue, PanelTemplate, RowTemplate, TemplateError, TemplatedPanel, TextSize, WidgetTemplate
 };
 
 
 pub use world::player::{get_vessel_systems, setup_instrument_cluster_system, PlayerPlugin};
-pub use vessel::vessel_systems::{create_vessel_systems, AisSystem, GpsSystem, RadarSystem, SystemInteraction, SystemStatus, VesselSystem};
+pub use vessel::vessel_systems::{create_vessel_systems, AisSystem, AlarmsSystem, CalibrationSystem, ChartSystem, ChecklistSystem, ConnectionsSystem, DataBoxSystem, DiagnosticsSystem, DisplaySystem, GpsSystem, NavLightsSystem, RadarSystem, SystemInteraction, SystemStatus, TimelineSystem, VesselSystem, WatchSystem};
 
-pub use geo_plugin::GeoPlugin;
\ No newline at end of file
+pub use alarms::escalation::{update_alarm_indicator, update_alarms, Alarm, AlarmTable, Alarms, Escalation, EscalationPolicy, EscalationStage, MAX_ESCALATION_DELAY_S, SHALLOW_WATER_ALARM};
+pub use air_draft::clearance::{update_air_draft, upcoming_bridges, AirDraft, AirDraftSettings, AirDraftState, BridgeClearance, AIR_DRAFT_ALARM, ROUTE_CORRIDOR_M};
+pub use collision::cpa::{closest_approach, update_collision_watch, Approach, CollisionMonitor, CollisionThresholds, CollisionWatch, Kinematics, ThreatEvent, ThreatLevel, TrackedTarget, COLLISION_ALARM, TARGET_TIMEOUT_S};
+pub use colregs::signals::{Blast, ColregsAdvisor, ColregsState, NavState, Propulsion, SoundSignal, VesselProfile};
+pub use watch::schedule::{format_remaining, update_watch_display, update_watch_schedule, WatchSchedule, WatchScheduleState, HANDOVER_NOTICE_S, WATCH_CREW_ENV, WATCH_HANDOVER_ALARM, WATCH_HOURS_ENV};
+pub use checklist::store::{ChecklistItem, ChecklistKind, ChecklistStore, Checklists};
+pub use calibration::depth_store::DepthCalibrationStore;
+pub use display::branding::{apply_branding_accent, parse_color, spawn_vessel_name_banner, Branding, BrandingPack, VesselNameBanner, BRANDING_DIR_ENV, BRANDING_FILE};
+pub use display::brightness::{brightness_for_lux, update_display_dimmer, DisplayBrightness, DisplayMode, DisplayState, DisplayTheme, DUSK_LUX, LIGHT_SENSOR_ENV, MIN_BRIGHTNESS, NIGHT_LUX};
+#[cfg(not(target_arch = "wasm32"))]
+pub use display::brightness::{read_light_sensor, AmbientLightSensor, LightSensor};
+#[cfg(not(target_arch = "wasm32"))]
+pub use dashboard::panel_templates::{spawn_configured_panel, PanelTemplateSource, PANEL_TEMPLATE_ENV};
+pub use alarms::outputs::{shore_report_sentences, GpioOutput, ShoreReporter};
+pub use ais::ais_system::{parse_mmsi, OWN_MMSI_ENV};
+pub use ais::static_cache::{AisStaticCache, AisStaticData};
+pub use connections::services::{BackendService, BackendServices, ServiceRegistry, ServiceState};
+pub use chart::store::{Annotation, AnnotationKind, ChartAnnotations, ChartStore, MarkSymbol, Waypoint, GPX_EXTENSION_NS};
+pub use timeline::history::{Series, TelemetrySample, TimelineEvent, TimelineEventKind, TimelineHistory};
+pub use ingest::data_feeds::{apply_data_message, ingest_data_feeds, DataFeed, DataFeeds, FeedPriority, THROTTLED_MESSAGES_PER_FRAME};
+pub use ingest::pressure::{LoadMetrics, SystemLoad, UpdateCost, DEFAULT_BACKLOG_LIMIT, DEFAULT_UPDATE_BUDGET_MS, RELEASE_PRESSURE, THROTTLE_PRESSURE};
+pub use ingest::link_diagnostics::{export_link_diagnostics, LinkDiagnostics, LinkLatencies};
+pub use gps::integrity::{update_gnss_integrity_alarm, GnssFix, GnssIntegrity, GnssIntegrityMonitor, IntegrityFlag, GNSS_INTEGRITY_ALARM, JAMMED_SNR_DB, MAX_HDOP, MAX_PLAUSIBLE_SPEED_KTS, RECOVERY_FIXES};
+pub use wind::true_wind::{compute_true_wind, ApparentWind, TrueWind, WindCorrection};
+
+pub use storage::data_dir;
+pub use geo_plugin::{GeoPlugin, LocationData, UserLocation};
\ No newline at end of file
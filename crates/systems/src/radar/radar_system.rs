@@ -1,6 +1,21 @@
 use bevy::prelude::Time;
 use components::VesselData;
 use crate::{SystemInteraction, SystemStatus, VesselSystem};
+#[cfg(not(target_arch = "wasm32"))]
+use datalink::DataLinkReceiver;
+#[cfg(not(target_arch = "wasm32"))]
+use datalink_provider::{RadarControl, RadarDataLinkProvider};
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::log::warn;
+
+/// Standard radar display ranges in nautical miles
+pub const RADAR_RANGE_STEPS_NM: [f32; 9] = [0.25, 0.5, 0.75, 1.5, 3.0, 6.0, 12.0, 24.0, 48.0];
+
+/// Gain settings cycled through by the `gain_step` control
+const RADAR_GAIN_STEPS: [&str; 4] = ["AUTO", "LOW", "MED", "HIGH"];
+
+/// Sea clutter adjustment per `sea_clutter_step`
+const SEA_CLUTTER_STEP_DB: i8 = 5;
 
 /// Radar System implementation
 pub struct RadarSystem {
@@ -10,6 +25,10 @@ pub struct RadarSystem {
     sea_clutter_db: i8,
     rain_clutter: bool,
     sweep_angle: f32,
+    #[cfg(not(target_arch = "wasm32"))]
+    datalink: Option<RadarDataLinkProvider>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pending_controls: Vec<RadarControl>,
 }
 
 impl RadarSystem {
@@ -21,7 +40,88 @@ impl RadarSystem {
             sea_clutter_db: -15,
             rain_clutter: false,
             sweep_angle: 0.0,
+            #[cfg(not(target_arch = "wasm32"))]
+            datalink: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            pending_controls: Vec::new(),
+        }
+    }
+
+    /// Attach a radar datalink so display settings are sent to the scanner.
+    /// The provider should already be connected, or be connected later by the caller.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn attach_datalink(&mut self, datalink: RadarDataLinkProvider) {
+        self.datalink = Some(datalink);
+        // Bring the scanner in line with the current display settings
+        self.queue_current_settings();
+    }
+
+    /// Next range step above (`up`) or below the current range
+    pub fn step_range(range_nm: f32, up: bool) -> f32 {
+        if up {
+            RADAR_RANGE_STEPS_NM.iter().copied().find(|step| *step > range_nm + f32::EPSILON)
+                .unwrap_or(RADAR_RANGE_STEPS_NM[RADAR_RANGE_STEPS_NM.len() - 1])
+        } else {
+            RADAR_RANGE_STEPS_NM.iter().rev().copied().find(|step| *step < range_nm - f32::EPSILON)
+                .unwrap_or(RADAR_RANGE_STEPS_NM[0])
+        }
+    }
+
+    pub fn range_nm(&self) -> f32 {
+        self.range_nm
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn queue_control(&mut self, control: RadarControl) {
+        if self.datalink.is_none() {
+            return;
+        }
+        // Only the latest value of each setting matters
+        self.pending_controls
+            .retain(|pending| std::mem::discriminant(pending) != std::mem::discriminant(&control));
+        self.pending_controls.push(control);
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn queue_current_settings(&mut self) {}
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn queue_current_settings(&mut self) {
+        self.queue_control(RadarControl::Range(self.range_nm));
+        self.queue_control(RadarControl::Gain(self.gain.clone()));
+        self.queue_control(RadarControl::SeaClutter(self.sea_clutter_db));
+        self.queue_control(RadarControl::RainClutter(self.rain_clutter));
+        self.queue_control(RadarControl::Transmit(self.status == SystemStatus::Active));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn flush_controls(&mut self) {
+        let Some(datalink) = self.datalink.as_mut() else {
+            return;
+        };
+        if !datalink.is_connected() {
+            return;
         }
+
+        for control in self.pending_controls.drain(..) {
+            if let Err(e) = datalink.send_control(&control) {
+                warn!("Failed to send radar control {:?}: {}", control, e);
+            }
+        }
+    }
+
+    fn link_status(&self) -> &'static str {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(datalink) = &self.datalink {
+            return if datalink.is_connected() { "CONNECTED" } else { "DISCONNECTED" };
+        }
+        "NOT CONFIGURED"
+    }
+}
+
+impl Default for RadarSystem {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -37,6 +137,9 @@ impl VesselSystem for RadarSystem {
     fn update(&mut self, _yacht_data: &VesselData, time: &Time) {
         // Update radar sweep angle
         self.sweep_angle = (time.elapsed_secs() * 60.0) % 360.0;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        self.flush_controls();
     }
 
     fn render_display(&self, _yacht_data: &VesselData) -> String {
@@ -47,6 +150,7 @@ impl VesselSystem for RadarSystem {
             Gain: {}\n\
             Sea Clutter: {} dB\n\
             Rain Clutter: {}\n\
+            Scanner Link: {}\n\
             \n\
             CONTACTS DETECTED:\n\
             • Vessel 1: 2.3 NM @ 045° (15 kts)\n\
@@ -63,8 +167,9 @@ impl VesselSystem for RadarSystem {
             self.sweep_angle,
             self.gain,
             self.sea_clutter_db,
-            if self.rain_clutter { "ON" } else { "OFF" }
-        )
+            if self.rain_clutter { "ON" } else { "OFF" },
+            self.link_status()
+        ) + "\n\n[+/-] Range  [G] Gain  [C/Shift+C] Sea Clutter  [R] Rain  [T] Transmit/Standby"
     }
 
     fn handle_interaction(&mut self, interaction: SystemInteraction) -> bool {
@@ -77,36 +182,60 @@ impl VesselSystem for RadarSystem {
                 match key.as_str() {
                     "range" => {
                         if let Ok(range) = value.parse::<f32>() {
-                            self.range_nm = range.clamp(1.0, 48.0);
-                            true
+                            self.range_nm = range.clamp(RADAR_RANGE_STEPS_NM[0], 48.0);
                         } else {
-                            false
+                            return false;
                         }
                     }
+                    "range_step" => {
+                        self.range_nm = Self::step_range(self.range_nm, value == "up");
+                    }
                     "gain" => {
-                        self.gain = value;
-                        true
+                        self.gain = value.to_uppercase();
+                    }
+                    "gain_step" => {
+                        let current = RADAR_GAIN_STEPS.iter().position(|gain| *gain == self.gain);
+                        let next = current.map_or(0, |index| (index + 1) % RADAR_GAIN_STEPS.len());
+                        self.gain = RADAR_GAIN_STEPS[next].to_string();
                     }
                     "sea_clutter" => {
                         if let Ok(db) = value.parse::<i8>() {
                             self.sea_clutter_db = db.clamp(-30, 0);
-                            true
                         } else {
-                            false
+                            return false;
                         }
                     }
+                    "sea_clutter_step" => {
+                        let step = if value == "up" { SEA_CLUTTER_STEP_DB } else { -SEA_CLUTTER_STEP_DB };
+                        self.sea_clutter_db = self.sea_clutter_db.saturating_add(step).clamp(-30, 0);
+                    }
                     "rain_clutter" => {
-                        self.rain_clutter = value.to_lowercase() == "on" || value == "true";
-                        true
+                        self.rain_clutter = match value.to_lowercase().as_str() {
+                            "toggle" => !self.rain_clutter,
+                            value => value == "on" || value == "true",
+                        };
                     }
-                    _ => false,
+                    _ => return false,
                 }
+
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    let control = match key.as_str() {
+                        "range" | "range_step" => RadarControl::Range(self.range_nm),
+                        "gain" | "gain_step" => RadarControl::Gain(self.gain.clone()),
+                        "sea_clutter" | "sea_clutter_step" => RadarControl::SeaClutter(self.sea_clutter_db),
+                        _ => RadarControl::RainClutter(self.rain_clutter),
+                    };
+                    self.queue_control(control);
+                }
+                true
             }
             SystemInteraction::Reset => {
                 self.range_nm = 12.0;
                 self.gain = "AUTO".to_string();
                 self.sea_clutter_db = -15;
                 self.rain_clutter = false;
+                self.queue_current_settings();
                 true
             }
             SystemInteraction::Toggle => {
@@ -115,6 +244,9 @@ impl VesselSystem for RadarSystem {
                     SystemStatus::Inactive => SystemStatus::Active,
                     _ => SystemStatus::Active,
                 };
+
+                #[cfg(not(target_arch = "wasm32"))]
+                self.queue_control(RadarControl::Transmit(self.status == SystemStatus::Active));
                 true
             }
         }
@@ -0,0 +1,8 @@
+use std::path::{Path, PathBuf};
+
+/// Directory app data is stored in: `$YACHTPIT_DATA_DIR`, falling back to `~/.yachtpit`
+pub fn data_dir() -> Option<PathBuf> {
+    std::env::var_os("YACHTPIT_DATA_DIR")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".yachtpit")))
+}
@@ -0,0 +1,236 @@
+use components::VesselData;
+use std::collections::VecDeque;
+
+/// Minimum spacing between recorded telemetry samples, in seconds
+pub const SAMPLE_INTERVAL_S: f64 = 5.0;
+
+/// How far back samples and events are kept, in seconds
+pub const HISTORY_SPAN_S: f64 = 4.0 * 3600.0;
+
+/// Block characters used for the sparkline rows, lowest first
+const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Telemetry plotted on the timeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Series {
+    Depth,
+    Wind,
+    Sog,
+}
+
+impl Series {
+    pub const ALL: [Series; 3] = [Series::Depth, Series::Wind, Series::Sog];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Series::Depth => "DEPTH",
+            Series::Wind => "WIND",
+            Series::Sog => "SOG",
+        }
+    }
+
+    pub fn unit(self) -> &'static str {
+        match self {
+            Series::Depth => "m",
+            Series::Wind | Series::Sog => "kn",
+        }
+    }
+
+    fn value(self, sample: &TelemetrySample) -> f32 {
+        match self {
+            Series::Depth => sample.depth_m,
+            Series::Wind => sample.wind_speed_kts,
+            Series::Sog => sample.sog_kts,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TelemetrySample {
+    /// Seconds since app start
+    pub time: f64,
+    pub depth_m: f32,
+    pub wind_speed_kts: f32,
+    pub sog_kts: f32,
+}
+
+impl TelemetrySample {
+    pub fn from_vessel_data(time: f64, yacht_data: &VesselData) -> Self {
+        Self {
+            time,
+            depth_m: yacht_data.depth,
+            wind_speed_kts: yacht_data.wind_speed,
+            sog_kts: yacht_data.speed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelineEventKind {
+    Alarm,
+    AlarmCleared,
+    Log,
+}
+
+impl TimelineEventKind {
+    /// Marker used on the event row
+    pub fn marker(self) -> char {
+        match self {
+            TimelineEventKind::Alarm => '!',
+            TimelineEventKind::AlarmCleared => '+',
+            TimelineEventKind::Log => 'L',
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelineEvent {
+    /// Seconds since app start
+    pub time: f64,
+    pub kind: TimelineEventKind,
+    pub text: String,
+}
+
+/// Rolling telemetry samples and events, trimmed to [`HISTORY_SPAN_S`]
+#[derive(Debug, Default)]
+pub struct TimelineHistory {
+    samples: VecDeque<TelemetrySample>,
+    events: VecDeque<TimelineEvent>,
+}
+
+impl TimelineHistory {
+    /// Record a sample unless the previous one is younger than [`SAMPLE_INTERVAL_S`]
+    pub fn record_sample(&mut self, sample: TelemetrySample) -> bool {
+        if self
+            .samples
+            .back()
+            .is_some_and(|last| sample.time - last.time < SAMPLE_INTERVAL_S)
+        {
+            return false;
+        }
+        self.samples.push_back(sample);
+        self.prune(sample.time);
+        true
+    }
+
+    pub fn push_event(&mut self, time: f64, kind: TimelineEventKind, text: &str) {
+        self.events.push_back(TimelineEvent {
+            time,
+            kind,
+            text: text.to_string(),
+        });
+        self.prune(time);
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.events.clear();
+    }
+
+    pub fn samples(&self) -> impl Iterator<Item = &TelemetrySample> {
+        self.samples.iter()
+    }
+
+    /// Events with `start <= time <= end`, oldest first
+    pub fn events_between(&self, start: f64, end: f64) -> impl Iterator<Item = &TimelineEvent> {
+        self.events
+            .iter()
+            .filter(move |event| event.time >= start && event.time <= end)
+    }
+
+    /// Lowest and highest value of a series inside the window
+    pub fn range(&self, series: Series, start: f64, end: f64) -> Option<(f32, f32)> {
+        self.samples
+            .iter()
+            .filter(|sample| sample.time >= start && sample.time <= end)
+            .map(|sample| series.value(sample))
+            .fold(None, |range, value| match range {
+                None => Some((value, value)),
+                Some((low, high)) => Some((low.min(value), high.max(value))),
+            })
+    }
+
+    /// One character per column: the column's average scaled to the window's
+    /// range, or a space where no sample falls
+    pub fn sparkline(&self, series: Series, start: f64, end: f64, columns: usize) -> String {
+        let Some((low, high)) = self.range(series, start, end) else {
+            return " ".repeat(columns);
+        };
+        let span = (end - start) / columns as f64;
+        let mut sums = vec![(0.0f32, 0usize); columns];
+        for sample in self.samples.iter().filter(|sample| sample.time >= start && sample.time <= end) {
+            let column = (((sample.time - start) / span) as usize).min(columns - 1);
+            sums[column].0 += series.value(sample);
+            sums[column].1 += 1;
+        }
+
+        sums.into_iter()
+            .map(|(sum, count)| {
+                if count == 0 {
+                    return ' ';
+                }
+                let average = sum / count as f32;
+                let level = if high > low {
+                    ((average - low) / (high - low) * (LEVELS.len() - 1) as f32).round() as usize
+                } else {
+                    LEVELS.len() / 2
+                };
+                LEVELS[level.min(LEVELS.len() - 1)]
+            })
+            .collect()
+    }
+
+    /// Event markers placed on the same columns as [`sparkline`](Self::sparkline);
+    /// alarms win over other events sharing a column
+    pub fn event_row(&self, start: f64, end: f64, columns: usize) -> String {
+        let span = (end - start) / columns as f64;
+        let mut row = vec!['·'; columns];
+        for event in self.events_between(start, end) {
+            let column = (((event.time - start) / span) as usize).min(columns - 1);
+            if row[column] != TimelineEventKind::Alarm.marker() {
+                row[column] = event.kind.marker();
+            }
+        }
+        row.into_iter().collect()
+    }
+
+    fn prune(&mut self, now: f64) {
+        let cutoff = now - HISTORY_SPAN_S;
+        while self.samples.front().is_some_and(|sample| sample.time < cutoff) {
+            self.samples.pop_front();
+        }
+        while self.events.front().is_some_and(|event| event.time < cutoff) {
+            self.events.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(time: f64, depth_m: f32) -> TelemetrySample {
+        TelemetrySample { time, depth_m, wind_speed_kts: 10.0, sog_kts: 5.0 }
+    }
+
+    #[test]
+    fn test_history_buckets_samples_and_events() {
+        let mut history = TimelineHistory::default();
+        assert!(history.record_sample(sample(0.0, 10.0)));
+        assert!(!history.record_sample(sample(2.0, 9.0)));
+        assert!(history.record_sample(sample(20.0, 2.0)));
+        assert!(history.record_sample(sample(39.0, 6.0)));
+        history.push_event(21.0, TimelineEventKind::Alarm, "SHALLOW WATER 2.0 m");
+        history.push_event(22.0, TimelineEventKind::Log, "Anchored");
+
+        assert_eq!(history.range(Series::Depth, 0.0, 40.0), Some((2.0, 10.0)));
+        assert_eq!(history.sparkline(Series::Depth, 0.0, 40.0, 4), "█ ▁▅");
+        assert_eq!(history.sparkline(Series::Wind, 0.0, 40.0, 4), "▅ ▅▅");
+        assert_eq!(history.event_row(0.0, 40.0, 4), "··!·");
+        assert_eq!(history.events_between(21.5, 40.0).count(), 1);
+
+        history.record_sample(sample(HISTORY_SPAN_S + 30.0, 5.0));
+        assert_eq!(history.samples().count(), 2);
+        assert_eq!(history.events_between(0.0, f64::MAX).count(), 0);
+    }
+}
@@ -0,0 +1,243 @@
+use bevy::prelude::Time;
+use components::{DepthTransducers, VesselData};
+use super::history::{Series, TelemetrySample, TimelineEventKind, TimelineHistory, HISTORY_SPAN_S};
+use crate::checklist::store::Checklists;
+use crate::watch::schedule::WatchSchedule;
+use crate::{SystemInteraction, SystemStatus, VesselSystem};
+
+/// Selectable window widths, in seconds
+const ZOOM_LEVELS_S: [f64; 5] = [60.0, 300.0, 900.0, 3600.0, 4.0 * 3600.0];
+
+/// Window shown when the page opens: 15 minutes
+const DEFAULT_ZOOM: usize = 2;
+
+/// Width of the plotted rows, in characters
+const COLUMNS: usize = 48;
+
+/// Most recent events listed below the plot
+const MAX_LISTED_EVENTS: usize = 8;
+
+/// Timeline page plotting alarms, log entries and key telemetry on one time axis
+pub struct TimelineSystem {
+    status: SystemStatus,
+    depth: DepthTransducers,
+    history: TimelineHistory,
+    zoom: usize,
+    /// How far the window's right edge sits behind now, in seconds
+    pan_s: f64,
+    /// Seconds since app start at the last update
+    now: f64,
+    shallow: bool,
+    last_sample: Option<TelemetrySample>,
+    watch: WatchSchedule,
+    checklists: Checklists,
+}
+
+impl TimelineSystem {
+    /// Create the timeline page, raising shallow alarms from the shared depth settings
+    pub fn new(depth: DepthTransducers) -> Self {
+        Self {
+            status: SystemStatus::Active,
+            depth,
+            history: TimelineHistory::default(),
+            zoom: DEFAULT_ZOOM,
+            pan_s: 0.0,
+            now: 0.0,
+            shallow: false,
+            last_sample: None,
+            watch: WatchSchedule::default(),
+            checklists: Checklists::default(),
+        }
+    }
+
+    /// Also log changes of watch from the shared schedule
+    pub fn with_watch_schedule(mut self, watch: WatchSchedule) -> Self {
+        self.watch = watch;
+        self
+    }
+
+    /// Also log completed passage checklists
+    pub fn with_checklists(mut self, checklists: Checklists) -> Self {
+        self.checklists = checklists;
+        self
+    }
+
+    pub fn history(&self) -> &TimelineHistory {
+        &self.history
+    }
+
+    fn window_s(&self) -> f64 {
+        ZOOM_LEVELS_S[self.zoom]
+    }
+
+    /// Start and end of the visible window, in seconds since app start
+    fn window(&self) -> (f64, f64) {
+        let end = self.now - self.pan_s;
+        (end - self.window_s(), end)
+    }
+
+    fn format_offset(seconds_ago: f64) -> String {
+        let total = seconds_ago.max(0.0).round() as u64;
+        let (hours, minutes, seconds) = (total / 3600, total / 60 % 60, total % 60);
+        if hours > 0 {
+            format!("-{}:{:02}:{:02}", hours, minutes, seconds)
+        } else {
+            format!("-{:02}:{:02}", minutes, seconds)
+        }
+    }
+
+    fn log(&mut self, text: &str) -> bool {
+        let text = if text.trim().is_empty() {
+            match self.last_sample {
+                Some(sample) => format!(
+                    "SOG {:.1} kn, depth {:.1} m, wind {:.1} kn",
+                    sample.sog_kts, sample.depth_m, sample.wind_speed_kts
+                ),
+                None => "Log entry".to_string(),
+            }
+        } else {
+            text.trim().to_string()
+        };
+        self.history.push_event(self.now, TimelineEventKind::Log, &text);
+        true
+    }
+
+    fn configure(&mut self, key: &str, value: &str) -> bool {
+        match key {
+            "zoom" => match value {
+                "in" if self.zoom > 0 => {
+                    self.zoom -= 1;
+                    true
+                }
+                "out" if self.zoom + 1 < ZOOM_LEVELS_S.len() => {
+                    self.zoom += 1;
+                    true
+                }
+                _ => false,
+            },
+            // Moves by half a window
+            "pan" => {
+                let step = self.window_s() / 2.0;
+                let max_pan = (HISTORY_SPAN_S - self.window_s()).max(0.0);
+                let pan_s = match value {
+                    "back" => (self.pan_s + step).min(max_pan),
+                    "forward" => (self.pan_s - step).max(0.0),
+                    _ => return false,
+                };
+                let moved = pan_s != self.pan_s;
+                self.pan_s = pan_s;
+                moved
+            }
+            "live" => {
+                self.pan_s = 0.0;
+                true
+            }
+            "log" => self.log(value),
+            _ => false,
+        }
+    }
+}
+
+impl VesselSystem for TimelineSystem {
+    fn id(&self) -> &'static str {
+        "timeline"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Timeline"
+    }
+
+    fn update(&mut self, yacht_data: &VesselData, time: &Time) {
+        self.now = time.elapsed_secs_f64();
+        let sample = TelemetrySample::from_vessel_data(self.now, yacht_data);
+        self.last_sample = Some(sample);
+        self.history.record_sample(sample);
+
+        let shallow = self.depth.read().is_shallow(yacht_data.depth);
+        if shallow != self.shallow {
+            self.shallow = shallow;
+            let (kind, text) = if shallow {
+                (TimelineEventKind::Alarm, format!("SHALLOW WATER {:.1} m", yacht_data.depth))
+            } else {
+                (TimelineEventKind::AlarmCleared, format!("Depth clear {:.1} m", yacht_data.depth))
+            };
+            self.history.push_event(self.now, kind, &text);
+        }
+
+        let entries = self.watch.write().take_log_entries().into_iter().chain(self.checklists.write().take_log_entries());
+        for entry in entries {
+            self.history.push_event(self.now, TimelineEventKind::Log, &entry);
+        }
+    }
+
+    fn render_display(&self, _yacht_data: &VesselData) -> String {
+        let (start, end) = self.window();
+        let window_minutes = self.window_s() / 60.0;
+        let window_label = if window_minutes >= 60.0 {
+            format!("{:.0} h", window_minutes / 60.0)
+        } else {
+            format!("{:.0} min", window_minutes)
+        };
+        let end_label = if self.pan_s > 0.0 { Self::format_offset(self.pan_s) } else { "now".to_string() };
+
+        let mut display = format!("EVENT TIMELINE\n\nWindow: {} ending {}\n\n", window_label, end_label);
+        for series in Series::ALL {
+            let range = self
+                .history
+                .range(series, start, end)
+                .map(|(low, high)| format!("{:.1}-{:.1} {}", low, high, series.unit()))
+                .unwrap_or_else(|| "NO DATA".to_string());
+            display.push_str(&format!(
+                "{:<6} {} {}\n",
+                series.label(),
+                self.history.sparkline(series, start, end, COLUMNS),
+                range
+            ));
+        }
+        display.push_str(&format!("{:<6} {}\n", "EVENTS", self.history.event_row(start, end, COLUMNS)));
+        let start_label = Self::format_offset(self.now - start);
+        display.push_str(&format!(
+            "{:<6} {}{:>width$}\n\n",
+            "",
+            start_label,
+            end_label,
+            width = COLUMNS - start_label.len()
+        ));
+
+        let events: Vec<_> = self.history.events_between(start, end).collect();
+        if events.is_empty() {
+            display.push_str("No events in window\n");
+        }
+        for event in events.iter().rev().take(MAX_LISTED_EVENTS) {
+            display.push_str(&format!(
+                "{} {} {}\n",
+                Self::format_offset(self.now - event.time),
+                event.kind.marker(),
+                event.text
+            ));
+        }
+        display.push_str("\n[+/-] Zoom  [Left/Right] Pan  [Home] Live  [L] Log Entry");
+        display
+    }
+
+    fn handle_interaction(&mut self, interaction: SystemInteraction) -> bool {
+        match interaction {
+            SystemInteraction::Select => {
+                self.status = SystemStatus::Active;
+                true
+            }
+            SystemInteraction::Configure(key, value) => self.configure(&key, &value),
+            SystemInteraction::Reset => {
+                self.history.clear();
+                self.zoom = DEFAULT_ZOOM;
+                self.pan_s = 0.0;
+                true
+            }
+            SystemInteraction::Toggle => false,
+        }
+    }
+
+    fn status(&self) -> SystemStatus {
+        self.status.clone()
+    }
+}
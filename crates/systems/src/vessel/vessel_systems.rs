@@ -4,8 +4,20 @@
 //! bridging the existing functionality with the new higher-level abstraction.
 
 pub use crate::ais::ais_system::AisSystem;
+pub use crate::alarms::alarms_system::AlarmsSystem;
+pub use crate::calibration::calibration_system::CalibrationSystem;
+pub use crate::chart::chart_system::ChartSystem;
+pub use crate::connections::connections_system::ConnectionsSystem;
+pub use crate::dashboard::data_box_system::DataBoxSystem;
+pub use crate::diagnostics::diagnostics_system::DiagnosticsSystem;
+pub use crate::display::display_system::DisplaySystem;
 pub use crate::gps::gps_system::GpsSystem;
+pub use crate::colregs::nav_lights_system::NavLightsSystem;
+pub use crate::watch::watch_system::WatchSystem;
+pub use crate::checklist::checklist_system::ChecklistSystem;
 pub use crate::radar::radar_system::RadarSystem;
+pub use crate::timeline::timeline_system::TimelineSystem;
+use crate::ais::static_cache::AisStaticCache;
 use bevy::prelude::*;
 use components::VesselData;
 
@@ -42,12 +54,13 @@ pub enum SystemInteraction {
 
 
 
-/// Helper function to create and register all vessel systems
-pub fn create_vessel_systems() -> Vec<Box<dyn VesselSystem>> {
+/// Helper function to create and register all vessel systems, with AIS
+/// names and dimensions kept in `ais_static_cache`
+pub fn create_vessel_systems(ais_static_cache: AisStaticCache) -> Vec<Box<dyn VesselSystem>> {
     vec![
         Box::new(GpsSystem::new()),
         Box::new(RadarSystem::new()),
-        Box::new(AisSystem::new()),
+        Box::new(AisSystem::new().with_static_cache(ais_static_cache)),
     ]
 }
 
@@ -78,6 +91,280 @@ mod tests {
         assert!(radar.handle_interaction(SystemInteraction::Configure("range".to_string(), "24".to_string())));
         let display = radar.render_display(&VesselData::default());
         assert!(display.contains("24 NM RANGE"));
+
+        // Range steps walk the standard scale and stop at either end
+        assert!(radar.handle_interaction(SystemInteraction::Configure("range_step".to_string(), "up".to_string())));
+        assert_eq!(radar.range_nm(), 48.0);
+        assert!(radar.handle_interaction(SystemInteraction::Configure("range_step".to_string(), "up".to_string())));
+        assert_eq!(radar.range_nm(), 48.0);
+        assert_eq!(RadarSystem::step_range(1.0, false), 0.75);
+        assert_eq!(RadarSystem::step_range(0.25, false), 0.25);
+    }
+
+    #[test]
+    fn test_calibration_system_edits_depth_settings() {
+        let depth = components::DepthTransducers::default();
+        let mut calibration = CalibrationSystem::new(depth.clone());
+        assert_eq!(calibration.id(), "calibration");
+
+        let configure = |key: &str, value: &str| SystemInteraction::Configure(key.to_string(), value.to_string());
+        assert!(calibration.handle_interaction(configure("depth_offset", "AFT=-0.8")));
+        assert!(calibration.handle_interaction(configure("primary_depth", "next")));
+        assert!(calibration.handle_interaction(configure("depth_offset_step", "up")));
+        assert!(calibration.handle_interaction(configure("depth_unit", "ft")));
+        assert!(!calibration.handle_interaction(configure("depth_offset", "KEEL=1.0")));
+
+        let settings = depth.read();
+        assert_eq!(settings.primary, "AFT");
+        assert!((settings.primary_transducer().unwrap().offset_m + 0.7).abs() < 1e-6);
+        assert_eq!(settings.unit, components::DepthUnit::Feet);
+        drop(settings);
+
+        assert!(calibration.render_display(&VesselData::default()).contains("Aft (AFT): offset -0.7 m"));
+
+        let air_draft = crate::AirDraft::default();
+        let mut calibration = calibration.with_air_draft(air_draft.clone());
+        assert!(calibration.render_display(&VesselData::default()).contains("Vessel Height: NOT SET"));
+        assert!(calibration.handle_interaction(configure("vessel_height", "15.5")));
+        assert!(calibration.handle_interaction(configure("tide_height_step", "down")));
+        assert!(!calibration.handle_interaction(configure("vessel_height", "-1")));
+        assert_eq!(air_draft.read().settings.tide_height_m, -0.1);
+        assert!(calibration.render_display(&VesselData::default()).contains("Vessel Height: 15.5 m (+1.0 m margin)"));
+    }
+
+    #[test]
+    fn test_chart_system_edits_annotations() {
+        let chart = crate::ChartAnnotations::default();
+        let mut page = ChartSystem::new(chart.clone());
+        assert_eq!(page.id(), "chart");
+
+        let configure = |key: &str, value: &str| SystemInteraction::Configure(key.to_string(), value.to_string());
+        assert!(page.handle_interaction(configure("add_mark", "43.73,7.42")));
+        assert!(page.handle_interaction(configure("symbol", "next")));
+        assert!(page.handle_interaction(configure("add_hazard", "43.72,7.43")));
+        assert!(page.handle_interaction(configure("hazard_radius_step", "up")));
+        assert!(!page.handle_interaction(configure("symbol", "next")));
+        assert!(!page.handle_interaction(configure("add_note", "95.0,7.0")));
+
+        let store = chart.read();
+        assert_eq!(store.annotations()[0].kind, crate::AnnotationKind::Mark { symbol: crate::MarkSymbol::Anchorage });
+        assert_eq!(store.annotations()[1].kind, crate::AnnotationKind::Hazard { radius_m: 150.0 });
+        drop(store);
+
+        assert!(page.handle_interaction(configure("delete", "")));
+        assert_eq!(chart.read().annotations().len(), 1);
+        assert_eq!(page.selected(), Some(chart.read().annotations()[0].id));
+        assert!(page.render_display(&VesselData::default()).contains("▶ MARK 1 (43.7300, 7.4200) ANCHORAGE"));
+    }
+
+    #[test]
+    fn test_timeline_system_records_alarms_and_log() {
+        let depth = components::DepthTransducers::default();
+        let mut timeline = TimelineSystem::new(depth);
+        assert_eq!(timeline.id(), "timeline");
+
+        let mut time = Time::<()>::default();
+        let mut vessel_data = VesselData::default();
+        vessel_data.depth = 12.0;
+        timeline.update(&vessel_data, &time);
+        time.advance_by(std::time::Duration::from_secs(30));
+        vessel_data.depth = 1.5;
+        timeline.update(&vessel_data, &time);
+
+        let configure = |key: &str, value: &str| SystemInteraction::Configure(key.to_string(), value.to_string());
+        assert!(timeline.handle_interaction(configure("log", "Touched bottom")));
+        assert!(timeline.handle_interaction(configure("zoom", "in")));
+        assert!(!timeline.handle_interaction(configure("pan", "forward")));
+
+        assert_eq!(timeline.history().samples().count(), 2);
+        let display = timeline.render_display(&vessel_data);
+        assert!(display.contains("Window: 5 min ending now"));
+        assert!(display.contains("-00:00 ! SHALLOW WATER 1.5 m"));
+        assert!(display.contains("-00:00 L Touched bottom"));
+        assert!(display.contains("DEPTH"));
+    }
+
+    #[test]
+    fn test_data_box_system_edits_shared_layout() {
+        let boxes = components::DataBoxes::default();
+        let mut editor = DataBoxSystem::new(boxes.clone());
+        assert_eq!(editor.id(), "data_boxes");
+
+        let configure = |key: &str, value: &str| SystemInteraction::Configure(key.to_string(), value.to_string());
+        assert!(editor.handle_interaction(configure("add", "depth")));
+        assert_eq!(editor.selected(), 2);
+        assert!(editor.handle_interaction(configure("unit", "next")));
+        assert!(editor.handle_interaction(configure("decimals", "down")));
+        assert!(!editor.handle_interaction(configure("decimals", "down")));
+        assert!(editor.handle_interaction(configure("label", "keel")));
+        assert!(editor.handle_interaction(configure("move", "left")));
+        assert!(!editor.handle_interaction(configure("field", "sea_temp")));
+
+        let display = editor.render_display(&VesselData::default());
+        assert!(display.contains("▶ 2. KEEL       depth        50 FT (0 decimals)"));
+
+        assert!(editor.handle_interaction(configure("remove", "")));
+        let layout = boxes.read();
+        assert_eq!(layout.boxes.len(), 2);
+        assert_eq!(editor.selected(), 1);
+    }
+
+    #[test]
+    fn test_diagnostics_system_reports_link_latency() {
+        use std::time::{Duration, SystemTime};
+
+        let links = crate::LinkDiagnostics::default();
+        let diagnostics = DiagnosticsSystem::new(links.clone());
+        assert_eq!(diagnostics.id(), "diagnostics");
+        assert!(diagnostics.render_display(&VesselData::default()).contains("No datalinks connected"));
+
+        let measured = SystemTime::now();
+        for delay_ms in [1500, 1500] {
+            let message = datalink::DataMessage::new("GPS_SENTENCE".to_string(), "GPS".to_string(), Vec::new())
+                .with_measured_at(measured)
+                .with_received_at(measured + Duration::from_millis(delay_ms));
+            links.write().observe("gps", &message);
+        }
+
+        let display = diagnostics.render_display(&VesselData::default());
+        assert!(display.contains("gps                2       2  1500/1500/1500        0"));
+        assert!(display.contains("gps: HIGH LATENCY"));
+    }
+
+    #[test]
+    fn test_connections_system_retries_unavailable_service() {
+        let services = crate::BackendServices::default();
+        services.write().register("ais", "AIS SERVER");
+        let mut connections = ConnectionsSystem::new(services.clone());
+        assert_eq!(connections.id(), "connections");
+
+        let retry = || SystemInteraction::Configure("retry".to_string(), "selected".to_string());
+        // Still starting, nothing to retry
+        assert!(!connections.handle_interaction(retry()));
+
+        services.write().set_state("ais", crate::ServiceState::Unavailable("not found".to_string()));
+        assert!(connections.render_display(&VesselData::default()).contains("AIS SERVER   UNAVAILABLE (not found)"));
+        assert!(connections.handle_interaction(retry()));
+        assert!(services.write().take_retry("ais"));
+        assert!(!services.write().take_retry("ais"));
+    }
+
+    #[test]
+    fn test_alarms_system_edits_policy_and_acknowledges() {
+        let alarms = crate::Alarms::default();
+        let mut page = AlarmsSystem::new(alarms.clone());
+        assert_eq!(page.id(), "alarms");
+
+        let configure = |key: &str, value: &str| SystemInteraction::Configure(key.to_string(), value.to_string());
+        assert!(page.handle_interaction(configure("toggle_stage", "relay")));
+        assert!(page.handle_interaction(configure("delay_step", "down")));
+        assert!(page.handle_interaction(configure("requires_ack", "toggle")));
+        assert!(!page.handle_interaction(configure("toggle_stage", "siren")));
+        let display = page.render_display(&VesselData::default());
+        assert!(display.contains("Escalation: VISUAL > AUDIBLE > RELAY every 25 s, CLEARS ITSELF"));
+
+        // Nothing raised yet
+        assert!(!page.handle_interaction(configure("acknowledge", "selected")));
+        alarms.write().set_condition(crate::SHALLOW_WATER_ALARM, true, 0.0);
+        alarms.write().update(60.0);
+        assert!(page.render_display(&VesselData::default()).contains("SHALLOW WATER  ACTIVE   RELAY"));
+        assert!(page.handle_interaction(configure("acknowledge", "selected")));
+        assert!(page.render_display(&VesselData::default()).contains("SHALLOW WATER  ACK"));
+    }
+
+    #[test]
+    fn test_nav_lights_system_follows_vessel_state() {
+        let advisor = crate::ColregsAdvisor::default();
+        let mut page = NavLightsSystem::new(advisor.clone());
+        assert_eq!(page.id(), "nav_lights");
+
+        let configure = |key: &str, value: &str| SystemInteraction::Configure(key.to_string(), value.to_string());
+        assert!(page.handle_interaction(configure("propulsion", "toggle")));
+        assert!(page.handle_interaction(configure("state", "next")));
+        assert!(!page.handle_interaction(configure("state", "aground")));
+        let display = page.render_display(&VesselData::default());
+        assert!(display.contains("Vessel: POWER 12 m"));
+        assert!(display.contains("State: STOPPED"));
+        assert!(display.contains("Signal: Two prolonged blasts"));
+
+        assert!(page.handle_interaction(SystemInteraction::Toggle));
+        assert!(advisor.read().restricted_visibility);
+        assert!(page.handle_interaction(SystemInteraction::Reset));
+        assert!(!advisor.read().restricted_visibility);
+        assert_eq!(advisor.read().profile.state, crate::NavState::UnderWay);
+    }
+
+    #[test]
+    fn test_watch_system_rotates_crew() {
+        let schedule = crate::WatchSchedule::default();
+        let mut page = WatchSystem::new(schedule.clone());
+        assert_eq!(page.id(), "watch");
+
+        let configure = |key: &str, value: &str| SystemInteraction::Configure(key.to_string(), value.to_string());
+        // Start from an empty crew whatever the environment holds
+        while !schedule.read().crew().is_empty() {
+            assert!(page.handle_interaction(configure("crew_remove", "selected")));
+        }
+        assert!(!page.handle_interaction(SystemInteraction::Toggle), "no crew to start with");
+        assert!(page.handle_interaction(configure("crew_add", "Anna")));
+        assert!(page.handle_interaction(configure("crew_add", "Ben")));
+        assert!(page.handle_interaction(configure("watch_length", "3")));
+        assert!(page.handle_interaction(configure("watch_length_step", "down")));
+        assert!(page.handle_interaction(SystemInteraction::Toggle));
+
+        let display = page.render_display(&VesselData::default());
+        assert!(display.contains("Rotation: 2.5 h watches, 2 crew"));
+        assert!(display.contains("On watch: ANNA (2:30 left)"));
+        assert!(display.contains("▶ 1. ANNA  (on watch)"));
+
+        // Handovers reach the timeline logbook
+        let mut timeline = TimelineSystem::new(components::DepthTransducers::default()).with_watch_schedule(schedule.clone());
+        schedule.write().update(0.0);
+        timeline.update(&VesselData::default(), &Time::default());
+        assert!(timeline.history().events_between(-1.0, 1.0).any(|event| event.text == "Watch schedule started, ANNA on watch"));
+    }
+
+    #[test]
+    fn test_checklist_system_ticks_items() {
+        let checklists = crate::Checklists::default();
+        let mut page = ChecklistSystem::new(checklists.clone());
+        assert_eq!(page.id(), "checklists");
+
+        let configure = |key: &str, value: &str| SystemInteraction::Configure(key.to_string(), value.to_string());
+        assert!(page.handle_interaction(configure("list", "arrival")));
+        assert!(page.handle_interaction(configure("select", "next")));
+        assert!(page.handle_interaction(SystemInteraction::Toggle));
+        assert!(page.handle_interaction(configure("check", "1")));
+        assert!(!page.handle_interaction(configure("check", "0")));
+        let display = page.render_display(&VesselData::default());
+        assert!(display.contains("ARRIVAL (2/5)"));
+        assert!(display.contains("▶ [x] Seacocks closed"));
+
+        assert!(page.handle_interaction(SystemInteraction::Reset));
+        assert!(checklists.read().items(crate::ChecklistKind::Arrival).iter().all(|item| !item.done));
+    }
+
+    #[test]
+    fn test_display_system_overrides_theme() {
+        let display = crate::DisplayBrightness::default();
+        let mut page = DisplaySystem::new(display.clone());
+        assert_eq!(page.id(), "display");
+        display.write().set_lux(4.0);
+        assert!(page.render_display(&VesselData::default()).contains("Theme: NIGHT (AUTO)"));
+
+        let configure = |key: &str, value: &str| SystemInteraction::Configure(key.to_string(), value.to_string());
+        assert!(page.handle_interaction(SystemInteraction::Toggle));
+        assert_eq!(display.read().theme(), crate::DisplayTheme::Day);
+        assert!(page.handle_interaction(configure("brightness", "0.5")));
+        assert!(page.handle_interaction(configure("brightness_step", "up")));
+        assert!(!page.handle_interaction(configure("theme", "sunset")));
+        let text = page.render_display(&VesselData::default());
+        assert!(text.contains("Theme: DAY (MANUAL)"));
+        assert!(text.contains("Brightness: 60%"));
+        assert!(text.contains("Ambient Light: 4.0 lux"));
+
+        assert!(page.handle_interaction(SystemInteraction::Reset));
+        assert_eq!(display.read().theme(), crate::DisplayTheme::Night);
     }
 
     #[test]
@@ -91,9 +378,54 @@ mod tests {
         assert_eq!(ais.status(), SystemStatus::Inactive);
     }
 
+    #[test]
+    fn test_ais_own_ship_filtered() {
+        use datalink::DataMessage;
+        use datalink_provider::AisReport;
+
+        let watch = crate::CollisionWatch::default();
+        let mut ais = AisSystem::new().with_static_cache(AisStaticCache::in_memory()).with_collision_watch(watch.clone());
+        assert!(!ais.handle_interaction(SystemInteraction::Configure("mmsi".to_string(), "12345".to_string())));
+        assert!(ais.handle_interaction(SystemInteraction::Configure("mmsi".to_string(), "235000001".to_string())));
+        assert_eq!(ais.own_mmsi(), Some(235000001));
+
+        let source = DataMessage::new("AIS_SENTENCE".to_string(), "ais".to_string(), Vec::new());
+        let position = |mmsi| AisReport::Position {
+            mmsi,
+            latitude: Some(50.1),
+            longitude: Some(-1.2),
+            speed_kts: Some(5.0),
+            course_deg: None,
+            heading_deg: None,
+        };
+        ais.apply_report(position(235000001), &source);
+        ais.apply_report(position(235000002), &source);
+
+        assert_eq!(ais.targets().len(), 1);
+        assert!(ais.targets().contains_key("235000002"));
+        assert_eq!(watch.read().targets().keys().collect::<Vec<_>>(), vec![&235000002]);
+        assert_eq!(ais.own_echo().map(|echo| echo.message_type.as_str()), Some("AIS_OWN_POSITION"));
+        let display = ais.render_display(&VesselData::default());
+        assert!(display.contains("Own Ship MMSI: 235000001"));
+        assert!(display.contains("Own Ship Echo: 50.10000, -1.20000"));
+
+        // Clearing the setting hands the echo back to the target list on the next report
+        assert!(ais.handle_interaction(SystemInteraction::Configure("mmsi".to_string(), "clear".to_string())));
+        assert!(ais.own_echo().is_none());
+        ais.apply_report(position(235000001), &source);
+        assert_eq!(ais.targets().len(), 2);
+        assert!(ais.render_display(&VesselData::default()).contains("Own Ship MMSI: NOT SET"));
+
+        // A VDO report is own ship even without the setting
+        let vdo = source.clone().with_data("own_ship", true);
+        ais.apply_report(position(235000003), &vdo);
+        assert_eq!(ais.targets().len(), 2);
+        assert!(ais.own_echo().is_some());
+    }
+
     #[test]
     fn test_create_vessel_systems() {
-        let systems = create_vessel_systems();
+        let systems = create_vessel_systems(AisStaticCache::in_memory());
         assert_eq!(systems.len(), 3);
 
         let ids: Vec<&str> = systems.iter().map(|s| s.id()).collect();
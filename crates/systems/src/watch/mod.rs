@@ -0,0 +1,2 @@
+pub(crate) mod schedule;
+pub(crate) mod watch_system;
@@ -0,0 +1,290 @@
+//! Crew watch rotation
+//!
+//! Watches of equal length rotate through the crew list from the moment the
+//! schedule is started. Shortly before each handover a visual-only alarm
+//! reminds the crew, and every change of watch is queued for the logbook on
+//! the timeline page.
+
+use bevy::log::info;
+use bevy::prelude::{Query, Res, Resource, Text, Time, With};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use components::WatchDisplay;
+use crate::alarms::escalation::Alarms;
+
+/// Alarm raised shortly before a change of watch
+pub const WATCH_HANDOVER_ALARM: &str = "watch_handover";
+
+/// Comma-separated crew names the rotation starts with
+pub const WATCH_CREW_ENV: &str = "YACHTPIT_WATCH_CREW";
+
+/// Watch length in hours
+pub const WATCH_HOURS_ENV: &str = "YACHTPIT_WATCH_HOURS";
+
+/// How long before a handover the reminder is raised, in seconds
+pub const HANDOVER_NOTICE_S: f64 = 5.0 * 60.0;
+
+/// Watch length used when none is configured
+const DEFAULT_WATCH_HOURS: f32 = 4.0;
+
+/// Shortest and longest selectable watch, in hours
+const WATCH_HOURS_RANGE: (f32, f32) = (1.0, 12.0);
+
+/// Crew list, watch length and where the rotation stands
+#[derive(Debug)]
+pub struct WatchScheduleState {
+    crew: Vec<String>,
+    watch_hours: f32,
+    /// Seconds since app start when the first watch began; `None` while stopped
+    started_at: Option<f64>,
+    /// Watch number seen by the last update
+    watch_number: Option<u64>,
+    /// Watch changes not yet written to the logbook
+    log_entries: Vec<String>,
+}
+
+impl Default for WatchScheduleState {
+    fn default() -> Self {
+        Self {
+            crew: Vec::new(),
+            watch_hours: DEFAULT_WATCH_HOURS,
+            started_at: None,
+            watch_number: None,
+            log_entries: Vec::new(),
+        }
+    }
+}
+
+impl WatchScheduleState {
+    /// Crew and watch length from [`WATCH_CREW_ENV`] and [`WATCH_HOURS_ENV`]
+    pub fn from_env() -> Self {
+        let mut state = Self::default();
+        if let Ok(crew) = std::env::var(WATCH_CREW_ENV) {
+            for name in crew.split(',') {
+                state.add_crew(name);
+            }
+        }
+        if let Some(hours) = std::env::var(WATCH_HOURS_ENV).ok().and_then(|hours| hours.parse().ok()) {
+            state.set_watch_hours(hours);
+        }
+        state
+    }
+
+    pub fn crew(&self) -> &[String] {
+        &self.crew
+    }
+
+    /// Add a crew member at the end of the rotation
+    pub fn add_crew(&mut self, name: &str) -> bool {
+        let name = name.trim().to_uppercase();
+        if name.is_empty() || self.crew.contains(&name) {
+            return false;
+        }
+        self.crew.push(name);
+        true
+    }
+
+    pub fn remove_crew(&mut self, index: usize) -> bool {
+        if index >= self.crew.len() {
+            return false;
+        }
+        self.crew.remove(index);
+        if self.crew.is_empty() {
+            self.stop();
+        }
+        true
+    }
+
+    pub fn watch_hours(&self) -> f32 {
+        self.watch_hours
+    }
+
+    pub fn set_watch_hours(&mut self, hours: f32) -> bool {
+        if !hours.is_finite() {
+            return false;
+        }
+        self.watch_hours = hours.clamp(WATCH_HOURS_RANGE.0, WATCH_HOURS_RANGE.1);
+        true
+    }
+
+    fn watch_s(&self) -> f64 {
+        self.watch_hours as f64 * 3600.0
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started_at.is_some()
+    }
+
+    /// Start the rotation at `now` with the first crew member on watch
+    pub fn start(&mut self, now: f64) -> bool {
+        if self.crew.is_empty() {
+            return false;
+        }
+        self.started_at = Some(now);
+        self.watch_number = None;
+        true
+    }
+
+    pub fn stop(&mut self) {
+        self.started_at = None;
+        self.watch_number = None;
+    }
+
+    /// Number of the watch running at `now`, counting from zero
+    fn watch_at(&self, now: f64) -> Option<u64> {
+        let started_at = self.started_at?;
+        Some(((now - started_at).max(0.0) / self.watch_s()) as u64)
+    }
+
+    fn crew_for(&self, watch_number: u64) -> Option<&str> {
+        let index = (watch_number % self.crew.len().max(1) as u64) as usize;
+        self.crew.get(index).map(String::as_str)
+    }
+
+    /// Index into the crew list of whoever is on watch at `now`
+    pub fn on_watch_index(&self, now: f64) -> Option<usize> {
+        let watch_number = self.watch_at(now)?;
+        (!self.crew.is_empty()).then(|| (watch_number % self.crew.len() as u64) as usize)
+    }
+
+    pub fn on_watch(&self, now: f64) -> Option<&str> {
+        self.crew_for(self.watch_at(now)?)
+    }
+
+    pub fn next_watch(&self, now: f64) -> Option<&str> {
+        self.crew_for(self.watch_at(now)? + 1)
+    }
+
+    /// Seconds until the next handover
+    pub fn remaining_s(&self, now: f64) -> Option<f64> {
+        let started_at = self.started_at?;
+        let elapsed = (now - started_at).max(0.0);
+        Some(self.watch_s() - elapsed % self.watch_s())
+    }
+
+    /// Whether the handover reminder should show at `now`
+    pub fn handover_due(&self, now: f64) -> bool {
+        self.remaining_s(now).is_some_and(|remaining| remaining <= HANDOVER_NOTICE_S) && self.crew.len() > 1
+    }
+
+    /// Advance to `now`, queueing a logbook entry when the watch has changed
+    pub fn update(&mut self, now: f64) {
+        let Some(watch_number) = self.watch_at(now) else {
+            return;
+        };
+        if self.watch_number == Some(watch_number) {
+            return;
+        }
+        let to = self.crew_for(watch_number).unwrap_or_default().to_string();
+        let from = self.watch_number.and_then(|_| self.crew_for(watch_number.saturating_sub(1)));
+        let entry = match from {
+            Some(from) => format!("Watch change: {} hands over to {}", from, to),
+            None => format!("Watch schedule started, {} on watch", to),
+        };
+        info!("{}", entry);
+        self.log_entries.push(entry);
+        self.watch_number = Some(watch_number);
+    }
+
+    /// Watch changes since the last call, oldest first
+    pub fn take_log_entries(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.log_entries)
+    }
+}
+
+/// `h:mm`
+pub fn format_remaining(seconds: f64) -> String {
+    let minutes = (seconds.max(0.0) / 60.0).ceil() as u64;
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Shared handle to the watch schedule, edited from the watch page
+#[derive(Resource, Clone)]
+pub struct WatchSchedule(Arc<RwLock<WatchScheduleState>>);
+
+impl Default for WatchSchedule {
+    fn default() -> Self {
+        Self(Arc::new(RwLock::new(WatchScheduleState::from_env())))
+    }
+}
+
+impl WatchSchedule {
+    pub fn read(&self) -> RwLockReadGuard<'_, WatchScheduleState> {
+        self.0.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, WatchScheduleState> {
+        self.0.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Advance the rotation and raise the handover reminder
+pub fn update_watch_schedule(schedule: Res<WatchSchedule>, alarms: Res<Alarms>, time: Res<Time>) {
+    let now = time.elapsed_secs_f64();
+    let mut state = schedule.write();
+    state.update(now);
+    alarms.write().set_condition(WATCH_HANDOVER_ALARM, state.handover_due(now), now);
+}
+
+/// Show who is on watch and who is next on the dashboard
+pub fn update_watch_display(
+    schedule: Res<WatchSchedule>,
+    time: Res<Time>,
+    mut displays: Query<&mut Text, With<WatchDisplay>>,
+) {
+    let now = time.elapsed_secs_f64();
+    let state = schedule.read();
+    let text = match (state.on_watch(now), state.next_watch(now), state.remaining_s(now)) {
+        (Some(current), Some(next), Some(remaining)) => {
+            format!("{}\nNEXT {} {}", current, next, format_remaining(remaining))
+        }
+        _ => "OFF".to_string(),
+    };
+    for mut display in displays.iter_mut() {
+        if display.0 != text {
+            display.0 = text.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_handover_and_log() {
+        let mut state = WatchScheduleState::default();
+        assert!(!state.start(0.0), "no crew");
+        for name in ["anna", "Ben", " cleo ", "ANNA", ""] {
+            state.add_crew(name);
+        }
+        assert_eq!(state.crew(), ["ANNA", "BEN", "CLEO"]);
+        state.set_watch_hours(2.0);
+
+        assert!(state.start(100.0));
+        state.update(100.0);
+        assert_eq!(state.on_watch(100.0), Some("ANNA"));
+        assert_eq!(state.next_watch(100.0), Some("BEN"));
+        assert_eq!(state.take_log_entries(), ["Watch schedule started, ANNA on watch"]);
+
+        // Five minutes before the first handover
+        let before = 100.0 + 2.0 * 3600.0 - 240.0;
+        assert!(!state.handover_due(before - 120.0));
+        assert!(state.handover_due(before));
+        assert_eq!(format_remaining(state.remaining_s(before).unwrap()), "0:04");
+
+        state.update(before);
+        assert!(state.take_log_entries().is_empty());
+        let third = 100.0 + 2.0 * 2.0 * 3600.0 + 1.0;
+        state.update(third);
+        assert_eq!(state.on_watch(third), Some("CLEO"));
+        assert_eq!(state.on_watch_index(third), Some(2));
+        assert_eq!(state.next_watch(third), Some("ANNA"));
+        assert_eq!(state.take_log_entries(), ["Watch change: BEN hands over to CLEO"]);
+
+        state.remove_crew(2);
+        state.remove_crew(1);
+        assert!(!state.handover_due(before), "nobody to hand over to");
+        state.remove_crew(0);
+        assert!(!state.is_running());
+    }
+}
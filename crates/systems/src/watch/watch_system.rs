@@ -0,0 +1,150 @@
+use bevy::prelude::Time;
+use components::VesselData;
+use super::schedule::{format_remaining, WatchSchedule};
+use crate::{SystemInteraction, SystemStatus, VesselSystem};
+
+/// Watch length adjustment per `watch_length_step`, in hours
+const WATCH_STEP_H: f32 = 0.5;
+
+/// Watch schedule page for the crew list and rotation
+pub struct WatchSystem {
+    status: SystemStatus,
+    schedule: WatchSchedule,
+    selected: usize,
+    /// Seconds since app start at the last update
+    now: f64,
+}
+
+impl WatchSystem {
+    /// Create the watch page editing the shared schedule
+    pub fn new(schedule: WatchSchedule) -> Self {
+        Self {
+            status: SystemStatus::Active,
+            schedule,
+            selected: 0,
+            now: 0.0,
+        }
+    }
+
+    fn configure(&mut self, key: &str, value: &str) -> bool {
+        let mut schedule = self.schedule.write();
+        let count = schedule.crew().len();
+        match key {
+            "select" if count > 0 => {
+                self.selected = match value {
+                    "next" => (self.selected + 1) % count,
+                    "previous" => (self.selected + count - 1) % count,
+                    _ => return false,
+                };
+                true
+            }
+            "crew_add" => schedule.add_crew(value),
+            // A crew name, or "selected"
+            "crew_remove" => {
+                let index = match value {
+                    "selected" => self.selected,
+                    name => match schedule.crew().iter().position(|crew| crew.eq_ignore_ascii_case(name)) {
+                        Some(index) => index,
+                        None => return false,
+                    },
+                };
+                let removed = schedule.remove_crew(index);
+                self.selected = self.selected.min(schedule.crew().len().saturating_sub(1));
+                removed
+            }
+            "watch_length" => value.parse().is_ok_and(|hours| schedule.set_watch_hours(hours)),
+            "watch_length_step" => {
+                let step = if value == "up" { WATCH_STEP_H } else { -WATCH_STEP_H };
+                let hours = schedule.watch_hours() + step;
+                schedule.set_watch_hours(hours)
+            }
+            "running" => match value {
+                "start" => schedule.start(self.now),
+                "stop" => {
+                    schedule.stop();
+                    true
+                }
+                "toggle" if schedule.is_running() => {
+                    schedule.stop();
+                    true
+                }
+                "toggle" => schedule.start(self.now),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+impl VesselSystem for WatchSystem {
+    fn id(&self) -> &'static str {
+        "watch"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Watch Schedule"
+    }
+
+    fn update(&mut self, _yacht_data: &VesselData, time: &Time) {
+        self.now = time.elapsed_secs_f64();
+    }
+
+    fn render_display(&self, _yacht_data: &VesselData) -> String {
+        let schedule = self.schedule.read();
+        let mut display = format!(
+            "WATCH SCHEDULE\n\n\
+            Rotation: {:.1} h watches, {} crew\n\
+            Status: {}\n",
+            schedule.watch_hours(),
+            schedule.crew().len(),
+            if schedule.is_running() { "RUNNING" } else { "STOPPED" }
+        );
+        if let (Some(current), Some(next), Some(remaining)) =
+            (schedule.on_watch(self.now), schedule.next_watch(self.now), schedule.remaining_s(self.now))
+        {
+            display.push_str(&format!(
+                "On watch: {} ({} left)\n\
+                Next: {}\n",
+                current,
+                format_remaining(remaining),
+                next
+            ));
+            if schedule.handover_due(self.now) {
+                display.push_str("HANDOVER DUE\n");
+            }
+        }
+
+        display.push_str("\nCREW\n");
+        if schedule.crew().is_empty() {
+            display.push_str("No crew entered\n");
+        }
+        let on_watch = schedule.on_watch_index(self.now);
+        for (index, name) in schedule.crew().iter().enumerate() {
+            display.push_str(&format!(
+                "{} {}. {}{}\n",
+                if index == self.selected { "▶" } else { " " },
+                index + 1,
+                name,
+                if on_watch == Some(index) { "  (on watch)" } else { "" }
+            ));
+        }
+        display.push_str("\n[S] Start/Stop  [+/-] Watch length  [Up/Down] Select  [Del] Remove");
+        display
+    }
+
+    fn handle_interaction(&mut self, interaction: SystemInteraction) -> bool {
+        match interaction {
+            SystemInteraction::Select => {
+                self.status = SystemStatus::Active;
+                true
+            }
+            SystemInteraction::Configure(key, value) => self.configure(&key, &value),
+            SystemInteraction::Toggle => self.configure("running", "toggle"),
+            SystemInteraction::Reset => false,
+        }
+    }
+
+    fn status(&self) -> SystemStatus {
+        self.status.clone()
+    }
+}
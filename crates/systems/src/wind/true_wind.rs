@@ -0,0 +1,159 @@
+use bevy::prelude::Resource;
+use datalink::DataMessage;
+
+/// Wind as measured by the masthead sensor, relative to the bow
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ApparentWind {
+    /// Apparent wind angle in degrees, 0 = on the bow, increasing clockwise
+    pub angle_deg: f32,
+    /// Apparent wind speed in knots
+    pub speed_kts: f32,
+}
+
+/// Wind relative to the water, derived from apparent wind and boat motion
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrueWind {
+    /// True wind angle relative to the bow in degrees (0..360)
+    pub angle_deg: f32,
+    /// True wind direction (where the wind blows from) in degrees true
+    pub direction_deg: f32,
+    /// True wind speed in knots
+    pub speed_kts: f32,
+}
+
+/// Correction inputs applied to the raw wind sensor reading.
+///
+/// Rotating wing masts carry the sensor round with them, so the measured angle
+/// is relative to the mast rather than the hull. On a heeled boat the sensor is
+/// tilted out of the horizontal plane, which under-reads the athwartships
+/// component of the wind; catamarans are particularly affected by both.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq)]
+pub struct WindCorrection {
+    /// Mast rotation relative to the centreline in degrees, positive to starboard
+    pub mast_rotation_deg: f32,
+    /// Heel angle in degrees, positive to starboard
+    pub heel_deg: f32,
+}
+
+impl WindCorrection {
+    /// Update the correction inputs from a mast rotation or attitude message
+    pub fn update_from_message(&mut self, message: &DataMessage) -> bool {
+        let value = |key: &str| message.get_f32(key);
+
+        match message.message_type.as_str() {
+            "MAST_ROTATION" => value("mast_rotation_deg")
+                .map(|rotation| self.mast_rotation_deg = rotation)
+                .is_some(),
+            "ATTITUDE" => value("heel_deg")
+                .map(|heel| self.heel_deg = heel)
+                .is_some(),
+            _ => false,
+        }
+    }
+
+    /// Correct a raw sensor reading to the horizontal plane of the hull
+    pub fn apply(&self, measured: ApparentWind) -> ApparentWind {
+        // Sensor angle is relative to the mast; bring it back to the bow
+        let angle = (measured.angle_deg + self.mast_rotation_deg).to_radians();
+
+        // The heeled sensor only sees cos(heel) of the athwartships flow
+        let heel_cos = self.heel_deg.to_radians().cos().max(0.1);
+        let ahead = measured.speed_kts * angle.cos();
+        let across = measured.speed_kts * angle.sin() / heel_cos;
+
+        ApparentWind {
+            angle_deg: across.atan2(ahead).to_degrees().rem_euclid(360.0),
+            speed_kts: (ahead * ahead + across * across).sqrt(),
+        }
+    }
+}
+
+/// Compute true wind from a sensor reading, boat speed through the water and heading
+pub fn compute_true_wind(
+    measured: ApparentWind,
+    boat_speed_kts: f32,
+    heading_deg: f32,
+    correction: &WindCorrection,
+) -> TrueWind {
+    let apparent = correction.apply(measured);
+    let angle = apparent.angle_deg.to_radians();
+
+    // Remove the headwind created by the boat's own motion
+    let ahead = apparent.speed_kts * angle.cos() - boat_speed_kts;
+    let across = apparent.speed_kts * angle.sin();
+
+    let angle_deg = across.atan2(ahead).to_degrees().rem_euclid(360.0);
+    TrueWind {
+        angle_deg,
+        direction_deg: (heading_deg + angle_deg).rem_euclid(360.0),
+        speed_kts: (ahead * ahead + across * across).sqrt(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datalink::Value;
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.01
+    }
+
+    #[test]
+    fn test_true_wind_without_correction() {
+        // 10 kt apparent on the beam at rest is 10 kt true on the beam
+        let wind = compute_true_wind(
+            ApparentWind { angle_deg: 90.0, speed_kts: 10.0 },
+            0.0,
+            180.0,
+            &WindCorrection::default(),
+        );
+        assert!(approx(wind.angle_deg, 90.0));
+        assert!(approx(wind.direction_deg, 270.0));
+        assert!(approx(wind.speed_kts, 10.0));
+
+        // Motoring at 5 kt into a 5 kt apparent headwind means no true wind
+        let calm = compute_true_wind(
+            ApparentWind { angle_deg: 0.0, speed_kts: 5.0 },
+            5.0,
+            0.0,
+            &WindCorrection::default(),
+        );
+        assert!(approx(calm.speed_kts, 0.0));
+    }
+
+    #[test]
+    fn test_mast_rotation_correction() {
+        let correction = WindCorrection { mast_rotation_deg: 20.0, heel_deg: 0.0 };
+        let corrected = correction.apply(ApparentWind { angle_deg: 25.0, speed_kts: 12.0 });
+        assert!(approx(corrected.angle_deg, 45.0));
+        assert!(approx(corrected.speed_kts, 12.0));
+    }
+
+    #[test]
+    fn test_heel_correction_widens_angle() {
+        let correction = WindCorrection { mast_rotation_deg: 0.0, heel_deg: 20.0 };
+        let measured = ApparentWind { angle_deg: 30.0, speed_kts: 15.0 };
+        let corrected = correction.apply(measured);
+        assert!(corrected.angle_deg > measured.angle_deg);
+        assert!(corrected.speed_kts > measured.speed_kts);
+
+        // Heel has no effect on wind from dead ahead
+        let ahead = correction.apply(ApparentWind { angle_deg: 0.0, speed_kts: 15.0 });
+        assert!(approx(ahead.angle_deg, 0.0));
+    }
+
+    #[test]
+    fn test_correction_from_messages() {
+        let mut correction = WindCorrection::default();
+        let attitude = DataMessage::new("ATTITUDE".to_string(), "IMU".to_string(), Vec::new())
+            .with_data("heel_deg", 12.5);
+        let mast = DataMessage::new("MAST_ROTATION".to_string(), "MAST".to_string(), Vec::new())
+            .with_data("mast_rotation_deg", Value::Angle(-8.0));
+
+        assert!(correction.update_from_message(&attitude));
+        assert!(correction.update_from_message(&mast));
+        assert!(approx(correction.heel_deg, 12.5));
+        assert!(approx(correction.mast_rotation_deg, -8.0));
+    }
+}
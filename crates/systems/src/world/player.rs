@@ -1,5 +1,20 @@
 use bevy::prelude::*;
-use components::{setup_instrument_cluster, VesselData, update_vessel_data, update_instrument_displays};
+use components::{setup_instrument_cluster, VesselData, update_vessel_data, update_instrument_displays, update_engine_status, update_simulation_indicator, update_data_age_indicators, update_depth_readout, DataAgeConfig, DepthTransducers, DataBoxes, update_data_boxes, update_bound_values};
+use crate::ingest::data_feeds::{ingest_data_feeds, DataFeeds};
+use crate::connections::services::BackendServices;
+use crate::air_draft::clearance::{update_air_draft, AirDraft};
+use crate::colregs::signals::ColregsAdvisor;
+use crate::collision::cpa::{update_collision_watch, CollisionWatch};
+use crate::checklist::store::Checklists;
+use crate::display::branding::{apply_branding_accent, spawn_vessel_name_banner, Branding};
+use crate::display::brightness::{update_display_dimmer, DisplayBrightness};
+use crate::gps::integrity::{update_gnss_integrity_alarm, GnssIntegrityMonitor};
+use crate::watch::schedule::{update_watch_display, update_watch_schedule, WatchSchedule};
+use crate::chart::store::ChartAnnotations;
+use crate::alarms::escalation::{update_alarm_indicator, update_alarms, Alarms};
+use crate::ingest::link_diagnostics::{export_link_diagnostics, LinkDiagnostics};
+use crate::ingest::pressure::SystemLoad;
+use crate::ais::static_cache::AisStaticCache;
 use crate::vessel::vessel_systems::{create_vessel_systems, VesselSystem};
 
 pub struct PlayerPlugin;
@@ -8,10 +23,42 @@ pub struct PlayerPlugin;
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<VesselData>()
+            .init_resource::<DataFeeds>()
+            .init_resource::<LinkDiagnostics>()
+            .init_resource::<SystemLoad>()
+            .init_resource::<BackendServices>()
+            .init_resource::<DataAgeConfig>()
+            .init_resource::<DepthTransducers>()
+            .init_resource::<DataBoxes>()
+            .init_resource::<Alarms>()
+            .init_resource::<ChartAnnotations>()
+            .init_resource::<AirDraft>()
+            .init_resource::<ColregsAdvisor>()
+            .init_resource::<WatchSchedule>()
+            .init_resource::<Checklists>()
+            .init_resource::<DisplayBrightness>()
+            .init_resource::<GnssIntegrityMonitor>()
+            .init_resource::<CollisionWatch>()
+            .init_resource::<Branding>()
             .add_systems(
                 Update, 
-                (update_vessel_data, update_instrument_displays)
-            );
+                (ingest_data_feeds, export_link_diagnostics.after(ingest_data_feeds), update_vessel_data, update_instrument_displays, update_engine_status, update_simulation_indicator, update_data_age_indicators, update_depth_readout, update_data_boxes, update_air_draft.before(update_alarms), update_watch_schedule.before(update_alarms), update_watch_display, update_alarms.after(update_vessel_data), update_alarm_indicator.after(update_alarms), update_display_dimmer, update_bound_values, update_gnss_integrity_alarm.before(update_alarms), update_collision_watch.after(ingest_data_feeds).before(update_alarms))
+            )
+            .add_systems(Update, (spawn_vessel_name_banner, apply_branding_accent.after(spawn_vessel_name_banner)));
+
+        #[cfg(not(target_arch = "wasm32"))]
+        app.insert_resource(Branding::load_default())
+            .insert_resource(crate::display::brightness::AmbientLightSensor::from_env())
+            .add_systems(Update, crate::display::brightness::read_light_sensor.before(update_display_dimmer))
+            .insert_resource(crate::dashboard::panel_templates::PanelTemplateSource::from_env())
+            .add_systems(Update, crate::dashboard::panel_templates::spawn_configured_panel);
+
+        // Edited panel templates are rebuilt in place during development
+        #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+        app.add_systems(Update, components::hot_reload_panels);
+
+        #[cfg(target_arch = "wasm32")]
+        app.add_systems(Startup, crate::display::brightness::listen_for_device_light);
     }
 }
 
@@ -20,7 +67,8 @@ pub fn setup_instrument_cluster_system() -> impl Fn(Commands) {
     setup_instrument_cluster
 }
 
-/// Initialize vessel systems - returns the systems for registration
+/// Initialize vessel systems - returns the systems for registration, with
+/// the AIS static cache loaded from the data directory
 pub fn get_vessel_systems() -> Vec<Box<dyn VesselSystem>> {
-    create_vessel_systems()
+    create_vessel_systems(AisStaticCache::load_default())
 }
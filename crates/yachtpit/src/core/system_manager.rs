@@ -4,9 +4,10 @@
 //! (GPS, Radar, AIS, etc.) with common patterns for state management, UI updates,
 //! and user interactions.
 
+use bevy::platform::time::Instant;
 use bevy::prelude::*;
 use std::collections::HashMap;
-use systems::{VesselSystem, SystemInteraction, SystemStatus};
+use systems::{VesselSystem, SystemInteraction, SystemLoad, SystemStatus};
 use components::{VesselData, SystemIndicator, SystemDisplayArea};
 use crate::ui::{spawn_gps_map_window, GpsMapState};
 // use crate::ui::{spawn_gps_map_window, GpsMapState};
@@ -17,14 +18,22 @@ pub struct SystemManager {
     systems: HashMap<String, Box<dyn VesselSystem>>,
     active_system: Option<String>,
     system_order: Vec<String>,
+    /// Update costs and backlog shared with ingestion
+    load: SystemLoad,
 }
 
 impl SystemManager {
     pub fn new() -> Self {
+        Self::with_load(SystemLoad::default())
+    }
+
+    /// Create a manager recording update costs into a shared load handle
+    pub fn with_load(load: SystemLoad) -> Self {
         Self {
             systems: HashMap::new(),
             active_system: None,
             system_order: Vec::new(),
+            load,
         }
     }
 
@@ -60,13 +69,27 @@ impl SystemManager {
             .collect()
     }
 
-    /// Update all systems
+    /// Update all systems, timing each update
     pub fn update_systems(&mut self, yacht_data: &VesselData, time: &Time) {
-        for system in self.systems.values_mut() {
+        let mut load = self.load.write();
+        for (id, system) in self.systems.iter_mut() {
+            let started = Instant::now();
             system.update(yacht_data, time);
+            load.record_update(id, started.elapsed());
         }
     }
 
+    /// Update costs and message backlog
+    pub fn load(&self) -> &SystemLoad {
+        &self.load
+    }
+
+    /// Current load between 0 and 1; ingestion throttles low-priority feeds
+    /// when it runs high
+    pub fn pressure(&self) -> f32 {
+        self.load.read().pressure()
+    }
+
     /// Handle interaction with a specific system
     pub fn handle_system_interaction(&mut self, system_id: &str, interaction: SystemInteraction) -> bool {
         if let Some(system) = self.systems.get_mut(system_id) {
@@ -99,12 +122,25 @@ pub struct SystemManagerPlugin;
 
 impl Plugin for SystemManagerPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<SystemManager>()
+        app.init_resource::<SystemLoad>();
+        let load = app.world().resource::<SystemLoad>().clone();
+        app.insert_resource(SystemManager::with_load(load))
             .add_systems(
                 Update,
                 (
                     update_all_systems,
                     handle_system_indicator_interactions,
+                    handle_radar_control_keys,
+                    handle_calibration_keys,
+                    handle_chart_keys,
+                    handle_timeline_keys,
+                    handle_data_box_keys,
+                    handle_connections_keys,
+                    handle_alarm_keys,
+                    handle_nav_lights_keys,
+                    handle_watch_keys,
+                    handle_checklist_keys,
+                    handle_display_keys,
                     update_system_display_content,
                 ).run_if(in_state(crate::GameState::Playing))
             );
@@ -156,6 +192,443 @@ fn handle_system_indicator_interactions(
     }
 }
 
+/// System to drive radar controls from the keyboard while the radar page is shown
+fn handle_radar_control_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut system_manager: ResMut<SystemManager>,
+) {
+    if system_manager.active_system().map(|system| system.id()) != Some("radar") {
+        return;
+    }
+
+    let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let configure = |key: &str, value: &str| SystemInteraction::Configure(key.to_string(), value.to_string());
+
+    let mut interactions = Vec::new();
+    if keyboard_input.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        interactions.push(configure("range_step", "up"));
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        interactions.push(configure("range_step", "down"));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyG) {
+        interactions.push(configure("gain_step", ""));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyC) {
+        interactions.push(configure("sea_clutter_step", if shift { "down" } else { "up" }));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyR) {
+        interactions.push(configure("rain_clutter", "toggle"));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyT) {
+        interactions.push(SystemInteraction::Toggle);
+    }
+
+    for interaction in interactions {
+        system_manager.handle_system_interaction("radar", interaction);
+    }
+}
+
+/// System to adjust depth calibration from the keyboard while the calibration page is shown
+fn handle_calibration_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut system_manager: ResMut<SystemManager>,
+) {
+    if system_manager.active_system().map(|system| system.id()) != Some("calibration") {
+        return;
+    }
+
+    let configure = |key: &str, value: &str| SystemInteraction::Configure(key.to_string(), value.to_string());
+
+    let mut interactions = Vec::new();
+    if keyboard_input.just_pressed(KeyCode::KeyP) {
+        interactions.push(configure("primary_depth", "next"));
+    }
+    if keyboard_input.just_pressed(KeyCode::BracketRight) {
+        interactions.push(configure("depth_offset_step", "up"));
+    }
+    if keyboard_input.just_pressed(KeyCode::BracketLeft) {
+        interactions.push(configure("depth_offset_step", "down"));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyU) {
+        interactions.push(configure("depth_unit", "next"));
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        interactions.push(configure("shallow_alarm_step", "up"));
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        interactions.push(configure("shallow_alarm_step", "down"));
+    }
+    let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let direction = if shift { "down" } else { "up" };
+    if keyboard_input.just_pressed(KeyCode::KeyH) {
+        interactions.push(configure("vessel_height_step", direction));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyT) {
+        interactions.push(configure("tide_height_step", direction));
+    }
+
+    for interaction in interactions {
+        system_manager.handle_system_interaction("calibration", interaction);
+    }
+}
+
+/// System to place and edit chart annotations from the keyboard while the chart page is shown.
+/// New annotations go at the vessel's current position.
+fn handle_chart_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gps_map_state: Res<GpsMapState>,
+    mut system_manager: ResMut<SystemManager>,
+) {
+    if system_manager.active_system().map(|system| system.id()) != Some("chart") {
+        return;
+    }
+
+    let configure = |key: &str, value: &str| SystemInteraction::Configure(key.to_string(), value.to_string());
+    let position = format!("{:.6},{:.6}", gps_map_state.vessel_lat, gps_map_state.vessel_lon);
+
+    let mut interactions = Vec::new();
+    if keyboard_input.just_pressed(KeyCode::KeyM) {
+        interactions.push(configure("add_mark", &position));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyH) {
+        interactions.push(configure("add_hazard", &position));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyN) {
+        interactions.push(configure("add_note", &position));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyB) {
+        interactions.push(configure("add_bridge", &position));
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        interactions.push(configure("select", "next"));
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        interactions.push(configure("select", "prev"));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyS) {
+        interactions.push(configure("symbol", "next"));
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        interactions.push(configure("hazard_radius_step", "up"));
+        interactions.push(configure("clearance_step", "up"));
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        interactions.push(configure("hazard_radius_step", "down"));
+        interactions.push(configure("clearance_step", "down"));
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Delete, KeyCode::Backspace]) {
+        interactions.push(configure("delete", ""));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyX) {
+        interactions.push(configure("export_gpx", "default"));
+    }
+
+    for interaction in interactions {
+        system_manager.handle_system_interaction("chart", interaction);
+    }
+}
+
+/// System to zoom, pan and log from the keyboard while the timeline page is shown
+fn handle_timeline_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut system_manager: ResMut<SystemManager>,
+) {
+    if system_manager.active_system().map(|system| system.id()) != Some("timeline") {
+        return;
+    }
+
+    let configure = |key: &str, value: &str| SystemInteraction::Configure(key.to_string(), value.to_string());
+
+    let mut interactions = Vec::new();
+    if keyboard_input.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        interactions.push(configure("zoom", "in"));
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        interactions.push(configure("zoom", "out"));
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowLeft) {
+        interactions.push(configure("pan", "back"));
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowRight) {
+        interactions.push(configure("pan", "forward"));
+    }
+    if keyboard_input.just_pressed(KeyCode::Home) {
+        interactions.push(configure("live", ""));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyL) {
+        interactions.push(configure("log", ""));
+    }
+
+    for interaction in interactions {
+        system_manager.handle_system_interaction("timeline", interaction);
+    }
+}
+
+/// System to edit the dashboard data boxes from the keyboard while their page is shown
+fn handle_data_box_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut system_manager: ResMut<SystemManager>,
+) {
+    if system_manager.active_system().map(|system| system.id()) != Some("data_boxes") {
+        return;
+    }
+
+    let configure = |key: &str, value: &str| SystemInteraction::Configure(key.to_string(), value.to_string());
+
+    let mut interactions = Vec::new();
+    if keyboard_input.just_pressed(KeyCode::KeyA) {
+        interactions.push(configure("add", ""));
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        interactions.push(configure("select", "previous"));
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        interactions.push(configure("select", "next"));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyF) {
+        interactions.push(configure("field", "next"));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyU) {
+        interactions.push(configure("unit", "next"));
+    }
+    if keyboard_input.just_pressed(KeyCode::BracketLeft) {
+        interactions.push(configure("decimals", "down"));
+    }
+    if keyboard_input.just_pressed(KeyCode::BracketRight) {
+        interactions.push(configure("decimals", "up"));
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowLeft) {
+        interactions.push(configure("move", "left"));
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowRight) {
+        interactions.push(configure("move", "right"));
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Delete, KeyCode::Backspace]) {
+        interactions.push(configure("remove", ""));
+    }
+
+    for interaction in interactions {
+        system_manager.handle_system_interaction("data_boxes", interaction);
+    }
+}
+
+/// System to select and retry backend services and rescan serial ports while the connections page is shown
+fn handle_connections_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut system_manager: ResMut<SystemManager>,
+) {
+    if system_manager.active_system().map(|system| system.id()) != Some("connections") {
+        return;
+    }
+
+    let configure = |key: &str, value: &str| SystemInteraction::Configure(key.to_string(), value.to_string());
+
+    let mut interactions = Vec::new();
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        interactions.push(configure("select", "previous"));
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        interactions.push(configure("select", "next"));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyR) {
+        interactions.push(configure("retry", "selected"));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyP) {
+        interactions.push(configure("scan_ports", ""));
+    }
+
+    for interaction in interactions {
+        system_manager.handle_system_interaction("connections", interaction);
+    }
+}
+
+/// System to edit escalation policies and acknowledge alarms while the alarms page is shown
+fn handle_alarm_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut system_manager: ResMut<SystemManager>,
+) {
+    if system_manager.active_system().map(|system| system.id()) != Some("alarms") {
+        return;
+    }
+
+    let configure = |key: &str, value: &str| SystemInteraction::Configure(key.to_string(), value.to_string());
+
+    let mut interactions = Vec::new();
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        interactions.push(configure("select", "previous"));
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        interactions.push(configure("select", "next"));
+    }
+    let stage_keys = [
+        (KeyCode::Digit1, "visual"),
+        (KeyCode::Digit2, "audible"),
+        (KeyCode::Digit3, "relay"),
+        (KeyCode::Digit4, "shore"),
+    ];
+    for (key, stage) in stage_keys {
+        if keyboard_input.just_pressed(key) {
+            interactions.push(configure("toggle_stage", stage));
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::BracketLeft) {
+        interactions.push(configure("delay_step", "down"));
+    }
+    if keyboard_input.just_pressed(KeyCode::BracketRight) {
+        interactions.push(configure("delay_step", "up"));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyK) {
+        interactions.push(configure("requires_ack", "toggle"));
+    }
+    if keyboard_input.just_pressed(KeyCode::Enter) {
+        let all = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        interactions.push(configure("acknowledge", if all { "all" } else { "selected" }));
+    }
+
+    for interaction in interactions {
+        system_manager.handle_system_interaction("alarms", interaction);
+    }
+}
+
+/// System to set the vessel state and switch fog signals while the nav lights page is shown
+fn handle_nav_lights_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut system_manager: ResMut<SystemManager>,
+) {
+    if system_manager.active_system().map(|system| system.id()) != Some("nav_lights") {
+        return;
+    }
+
+    let configure = |key: &str, value: &str| SystemInteraction::Configure(key.to_string(), value.to_string());
+
+    let mut interactions = Vec::new();
+    if keyboard_input.just_pressed(KeyCode::KeyP) {
+        interactions.push(configure("propulsion", "toggle"));
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        interactions.push(configure("state", "previous"));
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        interactions.push(configure("state", "next"));
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        interactions.push(configure("length_step", "up"));
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        interactions.push(configure("length_step", "down"));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyF) {
+        interactions.push(configure("restricted_visibility", "toggle"));
+    }
+
+    for interaction in interactions {
+        system_manager.handle_system_interaction("nav_lights", interaction);
+    }
+}
+
+/// System to run the watch rotation and edit the crew while the watch page is shown
+fn handle_watch_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut system_manager: ResMut<SystemManager>,
+) {
+    if system_manager.active_system().map(|system| system.id()) != Some("watch") {
+        return;
+    }
+
+    let configure = |key: &str, value: &str| SystemInteraction::Configure(key.to_string(), value.to_string());
+
+    let mut interactions = Vec::new();
+    if keyboard_input.just_pressed(KeyCode::KeyS) {
+        interactions.push(configure("running", "toggle"));
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        interactions.push(configure("select", "previous"));
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        interactions.push(configure("select", "next"));
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        interactions.push(configure("watch_length_step", "up"));
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        interactions.push(configure("watch_length_step", "down"));
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Delete, KeyCode::Backspace]) {
+        interactions.push(configure("crew_remove", "selected"));
+    }
+
+    for interaction in interactions {
+        system_manager.handle_system_interaction("watch", interaction);
+    }
+}
+
+/// System to tick off and edit passage checklists while the checklist page is shown
+fn handle_checklist_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut system_manager: ResMut<SystemManager>,
+) {
+    if system_manager.active_system().map(|system| system.id()) != Some("checklists") {
+        return;
+    }
+
+    let configure = |key: &str, value: &str| SystemInteraction::Configure(key.to_string(), value.to_string());
+
+    let mut interactions = Vec::new();
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        interactions.push(configure("list", "toggle"));
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        interactions.push(configure("select", "previous"));
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        interactions.push(configure("select", "next"));
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Space, KeyCode::Enter]) {
+        interactions.push(configure("check", "selected"));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyR) {
+        interactions.push(configure("reset", ""));
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Delete, KeyCode::Backspace]) {
+        interactions.push(configure("remove_item", ""));
+    }
+
+    for interaction in interactions {
+        system_manager.handle_system_interaction("checklists", interaction);
+    }
+}
+
+/// System to override the display theme and brightness while the display page is shown
+fn handle_display_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut system_manager: ResMut<SystemManager>,
+) {
+    if system_manager.active_system().map(|system| system.id()) != Some("display") {
+        return;
+    }
+
+    let configure = |key: &str, value: &str| SystemInteraction::Configure(key.to_string(), value.to_string());
+
+    let mut interactions = Vec::new();
+    if keyboard_input.just_pressed(KeyCode::KeyM) {
+        interactions.push(configure("theme", "cycle"));
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        interactions.push(configure("brightness_step", "up"));
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        interactions.push(configure("brightness_step", "down"));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyA) {
+        interactions.push(configure("theme", "auto"));
+    }
+
+    for interaction in interactions {
+        system_manager.handle_system_interaction("display", interaction);
+    }
+}
+
 /// System to update the main display area with active system content
 fn update_system_display_content(
     system_manager: Res<SystemManager>,
@@ -221,4 +694,20 @@ mod tests {
         assert!(manager.active_system().is_some());
         assert_eq!(manager.active_system().unwrap().id(), "test");
     }
+
+    #[test]
+    fn test_update_costs_feed_shared_load() {
+        let load = SystemLoad::default();
+        let mut manager = SystemManager::with_load(load.clone());
+        manager.register_system(Box::new(MockSystem {
+            id: "test",
+            name: "Test System",
+            status: SystemStatus::Active,
+        }));
+
+        manager.update_systems(&VesselData::default(), &Time::default());
+        manager.update_systems(&VesselData::default(), &Time::default());
+        assert_eq!(load.read().update_cost("test").map(|cost| cost.updates), Some(2));
+        assert!(manager.pressure() < 1.0);
+    }
 }
@@ -12,11 +12,13 @@ use bevy::prelude::*;
 use crate::core::{ActionsPlugin, SystemManagerPlugin};
 use crate::core::system_manager::SystemManager;
 use crate::ui::{LoadingPlugin, MenuPlugin, GpsMapPlugin};
-use crate::services::GpsServicePlugin;
-use systems::{PlayerPlugin, setup_instrument_cluster, get_vessel_systems, CompassGauge, SpeedGauge, VesselData, update_vessel_data_with_gps};
+use crate::services::{GpsService, GpsServicePlugin};
+use systems::{PlayerPlugin, AirDraft, Alarms, AlarmsSystem, setup_instrument_cluster, get_vessel_systems, CompassGauge, SpeedGauge, VesselData, update_vessel_data_with_gps, CalibrationSystem, ChartAnnotations, ChartStore, ChartSystem, ChecklistStore, ChecklistSystem, Checklists, ColregsAdvisor, DisplayBrightness, DisplaySystem, NavLightsSystem, WatchSchedule, WatchSystem, BackendServices, ConnectionsSystem, DataBoxes, DataBoxSystem, DepthCalibrationStore, DepthTransducers, DiagnosticsSystem, LinkDiagnostics, TimelineSystem};
 use crate::ui::GpsMapState;
 #[cfg(target_arch = "wasm32")]
 use systems::GeoPlugin;
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::ui::window_layout::{WindowLayout, WindowLayoutPlugin, KIOSK_FLAG};
 
 // See https://bevy-cheatbook.github.io/programming/states.html
 #[derive(States, Default, Clone, Eq, PartialEq, Debug, Hash)]
@@ -33,21 +35,53 @@ enum GameState {
 pub struct GamePlugin;
 
 /// Initialize systems in the SystemManager
-fn initialize_vessel_systems(mut system_manager: ResMut<SystemManager>) {
+fn initialize_vessel_systems(
+    mut system_manager: ResMut<SystemManager>,
+    depth_transducers: Res<DepthTransducers>,
+    chart_annotations: Res<ChartAnnotations>,
+    data_boxes: Res<DataBoxes>,
+    link_diagnostics: Res<LinkDiagnostics>,
+    backend_services: Res<BackendServices>,
+    alarms: Res<Alarms>,
+    air_draft: Res<AirDraft>,
+    colregs: Res<ColregsAdvisor>,
+    watch_schedule: Res<WatchSchedule>,
+    checklists: Res<Checklists>,
+    display: Res<DisplayBrightness>,
+) {
     let systems = get_vessel_systems();
     for system in systems {
         system_manager.register_system(system);
     }
+    system_manager.register_system(Box::new(
+        CalibrationSystem::new(depth_transducers.clone())
+            .with_depth_store(DepthCalibrationStore::at_default_path())
+            .with_air_draft(air_draft.clone()),
+    ));
+    system_manager.register_system(Box::new(ChartSystem::new(chart_annotations.clone())));
+    system_manager.register_system(Box::new(TimelineSystem::new(depth_transducers.clone()).with_watch_schedule(watch_schedule.clone()).with_checklists(checklists.clone())));
+    system_manager.register_system(Box::new(DataBoxSystem::new(data_boxes.clone())));
+    system_manager.register_system(Box::new(DiagnosticsSystem::new(link_diagnostics.clone())));
+    system_manager.register_system(Box::new(ConnectionsSystem::new(backend_services.clone())));
+    system_manager.register_system(Box::new(AlarmsSystem::new(alarms.clone())));
+    system_manager.register_system(Box::new(NavLightsSystem::new(colregs.clone())));
+    system_manager.register_system(Box::new(WatchSystem::new(watch_schedule.clone())));
+    system_manager.register_system(Box::new(ChecklistSystem::new(checklists.clone())));
+    system_manager.register_system(Box::new(DisplaySystem::new(display.clone())));
 }
 
-/// Update compass gauge with real GPS heading data
+/// Update compass gauge with the heading sensor, falling back to GPS course
 fn update_compass_heading(
     gps_map_state: Res<GpsMapState>,
+    vessel_data: Res<VesselData>,
+    time: Res<Time>,
     mut compass_query: Query<&mut Text, With<CompassGauge>>,
 ) {
+    let heading = vessel_data
+        .fresh_heading_true(time.elapsed_secs())
+        .unwrap_or(gps_map_state.vessel_heading as f32);
     for mut text in compass_query.iter_mut() {
-        // Update compass display with real GPS heading
-        text.0 = format!("{:03.0}°", gps_map_state.vessel_heading);
+        text.0 = format!("{:03.0}°", heading);
     }
 }
 
@@ -68,14 +102,22 @@ fn update_speed_gauge(
 /// Update vessel data with real GPS data for consistent system displays
 fn update_vessel_data_with_real_gps(
     gps_map_state: Res<GpsMapState>,
+    gps_service: Res<GpsService>,
     vessel_data: ResMut<VesselData>,
     time: Res<Time>,
 ) {
-    // Use real GPS data from GpsMapState
-    let gps_data = Some((gps_map_state.vessel_speed, gps_map_state.vessel_heading));
+    // Only a real receiver counts as live data; mock fixes stay simulated
+    let gps_data = gps_service
+        .is_live
+        .then_some((gps_map_state.vessel_speed, gps_map_state.vessel_heading));
     update_vessel_data_with_gps(vessel_data, time, gps_data);
 }
 
+/// Feed the vessel position to the air-draft check
+fn update_air_draft_position(gps_map_state: Res<GpsMapState>, air_draft: Res<AirDraft>) {
+    air_draft.write().set_position(gps_map_state.vessel_lat, gps_map_state.vessel_lon);
+}
+
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.init_state::<GameState>().add_plugins((
@@ -87,12 +129,16 @@ impl Plugin for GamePlugin {
             SystemManagerPlugin,
             PlayerPlugin,
         ))
+        .insert_resource(ChartAnnotations::new(ChartStore::load_default()))
+        .insert_resource(Checklists::new(ChecklistStore::load_default()))
+        .insert_resource(DepthTransducers::new(DepthCalibrationStore::at_default_path().load()))
 
         .add_systems(OnEnter(GameState::Playing), (setup_instrument_cluster, initialize_vessel_systems))
         .add_systems(Update, (
             update_compass_heading,
             update_speed_gauge,
             update_vessel_data_with_real_gps,
+            update_air_draft_position,
         ).run_if(in_state(GameState::Playing)));
 
         #[cfg(target_arch = "wasm32")]
@@ -100,6 +146,11 @@ impl Plugin for GamePlugin {
             app.add_plugins(GeoPlugin);
         }
 
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            app.add_plugins((crate::services::ais_server::AisServerPlugin, crate::services::alarm_outputs::AlarmOutputsPlugin, crate::services::fog_horn::FogHornPlugin));
+        }
+
         #[cfg(debug_assertions)]
         {
             app.add_plugins((
@@ -8,23 +8,13 @@ use bevy::window::PrimaryWindow;
 use bevy::winit::WinitWindows;
 use bevy::DefaultPlugins;
 use std::io::Cursor;
-use tokio::process::Command;
 use winit::window::Icon;
 use yachtpit::GamePlugin;
 
 #[cfg(not(target_arch = "wasm32"))]
 #[tokio::main]
 async fn main() {
-    // Start AIS server in background
-    tokio::spawn(async {
-        info!("Starting AIS server...");
-        let mut cmd = Command::new("target/release/ais").spawn().unwrap();
-        match cmd.wait().await {
-            Ok(status) => info!("AIS server process exited with status: {}", status),
-            Err(e) => error!("Error waiting for AIS server process: {}", e),
-        }
-    });
-
+    // The AIS server is started by AisServerPlugin as an optional service
     launch_bevy();
 }
 
@@ -33,8 +23,14 @@ async fn main() {
 #[cfg(not(target_arch = "wasm32"))]
 use bevy_webview_wry::WebviewWryPlugin;
 
+#[cfg(not(target_arch = "wasm32"))]
+use yachtpit::{WindowLayout, WindowLayoutPlugin, KIOSK_FLAG};
+
 #[cfg(not(target_arch = "wasm32"))]
 fn launch_bevy() {
+    let kiosk = std::env::args().any(|arg| arg == KIOSK_FLAG);
+    let layout = WindowLayout::load_default();
+
     App::new()
         .insert_resource(ClearColor(Color::NONE))
         .add_plugins(
@@ -45,7 +41,7 @@ fn launch_bevy() {
                         fit_canvas_to_parent: true,
                         // Tells wasm not to override default event handling, like F5 and Ctrl+R
                         prevent_default_event_handling: false,
-                        ..default()
+                        ..layout.window(kiosk)
                     }),
                     ..default()
                 })
@@ -55,6 +51,7 @@ fn launch_bevy() {
                 }),
         )
         .add_plugins(GamePlugin)
+        .add_plugins(WindowLayoutPlugin::new(layout, kiosk))
         .add_systems(Startup, set_window_icon) // Changed here
         .add_plugins(WebviewWryPlugin::default())
         .run();
@@ -0,0 +1,107 @@
+//! Optional AIS server backend
+//!
+//! The AIS server feeds the map with vessel positions from a separate
+//! executable. The app runs fine without it, so a missing binary or a crash
+//! only marks the service unavailable on the connections page, from where it
+//! can be started again.
+
+use bevy::prelude::*;
+use std::path::PathBuf;
+use std::process::{Child, Command};
+use systems::{BackendServices, ServiceState};
+
+/// Service id of the AIS server on the connections page
+pub const AIS_SERVICE_ID: &str = "ais";
+
+/// Environment variable overriding the AIS server executable
+pub const AIS_SERVER_ENV: &str = "YACHTPIT_AIS_SERVER";
+
+/// The running AIS server process, if any
+#[derive(Resource, Default)]
+pub struct AisServerProcess {
+    child: Option<Child>,
+}
+
+impl Drop for AisServerProcess {
+    fn drop(&mut self) {
+        if let Some(child) = self.child.as_mut() {
+            let _ = child.kill();
+        }
+    }
+}
+
+/// Candidate locations of the AIS server, most specific first
+fn ais_server_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Ok(path) = std::env::var(AIS_SERVER_ENV) {
+        paths.push(PathBuf::from(path));
+    }
+    if let Some(dir) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(PathBuf::from)) {
+        paths.push(dir.join(format!("ais{}", std::env::consts::EXE_SUFFIX)));
+    }
+    paths.push(PathBuf::from(format!("target/release/ais{}", std::env::consts::EXE_SUFFIX)));
+    paths
+}
+
+/// Try to spawn the AIS server, recording the outcome
+fn spawn_ais_server(process: &mut AisServerProcess, services: &BackendServices) {
+    services.write().set_state(AIS_SERVICE_ID, ServiceState::Starting);
+
+    let Some(path) = ais_server_paths().into_iter().find(|path| path.is_file()) else {
+        warn!("AIS server executable not found; AIS targets will be unavailable");
+        services.write().set_state(AIS_SERVICE_ID, ServiceState::Unavailable("executable not found".to_string()));
+        return;
+    };
+
+    info!("Starting AIS server from {}", path.display());
+    match Command::new(&path).spawn() {
+        Ok(child) => {
+            process.child = Some(child);
+            services.write().set_state(AIS_SERVICE_ID, ServiceState::Running);
+        }
+        Err(e) => {
+            warn!("Failed to start AIS server: {}", e);
+            services.write().set_state(AIS_SERVICE_ID, ServiceState::Unavailable(e.to_string()));
+        }
+    }
+}
+
+fn start_ais_server(mut process: ResMut<AisServerProcess>, services: Res<BackendServices>) {
+    services.write().register(AIS_SERVICE_ID, "AIS SERVER");
+    spawn_ais_server(&mut process, &services);
+}
+
+/// Notice when the server exits and restart it when a retry is requested
+fn supervise_ais_server(mut process: ResMut<AisServerProcess>, services: Res<BackendServices>) {
+    if let Some(child) = process.child.as_mut() {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                warn!("AIS server process exited with status: {}", status);
+                process.child = None;
+                services.write().set_state(AIS_SERVICE_ID, ServiceState::Exited(status.to_string()));
+            }
+            Ok(None) => {}
+            Err(e) => {
+                error!("Error waiting for AIS server process: {}", e);
+                process.child = None;
+                services.write().set_state(AIS_SERVICE_ID, ServiceState::Exited(e.to_string()));
+            }
+        }
+    }
+
+    if services.write().take_retry(AIS_SERVICE_ID) && process.child.is_none() {
+        spawn_ais_server(&mut process, &services);
+    }
+}
+
+/// Runs the AIS server as a managed optional service
+pub struct AisServerPlugin;
+
+impl Plugin for AisServerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AisServerProcess>()
+            .init_resource::<BackendServices>()
+            .add_systems(Startup, start_ais_server)
+            .add_systems(Update, supervise_ais_server);
+    }
+}
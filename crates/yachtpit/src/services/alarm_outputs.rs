@@ -0,0 +1,85 @@
+//! Alarm outputs beyond the dashboard
+//!
+//! The buzzer and the external relay are GPIO lines named by sysfs value
+//! files, and shore reports go to a UDP `host:port`. Each output is optional
+//! and configured from the environment, so a stage without hardware behind
+//! it only shows on the alarms page.
+
+use bevy::prelude::*;
+use systems::{Alarms, EscalationStage, GpioOutput, ShoreReporter};
+use crate::ui::GpsMapState;
+
+/// GPIO value file of the audible alarm buzzer
+pub const ALARM_BUZZER_ENV: &str = "YACHTPIT_ALARM_BUZZER";
+
+/// GPIO value file of the external alarm relay
+pub const ALARM_RELAY_ENV: &str = "YACHTPIT_ALARM_RELAY";
+
+/// `host:port` receiving position reports when an alarm escalates to shore
+pub const ALARM_SHORE_ENV: &str = "YACHTPIT_ALARM_SHORE";
+
+/// Configured alarm outputs
+#[derive(Resource, Default)]
+pub struct AlarmOutputs {
+    buzzer: Option<GpioOutput>,
+    relay: Option<GpioOutput>,
+    shore: Option<ShoreReporter>,
+}
+
+impl AlarmOutputs {
+    pub fn from_env() -> Self {
+        let shore = std::env::var(ALARM_SHORE_ENV).ok().and_then(|target| match ShoreReporter::connect(&target) {
+            Ok(reporter) => Some(reporter),
+            Err(e) => {
+                warn!("Alarm shore reports to {} disabled: {}", target, e);
+                None
+            }
+        });
+        Self {
+            buzzer: std::env::var(ALARM_BUZZER_ENV).ok().map(GpioOutput::new),
+            relay: std::env::var(ALARM_RELAY_ENV).ok().map(GpioOutput::new),
+            shore,
+        }
+    }
+}
+
+/// Switch the buzzer and relay to the active escalation stages and send a
+/// shore report for each alarm reaching that stage
+fn drive_alarm_outputs(mut outputs: ResMut<AlarmOutputs>, alarms: Res<Alarms>, gps_map_state: Res<GpsMapState>) {
+    let (active, escalations) = {
+        let mut table = alarms.write();
+        (table.active_outputs(), table.take_escalations())
+    };
+
+    for escalation in &escalations {
+        warn!("Alarm {} escalated to {}", escalation.label, escalation.stage.label());
+    }
+
+    let outputs = &mut *outputs;
+    for (output, stage) in [(&mut outputs.buzzer, EscalationStage::Audible), (&mut outputs.relay, EscalationStage::Relay)] {
+        if let Some(output) = output {
+            if let Err(e) = output.set(active.contains(&stage)) {
+                warn!("Failed to switch {} alarm output: {}", stage.label(), e);
+            }
+        }
+    }
+
+    if let Some(shore) = outputs.shore.as_mut() {
+        for escalation in escalations.iter().filter(|escalation| escalation.stage == EscalationStage::ShoreReport) {
+            if let Err(e) = shore.report(escalation, gps_map_state.vessel_lat, gps_map_state.vessel_lon) {
+                warn!("Failed to report alarm {} to shore: {}", escalation.label, e);
+            }
+        }
+    }
+}
+
+/// Drives the configured alarm outputs from the shared alarm table
+pub struct AlarmOutputsPlugin;
+
+impl Plugin for AlarmOutputsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(AlarmOutputs::from_env())
+            .init_resource::<Alarms>()
+            .add_systems(Update, drive_alarm_outputs.run_if(in_state(crate::GameState::Playing)));
+    }
+}
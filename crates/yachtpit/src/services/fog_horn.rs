@@ -0,0 +1,60 @@
+//! Fog horn output for restricted-visibility sound signals
+//!
+//! While fog signals are switched on from the nav lights page, the horn relay
+//! is keyed through the signal for the current vessel state, repeating at the
+//! interval the rules allow. Without a configured GPIO line the signal is only
+//! shown on the page.
+
+use bevy::prelude::*;
+use systems::{ColregsAdvisor, GpioOutput};
+
+/// GPIO value file of the horn relay
+pub const FOG_HORN_ENV: &str = "YACHTPIT_FOG_HORN";
+
+/// The horn line and when the current signal started
+#[derive(Resource, Default)]
+pub struct FogHorn {
+    output: Option<GpioOutput>,
+    started_at: Option<f64>,
+}
+
+impl FogHorn {
+    pub fn from_env() -> Self {
+        Self {
+            output: std::env::var(FOG_HORN_ENV).ok().map(GpioOutput::new),
+            started_at: None,
+        }
+    }
+}
+
+/// Key the horn through the current sound signal while fog signals are on
+fn drive_fog_horn(mut horn: ResMut<FogHorn>, advisor: Res<ColregsAdvisor>, time: Res<Time>) {
+    let now = time.elapsed_secs_f64();
+    let on = {
+        let state = advisor.read();
+        if state.restricted_visibility {
+            let started_at = *horn.started_at.get_or_insert(now);
+            state.profile.sound_signal().horn_on(now - started_at)
+        } else {
+            horn.started_at = None;
+            false
+        }
+    };
+
+    if let Some(output) = horn.output.as_mut() {
+        if let Err(e) = output.set(on) {
+            warn!("Failed to switch fog horn: {}", e);
+        }
+    }
+}
+
+/// Sounds fog signals on the configured horn
+pub struct FogHornPlugin;
+
+impl Plugin for FogHornPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(FogHorn::from_env())
+            .init_resource::<ColregsAdvisor>()
+            .add_systems(Update, drive_fog_horn.run_if(in_state(crate::GameState::Playing)));
+    }
+}
@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
+use systems::{GnssFix, GnssIntegrity, GnssIntegrityMonitor, IntegrityFlag};
 
 #[cfg(not(target_arch = "wasm32"))]
 use crate::services::gpyes_provider::GpyesProvider;
@@ -15,6 +16,49 @@ pub struct GpsData {
     pub heading: Option<f64>,
     pub speed: Option<f64>,
     pub timestamp: f64,
+    /// SNR of each satellite in view, in dB-Hz; empty when not reported
+    #[serde(default)]
+    pub satellite_snr: Vec<u8>,
+}
+
+/// Pass a receiver fix through the integrity checks. A suspect fix is
+/// replaced by the position dead reckoned from the last trusted one.
+pub fn screen_fix(integrity: &mut GnssIntegrity, gps_data: GpsData) -> GpsData {
+    let was_suspect = integrity.is_suspect();
+    let trusted = integrity.check(GnssFix {
+        latitude: gps_data.latitude,
+        longitude: gps_data.longitude,
+        time_s: gps_data.timestamp,
+        speed_kts: gps_data.speed,
+        course_deg: gps_data.heading,
+        hdop: gps_data.accuracy,
+        snr_db: gps_data.satellite_snr.clone(),
+    });
+    if trusted {
+        if was_suspect {
+            info!("GNSS fixes trusted again, leaving dead reckoning");
+        }
+        return gps_data;
+    }
+
+    if !was_suspect {
+        let reasons: Vec<String> = integrity.flags().iter().map(IntegrityFlag::label).collect();
+        warn!("Suspected GNSS jamming or spoofing ({}); dead reckoning", reasons.join(", "));
+    }
+    let Some((latitude, longitude)) = integrity.dead_reckoning(gps_data.timestamp) else {
+        // Nothing trusted to reckon from yet
+        return gps_data;
+    };
+    let last_trusted = integrity.last_trusted();
+    GpsData {
+        latitude,
+        longitude,
+        altitude: None,
+        accuracy: None,
+        heading: last_trusted.and_then(|fix| fix.course_deg),
+        speed: last_trusted.and_then(|fix| fix.speed_kts),
+        ..gps_data
+    }
 }
 
 #[derive(Resource)]
@@ -22,6 +66,8 @@ pub struct GpsService {
     pub current_position: Option<GpsData>,
     pub is_enabled: bool,
     pub last_update: f64,
+    /// Whether the current position came from a real receiver rather than mock data
+    pub is_live: bool,
     #[cfg(not(target_arch = "wasm32"))]
     pub gpyes_provider: Option<GpyesProvider>,
     #[cfg(not(target_arch = "wasm32"))]
@@ -40,6 +86,7 @@ impl GpsService {
             current_position: None,
             is_enabled: false,
             last_update: 0.0,
+            is_live: false,
             #[cfg(not(target_arch = "wasm32"))]
             gpyes_provider: None,
             #[cfg(not(target_arch = "wasm32"))]
@@ -100,7 +147,7 @@ impl GpsService {
 
 // Native GPS implementation using GPYes device
 #[cfg(not(target_arch = "wasm32"))]
-pub fn start_native_gps_tracking(mut gps_service: ResMut<GpsService>, time: Res<Time>) {
+pub fn start_native_gps_tracking(mut gps_service: ResMut<GpsService>, integrity: Res<GnssIntegrityMonitor>, time: Res<Time>) {
     use std::time::{SystemTime, UNIX_EPOCH};
 
     if !gps_service.is_enabled {
@@ -111,7 +158,9 @@ pub fn start_native_gps_tracking(mut gps_service: ResMut<GpsService>, time: Res<
     if let Some(receiver) = &mut gps_service.gps_receiver {
         match receiver.try_recv() {
             Ok(gps_data) => {
+                let gps_data = screen_fix(&mut integrity.write(), gps_data);
                 gps_service.update_position(gps_data);
+                gps_service.is_live = true;
                 return;
             }
             Err(mpsc::error::TryRecvError::Empty) => {
@@ -154,9 +203,11 @@ pub fn start_native_gps_tracking(mut gps_service: ResMut<GpsService>, time: Res<
         heading: Some(((timestamp / 30.0) * 57.2958) % 360.0), // Convert to degrees
         speed: Some(5.0 + (timestamp / 25.0).sin() * 2.0), // 3-7 knots
         timestamp,
+        satellite_snr: Vec::new(),
     };
 
     gps_service.update_position(mock_gps_data);
+    gps_service.is_live = false;
 }
 
 // Bevy plugin for GPS service
@@ -208,6 +259,7 @@ mod tests {
             heading: Some(90.0),
             speed: Some(5.0),
             timestamp: 1234567890.0,
+            satellite_snr: Vec::new(),
         };
         
         service.update_position(gps_data.clone());
@@ -220,4 +272,26 @@ mod tests {
         assert_eq!(position.longitude, 7.4246);
         assert_eq!(position.heading, Some(90.0));
     }
+
+    #[test]
+    fn test_suspect_fix_replaced_by_dead_reckoning() {
+        let mut integrity = GnssIntegrity::new();
+        let fix = |timestamp: f64, latitude: f64| GpsData {
+            latitude,
+            longitude: 7.4246,
+            altitude: None,
+            accuracy: Some(0.9),
+            heading: Some(0.0),
+            speed: Some(0.0),
+            timestamp,
+            satellite_snr: vec![42, 38, 45, 31],
+        };
+
+        assert_eq!(screen_fix(&mut integrity, fix(0.0, 43.7384)).latitude, 43.7384);
+        // Jammed: every satellite faded
+        let jammed = GpsData { satellite_snr: vec![8, 5, 11, 9], ..fix(1.0, 43.75) };
+        let screened = screen_fix(&mut integrity, jammed);
+        assert!(integrity.is_suspect());
+        assert_eq!((screened.latitude, screened.speed), (43.7384, Some(0.0)));
+    }
 }
\ No newline at end of file
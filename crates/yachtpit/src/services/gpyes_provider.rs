@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
@@ -53,10 +54,60 @@ impl From<EnhancedLocationData> for GpsData {
             heading: enhanced.heading,
             speed: enhanced.speed,
             timestamp,
+            satellite_snr: Vec::new(),
         }
     }
 }
 
+/// Satellite signal strengths from GSV groups, kept per talker so each
+/// constellation's latest complete group is reported
+#[derive(Debug, Default)]
+pub struct SnrCollector {
+    pending: HashMap<String, Vec<u8>>,
+    complete: HashMap<String, Vec<u8>>,
+}
+
+impl SnrCollector {
+    /// Take in a GSV sentence; returns false for any other sentence.
+    /// Satellites in view but not tracked count as 0 dB-Hz.
+    pub fn push(&mut self, sentence: &str) -> bool {
+        let body = sentence.split('*').next().unwrap_or(sentence);
+        let parts: Vec<&str> = body.split(',').collect();
+        let Some(talker) = parts[0].strip_prefix('$').filter(|address| address.len() == 5 && address.ends_with("GSV")) else {
+            return false;
+        };
+        let (Some(Ok(total)), Some(Ok(number))) = (parts.get(1).map(|p| p.parse::<u8>()), parts.get(2).map(|p| p.parse::<u8>())) else {
+            return true;
+        };
+        let talker = talker[..2].to_string();
+        if number == 1 {
+            self.pending.insert(talker.clone(), Vec::new());
+        }
+        let Some(group) = self.pending.get_mut(&talker) else {
+            return true;
+        };
+        group.extend(
+            parts
+                .get(4..)
+                .unwrap_or_default()
+                .chunks(4)
+                .filter(|block| block.len() == 4 && !block[0].is_empty())
+                .map(|block| block[3].parse().unwrap_or(0)),
+        );
+        if number >= total {
+            if let Some(group) = self.pending.remove(&talker) {
+                self.complete.insert(talker, group);
+            }
+        }
+        true
+    }
+
+    /// SNRs of every satellite in view across constellations
+    pub fn snapshot(&self) -> Vec<u8> {
+        self.complete.values().flatten().copied().collect()
+    }
+}
+
 /// Enhanced GNSS parser that supports heading and additional GPS data
 pub struct EnhancedGnssParser {
     debug_enabled: bool,
@@ -95,10 +146,20 @@ impl EnhancedGnssParser {
             debug!("[GPS_DEBUG] Parsing sentence type: {}", sentence_type);
         }
 
-        match sentence_type {
-            "$GPGGA" | "$GNGGA" => self.parse_gpgga(&parts),
-            "$GPRMC" | "$GNRMC" => self.parse_gprmc(&parts),
-            "$GPVTG" | "$GNVTG" => self.parse_gpvtg(&parts), // Course and speed
+        // Accept every GNSS talker: GP, GN, GL, GA, GB/BD, GQ/QZ, GI
+        let formatter = match sentence_type
+            .strip_prefix('$')
+            .filter(|address| address.len() == 5 && address.is_ascii())
+            .map(|address| address.split_at(2))
+        {
+            Some(("GP" | "GN" | "GL" | "GA" | "GB" | "BD" | "GQ" | "QZ" | "GI", formatter)) => formatter,
+            _ => "",
+        };
+
+        match formatter {
+            "GGA" => self.parse_gpgga(&parts),
+            "RMC" => self.parse_gprmc(&parts),
+            "VTG" => self.parse_gpvtg(&parts), // Course and speed
             _ => {
                 if self.debug_enabled {
                     debug!("[GPS_DEBUG] Unsupported sentence type: {}", sentence_type);
@@ -400,6 +461,7 @@ impl GpyesProvider {
 
                         let mut reader = BufReader::new(port.as_mut());
                         let mut line = String::new();
+                        let mut snr = SnrCollector::default();
 
                         loop {
                             // Check if we should stop
@@ -426,10 +488,15 @@ impl GpyesProvider {
                                             debug!("[GPS_DEBUG] Raw NMEA: {}", sentence);
                                         }
 
+                                        if snr.push(sentence) {
+                                            continue;
+                                        }
+
                                         if let Some(location) = parser.parse_sentence(sentence) {
                                             // Only send if we have valid position data
                                             if location.latitude.is_some() && location.longitude.is_some() {
-                                                let gps_data: GpsData = location.into();
+                                                let mut gps_data: GpsData = location.into();
+                                                gps_data.satellite_snr = snr.snapshot();
                                                 if debug_enabled {
                                                     debug!("[GPS_DEBUG] Sending GPS data: lat={:.6}, lon={:.6}, heading={:?}", 
                                                            gps_data.latitude, gps_data.longitude, gps_data.heading);
@@ -3,4 +3,13 @@ pub mod gps_service;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod gpyes_provider;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod ais_server;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod alarm_outputs;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod fog_horn;
+
 pub use gps_service::*;
\ No newline at end of file
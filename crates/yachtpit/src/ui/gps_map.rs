@@ -4,6 +4,8 @@ use bevy::window::Window;
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use crate::services::{GpsService, GpsData};
+#[cfg(not(target_arch = "wasm32"))]
+use systems::{Annotation, ChartAnnotations};
 
 #[cfg(not(target_arch = "wasm32"))]
 use bevy_flurx::prelude::*;
@@ -46,6 +48,12 @@ pub struct AuthParams {
     pub token: Option<String>,
 }
 
+/// Annotation id parameter
+#[derive(Deserialize, Debug, Clone)]
+pub struct AnnotationIdParams {
+    pub id: u64,
+}
+
 /// Component to mark the GPS map window
 #[derive(Component)]
 pub struct GpsMapWindow;
@@ -246,7 +254,10 @@ fn spawn_gps_webview(commands: &mut Commands, gps_map_state: &mut ResMut<GpsMapS
                 ipc_commands::map_view_changed,
                 ipc_commands::auth_status_changed,
                 ipc_commands::get_map_init,
-                ipc_commands::get_vessel_status
+                ipc_commands::get_vessel_status,
+                ipc_commands::get_annotations,
+                ipc_commands::save_annotation,
+                ipc_commands::delete_annotation
             ]),
             Webview::Uri(WebviewUri::relative_local(
                 // Using the build output of the base-map package
@@ -340,6 +351,42 @@ mod ipc_commands {
             }
         })).await
     }
+
+    /// Get the user annotations shown on the chart
+    #[command]
+    pub async fn get_annotations(
+        WebviewEntity(_entity): WebviewEntity,
+        task: ReactorTask,
+    ) -> Vec<Annotation> {
+        task.will(Update, once::run(|chart: Res<ChartAnnotations>| {
+            chart.read().annotations().to_vec()
+        })).await
+    }
+
+    /// Add an annotation (id 0) or replace an existing one; returns it as stored
+    #[command]
+    pub async fn save_annotation(
+        In(annotation): In<Annotation>,
+        WebviewEntity(_entity): WebviewEntity,
+        task: ReactorTask,
+    ) -> Annotation {
+        task.will(Update, once::run(|In(annotation): In<Annotation>, chart: Res<ChartAnnotations>| {
+            info!("Annotation saved from map: {:?}", annotation);
+            chart.write().upsert_annotation(annotation)
+        }).with(annotation)).await
+    }
+
+    /// Remove an annotation; returns whether it existed
+    #[command]
+    pub async fn delete_annotation(
+        In(params): In<AnnotationIdParams>,
+        WebviewEntity(_entity): WebviewEntity,
+        task: ReactorTask,
+    ) -> bool {
+        task.will(Update, once::run(|In(id): In<u64>, chart: Res<ChartAnnotations>| {
+            chart.write().remove_annotation(id)
+        }).with(params.id)).await
+    }
 }
 
 /// System to enable GPS service on startup
@@ -1,5 +1,6 @@
 use crate::GameState;
 use bevy::prelude::*;
+use systems::Branding;
 
 pub struct MenuPlugin;
 
@@ -56,7 +57,7 @@ impl Default for ButtonColors {
 #[derive(Component)]
 struct Menu;
 
-fn setup_menu(mut commands: Commands) {
+fn setup_menu(mut commands: Commands, branding: Res<Branding>, mut images: ResMut<Assets<Image>>) {
     info!("menu");
     commands.spawn((Camera2d, Msaa::Off));
     
@@ -84,6 +85,33 @@ fn setup_menu(mut commands: Commands) {
             Menu,
         ))
         .with_children(|children| {
+            // Branding pack logo and vessel name above the play button
+            if let Some(logo) = branding.load_logo() {
+                children.spawn((
+                    ImageNode::new(images.add(logo)),
+                    Node {
+                        max_width: Val::Px(320.0),
+                        max_height: Val::Px(160.0),
+                        margin: UiRect::bottom(Val::Px(16.0)),
+                        ..default()
+                    },
+                ));
+            }
+            if let Some(name) = branding.vessel_name() {
+                children.spawn((
+                    Text::new(name),
+                    TextFont {
+                        font_size: 36.0,
+                        ..default()
+                    },
+                    TextColor(branding.accent().unwrap_or(NeumorphicColors::TEXT_ACCENT)),
+                    Node {
+                        margin: UiRect::bottom(Val::Px(16.0)),
+                        ..default()
+                    },
+                ));
+            }
+
             let button_colors = ButtonColors::default();
             children
                 .spawn((
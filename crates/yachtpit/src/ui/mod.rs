@@ -2,6 +2,9 @@ pub mod loading;
 pub mod menu;
 pub mod gps_map;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod window_layout;
+
 pub use loading::LoadingPlugin;
 pub use menu::MenuPlugin;
 pub use gps_map::{GpsMapPlugin, spawn_gps_map_window, GpsMapState};
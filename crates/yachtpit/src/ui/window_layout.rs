@@ -0,0 +1,292 @@
+//! Window size, position, monitor and fullscreen state kept across launches
+//!
+//! Helm PCs boot straight into the app on a fixed display, so the primary
+//! window reopens where it was left. `--kiosk` opens it borderless
+//! fullscreen without decorations and leaves the saved layout untouched.
+
+use bevy::prelude::*;
+use bevy::window::{Monitor, MonitorSelection, PrimaryMonitor, PrimaryWindow, WindowCloseRequested, WindowMode, WindowPosition, WindowResolution};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// File name of the saved layout inside the data directory
+const LAYOUT_FILE_NAME: &str = "window_layout.json";
+
+/// Command line flag opening the app fullscreen without window decorations
+pub const KIOSK_FLAG: &str = "--kiosk";
+
+/// Seconds a layout must stay unchanged before it is written, so dragging
+/// or resizing the window does not write on every frame
+const SAVE_DELAY_S: f32 = 1.0;
+
+/// Saved state of the primary window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowLayout {
+    /// Logical size
+    pub width: f32,
+    pub height: f32,
+    /// Top-left corner in physical pixels; `None` lets the window manager decide
+    #[serde(default)]
+    pub position: Option<(i32, i32)>,
+    /// Name of the monitor the window was on
+    #[serde(default)]
+    pub monitor: Option<String>,
+    #[serde(default)]
+    pub fullscreen: bool,
+}
+
+impl Default for WindowLayout {
+    fn default() -> Self {
+        Self {
+            width: 1280.0,
+            height: 720.0,
+            position: None,
+            monitor: None,
+            fullscreen: false,
+        }
+    }
+}
+
+/// Area of a connected monitor, in physical pixels
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorArea {
+    pub name: Option<String>,
+    pub position: IVec2,
+    pub size: UVec2,
+}
+
+impl MonitorArea {
+    fn contains(&self, point: IVec2) -> bool {
+        let offset = point - self.position;
+        offset.x >= 0 && offset.y >= 0 && (offset.x as u32) < self.size.x && (offset.y as u32) < self.size.y
+    }
+}
+
+impl WindowLayout {
+    /// `window_layout.json` in the app data directory
+    pub fn default_path() -> Option<PathBuf> {
+        Some(systems::data_dir()?.join(LAYOUT_FILE_NAME))
+    }
+
+    /// The saved layout, or the default if there is none or it is unreadable
+    pub fn load(path: &Path) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable window layout {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn load_default() -> Self {
+        Self::default_path().map(|path| Self::load(&path)).unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&temp_path, path)
+    }
+
+    /// Primary window settings for this layout. Fullscreen is applied once
+    /// the monitors are known, see [`WindowLayoutPlugin`].
+    pub fn window(&self, kiosk: bool) -> Window {
+        Window {
+            resolution: WindowResolution::new(self.width, self.height),
+            position: self
+                .position
+                .map_or(WindowPosition::Automatic, |(x, y)| WindowPosition::At(IVec2::new(x, y))),
+            decorations: !kiosk,
+            ..default()
+        }
+    }
+
+    /// Index of the monitor to restore onto: the saved one by name, else the
+    /// one containing the saved position
+    pub fn choose_monitor(&self, monitors: &[MonitorArea]) -> Option<usize> {
+        let by_name = self
+            .monitor
+            .as_ref()
+            .and_then(|name| monitors.iter().position(|monitor| monitor.name.as_ref() == Some(name)));
+        by_name.or_else(|| {
+            let (x, y) = self.position?;
+            monitors.iter().position(|monitor| monitor.contains(IVec2::new(x, y)))
+        })
+    }
+}
+
+/// Layout persistence state of the primary window
+#[derive(Resource)]
+struct WindowLayoutState {
+    layout: WindowLayout,
+    kiosk: bool,
+    path: Option<PathBuf>,
+    /// Whether the saved monitor and fullscreen state were applied
+    restored: bool,
+    /// Elapsed seconds when the window last changed without being saved
+    changed_at: Option<f32>,
+}
+
+/// Restores the primary window onto its saved monitor and saves layout changes
+pub struct WindowLayoutPlugin {
+    layout: WindowLayout,
+    kiosk: bool,
+}
+
+impl WindowLayoutPlugin {
+    /// `layout` is the one the primary window was created from
+    pub fn new(layout: WindowLayout, kiosk: bool) -> Self {
+        Self { layout, kiosk }
+    }
+}
+
+impl Plugin for WindowLayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WindowLayoutState {
+            layout: self.layout.clone(),
+            kiosk: self.kiosk,
+            path: WindowLayout::default_path(),
+            restored: false,
+            changed_at: None,
+        })
+        .add_systems(Update, (restore_window_monitor, save_window_layout).chain());
+    }
+}
+
+fn monitor_areas(monitors: &Query<(Entity, &Monitor, Has<PrimaryMonitor>)>) -> Vec<(Entity, MonitorArea, bool)> {
+    monitors
+        .iter()
+        .map(|(entity, monitor, primary)| {
+            let area = MonitorArea {
+                name: monitor.name.clone(),
+                position: monitor.physical_position,
+                size: UVec2::new(monitor.physical_width, monitor.physical_height),
+            };
+            (entity, area, primary)
+        })
+        .collect()
+}
+
+/// Once monitors are known, move the window back to its monitor (or the
+/// primary one if that monitor is gone) and restore fullscreen
+fn restore_window_monitor(
+    mut state: ResMut<WindowLayoutState>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    monitors: Query<(Entity, &Monitor, Has<PrimaryMonitor>)>,
+) {
+    if state.restored || monitors.is_empty() {
+        return;
+    }
+    let Ok(mut window) = windows.single_mut() else {
+        return;
+    };
+    state.restored = true;
+
+    let monitors = monitor_areas(&monitors);
+    let areas: Vec<MonitorArea> = monitors.iter().map(|(_, area, _)| area.clone()).collect();
+    let selection = match state.layout.choose_monitor(&areas) {
+        Some(index) => MonitorSelection::Entity(monitors[index].0),
+        None => {
+            if state.layout.position.is_some() {
+                info!("Saved monitor is not connected; opening on the primary monitor");
+                window.position = WindowPosition::Centered(MonitorSelection::Primary);
+            }
+            monitors
+                .iter()
+                .find(|(_, _, primary)| *primary)
+                .map_or(MonitorSelection::Primary, |(entity, _, _)| MonitorSelection::Entity(*entity))
+        }
+    };
+
+    if state.kiosk || state.layout.fullscreen {
+        window.mode = WindowMode::BorderlessFullscreen(selection);
+    }
+}
+
+/// Save the window layout once it has settled after a move, resize or mode change
+fn save_window_layout(
+    mut state: ResMut<WindowLayoutState>,
+    windows: Query<Ref<Window>, With<PrimaryWindow>>,
+    monitors: Query<(Entity, &Monitor, Has<PrimaryMonitor>)>,
+    mut close_requests: EventReader<WindowCloseRequested>,
+    time: Res<Time>,
+) {
+    if state.kiosk || !state.restored {
+        return;
+    }
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let now = time.elapsed_secs();
+    if window.is_changed() {
+        state.changed_at.get_or_insert(now);
+    }
+    let closing = close_requests.read().count() > 0;
+    let Some(changed_at) = state.changed_at else {
+        return;
+    };
+    if !closing && now - changed_at < SAVE_DELAY_S {
+        return;
+    }
+    state.changed_at = None;
+
+    let fullscreen = !matches!(window.mode, WindowMode::Windowed);
+    let mut layout = WindowLayout {
+        fullscreen,
+        ..state.layout.clone()
+    };
+    // A fullscreen window keeps the windowed size and position to return to
+    if !fullscreen {
+        layout.width = window.resolution.width();
+        layout.height = window.resolution.height();
+        if let WindowPosition::At(position) = window.position {
+            layout.position = Some((position.x, position.y));
+            let areas = monitor_areas(&monitors);
+            if let Some((_, area, _)) = areas.iter().find(|(_, area, _)| area.contains(position)) {
+                layout.monitor = area.name.clone();
+            }
+        }
+    }
+    if layout == state.layout {
+        return;
+    }
+
+    if let Some(path) = &state.path {
+        match layout.save(path) {
+            Ok(()) => debug!("Saved window layout to {}", path.display()),
+            Err(e) => warn!("Failed to save window layout: {}", e),
+        }
+    }
+    state.layout = layout;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choose_monitor_prefers_name_then_position() {
+        let monitors = [
+            MonitorArea { name: Some("DELL P2419H".to_string()), position: IVec2::ZERO, size: UVec2::new(1920, 1080) },
+            MonitorArea { name: Some("HELM".to_string()), position: IVec2::new(1920, 0), size: UVec2::new(1280, 800) },
+        ];
+        let mut layout = WindowLayout { position: Some((2000, 100)), ..default() };
+        assert_eq!(layout.choose_monitor(&monitors), Some(1));
+
+        layout.monitor = Some("DELL P2419H".to_string());
+        assert_eq!(layout.choose_monitor(&monitors), Some(0));
+
+        // The saved monitor was unplugged and the position is off screen
+        layout.monitor = Some("PORTABLE".to_string());
+        layout.position = Some((4000, 0));
+        assert_eq!(layout.choose_monitor(&monitors), None);
+
+        let restored: WindowLayout = serde_json::from_str(r#"{"width": 800.0, "height": 600.0}"#).unwrap();
+        assert_eq!(restored.position, None);
+        assert!(!restored.fullscreen);
+    }
+}